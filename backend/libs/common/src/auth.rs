@@ -4,6 +4,10 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{Error, Result};
+use crate::tenant::Tenant;
+
+/// Role of the tokens services mint for calls they make on their own behalf
+pub const SERVICE_ROLE: &str = "SERVICE";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -36,6 +40,16 @@ impl Claims {
         }
     }
 
+    /// Claims of a background job calling another service, such as an
+    /// instrument result. The subject is the nil system user.
+    pub fn service(organization_id: Uuid) -> Self {
+        Self::new(Uuid::nil(), organization_id, vec![SERVICE_ROLE.to_string()], Vec::new(), 1)
+    }
+
+    pub fn is_service(&self) -> bool {
+        self.has_role(SERVICE_ROLE)
+    }
+
     pub fn has_permission(&self, permission: &str) -> bool {
         self.permissions.contains(&permission.to_string())
     }
@@ -53,8 +67,18 @@ impl Claims {
         Uuid::parse_str(&self.organization_id)
             .map_err(|_| Error::InvalidToken)
     }
+
+    /// Tenant whose rows the caller may see. Service tokens minted for the
+    /// nil organization act across every organization.
+    pub fn tenant(&self) -> Result<Tenant> {
+        match self.organization_id()? {
+            id if id.is_nil() && self.is_service() => Ok(Tenant::System),
+            id => Ok(Tenant::Organization(id)),
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct JwtService {
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
@@ -89,6 +113,214 @@ impl JwtService {
 
         Ok(token_data.claims)
     }
+
+    /// Bearer token for a service-to-service call on behalf of an organization
+    pub fn service_token(&self, organization_id: Uuid) -> Result<String> {
+        self.generate_token(Claims::service(organization_id))
+    }
+
+    /// Service token that carries the tenant of the current task to another service
+    pub fn tenant_token(&self) -> Result<String> {
+        match Tenant::current() {
+            Some(Tenant::Organization(organization_id)) => self.service_token(organization_id),
+            Some(Tenant::System) => self.service_token(Uuid::nil()),
+            None => Err(Error::Unauthorized),
+        }
+    }
+}
+
+// ============================================================================
+// Request Authentication
+// ============================================================================
+
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use async_graphql::{Context, ErrorExtensions, Guard};
+
+/// Token of an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Actix middleware validating bearer tokens.
+///
+/// Valid claims are stored in the request extensions for [`RequestClaims`].
+/// Requests without a token pass through anonymously, so health checks and
+/// open queries keep working; a bad or expired token is rejected with 401.
+#[derive(Clone)]
+pub struct JwtAuth {
+    jwt: Arc<JwtService>,
+}
+
+impl JwtAuth {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            jwt: Arc::new(JwtService::new(secret)),
+        }
+    }
+}
+
+impl<S, B: 'static> Transform<S, ServiceRequest> for JwtAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = JwtAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<std::result::Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuthMiddleware {
+            service,
+            jwt: self.jwt.clone(),
+        }))
+    }
+}
+
+pub struct JwtAuthMiddleware<S> {
+    service: S,
+    jwt: Arc<JwtService>,
+}
+
+impl<S, B: 'static> Service<ServiceRequest> for JwtAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<std::result::Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(token) = bearer_token(req.headers()) {
+            match self.jwt.verify_token(token) {
+                Ok(claims) => {
+                    req.extensions_mut().insert(claims);
+                }
+                Err(e) => return Box::pin(ready(Err(e.into()))),
+            }
+        }
+
+        Box::pin(self.service.call(req))
+    }
+}
+
+/// Claims set by [`JwtAuth`], `None` for anonymous requests
+pub struct RequestClaims(pub Option<Claims>);
+
+impl RequestClaims {
+    /// Tenant to run the request as; anonymous requests get none
+    pub fn tenant(&self) -> Option<Tenant> {
+        self.0.as_ref().and_then(|claims| claims.tenant().ok())
+    }
+
+    /// Makes the claims available to the resolvers of a GraphQL request
+    pub fn attach(self, request: async_graphql::Request) -> async_graphql::Request {
+        match self.0 {
+            Some(claims) => request.data(claims),
+            None => request,
+        }
+    }
+}
+
+impl FromRequest for RequestClaims {
+    type Error = actix_web::Error;
+    type Future = Ready<std::result::Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Self(req.extensions().get::<Claims>().cloned())))
+    }
+}
+
+/// Caller identity inside GraphQL resolvers
+pub trait AuthContext {
+    /// Claims of the authenticated caller, `Unauthorized` when anonymous
+    fn claims(&self) -> Result<&Claims>;
+
+    fn user_id(&self) -> Result<Uuid> {
+        self.claims()?.user_id()
+    }
+
+    fn organization_id(&self) -> Result<Uuid> {
+        self.claims()?.organization_id()
+    }
+}
+
+impl AuthContext for Context<'_> {
+    fn claims(&self) -> Result<&Claims> {
+        self.data_opt::<Claims>().ok_or(Error::Unauthorized)
+    }
+}
+
+/// Requires an authenticated caller: `#[graphql(guard = "AuthGuard")]`
+pub struct AuthGuard;
+
+impl Guard for AuthGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        ctx.claims().map(|_| ()).map_err(|e| e.extend())
+    }
+}
+
+/// Requires a permission: `#[graphql(guard = "PermissionGuard::new(\"QC_MANAGE\")")]`.
+/// Service tokens pass every permission check.
+pub struct PermissionGuard {
+    permission: &'static str,
+}
+
+impl PermissionGuard {
+    pub fn new(permission: &'static str) -> Self {
+        Self { permission }
+    }
+}
+
+impl Guard for PermissionGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let claims = ctx.claims().map_err(|e| e.extend())?;
+        if claims.is_service() || claims.has_permission(self.permission) {
+            Ok(())
+        } else {
+            Err(Error::InsufficientPermissions.extend())
+        }
+    }
+}
+
+/// Requires a role: `#[graphql(guard = "RoleGuard::new(\"LAB_DIRECTOR\")")]`
+pub struct RoleGuard {
+    role: &'static str,
+}
+
+impl RoleGuard {
+    pub fn new(role: &'static str) -> Self {
+        Self { role }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let claims = ctx.claims().map_err(|e| e.extend())?;
+        if claims.has_role(self.role) {
+            Ok(())
+        } else {
+            Err(Error::InsufficientPermissions.extend())
+        }
+    }
 }
 
 // Password hashing utilities
@@ -154,4 +386,126 @@ mod tests {
         assert_eq!(claims.sub, verified_claims.sub);
         assert!(verified_claims.has_permission("READ_SAMPLES"));
     }
+
+    fn test_token(claims: Claims) -> String {
+        JwtService::new("test_secret_key").generate_token(claims).unwrap()
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, "Bearer abc.def".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc.def"));
+
+        headers.insert(AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_claims_tenant() {
+        let organization_id = Uuid::new_v4();
+        let user = Claims::new(Uuid::new_v4(), organization_id, vec![], vec![], 1);
+        assert_eq!(user.tenant().unwrap(), Tenant::Organization(organization_id));
+        assert_eq!(Claims::service(Uuid::nil()).tenant().unwrap(), Tenant::System);
+
+        // A user token for the nil organization gains nothing
+        let user = Claims::new(Uuid::new_v4(), Uuid::nil(), vec![], vec![], 1);
+        assert_eq!(user.tenant().unwrap(), Tenant::Organization(Uuid::nil()));
+
+        let jwt = JwtService::new("test_secret_key");
+        assert!(jwt.tenant_token().is_err());
+        let token = Tenant::Organization(organization_id)
+            .scope(async { jwt.tenant_token().unwrap() })
+            .await;
+        assert_eq!(jwt.verify_token(&token).unwrap().tenant().unwrap(), Tenant::Organization(organization_id));
+    }
+
+    #[actix_web::test]
+    async fn test_jwt_auth_middleware() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let app = test::init_service(
+            App::new().wrap(JwtAuth::new("test_secret_key")).route(
+                "/",
+                web::get().to(|claims: RequestClaims| async move {
+                    HttpResponse::Ok().body(claims.0.map(|c| c.sub).unwrap_or_default())
+                }),
+            ),
+        )
+        .await;
+
+        let user_id = Uuid::new_v4();
+        let token = test_token(Claims::new(user_id, Uuid::new_v4(), vec![], vec![], 1));
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert!(body.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, user_id.to_string());
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, "Bearer not-a-token"))
+            .to_request();
+        let err = test::try_call_service(&app, req).await.err().unwrap();
+        assert_eq!(err.as_response_error().status_code(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_graphql_guards() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn me(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+                Ok(ctx.user_id()?.to_string())
+            }
+
+            #[graphql(guard = "PermissionGuard::new(\"VERIFY_RESULTS\")")]
+            async fn verify(&self) -> bool {
+                true
+            }
+
+            #[graphql(guard = "RoleGuard::new(\"LAB_DIRECTOR\")")]
+            async fn sign(&self) -> bool {
+                true
+            }
+        }
+
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let run = |claims: Option<Claims>, query: &'static str| {
+            let schema = schema.clone();
+            async move { schema.execute(RequestClaims(claims).attach(Request::new(query))).await }
+        };
+
+        let technician = Claims::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            vec!["TECHNICIAN".to_string()],
+            vec!["VERIFY_RESULTS".to_string()],
+            1,
+        );
+
+        let response = run(None, "{ me }").await;
+        assert_eq!(response.errors[0].message, "Unauthorized access");
+
+        let response = run(Some(technician.clone()), "{ me verify }").await;
+        assert!(response.errors.is_empty());
+        assert_eq!(response.data.into_json().unwrap()["me"], technician.sub.as_str());
+
+        let response = run(Some(technician), "{ sign }").await;
+        assert_eq!(response.errors[0].message, "Insufficient permissions");
+
+        let response = run(Some(Claims::service(Uuid::new_v4())), "{ verify }").await;
+        assert!(response.errors.is_empty());
+    }
 }
@@ -2,6 +2,10 @@ use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Errors shared across services. Services with an error type of their own
+/// convert it into this one for event handlers, and the variant they pick
+/// decides whether the consumer retries the event or dead-letters it (see
+/// `infrastructure::consumer::is_permanent`).
 #[derive(Error, Debug)]
 pub enum Error {
     // Database Errors
@@ -266,3 +270,18 @@ impl ErrorExtensions for Error {
             })
     }
 }
+
+// HTTP Error Responses
+impl actix_web::ResponseError for Error {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        actix_web::http::StatusCode::from_u16(Error::status_code(self))
+            .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        actix_web::HttpResponse::build(actix_web::ResponseError::status_code(self)).json(serde_json::json!({
+            "error": self.error_code(),
+            "message": self.to_string(),
+        }))
+    }
+}
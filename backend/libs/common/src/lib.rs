@@ -3,6 +3,8 @@ pub mod auth;
 pub mod pagination;
 pub mod utils;
 pub mod types;
+pub mod tenant;
+pub mod permissions;
 
 pub use error::{Error, Result};
 pub use pagination::{Paginated, PaginationParams, PaginationInput, Connection};
@@ -0,0 +1,147 @@
+//! Permission catalog.
+//!
+//! Every permission a GraphQL field can require, as guarded with
+//! `#[graphql(guard = "PermissionGuard::new(permissions::RESULT_APPROVE)")]`.
+//! user-service seeds the same codes into its `permission` table and bundles
+//! them into roles; access tokens carry the codes of the caller's roles.
+
+/// Catalog entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermissionDef {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub module: &'static str,
+    pub action: &'static str,
+}
+
+macro_rules! catalog {
+    ($($code:ident => $name:literal, $module:literal, $action:literal;)*) => {
+        $(
+            #[doc = $name]
+            pub const $code: &str = stringify!($code);
+        )*
+
+        /// Every permission, grouped by module
+        pub const CATALOG: &[PermissionDef] = &[
+            $(PermissionDef { code: $code, name: $name, module: $module, action: $action },)*
+        ];
+    };
+}
+
+catalog! {
+    // Patients
+    PATIENT_CREATE => "Create Patient", "PATIENT", "CREATE";
+    PATIENT_READ => "View Patient", "PATIENT", "READ";
+    PATIENT_UPDATE => "Update Patient", "PATIENT", "UPDATE";
+    PATIENT_DELETE => "Delete Patient", "PATIENT", "DELETE";
+
+    // Orders and the test catalog
+    ORDER_CREATE => "Create Order", "ORDER", "CREATE";
+    ORDER_READ => "View Order", "ORDER", "READ";
+    ORDER_UPDATE => "Update Order", "ORDER", "UPDATE";
+    ORDER_DELETE => "Delete Order", "ORDER", "DELETE";
+    ORDER_CONFIRM => "Confirm Order", "ORDER", "CONFIRM";
+    ORDER_CANCEL => "Cancel Order", "ORDER", "CANCEL";
+
+    // Samples
+    SAMPLE_CREATE => "Create Sample", "SAMPLE", "CREATE";
+    SAMPLE_READ => "View Sample", "SAMPLE", "READ";
+    SAMPLE_UPDATE => "Track Sample", "SAMPLE", "UPDATE";
+    SAMPLE_RECEIVE => "Receive Sample", "SAMPLE", "RECEIVE";
+    SAMPLE_REJECT => "Reject Sample", "SAMPLE", "REJECT";
+
+    // Results
+    RESULT_CREATE => "Create Result", "RESULT", "CREATE";
+    RESULT_READ => "View Result", "RESULT", "READ";
+    RESULT_UPDATE => "Update Result", "RESULT", "UPDATE";
+    RESULT_VERIFY => "Verify Result", "RESULT", "VERIFY";
+    RESULT_APPROVE => "Approve Result", "RESULT", "APPROVE";
+    RESULT_CORRECT => "Correct Result", "RESULT", "CORRECT";
+    RESULT_RELEASE => "Release QC-Held Results", "RESULT", "RELEASE";
+    RESULT_CRITICAL_NOTIFY => "Notify Critical Results", "RESULT", "NOTIFY";
+    RESULT_RULE_MANAGE => "Manage Auto-Verification Rules", "RESULT", "MANAGE";
+
+    // Reports
+    REPORT_READ => "View Report", "REPORT", "READ";
+    REPORT_GENERATE => "Generate Report", "REPORT", "GENERATE";
+    REPORT_DOWNLOAD => "Download Report", "REPORT", "DOWNLOAD";
+    REPORT_SIGN => "Sign Report", "REPORT", "SIGN";
+    REPORT_DELIVER => "Deliver Report", "REPORT", "DELIVER";
+    REPORT_TEMPLATE_MANAGE => "Manage Report Templates", "REPORT", "MANAGE";
+    REPORT_AUDIT => "View Report Access Log", "REPORT", "AUDIT";
+
+    // Billing
+    BILLING_CREATE => "Create Bill", "BILLING", "CREATE";
+    BILLING_READ => "View Bill", "BILLING", "READ";
+    BILLING_UPDATE => "Update Bill", "BILLING", "UPDATE";
+    BILLING_PAYMENT => "Record Payment", "BILLING", "PAYMENT";
+    BILLING_REFUND => "Issue Refund", "BILLING", "REFUND";
+    BILLING_CONFIGURE => "Manage Insurers and Discounts", "BILLING", "MANAGE";
+
+    // Quality control
+    QC_READ => "View QC", "QC", "READ";
+    QC_ENTRY => "Record QC Result", "QC", "CREATE";
+    QC_REVIEW => "Review QC", "QC", "REVIEW";
+    QC_MANAGE => "Manage QC Materials and Rules", "QC", "MANAGE";
+
+    // Equipment
+    EQUIPMENT_READ => "View Equipment", "EQUIPMENT", "READ";
+    EQUIPMENT_MAINTAIN => "Maintain Equipment", "EQUIPMENT", "MAINTAIN";
+    EQUIPMENT_MANAGE => "Manage Equipment", "EQUIPMENT", "MANAGE";
+
+    // Inventory
+    INVENTORY_READ => "View Inventory", "INVENTORY", "READ";
+    INVENTORY_UPDATE => "Update Stock", "INVENTORY", "UPDATE";
+    INVENTORY_MANAGE => "Manage Inventory", "INVENTORY", "MANAGE";
+    INVENTORY_APPROVE => "Approve Purchase Order", "INVENTORY", "APPROVE";
+
+    // Notifications
+    NOTIFICATION_READ => "View Notifications", "NOTIFICATION", "READ";
+    NOTIFICATION_SEND => "Send Notifications", "NOTIFICATION", "SEND";
+    NOTIFICATION_MANAGE => "Manage Notification Setup", "NOTIFICATION", "MANAGE";
+
+    // Compliance
+    COMPLIANCE_READ => "View Compliance", "COMPLIANCE", "READ";
+    COMPLIANCE_MANAGE => "Manage Compliance", "COMPLIANCE", "MANAGE";
+    DOCUMENT_APPROVE => "Approve Controlled Document", "COMPLIANCE", "APPROVE";
+    AUDIT_READ => "View Audit Log", "COMPLIANCE", "AUDIT";
+
+    // Instrument and HIS interfaces
+    HL7_MANAGE => "Manage HL7 Messages", "INTERFACE", "MANAGE";
+
+    // Analytics
+    ANALYTICS_READ => "View Analytics", "ANALYTICS", "READ";
+
+    // Organization
+    ORGANIZATION_READ => "View Organization", "ORGANIZATION", "READ";
+    ORGANIZATION_MANAGE => "Manage Organization", "ORGANIZATION", "MANAGE";
+    ORGANIZATION_ADMIN => "Administer All Organizations", "ORGANIZATION", "ADMIN";
+
+    // Administration
+    USER_READ => "View Users", "ADMIN", "READ";
+    USER_MANAGE => "Manage Users", "ADMIN", "MANAGE";
+    ROLE_MANAGE => "Manage Roles", "ADMIN", "MANAGE";
+    SETTINGS_MANAGE => "Manage Settings", "ADMIN", "MANAGE";
+}
+
+/// Catalog entry of a code
+pub fn find(code: &str) -> Option<&'static PermissionDef> {
+    CATALOG.iter().find(|permission| permission.code == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_codes_are_unique_and_named_after_their_constant() {
+        let mut codes: Vec<&str> = CATALOG.iter().map(|p| p.code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), CATALOG.len());
+
+        assert_eq!(RESULT_APPROVE, "RESULT_APPROVE");
+        assert_eq!(find(REPORT_SIGN).unwrap().module, "REPORT");
+        assert!(find("NOT_A_PERMISSION").is_none());
+    }
+}
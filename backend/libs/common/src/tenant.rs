@@ -0,0 +1,83 @@
+//! Tenant of the running task.
+//!
+//! Requests run inside [`Tenant::scope`] with the organization of their
+//! claims; `infrastructure::DatabasePool` hands every connection the tenant
+//! of the task that acquires it, and row-level security does the rest. Code
+//! outside any scope sees no tenant rows at all.
+//!
+//! Task-locals do not cross `tokio::spawn`; use [`spawn`] to keep the tenant.
+
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tenant {
+    /// Rows of one organization
+    Organization(Uuid),
+    /// Rows of every organization, for background jobs and lookups made
+    /// before the caller is authenticated
+    System,
+}
+
+tokio::task_local! {
+    static CURRENT: Tenant;
+}
+
+impl Tenant {
+    /// Tenant of the running task, `None` outside any scope
+    pub fn current() -> Option<Tenant> {
+        CURRENT.try_with(|tenant| *tenant).ok()
+    }
+
+    /// Run `future` as this tenant
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Run `future` as `tenant`, or outside any tenant when `None`
+pub async fn scope<F: Future>(tenant: Option<Tenant>, future: F) -> F::Output {
+    match tenant {
+        Some(tenant) => tenant.scope(future).await,
+        None => future.await,
+    }
+}
+
+/// `tokio::spawn` that keeps the tenant of the calling task
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(scope(Tenant::current(), future))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scope_sets_and_restores_the_tenant() {
+        let org = Uuid::new_v4();
+        assert_eq!(Tenant::current(), None);
+
+        let inner = Tenant::Organization(org)
+            .scope(async { (Tenant::current(), Tenant::System.scope(async { Tenant::current() }).await) })
+            .await;
+
+        assert_eq!(inner, (Some(Tenant::Organization(org)), Some(Tenant::System)));
+        assert_eq!(Tenant::current(), None);
+    }
+
+    #[tokio::test]
+    async fn spawned_tasks_keep_the_tenant() {
+        let org = Uuid::new_v4();
+        let spawned = Tenant::Organization(org)
+            .scope(async { spawn(async { Tenant::current() }).await.unwrap() })
+            .await;
+
+        assert_eq!(spawned, Some(Tenant::Organization(org)));
+    }
+}
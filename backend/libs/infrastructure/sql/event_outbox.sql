@@ -0,0 +1,27 @@
+-- Transactional outbox
+-- Domain events are written in the same transaction as the change they
+-- describe and published by infrastructure::outbox::OutboxRelay.
+
+CREATE TABLE event_outbox (
+    seq BIGSERIAL PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    topic VARCHAR(255) NOT NULL,
+    event_type VARCHAR(100) NOT NULL,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(100) NOT NULL,
+    organization_id VARCHAR(100) NOT NULL,
+    event JSONB NOT NULL,
+
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'PUBLISHED', 'FAILED')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_event_outbox_pending ON event_outbox(next_attempt_at, seq) WHERE status = 'PENDING';
+CREATE INDEX idx_event_outbox_aggregate ON event_outbox(aggregate_id, seq) WHERE status = 'PENDING';
+CREATE INDEX idx_event_outbox_published ON event_outbox(published_at) WHERE status = 'PUBLISHED';
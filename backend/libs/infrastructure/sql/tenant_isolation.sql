@@ -0,0 +1,78 @@
+-- Row-level tenant isolation helpers, shared by every service schema
+--
+-- DatabasePool stamps every connection it hands out with the tenant of the
+-- acquiring task: app.organization_id for requests, app.bypass_rls for
+-- system work. A connection with neither sees no tenant rows. Policies are
+-- forced so the owning role is bound too; superusers and BYPASSRLS roles
+-- still ignore them, so services must connect as an ordinary role.
+--
+-- database::migrator() runs this just before each service's own
+-- tenant_isolation migration, which lists the tables to isolate.
+
+CREATE OR REPLACE FUNCTION current_tenant() RETURNS UUID
+LANGUAGE sql STABLE AS $$
+    SELECT NULLIF(current_setting('app.organization_id', true), '')::uuid
+$$;
+
+CREATE OR REPLACE FUNCTION tenant_bypass() RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(current_setting('app.bypass_rls', true), '') = 'on'
+$$;
+
+-- shared_rows: rows without an organization are reference data every tenant
+-- may read (but not change or delete), granted by a separate SELECT policy
+CREATE OR REPLACE FUNCTION enable_tenant_isolation(
+    target REGCLASS,
+    tenant_column NAME DEFAULT 'organization_id',
+    shared_rows BOOLEAN DEFAULT FALSE
+) RETURNS VOID
+LANGUAGE plpgsql AS $$
+DECLARE
+    tenant TEXT := 'current_tenant()';
+BEGIN
+    -- Text columns (event_outbox) compare with the tenant as text
+    IF (SELECT atttypid FROM pg_attribute WHERE attrelid = target AND attname = tenant_column) <> 'uuid'::regtype THEN
+        tenant := 'current_tenant()::text';
+    END IF;
+
+    EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', target);
+    EXECUTE format('ALTER TABLE %s FORCE ROW LEVEL SECURITY', target);
+    EXECUTE format('DROP POLICY IF EXISTS tenant_isolation ON %s', target);
+    EXECUTE format(
+        'CREATE POLICY tenant_isolation ON %1$s
+            USING (tenant_bypass() OR %2$I = %3$s)
+            WITH CHECK (tenant_bypass() OR %2$I = %3$s)',
+        target,
+        tenant_column,
+        tenant
+    );
+
+    EXECUTE format('DROP POLICY IF EXISTS tenant_shared_rows ON %s', target);
+    IF shared_rows THEN
+        EXECUTE format('CREATE POLICY tenant_shared_rows ON %s FOR SELECT USING (%I IS NULL)', target, tenant_column);
+    END IF;
+END
+$$;
+
+-- Rows of a child table without an organization are visible and writable
+-- exactly when the parent row they reference is
+CREATE OR REPLACE FUNCTION enable_parent_isolation(
+    target REGCLASS,
+    parent_column NAME,
+    parent REGCLASS
+) RETURNS VOID
+LANGUAGE plpgsql AS $$
+BEGIN
+    EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', target);
+    EXECUTE format('ALTER TABLE %s FORCE ROW LEVEL SECURITY', target);
+    EXECUTE format('DROP POLICY IF EXISTS tenant_isolation ON %s', target);
+    EXECUTE format(
+        'CREATE POLICY tenant_isolation ON %1$s
+            USING (tenant_bypass() OR EXISTS (SELECT 1 FROM %3$s p WHERE p.id = %1$s.%2$I))
+            WITH CHECK (tenant_bypass() OR EXISTS (SELECT 1 FROM %3$s p WHERE p.id = %1$s.%2$I))',
+        target,
+        parent_column,
+        parent
+    );
+END
+$$;
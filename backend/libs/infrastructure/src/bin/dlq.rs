@@ -0,0 +1,147 @@
+//! Inspect and replay dead-lettered events.
+//!
+//! ```text
+//! dlq inspect <topic> [--event-id <id>]
+//! dlq replay <topic> [--event-id <id>]
+//! ```
+//!
+//! `<topic>` is either the consumed topic or its `.dlq` topic. The tool reads
+//! the dead-letter topic from the beginning with a throwaway consumer group
+//! and stops once no message has arrived for a few seconds; it never commits,
+//! so running it twice replays twice. The transport follows `EVENT_BACKEND`
+//! like the services do; brokers come from `KAFKA_BROKERS` (default
+//! `localhost:9092`).
+
+use std::time::Duration;
+
+use common::error::{Error, Result};
+use infrastructure::consumer::{dlq_topic, DeadLetter, EventSource, DLQ_SUFFIX};
+use infrastructure::{EventBusConfig, EventTransport};
+
+/// Quiet period after which the end of the topic is assumed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum Command {
+    Inspect,
+    Replay,
+}
+
+struct Args {
+    command: Command,
+    topic: String,
+    event_id: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: dlq <inspect|replay> <topic> [--event-id <id>]");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = std::env::args().skip(1);
+
+    let command = match args.next().as_deref() {
+        Some("inspect") => Command::Inspect,
+        Some("replay") => Command::Replay,
+        _ => usage(),
+    };
+    let topic = match args.next() {
+        Some(topic) if topic.ends_with(DLQ_SUFFIX) => topic,
+        Some(topic) => dlq_topic(&topic),
+        None => usage(),
+    };
+
+    let mut event_id = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--event-id" => event_id = Some(args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    Args {
+        command,
+        topic,
+        event_id,
+    }
+}
+
+/// Every dead letter currently in `topic`
+async fn read_all(transport: &EventTransport, topic: &str) -> Result<Vec<DeadLetter>> {
+    let group_id = format!("dlq-tool-{}", uuid::Uuid::new_v4());
+    let consumer = transport.subscribe(&group_id, &[topic])?;
+
+    let mut letters = Vec::new();
+    while let Ok(message) = tokio::time::timeout(IDLE_TIMEOUT, consumer.recv()).await {
+        let message = message?;
+        match message.decode().and_then(|envelope| DeadLetter::from_event(&envelope)) {
+            Ok(letter) => letters.push(letter),
+            Err(e) => eprintln!("Skipping {}@{}: {}", message.topic, message.offset, e),
+        }
+    }
+
+    Ok(letters)
+}
+
+fn print_letter(letter: &DeadLetter) {
+    let (event_type, event_id) = match &letter.event {
+        Some(event) => (event.event_type.clone(), event.event_id.to_string()),
+        None => ("<undecodable>".to_string(), "-".to_string()),
+    };
+
+    println!(
+        "{}  {}[{}]@{}  {}  {}  attempts={}  {}",
+        letter.failed_at.to_rfc3339(),
+        letter.topic,
+        letter.partition,
+        letter.offset,
+        event_type,
+        event_id,
+        letter.attempts,
+        letter.error
+    );
+    if let Some(raw) = &letter.raw_payload {
+        println!("    raw: {}", raw);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args();
+    let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+
+    let transport = EventTransport::connect(&EventBusConfig::from_env(&brokers)?).await?;
+
+    let letters: Vec<DeadLetter> = read_all(&transport, &args.topic)
+        .await?
+        .into_iter()
+        .filter(|letter| match &args.event_id {
+            Some(id) => letter.event.as_ref().is_some_and(|event| event.event_id.to_string() == *id),
+            None => true,
+        })
+        .collect();
+
+    match args.command {
+        Command::Inspect => {
+            for letter in &letters {
+                print_letter(letter);
+            }
+            println!("{} dead letter(s) in {}", letters.len(), args.topic);
+        }
+        Command::Replay => {
+            let mut replayed = 0;
+            for letter in &letters {
+                match letter.replay(&transport).await {
+                    Ok(()) => replayed += 1,
+                    Err(e) => eprintln!("Not replayed: {}", e),
+                }
+            }
+            println!("Replayed {} of {} dead letter(s) from {}", replayed, letters.len(), args.topic);
+            if replayed < letters.len() {
+                return Err(Error::InvalidState("Some dead letters could not be replayed".to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,662 @@
+//! Consumer runtime for domain events.
+//!
+//! An [`EventRouter`] maps event types to async handlers. [`ConsumerRuntime`]
+//! pulls messages from an [`EventSource`], dispatches them and only then
+//! commits them, so a crash redelivers a message instead of losing it.
+//! Failing handlers are retried with exponential backoff. Messages that cannot
+//! be decoded, that a handler rejects, or that keep failing are wrapped in a
+//! [`DeadLetter`] and published to `<topic>.dlq`; the `dlq` binary inspects
+//! and replays them.
+//!
+//! Delivery is at least once, so handlers must be idempotent. Handlers run as
+//! the tenant of the event's organization.
+
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use common::error::{Error, Result};
+use common::tenant::{self, Tenant};
+
+use crate::event_bus::{DomainEvent, EventPublisher};
+
+/// Suffix of the dead-letter topic kept next to every consumed topic
+pub const DLQ_SUFFIX: &str = ".dlq";
+
+/// Event type of the envelope published to dead-letter topics
+pub const DEAD_LETTER: &str = "dead_letter";
+
+pub fn dlq_topic(topic: &str) -> String {
+    format!("{}{}", topic, DLQ_SUFFIX)
+}
+
+/// Errors that retrying cannot fix; the message goes straight to the DLQ
+pub fn is_permanent(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Serialization(_) | Error::Validation(_) | Error::InvalidInput(_)
+    )
+}
+
+// ============================================================================
+// Sources
+// ============================================================================
+
+/// A message as read from a topic, before decoding
+#[derive(Debug, Clone)]
+pub struct ConsumedMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+impl ConsumedMessage {
+    pub fn decode(&self) -> Result<DomainEvent> {
+        serde_json::from_slice(&self.payload).map_err(Error::Serialization)
+    }
+}
+
+/// Subscription that hands out messages and records which ones are processed
+pub trait EventSource: Send + Sync {
+    /// Wait for the next message on the subscribed topics
+    fn recv(&self) -> impl Future<Output = Result<ConsumedMessage>> + Send;
+
+    /// Mark `message` as processed so the consumer group does not see it again
+    fn commit(&self, message: &ConsumedMessage) -> impl Future<Output = Result<()>> + Send;
+
+    /// Messages as a stream; nothing is committed by reading it
+    fn stream(&self) -> impl Stream<Item = Result<ConsumedMessage>> + Send + '_
+    where
+        Self: Sized,
+    {
+        futures::stream::unfold(self, |source| async move { Some((source.recv().await, source)) })
+    }
+}
+
+// ============================================================================
+// Routing
+// ============================================================================
+
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type Handler = Arc<dyn Fn(DomainEvent) -> HandlerFuture + Send + Sync>;
+
+/// Handlers keyed by event type
+#[derive(Clone, Default)]
+pub struct EventRouter {
+    handlers: HashMap<String, Handler>,
+}
+
+impl EventRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle `event_type` with `handler`, replacing any earlier handler
+    pub fn on<F, Fut>(mut self, event_type: &str, handler: F) -> Self
+    where
+        F: Fn(DomainEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |event| Box::pin(handler(event)));
+        self.handlers.insert(event_type.to_string(), handler);
+        self
+    }
+
+    /// Handle `event_type` with the payload decoded as `T`. A payload that
+    /// does not decode is dead-lettered without retrying.
+    pub fn on_payload<T, F, Fut>(self, event_type: &str, handler: F) -> Self
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(DomainEvent, T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.on(event_type, move |event| {
+            let handler = handler.clone();
+            async move {
+                let payload = serde_json::from_value::<T>(event.payload.clone()).map_err(Error::Serialization)?;
+                handler(event, payload).await
+            }
+        })
+    }
+
+    pub fn event_types(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Run the handler for `event`; `Ok(false)` when none is registered
+    pub async fn dispatch(&self, event: &DomainEvent) -> Result<bool> {
+        match self.handlers.get(&event.event_type) {
+            Some(handler) => handler(event.clone()).await.map(|()| true),
+            None => Ok(false),
+        }
+    }
+}
+
+// ============================================================================
+// Dead Letters
+// ============================================================================
+
+/// A message that could not be processed, as published to the DLQ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Topic the message was consumed from
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    pub error: String,
+    /// Handler attempts made; 0 when the message was never dispatched
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+    /// The failed event; absent when the message was not a domain event
+    pub event: Option<DomainEvent>,
+    /// The undecodable message body
+    pub raw_payload: Option<String>,
+}
+
+impl DeadLetter {
+    pub fn new(message: &ConsumedMessage, event: Option<DomainEvent>, error: &Error, attempts: u32) -> Self {
+        let raw_payload = match event {
+            Some(_) => None,
+            None => Some(String::from_utf8_lossy(&message.payload).into_owned()),
+        };
+
+        Self {
+            topic: message.topic.clone(),
+            partition: message.partition,
+            offset: message.offset,
+            key: message.key.clone(),
+            error: error.to_string(),
+            attempts,
+            failed_at: Utc::now(),
+            event,
+            raw_payload,
+        }
+    }
+
+    /// Envelope published to the DLQ. It keeps the original aggregate as key
+    /// and points back at the failed event through `causation_id`.
+    pub fn to_event(&self) -> Result<DomainEvent> {
+        let payload = serde_json::to_value(self).map_err(Error::Serialization)?;
+        Ok(self.envelope(payload))
+    }
+
+    /// Envelope for a letter whose event cannot be serialized: the body of
+    /// `message` as consumed takes the event's place, so the message is still
+    /// stored before it is committed
+    pub fn to_raw_event(&self, message: &ConsumedMessage) -> DomainEvent {
+        self.envelope(serde_json::json!({
+            "topic": self.topic,
+            "partition": self.partition,
+            "offset": self.offset,
+            "key": self.key,
+            "error": self.error,
+            "attempts": self.attempts,
+            "failed_at": self.failed_at,
+            "event": null,
+            "raw_payload": String::from_utf8_lossy(&message.payload),
+        }))
+    }
+
+    fn envelope(&self, payload: serde_json::Value) -> DomainEvent {
+        let aggregate_id = match (&self.event, &self.key) {
+            (Some(event), _) => event.aggregate_id.clone(),
+            (None, Some(key)) => key.clone(),
+            (None, None) => format!("{}:{}:{}", self.topic, self.partition, self.offset),
+        };
+        let organization_id = self
+            .event
+            .as_ref()
+            .map(|event| event.metadata.organization_id.clone())
+            .unwrap_or_default();
+
+        let mut envelope = DomainEvent::new(
+            DEAD_LETTER.to_string(),
+            aggregate_id,
+            "DeadLetter".to_string(),
+            payload,
+            organization_id,
+            None,
+        );
+        envelope.metadata.causation_id = self.event.as_ref().map(|event| event.event_id.to_string());
+
+        envelope
+    }
+
+    pub fn from_event(envelope: &DomainEvent) -> Result<Self> {
+        if envelope.event_type != DEAD_LETTER {
+            return Err(Error::InvalidInput(format!(
+                "{} event {} is not a dead letter",
+                envelope.event_type, envelope.event_id
+            )));
+        }
+        serde_json::from_value(envelope.payload.clone()).map_err(Error::Serialization)
+    }
+
+    /// Publish the failed event to its original topic again
+    pub async fn replay<P: EventPublisher>(&self, publisher: &P) -> Result<()> {
+        let event = self.event.as_ref().ok_or_else(|| {
+            Error::InvalidState(format!(
+                "Dead letter from {}:{}@{} holds no domain event to replay",
+                self.topic, self.partition, self.offset
+            ))
+        })?;
+
+        publisher.publish(&self.topic, event).await
+    }
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Handler attempts before the message is dead-lettered
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt after `attempts` failed ones
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(20);
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+/// What happened to a consumed message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Handled,
+    /// No handler is registered for the event type
+    Ignored,
+    DeadLettered,
+}
+
+/// Reads a source, dispatches to the router and commits after each message
+pub struct ConsumerRuntime<S, P> {
+    source: S,
+    router: EventRouter,
+    dead_letters: P,
+    retry: RetryPolicy,
+}
+
+impl<S: EventSource, P: EventPublisher> ConsumerRuntime<S, P> {
+    /// `dead_letters` publishes to the `.dlq` topics
+    pub fn new(source: S, router: EventRouter, dead_letters: P) -> Self {
+        Self {
+            source,
+            router,
+            dead_letters,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Handle one message, retrying and dead-lettering as needed. Returns
+    /// once the message may be committed.
+    pub async fn process(&self, message: &ConsumedMessage) -> Outcome {
+        let event = match message.decode() {
+            Ok(event) => event,
+            Err(e) => {
+                self.dead_letter(message, DeadLetter::new(message, None, &e, 0)).await;
+                return Outcome::DeadLettered;
+            }
+        };
+
+        let tenant = event.organization_uuid().map(Tenant::Organization);
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+
+            match tenant::scope(tenant, self.router.dispatch(&event)).await {
+                Ok(true) => return Outcome::Handled,
+                Ok(false) => return Outcome::Ignored,
+                Err(e) if is_permanent(&e) || attempts >= self.retry.max_attempts => {
+                    error!(
+                        "Dead-lettering {} event {} after {} attempt(s): {}",
+                        event.event_type, event.event_id, attempts, e
+                    );
+                    let letter = DeadLetter::new(message, Some(event), &e, attempts);
+                    self.dead_letter(message, letter).await;
+                    return Outcome::DeadLettered;
+                }
+                Err(e) => {
+                    warn!(
+                        "Handling {} event {} failed, attempt {}: {}",
+                        event.event_type, event.event_id, attempts, e
+                    );
+                    tokio::time::sleep(self.retry.backoff(attempts)).await;
+                }
+            }
+        }
+    }
+
+    /// Publish to the DLQ, retrying until it succeeds: the message must not
+    /// be committed before it is safely stored somewhere
+    async fn dead_letter(&self, message: &ConsumedMessage, letter: DeadLetter) {
+        let topic = dlq_topic(&letter.topic);
+        let envelope = letter.to_event().unwrap_or_else(|e| {
+            error!(
+                "Failed to build dead letter for {}@{}, keeping the raw message: {}",
+                letter.topic, letter.offset, e
+            );
+            letter.to_raw_event(message)
+        });
+
+        let mut failures = 0;
+        while let Err(e) = self.dead_letters.publish(&topic, &envelope).await {
+            failures += 1;
+            error!("Failed to publish dead letter to {} (attempt {}): {}", topic, failures, e);
+            tokio::time::sleep(self.retry.backoff(failures)).await;
+        }
+    }
+
+    /// Consume until the task is dropped
+    pub async fn run(self) {
+        info!("Event consumer started for: {:?}", self.router.event_types().collect::<Vec<_>>());
+
+        loop {
+            let message = match self.source.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Failed to receive event: {}", e);
+                    tokio::time::sleep(self.retry.base_backoff).await;
+                    continue;
+                }
+            };
+
+            let outcome = self.process(&message).await;
+            debug!("{}@{}: {:?}", message.topic, message.offset, outcome);
+
+            if let Err(e) = self.source.commit(&message).await {
+                error!("Failed to commit {}@{}: {}", message.topic, message.offset, e);
+            }
+        }
+    }
+}
+
+impl<S, P> ConsumerRuntime<S, P>
+where
+    S: EventSource + 'static,
+    P: EventPublisher + 'static,
+{
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::events;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingPublisher {
+        published: Mutex<Vec<(String, DomainEvent)>>,
+    }
+
+    impl EventPublisher for RecordingPublisher {
+        async fn publish(&self, topic: &str, event: &DomainEvent) -> Result<()> {
+            self.published.lock().unwrap().push((topic.to_string(), event.clone()));
+            Ok(())
+        }
+    }
+
+    struct NoSource;
+
+    impl EventSource for NoSource {
+        async fn recv(&self) -> Result<ConsumedMessage> {
+            std::future::pending().await
+        }
+
+        async fn commit(&self, _message: &ConsumedMessage) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn order_created() -> DomainEvent {
+        DomainEvent::new(
+            events::ORDER_CREATED.to_string(),
+            "order-1".to_string(),
+            "TestOrder".to_string(),
+            serde_json::json!({ "order_number": "ORD-1" }),
+            "org-1".to_string(),
+            None,
+        )
+    }
+
+    fn message(event: &DomainEvent) -> ConsumedMessage {
+        ConsumedMessage {
+            topic: "lis.order.events".to_string(),
+            partition: 0,
+            offset: 42,
+            key: Some(event.aggregate_id.clone()),
+            payload: serde_json::to_vec(event).unwrap(),
+        }
+    }
+
+    fn runtime(router: EventRouter) -> ConsumerRuntime<NoSource, RecordingPublisher> {
+        ConsumerRuntime::new(NoSource, router, RecordingPublisher::default()).with_retry(RetryPolicy {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        })
+    }
+
+    fn dead_letters(runtime: &ConsumerRuntime<NoSource, RecordingPublisher>) -> Vec<(String, DeadLetter)> {
+        runtime
+            .dead_letters
+            .published
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(topic, event)| (topic.clone(), DeadLetter::from_event(event).unwrap()))
+            .collect()
+    }
+
+    #[derive(Deserialize)]
+    struct OrderCreated {
+        order_number: String,
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_receives_payload() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let runtime = runtime(EventRouter::new().on_payload(
+            events::ORDER_CREATED,
+            move |_event, payload: OrderCreated| {
+                let recorded = recorded.clone();
+                async move {
+                    recorded.lock().unwrap().push(payload.order_number);
+                    Ok(())
+                }
+            },
+        ));
+
+        assert_eq!(runtime.process(&message(&order_created())).await, Outcome::Handled);
+        assert_eq!(*seen.lock().unwrap(), vec!["ORD-1".to_string()]);
+        assert!(dead_letters(&runtime).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handlers_run_as_the_event_organization() {
+        let organization_id = uuid::Uuid::new_v4();
+        let mut event = order_created();
+        event.metadata.organization_id = organization_id.to_string();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let runtime = runtime(EventRouter::new().on(events::ORDER_CREATED, move |_event| {
+            let recorded = recorded.clone();
+            async move {
+                recorded.lock().unwrap().push(Tenant::current());
+                Ok(())
+            }
+        }));
+
+        assert_eq!(runtime.process(&message(&event)).await, Outcome::Handled);
+        // Events without an organization run without a tenant
+        assert_eq!(runtime.process(&message(&order_created())).await, Outcome::Handled);
+        assert_eq!(*seen.lock().unwrap(), vec![Some(Tenant::Organization(organization_id)), None]);
+    }
+
+    #[tokio::test]
+    async fn test_unrouted_events_are_ignored() {
+        let runtime = runtime(EventRouter::new());
+        assert_eq!(runtime.process(&message(&order_created())).await, Outcome::Ignored);
+        assert!(dead_letters(&runtime).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let runtime = runtime(EventRouter::new().on(events::ORDER_CREATED, move |_event| {
+            let counter = counter.clone();
+            async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Error::ExternalService("billing unavailable".to_string())),
+                    _ => Ok(()),
+                }
+            }
+        }));
+
+        assert_eq!(runtime.process(&message(&order_created())).await, Outcome::Handled);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_go_to_the_dlq() {
+        let runtime = runtime(EventRouter::new().on(events::ORDER_CREATED, |_event| async {
+            Err(Error::ExternalService("billing unavailable".to_string()))
+        }));
+        let event = order_created();
+
+        assert_eq!(runtime.process(&message(&event)).await, Outcome::DeadLettered);
+
+        let letters = dead_letters(&runtime);
+        assert_eq!(letters.len(), 1);
+        let (topic, letter) = &letters[0];
+        assert_eq!(topic, "lis.order.events.dlq");
+        assert_eq!(letter.attempts, 3);
+        assert_eq!(letter.offset, 42);
+        assert_eq!(letter.event.as_ref().unwrap().event_id, event.event_id);
+        assert!(letter.error.contains("billing unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_payload_is_dead_lettered_without_retry() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let runtime = runtime(EventRouter::new().on_payload(
+            events::ORDER_CREATED,
+            move |_event, _payload: OrderCreated| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok(()) }
+            },
+        ));
+        let mut event = order_created();
+        event.payload = serde_json::json!({ "unexpected": true });
+
+        assert_eq!(runtime.process(&message(&event)).await, Outcome::DeadLettered);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(dead_letters(&runtime)[0].1.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_poison_message_keeps_its_raw_body() {
+        let runtime = runtime(EventRouter::new());
+        let poison = ConsumedMessage {
+            payload: b"not json".to_vec(),
+            ..message(&order_created())
+        };
+
+        assert_eq!(runtime.process(&poison).await, Outcome::DeadLettered);
+
+        let (_, letter) = &dead_letters(&runtime)[0];
+        assert!(letter.event.is_none());
+        assert_eq!(letter.raw_payload.as_deref(), Some("not json"));
+        assert_eq!(letter.attempts, 0);
+    }
+
+    #[test]
+    fn test_raw_envelope_keeps_the_message_body() {
+        let event = order_created();
+        let message = message(&event);
+        let letter = DeadLetter::new(&message, Some(event.clone()), &Error::InternalServerError, 5);
+
+        let envelope = letter.to_raw_event(&message);
+        assert_eq!(envelope.aggregate_id, "order-1");
+        assert_eq!(envelope.metadata.causation_id, Some(event.event_id.to_string()));
+
+        let restored = DeadLetter::from_event(&envelope).unwrap();
+        assert!(restored.event.is_none());
+        assert_eq!(restored.raw_payload.map(|raw| raw.into_bytes()), Some(message.payload));
+        assert_eq!(restored.attempts, 5);
+        assert_eq!(restored.offset, 42);
+    }
+
+    #[tokio::test]
+    async fn test_replay_republishes_to_the_original_topic() {
+        let event = order_created();
+        let letter = DeadLetter::new(&message(&event), Some(event.clone()), &Error::InternalServerError, 5);
+        let restored = DeadLetter::from_event(&letter.to_event().unwrap()).unwrap();
+
+        let publisher = RecordingPublisher::default();
+        restored.replay(&publisher).await.unwrap();
+
+        let published = publisher.published.lock().unwrap();
+        assert_eq!(published[0].0, "lis.order.events");
+        assert_eq!(published[0].1.event_id, event.event_id);
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(3),
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(3));
+    }
+}
@@ -1,9 +1,32 @@
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use futures::future::BoxFuture;
+use sqlx::migrate::{Migration, MigrationType, Migrator};
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions};
 use sqlx::Error as SqlxError;
 use std::time::Duration;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
 use common::error::{Error, Result};
+use common::tenant::Tenant;
+
+/// Hand `conn` the tenant of the acquiring task, read by the
+/// `tenant_isolation` policies. Session-level, so every acquire overwrites
+/// whatever the previous user of the connection left.
+fn apply_tenant(conn: &mut PgConnection) -> BoxFuture<'_, std::result::Result<(), SqlxError>> {
+    let (organization_id, bypass) = match Tenant::current() {
+        Some(Tenant::Organization(id)) => (id.to_string(), "off"),
+        Some(Tenant::System) => (String::new(), "on"),
+        None => (String::new(), "off"),
+    };
+
+    Box::pin(async move {
+        sqlx::query("SELECT set_config('app.organization_id', $1, false), set_config('app.bypass_rls', $2, false)")
+            .bind(organization_id)
+            .bind(bypass)
+            .execute(conn)
+            .await?;
+        Ok(())
+    })
+}
 
 #[derive(Clone)]
 pub struct DatabasePool {
@@ -19,6 +42,11 @@ impl DatabasePool {
             .acquire_timeout(Duration::from_secs(30))
             .idle_timeout(Duration::from_secs(600))
             .max_lifetime(Duration::from_secs(1800))
+            .after_connect(|conn, _| apply_tenant(conn))
+            .before_acquire(|conn, _| {
+                let applied = apply_tenant(conn);
+                Box::pin(async move { applied.await.map(|_| true) })
+            })
             .connect(database_url)
             .await
             .map_err(|e| {
@@ -35,6 +63,17 @@ impl DatabasePool {
                 Error::Database(e)
             })?;
 
+        // Superusers and BYPASSRLS roles ignore every policy
+        let bypasses_rls: bool = sqlx::query_scalar(
+            "SELECT rolsuper OR rolbypassrls FROM pg_roles WHERE rolname = current_user",
+        )
+        .fetch_one(&pg_pool)
+        .await
+        .map_err(Error::Database)?;
+        if bypasses_rls {
+            warn!("Database role bypasses row-level security; tenant isolation is not enforced");
+        }
+
         info!("Successfully connected to PostgreSQL database");
 
         Ok(Self { pg_pool })
@@ -63,6 +102,38 @@ impl DatabasePool {
     }
 }
 
+/// Version of the shared tenant isolation migration, just before the
+/// `20250113000001_tenant_isolation` migration of every service
+const TENANT_ISOLATION_VERSION: i64 = 20250113000000;
+
+/// A service's migrations plus the ones every service schema shares: the
+/// row-level security helpers its own tenant_isolation migration calls
+pub fn migrator(service: Migrator) -> Migrator {
+    with_migration(
+        service,
+        TENANT_ISOLATION_VERSION,
+        "tenant isolation helpers",
+        include_str!("../sql/tenant_isolation.sql"),
+    )
+}
+
+/// Adds a migration shipped by this crate to a service's migrations
+pub(crate) fn with_migration(service: Migrator, version: i64, description: &'static str, sql: &'static str) -> Migrator {
+    let mut migrations = service.migrations.into_owned();
+    migrations.push(Migration::new(
+        version,
+        description.into(),
+        MigrationType::Simple,
+        sql.into(),
+    ));
+    migrations.sort_by_key(|migration| migration.version);
+
+    Migrator {
+        migrations: migrations.into(),
+        ..service
+    }
+}
+
 /// Transaction helper for multi-step operations
 pub struct Transaction<'a> {
     tx: sqlx::Transaction<'a, sqlx::Postgres>,
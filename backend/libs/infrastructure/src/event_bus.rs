@@ -1,15 +1,17 @@
 use rdkafka::config::ClientConfig;
 use rdkafka::producer::{FutureProducer, FutureRecord};
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::message::{Header, Message, OwnedHeaders};
 use rdkafka::util::Timeout;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use tracing::{info, error, warn};
+use tracing::{info, error};
 use uuid::Uuid;
 
 use common::error::{Error, Result};
 
+use crate::consumer::{ConsumedMessage, EventSource};
+
 /// Domain event that gets published to Kafka
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainEvent {
@@ -54,6 +56,15 @@ impl DomainEvent {
             },
         }
     }
+
+    pub fn organization_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.metadata.organization_id).ok()
+    }
+
+    /// The user who caused the event, when it was caused by one
+    pub fn user_uuid(&self) -> Option<Uuid> {
+        self.metadata.user_id.as_deref().and_then(|id| Uuid::parse_str(id).ok())
+    }
 }
 
 /// Event bus for publishing and consuming domain events
@@ -89,6 +100,10 @@ impl EventBus {
         })
     }
 
+    pub fn brokers(&self) -> &str {
+        &self.brokers
+    }
+
     pub async fn publish(&self, topic: &str, event: &DomainEvent) -> Result<()> {
         let key = event.aggregate_id.clone();
         let payload = serde_json::to_string(event)
@@ -148,7 +163,10 @@ impl EventPublisher for EventBus {
     }
 }
 
-/// Event consumer for subscribing to topics
+/// Event consumer for subscribing to topics.
+///
+/// Offsets are committed only for messages passed to
+/// [`EventSource::commit`]; see [`crate::consumer::ConsumerRuntime`].
 pub struct EventConsumer {
     consumer: StreamConsumer,
 }
@@ -161,6 +179,7 @@ impl EventConsumer {
             .set("bootstrap.servers", brokers)
             .set("group.id", group_id)
             .set("enable.auto.commit", "true")
+            .set("enable.auto.offset.store", "false")
             .set("auto.commit.interval.ms", "5000")
             .set("session.timeout.ms", "30000")
             .set("enable.partition.eof", "false")
@@ -182,9 +201,30 @@ impl EventConsumer {
 
         Ok(Self { consumer })
     }
+}
 
-    // In production, would implement async stream consumption
-    // pub async fn consume_stream(&self) -> impl Stream<Item = Result<DomainEvent>>
+impl EventSource for EventConsumer {
+    async fn recv(&self) -> Result<ConsumedMessage> {
+        let message = self.consumer
+            .recv()
+            .await
+            .map_err(|e| Error::Kafka(format!("Event receive failed: {}", e)))?;
+
+        Ok(ConsumedMessage {
+            topic: message.topic().to_string(),
+            partition: message.partition(),
+            offset: message.offset(),
+            key: message.key().map(|key| String::from_utf8_lossy(key).into_owned()),
+            payload: message.payload().unwrap_or_default().to_vec(),
+        })
+    }
+
+    async fn commit(&self, message: &ConsumedMessage) -> Result<()> {
+        // Stores offset + 1; the auto-commit timer sends it to the broker
+        self.consumer
+            .store_offset(&message.topic, message.partition, message.offset)
+            .map_err(|e| Error::Kafka(format!("Offset store failed: {}", e)))
+    }
 }
 
 // Common event types as constants
@@ -209,8 +249,10 @@ pub mod events {
     // Result events
     pub const RESULT_ENTERED: &str = "result.entered";
     pub const RESULT_VERIFIED: &str = "result.verified";
+    pub const RESULT_APPROVED: &str = "result.approved";
     pub const RESULT_AMENDED: &str = "result.amended";
     pub const CRITICAL_VALUE_DETECTED: &str = "result.critical_value_detected";
+    pub const CRITICAL_VALUE_ACKNOWLEDGED: &str = "result.critical_value_acknowledged";
 
     // Report events
     pub const REPORT_GENERATED: &str = "report.generated";
@@ -220,6 +262,10 @@ pub mod events {
     // Payment events
     pub const PAYMENT_RECEIVED: &str = "payment.received";
     pub const PAYMENT_FAILED: &str = "payment.failed";
+
+    // Notification events
+    pub const NOTIFICATION_DELIVERED: &str = "notification.delivered";
+    pub const NOTIFICATION_FAILED: &str = "notification.failed";
 }
 
 // Topic names
@@ -283,7 +283,27 @@ pub mod whatsapp {
     pub struct Parameter {
         #[serde(rename = "type")]
         pub type_: String,
-        pub text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub text: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub document: Option<DocumentContent>,
+    }
+
+    #[derive(Serialize)]
+    pub struct DocumentContent {
+        /// Public URL WhatsApp downloads the document from
+        pub link: String,
+        pub filename: String,
+    }
+
+    impl Parameter {
+        pub fn text(text: String) -> Self {
+            Self { type_: "text".to_string(), text: Some(text), document: None }
+        }
+
+        pub fn document(link: String, filename: String) -> Self {
+            Self { type_: "document".to_string(), text: None, document: Some(DocumentContent { link, filename }) }
+        }
     }
 
     #[derive(Deserialize)]
@@ -304,6 +324,77 @@ pub mod whatsapp {
         pub id: String,
     }
 
+    /// Body of a webhook call; only message status updates are read
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WebhookPayload {
+        #[serde(default)]
+        pub entry: Vec<WebhookEntry>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WebhookEntry {
+        #[serde(default)]
+        pub changes: Vec<WebhookChange>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WebhookChange {
+        pub value: WebhookValue,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WebhookValue {
+        #[serde(default)]
+        pub statuses: Vec<MessageStatus>,
+    }
+
+    /// Status of a sent message: `sent`, `delivered`, `read` or `failed`
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct MessageStatus {
+        pub id: String,
+        pub status: String,
+        pub timestamp: Option<String>,
+        pub recipient_id: Option<String>,
+        #[serde(default)]
+        pub errors: Vec<StatusError>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct StatusError {
+        pub code: Option<i64>,
+        pub title: Option<String>,
+        pub message: Option<String>,
+    }
+
+    impl WebhookPayload {
+        pub fn statuses(&self) -> impl Iterator<Item = &MessageStatus> {
+            self.entry
+                .iter()
+                .flat_map(|entry| &entry.changes)
+                .flat_map(|change| &change.value.statuses)
+        }
+    }
+
+    /// Checks the `X-Hub-Signature-256` header of a webhook call, the
+    /// HMAC-SHA256 of the raw body keyed with the app secret
+    pub fn verify_webhook_signature(app_secret: &str, body: &[u8], signature_header: &str) -> bool {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        let Some(signature) = signature_header
+            .strip_prefix("sha256=")
+            .and_then(|hex_signature| hex::decode(hex_signature.trim()).ok())
+        else {
+            return false;
+        };
+
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes()) else {
+            return false;
+        };
+        mac.update(body);
+        mac.verify_slice(&signature).is_ok()
+    }
+
     impl WhatsAppClient {
         pub fn new(api_url: String, phone_number_id: String, access_token: String) -> Self {
             Self {
@@ -345,13 +436,7 @@ pub mod whatsapp {
 
             let components = vec![Component {
                 type_: "body".to_string(),
-                parameters: parameters
-                    .into_iter()
-                    .map(|p| Parameter {
-                        type_: "text".to_string(),
-                        text: p,
-                    })
-                    .collect(),
+                parameters: parameters.into_iter().map(Parameter::text).collect(),
             }];
 
             let request = WhatsAppTemplateMessage {
@@ -375,6 +460,51 @@ pub mod whatsapp {
             self.http_client.post(&url, &request, headers).await
                 .map_err(|e| Error::WhatsAppError(e.to_string()))
         }
+
+        /// Send an approved template whose header is a document, such as a
+        /// PDF report
+        pub async fn send_document_template(
+            &self,
+            to: &str,
+            template_name: &str,
+            document_link: &str,
+            filename: &str,
+            parameters: Vec<String>,
+        ) -> Result<WhatsAppResponse> {
+            let url = format!("{}/{}/messages", self.api_url, self.phone_number_id);
+
+            let components = vec![
+                Component {
+                    type_: "header".to_string(),
+                    parameters: vec![Parameter::document(document_link.to_string(), filename.to_string())],
+                },
+                Component {
+                    type_: "body".to_string(),
+                    parameters: parameters.into_iter().map(Parameter::text).collect(),
+                },
+            ];
+
+            let request = WhatsAppTemplateMessage {
+                messaging_product: "whatsapp".to_string(),
+                to: to.to_string(),
+                type_: "template".to_string(),
+                template: Template {
+                    name: template_name.to_string(),
+                    language: Language {
+                        code: "en".to_string(),
+                    },
+                    components,
+                },
+            };
+
+            let auth_header = format!("Bearer {}", self.access_token);
+            let headers = vec![
+                ("Authorization", auth_header.as_str()),
+            ];
+
+            self.http_client.post(&url, &request, headers).await
+                .map_err(|e| Error::WhatsAppError(e.to_string()))
+        }
     }
 }
 
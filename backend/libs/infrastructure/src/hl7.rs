@@ -0,0 +1,635 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use common::error::{Error, Result};
+
+// ============================================================================
+// MLLP (Minimal Lower Layer Protocol) Framing
+// ============================================================================
+
+pub const MLLP_START: u8 = 0x0B;
+pub const MLLP_END: u8 = 0x1C;
+pub const MLLP_CR: u8 = 0x0D;
+
+/// Wraps a message in an MLLP block: `<VT> message <FS><CR>`
+pub fn mllp_encode(message: &str) -> Vec<u8> {
+    let mut block = Vec::with_capacity(message.len() + 3);
+    block.push(MLLP_START);
+    block.extend_from_slice(message.as_bytes());
+    block.push(MLLP_END);
+    block.push(MLLP_CR);
+    block
+}
+
+/// Incremental MLLP decoder that tolerates blocks split across reads
+#[derive(Debug, Default)]
+pub struct MllpDecoder {
+    buffer: Vec<u8>,
+    in_block: bool,
+}
+
+impl MllpDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut messages = Vec::new();
+
+        for &byte in bytes {
+            match byte {
+                MLLP_START => {
+                    self.buffer.clear();
+                    self.in_block = true;
+                }
+                MLLP_END if self.in_block => {
+                    messages.push(String::from_utf8_lossy(&self.buffer).to_string());
+                    self.buffer.clear();
+                    self.in_block = false;
+                }
+                _ if self.in_block => self.buffer.push(byte),
+                // Trailing CR after <FS> and bytes outside a block are ignored
+                _ => {}
+            }
+        }
+
+        messages
+    }
+}
+
+pub async fn write_mllp_message<S: AsyncWrite + Unpin>(stream: &mut S, message: &str) -> Result<()> {
+    stream.write_all(&mllp_encode(message)).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Reads the next complete MLLP block. Returns `None` when the peer closes the connection.
+pub async fn read_mllp_message<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    decoder: &mut MllpDecoder,
+    pending: &mut Vec<String>,
+) -> Result<Option<String>> {
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        if !pending.is_empty() {
+            return Ok(Some(pending.remove(0)));
+        }
+
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        pending.extend(decoder.feed(&buffer[..read]));
+    }
+}
+
+/// Sends HL7 messages to a remote MLLP listener and waits for the acknowledgment
+#[derive(Clone)]
+pub struct MllpClient {
+    address: String,
+    timeout: Duration,
+}
+
+impl MllpClient {
+    pub fn new(address: String, timeout: Duration) -> Self {
+        Self { address, timeout }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Sends one message and returns the raw acknowledgment
+    pub async fn send(&self, message: &str) -> Result<String> {
+        let exchange = async {
+            let mut stream = TcpStream::connect(&self.address).await?;
+            write_mllp_message(&mut stream, message).await?;
+
+            let mut decoder = MllpDecoder::new();
+            let mut pending = Vec::new();
+            read_mllp_message(&mut stream, &mut decoder, &mut pending)
+                .await?
+                .ok_or_else(|| Error::ExternalService("Connection closed before acknowledgment".to_string()))
+        };
+
+        match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::ExternalService(format!(
+                "Timed out waiting for HL7 acknowledgment from {}",
+                self.address
+            ))),
+        }
+    }
+}
+
+// ============================================================================
+// HL7 v2 Message Model
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingCharacters {
+    pub field: char,
+    pub component: char,
+    pub repetition: char,
+    pub escape: char,
+    pub subcomponent: char,
+}
+
+impl Default for EncodingCharacters {
+    fn default() -> Self {
+        Self {
+            field: '|',
+            component: '^',
+            repetition: '~',
+            escape: '\\',
+            subcomponent: '&',
+        }
+    }
+}
+
+impl EncodingCharacters {
+    fn msh2(&self) -> String {
+        [self.component, self.repetition, self.escape, self.subcomponent]
+            .iter()
+            .collect()
+    }
+
+    pub fn escape(&self, value: &str) -> String {
+        let e = self.escape;
+        let mut escaped = String::with_capacity(value.len());
+
+        for c in value.chars() {
+            if c == self.escape {
+                escaped.push_str(&format!("{e}E{e}"));
+            } else if c == self.field {
+                escaped.push_str(&format!("{e}F{e}"));
+            } else if c == self.component {
+                escaped.push_str(&format!("{e}S{e}"));
+            } else if c == self.repetition {
+                escaped.push_str(&format!("{e}R{e}"));
+            } else if c == self.subcomponent {
+                escaped.push_str(&format!("{e}T{e}"));
+            } else if c == '\r' || c == '\n' {
+                escaped.push_str(&format!("{e}.br{e}"));
+            } else {
+                escaped.push(c);
+            }
+        }
+
+        escaped
+    }
+
+    pub fn unescape(&self, value: &str) -> String {
+        let e = self.escape;
+        value
+            .replace(&format!("{e}F{e}"), &self.field.to_string())
+            .replace(&format!("{e}S{e}"), &self.component.to_string())
+            .replace(&format!("{e}R{e}"), &self.repetition.to_string())
+            .replace(&format!("{e}T{e}"), &self.subcomponent.to_string())
+            .replace(&format!("{e}.br{e}"), "\n")
+            .replace(&format!("{e}E{e}"), &self.escape.to_string())
+    }
+}
+
+/// One segment. Fields are addressed with standard HL7 numbering
+/// (`PID-3` is `field(3)`, `MSH-9` is `field(9)`).
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub name: String,
+    fields: Vec<String>,
+    encoding: EncodingCharacters,
+}
+
+impl Segment {
+    pub fn new(name: &str) -> Self {
+        let encoding = EncodingCharacters::default();
+        let mut fields = vec![name.to_string()];
+        if name == "MSH" {
+            fields.push(encoding.msh2());
+        }
+
+        Self {
+            name: name.to_string(),
+            fields,
+            encoding,
+        }
+    }
+
+    fn parse(line: &str, encoding: EncodingCharacters) -> Self {
+        let fields: Vec<String> = line.split(encoding.field).map(str::to_string).collect();
+        Self {
+            name: fields.first().cloned().unwrap_or_default(),
+            fields,
+            encoding,
+        }
+    }
+
+    fn index(&self, n: usize) -> usize {
+        // MSH-1 is the field separator itself, so MSH fields sit one slot to the left
+        if self.name == "MSH" { n - 1 } else { n }
+    }
+
+    fn raw(&self, n: usize) -> Option<&str> {
+        if n == 0 {
+            return None;
+        }
+        if self.name == "MSH" && n == 1 {
+            return None;
+        }
+        self.fields.get(self.index(n)).map(String::as_str)
+    }
+
+    /// Full field value (all components), unescaped
+    pub fn field(&self, n: usize) -> Option<String> {
+        self.raw(n)
+            .filter(|v| !v.is_empty())
+            .map(|v| self.encoding.unescape(v))
+    }
+
+    /// Component `c` (1-based) of the first repetition of field `n`
+    pub fn component(&self, n: usize, c: usize) -> Option<String> {
+        self.repetitions(n).into_iter().next().and_then(|r| {
+            r.split(self.encoding.component)
+                .nth(c.saturating_sub(1))
+                .filter(|v| !v.is_empty())
+                .map(|v| self.encoding.unescape(v))
+        })
+    }
+
+    /// Raw repetitions of field `n`, components still joined
+    pub fn repetitions(&self, n: usize) -> Vec<String> {
+        if self.name == "MSH" && n == 2 {
+            return self.raw(n).map(|v| vec![v.to_string()]).unwrap_or_default();
+        }
+
+        self.raw(n)
+            .map(|v| {
+                v.split(self.encoding.repetition)
+                    .filter(|r| !r.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Splits a raw repetition into unescaped components
+    pub fn split_components(&self, repetition: &str) -> Vec<String> {
+        repetition
+            .split(self.encoding.component)
+            .map(|c| self.encoding.unescape(c))
+            .collect()
+    }
+
+    fn ensure_len(&mut self, index: usize) {
+        while self.fields.len() <= index {
+            self.fields.push(String::new());
+        }
+    }
+
+    /// Sets field `n` to a single escaped value
+    pub fn set(mut self, n: usize, value: &str) -> Self {
+        let index = self.index(n);
+        self.ensure_len(index);
+        self.fields[index] = self.encoding.escape(value);
+        self
+    }
+
+    pub fn set_opt(self, n: usize, value: Option<&str>) -> Self {
+        match value {
+            Some(v) => self.set(n, v),
+            None => self,
+        }
+    }
+
+    /// Sets field `n` from components, escaping each one
+    pub fn set_components(mut self, n: usize, components: &[&str]) -> Self {
+        let index = self.index(n);
+        self.ensure_len(index);
+        self.fields[index] = components
+            .iter()
+            .map(|c| self.encoding.escape(c))
+            .collect::<Vec<_>>()
+            .join(&self.encoding.component.to_string());
+        self
+    }
+
+    pub fn encode(&self) -> String {
+        let mut fields = self.fields.clone();
+        while fields.len() > 1 && fields.last().map(|f| f.is_empty()).unwrap_or(false) {
+            fields.pop();
+        }
+        fields.join(&self.encoding.field.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckCode {
+    /// Application Accept
+    Accept,
+    /// Application Error
+    Error,
+    /// Application Reject
+    Reject,
+}
+
+impl AckCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AckCode::Accept => "AA",
+            AckCode::Error => "AE",
+            AckCode::Reject => "AR",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        match code {
+            "AA" | "CA" => Some(AckCode::Accept),
+            "AE" | "CE" => Some(AckCode::Error),
+            "AR" | "CR" => Some(AckCode::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// Sending/receiving application and facility used in MSH-3..MSH-6
+#[derive(Debug, Clone)]
+pub struct Hl7Endpoint {
+    pub sending_application: String,
+    pub sending_facility: String,
+    pub receiving_application: String,
+    pub receiving_facility: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hl7Message {
+    pub encoding: EncodingCharacters,
+    pub segments: Vec<Segment>,
+}
+
+impl Hl7Message {
+    /// Starts a v2.5.1 message with a populated MSH segment
+    pub fn new(endpoint: &Hl7Endpoint, message_type: &str, trigger_event: &str, structure: &str) -> Self {
+        let msh = Segment::new("MSH")
+            .set(3, &endpoint.sending_application)
+            .set(4, &endpoint.sending_facility)
+            .set(5, &endpoint.receiving_application)
+            .set(6, &endpoint.receiving_facility)
+            .set(7, &format_timestamp(Utc::now()))
+            .set_components(9, &[message_type, trigger_event, structure])
+            .set(10, &new_control_id())
+            .set(11, "P")
+            .set(12, "2.5.1");
+
+        Self {
+            encoding: EncodingCharacters::default(),
+            segments: vec![msh],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim_start_matches(|c: char| c.is_whitespace());
+        if !text.starts_with("MSH") || text.len() < 8 {
+            return Err(Error::InvalidInput("HL7 message must start with an MSH segment".to_string()));
+        }
+
+        let chars: Vec<char> = text.chars().skip(3).take(5).collect();
+        let encoding = EncodingCharacters {
+            field: chars[0],
+            component: chars[1],
+            repetition: chars[2],
+            escape: chars[3],
+            subcomponent: chars[4],
+        };
+
+        let segments = text
+            .split(['\r', '\n'])
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Segment::parse(line, encoding))
+            .collect();
+
+        Ok(Self { encoding, segments })
+    }
+
+    pub fn push(&mut self, segment: Segment) {
+        self.segments.push(segment);
+    }
+
+    pub fn segment(&self, name: &str) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.name == name)
+    }
+
+    pub fn segments_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Segment> + 'a {
+        self.segments.iter().filter(move |s| s.name == name)
+    }
+
+    pub fn header(&self) -> Result<&Segment> {
+        self.segment("MSH")
+            .ok_or_else(|| Error::InvalidInput("Missing MSH segment".to_string()))
+    }
+
+    /// MSH-9 as `TYPE^EVENT`, e.g. `OML^O21`
+    pub fn message_type(&self) -> Option<String> {
+        let msh = self.segment("MSH")?;
+        let code = msh.component(9, 1)?;
+        Some(match msh.component(9, 2) {
+            Some(event) => format!("{}^{}", code, event),
+            None => code,
+        })
+    }
+
+    pub fn control_id(&self) -> Option<String> {
+        self.segment("MSH").and_then(|msh| msh.field(10))
+    }
+
+    /// For acknowledgments: MSA-1 code and MSA-2 referenced control ID
+    pub fn acknowledgment(&self) -> Option<(AckCode, Option<String>, Option<String>)> {
+        let msa = self.segment("MSA")?;
+        let code = AckCode::parse(&msa.field(1)?)?;
+        Some((code, msa.field(2), msa.field(3)))
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = self
+            .segments
+            .iter()
+            .map(Segment::encode)
+            .collect::<Vec<_>>()
+            .join("\r");
+        text.push('\r');
+        text
+    }
+
+    /// Builds the ACK for this message, swapping sender and receiver
+    pub fn build_ack(&self, code: AckCode, text: Option<&str>, error: Option<&str>) -> Hl7Message {
+        let original = self.segment("MSH");
+        let get = |n: usize| original.and_then(|m| m.field(n)).unwrap_or_default();
+
+        let endpoint = Hl7Endpoint {
+            sending_application: get(5),
+            sending_facility: get(6),
+            receiving_application: get(3),
+            receiving_facility: get(4),
+        };
+        let trigger = original.and_then(|m| m.component(9, 2)).unwrap_or_default();
+
+        let mut ack = Hl7Message::new(&endpoint, "ACK", &trigger, "ACK");
+        ack.push(
+            Segment::new("MSA")
+                .set(1, code.as_str())
+                .set(2, &self.control_id().unwrap_or_default())
+                .set_opt(3, text),
+        );
+
+        if let Some(error) = error {
+            let severity = if code == AckCode::Accept { "W" } else { "E" };
+            ack.push(
+                Segment::new("ERR")
+                    .set_components(3, &["207", "Application internal error", "HL70357"])
+                    .set(4, severity)
+                    .set(8, error),
+            );
+        }
+
+        ack
+    }
+}
+
+pub fn new_control_id() -> String {
+    Uuid::new_v4().simple().to_string()[..20].to_uppercase()
+}
+
+pub fn format_timestamp(dt: DateTime<Utc>) -> String {
+    dt.format("%Y%m%d%H%M%S").to_string()
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Parses an HL7 DTM (`YYYY[MM[DD[HH[MM[SS]]]]][+/-ZZZZ]`), treating values
+/// without an offset as UTC.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let digits: String = value
+        .split(['+', '-'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let padded = match digits.len() {
+        8 => format!("{}000000", digits),
+        10 => format!("{}0000", digits),
+        12 => format!("{}00", digits),
+        14 => digits,
+        _ => return None,
+    };
+
+    NaiveDateTime::parse_from_str(&padded, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|dt| Utc.from_utc_datetime(&dt))
+}
+
+/// Logs the outcome of an outbound exchange and returns the parsed ACK code
+pub fn interpret_ack(raw_ack: &str) -> Result<(AckCode, Option<String>)> {
+    let ack = Hl7Message::parse(raw_ack)?;
+    let (code, control_id, text) = ack
+        .acknowledgment()
+        .ok_or_else(|| Error::InvalidInput("Acknowledgment without MSA segment".to_string()))?;
+
+    if code == AckCode::Accept {
+        info!("HL7 message {} accepted", control_id.unwrap_or_default());
+    } else {
+        warn!(
+            "HL7 message {} rejected ({}): {}",
+            control_id.unwrap_or_default(),
+            code.as_str(),
+            text.clone().unwrap_or_default()
+        );
+    }
+
+    Ok((code, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OML: &str = "MSH|^~\\&|HIS|CITYHOSP|LIS|LAB|20250105101500||OML^O21^OML_O21|MSG00001|P|2.5.1\r\
+PID|1||MRN0001^^^CITYHOSP^MR~998877^^^NATID^NI||Doe^Jane||19800115|F\r\
+ORC|NW|PLC123||||||||||1234^Smith^John\r\
+OBR|1|PLC123||GLU^Glucose^L|||20250105100000\r\
+OBR|2|PLC123||CREA^Creatinine^L\r";
+
+    #[test]
+    fn test_parse_oml_fields() {
+        let message = Hl7Message::parse(OML).unwrap();
+
+        assert_eq!(message.message_type().as_deref(), Some("OML^O21"));
+        assert_eq!(message.control_id().as_deref(), Some("MSG00001"));
+
+        let pid = message.segment("PID").unwrap();
+        let identifiers = pid.repetitions(3);
+        assert_eq!(identifiers.len(), 2);
+        assert_eq!(pid.split_components(&identifiers[0])[4], "MR");
+        assert_eq!(pid.component(5, 1).as_deref(), Some("Doe"));
+
+        let tests: Vec<String> = message
+            .segments_named("OBR")
+            .filter_map(|obr| obr.component(4, 1))
+            .collect();
+        assert_eq!(tests, vec!["GLU".to_string(), "CREA".to_string()]);
+    }
+
+    #[test]
+    fn test_build_ack_references_original() {
+        let message = Hl7Message::parse(OML).unwrap();
+        let ack = message.build_ack(AckCode::Error, Some("Unknown test"), Some("Test XYZ not found"));
+        let reparsed = Hl7Message::parse(&ack.encode()).unwrap();
+
+        let msh = reparsed.header().unwrap();
+        assert_eq!(msh.field(3).as_deref(), Some("LIS"));
+        assert_eq!(msh.field(5).as_deref(), Some("HIS"));
+
+        let (code, control_id, text) = reparsed.acknowledgment().unwrap();
+        assert_eq!(code, AckCode::Error);
+        assert_eq!(control_id.as_deref(), Some("MSG00001"));
+        assert_eq!(text.as_deref(), Some("Unknown test"));
+        assert_eq!(reparsed.segment("ERR").unwrap().field(8).as_deref(), Some("Test XYZ not found"));
+    }
+
+    #[test]
+    fn test_escaping_round_trip() {
+        let segment = Segment::new("NTE").set(3, "Hb < 7 | repeat^confirm ~ & \\ done");
+        let message = Hl7Message::parse(&format!("MSH|^~\\&|A\r{}", segment.encode())).unwrap();
+
+        assert_eq!(
+            message.segment("NTE").unwrap().field(3).as_deref(),
+            Some("Hb < 7 | repeat^confirm ~ & \\ done")
+        );
+    }
+
+    #[test]
+    fn test_mllp_decoder_handles_split_blocks() {
+        let block = mllp_encode("MSH|^~\\&|A\r");
+        let mut decoder = MllpDecoder::new();
+
+        assert!(decoder.feed(&block[..5]).is_empty());
+        let messages = decoder.feed(&block[5..]);
+        assert_eq!(messages, vec!["MSH|^~\\&|A\r".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_timestamp_precision() {
+        let ts = parse_timestamp("202501051015").unwrap();
+        assert_eq!(format_timestamp(ts), "20250105101500");
+        assert!(parse_timestamp("2025").is_none());
+    }
+}
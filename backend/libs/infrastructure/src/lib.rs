@@ -1,8 +1,17 @@
 pub mod database;
 pub mod event_bus;
+pub mod consumer;
+pub mod memory_bus;
+pub mod durable_bus;
+pub mod transport;
 pub mod cache;
 pub mod external;
+pub mod hl7;
+pub mod outbox;
 
 pub use database::DatabasePool;
 pub use event_bus::{EventBus, EventPublisher, EventConsumer};
 pub use cache::CacheClient;
+pub use outbox::OutboxRelay;
+pub use consumer::{ConsumerRuntime, EventRouter, EventSource};
+pub use transport::{EventBackend, EventBusConfig, EventTransport};
@@ -0,0 +1,156 @@
+//! In-process event bus for local runs and tests.
+//!
+//! Events go through a `tokio::sync::broadcast` channel, so every
+//! [`InMemorySubscription`] sees every event published after it subscribed,
+//! like one consumer group per subscription. Nothing is persisted and
+//! committing is a no-op; a subscriber that falls more than the channel
+//! capacity behind skips the oldest events.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use common::error::{Error, Result};
+
+use crate::consumer::{ConsumedMessage, EventSource};
+use crate::event_bus::{DomainEvent, EventPublisher};
+
+/// Events buffered per subscriber before the oldest are dropped
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+pub struct InMemoryEventBus {
+    sender: broadcast::Sender<ConsumedMessage>,
+    /// Next offset per topic
+    offsets: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl InMemoryEventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            offsets: Arc::default(),
+        }
+    }
+
+    /// Receive the events published to `topics` from now on
+    pub fn subscribe(&self, topics: &[&str]) -> InMemorySubscription {
+        InMemorySubscription {
+            receiver: tokio::sync::Mutex::new(self.sender.subscribe()),
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+        }
+    }
+
+    fn next_offset(&self, topic: &str) -> i64 {
+        let mut offsets = self.offsets.lock().unwrap_or_else(|e| e.into_inner());
+        let offset = offsets.entry(topic.to_string()).or_insert(0);
+        *offset += 1;
+        *offset - 1
+    }
+}
+
+impl Default for InMemoryEventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl EventPublisher for InMemoryEventBus {
+    async fn publish(&self, topic: &str, event: &DomainEvent) -> Result<()> {
+        let message = ConsumedMessage {
+            topic: topic.to_string(),
+            partition: 0,
+            offset: self.next_offset(topic),
+            key: Some(event.aggregate_id.clone()),
+            payload: serde_json::to_vec(event).map_err(Error::Serialization)?,
+        };
+
+        // Without subscribers the event is simply dropped, as Kafka would
+        // keep it for nobody
+        if self.sender.send(message).is_err() {
+            debug!("No subscribers for {} event on {}", event.event_type, topic);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct InMemorySubscription {
+    receiver: tokio::sync::Mutex<broadcast::Receiver<ConsumedMessage>>,
+    topics: Vec<String>,
+}
+
+impl EventSource for InMemorySubscription {
+    async fn recv(&self) -> Result<ConsumedMessage> {
+        let mut receiver = self.receiver.lock().await;
+        loop {
+            match receiver.recv().await {
+                Ok(message) if self.topics.contains(&message.topic) => return Ok(message),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("In-memory subscriber lagged, {} event(s) skipped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(Error::InvalidState("In-memory event bus closed".to_string()));
+                }
+            }
+        }
+    }
+
+    async fn commit(&self, _message: &ConsumedMessage) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::{events, topics};
+    use uuid::Uuid;
+
+    fn event(event_type: &str) -> DomainEvent {
+        DomainEvent::new(
+            event_type.to_string(),
+            Uuid::new_v4().to_string(),
+            "TestOrder".to_string(),
+            serde_json::json!({}),
+            Uuid::new_v4().to_string(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_their_topics_in_order() {
+        let bus = InMemoryEventBus::default();
+        let orders = bus.subscribe(&[topics::ORDER_EVENTS]);
+        let everything = bus.subscribe(&[topics::ORDER_EVENTS, topics::RESULT_EVENTS]);
+
+        bus.publish(topics::RESULT_EVENTS, &event(events::RESULT_ENTERED)).await.unwrap();
+        bus.publish(topics::ORDER_EVENTS, &event(events::ORDER_CREATED)).await.unwrap();
+        bus.publish(topics::ORDER_EVENTS, &event(events::ORDER_CONFIRMED)).await.unwrap();
+
+        let first = orders.recv().await.unwrap();
+        let second = orders.recv().await.unwrap();
+        assert_eq!(first.decode().unwrap().event_type, events::ORDER_CREATED);
+        assert_eq!((first.offset, second.offset), (0, 1));
+        assert_eq!(second.decode().unwrap().event_type, events::ORDER_CONFIRMED);
+
+        assert_eq!(everything.recv().await.unwrap().topic, topics::RESULT_EVENTS);
+        assert_eq!(everything.recv().await.unwrap().topic, topics::ORDER_EVENTS);
+    }
+
+    #[tokio::test]
+    async fn events_published_before_subscribing_are_not_seen() {
+        let bus = InMemoryEventBus::default();
+        bus.publish(topics::ORDER_EVENTS, &event(events::ORDER_CREATED)).await.unwrap();
+
+        let orders = bus.subscribe(&[topics::ORDER_EVENTS]);
+        bus.publish(topics::ORDER_EVENTS, &event(events::ORDER_CANCELLED)).await.unwrap();
+
+        let message = orders.recv().await.unwrap();
+        assert_eq!(message.decode().unwrap().event_type, events::ORDER_CANCELLED);
+    }
+}
@@ -0,0 +1,375 @@
+//! Transactional outbox for domain events.
+//!
+//! Repositories call [`enqueue`] inside the transaction that performs their
+//! write, so an event is stored if and only if the change it describes is
+//! committed. [`OutboxRelay`] drains the `event_outbox` table in the
+//! background: it publishes pending events, retries failures with exponential
+//! backoff and records delivery. Events of one aggregate are published in the
+//! order they were written; an event waits while an earlier one for the same
+//! aggregate is still pending.
+//!
+//! Every service that enqueues events runs its migrations through
+//! [`migrator`], which adds the `event_outbox` table. The relay works across
+//! organizations, so it runs as [`Tenant::System`].
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgExecutor, PgPool};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use common::error::{Error, Result};
+use common::tenant::Tenant;
+
+use crate::database;
+use crate::event_bus::{DomainEvent, EventPublisher};
+
+pub const STATUS_PENDING: &str = "PENDING";
+pub const STATUS_PUBLISHED: &str = "PUBLISHED";
+pub const STATUS_FAILED: &str = "FAILED";
+
+/// Version of the `event_outbox` migration, before the tenant isolation
+/// migrations that enable row-level security on it
+const MIGRATION_VERSION: i64 = 20250111000001;
+
+/// A service's migrations plus the one creating `event_outbox`
+pub fn migrator(service: Migrator) -> Migrator {
+    database::with_migration(service, MIGRATION_VERSION, "event outbox", include_str!("../sql/event_outbox.sql"))
+}
+
+/// Store `event` for publication to `topic` as part of the caller's transaction
+pub async fn enqueue<'e, E>(executor: E, topic: &str, event: &DomainEvent) -> Result<()>
+where
+    E: PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO event_outbox (
+            id, topic, event_type, aggregate_type, aggregate_id, organization_id, event
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(event.event_id)
+    .bind(topic)
+    .bind(&event.event_type)
+    .bind(&event.aggregate_type)
+    .bind(&event.aggregate_id)
+    .bind(&event.metadata.organization_id)
+    .bind(Json(event))
+    .execute(executor)
+    .await
+    .map_err(Error::Database)?;
+
+    Ok(())
+}
+
+/// Builds the event for an aggregate and enqueues it in one call
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_event<'e, E, T>(
+    executor: E,
+    topic: &str,
+    event_type: &str,
+    aggregate_type: &str,
+    aggregate_id: Uuid,
+    organization_id: Uuid,
+    user_id: Option<Uuid>,
+    payload: &T,
+) -> Result<()>
+where
+    E: PgExecutor<'e>,
+    T: Serialize,
+{
+    let event = DomainEvent::new(
+        event_type.to_string(),
+        aggregate_id.to_string(),
+        aggregate_type.to_string(),
+        serde_json::to_value(payload).map_err(Error::Serialization)?,
+        organization_id.to_string(),
+        user_id.map(|id| id.to_string()),
+    );
+
+    enqueue(executor, topic, &event).await
+}
+
+/// Put events that exhausted their attempts back in the queue
+pub async fn retry_failed(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE event_outbox
+        SET status = 'PENDING', attempts = 0, next_attempt_at = NOW()
+        WHERE status = 'FAILED'
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(Error::Database)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete published events older than `retention`. Events carry the data of
+/// the change they describe, so they are not kept longer than needed for
+/// troubleshooting.
+pub async fn purge_published(pool: &PgPool, retention: Duration) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM event_outbox
+        WHERE status = 'PUBLISHED'
+          AND published_at < NOW() - make_interval(secs => $1)
+        "#,
+    )
+    .bind(retention.as_secs_f64())
+    .execute(pool)
+    .await
+    .map_err(Error::Database)?;
+
+    Ok(result.rows_affected())
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// Events claimed per pass
+    pub batch_size: i64,
+    /// Pause between passes that found nothing to publish
+    pub poll_interval: Duration,
+    /// Attempts before an event is marked FAILED
+    pub max_attempts: i32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How long published events are kept
+    pub published_retention: Duration,
+    /// Pause between purges of published events
+    pub purge_interval: Duration,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            poll_interval: Duration::from_secs(1),
+            max_attempts: 10,
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(300),
+            published_retention: Duration::from_secs(7 * 24 * 3600),
+            purge_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl RelayConfig {
+    /// Delay before the next attempt after `attempts` failed ones
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+/// Outcome of one relay pass
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RelayStats {
+    pub published: usize,
+    pub retried: usize,
+    pub failed: usize,
+}
+
+#[derive(FromRow)]
+struct OutboxRow {
+    id: Uuid,
+    topic: String,
+    attempts: i32,
+    event: Json<DomainEvent>,
+}
+
+/// Background publisher for the `event_outbox` table
+pub struct OutboxRelay<P> {
+    pool: PgPool,
+    publisher: P,
+    config: RelayConfig,
+}
+
+impl<P: EventPublisher> OutboxRelay<P> {
+    pub fn new(pool: PgPool, publisher: P) -> Self {
+        Self {
+            pool,
+            publisher,
+            config: RelayConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: RelayConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Claim a batch of due events, publish them and record the outcome.
+    ///
+    /// Rows are locked with `SKIP LOCKED`, so several relay instances can
+    /// drain the same table.
+    pub async fn relay_once(&self) -> Result<RelayStats> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let rows = sqlx::query_as::<_, OutboxRow>(
+            r#"
+            SELECT o.id, o.topic, o.attempts, o.event
+            FROM event_outbox o
+            WHERE o.status = 'PENDING'
+              AND o.next_attempt_at <= NOW()
+              AND NOT EXISTS (
+                SELECT 1 FROM event_outbox earlier
+                WHERE earlier.aggregate_id = o.aggregate_id
+                  AND earlier.status = 'PENDING'
+                  AND earlier.seq < o.seq
+              )
+            ORDER BY o.seq
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(self.config.batch_size)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let mut stats = RelayStats::default();
+
+        for row in rows {
+            let attempts = row.attempts + 1;
+
+            match self.publisher.publish(&row.topic, &row.event.0).await {
+                Ok(()) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE event_outbox
+                        SET status = 'PUBLISHED', attempts = $2, published_at = NOW(), last_error = NULL
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(row.id)
+                    .bind(attempts)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(Error::Database)?;
+
+                    stats.published += 1;
+                }
+                Err(e) => {
+                    let exhausted = attempts >= self.config.max_attempts;
+                    let status = if exhausted { STATUS_FAILED } else { STATUS_PENDING };
+                    let next_attempt_at = Utc::now()
+                        + chrono::Duration::from_std(self.config.backoff(attempts)).unwrap_or_default();
+
+                    sqlx::query(
+                        r#"
+                        UPDATE event_outbox
+                        SET status = $2, attempts = $3, next_attempt_at = $4, last_error = $5
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(row.id)
+                    .bind(status)
+                    .bind(attempts)
+                    .bind(next_attempt_at)
+                    .bind(e.to_string())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(Error::Database)?;
+
+                    if exhausted {
+                        error!(
+                            "Giving up on event {} ({}) after {} attempts: {}",
+                            row.id, row.event.0.event_type, attempts, e
+                        );
+                        stats.failed += 1;
+                    } else {
+                        warn!(
+                            "Publishing event {} ({}) failed, attempt {}: {}",
+                            row.id, row.event.0.event_type, attempts, e
+                        );
+                        stats.retried += 1;
+                    }
+                }
+            }
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(stats)
+    }
+
+    /// Drain the outbox until the task is dropped, purging published events
+    /// past their retention along the way
+    pub async fn run(self) {
+        info!("Outbox relay started");
+        let mut last_purge: Option<Instant> = None;
+
+        loop {
+            if last_purge.is_none_or(|at| at.elapsed() >= self.config.purge_interval) {
+                last_purge = Some(Instant::now());
+                match Tenant::System.scope(purge_published(&self.pool, self.config.published_retention)).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} published events", purged),
+                    Err(e) => error!("Purging published events failed: {}", e),
+                }
+            }
+
+            match Tenant::System.scope(self.relay_once()).await {
+                // Keep draining while there is work
+                Ok(stats) if stats.published > 0 => continue,
+                Ok(_) => {}
+                Err(e) => error!("Outbox relay pass failed: {}", e),
+            }
+
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+}
+
+impl<P: EventPublisher + 'static> OutboxRelay<P> {
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let config = RelayConfig {
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(60),
+            ..RelayConfig::default()
+        };
+
+        assert_eq!(config.backoff(1), Duration::from_secs(2));
+        assert_eq!(config.backoff(2), Duration::from_secs(4));
+        assert_eq!(config.backoff(5), Duration::from_secs(32));
+        assert_eq!(config.backoff(6), Duration::from_secs(60));
+        assert_eq!(config.backoff(1000), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_outbox_stores_the_whole_event() {
+        let event = DomainEvent::new(
+            crate::event_bus::events::ORDER_CREATED.to_string(),
+            Uuid::new_v4().to_string(),
+            "TestOrder".to_string(),
+            serde_json::json!({ "orderNumber": "ORD-1" }),
+            Uuid::new_v4().to_string(),
+            None,
+        );
+
+        let stored = serde_json::to_value(Json(&event)).unwrap();
+        let restored: DomainEvent = serde_json::from_value(stored).unwrap();
+        assert_eq!(restored.event_id, event.event_id);
+        assert_eq!(restored.payload["orderNumber"], "ORD-1");
+    }
+}
@@ -0,0 +1,162 @@
+//! Event transport selected by configuration.
+//!
+//! Services publish through [`EventTransport`] and subscribe with
+//! [`EventTransport::subscribe`], so the same relay and consumer code runs
+//! over Kafka in production, the Postgres event log on a laptop and the
+//! in-memory bus in tests.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use common::error::{Error, Result};
+
+use crate::consumer::{ConsumedMessage, EventSource};
+use crate::durable_bus::{DurableEventBus, DurableSubscription};
+use crate::event_bus::{DomainEvent, EventBus, EventConsumer, EventPublisher};
+use crate::memory_bus::{InMemoryEventBus, InMemorySubscription};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventBackend {
+    #[default]
+    Kafka,
+    /// Postgres event log, see [`crate::durable_bus`]
+    Postgres,
+    /// In-process only, see [`crate::memory_bus`]
+    Memory,
+}
+
+impl FromStr for EventBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "kafka" => Ok(Self::Kafka),
+            "postgres" => Ok(Self::Postgres),
+            "memory" => Ok(Self::Memory),
+            other => Err(Error::Configuration(format!("Unknown event backend: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventBusConfig {
+    pub backend: EventBackend,
+    pub kafka_brokers: String,
+    /// Shared by every service when the backend is `postgres`
+    pub database_url: Option<String>,
+}
+
+impl EventBusConfig {
+    pub fn kafka(brokers: &str) -> Self {
+        Self {
+            backend: EventBackend::Kafka,
+            kafka_brokers: brokers.to_string(),
+            database_url: None,
+        }
+    }
+
+    /// Kafka on `kafka_brokers` unless `EVENT_BACKEND` says otherwise; the
+    /// Postgres backend reads `EVENT_DATABASE_URL`
+    pub fn from_env(kafka_brokers: &str) -> Result<Self> {
+        let backend = match std::env::var("EVENT_BACKEND") {
+            Ok(backend) => backend.parse()?,
+            Err(_) => EventBackend::default(),
+        };
+
+        Ok(Self {
+            backend,
+            kafka_brokers: kafka_brokers.to_string(),
+            database_url: std::env::var("EVENT_DATABASE_URL").ok(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub enum EventTransport {
+    Kafka(EventBus),
+    Postgres(DurableEventBus),
+    Memory(InMemoryEventBus),
+}
+
+impl EventTransport {
+    pub async fn connect(config: &EventBusConfig) -> Result<Self> {
+        match config.backend {
+            EventBackend::Kafka => Ok(Self::Kafka(EventBus::new(&config.kafka_brokers)?)),
+            EventBackend::Postgres => {
+                let database_url = config.database_url.as_deref().ok_or_else(|| {
+                    Error::Configuration("EVENT_DATABASE_URL is required for the postgres event backend".to_string())
+                })?;
+                Ok(Self::Postgres(DurableEventBus::connect(database_url).await?))
+            }
+            EventBackend::Memory => Ok(Self::Memory(InMemoryEventBus::default())),
+        }
+    }
+
+    /// Join consumer group `group_id` on `topics`
+    pub fn subscribe(&self, group_id: &str, topics: &[&str]) -> Result<Subscription> {
+        match self {
+            Self::Kafka(bus) => Ok(Subscription::Kafka(EventConsumer::new(bus.brokers(), group_id, topics)?)),
+            Self::Postgres(bus) => Ok(Subscription::Postgres(bus.subscribe(group_id, topics))),
+            Self::Memory(bus) => Ok(Subscription::Memory(bus.subscribe(topics))),
+        }
+    }
+}
+
+impl EventPublisher for EventTransport {
+    async fn publish(&self, topic: &str, event: &DomainEvent) -> Result<()> {
+        match self {
+            Self::Kafka(bus) => bus.publish(topic, event).await,
+            Self::Postgres(bus) => bus.publish(topic, event).await,
+            Self::Memory(bus) => bus.publish(topic, event).await,
+        }
+    }
+}
+
+pub enum Subscription {
+    Kafka(EventConsumer),
+    Postgres(DurableSubscription),
+    Memory(InMemorySubscription),
+}
+
+impl EventSource for Subscription {
+    async fn recv(&self) -> Result<ConsumedMessage> {
+        match self {
+            Self::Kafka(source) => source.recv().await,
+            Self::Postgres(source) => source.recv().await,
+            Self::Memory(source) => source.recv().await,
+        }
+    }
+
+    async fn commit(&self, message: &ConsumedMessage) -> Result<()> {
+        match self {
+            Self::Kafka(source) => source.commit(message).await,
+            Self::Postgres(source) => source.commit(message).await,
+            Self::Memory(source) => source.commit(message).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_names_parse() {
+        assert_eq!("kafka".parse::<EventBackend>().unwrap(), EventBackend::Kafka);
+        assert_eq!("Postgres".parse::<EventBackend>().unwrap(), EventBackend::Postgres);
+        assert_eq!("memory".parse::<EventBackend>().unwrap(), EventBackend::Memory);
+        assert!("sqs".parse::<EventBackend>().is_err());
+    }
+
+    #[tokio::test]
+    async fn postgres_backend_needs_a_database_url() {
+        let config = EventBusConfig {
+            backend: EventBackend::Postgres,
+            kafka_brokers: String::new(),
+            database_url: None,
+        };
+        assert!(matches!(EventTransport::connect(&config).await, Err(Error::Configuration(_))));
+    }
+}
@@ -0,0 +1,361 @@
+//! Row-level security of every service schema. Each test migrates a scratch
+//! database, seeds one row per isolated table for organization A and checks
+//! that an ordinary role acting for organization B can neither see nor
+//! change it.
+//!
+//! Needs a superuser connection in TEST_DATABASE_URL; the checks themselves
+//! run as `lis_rls_app`, since superusers ignore row-level security.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+const APP_ROLE: &str = "lis_rls_app";
+
+/// Tables every tenant shares
+const SHARED_TABLES: &[&str] = &["_sqlx_migrations", "processed_event"];
+
+/// Services that publish domain events through the outbox
+const OUTBOX_SERVICES: &[&str] = &[
+    "billing-service",
+    "order-service",
+    "patient-service",
+    "report-service",
+    "result-service",
+    "sample-service",
+];
+
+/// Nullable columns a multi-column CHECK needs set
+const OPTIONAL_VALUES: &[(&str, &str, &str)] = &[("test_order_item", "test_id", "gen_random_uuid()")];
+
+struct Column {
+    name: String,
+    sql_type: String,
+    type_kind: String,
+    category: String,
+    check: Option<String>,
+}
+
+/// A table isolated through its own tenant column, or through its parent
+enum Isolation {
+    Tenant { column: String },
+    Parent { column: String, parent: String },
+}
+
+async fn test_database(service: &str) -> (PgPool, PgPool, String) {
+    let url = std::env::var("TEST_DATABASE_URL")
+        .unwrap_or_else(|_| "postgres://postgres@localhost:5432/postgres".to_string());
+    let admin = PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap();
+
+    let name = format!("lis_rls_{}_{}", service.replace('-', "_"), Uuid::new_v4().simple());
+    sqlx::query(&format!("CREATE DATABASE {}", name)).execute(&admin).await.unwrap();
+
+    let options = PgConnectOptions::from_str(&url).unwrap().database(&name);
+    let pool = PgPoolOptions::new().max_connections(2).connect_with(options).await.unwrap();
+
+    let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../services").join(service).join("migrations");
+    let mut migrator = sqlx::migrate::Migrator::new(migrations).await.unwrap();
+    if OUTBOX_SERVICES.contains(&service) {
+        migrator = infrastructure::outbox::migrator(migrator);
+    }
+    infrastructure::database::migrator(migrator).run(&pool).await.unwrap();
+
+    // Roles are cluster-wide, so parallel tests may race to create it
+    if let Err(e) = sqlx::query(&format!("CREATE ROLE {} NOLOGIN", APP_ROLE)).execute(&pool).await {
+        let code = e.as_database_error().and_then(|e| e.code()).unwrap_or_default().to_string();
+        assert!(code == "42710" || code == "23505", "Failed to create role: {}", e);
+    }
+    sqlx::query(&format!("GRANT USAGE ON SCHEMA public TO {}", APP_ROLE)).execute(&pool).await.unwrap();
+    sqlx::query(&format!("GRANT ALL ON ALL TABLES IN SCHEMA public TO {}", APP_ROLE)).execute(&pool).await.unwrap();
+    sqlx::query(&format!("GRANT ALL ON ALL SEQUENCES IN SCHEMA public TO {}", APP_ROLE)).execute(&pool).await.unwrap();
+
+    (admin, pool, name)
+}
+
+async fn drop_database(admin: PgPool, pool: PgPool, name: String) {
+    pool.close().await;
+    sqlx::query(&format!("DROP DATABASE {} WITH (FORCE)", name)).execute(&admin).await.unwrap();
+}
+
+/// Isolated tables by name, read back from the policies the migrations created
+async fn isolated_tables(pool: &PgPool) -> HashMap<String, Isolation> {
+    let rows = sqlx::query(
+        "SELECT c.relname::text AS name, c.relrowsecurity AS enabled, c.relforcerowsecurity AS forced,
+                p.qual AS qual
+         FROM pg_class c
+         LEFT JOIN pg_policies p ON p.tablename = c.relname AND p.policyname = 'tenant_isolation'
+         WHERE c.relnamespace = 'public'::regnamespace AND c.relkind = 'r'",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    let mut tables = HashMap::new();
+    for row in rows {
+        let name: String = row.get("name");
+        let qual: Option<String> = row.get("qual");
+        let Some(qual) = qual else { continue };
+
+        assert!(row.get::<bool, _>("enabled") && row.get::<bool, _>("forced"), "{} policy is not enforced", name);
+
+        let isolation = match parent_of(&qual) {
+            Some((parent, column)) => Isolation::Parent { column, parent },
+            None => {
+                let column = qual
+                    .split(" OR ")
+                    .nth(1)
+                    .map(|clause| {
+                        clause
+                            .trim_start_matches('(')
+                            .chars()
+                            .take_while(|c| c.is_alphanumeric() || *c == '_')
+                            .collect::<String>()
+                    })
+                    .unwrap_or_else(|| panic!("Unexpected policy on {}: {}", name, qual));
+                Isolation::Tenant { column }
+            }
+        };
+        tables.insert(name, isolation);
+    }
+    tables
+}
+
+/// Parent table and column of an enable_parent_isolation() policy
+fn parent_of(qual: &str) -> Option<(String, String)> {
+    let rest = qual.split("FROM ").nth(1)?;
+    let parent = rest.split_whitespace().next()?.to_string();
+    let column = rest.split("(p.id = ").nth(1)?.split(')').next()?.rsplit('.').next()?.to_string();
+    Some((parent, column))
+}
+
+async fn columns(pool: &PgPool, table: &str) -> Vec<Column> {
+    let rows = sqlx::query(
+        "SELECT a.attname::text AS name, format_type(a.atttypid, a.atttypmod) AS sql_type,
+                t.typtype::text AS type_kind, t.typcategory::text AS category,
+                (SELECT pg_get_constraintdef(k.oid) FROM pg_constraint k
+                 WHERE k.conrelid = a.attrelid AND k.contype = 'c' AND k.conkey = ARRAY[a.attnum]
+                 LIMIT 1) AS check_def
+         FROM pg_attribute a
+         JOIN pg_type t ON t.oid = a.atttypid
+         WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped
+           AND a.attnotnull AND NOT a.atthasdef AND a.attidentity = '' AND a.attgenerated = ''
+         ORDER BY a.attnum",
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    rows.into_iter()
+        .map(|row| Column {
+            name: row.get("name"),
+            sql_type: row.get("sql_type"),
+            type_kind: row.get("type_kind"),
+            category: row.get("category"),
+            check: row.get("check_def"),
+        })
+        .collect()
+}
+
+/// A value for a required column that satisfies simple CHECK constraints
+fn value(column: &Column) -> String {
+    // The first literal of an IN list or equality, but not of a pattern
+    let literal = column
+        .check
+        .as_deref()
+        .filter(|check| !check.contains('~'))
+        .and_then(|check| check.split('\'').nth(1));
+    match (literal, column.type_kind.as_str(), column.category.as_str()) {
+        (Some(literal), _, "S") => format!("'{}'::{}", literal, column.sql_type),
+        (_, _, "S") if column.name.contains("email") => "md5(random()::text) || '@example.com'".to_string(),
+        (_, "e", _) => format!("(enum_range(NULL::{}))[1]", column.sql_type),
+        (_, _, "A") => format!("'{{}}'::{}", column.sql_type),
+        (_, _, "S") => format!("md5(random()::text)::{}", column.sql_type),
+        (_, _, "N") => format!("1::{}", column.sql_type),
+        (_, _, "B") => "false".to_string(),
+        (_, _, "D") => format!("now()::{}", column.sql_type),
+        (_, _, "T") => "interval '1 hour'".to_string(),
+        (_, _, "I") => "'127.0.0.1'::inet".to_string(),
+        _ if column.sql_type == "uuid" => "gen_random_uuid()".to_string(),
+        _ if column.sql_type.starts_with("json") => format!("'{{}}'::{}", column.sql_type),
+        _ if column.sql_type == "bytea" => "'\\x00'::bytea".to_string(),
+        _ => panic!("No sample value for {} {}", column.name, column.sql_type),
+    }
+}
+
+/// INSERT of one row with the given columns fixed, returning its id
+async fn insert_sql(pool: &PgPool, table: &str, fixed: &[(&str, String)]) -> String {
+    let mut names: Vec<String> = fixed.iter().map(|(name, _)| name.to_string()).collect();
+    let mut values: Vec<String> = fixed.iter().map(|(_, value)| value.clone()).collect();
+    for (_, name, value) in OPTIONAL_VALUES.iter().filter(|(t, _, _)| *t == table) {
+        names.push(name.to_string());
+        values.push(value.to_string());
+    }
+    for column in columns(pool, table).await {
+        if !names.contains(&column.name) {
+            values.push(value(&column));
+            names.push(column.name);
+        }
+    }
+
+    let has_id: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_attribute WHERE attrelid = $1::regclass AND attname = 'id')",
+    )
+    .bind(table)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+        table,
+        names.join(", "),
+        values.join(", "),
+        if has_id { "id::text" } else { "''" }
+    )
+}
+
+async fn column_type(pool: &PgPool, table: &str, column: &str) -> String {
+    sqlx::query_scalar(
+        "SELECT format_type(atttypid, atttypmod) FROM pg_attribute WHERE attrelid = $1::regclass AND attname = $2",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn act_as(conn: &mut PgConnection, organization_id: Option<Uuid>) {
+    let organization_id = organization_id.map(|id| id.to_string()).unwrap_or_default();
+    sqlx::query("SELECT set_config('app.organization_id', $1, false), set_config('app.bypass_rls', 'off', false)")
+        .bind(organization_id)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+}
+
+async fn count(conn: &mut PgConnection, table: &str, filter: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT count(*) FROM {} WHERE {}", table, filter))
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap()
+}
+
+/// Seeds a row of `table` for organization A and checks what A, B and a
+/// connection without a tenant can do with it
+async fn check_table(pool: &PgPool, table: &str, isolation: &Isolation, tables: &HashMap<String, Isolation>) {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+
+    // Seed as the superuser, without foreign key triggers
+    let mut seed = pool.acquire().await.unwrap();
+    sqlx::query("SET session_replication_role = replica").execute(&mut *seed).await.unwrap();
+    let fixed = match isolation {
+        Isolation::Tenant { column } => {
+            let sql_type = column_type(pool, table, column).await;
+            vec![(column.as_str(), format!("'{}'::{}", tenant_a, sql_type))]
+        }
+        Isolation::Parent { column, parent } => {
+            let Some(Isolation::Tenant { column: parent_tenant }) = tables.get(parent) else {
+                // Grandchildren are covered by the catalog check alone
+                return;
+            };
+            let sql_type = column_type(pool, parent, parent_tenant).await;
+            let parent_insert =
+                insert_sql(pool, parent, &[(parent_tenant.as_str(), format!("'{}'::{}", tenant_a, sql_type))]).await;
+            let parent_id: String = sqlx::query_scalar(&parent_insert).fetch_one(&mut *seed).await.unwrap();
+            let sql_type = column_type(pool, table, column).await;
+            vec![(column.as_str(), format!("'{}'::{}", parent_id, sql_type))]
+        }
+    };
+    let insert = insert_sql(pool, table, &fixed).await;
+    sqlx::query(&insert)
+        .fetch_one(&mut *seed)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to seed {}: {}", table, e));
+    // Shared reference rows stay readable, so count the seeded row only
+    let seeded = format!("{} = {}", fixed[0].0, fixed[0].1);
+
+    sqlx::query(&format!("SET ROLE {}", APP_ROLE)).execute(&mut *seed).await.unwrap();
+    let conn = &mut *seed;
+
+    act_as(conn, Some(tenant_b)).await;
+    assert_eq!(count(conn, table, &seeded).await, 0, "{} leaks rows to another organization", table);
+    let first_column = &fixed[0].0;
+    let updated = sqlx::query(&format!("UPDATE {} SET {} = {}", table, first_column, first_column))
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    assert_eq!(updated.rows_affected(), 0, "{} rows updated by another organization", table);
+    let deleted = sqlx::query(&format!("DELETE FROM {}", table)).execute(&mut *conn).await.unwrap();
+    assert_eq!(deleted.rows_affected(), 0, "{} rows deleted by another organization", table);
+
+    let err = sqlx::query(&insert).execute(&mut *conn).await.expect_err(table);
+    let code = err.as_database_error().and_then(|e| e.code()).unwrap_or_default().to_string();
+    assert_eq!(code, "42501", "{} accepted a row for another organization: {}", table, err);
+
+    act_as(conn, Some(tenant_a)).await;
+    assert_eq!(count(conn, table, &seeded).await, 1, "{} hides rows from their organization", table);
+
+    act_as(conn, None).await;
+    assert_eq!(count(conn, table, &seeded).await, 0, "{} shows rows without a tenant", table);
+
+    sqlx::query("SELECT set_config('app.bypass_rls', 'on', false)").execute(&mut *conn).await.unwrap();
+    assert_eq!(count(conn, table, &seeded).await, 1, "{} hides rows from system work", table);
+
+    sqlx::query("RESET ROLE").execute(&mut *conn).await.unwrap();
+}
+
+async fn check_service(service: &str, shared: &[&str]) {
+    let (admin, pool, name) = test_database(service).await;
+    let tables = isolated_tables(&pool).await;
+
+    // Every table is isolated unless it is meant to be shared
+    let mut unisolated: Vec<String> = sqlx::query_scalar(
+        "SELECT relname::text FROM pg_class WHERE relnamespace = 'public'::regnamespace AND relkind = 'r'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    unisolated.retain(|table| !tables.contains_key(table) && !SHARED_TABLES.contains(&table.as_str()));
+    unisolated.sort();
+    assert_eq!(unisolated, shared, "{}: tables without tenant isolation", service);
+
+    for (table, isolation) in &tables {
+        check_table(&pool, table, isolation, &tables).await;
+    }
+
+    drop_database(admin, pool, name).await;
+}
+
+// The second argument lists the service's shared tables: organization
+// directories, the global test catalog and permission definitions
+macro_rules! isolation_test {
+    ($name:ident, $service:literal, [$($shared:literal),*]) => {
+        #[tokio::test]
+        #[ignore] // Requires database connection
+        async fn $name() {
+            check_service($service, &[$($shared),*]).await;
+        }
+    };
+}
+
+isolation_test!(test_analytics_service_isolation, "analytics-service", []);
+isolation_test!(test_billing_service_isolation, "billing-service", ["organization"]);
+isolation_test!(test_compliance_service_isolation, "compliance-service", ["organization"]);
+isolation_test!(test_equipment_service_isolation, "equipment-service", ["organization"]);
+isolation_test!(test_inventory_service_isolation, "inventory-service", []);
+isolation_test!(test_notification_service_isolation, "notification-service", []);
+isolation_test!(test_order_service_isolation, "order-service", ["test_catalog", "test_category", "test_panel", "test_panel_item"]);
+isolation_test!(test_organization_service_isolation, "organization-service", []);
+isolation_test!(test_patient_service_isolation, "patient-service", ["organization", "user"]);
+isolation_test!(test_qc_service_isolation, "qc-service", ["organization"]);
+isolation_test!(test_report_service_isolation, "report-service", []);
+isolation_test!(test_result_service_isolation, "result-service", ["result_comment_template"]);
+isolation_test!(test_sample_service_isolation, "sample-service", []);
+isolation_test!(test_user_service_isolation, "user-service", ["permission"]);
@@ -0,0 +1,21 @@
+-- Daily counters maintained from order and result events
+
+CREATE TABLE IF NOT EXISTS event_metric (
+    organization_id UUID NOT NULL,
+    metric_key VARCHAR(100) NOT NULL,
+    metric_date DATE NOT NULL,
+
+    metric_value DECIMAL(15,2) NOT NULL DEFAULT 0,
+    event_count BIGINT NOT NULL DEFAULT 0,
+
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (organization_id, metric_key, metric_date)
+);
+
+-- Events already counted, so redelivered events are not counted twice
+CREATE TABLE IF NOT EXISTS processed_event (
+    event_id UUID PRIMARY KEY,
+    event_type VARCHAR(100) NOT NULL,
+    processed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
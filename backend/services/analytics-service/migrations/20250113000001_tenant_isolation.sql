@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('cached_metric');
+SELECT enable_tenant_isolation('dashboard_config');
+SELECT enable_tenant_isolation('event_metric');
+SELECT enable_tenant_isolation('kpi_definition');
+SELECT enable_tenant_isolation('kpi_value');
+SELECT enable_tenant_isolation('report_execution_log');
+SELECT enable_tenant_isolation('saved_report');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('dashboard_widget', 'dashboard_id', 'dashboard_config');
+SELECT enable_parent_isolation('kpi_alert', 'kpi_id', 'kpi_definition');
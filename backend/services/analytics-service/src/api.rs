@@ -1,4 +1,6 @@
 use async_graphql::{Context, Object, Result};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use chrono::NaiveDate;
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -13,6 +15,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get role-based dashboard for a user
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn dashboard(
         &self,
         ctx: &Context<'_>,
@@ -29,6 +32,7 @@ impl QueryRoot {
     }
 
     /// Get TAT analytics for a period
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn tat_analytics(
         &self,
         ctx: &Context<'_>,
@@ -45,6 +49,7 @@ impl QueryRoot {
     }
 
     /// Get revenue analytics for a date range
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn revenue_analytics(
         &self,
         ctx: &Context<'_>,
@@ -65,6 +70,7 @@ impl QueryRoot {
     }
 
     /// Get sample volume analytics
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn sample_volume_analytics(
         &self,
         ctx: &Context<'_>,
@@ -85,6 +91,7 @@ impl QueryRoot {
     }
 
     /// Get quality metrics
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn quality_metrics(
         &self,
         ctx: &Context<'_>,
@@ -101,6 +108,7 @@ impl QueryRoot {
     }
 
     /// Get equipment utilization metrics
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn equipment_utilization(
         &self,
         ctx: &Context<'_>,
@@ -118,7 +126,30 @@ impl QueryRoot {
         Ok(utilization)
     }
 
+    /// Get a daily counter maintained from order and result events
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
+    async fn event_metrics(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: String,
+        metric_key: String,
+        start_date: String,
+        end_date: String,
+    ) -> Result<Vec<EventMetric>> {
+        let org_id = Uuid::parse_str(&organization_id)?;
+        let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")?;
+        let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")?;
+
+        let pool = ctx.data::<PgPool>()?;
+        let repository = AnalyticsRepository::new(pool.clone());
+        let service = AnalyticsService::new(repository);
+
+        let metrics = service.get_event_metrics(org_id, &metric_key, start, end).await?;
+        Ok(metrics)
+    }
+
     /// Get operational metrics
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn operational_metrics(
         &self,
         ctx: &Context<'_>,
@@ -137,6 +168,7 @@ impl QueryRoot {
     }
 
     /// Get saved reports for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn saved_reports(
         &self,
         ctx: &Context<'_>,
@@ -158,15 +190,14 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create a new saved report
+    #[graphql(guard = "PermissionGuard::new(permissions::ANALYTICS_READ)")]
     async fn create_saved_report(
         &self,
         ctx: &Context<'_>,
         input: CreateSavedReportInput,
-        organization_id: String,
-        created_by: String,
     ) -> Result<SavedReport> {
-        let org_id = Uuid::parse_str(&organization_id)?;
-        let user_id = Uuid::parse_str(&created_by)?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
 
         let pool = ctx.data::<PgPool>()?;
         let repository = AnalyticsRepository::new(pool.clone());
@@ -6,6 +6,9 @@ pub struct Config {
     pub host: String,
     pub port: u16,
     pub database_max_connections: u32,
+    pub jwt_secret: String,
+    pub enable_events: bool,
+    pub kafka_brokers: String,
 }
 
 impl Config {
@@ -25,11 +28,24 @@ impl Config {
             .parse()
             .expect("DATABASE_MAX_CONNECTIONS must be a number");
 
+        let jwt_secret = std::env::var("JWT_SECRET")
+            .unwrap_or_else(|_| "development-secret-change-in-production".to_string());
+
+        let enable_events = std::env::var("ENABLE_EVENTS")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let kafka_brokers = std::env::var("KAFKA_BROKERS")
+            .unwrap_or_else(|_| "localhost:9092".to_string());
+
         Ok(Config {
             database_url,
             host,
             port,
             database_max_connections,
+            jwt_secret,
+            enable_events,
+            kafka_brokers,
         })
     }
 }
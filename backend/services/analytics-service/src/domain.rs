@@ -373,3 +373,25 @@ pub struct OperationalMetrics {
     pub pending_verifications: i64,
     pub critical_alerts: i64,
 }
+
+// ============================================================================
+// Event Metrics
+// ============================================================================
+
+pub const ORDERS_CREATED: &str = "orders_created";
+pub const ORDERS_COMPLETED: &str = "orders_completed";
+pub const ORDERS_CANCELLED: &str = "orders_cancelled";
+/// Summed turnaround hours of completed orders; divide by `event_count`
+pub const ORDER_TAT_HOURS: &str = "order_tat_hours";
+pub const RESULTS_ENTERED: &str = "results_entered";
+pub const RESULTS_APPROVED: &str = "results_approved";
+pub const CRITICAL_VALUES: &str = "critical_values";
+
+/// A daily counter maintained from domain events
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, FromRow)]
+pub struct EventMetric {
+    pub metric_key: String,
+    pub metric_date: NaiveDate,
+    pub metric_value: rust_decimal::Decimal,
+    pub event_count: i64,
+}
@@ -0,0 +1,113 @@
+//! Daily metrics maintained from order and result events.
+
+use chrono::{DateTime, Utc};
+use infrastructure::event_bus::{events, DomainEvent};
+use infrastructure::EventRouter;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::domain::*;
+use crate::service::AnalyticsService;
+
+/// Events counted into metrics
+const COUNTED_EVENTS: &[&str] = &[
+    events::ORDER_CREATED,
+    events::ORDER_COMPLETED,
+    events::ORDER_CANCELLED,
+    events::RESULT_ENTERED,
+    events::RESULT_APPROVED,
+    events::CRITICAL_VALUE_DETECTED,
+];
+
+#[derive(Debug, Deserialize)]
+struct CompletedOrder {
+    order_date: DateTime<Utc>,
+    actual_completion_date: Option<DateTime<Utc>>,
+}
+
+/// The metric contributions of an event
+fn event_metrics(event: &DomainEvent) -> common::error::Result<Vec<(&'static str, Decimal)>> {
+    let metrics = match event.event_type.as_str() {
+        events::ORDER_CREATED => vec![(ORDERS_CREATED, Decimal::ONE)],
+        events::ORDER_CANCELLED => vec![(ORDERS_CANCELLED, Decimal::ONE)],
+        events::ORDER_COMPLETED => {
+            let order: CompletedOrder = serde_json::from_value(event.payload.clone())?;
+            let completed_at = order.actual_completion_date.unwrap_or(event.metadata.timestamp);
+            let minutes = (completed_at - order.order_date).num_minutes().max(0);
+            let hours = (Decimal::from(minutes) / Decimal::from(60)).round_dp(2);
+            vec![(ORDERS_COMPLETED, Decimal::ONE), (ORDER_TAT_HOURS, hours)]
+        }
+        events::RESULT_ENTERED => vec![(RESULTS_ENTERED, Decimal::ONE)],
+        events::RESULT_APPROVED => vec![(RESULTS_APPROVED, Decimal::ONE)],
+        events::CRITICAL_VALUE_DETECTED => vec![(CRITICAL_VALUES, Decimal::ONE)],
+        _ => Vec::new(),
+    };
+
+    Ok(metrics)
+}
+
+pub fn event_routes(router: EventRouter, service: AnalyticsService) -> EventRouter {
+    COUNTED_EVENTS.iter().fold(router, |router, event_type| {
+        let service = service.clone();
+        router.on(event_type, move |event| {
+            let service = service.clone();
+            async move {
+                let Some(org_id) = event.organization_uuid() else {
+                    tracing::warn!("Event {} has no organization, not counted", event.event_id);
+                    return Ok(());
+                };
+                let metrics = event_metrics(&event)?;
+                let date = event.metadata.timestamp.date_naive();
+                service
+                    .record_event(event.event_id, &event.event_type, org_id, date, &metrics)
+                    .await?;
+                Ok(())
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: &str, payload: serde_json::Value) -> DomainEvent {
+        DomainEvent::new(
+            event_type.to_string(),
+            uuid::Uuid::new_v4().to_string(),
+            "TestOrder".to_string(),
+            payload,
+            uuid::Uuid::new_v4().to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn completed_order_counts_turnaround_hours() {
+        let completed = event(
+            events::ORDER_COMPLETED,
+            json!({
+                "order_date": "2025-01-10T08:00:00Z",
+                "actual_completion_date": "2025-01-10T14:30:00Z",
+            }),
+        );
+
+        let metrics = event_metrics(&completed).unwrap();
+        assert_eq!(
+            metrics,
+            vec![(ORDERS_COMPLETED, Decimal::ONE), (ORDER_TAT_HOURS, Decimal::new(650, 2))]
+        );
+    }
+
+    #[test]
+    fn unknown_events_count_nothing() {
+        let metrics = event_metrics(&event("order.updated", json!({}))).unwrap();
+        assert!(metrics.is_empty());
+    }
+
+    #[test]
+    fn malformed_completed_order_is_rejected() {
+        assert!(event_metrics(&event(events::ORDER_COMPLETED, json!({"id": 1}))).is_err());
+    }
+}
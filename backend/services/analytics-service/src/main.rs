@@ -1,6 +1,7 @@
 mod api;
 mod config;
 mod domain;
+mod events;
 mod repository;
 mod service;
 
@@ -8,12 +9,17 @@ use actix_cors::Cors;
 use actix_web::{middleware, web, App, HttpServer};
 use async_graphql::{EmptySubscription, Schema};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::event_bus::topics;
+use infrastructure::{ConsumerRuntime, DatabasePool, EventBusConfig, EventRouter, EventTransport};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use crate::api::{MutationRoot, QueryRoot};
 use crate::config::Config;
+use crate::repository::AnalyticsRepository;
+use crate::service::AnalyticsService;
 
 pub type ServiceSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
@@ -33,22 +39,40 @@ async fn main() -> std::io::Result<()> {
     info!("Starting Analytics Service on {}:{}", config.host, config.port);
 
     // Database connection pool
-    let db_pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let db_pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     info!("Connected to database");
 
-    // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&db_pool)
+    // Run migrations; they may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&db_pool))
         .await
         .expect("Failed to run migrations");
 
     info!("Database migrations completed");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Count order and result events into daily metrics
+    if let Some(event_bus) = &event_bus {
+        let consumer = event_bus
+            .subscribe("analytics-service", &[topics::ORDER_EVENTS, topics::RESULT_EVENTS])
+            .expect("Failed to create event consumer");
+        let service = AnalyticsService::new(AnalyticsRepository::new(db_pool.clone()));
+        let router = events::event_routes(EventRouter::new(), service);
+        ConsumerRuntime::new(consumer, router, event_bus.clone()).spawn();
+        info!("Event consumer started");
+    }
+
     // Build GraphQL schema
     let schema = Schema::build(
         QueryRoot::default(),
@@ -63,6 +87,7 @@ async fn main() -> std::io::Result<()> {
     // Start HTTP server
     let bind_address = format!("{}:{}", config.host, config.port);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
@@ -71,6 +96,7 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
 
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .app_data(web::Data::new(schema.clone()))
@@ -89,9 +115,10 @@ async fn main() -> std::io::Result<()> {
 
 async fn graphql(
     schema: web::Data<ServiceSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> actix_web::Result<actix_web::HttpResponse> {
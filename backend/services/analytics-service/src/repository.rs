@@ -401,4 +401,81 @@ impl AnalyticsRepository {
 
         Ok(count.0)
     }
+
+    // ========================================================================
+    // Event Metrics
+    // ========================================================================
+
+    /// Add an event's contributions to the daily metrics. Returns `false`
+    /// when the event was already counted.
+    pub async fn record_event_metrics(
+        &self,
+        event_id: Uuid,
+        event_type: &str,
+        org_id: Uuid,
+        date: NaiveDate,
+        metrics: &[(&str, rust_decimal::Decimal)],
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let inserted = sqlx::query(
+            "INSERT INTO processed_event (event_id, event_type) VALUES ($1, $2) ON CONFLICT (event_id) DO NOTHING"
+        )
+        .bind(event_id)
+        .bind(event_type)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        for (metric_key, value) in metrics {
+            sqlx::query(
+                r#"
+                INSERT INTO event_metric (organization_id, metric_key, metric_date, metric_value, event_count)
+                VALUES ($1, $2, $3, $4, 1)
+                ON CONFLICT (organization_id, metric_key, metric_date) DO UPDATE SET
+                    metric_value = event_metric.metric_value + EXCLUDED.metric_value,
+                    event_count = event_metric.event_count + 1,
+                    updated_at = NOW()
+                "#
+            )
+            .bind(org_id)
+            .bind(metric_key)
+            .bind(date)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn get_event_metrics(
+        &self,
+        org_id: Uuid,
+        metric_key: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<EventMetric>> {
+        let metrics = sqlx::query_as::<_, EventMetric>(
+            r#"
+            SELECT metric_key, metric_date, metric_value, event_count
+            FROM event_metric
+            WHERE organization_id = $1 AND metric_key = $2 AND metric_date BETWEEN $3 AND $4
+            ORDER BY metric_date
+            "#
+        )
+        .bind(org_id)
+        .bind(metric_key)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(metrics)
+    }
 }
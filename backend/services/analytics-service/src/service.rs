@@ -371,14 +371,58 @@ impl AnalyticsService {
         let pending_verifications = self.repository.get_pending_verifications_count(org_id).await?;
         let critical_alerts = self.repository.get_critical_result_count(org_id, 1).await?;
 
+        let total_orders = self.repository
+            .get_event_metrics(org_id, ORDERS_CREATED, start_date, today)
+            .await?
+            .iter()
+            .map(|m| m.event_count)
+            .sum();
+        let total_results = self.repository
+            .get_event_metrics(org_id, RESULTS_ENTERED, start_date, today)
+            .await?
+            .iter()
+            .map(|m| m.event_count)
+            .sum();
+
         Ok(OperationalMetrics {
             period: format!("Last {} days", days),
-            total_orders: 0, // TODO: Implement
+            total_orders,
             total_samples,
-            total_results: 0, // TODO: Implement
+            total_results,
             pending_results,
             pending_verifications,
             critical_alerts,
         })
     }
+
+    // ========================================================================
+    // Event Metrics
+    // ========================================================================
+
+    /// Count an event into the daily metrics of its organization. Returns
+    /// `false` when the event was already counted.
+    pub async fn record_event(
+        &self,
+        event_id: Uuid,
+        event_type: &str,
+        org_id: Uuid,
+        date: NaiveDate,
+        metrics: &[(&str, rust_decimal::Decimal)],
+    ) -> Result<bool> {
+        self.repository
+            .record_event_metrics(event_id, event_type, org_id, date, metrics)
+            .await
+    }
+
+    pub async fn get_event_metrics(
+        &self,
+        org_id: Uuid,
+        metric_key: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<EventMetric>> {
+        self.repository
+            .get_event_metrics(org_id, metric_key, start_date, end_date)
+            .await
+    }
 }
@@ -0,0 +1,198 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::service_client::{ServiceClient, ServiceRegistry};
+
+/// Standard introspection query, deep enough for `[[Type!]!]!`-style wrappers
+pub const INTROSPECTION_QUERY: &str = r#"
+    query IntrospectionQuery {
+        __schema {
+            queryType { name }
+            mutationType { name }
+            types { ...FullType }
+        }
+    }
+
+    fragment FullType on __Type {
+        kind
+        name
+        description
+        fields(includeDeprecated: true) {
+            name
+            description
+            args { ...InputValue }
+            type { ...TypeRef }
+            isDeprecated
+            deprecationReason
+        }
+        inputFields { ...InputValue }
+        interfaces { ...TypeRef }
+        enumValues(includeDeprecated: true) {
+            name
+            description
+            isDeprecated
+            deprecationReason
+        }
+        possibleTypes { ...TypeRef }
+    }
+
+    fragment InputValue on __InputValue {
+        name
+        description
+        type { ...TypeRef }
+        defaultValue
+    }
+
+    fragment TypeRef on __Type {
+        kind
+        name
+        ofType { kind name ofType { kind name ofType { kind name ofType {
+            kind name ofType { kind name ofType { kind name ofType { kind name } } }
+        } } } }
+    }
+"#;
+
+#[derive(Debug, Deserialize)]
+struct IntrospectionResponse {
+    #[serde(rename = "__schema")]
+    schema: IntrospectionSchema,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntrospectionSchema {
+    pub query_type: Option<NamedType>,
+    pub mutation_type: Option<NamedType>,
+    pub types: Vec<FullType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NamedType {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+    List,
+    NonNull,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullType {
+    pub kind: TypeKind,
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: Option<Vec<FieldDef>>,
+    pub input_fields: Option<Vec<InputValueDef>>,
+    pub interfaces: Option<Vec<TypeRefDef>>,
+    pub enum_values: Option<Vec<EnumValueDef>>,
+    pub possible_types: Option<Vec<TypeRefDef>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDef {
+    pub name: String,
+    pub description: Option<String>,
+    pub args: Vec<InputValueDef>,
+    #[serde(rename = "type")]
+    pub ty: TypeRefDef,
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputValueDef {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: TypeRefDef,
+    pub default_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumValueDef {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeRefDef {
+    pub kind: TypeKind,
+    pub name: Option<String>,
+    pub of_type: Option<Box<TypeRefDef>>,
+}
+
+impl TypeRefDef {
+    /// Innermost named type, unwrapping lists and non-null wrappers
+    pub fn named(&self) -> &TypeRefDef {
+        match &self.of_type {
+            Some(inner) if matches!(self.kind, TypeKind::List | TypeKind::NonNull) => inner.named(),
+            _ => self,
+        }
+    }
+
+    /// Innermost type name
+    pub fn name(&self) -> &str {
+        self.named().name.as_deref().unwrap_or_default()
+    }
+}
+
+/// A downstream service together with its introspected schema
+#[derive(Clone)]
+pub struct Subgraph {
+    pub client: ServiceClient,
+    pub schema: IntrospectionSchema,
+}
+
+/// Introspect a single service
+pub async fn introspect(client: &ServiceClient) -> Result<IntrospectionSchema, String> {
+    let response: IntrospectionResponse = client.execute(None, INTROSPECTION_QUERY, None).await?;
+    Ok(response.schema)
+}
+
+/// Introspect every registered service, retrying while services are still starting.
+///
+/// Services that never answer are left out of the gateway schema.
+pub async fn introspect_all(registry: &ServiceRegistry, attempts: u32, delay: Duration) -> Vec<Subgraph> {
+    let pending = registry.iter().map(|client| async move {
+        for attempt in 1..=attempts {
+            match introspect(client).await {
+                Ok(schema) => {
+                    return Some(Subgraph {
+                        client: client.clone(),
+                        schema,
+                    })
+                }
+                Err(e) if attempt < attempts => {
+                    tracing::debug!("Introspection of {}-service failed (attempt {}): {}", client.name(), attempt, e);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping {}-service at {}: introspection failed: {}",
+                        client.name(),
+                        client.base_url(),
+                        e
+                    );
+                }
+            }
+        }
+        None
+    });
+
+    futures::future::join_all(pending).await.into_iter().flatten().collect()
+}
@@ -0,0 +1,209 @@
+use async_graphql::dataloader::{DataLoader, Loader};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::service_client::{ServiceClient, ServiceRegistry};
+
+/// Alias under which the gateway requests key fields it needs for itself
+pub const KEY_ALIAS_PREFIX: &str = "_key_";
+
+/// A type that can be fetched in batches from its owning service
+pub struct Entity {
+    /// Gateway type name
+    pub type_name: &'static str,
+    /// Owning service
+    pub service: &'static str,
+    /// Root query field taking `ids` and returning a list of the type
+    pub batch_field: &'static str,
+    /// GraphQL type of the ids accepted by `batch_field`
+    pub id_type: &'static str,
+}
+
+pub const ENTITIES: &[Entity] = &[
+    Entity {
+        type_name: "Patient",
+        service: "patient",
+        batch_field: "patientsByIds",
+        id_type: "String",
+    },
+    Entity {
+        type_name: "TestOrder",
+        service: "order",
+        batch_field: "ordersByIds",
+        id_type: "ID",
+    },
+    Entity {
+        type_name: "Sample",
+        service: "sample",
+        batch_field: "samplesByIds",
+        id_type: "ID",
+    },
+];
+
+/// A field the gateway adds to a type, resolved from another service by key
+pub struct Relation {
+    /// Gateway type that gets the field
+    pub type_name: &'static str,
+    /// Name of the added field
+    pub field: &'static str,
+    /// Field of `type_name` holding the id of the related entity
+    pub key: &'static str,
+    /// Gateway type of the related entity (one of `ENTITIES`)
+    pub target: &'static str,
+}
+
+pub const RELATIONS: &[Relation] = &[
+    Relation {
+        type_name: "TestOrder",
+        field: "patient",
+        key: "patientId",
+        target: "Patient",
+    },
+    Relation {
+        type_name: "Sample",
+        field: "patient",
+        key: "patientId",
+        target: "Patient",
+    },
+    Relation {
+        type_name: "Sample",
+        field: "order",
+        key: "orderId",
+        target: "TestOrder",
+    },
+    Relation {
+        type_name: "TestResult",
+        field: "patient",
+        key: "patientId",
+        target: "Patient",
+    },
+    Relation {
+        type_name: "TestResult",
+        field: "order",
+        key: "orderId",
+        target: "TestOrder",
+    },
+    Relation {
+        type_name: "TestResult",
+        field: "sample",
+        key: "sampleId",
+        target: "Sample",
+    },
+];
+
+pub fn entity(type_name: &str) -> Option<&'static Entity> {
+    ENTITIES.iter().find(|entity| entity.type_name == type_name)
+}
+
+/// Entity id together with the selection set the caller asked for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntityKey {
+    pub id: String,
+    pub selection: String,
+}
+
+/// Batches entity lookups into one `*ByIds` call per distinct selection set
+pub struct EntityLoader {
+    entity: &'static Entity,
+    client: ServiceClient,
+    token: Option<String>,
+}
+
+impl EntityLoader {
+    fn query(&self, selection: &str) -> String {
+        format!(
+            "query($ids: [{}!]!) {{ items: {}(ids: $ids) {{ {}id: id __typename{} }} }}",
+            self.entity.id_type, self.entity.batch_field, KEY_ALIAS_PREFIX, selection
+        )
+    }
+
+    async fn load_selection(&self, selection: &str, ids: Vec<&str>) -> Result<Vec<Value>, String> {
+        let variables = serde_json::json!({ "ids": ids });
+        let mut data: Value = self
+            .client
+            .execute(self.token.as_deref(), &self.query(selection), Some(variables))
+            .await?;
+
+        match data["items"].take() {
+            Value::Array(items) => Ok(items),
+            Value::Null => Ok(Vec::new()),
+            other => Err(format!(
+                "Unexpected {} result from {}-service: {}",
+                self.entity.batch_field,
+                self.client.name(),
+                other
+            )),
+        }
+    }
+}
+
+impl Loader<EntityKey> for EntityLoader {
+    type Value = Value;
+    type Error = String;
+
+    async fn load(&self, keys: &[EntityKey]) -> Result<HashMap<EntityKey, Value>, String> {
+        let mut by_selection: HashMap<&str, Vec<&str>> = HashMap::new();
+        for key in keys {
+            by_selection.entry(key.selection.as_str()).or_default().push(key.id.as_str());
+        }
+
+        let batches = by_selection.into_iter().map(|(selection, ids)| async move {
+            self.load_selection(selection, ids)
+                .await
+                .map(|items| (selection, items))
+        });
+
+        let mut loaded = HashMap::new();
+        for (selection, items) in futures::future::try_join_all(batches).await? {
+            for item in items {
+                let id = match item.get(format!("{}id", KEY_ALIAS_PREFIX)) {
+                    Some(Value::String(id)) => id.clone(),
+                    _ => continue,
+                };
+                loaded.insert(
+                    EntityKey {
+                        id,
+                        selection: selection.to_string(),
+                    },
+                    item,
+                );
+            }
+        }
+
+        Ok(loaded)
+    }
+}
+
+/// Per-request data loaders, one per entity type whose service is registered
+pub struct Loaders {
+    loaders: HashMap<&'static str, DataLoader<EntityLoader>>,
+}
+
+impl Loaders {
+    pub fn new(registry: &ServiceRegistry, token: Option<&str>) -> Self {
+        let loaders = ENTITIES
+            .iter()
+            .filter_map(|entity| {
+                let client = registry.get(entity.service)?.clone();
+                let loader = EntityLoader {
+                    entity,
+                    client,
+                    token: token.map(str::to_string),
+                };
+                Some((entity.type_name, DataLoader::new(loader, tokio::spawn)))
+            })
+            .collect();
+
+        Self { loaders }
+    }
+
+    /// Load one entity; batched with every other load issued in the same tick
+    pub async fn load(&self, type_name: &str, key: EntityKey) -> async_graphql::Result<Option<Value>> {
+        let loader = self
+            .loaders
+            .get(type_name)
+            .ok_or_else(|| async_graphql::Error::new(format!("No loader for {}", type_name)))?;
+
+        loader.load_one(key).await.map_err(async_graphql::Error::new)
+    }
+}
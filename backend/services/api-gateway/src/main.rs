@@ -1,17 +1,38 @@
 use actix_cors::Cors;
-use actix_web::{get, guard, middleware, web, App, HttpResponse, HttpServer, Result};
-use async_graphql::{
-    http::{playground_source, GraphQLPlaygroundConfig},
-    EmptySubscription, Schema,
-};
+use actix_web::{get, guard, middleware, web, App, HttpRequest, HttpResponse, HttpServer, Result};
+use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use common::auth::{bearer_token, JwtAuth, RequestClaims};
 use serde_json::json;
 use std::env;
+use std::time::Duration;
 
-mod patient_client;
+mod introspection;
+mod loaders;
 mod schema;
-
-use schema::*;
+mod service_client;
+
+use loaders::Loaders;
+use schema::AppSchema;
+use service_client::{ForwardedToken, ServiceClient, ServiceRegistry};
+
+/// Downstream services in stitching priority order: (name, URL variable, default URL)
+const SERVICES: &[(&str, &str, &str)] = &[
+    ("patient", "PATIENT_SERVICE_URL", "http://localhost:8081"),
+    ("order", "ORDER_SERVICE_URL", "http://localhost:8083"),
+    ("sample", "SAMPLE_SERVICE_URL", "http://localhost:8082"),
+    ("result", "RESULT_SERVICE_URL", "http://localhost:8084"),
+    ("user", "USER_SERVICE_URL", "http://localhost:8080"),
+    ("organization", "ORGANIZATION_SERVICE_URL", "http://localhost:8095"),
+    ("equipment", "EQUIPMENT_SERVICE_URL", "http://localhost:8087"),
+    ("qc", "QC_SERVICE_URL", "http://localhost:8088"),
+    ("billing", "BILLING_SERVICE_URL", "http://localhost:8089"),
+    ("report", "REPORT_SERVICE_URL", "http://localhost:8090"),
+    ("inventory", "INVENTORY_SERVICE_URL", "http://localhost:8091"),
+    ("notification", "NOTIFICATION_SERVICE_URL", "http://localhost:8092"),
+    ("analytics", "ANALYTICS_SERVICE_URL", "http://localhost:8093"),
+    ("compliance", "COMPLIANCE_SERVICE_URL", "http://localhost:8094"),
+];
 
 /// Health check endpoint
 #[get("/health")]
@@ -31,9 +52,22 @@ async fn ready() -> Result<HttpResponse> {
     })))
 }
 
-/// GraphQL endpoint
-async fn graphql(schema: web::Data<AppSchema>, req: GraphQLRequest) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+/// GraphQL endpoint; the caller's token is forwarded to the services
+async fn graphql(
+    schema: web::Data<AppSchema>,
+    registry: web::Data<ServiceRegistry>,
+    http_req: HttpRequest,
+    claims: RequestClaims,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let token = bearer_token(http_req.headers());
+    let mut request = claims
+        .attach(req.into_inner())
+        .data(Loaders::new(&registry, token));
+    if let Some(token) = token {
+        request = request.data(ForwardedToken(token.to_string()));
+    }
+    schema.execute(request).await.into()
 }
 
 /// GraphQL Playground UI
@@ -57,37 +91,36 @@ async fn main() -> std::io::Result<()> {
     let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("PORT").unwrap_or_else(|_| "8000".to_string());
     let bind_address = format!("{}:{}", host, port);
+    let jwt_secret =
+        env::var("JWT_SECRET").unwrap_or_else(|_| "development-secret-change-in-production".to_string());
 
-    // Service URLs
-    let patient_service_url =
-        env::var("PATIENT_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8081".to_string());
-    let order_service_url =
-        env::var("ORDER_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8082".to_string());
-    let sample_service_url =
-        env::var("SAMPLE_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8083".to_string());
-    let result_service_url =
-        env::var("RESULT_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8084".to_string());
-    let user_service_url =
-        env::var("USER_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let organization_service_url =
-        env::var("ORGANIZATION_SERVICE_URL").unwrap_or_else(|_| "http://localhost:8094".to_string());
+    let introspection_attempts = env::var("INTROSPECTION_ATTEMPTS")
+        .ok()
+        .and_then(|attempts| attempts.parse().ok())
+        .unwrap_or(10);
 
     tracing::info!("Configuration loaded:");
     tracing::info!("  Gateway: {}", bind_address);
-    tracing::info!("  Patient Service: {}", patient_service_url);
-    tracing::info!("  Order Service: {}", order_service_url);
-    tracing::info!("  Sample Service: {}", sample_service_url);
-    tracing::info!("  Result Service: {}", result_service_url);
-    tracing::info!("  User Service: {}", user_service_url);
-    tracing::info!("  Organization Service: {}", organization_service_url);
-
-    // Create service clients
-    let patient_client = patient_client::PatientClient::new(patient_service_url);
 
-    // Build GraphQL schema
-    let schema = Schema::build(QueryRoot::default(), MutationRoot::default(), EmptySubscription)
-        .data(patient_client)
-        .finish();
+    // Service clients
+    let registry = ServiceRegistry::new(
+        SERVICES
+            .iter()
+            .map(|(name, url_var, default_url)| {
+                let url = env::var(url_var).unwrap_or_else(|_| default_url.to_string());
+                tracing::info!("  {} service: {}", name, url);
+                ServiceClient::new(*name, url)
+            })
+            .collect(),
+    );
+
+    // Stitch the services' schemas
+    let subgraphs =
+        introspection::introspect_all(&registry, introspection_attempts, Duration::from_secs(3)).await;
+    tracing::info!("Stitched {} of {} services", subgraphs.len(), SERVICES.len());
+
+    let schema = schema::build_schema(&subgraphs)
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
 
     tracing::info!("GraphQL schema built successfully");
     tracing::info!("Starting HTTP server on {}", bind_address);
@@ -110,9 +143,11 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
 
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(cors)
             .wrap(middleware::Logger::default())
             .app_data(web::Data::new(schema.clone()))
+            .app_data(web::Data::new(registry.clone()))
             .service(health)
             .service(ready)
             .service(
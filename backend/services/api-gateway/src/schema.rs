@@ -1,153 +1,983 @@
-use async_graphql::{Context, Object, Result, Schema};
+//! Stitched gateway schema.
+//!
+//! Every service is introspected at startup and its types and root fields are
+//! merged into one dynamic schema. Root fields are forwarded to the owning
+//! service with the caller's token; relation fields such as `TestOrder.patient`
+//! are resolved from the owning service through batched data loaders.
+
+use async_graphql::dynamic::{
+    Enum, EnumItem, Field, FieldFuture, FieldValue, InputObject, InputValue, Interface, InterfaceField,
+    Object, ResolverContext, Scalar, Schema, SchemaError, TypeRef, Union,
+};
+use async_graphql::{Error, Name, SelectionField, ServerResult, Value as GqlValue};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::introspection::{FieldDef, FullType, InputValueDef, Subgraph, TypeKind, TypeRefDef};
+use crate::loaders::{self, EntityKey, Loaders, Relation, KEY_ALIAS_PREFIX, RELATIONS};
+use crate::service_client::{ForwardedToken, ServiceClient};
+
+pub type AppSchema = Schema;
+
+pub const QUERY_ROOT: &str = "QueryRoot";
+pub const MUTATION_ROOT: &str = "MutationRoot";
+
+const BUILTIN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+/// Bearer token of the caller, if any
+fn forwarded_token<'a>(ctx: &ResolverContext<'a>) -> Option<&'a str> {
+    ctx.ctx.data_opt::<ForwardedToken>().map(|token| token.0.as_str())
+}
+
+/// Key under which a field appears in the response
+fn response_key<'a>(field: &SelectionField<'a>) -> &'a str {
+    field.alias().unwrap_or_else(|| field.name())
+}
+
+/// Gateway name of a service type: `TestOrderGQL` is exposed as `TestOrder`
+fn gateway_type_name(name: &str) -> &str {
+    name.strip_suffix("GQL").filter(|base| !base.is_empty()).unwrap_or(name)
+}
+
+/// `billing` -> `Billing`, used to keep conflicting type names apart
+fn service_prefix(service: &str) -> String {
+    service
+        .split(['-', '_'])
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn is_builtin(name: &str) -> bool {
+    name.starts_with("__") || BUILTIN_SCALARS.contains(&name)
+}
+
+fn type_signature(ty: &TypeRefDef) -> String {
+    match (ty.kind, &ty.of_type) {
+        (TypeKind::NonNull, Some(inner)) => format!("{}!", type_signature(inner)),
+        (TypeKind::List, Some(inner)) => format!("[{}]", type_signature(inner)),
+        _ => gateway_type_name(ty.name()).to_string(),
+    }
+}
+
+/// Shape of a type, used to tell whether two services define the same type
+fn fingerprint(ty: &FullType) -> String {
+    let mut parts: Vec<String> = match ty.kind {
+        TypeKind::Object | TypeKind::Interface => ty
+            .fields
+            .iter()
+            .flatten()
+            .map(|field| format!("{}:{}", field.name, type_signature(&field.ty)))
+            .collect(),
+        TypeKind::InputObject => ty
+            .input_fields
+            .iter()
+            .flatten()
+            .map(|field| format!("{}:{}", field.name, type_signature(&field.ty)))
+            .collect(),
+        TypeKind::Enum => ty.enum_values.iter().flatten().map(|value| value.name.clone()).collect(),
+        TypeKind::Union => ty.possible_types.iter().flatten().map(type_signature).collect(),
+        _ => Vec::new(),
+    };
+    parts.sort();
+    format!("{:?}({})", ty.kind, parts.join(","))
+}
+
+/// Service type name -> gateway type name, for one subgraph
+type TypeNames = HashMap<String, String>;
+
+fn rename<'a>(names: &'a TypeNames, name: &'a str) -> &'a str {
+    names.get(name).map(String::as_str).unwrap_or(name)
+}
 
-use crate::patient_client::PatientClient;
+fn type_ref(ty: &TypeRefDef, names: &TypeNames) -> TypeRef {
+    match (ty.kind, &ty.of_type) {
+        (TypeKind::NonNull, Some(inner)) => TypeRef::NonNull(Box::new(type_ref(inner, names))),
+        (TypeKind::List, Some(inner)) => TypeRef::List(Box::new(type_ref(inner, names))),
+        _ => TypeRef::named(rename(names, ty.name())),
+    }
+}
+
+/// Arguments with a default may be omitted; the owning service applies the default
+fn input_type_ref(value: &InputValueDef, names: &TypeNames) -> TypeRef {
+    match type_ref(&value.ty, names) {
+        TypeRef::NonNull(inner) if value.default_value.is_some() => *inner,
+        ty => ty,
+    }
+}
+
+// ============================================================================
+// Type Index
+// ============================================================================
 
-pub type AppSchema = Schema<QueryRoot, MutationRoot, async_graphql::EmptySubscription>;
+struct FieldMeta {
+    /// Gateway name of the field's named type
+    ty: String,
+    /// Argument name -> gateway name of its named type
+    args: HashMap<String, String>,
+    relation: Option<&'static Relation>,
+}
 
-/// Root Query
+/// Type information needed to rewrite selections before forwarding them
 #[derive(Default)]
-pub struct QueryRoot;
+struct TypeIndex {
+    fields: HashMap<String, HashMap<String, FieldMeta>>,
+    inputs: HashMap<String, HashMap<String, String>>,
+    enums: HashSet<String>,
+}
+
+impl TypeIndex {
+    fn field(&self, type_name: &str, field: &str) -> Option<&FieldMeta> {
+        self.fields.get(type_name)?.get(field)
+    }
+
+    fn add_field(&mut self, type_name: &str, field: &FieldDef, names: &TypeNames) {
+        let meta = FieldMeta {
+            ty: rename(names, field.ty.name()).to_string(),
+            args: field
+                .args
+                .iter()
+                .map(|arg| (arg.name.clone(), rename(names, arg.ty.name()).to_string()))
+                .collect(),
+            relation: None,
+        };
+        self.fields
+            .entry(type_name.to_string())
+            .or_default()
+            .insert(field.name.clone(), meta);
+    }
+}
+
+// ============================================================================
+// Forwarded Documents
+// ============================================================================
 
-#[Object]
-impl QueryRoot {
-    /// Get list of patients
-    async fn patients(
-        &self,
-        ctx: &Context<'_>,
-        organization_id: Option<String>,
-        page: Option<i32>,
-        page_size: Option<i32>,
-    ) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
+fn write_value(out: &mut String, value: &GqlValue, ty: Option<&str>, index: &TypeIndex) {
+    match value {
+        // Enum values arrive as strings when passed through variables
+        GqlValue::String(item) if ty.is_some_and(|ty| index.enums.contains(ty)) => out.push_str(item),
+        GqlValue::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, item, ty, index);
+            }
+            out.push(']');
+        }
+        GqlValue::Object(fields) => {
+            let field_types = ty.and_then(|ty| index.inputs.get(ty));
+            out.push('{');
+            for (i, (name, item)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let _ = write!(out, "{}: ", name);
+                let field_type = field_types.and_then(|types| types.get(name.as_str()));
+                write_value(out, item, field_type.map(String::as_str), index);
+            }
+            out.push('}');
+        }
+        other => {
+            let _ = write!(out, "{}", other);
+        }
+    }
+}
 
-        patient_client
-            .get_patients(organization_id, page, page_size)
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
+fn write_arguments(out: &mut String, arguments: &[(Name, GqlValue)], types: Option<&HashMap<String, String>>, index: &TypeIndex) {
+    if arguments.is_empty() {
+        return;
+    }
+    out.push('(');
+    for (i, (name, value)) in arguments.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{}: ", name);
+        let ty = types.and_then(|types| types.get(name.as_str()));
+        write_value(out, value, ty.map(String::as_str), index);
     }
+    out.push(')');
+}
 
-    /// Get single patient by ID
-    async fn patient(&self, ctx: &Context<'_>, id: String) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
+/// Print a selected field as the owning service should see it.
+///
+/// Relation fields are replaced by their key field, fetched under a reserved
+/// alias so it never clashes with what the caller selected.
+fn write_field(out: &mut String, field: &SelectionField<'_>, parent: &str, index: &TypeIndex) -> ServerResult<()> {
+    if field.name().starts_with("__") {
+        return Ok(());
+    }
 
-        patient_client
-            .get_patient(id)
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
+    let meta = index.field(parent, field.name());
+    if let Some(relation) = meta.and_then(|meta| meta.relation) {
+        let _ = write!(out, " {}{}: {}", KEY_ALIAS_PREFIX, relation.key, relation.key);
+        return Ok(());
     }
 
-    /// Get patient by MRN number
-    async fn patient_by_mrn(&self, ctx: &Context<'_>, mrn_number: String) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
+    out.push(' ');
+    if let Some(alias) = field.alias() {
+        let _ = write!(out, "{}: ", alias);
+    }
+    out.push_str(field.name());
+    write_arguments(out, &field.arguments()?, meta.map(|meta| &meta.args), index);
 
-        let query = r#"
-            query GetPatientByMrn($mrnNumber: String!) {
-                patientByMrn(mrnNumber: $mrnNumber) {
-                    id
-                    mrnNumber
-                    firstName
-                    lastName
-                    dateOfBirth
-                    gender
-                    mobileNumber
-                    email
+    for directive in field.directives()? {
+        let _ = write!(out, " @{}", directive.name.node);
+        let arguments: Vec<_> = directive
+            .arguments
+            .iter()
+            .map(|(name, value)| (name.node.clone(), value.node.clone()))
+            .collect();
+        write_arguments(out, &arguments, None, index);
+    }
+
+    let ty = meta.map(|meta| meta.ty.as_str()).unwrap_or_default();
+    let mut children = field.selection_set().peekable();
+    if children.peek().is_some() {
+        out.push_str(" { __typename");
+        for child in children {
+            write_field(out, &child, ty, index)?;
+        }
+        out.push_str(" }");
+    }
+
+    Ok(())
+}
+
+/// Print the sub-selection of `field`, whose type is `type_name`
+fn write_selection(field: &SelectionField<'_>, type_name: &str, index: &TypeIndex) -> ServerResult<String> {
+    let mut out = String::new();
+    for child in field.selection_set() {
+        write_field(&mut out, &child, type_name, index)?;
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Field Values
+// ============================================================================
+
+/// How a field's JSON value maps onto its gateway type
+#[derive(Clone)]
+enum Shape {
+    Leaf,
+    Object,
+    /// Interface or union; the concrete type comes from `__typename`
+    Abstract(Arc<TypeNames>),
+}
+
+impl Shape {
+    fn of(ty: &TypeRefDef, names: &Arc<TypeNames>) -> Self {
+        match ty.named().kind {
+            TypeKind::Scalar | TypeKind::Enum => Shape::Leaf,
+            TypeKind::Interface | TypeKind::Union => Shape::Abstract(names.clone()),
+            _ => Shape::Object,
+        }
+    }
+
+    /// Gateway name of the concrete type of an interface or union value
+    fn concrete_type(&self, json: &Value) -> Option<String> {
+        match self {
+            Shape::Abstract(names) => json
+                .get("__typename")
+                .and_then(Value::as_str)
+                .map(|ty| rename(names, ty).to_string()),
+            _ => None,
+        }
+    }
+
+    fn owned(&self, json: Value) -> FieldValue<'static> {
+        match json {
+            json if matches!(self, Shape::Leaf) => {
+                FieldValue::value(GqlValue::from_json(json).unwrap_or_default())
+            }
+            Value::Array(items) => FieldValue::list(items.into_iter().map(|item| self.owned(item))),
+            Value::Null => FieldValue::NULL,
+            json => match self.concrete_type(&json) {
+                Some(ty) => FieldValue::owned_any(json).with_type(ty),
+                None => FieldValue::owned_any(json),
+            },
+        }
+    }
+
+    fn borrowed<'a>(&self, json: &'a Value) -> FieldValue<'a> {
+        match json {
+            json if matches!(self, Shape::Leaf) => {
+                FieldValue::value(GqlValue::from_json(json.clone()).unwrap_or_default())
+            }
+            Value::Array(items) => FieldValue::list(items.iter().map(|item| self.borrowed(item))),
+            Value::Null => FieldValue::NULL,
+            json => match self.concrete_type(json) {
+                Some(ty) => FieldValue::borrowed_any(json).with_type(ty),
+                None => FieldValue::borrowed_any(json),
+            },
+        }
+    }
+}
+
+/// Field of a stitched object type: read from the parent's JSON
+fn object_field(field: &FieldDef, names: &Arc<TypeNames>) -> Field {
+    let shape = Shape::of(&field.ty, names);
+    let resolved = Field::new(&field.name, type_ref(&field.ty, names), move |ctx| {
+        let value = ctx
+            .parent_value
+            .downcast_ref::<Value>()
+            .and_then(|parent| parent.get(response_key(&ctx.field())))
+            .filter(|value| !value.is_null());
+        FieldFuture::Value(value.map(|value| shape.borrowed(value)))
+    });
+    with_field_docs(resolved, field, names)
+}
+
+/// Root field of a service: forward the selection to the owning service
+fn root_field(
+    operation: &'static str,
+    root: &'static str,
+    field: &FieldDef,
+    client: ServiceClient,
+    names: &Arc<TypeNames>,
+    index: Arc<TypeIndex>,
+) -> Field {
+    let shape = Shape::of(&field.ty, names);
+    let resolved = Field::new(&field.name, type_ref(&field.ty, names), move |ctx| {
+        let client = client.clone();
+        let index = index.clone();
+        let shape = shape.clone();
+        FieldFuture::new(async move {
+            let selection = ctx.field();
+            let mut document = format!("{} {{", operation);
+            write_field(&mut document, &selection, root, &index).map_err(|e| Error::new(e.message))?;
+            document.push_str(" }");
+
+            let mut data: Value = client
+                .execute(forwarded_token(&ctx), &document, None)
+                .await
+                .map_err(Error::new)?;
+
+            let value = data
+                .get_mut(response_key(&selection))
+                .map(Value::take)
+                .filter(|value| !value.is_null());
+            Ok(value.map(|value| shape.owned(value)))
+        })
+    });
+    with_field_docs(resolved, field, names)
+}
+
+fn with_field_docs(mut resolved: Field, field: &FieldDef, names: &TypeNames) -> Field {
+    for arg in &field.args {
+        let mut input = InputValue::new(&arg.name, input_type_ref(arg, names));
+        if let Some(description) = &arg.description {
+            input = input.description(description);
+        }
+        resolved = resolved.argument(input);
+    }
+    if let Some(description) = &field.description {
+        resolved = resolved.description(description);
+    }
+    if field.is_deprecated {
+        resolved = resolved.deprecation(field.deprecation_reason.as_deref());
+    }
+    resolved
+}
+
+/// Field added by the gateway, fetched from the related entity's service
+fn relation_field(relation: &'static Relation, index: Arc<TypeIndex>) -> Field {
+    Field::new(relation.field, TypeRef::named(relation.target), move |ctx| {
+        let index = index.clone();
+        FieldFuture::new(async move {
+            let key = format!("{}{}", KEY_ALIAS_PREFIX, relation.key);
+            let id = match ctx.parent_value.downcast_ref::<Value>().and_then(|parent| parent.get(&key)) {
+                Some(Value::String(id)) => id.clone(),
+                _ => return Ok(None),
+            };
+
+            let selection = write_selection(&ctx.field(), relation.target, &index).map_err(|e| Error::new(e.message))?;
+            let loaders = ctx.ctx.data::<Loaders>()?;
+            let value = loaders.load(relation.target, EntityKey { id, selection }).await?;
+            Ok(value.map(FieldValue::owned_any))
+        })
+    })
+    .description(format!("{} referenced by `{}`", relation.target, relation.key))
+}
+
+// ============================================================================
+// Schema Assembly
+// ============================================================================
+
+/// A type definition the gateway registers, taken from the first service defining it
+struct OwnedType<'a> {
+    subgraph: usize,
+    name: String,
+    ty: &'a FullType,
+}
+
+/// Assign gateway names to every service type.
+///
+/// Identical definitions from several services are merged; a conflicting
+/// definition is kept apart by prefixing the service name.
+fn assign_type_names(subgraphs: &[Subgraph]) -> (Vec<TypeNames>, Vec<OwnedType<'_>>) {
+    let mut fingerprints: HashMap<String, String> = HashMap::new();
+    let mut all_names = Vec::with_capacity(subgraphs.len());
+    let mut owned = Vec::new();
+
+    for (subgraph_index, subgraph) in subgraphs.iter().enumerate() {
+        let roots: Vec<&str> = [&subgraph.schema.query_type, &subgraph.schema.mutation_type]
+            .into_iter()
+            .flatten()
+            .map(|root| root.name.as_str())
+            .collect();
+        let mut names = TypeNames::new();
+
+        for ty in &subgraph.schema.types {
+            if is_builtin(&ty.name) || roots.contains(&ty.name.as_str()) {
+                continue;
+            }
+
+            let fingerprint = fingerprint(ty);
+            let mut name = gateway_type_name(&ty.name).to_string();
+            match fingerprints.get(&name) {
+                Some(existing) if *existing == fingerprint => {
+                    names.insert(ty.name.clone(), name);
+                    continue;
+                }
+                Some(_) => {
+                    let prefixed = format!("{}{}", service_prefix(subgraph.client.name()), name);
+                    tracing::warn!(
+                        "Type {} from {}-service conflicts with another service, exposed as {}",
+                        ty.name,
+                        subgraph.client.name(),
+                        prefixed
+                    );
+                    name = prefixed;
                 }
+                None => {}
+            }
+
+            fingerprints.insert(name.clone(), fingerprint);
+            names.insert(ty.name.clone(), name.clone());
+            owned.push(OwnedType {
+                subgraph: subgraph_index,
+                name,
+                ty,
+            });
+        }
+
+        all_names.push(names);
+    }
+
+    (all_names, owned)
+}
+
+/// Root fields of one operation type, first service wins on name clashes
+fn collect_root_fields(
+    subgraphs: &[Subgraph],
+    root_of: impl Fn(&Subgraph) -> Option<&str>,
+    reserved: &[&str],
+) -> Vec<(usize, FieldDef)> {
+    let mut seen: HashSet<String> = reserved.iter().map(|name| name.to_string()).collect();
+    let mut fields = Vec::new();
+
+    for (subgraph_index, subgraph) in subgraphs.iter().enumerate() {
+        let Some(root) = root_of(subgraph) else { continue };
+        let Some(root_type) = subgraph.schema.types.iter().find(|ty| ty.name == root) else { continue };
+
+        for field in root_type.fields.iter().flatten() {
+            if field.name.starts_with("__") {
+                continue;
+            }
+            if !seen.insert(field.name.clone()) {
+                tracing::warn!(
+                    "Root field {} of {}-service is shadowed by another service",
+                    field.name,
+                    subgraph.client.name()
+                );
+                continue;
             }
-        "#;
+            fields.push((subgraph_index, field.clone()));
+        }
+    }
+
+    fields
+}
+
+/// Build the stitched schema from the introspected services
+pub fn build_schema(subgraphs: &[Subgraph]) -> Result<AppSchema, SchemaError> {
+    let (names, owned) = assign_type_names(subgraphs);
+    let names: Vec<Arc<TypeNames>> = names.into_iter().map(Arc::new).collect();
 
-        let variables = serde_json::json!({ "mrnNumber": mrn_number });
+    let query_fields = collect_root_fields(
+        subgraphs,
+        |subgraph| subgraph.schema.query_type.as_ref().map(|root| root.name.as_str()),
+        &["health"],
+    );
+    let mutation_fields = collect_root_fields(
+        subgraphs,
+        |subgraph| subgraph.schema.mutation_type.as_ref().map(|root| root.name.as_str()),
+        &[],
+    );
 
-        patient_client
-            .execute(query, Some(variables))
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
+    // Relations apply only where both sides come from the expected services
+    let owner: HashMap<&str, &str> = owned
+        .iter()
+        .map(|ty| (ty.name.as_str(), subgraphs[ty.subgraph].client.name()))
+        .collect();
+    let relations: Vec<&'static Relation> = RELATIONS
+        .iter()
+        .filter(|relation| {
+            let target_owned = loaders::entity(relation.target)
+                .is_some_and(|entity| owner.get(relation.target) == Some(&entity.service));
+            target_owned && owner.contains_key(relation.type_name)
+        })
+        .collect();
+
+    let mut index = TypeIndex::default();
+    for ty in &owned {
+        let names = &names[ty.subgraph];
+        match ty.ty.kind {
+            TypeKind::Object | TypeKind::Interface => {
+                for field in ty.ty.fields.iter().flatten() {
+                    index.add_field(&ty.name, field, names);
+                }
+            }
+            TypeKind::InputObject => {
+                let fields = ty
+                    .ty
+                    .input_fields
+                    .iter()
+                    .flatten()
+                    .map(|field| (field.name.clone(), rename(names, field.ty.name()).to_string()))
+                    .collect();
+                index.inputs.insert(ty.name.clone(), fields);
+            }
+            TypeKind::Enum => {
+                index.enums.insert(ty.name.clone());
+            }
+            _ => {}
+        }
+    }
+    for (subgraph, field) in &query_fields {
+        index.add_field(QUERY_ROOT, field, &names[*subgraph]);
     }
+    for (subgraph, field) in &mutation_fields {
+        index.add_field(MUTATION_ROOT, field, &names[*subgraph]);
+    }
+    for relation in &relations {
+        index.fields.entry(relation.type_name.to_string()).or_default().insert(
+            relation.field.to_string(),
+            FieldMeta {
+                ty: relation.target.to_string(),
+                args: HashMap::new(),
+                relation: Some(relation),
+            },
+        );
+    }
+    let index = Arc::new(index);
+
+    let has_mutations = !mutation_fields.is_empty();
+    let mut builder = Schema::build(QUERY_ROOT, has_mutations.then_some(MUTATION_ROOT), None);
 
-    /// Search patients
-    async fn search_patients(
-        &self,
-        ctx: &Context<'_>,
-        query: String,
-        organization_id: Option<String>,
-        limit: Option<i32>,
-    ) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
+    for ty in &owned {
+        let names = &names[ty.subgraph];
+        let def = ty.ty;
+        builder = match def.kind {
+            TypeKind::Scalar => {
+                let mut scalar = Scalar::new(&ty.name);
+                if let Some(description) = &def.description {
+                    scalar = scalar.description(description);
+                }
+                builder.register(scalar)
+            }
+            TypeKind::Enum => {
+                let mut item_enum = Enum::new(&ty.name);
+                for value in def.enum_values.iter().flatten() {
+                    let mut item = EnumItem::new(&value.name);
+                    if let Some(description) = &value.description {
+                        item = item.description(description);
+                    }
+                    if value.is_deprecated {
+                        item = item.deprecation(value.deprecation_reason.as_deref());
+                    }
+                    item_enum = item_enum.item(item);
+                }
+                if let Some(description) = &def.description {
+                    item_enum = item_enum.description(description);
+                }
+                builder.register(item_enum)
+            }
+            TypeKind::InputObject => {
+                let mut input = InputObject::new(&ty.name);
+                for field in def.input_fields.iter().flatten() {
+                    let mut value = InputValue::new(&field.name, input_type_ref(field, names));
+                    if let Some(description) = &field.description {
+                        value = value.description(description);
+                    }
+                    input = input.field(value);
+                }
+                if let Some(description) = &def.description {
+                    input = input.description(description);
+                }
+                builder.register(input)
+            }
+            TypeKind::Interface => {
+                let mut interface = Interface::new(&ty.name);
+                for field in def.fields.iter().flatten() {
+                    interface = interface.field(InterfaceField::new(&field.name, type_ref(&field.ty, names)));
+                }
+                if let Some(description) = &def.description {
+                    interface = interface.description(description);
+                }
+                builder.register(interface)
+            }
+            TypeKind::Union => {
+                let mut union = Union::new(&ty.name);
+                for possible in def.possible_types.iter().flatten() {
+                    union = union.possible_type(rename(names, possible.name()));
+                }
+                if let Some(description) = &def.description {
+                    union = union.description(description);
+                }
+                builder.register(union)
+            }
+            TypeKind::Object => {
+                let mut object = Object::new(&ty.name);
+                for interface in def.interfaces.iter().flatten() {
+                    object = object.implement(rename(names, interface.name()));
+                }
+                for field in def.fields.iter().flatten() {
+                    object = object.field(object_field(field, names));
+                }
+                for relation in relations.iter().filter(|relation| relation.type_name == ty.name) {
+                    object = object.field(relation_field(relation, index.clone()));
+                }
+                if let Some(description) = &def.description {
+                    object = object.description(description);
+                }
+                builder.register(object)
+            }
+            TypeKind::List | TypeKind::NonNull => builder,
+        };
+    }
 
-        patient_client
-            .search_patients(query, organization_id, limit)
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
+    let mut query = Object::new(QUERY_ROOT).field(
+        Field::new("health", TypeRef::named_nn(TypeRef::STRING), |_| {
+            FieldFuture::new(async { Ok(Some(FieldValue::value("API Gateway is healthy"))) })
+        })
+        .description("Health check - returns API Gateway status"),
+    );
+    for (subgraph, field) in &query_fields {
+        let client = subgraphs[*subgraph].client.clone();
+        query = query.field(root_field("query", QUERY_ROOT, field, client, &names[*subgraph], index.clone()));
     }
+    builder = builder.register(query);
 
-    /// Health check - returns API Gateway status
-    async fn health(&self) -> Result<String> {
-        Ok("API Gateway is healthy".to_string())
+    if has_mutations {
+        let mut mutation = Object::new(MUTATION_ROOT);
+        for (subgraph, field) in &mutation_fields {
+            let client = subgraphs[*subgraph].client.clone();
+            mutation = mutation.field(root_field("mutation", MUTATION_ROOT, field, client, &names[*subgraph], index.clone()));
+        }
+        builder = builder.register(mutation);
     }
+
+    builder.finish()
 }
 
-/// Root Mutation
-#[derive(Default)]
-pub struct MutationRoot;
-
-#[Object]
-impl MutationRoot {
-    /// Create a new patient
-    async fn create_patient(
-        &self,
-        ctx: &Context<'_>,
-        input: Value,
-        organization_id: String,
-        created_by: String,
-    ) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
-
-        patient_client
-            .create_patient(input, organization_id, created_by)
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
-    }
-
-    /// Update an existing patient
-    async fn update_patient(
-        &self,
-        ctx: &Context<'_>,
-        id: String,
-        input: Value,
-        updated_by: String,
-    ) -> Result<Value> {
-        let patient_client = ctx.data::<PatientClient>()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::introspection::{introspect, IntrospectionSchema};
+    use crate::service_client::ServiceRegistry;
+    use actix_web::{web, App, HttpRequest, HttpServer};
+    use async_graphql::{EmptyMutation, EmptySubscription, Request, ID};
+    use std::sync::Mutex;
+
+    /// Documents and tokens a mock service received, introspection excluded
+    #[derive(Default)]
+    struct Calls {
+        documents: Mutex<Vec<String>>,
+        tokens: Mutex<Vec<Option<String>>>,
+    }
 
-        patient_client
-            .update_patient(id, input, updated_by)
-            .await
-            .map_err(|e| async_graphql::Error::new(e))
+    #[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq)]
+    enum Gender {
+        Male,
+        Female,
     }
 
-    /// Delete a patient (soft delete)
-    async fn delete_patient(
-        &self,
-        ctx: &Context<'_>,
+    #[derive(async_graphql::SimpleObject)]
+    struct Patient {
         id: String,
-        deleted_by: String,
-    ) -> Result<bool> {
-        let patient_client = ctx.data::<PatientClient>()?;
-
-        let query = r#"
-            mutation DeletePatient($id: String!, $deletedBy: String!) {
-                deletePatient(id: $id, deletedBy: $deletedBy)
-            }
-        "#;
-
-        let variables = serde_json::json!({
-            "id": id,
-            "deletedBy": deleted_by
-        });
-
-        patient_client
-            .execute::<serde_json::Value>(query, Some(variables))
-            .await
-            .map(|_| true)
-            .map_err(|e| async_graphql::Error::new(e))
+        first_name: String,
+        gender: Gender,
+    }
+
+    struct PatientQuery;
+
+    #[async_graphql::Object]
+    impl PatientQuery {
+        async fn patients_by_ids(&self, ids: Vec<String>) -> Vec<Patient> {
+            ids.into_iter()
+                .map(|id| Patient {
+                    first_name: format!("Patient {}", id),
+                    id,
+                    gender: Gender::Female,
+                })
+                .collect()
+        }
+    }
+
+    #[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq)]
+    enum OrderStatusEnum {
+        Pending,
+        Confirmed,
+    }
+
+    #[derive(async_graphql::SimpleObject, Clone)]
+    struct TestOrderGQL {
+        id: ID,
+        patient_id: ID,
+        order_status: OrderStatusEnum,
+    }
+
+    fn orders() -> Vec<TestOrderGQL> {
+        [("o1", "p1", OrderStatusEnum::Pending), ("o2", "p2", OrderStatusEnum::Confirmed), ("o3", "p1", OrderStatusEnum::Pending)]
+            .into_iter()
+            .map(|(id, patient_id, order_status)| TestOrderGQL {
+                id: ID::from(id),
+                patient_id: ID::from(patient_id),
+                order_status,
+            })
+            .collect()
+    }
+
+    struct OrderQuery;
+
+    #[async_graphql::Object]
+    impl OrderQuery {
+        async fn orders(&self, status: Option<OrderStatusEnum>) -> Vec<TestOrderGQL> {
+            orders()
+                .into_iter()
+                .filter(|order| status.is_none_or(|status| order.order_status == status))
+                .collect()
+        }
+    }
+
+    struct OrderMutation;
+
+    #[async_graphql::Object]
+    impl OrderMutation {
+        async fn confirm_order(&self, id: ID) -> Option<TestOrderGQL> {
+            orders().into_iter().find(|order| order.id == id).map(|order| TestOrderGQL {
+                order_status: OrderStatusEnum::Confirmed,
+                ..order
+            })
+        }
+    }
+
+    /// Serve a schema on a random local port and return its base URL
+    fn serve<Q, M>(schema: async_graphql::Schema<Q, M, EmptySubscription>, calls: Arc<Calls>) -> String
+    where
+        Q: async_graphql::ObjectType + 'static,
+        M: async_graphql::ObjectType + 'static,
+    {
+        let server = HttpServer::new(move || {
+            let schema = schema.clone();
+            let calls = calls.clone();
+            App::new().route(
+                "/graphql",
+                web::post().to(move |req: HttpRequest, body: web::Json<Request>| {
+                    let schema = schema.clone();
+                    let calls = calls.clone();
+                    async move {
+                        let request = body.into_inner();
+                        if !request.query.contains("__schema") {
+                            calls.documents.lock().unwrap().push(request.query.clone());
+                            let token = req
+                                .headers()
+                                .get("authorization")
+                                .and_then(|value| value.to_str().ok())
+                                .map(str::to_string);
+                            calls.tokens.lock().unwrap().push(token);
+                        }
+                        web::Json(schema.execute(request).await)
+                    }
+                }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", addr)
+    }
+
+    struct Gateway {
+        schema: AppSchema,
+        registry: ServiceRegistry,
+        patient_calls: Arc<Calls>,
+        order_calls: Arc<Calls>,
+    }
+
+    impl Gateway {
+        async fn start() -> Self {
+            let patient_calls = Arc::new(Calls::default());
+            let order_calls = Arc::new(Calls::default());
+
+            let patient_url = serve(
+                async_graphql::Schema::new(PatientQuery, EmptyMutation, EmptySubscription),
+                patient_calls.clone(),
+            );
+            let order_url = serve(
+                async_graphql::Schema::new(OrderQuery, OrderMutation, EmptySubscription),
+                order_calls.clone(),
+            );
+
+            let registry = ServiceRegistry::new(vec![
+                ServiceClient::new("patient", patient_url),
+                ServiceClient::new("order", order_url),
+            ]);
+            let mut subgraphs = Vec::new();
+            for client in registry.iter() {
+                subgraphs.push(Subgraph {
+                    client: client.clone(),
+                    schema: introspect(client).await.unwrap(),
+                });
+            }
+
+            Self {
+                schema: build_schema(&subgraphs).unwrap(),
+                registry,
+                patient_calls,
+                order_calls,
+            }
+        }
+
+        async fn execute(&self, request: Request) -> serde_json::Value {
+            let request = request
+                .data(Loaders::new(&self.registry, Some("caller-token")))
+                .data(ForwardedToken("caller-token".to_string()));
+            let response = self.schema.execute(request).await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            response.data.into_json().unwrap()
+        }
+    }
+
+    fn subgraph(name: &str, schema: serde_json::Value) -> Subgraph {
+        Subgraph {
+            client: ServiceClient::new(name, "http://localhost"),
+            schema: serde_json::from_value::<IntrospectionSchema>(schema).unwrap(),
+        }
+    }
+
+    fn patient_type(fields: &[&str]) -> serde_json::Value {
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|name| {
+                serde_json::json!({
+                    "name": name, "description": null, "args": [], "isDeprecated": false, "deprecationReason": null,
+                    "type": { "kind": "SCALAR", "name": "String", "ofType": null }
+                })
+            })
+            .collect();
+        serde_json::json!({ "kind": "OBJECT", "name": "Patient", "description": null, "fields": fields,
+            "inputFields": null, "interfaces": [], "enumValues": null, "possibleTypes": null })
+    }
+
+    #[test]
+    fn test_conflicting_types_are_prefixed() {
+        let patient = subgraph("patient", serde_json::json!({
+            "queryType": null, "mutationType": null, "types": [patient_type(&["id", "firstName"])]
+        }));
+        let billing = subgraph("billing", serde_json::json!({
+            "queryType": null, "mutationType": null, "types": [patient_type(&["id", "balance"])]
+        }));
+        let report = subgraph("report", serde_json::json!({
+            "queryType": null, "mutationType": null, "types": [patient_type(&["firstName", "id"])]
+        }));
+
+        let subgraphs = [patient, billing, report];
+        let (names, owned) = assign_type_names(&subgraphs);
+        assert_eq!(names[0]["Patient"], "Patient");
+        assert_eq!(names[1]["Patient"], "BillingPatient");
+        assert_eq!(names[2]["Patient"], "Patient");
+        assert_eq!(owned.len(), 2);
+        assert_eq!(service_prefix("report-service"), "ReportService");
+    }
+
+    #[actix_web::test]
+    async fn test_stitched_schema_covers_every_service() {
+        let gateway = Gateway::start().await;
+        let sdl = gateway.schema.sdl();
+
+        assert!(sdl.contains("type TestOrder {"));
+        assert!(!sdl.contains("TestOrderGQL"));
+        assert!(sdl.contains("patient: Patient"));
+        assert!(sdl.contains("patientsByIds(ids: [String!]!): [Patient!]!"));
+        assert!(sdl.contains("confirmOrder(id: ID!): TestOrder"));
+        assert!(sdl.contains("health: String!"));
+    }
+
+    #[actix_web::test]
+    async fn test_relations_are_batched_and_token_forwarded() {
+        let gateway = Gateway::start().await;
+
+        let data = gateway
+            .execute(Request::new(
+                "{ orders { id patientId patient { id firstName gender } } }",
+            ))
+            .await;
+
+        let orders = data["orders"].as_array().unwrap();
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0]["patient"]["firstName"], "Patient p1");
+        assert_eq!(orders[1]["patient"]["id"], "p2");
+        assert_eq!(orders[2]["patient"]["gender"], "FEMALE");
+
+        // One batched call for all three orders
+        let patient_documents = gateway.patient_calls.documents.lock().unwrap();
+        assert_eq!(patient_documents.len(), 1);
+        assert!(patient_documents[0].contains("patientsByIds"));
+
+        for calls in [&gateway.patient_calls, &gateway.order_calls] {
+            for token in calls.tokens.lock().unwrap().iter() {
+                assert_eq!(token.as_deref(), Some("Bearer caller-token"));
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_arguments_aliases_and_mutations_are_forwarded() {
+        let gateway = Gateway::start().await;
+
+        let data = gateway
+            .execute(
+                Request::new(
+                    "query($status: OrderStatusEnum) { confirmed: orders(status: $status) { ...Order } } \
+                     fragment Order on TestOrder { ref: id orderStatus }",
+                )
+                .variables(async_graphql::Variables::from_json(serde_json::json!({ "status": "CONFIRMED" }))),
+            )
+            .await;
+        assert_eq!(data, serde_json::json!({ "confirmed": [{ "ref": "o2", "orderStatus": "CONFIRMED" }] }));
+
+        let data = gateway
+            .execute(Request::new(
+                r#"mutation { confirmOrder(id: "o3") { orderStatus patient { firstName } } }"#,
+            ))
+            .await;
+        assert_eq!(data["confirmOrder"]["orderStatus"], "CONFIRMED");
+        assert_eq!(data["confirmOrder"]["patient"]["firstName"], "Patient p1");
     }
 }
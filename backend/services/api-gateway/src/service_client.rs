@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Caller's bearer token, passed on to downstream services
+pub struct ForwardedToken(pub String);
+
+/// Client for communicating with a downstream service's GraphQL endpoint
+#[derive(Clone)]
+pub struct ServiceClient {
+    name: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQLResponse<T> {
+    pub data: Option<T>,
+    pub errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    pub path: Option<Vec<Value>>,
+}
+
+impl std::fmt::Display for GraphQLError {
+    /// The message, followed by the field it was raised on, e.g. `Not found (at patient.orders.0)`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(path) = self.path.as_ref().filter(|path| !path.is_empty()) {
+            let segments: Vec<String> = path
+                .iter()
+                .map(|segment| match segment {
+                    Value::String(field) => field.clone(),
+                    other => other.to_string(),
+                })
+                .collect();
+            write!(f, " (at {})", segments.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl ServiceClient {
+    pub fn new(name: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            base_url: base_url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Short service name, e.g. "patient"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Execute a GraphQL query/mutation against the service
+    pub async fn execute<T: for<'de> Deserialize<'de>>(
+        &self,
+        token: Option<&str>,
+        query: &str,
+        variables: Option<Value>,
+    ) -> Result<T, String> {
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables,
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        tracing::debug!("Calling {}-service: {}", self.name, url);
+
+        let mut builder = self.client.post(&url);
+        if let Some(token) = token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response = builder
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call {}-service: {}", self.name, e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("{}-service returned error {}: {}", self.name, status, body);
+            return Err(format!("{}-service returned error {}: {}", self.name, status, body));
+        }
+
+        let graphql_response: GraphQLResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse {}-service response: {}", self.name, e))?;
+
+        if let Some(errors) = graphql_response.errors.filter(|errors| !errors.is_empty()) {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            let error_msg = error_messages.join(", ");
+            tracing::error!("{}-service GraphQL errors: {}", self.name, error_msg);
+            return Err(format!("{}-service errors: {}", self.name, error_msg));
+        }
+
+        graphql_response
+            .data
+            .ok_or_else(|| format!("No data returned from {}-service", self.name))
+    }
+}
+
+/// All downstream services known to the gateway, in stitching priority order
+#[derive(Clone, Default)]
+pub struct ServiceRegistry {
+    services: Vec<ServiceClient>,
+}
+
+impl ServiceRegistry {
+    pub fn new(services: Vec<ServiceClient>) -> Self {
+        Self { services }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ServiceClient> {
+        self.services.iter().find(|service| service.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ServiceClient> {
+        self.services.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_carries_its_path() {
+        let error: GraphQLError = serde_json::from_value(serde_json::json!({
+            "message": "Not found",
+            "path": ["patient", "orders", 0],
+        }))
+        .unwrap();
+        assert_eq!(error.to_string(), "Not found (at patient.orders.0)");
+
+        let error: GraphQLError = serde_json::from_value(serde_json::json!({ "message": "Unauthorized" })).unwrap();
+        assert_eq!(error.to_string(), "Unauthorized");
+    }
+}
@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('credit_note');
+SELECT enable_tenant_isolation('discount_scheme');
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('insurance_claim');
+SELECT enable_tenant_isolation('insurance_company');
+SELECT enable_tenant_isolation('invoice');
+SELECT enable_tenant_isolation('payment');
+SELECT enable_tenant_isolation('transaction_ledger');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('invoice_item', 'invoice_id', 'invoice');
@@ -1,6 +1,8 @@
 use async_graphql::{Context, Object, Result as GqlResult, ID, ErrorExtensions};
 use crate::domain::*;
 use crate::service::BillingService;
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use uuid::Uuid;
 use std::str::FromStr;
 
@@ -13,6 +15,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get invoice by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoice(&self, ctx: &Context<'_>, id: ID) -> GqlResult<Invoice> {
         let service = ctx.data::<BillingService>()?;
         let invoice_id = Uuid::from_str(&id)?;
@@ -21,6 +24,7 @@ impl QueryRoot {
     }
 
     /// Get invoice by invoice number
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoice_by_number(
         &self,
         ctx: &Context<'_>,
@@ -32,6 +36,7 @@ impl QueryRoot {
     }
 
     /// List invoices with optional filters and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoices(
         &self,
         ctx: &Context<'_>,
@@ -60,6 +65,7 @@ impl QueryRoot {
     }
 
     /// Get all invoices for a patient
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn patient_invoices(&self, ctx: &Context<'_>, patient_id: ID) -> GqlResult<Vec<Invoice>> {
         let service = ctx.data::<BillingService>()?;
         let patient_uuid = Uuid::from_str(&patient_id)?;
@@ -68,6 +74,7 @@ impl QueryRoot {
     }
 
     /// Get all invoices for an order
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn order_invoices(&self, ctx: &Context<'_>, order_id: ID) -> GqlResult<Vec<Invoice>> {
         let service = ctx.data::<BillingService>()?;
         let order_uuid = Uuid::from_str(&order_id)?;
@@ -76,6 +83,7 @@ impl QueryRoot {
     }
 
     /// Get invoice items
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoice_items(&self, ctx: &Context<'_>, invoice_id: ID) -> GqlResult<Vec<InvoiceItem>> {
         let service = ctx.data::<BillingService>()?;
         let invoice_uuid = Uuid::from_str(&invoice_id)?;
@@ -88,6 +96,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get payment by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn payment(&self, ctx: &Context<'_>, id: ID) -> GqlResult<Payment> {
         let service = ctx.data::<BillingService>()?;
         let payment_id = Uuid::from_str(&id)?;
@@ -96,6 +105,7 @@ impl QueryRoot {
     }
 
     /// List payments with optional filters and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn payments(
         &self,
         ctx: &Context<'_>,
@@ -124,6 +134,7 @@ impl QueryRoot {
     }
 
     /// Get all payments for an invoice
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoice_payments(&self, ctx: &Context<'_>, invoice_id: ID) -> GqlResult<Vec<Payment>> {
         let service = ctx.data::<BillingService>()?;
         let invoice_uuid = Uuid::from_str(&invoice_id)?;
@@ -136,6 +147,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get insurance company by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn insurance_company(&self, ctx: &Context<'_>, id: ID) -> GqlResult<InsuranceCompany> {
         let service = ctx.data::<BillingService>()?;
         let company_id = Uuid::from_str(&id)?;
@@ -144,6 +156,7 @@ impl QueryRoot {
     }
 
     /// List all active insurance companies
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn insurance_companies(&self, ctx: &Context<'_>, organization_id: ID) -> GqlResult<Vec<InsuranceCompany>> {
         let service = ctx.data::<BillingService>()?;
         let org_id = Uuid::from_str(&organization_id)?;
@@ -156,6 +169,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get insurance claim by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn insurance_claim(&self, ctx: &Context<'_>, id: ID) -> GqlResult<InsuranceClaim> {
         let service = ctx.data::<BillingService>()?;
         let claim_id = Uuid::from_str(&id)?;
@@ -164,6 +178,7 @@ impl QueryRoot {
     }
 
     /// List insurance claims with optional filters and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn insurance_claims(
         &self,
         ctx: &Context<'_>,
@@ -195,6 +210,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get credit note by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn credit_note(&self, ctx: &Context<'_>, id: ID) -> GqlResult<CreditNote> {
         let service = ctx.data::<BillingService>()?;
         let credit_note_id = Uuid::from_str(&id)?;
@@ -203,6 +219,7 @@ impl QueryRoot {
     }
 
     /// Get all credit notes for an invoice
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn invoice_credit_notes(&self, ctx: &Context<'_>, invoice_id: ID) -> GqlResult<Vec<CreditNote>> {
         let service = ctx.data::<BillingService>()?;
         let invoice_uuid = Uuid::from_str(&invoice_id)?;
@@ -215,6 +232,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get discount scheme by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn discount_scheme(&self, ctx: &Context<'_>, id: ID) -> GqlResult<DiscountScheme> {
         let service = ctx.data::<BillingService>()?;
         let scheme_id = Uuid::from_str(&id)?;
@@ -223,6 +241,7 @@ impl QueryRoot {
     }
 
     /// List all active discount schemes
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn discount_schemes(&self, ctx: &Context<'_>, organization_id: ID) -> GqlResult<Vec<DiscountScheme>> {
         let service = ctx.data::<BillingService>()?;
         let org_id = Uuid::from_str(&organization_id)?;
@@ -231,6 +250,7 @@ impl QueryRoot {
     }
 
     /// Get applicable discount schemes for a patient category
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_READ)")]
     async fn applicable_discount_schemes(
         &self,
         ctx: &Context<'_>,
@@ -253,29 +273,29 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create a new invoice
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_CREATE)")]
     async fn create_invoice(
         &self,
         ctx: &Context<'_>,
         input: CreateInvoiceInput,
-        created_by: ID,
     ) -> GqlResult<Invoice> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let invoice = service.create_invoice(input, creator_id).await?;
         Ok(invoice)
     }
 
     /// Cancel an invoice
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_UPDATE)")]
     async fn cancel_invoice(
         &self,
         ctx: &Context<'_>,
         invoice_id: ID,
         cancellation_reason: Option<String>,
-        cancelled_by: ID,
     ) -> GqlResult<Invoice> {
         let service = ctx.data::<BillingService>()?;
         let invoice_uuid = Uuid::from_str(&invoice_id)?;
-        let canceller_id = Uuid::from_str(&cancelled_by)?;
+        let canceller_id = ctx.user_id()?;
         let invoice = service.cancel_invoice(
             invoice_uuid,
             cancellation_reason,
@@ -285,6 +305,7 @@ impl MutationRoot {
     }
 
     /// Update invoice status (checks for overdue)
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_UPDATE)")]
     async fn update_invoice_status(
         &self,
         ctx: &Context<'_>,
@@ -301,28 +322,28 @@ impl MutationRoot {
     // ============================================================================
 
     /// Record a payment for an invoice
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_PAYMENT)")]
     async fn record_payment(
         &self,
         ctx: &Context<'_>,
         input: CreatePaymentInput,
-        created_by: ID,
     ) -> GqlResult<Payment> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let payment = service.record_payment(input, creator_id).await?;
         Ok(payment)
     }
 
     /// Reconcile a payment
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_PAYMENT)")]
     async fn reconcile_payment(
         &self,
         ctx: &Context<'_>,
         payment_id: ID,
-        reconciled_by: ID,
     ) -> GqlResult<Payment> {
         let service = ctx.data::<BillingService>()?;
         let payment_uuid = Uuid::from_str(&payment_id)?;
-        let reconciler_id = Uuid::from_str(&reconciled_by)?;
+        let reconciler_id = ctx.user_id()?;
         let payment = service.reconcile_payment(payment_uuid, reconciler_id).await?;
         Ok(payment)
     }
@@ -332,14 +353,14 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create an insurance company
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_CONFIGURE)")]
     async fn create_insurance_company(
         &self,
         ctx: &Context<'_>,
         input: CreateInsuranceCompanyInput,
-        created_by: ID,
     ) -> GqlResult<InsuranceCompany> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let company = service.create_insurance_company(input, creator_id).await?;
         Ok(company)
     }
@@ -349,19 +370,20 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create an insurance claim
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_CREATE)")]
     async fn create_insurance_claim(
         &self,
         ctx: &Context<'_>,
         input: CreateInsuranceClaimInput,
-        created_by: ID,
     ) -> GqlResult<InsuranceClaim> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let claim = service.create_insurance_claim(input, creator_id).await?;
         Ok(claim)
     }
 
     /// Update insurance claim status
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_UPDATE)")]
     async fn update_claim_status(
         &self,
         ctx: &Context<'_>,
@@ -369,11 +391,10 @@ impl MutationRoot {
         new_status: InsuranceClaimStatus,
         approved_amount: Option<String>,
         rejection_reason: Option<String>,
-        updated_by: ID,
     ) -> GqlResult<InsuranceClaim> {
         let service = ctx.data::<BillingService>()?;
         let claim_uuid = Uuid::from_str(&claim_id)?;
-        let updater_id = Uuid::from_str(&updated_by)?;
+        let updater_id = ctx.user_id()?;
 
         let approved_decimal = if let Some(amount_str) = approved_amount {
             Some(rust_decimal::Decimal::from_str(&amount_str)?)
@@ -396,14 +417,14 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create a credit note
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_REFUND)")]
     async fn create_credit_note(
         &self,
         ctx: &Context<'_>,
         input: CreateCreditNoteInput,
-        created_by: ID,
     ) -> GqlResult<CreditNote> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let credit_note = service.create_credit_note(input, creator_id).await?;
         Ok(credit_note)
     }
@@ -413,14 +434,14 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create a discount scheme
+    #[graphql(guard = "PermissionGuard::new(permissions::BILLING_CONFIGURE)")]
     async fn create_discount_scheme(
         &self,
         ctx: &Context<'_>,
         input: CreateDiscountSchemeInput,
-        created_by: ID,
     ) -> GqlResult<DiscountScheme> {
         let service = ctx.data::<BillingService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let scheme = service.create_discount_scheme(input, creator_id).await?;
         Ok(scheme)
     }
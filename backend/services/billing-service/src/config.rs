@@ -6,8 +6,10 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub kafka_brokers: String,
 }
 
 impl Config {
@@ -16,8 +18,10 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8089)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("kafka_brokers", "localhost:9092")?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -29,8 +33,10 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8089,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            kafka_brokers: "localhost:9092".to_string(),
         }
     }
 }
@@ -0,0 +1,28 @@
+//! Reactions to order events
+
+use infrastructure::event_bus::events;
+use infrastructure::EventRouter;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::service::BillingService;
+
+/// The fields of a cancelled `TestOrder` that billing needs
+#[derive(Debug, Deserialize)]
+struct CancelledOrder {
+    id: Uuid,
+    cancellation_reason: Option<String>,
+}
+
+pub fn event_routes(router: EventRouter, service: BillingService) -> EventRouter {
+    router.on_payload(events::ORDER_CANCELLED, move |event, order: CancelledOrder| {
+        let service = service.clone();
+        async move {
+            let cancelled_by = event.user_uuid().unwrap_or_default();
+            service
+                .void_order_invoice(order.id, order.cancellation_reason, cancelled_by)
+                .await?;
+            Ok(())
+        }
+    })
+}
@@ -1,13 +1,17 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::event_bus::topics;
+use infrastructure::{ConsumerRuntime, DatabasePool, EventBusConfig, EventRouter, EventTransport, OutboxRelay};
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod events;
 
 use repository::*;
 use service::BillingService;
@@ -18,9 +22,10 @@ type BillingSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<BillingSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -92,23 +97,37 @@ async fn main() -> std::io::Result<()> {
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&pool))
         .await
         .expect("Failed to run migrations");
 
     tracing::info!("Migrations completed successfully");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+        tracing::info!("Outbox relay started");
+    }
+
     // Create repositories
     let invoice_repo = InvoiceRepository::new(pool.clone());
     let payment_repo = PaymentRepository::new(pool.clone());
@@ -127,6 +146,15 @@ async fn main() -> std::io::Result<()> {
         discount_scheme_repo,
     );
 
+    // React to order events
+    if let Some(event_bus) = &event_bus {
+        let consumer = event_bus
+            .subscribe("billing-service", &[topics::ORDER_EVENTS])
+            .expect("Failed to create order event consumer");
+        let router = events::event_routes(EventRouter::new(), billing_service.clone());
+        ConsumerRuntime::new(consumer, router, event_bus.clone()).spawn();
+    }
+
     // Build GraphQL schema
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(billing_service)
@@ -144,8 +172,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
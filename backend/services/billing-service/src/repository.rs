@@ -3,6 +3,8 @@ use uuid::Uuid;
 use common::error::{Error, Result};
 use common::pagination::{Paginated, PaginationParams};
 use crate::domain::*;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 use rust_decimal::Decimal;
 
 // ============================================================================
@@ -331,6 +333,8 @@ impl PaymentRepository {
             .await
             .map_err(|e| Error::Database(e))?;
 
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let payment = sqlx::query_as::<_, Payment>(
             r#"
             INSERT INTO payment (
@@ -364,10 +368,32 @@ impl PaymentRepository {
         .bind(&input.notes)
         .bind(created_by)
         .bind(created_by) // received_by same as created_by
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| Error::Database(e))?;
 
+        let event_type = match payment.payment_status {
+            PaymentStatus::Success => Some(events::PAYMENT_RECEIVED),
+            PaymentStatus::Failed => Some(events::PAYMENT_FAILED),
+            _ => None,
+        };
+
+        if let Some(event_type) = event_type {
+            outbox::enqueue_event(
+                &mut *tx,
+                topics::BILLING_EVENTS,
+                event_type,
+                "Payment",
+                payment.id,
+                payment.organization_id,
+                Some(created_by),
+                &payment,
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(payment)
     }
 
@@ -53,6 +53,18 @@ impl From<common::error::Error> for BillingError {
     }
 }
 
+/// Billing rule breaches are final; missing records and database failures
+/// are retried
+impl From<BillingError> for common::error::Error {
+    fn from(error: BillingError) -> Self {
+        match error {
+            BillingError::NotFound(msg) => Self::NotFound(msg),
+            BillingError::DatabaseError(msg) => Self::ExternalService(msg),
+            other => Self::Validation(other.to_string()),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BillingError>;
 
 #[derive(Clone)]
@@ -199,6 +211,36 @@ impl BillingService {
         Ok(cancelled_invoice)
     }
 
+    /// Void the invoice of a cancelled order. Invoices that already took a
+    /// payment are left for a credit note.
+    pub async fn void_order_invoice(
+        &self,
+        order_id: Uuid,
+        cancellation_reason: Option<String>,
+        cancelled_by: Uuid,
+    ) -> Result<Option<Invoice>> {
+        let Some(invoice) = self.invoice_repo.find_by_order(order_id).await? else {
+            return Ok(None);
+        };
+
+        if invoice.invoice_status == InvoiceStatus::Cancelled {
+            return Ok(None);
+        }
+
+        if invoice.paid_amount.unwrap_or(Decimal::ZERO) > Decimal::ZERO {
+            tracing::warn!(
+                "Order {} was cancelled but invoice {} has payments; issue a credit note",
+                order_id,
+                invoice.invoice_number
+            );
+            return Ok(None);
+        }
+
+        let invoice = self.cancel_invoice(invoice.id, cancellation_reason, cancelled_by).await?;
+        tracing::info!("Invoice {} voided for cancelled order {}", invoice.invoice_number, order_id);
+        Ok(Some(invoice))
+    }
+
     /// Get invoice items
     pub async fn get_invoice_items(&self, invoice_id: Uuid) -> Result<Vec<InvoiceItem>> {
         let items = self.invoice_repo.get_invoice_items(invoice_id).await?;
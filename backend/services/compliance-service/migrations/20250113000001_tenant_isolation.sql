@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('audit_log');
+SELECT enable_tenant_isolation('capa');
+SELECT enable_tenant_isolation('compliance_assessment');
+SELECT enable_tenant_isolation('compliance_checklist');
+SELECT enable_tenant_isolation('document_control');
+SELECT enable_tenant_isolation('quality_indicator');
+SELECT enable_tenant_isolation('quality_indicator_value');
+SELECT enable_tenant_isolation('training_record');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('document_acknowledgement', 'document_id', 'document_control');
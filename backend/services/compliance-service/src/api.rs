@@ -1,4 +1,6 @@
 use async_graphql::{Context, Object, Result};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -12,6 +14,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get audit logs with optional filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::AUDIT_READ)")]
     async fn audit_logs(
         &self,
         ctx: &Context<'_>,
@@ -32,6 +35,7 @@ impl QueryRoot {
     }
 
     /// Get a specific document by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn document(&self, ctx: &Context<'_>, id: String) -> Result<DocumentControl> {
         let document_id = Uuid::parse_str(&id)?;
         let pool = ctx.data::<PgPool>()?;
@@ -43,6 +47,7 @@ impl QueryRoot {
     }
 
     /// Get documents for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn documents(
         &self,
         ctx: &Context<'_>,
@@ -63,6 +68,7 @@ impl QueryRoot {
     }
 
     /// Get a specific CAPA by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn capa(&self, ctx: &Context<'_>, id: String) -> Result<CAPA> {
         let capa_id = Uuid::parse_str(&id)?;
         let pool = ctx.data::<PgPool>()?;
@@ -74,6 +80,7 @@ impl QueryRoot {
     }
 
     /// Get CAPAs for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn capas(
         &self,
         ctx: &Context<'_>,
@@ -94,6 +101,7 @@ impl QueryRoot {
     }
 
     /// Get training records
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn training_records(
         &self,
         ctx: &Context<'_>,
@@ -115,6 +123,7 @@ impl QueryRoot {
     }
 
     /// Get quality indicators for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn quality_indicators(
         &self,
         ctx: &Context<'_>,
@@ -130,6 +139,7 @@ impl QueryRoot {
     }
 
     /// Get compliance dashboard summary
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_READ)")]
     async fn compliance_dashboard(
         &self,
         ctx: &Context<'_>,
@@ -151,6 +161,7 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create an audit log entry
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn create_audit_log(
         &self,
         ctx: &Context<'_>,
@@ -169,6 +180,7 @@ impl MutationRoot {
     }
 
     /// Create a new controlled document
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn create_document(
         &self,
         ctx: &Context<'_>,
@@ -189,6 +201,7 @@ impl MutationRoot {
     }
 
     /// Update a document
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn update_document(
         &self,
         ctx: &Context<'_>,
@@ -207,6 +220,7 @@ impl MutationRoot {
     }
 
     /// Approve and publish a document
+    #[graphql(guard = "PermissionGuard::new(permissions::DOCUMENT_APPROVE)")]
     async fn approve_document(
         &self,
         ctx: &Context<'_>,
@@ -227,15 +241,14 @@ impl MutationRoot {
     }
 
     /// Create a new CAPA
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn create_capa(
         &self,
         ctx: &Context<'_>,
         input: CreateCAPAInput,
-        organization_id: String,
-        created_by: String,
     ) -> Result<CAPA> {
-        let org_id = Uuid::parse_str(&organization_id)?;
-        let user_id = Uuid::parse_str(&created_by)?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
         let pool = ctx.data::<PgPool>()?;
         let repository = ComplianceRepository::new(pool.clone());
         let service = ComplianceService::new(repository);
@@ -245,6 +258,7 @@ impl MutationRoot {
     }
 
     /// Update a CAPA
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn update_capa(
         &self,
         ctx: &Context<'_>,
@@ -263,6 +277,7 @@ impl MutationRoot {
     }
 
     /// Close a CAPA
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn close_capa(
         &self,
         ctx: &Context<'_>,
@@ -281,15 +296,14 @@ impl MutationRoot {
     }
 
     /// Create a training record
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn create_training_record(
         &self,
         ctx: &Context<'_>,
         input: CreateTrainingRecordInput,
-        organization_id: String,
-        created_by: String,
     ) -> Result<TrainingRecord> {
-        let org_id = Uuid::parse_str(&organization_id)?;
-        let creator_id = Uuid::parse_str(&created_by)?;
+        let org_id = ctx.organization_id()?;
+        let creator_id = ctx.user_id()?;
         let pool = ctx.data::<PgPool>()?;
         let repository = ComplianceRepository::new(pool.clone());
         let service = ComplianceService::new(repository);
@@ -301,15 +315,14 @@ impl MutationRoot {
     }
 
     /// Record a quality indicator value
+    #[graphql(guard = "PermissionGuard::new(permissions::COMPLIANCE_MANAGE)")]
     async fn record_quality_indicator_value(
         &self,
         ctx: &Context<'_>,
         input: RecordQualityIndicatorInput,
-        organization_id: String,
-        measured_by: String,
     ) -> Result<QualityIndicatorValue> {
-        let org_id = Uuid::parse_str(&organization_id)?;
-        let user_id = Uuid::parse_str(&measured_by)?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
         let pool = ctx.data::<PgPool>()?;
         let repository = ComplianceRepository::new(pool.clone());
         let service = ComplianceService::new(repository);
@@ -6,6 +6,7 @@ pub struct Config {
     pub host: String,
     pub port: u16,
     pub database_max_connections: u32,
+    pub jwt_secret: String,
 }
 
 impl Config {
@@ -25,11 +26,15 @@ impl Config {
             .parse()
             .expect("DATABASE_MAX_CONNECTIONS must be a number");
 
+        let jwt_secret = std::env::var("JWT_SECRET")
+            .unwrap_or_else(|_| "development-secret-change-in-production".to_string());
+
         Ok(Config {
             database_url,
             host,
             port,
             database_max_connections,
+            jwt_secret,
         })
     }
 }
@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('equipment');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('equipment_alert', 'equipment_id', 'equipment');
+SELECT enable_parent_isolation('equipment_audit_log', 'equipment_id', 'equipment');
+SELECT enable_parent_isolation('equipment_calibration', 'equipment_id', 'equipment');
+SELECT enable_parent_isolation('equipment_maintenance', 'equipment_id', 'equipment');
+SELECT enable_parent_isolation('equipment_performance_log', 'equipment_id', 'equipment');
+SELECT enable_parent_isolation('equipment_test_assignment', 'equipment_id', 'equipment');
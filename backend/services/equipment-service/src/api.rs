@@ -1,5 +1,7 @@
 use async_graphql::{Context, Object, Result, ErrorExtensions};
 use uuid::Uuid;
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use common::pagination::PaginationParams;
 use crate::domain::*;
 use crate::service::EquipmentService;
@@ -13,6 +15,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get equipment by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn equipment(
         &self,
         ctx: &Context<'_>,
@@ -24,6 +27,7 @@ impl QueryRoot {
     }
 
     /// Get equipment by code
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn equipment_by_code(
         &self,
         ctx: &Context<'_>,
@@ -35,6 +39,7 @@ impl QueryRoot {
     }
 
     /// List equipment with filtering and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn equipment_list(
         &self,
         ctx: &Context<'_>,
@@ -61,6 +66,7 @@ impl QueryRoot {
     }
 
     /// Get maintenance record by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn maintenance(
         &self,
         ctx: &Context<'_>,
@@ -72,6 +78,7 @@ impl QueryRoot {
     }
 
     /// List maintenance records with filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn maintenance_list(
         &self,
         ctx: &Context<'_>,
@@ -82,6 +89,7 @@ impl QueryRoot {
     }
 
     /// Get calibration record by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn calibration(
         &self,
         ctx: &Context<'_>,
@@ -93,6 +101,7 @@ impl QueryRoot {
     }
 
     /// List calibration records with filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn calibration_list(
         &self,
         ctx: &Context<'_>,
@@ -103,6 +112,7 @@ impl QueryRoot {
     }
 
     /// List test assignments for equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn test_assignments(
         &self,
         ctx: &Context<'_>,
@@ -113,6 +123,7 @@ impl QueryRoot {
     }
 
     /// Get performance logs for equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn performance_logs(
         &self,
         ctx: &Context<'_>,
@@ -124,6 +135,7 @@ impl QueryRoot {
     }
 
     /// Get alert by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn alert(
         &self,
         ctx: &Context<'_>,
@@ -135,6 +147,7 @@ impl QueryRoot {
     }
 
     /// List alerts with filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_READ)")]
     async fn alert_list(
         &self,
         ctx: &Context<'_>,
@@ -154,90 +167,85 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create new equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn create_equipment(
         &self,
         ctx: &Context<'_>,
         input: CreateEquipmentInput,
     ) -> Result<Equipment> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_equipment(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn update_equipment(
         &self,
         ctx: &Context<'_>,
         input: UpdateEquipmentInput,
     ) -> Result<Equipment> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_equipment(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update equipment status
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn update_equipment_status(
         &self,
         ctx: &Context<'_>,
         input: UpdateEquipmentStatusInput,
     ) -> Result<Equipment> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_equipment_status(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Delete equipment (soft delete)
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn delete_equipment(
         &self,
         ctx: &Context<'_>,
         id: Uuid,
     ) -> Result<bool> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get deleted_by from authenticated user context
-        let deleted_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let deleted_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.delete_equipment(id, deleted_by).await.map_err(|e| e.extend())?)
     }
 
     /// Schedule maintenance
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn schedule_maintenance(
         &self,
         ctx: &Context<'_>,
         input: ScheduleMaintenanceInput,
     ) -> Result<EquipmentMaintenance> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.schedule_maintenance(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Complete maintenance
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn complete_maintenance(
         &self,
         ctx: &Context<'_>,
         input: CompleteMaintenanceInput,
     ) -> Result<EquipmentMaintenance> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.complete_maintenance(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Cancel maintenance
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn cancel_maintenance(
         &self,
         ctx: &Context<'_>,
@@ -248,34 +256,33 @@ impl MutationRoot {
     }
 
     /// Record calibration
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn record_calibration(
         &self,
         ctx: &Context<'_>,
         input: RecordCalibrationInput,
     ) -> Result<EquipmentCalibration> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.record_calibration(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Assign test to equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn assign_test(
         &self,
         ctx: &Context<'_>,
         input: AssignTestInput,
     ) -> Result<EquipmentTestAssignment> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.assign_test(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Unassign test from equipment
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn unassign_test(
         &self,
         ctx: &Context<'_>,
@@ -287,6 +294,7 @@ impl MutationRoot {
     }
 
     /// Log equipment performance
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn log_performance(
         &self,
         ctx: &Context<'_>,
@@ -297,34 +305,33 @@ impl MutationRoot {
     }
 
     /// Acknowledge alert
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn acknowledge_alert(
         &self,
         ctx: &Context<'_>,
         input: AcknowledgeAlertInput,
     ) -> Result<EquipmentAlert> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get acknowledged_by from authenticated user context
-        let acknowledged_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let acknowledged_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.acknowledge_alert(input, acknowledged_by).await.map_err(|e| e.extend())?)
     }
 
     /// Resolve alert
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MAINTAIN)")]
     async fn resolve_alert(
         &self,
         ctx: &Context<'_>,
         input: ResolveAlertInput,
     ) -> Result<EquipmentAlert> {
         let service = ctx.data::<EquipmentService>()?;
-
-        // In production, get resolved_by from authenticated user context
-        let resolved_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let resolved_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.resolve_alert(input, resolved_by).await.map_err(|e| e.extend())?)
     }
 
     /// Increment test counter (called by other services when processing tests)
+    #[graphql(guard = "PermissionGuard::new(permissions::EQUIPMENT_MANAGE)")]
     async fn increment_test_counter(
         &self,
         ctx: &Context<'_>,
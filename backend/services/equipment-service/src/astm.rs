@@ -0,0 +1,872 @@
+use chrono::NaiveDateTime;
+use common::error::{Error, Result};
+
+// ============================================================================
+// ASTM E1381 (LIS1-A) Low-Level Protocol
+// ============================================================================
+
+pub const ENQ: u8 = 0x05;
+pub const ACK: u8 = 0x06;
+pub const NAK: u8 = 0x15;
+pub const STX: u8 = 0x02;
+pub const ETX: u8 = 0x03;
+pub const ETB: u8 = 0x17;
+pub const EOT: u8 = 0x04;
+pub const CR: u8 = 0x0D;
+pub const LF: u8 = 0x0A;
+
+/// Maximum number of text characters carried by a single frame
+pub const MAX_FRAME_TEXT: usize = 240;
+
+/// Number of times a sender retransmits a frame after NAK before aborting
+pub const MAX_RETRANSMITS: usize = 6;
+
+/// Longest frame the receiver buffers. E1381 frames are at most 247
+/// characters, but some analyzers send longer ones; anything past this is
+/// taken as a broken link.
+pub const MAX_FRAME_BYTES: usize = 1024;
+
+/// Computes the two-character checksum of a frame: the modulo-256 sum of
+/// every byte from the frame number up to and including ETX/ETB.
+pub fn checksum(frame_number: u8, text: &[u8], terminator: u8) -> [u8; 2] {
+    let mut sum: u32 = (b'0' + frame_number) as u32;
+    sum += text.iter().map(|b| *b as u32).sum::<u32>();
+    sum += terminator as u32;
+
+    let hex = format!("{:02X}", sum % 256);
+    let bytes = hex.as_bytes();
+    [bytes[0], bytes[1]]
+}
+
+/// Encodes a single frame: `<STX> FN text <ETB|ETX> C1 C2 <CR> <LF>`
+pub fn encode_frame(frame_number: u8, text: &[u8], is_last: bool) -> Vec<u8> {
+    let terminator = if is_last { ETX } else { ETB };
+    let mut frame = Vec::with_capacity(text.len() + 7);
+
+    frame.push(STX);
+    frame.push(b'0' + frame_number);
+    frame.extend_from_slice(text);
+    frame.push(terminator);
+    frame.extend_from_slice(&checksum(frame_number, text, terminator));
+    frame.push(CR);
+    frame.push(LF);
+
+    frame
+}
+
+/// Splits a message into numbered frames. Each record becomes one or more
+/// frames; records longer than `MAX_FRAME_TEXT` are carried by intermediate
+/// (ETB) frames followed by a final (ETX) frame. Frame numbers run 1..7, 0, 1...
+pub fn encode_message(records: &[String]) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut frame_number: u8 = 1;
+
+    for record in records {
+        let mut text = record.as_bytes().to_vec();
+        text.push(CR);
+
+        let chunks: Vec<&[u8]> = text.chunks(MAX_FRAME_TEXT).collect();
+        let last_index = chunks.len() - 1;
+
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            frames.push(encode_frame(frame_number, chunk, i == last_index));
+            frame_number = (frame_number + 1) % 8;
+        }
+    }
+
+    frames
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub frame_number: u8,
+    pub text: Vec<u8>,
+    pub is_last: bool,
+}
+
+/// Decodes and validates a complete frame (STX through LF)
+pub fn decode_frame(bytes: &[u8]) -> Result<Frame> {
+    if bytes.len() < 7 || bytes[0] != STX {
+        return Err(Error::InvalidInput("Frame does not start with STX".to_string()));
+    }
+
+    let len = bytes.len();
+    if bytes[len - 2] != CR || bytes[len - 1] != LF {
+        return Err(Error::InvalidInput("Frame is not terminated by CR LF".to_string()));
+    }
+
+    let terminator = bytes[len - 5];
+    if terminator != ETX && terminator != ETB {
+        return Err(Error::InvalidInput("Frame is missing ETX/ETB".to_string()));
+    }
+
+    let fn_char = bytes[1];
+    if !(b'0'..=b'7').contains(&fn_char) {
+        return Err(Error::InvalidInput(format!("Invalid frame number: {}", fn_char as char)));
+    }
+    let frame_number = fn_char - b'0';
+
+    let text = &bytes[2..len - 5];
+    let expected = checksum(frame_number, text, terminator);
+    let received = [
+        bytes[len - 4].to_ascii_uppercase(),
+        bytes[len - 3].to_ascii_uppercase(),
+    ];
+
+    if expected != received {
+        return Err(Error::InvalidInput(format!(
+            "Checksum mismatch: expected {}, received {}",
+            String::from_utf8_lossy(&expected),
+            String::from_utf8_lossy(&received)
+        )));
+    }
+
+    Ok(Frame {
+        frame_number,
+        text: text.to_vec(),
+        is_last: terminator == ETX,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReceiverState {
+    Idle,
+    Receiving,
+}
+
+/// Output of feeding bytes into the receiver
+#[derive(Debug, Default)]
+pub struct ReceiverOutput {
+    /// Control characters (ACK/NAK) to write back to the sender
+    pub replies: Vec<u8>,
+    /// Complete messages (all frame texts of one ENQ..EOT transmission)
+    pub messages: Vec<String>,
+}
+
+/// Receiver side of the E1381 establishment/transfer/termination phases.
+///
+/// The receiver is a pure state machine so it can be driven by any transport.
+#[derive(Debug)]
+pub struct Receiver {
+    state: ReceiverState,
+    expected_frame: u8,
+    frame_buffer: Vec<u8>,
+    message_text: Vec<u8>,
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Receiver {
+    pub fn new() -> Self {
+        Self {
+            state: ReceiverState::Idle,
+            expected_frame: 1,
+            frame_buffer: Vec::new(),
+            message_text: Vec::new(),
+        }
+    }
+
+    pub fn is_receiving(&self) -> bool {
+        self.state == ReceiverState::Receiving
+    }
+
+    /// Discards any partially received message, e.g. after a receive timeout
+    pub fn reset(&mut self) {
+        self.state = ReceiverState::Idle;
+        self.expected_frame = 1;
+        self.frame_buffer.clear();
+        self.message_text.clear();
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> ReceiverOutput {
+        let mut output = ReceiverOutput::default();
+
+        for &byte in bytes {
+            match self.state {
+                ReceiverState::Idle => {
+                    if byte == ENQ {
+                        self.reset();
+                        self.state = ReceiverState::Receiving;
+                        output.replies.push(ACK);
+                    }
+                    // Anything else outside of a transmission is line noise
+                }
+                ReceiverState::Receiving => {
+                    if !self.frame_buffer.is_empty() {
+                        if self.frame_buffer.len() >= MAX_FRAME_BYTES {
+                            // A frame this long never ends with a valid
+                            // checksum; drop the transmission and wait for
+                            // the sender to establish the link again
+                            tracing::warn!("ASTM frame exceeds {} bytes; resetting the link", MAX_FRAME_BYTES);
+                            self.reset();
+                            continue;
+                        }
+                        self.frame_buffer.push(byte);
+                        if byte == LF {
+                            let reply = self.accept_frame();
+                            output.replies.push(reply);
+                        }
+                        continue;
+                    }
+
+                    match byte {
+                        STX => self.frame_buffer.push(byte),
+                        EOT => {
+                            let text = String::from_utf8_lossy(&self.message_text).to_string();
+                            self.reset();
+                            if !text.is_empty() {
+                                output.messages.push(text);
+                            }
+                        }
+                        ENQ => {
+                            // Sender restarted the establishment phase
+                            self.reset();
+                            self.state = ReceiverState::Receiving;
+                            output.replies.push(ACK);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    fn accept_frame(&mut self) -> u8 {
+        let raw = std::mem::take(&mut self.frame_buffer);
+
+        let frame = match decode_frame(&raw) {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::warn!("ASTM frame rejected: {}", e);
+                return NAK;
+            }
+        };
+
+        let previous_frame = (self.expected_frame + 7) % 8;
+
+        if frame.frame_number == self.expected_frame {
+            self.message_text.extend_from_slice(&frame.text);
+            self.expected_frame = (self.expected_frame + 1) % 8;
+            ACK
+        } else if frame.frame_number == previous_frame {
+            // Retransmission of a frame we already accepted (our ACK was lost)
+            ACK
+        } else {
+            tracing::warn!(
+                "ASTM frame out of sequence: expected {}, received {}",
+                self.expected_frame,
+                frame.frame_number
+            );
+            NAK
+        }
+    }
+}
+
+// ============================================================================
+// ASTM E1394 (LIS2-A2) Record Layer
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delimiters {
+    pub field: char,
+    pub repeat: char,
+    pub component: char,
+    pub escape: char,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            field: '|',
+            repeat: '\\',
+            component: '^',
+            escape: '&',
+        }
+    }
+}
+
+impl Delimiters {
+    /// Reads the delimiter definition from a header record (`H|\^&...`)
+    pub fn from_header(header: &str) -> Result<Self> {
+        let chars: Vec<char> = header.chars().take(5).collect();
+        if chars.len() < 5 || chars[0] != 'H' {
+            return Err(Error::InvalidInput("Message does not start with a header record".to_string()));
+        }
+
+        Ok(Self {
+            field: chars[1],
+            repeat: chars[2],
+            component: chars[3],
+            escape: chars[4],
+        })
+    }
+
+    /// Resolves the `&F&`, `&S&`, `&R&` and `&E&` escape sequences
+    pub fn unescape(&self, value: &str) -> String {
+        let e = self.escape;
+        value
+            .replace(&format!("{e}F{e}"), &self.field.to_string())
+            .replace(&format!("{e}S{e}"), &self.component.to_string())
+            .replace(&format!("{e}R{e}"), &self.repeat.to_string())
+            .replace(&format!("{e}E{e}"), &self.escape.to_string())
+    }
+
+    pub fn escape(&self, value: &str) -> String {
+        let e = self.escape;
+        value
+            .replace(self.escape, &format!("{e}E{e}"))
+            .replace(self.field, &format!("{e}F{e}"))
+            .replace(self.component, &format!("{e}S{e}"))
+            .replace(self.repeat, &format!("{e}R{e}"))
+    }
+}
+
+/// A parsed record split into fields. `field(n)` uses the 1-based numbering of
+/// the standard, where field 1 is the record type identifier.
+#[derive(Debug, Clone)]
+pub struct RawRecord {
+    fields: Vec<String>,
+    delimiters: Delimiters,
+}
+
+impl RawRecord {
+    pub fn parse(line: &str, delimiters: Delimiters) -> Self {
+        let fields = if line.starts_with('H') {
+            // The header's second field is the delimiter definition itself
+            let mut fields = vec!["H".to_string(), line.chars().skip(1).take(4).collect()];
+            let rest: String = line.chars().skip(5).collect();
+            fields.extend(rest.split(delimiters.field).skip(1).map(str::to_string));
+            fields
+        } else {
+            line.split(delimiters.field).map(str::to_string).collect()
+        };
+
+        Self { fields, delimiters }
+    }
+
+    pub fn record_type(&self) -> char {
+        self.fields
+            .first()
+            .and_then(|f| f.chars().next())
+            .unwrap_or(' ')
+            .to_ascii_uppercase()
+    }
+
+    pub fn field(&self, n: usize) -> Option<String> {
+        self.fields
+            .get(n.saturating_sub(1))
+            .map(|f| self.delimiters.unescape(f.trim()))
+            .filter(|f| !f.is_empty())
+    }
+
+    pub fn components(&self, n: usize) -> Vec<String> {
+        self.fields
+            .get(n.saturating_sub(1))
+            .map(|f| {
+                f.split(self.delimiters.component)
+                    .map(|c| self.delimiters.unescape(c.trim()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn repeats(&self, n: usize) -> Vec<String> {
+        self.fields
+            .get(n.saturating_sub(1))
+            .map(|f| {
+                f.split(self.delimiters.repeat)
+                    .filter(|r| !r.trim().is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Extracts the local test code from a universal test ID (`^^^GLU^...`).
+/// The manufacturer's code lives in the fourth component; analyzers that do
+/// not follow the layout get their first non-empty component used instead.
+pub fn test_code_from_universal_id(universal_id: &str, delimiters: Delimiters) -> Option<String> {
+    let components: Vec<&str> = universal_id.split(delimiters.component).collect();
+
+    components
+        .get(3)
+        .filter(|c| !c.trim().is_empty())
+        .or_else(|| components.iter().find(|c| !c.trim().is_empty()))
+        .map(|c| delimiters.unescape(c.trim()))
+}
+
+/// Parses an ASTM `YYYYMMDD[HHMMSS]` timestamp
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    match value.len() {
+        14 => NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M%S").ok(),
+        12 => NaiveDateTime::parse_from_str(&format!("{}00", value), "%Y%m%d%H%M%S").ok(),
+        8 => chrono::NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AstmResult {
+    pub sample_barcode: String,
+    pub test_code: String,
+    pub value: String,
+    pub units: Option<String>,
+    pub reference_range: Option<String>,
+    pub abnormal_flags: Option<String>,
+    /// F = final, C = correction, P = preliminary, X = cannot be done ...
+    pub result_status: Option<String>,
+    pub operator_id: Option<String>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub instrument_identification: Option<String>,
+    pub comments: Vec<String>,
+}
+
+impl AstmResult {
+    /// Only final and corrected results are released to the LIS; results
+    /// the analyzer marks as previously transmitted (R) were already posted
+    pub fn is_reportable(&self) -> bool {
+        matches!(self.result_status.as_deref(), None | Some("F") | Some("C"))
+    }
+
+    /// A correction (C) replaces the result posted for the test before
+    pub fn is_correction(&self) -> bool {
+        self.result_status.as_deref() == Some("C")
+    }
+
+    /// What the analyzer reported beyond the value, as technician notes
+    pub fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if let Some(ref flags) = self.abnormal_flags {
+            notes.push(format!("Analyzer flags: {}", flags));
+        }
+        if let Some(ref range) = self.reference_range {
+            notes.push(format!("Analyzer reference range: {}", range));
+        }
+        if let Some(ref operator) = self.operator_id {
+            notes.push(format!("Operator: {}", operator));
+        }
+        if let Some(completed_at) = self.completed_at {
+            notes.push(format!("Completed at {}", completed_at.format("%Y-%m-%d %H:%M:%S")));
+        }
+        notes.extend(self.comments.iter().cloned());
+        notes
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AstmOrder {
+    pub specimen_id: String,
+    /// Q marks a quality control specimen
+    pub action_code: Option<String>,
+    pub results: Vec<AstmResult>,
+    pub comments: Vec<String>,
+}
+
+/// Host query (Q record): the analyzer asks for the tests to run on a specimen
+#[derive(Debug, Clone, Default)]
+pub struct AstmQuery {
+    pub specimen_ids: Vec<String>,
+    /// Requested test codes; empty when the analyzer asks for `ALL`
+    pub test_codes: Vec<String>,
+    /// O = request test orders, A = abort/cancel the last request
+    pub request_status: Option<String>,
+}
+
+impl AstmQuery {
+    pub fn is_cancellation(&self) -> bool {
+        self.request_status.as_deref() == Some("A")
+    }
+}
+
+/// Tests to download for one specimen in reply to a host query
+#[derive(Debug, Clone, Default)]
+pub struct AstmWorkOrder {
+    pub specimen_id: String,
+    pub patient_id: Option<String>,
+    pub patient_name: Option<String>,
+    pub test_codes: Vec<String>,
+    /// S = stat, R = routine
+    pub priority: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AstmMessage {
+    pub orders: Vec<AstmOrder>,
+    pub queries: Vec<AstmQuery>,
+    pub termination_code: Option<String>,
+}
+
+impl AstmMessage {
+    /// Parses the reassembled text of one transmission into its records.
+    pub fn parse(text: &str) -> Result<Self> {
+        let lines: Vec<&str> = text
+            .split(['\r', '\n'])
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+
+        let first = lines
+            .first()
+            .ok_or_else(|| Error::InvalidInput("Empty ASTM message".to_string()))?;
+        let delimiters = Delimiters::from_header(first)?;
+
+        let mut message = AstmMessage::default();
+        // Tracks which record a following C record annotates
+        let mut last_record_type = 'H';
+
+        for line in lines {
+            let record = RawRecord::parse(line, delimiters);
+
+            match record.record_type() {
+                // The header only defines the delimiters, and samples are
+                // matched by barcode rather than by patient
+                'H' | 'P' => {}
+                'O' => {
+                    let specimen_id = record
+                        .components(3)
+                        .into_iter()
+                        .find(|c| !c.is_empty())
+                        .ok_or_else(|| Error::InvalidInput("Order record without specimen ID".to_string()))?;
+
+                    message.orders.push(AstmOrder {
+                        specimen_id,
+                        action_code: record.field(12),
+                        results: Vec::new(),
+                        comments: Vec::new(),
+                    });
+                }
+                'R' => {
+                    let order = message.orders.last_mut().ok_or_else(|| {
+                        Error::InvalidInput("Result record without a preceding order record".to_string())
+                    })?;
+
+                    let universal_id = record.fields.get(2).cloned().unwrap_or_default();
+                    let test_code = test_code_from_universal_id(&universal_id, delimiters)
+                        .ok_or_else(|| Error::InvalidInput("Result record without test ID".to_string()))?;
+
+                    let value = record.components(4).into_iter().find(|c| !c.is_empty()).unwrap_or_default();
+
+                    order.results.push(AstmResult {
+                        sample_barcode: order.specimen_id.clone(),
+                        test_code,
+                        value,
+                        units: record.field(5),
+                        reference_range: Some(
+                            record.components(6).into_iter().filter(|c| !c.is_empty()).collect::<Vec<_>>().join("-"),
+                        )
+                        .filter(|range| !range.is_empty()),
+                        abnormal_flags: record.field(7),
+                        result_status: record.field(9),
+                        operator_id: record.field(11),
+                        completed_at: record.field(13).as_deref().and_then(parse_timestamp),
+                        instrument_identification: record.field(14),
+                        comments: Vec::new(),
+                    });
+                }
+                'C' => {
+                    let text = record
+                        .components(4)
+                        .into_iter()
+                        .filter(|c| !c.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+
+                    if let Some(order) = message.orders.last_mut() {
+                        match (last_record_type, order.results.last_mut()) {
+                            ('R', Some(result)) => result.comments.push(text),
+                            _ => order.comments.push(text),
+                        }
+                    }
+                    // C records keep annotating the record they follow
+                    continue;
+                }
+                'Q' => {
+                    // Starting range ID is `patient ID^specimen ID^...`
+                    let specimen_ids = record
+                        .repeats(3)
+                        .iter()
+                        .filter_map(|range| {
+                            let components: Vec<String> = range
+                                .split(delimiters.component)
+                                .map(|c| delimiters.unescape(c.trim()))
+                                .collect();
+                            components
+                                .get(1)
+                                .filter(|c| !c.is_empty())
+                                .or_else(|| components.iter().find(|c| !c.is_empty()))
+                                .cloned()
+                        })
+                        .collect();
+
+                    let test_codes = record
+                        .repeats(5)
+                        .iter()
+                        .filter_map(|id| test_code_from_universal_id(id, delimiters))
+                        .filter(|code| !code.eq_ignore_ascii_case("ALL"))
+                        .collect();
+
+                    message.queries.push(AstmQuery {
+                        specimen_ids,
+                        test_codes,
+                        request_status: record.field(13),
+                    });
+                }
+                'L' => {
+                    message.termination_code = record.field(3);
+                }
+                other => {
+                    tracing::debug!("Ignoring unsupported ASTM record type: {}", other);
+                }
+            }
+
+            last_record_type = record.record_type();
+        }
+
+        Ok(message)
+    }
+
+    /// Patient results; quality control runs stay on the analyzer
+    pub fn results(&self) -> impl Iterator<Item = &AstmResult> {
+        self.orders
+            .iter()
+            .filter(|o| o.action_code.as_deref() != Some("Q"))
+            .flat_map(|o| o.results.iter())
+    }
+}
+
+/// Builds the order message answering a host query. Each work order becomes a
+/// P/O pair with action code N (new); with no work orders the terminator
+/// carries code I (no information available).
+pub fn build_query_response(work_orders: &[AstmWorkOrder], timestamp: NaiveDateTime) -> Vec<String> {
+    let d = Delimiters::default();
+    let mut records = vec![format!(
+        "H{}{}{}{}|||LIS|||||||P|LIS2-A2|{}",
+        d.field,
+        d.repeat,
+        d.component,
+        d.escape,
+        timestamp.format("%Y%m%d%H%M%S")
+    )];
+
+    for (index, order) in work_orders.iter().enumerate() {
+        records.push(format!(
+            "P|{}|{}|||{}",
+            index + 1,
+            d.escape(order.patient_id.as_deref().unwrap_or_default()),
+            d.escape(order.patient_name.as_deref().unwrap_or_default()),
+        ));
+
+        let tests = order
+            .test_codes
+            .iter()
+            .map(|code| format!("{c}{c}{c}{}", d.escape(code), c = d.component))
+            .collect::<Vec<_>>()
+            .join(&d.repeat.to_string());
+
+        records.push(format!(
+            "O|1|{}||{}|{}||||||N||||||||||||||O",
+            d.escape(&order.specimen_id),
+            tests,
+            order.priority.as_deref().unwrap_or("R"),
+        ));
+    }
+
+    let termination = if work_orders.is_empty() { "I" } else { "N" };
+    records.push(format!("L|1|{}", termination));
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_MESSAGE: &str = concat!(
+        "H|\\^&|||Cobas^c311^1.0|||||||P|LIS2-A2|20250105093000\r",
+        "P|1|MRN0001|||Doe^Jane\r",
+        "O|1|BC123456||^^^GLU\\^^^CREA|R||20250105080000||||N\r",
+        "R|1|^^^GLU|5.4|mmol/L|3.9^6.1|N||F||TECH1||20250105092500|c311\r",
+        "C|1|I|Sample slightly lipemic|G\r",
+        "R|2|^^^CREA|130|umol/L|60^110|H||F||TECH1||20250105092600|c311\r",
+        "L|1|N\r",
+    );
+
+    #[test]
+    fn test_frame_round_trip() {
+        let text = b"H|\\^&\r";
+        let frame = encode_frame(1, text, true);
+        let decoded = decode_frame(&frame).unwrap();
+
+        assert_eq!(decoded.frame_number, 1);
+        assert_eq!(decoded.text, text.to_vec());
+        assert!(decoded.is_last);
+    }
+
+    #[test]
+    fn test_corrupted_frame_is_rejected() {
+        let mut frame = encode_frame(2, b"R|1|^^^GLU|5.4\r", true);
+        frame[5] = b'X';
+
+        assert!(decode_frame(&frame).is_err());
+    }
+
+    #[test]
+    fn test_long_record_is_split_into_intermediate_frames() {
+        let long_comment = format!("C|1|I|{}|G", "A".repeat(500));
+        let frames = encode_message(std::slice::from_ref(&long_comment));
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0][frames[0].len() - 5], ETB);
+        assert_eq!(frames[2][frames[2].len() - 5], ETX);
+
+        let mut receiver = Receiver::new();
+        let mut output = receiver.feed(&[ENQ]);
+        for frame in &frames {
+            output.replies.extend(receiver.feed(frame).replies);
+        }
+        output.messages.extend(receiver.feed(&[EOT]).messages);
+
+        assert!(output.replies.iter().all(|r| *r == ACK));
+        assert_eq!(output.messages, vec![format!("{}\r", long_comment)]);
+    }
+
+    #[test]
+    fn test_frame_numbers_wrap_after_seven() {
+        let records: Vec<String> = (0..10).map(|i| format!("C|{}|I|note|G", i)).collect();
+        let frames = encode_message(&records);
+
+        let numbers: Vec<u8> = frames.iter().map(|f| f[1] - b'0').collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4, 5, 6, 7, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_receiver_naks_out_of_sequence_and_acks_duplicates() {
+        let mut receiver = Receiver::new();
+        assert_eq!(receiver.feed(&[ENQ]).replies, vec![ACK]);
+
+        let first = encode_frame(1, b"H|\\^&\r", true);
+        assert_eq!(receiver.feed(&first).replies, vec![ACK]);
+
+        // Duplicate of the accepted frame is acknowledged but not appended
+        assert_eq!(receiver.feed(&first).replies, vec![ACK]);
+
+        // Skipping frame 2 is a sequence error
+        let skipped = encode_frame(3, b"L|1|N\r", true);
+        assert_eq!(receiver.feed(&skipped).replies, vec![NAK]);
+
+        let second = encode_frame(2, b"L|1|N\r", true);
+        assert_eq!(receiver.feed(&second).replies, vec![ACK]);
+
+        let output = receiver.feed(&[EOT]);
+        assert_eq!(output.messages, vec!["H|\\^&\rL|1|N\r".to_string()]);
+    }
+
+    #[test]
+    fn test_receiver_resets_on_an_oversized_frame() {
+        let mut receiver = Receiver::new();
+        assert_eq!(receiver.feed(&[ENQ]).replies, vec![ACK]);
+
+        let mut runaway = vec![STX];
+        runaway.extend(std::iter::repeat_n(b'1', MAX_FRAME_BYTES * 4));
+        let output = receiver.feed(&runaway);
+        assert!(output.replies.is_empty());
+        assert!(!receiver.is_receiving());
+        assert!(receiver.frame_buffer.is_empty());
+
+        // The sender starts over with a new establishment phase
+        assert_eq!(receiver.feed(&[ENQ]).replies, vec![ACK]);
+        assert_eq!(receiver.feed(&encode_frame(1, b"L|1|N\r", true)).replies, vec![ACK]);
+        assert_eq!(receiver.feed(&[EOT]).messages, vec!["L|1|N\r".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_message_records() {
+        let message = AstmMessage::parse(SAMPLE_MESSAGE).unwrap();
+
+        assert_eq!(message.orders.len(), 1);
+
+        let order = &message.orders[0];
+        assert_eq!(order.specimen_id, "BC123456");
+
+        let results: Vec<&AstmResult> = message.results().collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].sample_barcode, "BC123456");
+        assert_eq!(results[0].test_code, "GLU");
+        assert_eq!(results[0].value, "5.4");
+        assert_eq!(results[0].units.as_deref(), Some("mmol/L"));
+        assert_eq!(results[0].comments, vec!["Sample slightly lipemic".to_string()]);
+        assert_eq!(results[1].abnormal_flags.as_deref(), Some("H"));
+        assert!(results[1].is_reportable());
+        assert_eq!(
+            results[0].notes(),
+            vec![
+                "Analyzer flags: N".to_string(),
+                "Analyzer reference range: 3.9-6.1".to_string(),
+                "Operator: TECH1".to_string(),
+                "Completed at 2025-01-05 09:25:00".to_string(),
+                "Sample slightly lipemic".to_string(),
+            ]
+        );
+
+        let qc = AstmMessage::parse(&SAMPLE_MESSAGE.replace("||||N\r", "||||Q\r")).unwrap();
+        assert_eq!(qc.orders[0].results.len(), 2);
+        assert_eq!(qc.results().count(), 0);
+
+        let retransmitted = AstmResult { result_status: Some("R".to_string()), ..results[0].clone() };
+        assert!(!retransmitted.is_reportable());
+        let corrected = AstmResult { result_status: Some("C".to_string()), ..results[0].clone() };
+        assert!(corrected.is_reportable() && corrected.is_correction());
+        assert!(!results[0].is_correction());
+        assert_eq!(message.termination_code.as_deref(), Some("N"));
+    }
+
+    #[test]
+    fn test_parse_host_query_and_build_response() {
+        let message = AstmMessage::parse(
+            "H|\\^&|||Cobas^c311^1.0|||||||P|LIS2-A2|20250105093000\rQ|1|^BC0001\\^BC0002||^^^ALL||||||||O\rL|1|N\r"
+        ).unwrap();
+
+        assert_eq!(message.queries.len(), 1);
+        let query = &message.queries[0];
+        assert_eq!(query.specimen_ids, vec!["BC0001".to_string(), "BC0002".to_string()]);
+        assert!(query.test_codes.is_empty());
+        assert!(!query.is_cancellation());
+
+        let timestamp = parse_timestamp("20250105093100").unwrap();
+        let records = build_query_response(
+            &[AstmWorkOrder {
+                specimen_id: "BC0001".to_string(),
+                patient_id: Some("MRN0001".to_string()),
+                patient_name: None,
+                test_codes: vec!["GLU".to_string(), "CREA".to_string()],
+                priority: Some("S".to_string()),
+            }],
+            timestamp,
+        );
+
+        assert_eq!(records[1], "P|1|MRN0001|||");
+        assert_eq!(records[2], "O|1|BC0001||^^^GLU\\^^^CREA|S||||||N||||||||||||||O");
+        let response = AstmMessage::parse(&records.join("\r")).unwrap();
+        assert_eq!(response.orders.len(), 1);
+        assert_eq!(response.orders[0].specimen_id, "BC0001");
+
+        let empty = build_query_response(&[], timestamp);
+        assert_eq!(empty.last().map(String::as_str), Some("L|1|I"));
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let delimiters = Delimiters::default();
+        let raw = "A|B^C\\D&E";
+
+        assert_eq!(delimiters.unescape(&delimiters.escape(raw)), raw);
+    }
+}
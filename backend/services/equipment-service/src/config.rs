@@ -6,8 +6,16 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub astm_listener_enabled: bool,
+    pub astm_listener_port: u16,
+    pub hl7_query_listener_enabled: bool,
+    pub hl7_query_listener_port: u16,
+    pub sample_service_url: String,
+    pub order_service_url: String,
+    pub result_service_url: String,
 }
 
 impl Config {
@@ -16,8 +24,16 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8087)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("astm_listener_enabled", false)?
+            .set_default("astm_listener_port", 9087)?
+            .set_default("hl7_query_listener_enabled", false)?
+            .set_default("hl7_query_listener_port", 9088)?
+            .set_default("sample_service_url", "http://localhost:8083")?
+            .set_default("order_service_url", "http://localhost:8082")?
+            .set_default("result_service_url", "http://localhost:8084")?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -29,8 +45,16 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8087,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            astm_listener_enabled: false,
+            astm_listener_port: 9087,
+            hl7_query_listener_enabled: false,
+            hl7_query_listener_port: 9088,
+            sample_service_url: "http://localhost:8083".to_string(),
+            order_service_url: "http://localhost:8082".to_string(),
+            result_service_url: "http://localhost:8084".to_string(),
         }
     }
 }
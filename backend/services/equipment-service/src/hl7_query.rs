@@ -0,0 +1,228 @@
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+use common::error::Result;
+use common::tenant::Tenant;
+use infrastructure::hl7::{self, AckCode, Hl7Endpoint, Hl7Message, MllpDecoder, Segment};
+
+use crate::host_query::{HostQueryHandler, WorkOrder};
+use crate::service::EquipmentService;
+
+/// Builds the RSP^K11 answering a QBP^Q11 work order query: QAK reports OK or
+/// NF (no data found), followed by PID, SPM and one ORC/TQ1/OBR group per test.
+pub fn build_work_order_response(query: &Hl7Message, work_order: Option<&WorkOrder>) -> Hl7Message {
+    let msh = query.segment("MSH");
+    let get = |n: usize| msh.and_then(|m| m.field(n)).unwrap_or_default();
+
+    let endpoint = Hl7Endpoint {
+        sending_application: get(5),
+        sending_facility: get(6),
+        receiving_application: get(3),
+        receiving_facility: get(4),
+    };
+
+    let mut response = Hl7Message::new(&endpoint, "RSP", "K11", "RSP_K11");
+    response.push(
+        Segment::new("MSA")
+            .set(1, AckCode::Accept.as_str())
+            .set(2, &query.control_id().unwrap_or_default()),
+    );
+
+    let qpd = query.segment("QPD");
+    let query_tag = qpd.and_then(|q| q.field(2)).unwrap_or_default();
+    let query_name = qpd.and_then(|q| q.field(1)).unwrap_or_default();
+
+    response.push(
+        Segment::new("QAK")
+            .set(1, &query_tag)
+            .set(2, if work_order.is_some() { "OK" } else { "NF" })
+            .set(3, &query_name),
+    );
+    if let Some(qpd) = qpd {
+        response.push(qpd.clone());
+    }
+
+    if let Some(work_order) = work_order {
+        let priority = work_order.astm_priority();
+
+        response.push(
+            Segment::new("PID")
+                .set(1, "1")
+                .set(3, &work_order.sample.patient_id.to_string()),
+        );
+        response.push(
+            Segment::new("SPM")
+                .set(1, "1")
+                .set(2, &work_order.barcode),
+        );
+
+        for (index, item) in work_order.items.iter().enumerate() {
+            let placer = item.id.to_string();
+            response.push(Segment::new("ORC").set(1, "NW").set(2, &placer));
+            response.push(Segment::new("TQ1").set(1, "1").set(9, priority));
+            response.push(
+                Segment::new("OBR")
+                    .set(1, &(index + 1).to_string())
+                    .set(2, &placer)
+                    .set_components(4, &[&item.test_code, &item.test_name, "L"]),
+            );
+        }
+    }
+
+    response
+}
+
+/// Handles one inbound message and returns the encoded reply
+pub async fn handle_query_message<Q: HostQueryHandler>(
+    raw: &str,
+    instrument_id: Option<Uuid>,
+    queries: &Q,
+) -> Result<String> {
+    let message = Hl7Message::parse(raw)?;
+
+    if message.message_type().as_deref() != Some("QBP^Q11") {
+        return Ok(message
+            .build_ack(AckCode::Reject, Some("Unsupported message type"), None)
+            .encode());
+    }
+
+    let barcode = match message.segment("QPD").and_then(|qpd| qpd.component(3, 1)) {
+        Some(barcode) => barcode,
+        None => {
+            return Ok(message
+                .build_ack(AckCode::Error, Some("QPD-3 specimen ID is required"), None)
+                .encode())
+        }
+    };
+
+    match queries.find_work_order(instrument_id, &barcode, &[]).await {
+        Ok(work_order) => Ok(build_work_order_response(&message, work_order.as_ref()).encode()),
+        Err(e) => {
+            tracing::error!("HL7 host query for sample {} failed: {}", barcode, e);
+            Ok(message
+                .build_ack(AckCode::Error, Some("Work order query failed"), Some(&e.to_string()))
+                .encode())
+        }
+    }
+}
+
+// ============================================================================
+// MLLP Listener
+// ============================================================================
+
+pub struct Hl7QueryListener<Q: HostQueryHandler> {
+    bind_addr: String,
+    equipment_service: EquipmentService,
+    queries: Arc<Q>,
+}
+
+impl<Q: HostQueryHandler + 'static> Hl7QueryListener<Q> {
+    pub fn new(bind_addr: String, equipment_service: EquipmentService, queries: Q) -> Self {
+        Self {
+            bind_addr,
+            equipment_service,
+            queries: Arc::new(queries),
+        }
+    }
+
+    /// Accepts HL7 analyzer connections, identifying the instrument by its
+    /// address the same way the ASTM listener does.
+    pub async fn run(self) -> Result<()> {
+        let listener = TcpListener::bind(&self.bind_addr).await?;
+        tracing::info!("HL7 host query listener started on {}", self.bind_addr);
+
+        loop {
+            let (mut stream, peer) = listener.accept().await?;
+            let peer_ip = peer.ip().to_string();
+
+            // Instruments are known by address only; the connection then works
+            // for the organization that owns the instrument
+            let lookup = self.equipment_service.get_interfaced_equipment_by_ip(&peer_ip);
+            let equipment = match Tenant::System.scope(lookup).await {
+                Ok(equipment) => equipment,
+                Err(e) => {
+                    tracing::warn!("Rejected HL7 instrument connection from {}: {}", peer_ip, e);
+                    continue;
+                }
+            };
+
+            let queries = self.queries.clone();
+            let tenant = Tenant::Organization(equipment.organization_id);
+            tokio::spawn(tenant.scope(async move {
+                let mut decoder = MllpDecoder::new();
+                let mut pending = Vec::new();
+
+                loop {
+                    let raw = match hl7::read_mllp_message(&mut stream, &mut decoder, &mut pending).await {
+                        Ok(Some(raw)) => raw,
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::error!("Instrument {} HL7 connection error: {}", equipment.equipment_code, e);
+                            break;
+                        }
+                    };
+
+                    let reply = match handle_query_message(&raw, Some(equipment.id), queries.as_ref()).await {
+                        Ok(reply) => reply,
+                        Err(e) => {
+                            tracing::error!("Unreadable HL7 message from {}: {}", equipment.equipment_code, e);
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = hl7::write_mllp_message(&mut stream, &reply).await {
+                        tracing::error!("Failed to reply to {}: {}", equipment.equipment_code, e);
+                        break;
+                    }
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lis_client::{OrderItemData, SampleData};
+
+    const QUERY: &str = "MSH|^~\\&|ANALYZER|LAB|LIS|LAB|20250105093000||QBP^Q11^QBP_Q11|Q0001|P|2.5.1\r\
+QPD|WOS^Work Order Step^IHE_LABTF|TAG01|BC0001\r\
+RCP|I||R\r";
+
+    #[test]
+    fn test_work_order_response_lists_assigned_tests() {
+        let query = Hl7Message::parse(QUERY).unwrap();
+        let work_order = WorkOrder {
+            sample: SampleData {
+                id: Uuid::new_v4(),
+                sample_id: "S-0001".to_string(),
+                organization_id: Uuid::new_v4(),
+                order_id: Uuid::new_v4(),
+                patient_id: Uuid::new_v4(),
+                is_rejected: false,
+                priority: "ROUTINE".to_string(),
+            },
+            barcode: "BC0001".to_string(),
+            items: vec![OrderItemData {
+                id: Uuid::new_v4(),
+                test_id: Some(Uuid::new_v4()),
+                test_code: "GLU".to_string(),
+                test_name: "Glucose".to_string(),
+                item_status: "PENDING".to_string(),
+            }],
+        };
+
+        let response = Hl7Message::parse(&build_work_order_response(&query, Some(&work_order)).encode()).unwrap();
+
+        assert_eq!(response.message_type().as_deref(), Some("RSP^K11"));
+        assert_eq!(response.segment("MSA").and_then(|s| s.field(2)).as_deref(), Some("Q0001"));
+        assert_eq!(response.segment("QAK").and_then(|s| s.field(2)).as_deref(), Some("OK"));
+        assert_eq!(response.segment("SPM").and_then(|s| s.field(2)).as_deref(), Some("BC0001"));
+        assert_eq!(response.segment("OBR").and_then(|s| s.component(4, 1)).as_deref(), Some("GLU"));
+        assert_eq!(response.segment("TQ1").and_then(|s| s.field(9)).as_deref(), Some("R"));
+
+        let not_found = build_work_order_response(&query, None);
+        assert_eq!(not_found.segment("QAK").and_then(|s| s.field(2)).as_deref(), Some("NF"));
+        assert!(not_found.segment("OBR").is_none());
+    }
+}
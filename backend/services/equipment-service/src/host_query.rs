@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::future::Future;
+use uuid::Uuid;
+use common::error::{Error, Result};
+
+use crate::astm::AstmWorkOrder;
+use crate::lis_client::{LisClient, OrderItemData, SampleData};
+use crate::service::EquipmentService;
+
+const CUSTODY_ACTION_DOWNLOAD: &str = "INSTRUMENT_WORKLIST_DOWNLOAD";
+
+/// Pending tests for one specimen, answered to an analyzer host query
+#[derive(Debug, Clone)]
+pub struct WorkOrder {
+    pub sample: SampleData,
+    pub barcode: String,
+    pub items: Vec<OrderItemData>,
+}
+
+impl WorkOrder {
+    /// ASTM priority: S = stat, A = as soon as possible, R = routine
+    pub fn astm_priority(&self) -> &'static str {
+        match self.sample.priority.as_str() {
+            "STAT" => "S",
+            "URGENT" => "A",
+            _ => "R",
+        }
+    }
+
+    pub fn to_astm(&self) -> AstmWorkOrder {
+        AstmWorkOrder {
+            specimen_id: self.barcode.clone(),
+            patient_id: Some(self.sample.patient_id.to_string()),
+            patient_name: None,
+            test_codes: self.items.iter().map(|item| item.test_code.clone()).collect(),
+            priority: Some(self.astm_priority().to_string()),
+        }
+    }
+}
+
+/// Selects the order items an instrument should run: tests assigned to it
+/// that are still outstanding, optionally narrowed to the codes it asked for.
+pub fn select_items(
+    items: Vec<OrderItemData>,
+    assigned_test_ids: &HashSet<Uuid>,
+    requested_codes: &[String],
+) -> Vec<OrderItemData> {
+    items
+        .into_iter()
+        .filter(|item| !matches!(item.item_status.as_str(), "COMPLETED" | "CANCELLED"))
+        .filter(|item| item.test_id.map(|id| assigned_test_ids.contains(&id)).unwrap_or(false))
+        .filter(|item| {
+            requested_codes.is_empty()
+                || requested_codes.iter().any(|code| code.eq_ignore_ascii_case(&item.test_code))
+        })
+        .collect()
+}
+
+// ============================================================================
+// Host Query Handler
+// ============================================================================
+
+/// Answers analyzer host queries with the tests to run on a specimen
+pub trait HostQueryHandler: Send + Sync {
+    fn find_work_order(
+        &self,
+        instrument_id: Option<Uuid>,
+        barcode: &str,
+        requested_codes: &[String],
+    ) -> impl Future<Output = Result<Option<WorkOrder>>> + Send;
+}
+
+/// Builds worklists from the sample and order services and records every
+/// download in the sample's chain of custody.
+#[derive(Clone)]
+pub struct HostQueryResponder {
+    lis_client: LisClient,
+    equipment_service: EquipmentService,
+}
+
+impl HostQueryResponder {
+    pub fn new(lis_client: LisClient, equipment_service: EquipmentService) -> Self {
+        Self {
+            lis_client,
+            equipment_service,
+        }
+    }
+}
+
+impl HostQueryHandler for HostQueryResponder {
+    async fn find_work_order(
+        &self,
+        instrument_id: Option<Uuid>,
+        barcode: &str,
+        requested_codes: &[String],
+    ) -> Result<Option<WorkOrder>> {
+        let equipment_id = instrument_id
+            .ok_or_else(|| Error::Validation("Host query from an unidentified instrument".to_string()))?;
+        let equipment = self.equipment_service.get_equipment(equipment_id).await?;
+
+        let sample = match self.lis_client.find_sample_by_barcode(barcode).await? {
+            Some(sample) => sample,
+            None => {
+                tracing::info!("Host query for unknown barcode {} from {}", barcode, equipment.equipment_code);
+                return Ok(None);
+            }
+        };
+
+        if sample.is_rejected {
+            tracing::warn!("Host query for rejected sample {} from {}", sample.sample_id, equipment.equipment_code);
+            return Ok(None);
+        }
+
+        let assigned_test_ids: HashSet<Uuid> = self.equipment_service
+            .list_test_assignments(equipment_id)
+            .await?
+            .into_iter()
+            .map(|assignment| assignment.test_id)
+            .collect();
+
+        let items = self.lis_client.get_order_items(sample.order_id).await?;
+        let items = select_items(items, &assigned_test_ids, requested_codes);
+
+        if items.is_empty() {
+            tracing::info!("No tests for {} on sample {}", equipment.equipment_code, sample.sample_id);
+            return Ok(None);
+        }
+
+        // The download is only sent once it is on record
+        let location = format!("{} ({})", equipment.equipment_name, equipment.equipment_code);
+        self.lis_client
+            .record_custody_event(sample.id, equipment.id, CUSTODY_ACTION_DOWNLOAD, &location)
+            .await?;
+
+        tracing::info!(
+            "Worklist for sample {} downloaded to {}: {}",
+            sample.sample_id,
+            equipment.equipment_code,
+            items.iter().map(|i| i.test_code.as_str()).collect::<Vec<_>>().join(", ")
+        );
+
+        Ok(Some(WorkOrder {
+            sample,
+            barcode: barcode.to_string(),
+            items,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(code: &str, test_id: Uuid, status: &str) -> OrderItemData {
+        OrderItemData {
+            id: Uuid::new_v4(),
+            test_id: Some(test_id),
+            test_code: code.to_string(),
+            test_name: code.to_string(),
+            item_status: status.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_items_filters_by_assignment_status_and_request() {
+        let glucose = Uuid::new_v4();
+        let creatinine = Uuid::new_v4();
+        let hba1c = Uuid::new_v4();
+        let assigned: HashSet<Uuid> = [glucose, creatinine].into_iter().collect();
+
+        let items = vec![
+            item("GLU", glucose, "PENDING"),
+            item("CREA", creatinine, "COMPLETED"),
+            item("HBA1C", hba1c, "PENDING"),
+        ];
+
+        let selected = select_items(items.clone(), &assigned, &[]);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].test_code, "GLU");
+
+        let selected = select_items(items, &assigned, &["crea".to_string()]);
+        assert!(selected.is_empty());
+    }
+}
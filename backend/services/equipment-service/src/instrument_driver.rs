@@ -0,0 +1,592 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+use common::error::{Error, Result};
+use common::tenant::Tenant;
+
+use crate::astm::{self, AstmMessage, AstmResult, Receiver};
+use crate::host_query::HostQueryHandler;
+use crate::lis_client::{CreateResultInput, CreatedResultData, LisClient, SampleData};
+use crate::service::EquipmentService;
+
+/// E1381 receiver timeout: a sender that goes quiet mid-message for longer
+/// than this loses the partial transmission.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// E1381 sender timeout when waiting for ACK/NAK
+const REPLY_TIMEOUT: Duration = Duration::from_secs(15);
+
+const ENTRY_METHOD: &str = "INSTRUMENT_INTERFACE";
+
+const CORRECTION_REASON: &str = "Corrected by the analyzer (ASTM result status C)";
+
+// ============================================================================
+// Result Sink
+// ============================================================================
+
+/// Destination for results received from an analyzer
+pub trait ResultSink: Send + Sync {
+    fn submit_result(
+        &self,
+        instrument_id: Option<Uuid>,
+        result: &AstmResult,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Forwards analyzer results to result-service, resolving the sample by its
+/// barcode and the order item by the analyzer's test code.
+#[derive(Clone)]
+pub struct InterfaceResultSink {
+    lis_client: LisClient,
+    equipment_service: EquipmentService,
+}
+
+impl InterfaceResultSink {
+    pub fn new(lis_client: LisClient, equipment_service: EquipmentService) -> Self {
+        Self {
+            lis_client,
+            equipment_service,
+        }
+    }
+}
+
+impl InterfaceResultSink {
+    /// Replaces the result posted earlier for the test with a corrected
+    /// value: approved results through the correction workflow, others by
+    /// updating them. Returns `None` when nothing was posted before, so the
+    /// correction is recorded as a new result.
+    async fn submit_correction(
+        &self,
+        sample: &SampleData,
+        test_id: Uuid,
+        result: &AstmResult,
+        technician_notes: Option<&str>,
+    ) -> Result<Option<CreatedResultData>> {
+        let posted = self.lis_client
+            .get_sample_results(sample.organization_id, sample.id)
+            .await?
+            .into_iter()
+            .filter(|posted| posted.test_id == test_id)
+            .max_by_key(|posted| posted.created_at);
+        let Some(posted) = posted else {
+            return Ok(None);
+        };
+
+        let corrected = if posted.approval_date.is_some() {
+            self.lis_client
+                .correct_result(sample.organization_id, posted.id, &result.value, CORRECTION_REASON)
+                .await?
+        } else {
+            self.lis_client
+                .update_result(
+                    sample.organization_id,
+                    posted.id,
+                    &result.value,
+                    result.units.as_deref(),
+                    technician_notes,
+                )
+                .await?
+        };
+
+        Ok(Some(corrected))
+    }
+}
+
+impl ResultSink for InterfaceResultSink {
+    async fn submit_result(&self, instrument_id: Option<Uuid>, result: &AstmResult) -> Result<()> {
+        if !result.is_reportable() {
+            tracing::info!(
+                "Skipping non-final result {} for sample {} (status {:?})",
+                result.test_code,
+                result.sample_barcode,
+                result.result_status
+            );
+            return Ok(());
+        }
+
+        let sample = self.lis_client
+            .find_sample_by_barcode(&result.sample_barcode)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Sample not found for barcode: {}", result.sample_barcode)))?;
+
+        if sample.is_rejected {
+            return Err(Error::Validation(format!(
+                "Sample {} is rejected; result {} discarded",
+                sample.sample_id, result.test_code
+            )));
+        }
+
+        let items = self.lis_client.get_order_items(sample.order_id).await?;
+        let item = items
+            .into_iter()
+            .find(|item| item.test_code.eq_ignore_ascii_case(&result.test_code))
+            .ok_or_else(|| Error::NotFound(format!(
+                "Test {} is not ordered for sample {}",
+                result.test_code, result.sample_barcode
+            )))?;
+        let test_id = item.test_id.ok_or_else(|| Error::Validation(format!(
+            "Order item {} has no test assigned",
+            item.id
+        )))?;
+
+        let notes = result.notes();
+        let technician_notes = if notes.is_empty() { None } else { Some(notes.join("; ")) };
+
+        if result.is_correction() {
+            if let Some(corrected) = self.submit_correction(&sample, test_id, result, technician_notes.as_deref()).await? {
+                tracing::info!(
+                    "Instrument correction {} recorded for sample {} ({})",
+                    corrected.result_number,
+                    result.sample_barcode,
+                    result.test_code
+                );
+                return Ok(());
+            }
+        }
+
+        let created = self.lis_client
+            .create_result(sample.organization_id, CreateResultInput {
+                order_id: sample.order_id,
+                order_item_id: item.id,
+                test_id,
+                sample_id: sample.id,
+                result_value: result.value.clone(),
+                result_unit: result.units.clone(),
+                entry_method: ENTRY_METHOD.to_string(),
+                instrument_id,
+                run_number: result.instrument_identification.clone(),
+                technician_notes,
+            })
+            .await?;
+
+        if let Some(equipment_id) = instrument_id {
+            self.equipment_service.increment_test_counter(equipment_id).await?;
+        }
+
+        tracing::info!(
+            "Instrument result {} recorded for sample {} ({})",
+            created.result_number,
+            result.sample_barcode,
+            result.test_code
+        );
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Connection Handling
+// ============================================================================
+
+/// Answers the host queries of a received message by turning the line
+/// around and sending the matching order records.
+async fn respond_to_queries<S, Q>(stream: &mut S, instrument_id: Option<Uuid>, message: &AstmMessage, queries: &Q) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    Q: HostQueryHandler,
+{
+    if message.queries.iter().all(|q| q.is_cancellation()) {
+        return Ok(());
+    }
+
+    let mut work_orders = Vec::new();
+
+    for query in message.queries.iter().filter(|q| !q.is_cancellation()) {
+        for barcode in &query.specimen_ids {
+            match queries.find_work_order(instrument_id, barcode, &query.test_codes).await {
+                Ok(Some(work_order)) => work_orders.push(work_order.to_astm()),
+                Ok(None) => {}
+                Err(e) => tracing::error!("Host query for sample {} failed: {}", barcode, e),
+            }
+        }
+    }
+
+    let records = astm::build_query_response(&work_orders, chrono::Utc::now().naive_utc());
+    send_message(stream, &records).await
+}
+
+/// Runs the receiver side of an analyzer connection until it is closed,
+/// answering host queries in between. Returns the number of results accepted
+/// by the sink.
+pub async fn handle_connection<S, K, Q>(
+    mut stream: S,
+    instrument_id: Option<Uuid>,
+    sink: &K,
+    queries: &Q,
+) -> Result<usize>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    K: ResultSink,
+    Q: HostQueryHandler,
+{
+    let mut receiver = Receiver::new();
+    let mut buffer = [0u8; 4096];
+    let mut submitted = 0;
+
+    loop {
+        let read = if receiver.is_receiving() {
+            match tokio::time::timeout(RECEIVE_TIMEOUT, stream.read(&mut buffer)).await {
+                Ok(read) => read?,
+                Err(_) => {
+                    tracing::warn!("ASTM receive timeout; discarding partial message");
+                    receiver.reset();
+                    continue;
+                }
+            }
+        } else {
+            stream.read(&mut buffer).await?
+        };
+
+        if read == 0 {
+            return Ok(submitted);
+        }
+
+        let output = receiver.feed(&buffer[..read]);
+
+        if !output.replies.is_empty() {
+            stream.write_all(&output.replies).await?;
+            stream.flush().await?;
+        }
+
+        for text in output.messages {
+            let message = match AstmMessage::parse(&text) {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::error!("Failed to parse ASTM message: {}", e);
+                    continue;
+                }
+            };
+
+            for result in message.results() {
+                match sink.submit_result(instrument_id, result).await {
+                    Ok(()) => submitted += 1,
+                    Err(e) => tracing::error!(
+                        "Failed to record result {} for sample {}: {}",
+                        result.test_code,
+                        result.sample_barcode,
+                        e
+                    ),
+                }
+            }
+
+            if !message.queries.is_empty() {
+                if let Err(e) = respond_to_queries(&mut stream, instrument_id, &message, queries).await {
+                    tracing::error!("Failed to send host query response: {}", e);
+                }
+            }
+        }
+    }
+}
+
+async fn read_reply<S: AsyncRead + Unpin>(stream: &mut S) -> Result<u8> {
+    let mut reply = [0u8; 1];
+    match tokio::time::timeout(REPLY_TIMEOUT, stream.read_exact(&mut reply)).await {
+        Ok(result) => {
+            result?;
+            Ok(reply[0])
+        }
+        Err(_) => Err(Error::ExternalService("Timed out waiting for instrument reply".to_string())),
+    }
+}
+
+/// Sender side of E1381: transmits the records as one message, retransmitting
+/// frames that are NAKed up to `MAX_RETRANSMITS` times.
+pub async fn send_message<S>(stream: &mut S, records: &[String]) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(&[astm::ENQ]).await?;
+    stream.flush().await?;
+
+    if read_reply(stream).await? != astm::ACK {
+        return Err(Error::ExternalService("Instrument refused establishment (line busy)".to_string()));
+    }
+
+    for frame in astm::encode_message(records) {
+        let mut attempts = 0;
+
+        loop {
+            stream.write_all(&frame).await?;
+            stream.flush().await?;
+
+            match read_reply(stream).await? {
+                // EOT during transfer is a receiver interrupt; the frame was accepted
+                astm::ACK | astm::EOT => break,
+                _ => {
+                    attempts += 1;
+                    if attempts > astm::MAX_RETRANSMITS {
+                        stream.write_all(&[astm::EOT]).await?;
+                        return Err(Error::ExternalService(
+                            "Frame rejected too many times; transmission aborted".to_string()
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    stream.write_all(&[astm::EOT]).await?;
+    stream.flush().await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TCP Listener
+// ============================================================================
+
+pub struct AstmListener<K: ResultSink, Q: HostQueryHandler> {
+    bind_addr: String,
+    equipment_service: EquipmentService,
+    sink: Arc<K>,
+    queries: Arc<Q>,
+}
+
+impl<K: ResultSink + 'static, Q: HostQueryHandler + 'static> AstmListener<K, Q> {
+    pub fn new(bind_addr: String, equipment_service: EquipmentService, sink: K, queries: Q) -> Self {
+        Self {
+            bind_addr,
+            equipment_service,
+            sink: Arc::new(sink),
+            queries: Arc::new(queries),
+        }
+    }
+
+    /// Accepts analyzer connections. Only peers registered as equipment with
+    /// LIS integration enabled are served; everyone else is disconnected.
+    pub async fn run(self) -> Result<()> {
+        let listener = TcpListener::bind(&self.bind_addr).await?;
+        tracing::info!("ASTM instrument listener started on {}", self.bind_addr);
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let peer_ip = peer.ip().to_string();
+
+            // Instruments are known by address only; the connection then works
+            // for the organization that owns the instrument
+            let lookup = self.equipment_service.get_interfaced_equipment_by_ip(&peer_ip);
+            let equipment = match Tenant::System.scope(lookup).await {
+                Ok(equipment) => equipment,
+                Err(e) => {
+                    tracing::warn!("Rejected instrument connection from {}: {}", peer_ip, e);
+                    continue;
+                }
+            };
+
+            tracing::info!(
+                "Instrument connected: {} ({}) from {}",
+                equipment.equipment_name,
+                equipment.equipment_code,
+                peer_ip
+            );
+
+            let sink = self.sink.clone();
+            let queries = self.queries.clone();
+            let tenant = Tenant::Organization(equipment.organization_id);
+            tokio::spawn(tenant.scope(async move {
+                match handle_connection(stream, Some(equipment.id), sink.as_ref(), queries.as_ref()).await {
+                    Ok(count) => tracing::info!(
+                        "Instrument {} disconnected after {} results",
+                        equipment.equipment_code,
+                        count
+                    ),
+                    Err(e) => tracing::error!(
+                        "Instrument {} connection error: {}",
+                        equipment.equipment_code,
+                        e
+                    ),
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::net::TcpStream;
+    use crate::host_query::WorkOrder;
+    use crate::lis_client::{OrderItemData, SampleData};
+
+    #[derive(Default)]
+    struct RecordingSink {
+        results: Mutex<Vec<AstmResult>>,
+    }
+
+    impl ResultSink for RecordingSink {
+        async fn submit_result(&self, _instrument_id: Option<Uuid>, result: &AstmResult) -> Result<()> {
+            self.results.lock().unwrap().push(result.clone());
+            Ok(())
+        }
+    }
+
+    /// Knows a single sample, BC0001, with glucose and sodium pending
+    struct FixedWorklist;
+
+    impl HostQueryHandler for FixedWorklist {
+        async fn find_work_order(
+            &self,
+            _instrument_id: Option<Uuid>,
+            barcode: &str,
+            _requested_codes: &[String],
+        ) -> Result<Option<WorkOrder>> {
+            if barcode != "BC0001" {
+                return Ok(None);
+            }
+
+            let item = |code: &str| OrderItemData {
+                id: Uuid::new_v4(),
+                test_id: Some(Uuid::new_v4()),
+                test_code: code.to_string(),
+                test_name: code.to_string(),
+                item_status: "PENDING".to_string(),
+            };
+
+            Ok(Some(WorkOrder {
+                sample: SampleData {
+                    id: Uuid::new_v4(),
+                    sample_id: "S-0001".to_string(),
+                    organization_id: Uuid::new_v4(),
+                    order_id: Uuid::new_v4(),
+                    patient_id: Uuid::new_v4(),
+                    is_rejected: false,
+                    priority: "STAT".to_string(),
+                },
+                barcode: barcode.to_string(),
+                items: vec![item("GLU"), item("NA")],
+            }))
+        }
+    }
+
+    fn analyzer_records() -> Vec<String> {
+        vec![
+            "H|\\^&|||SimAnalyzer^1.0|||||||P|LIS2-A2|20250105093000".to_string(),
+            "P|1|MRN0001".to_string(),
+            "O|1|BC0001||^^^GLU\\^^^NA|R".to_string(),
+            "R|1|^^^GLU|5.4|mmol/L|3.9^6.1|N||F".to_string(),
+            format!("C|1|I|{}|G", "Long instrument comment ".repeat(20)),
+            "R|2|^^^NA|141|mmol/L|135^145|N||F".to_string(),
+            "O|2|BC0002||^^^GLU|R".to_string(),
+            "R|1|^^^GLU|12.9|mmol/L|3.9^6.1|H||F".to_string(),
+            "L|1|N".to_string(),
+        ]
+    }
+
+    async fn start_lis(sink: Arc<RecordingSink>) -> (String, tokio::task::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(stream, None, sink.as_ref(), &FixedWorklist).await.unwrap()
+        });
+
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_simulated_analyzer_multi_frame_round_trip() {
+        let sink = Arc::new(RecordingSink::default());
+        let (addr, handle) = start_lis(sink.clone()).await;
+
+        let mut analyzer = TcpStream::connect(&addr).await.unwrap();
+        send_message(&mut analyzer, &analyzer_records()).await.unwrap();
+        drop(analyzer);
+
+        assert_eq!(handle.await.unwrap(), 3);
+
+        let results = sink.results.lock().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].sample_barcode, "BC0001");
+        assert_eq!(results[0].test_code, "GLU");
+        assert_eq!(results[0].comments.len(), 1);
+        assert!(results[0].comments[0].len() > astm::MAX_FRAME_TEXT);
+        assert_eq!(results[1].test_code, "NA");
+        assert_eq!(results[1].value, "141");
+        assert_eq!(results[2].sample_barcode, "BC0002");
+        assert_eq!(results[2].abnormal_flags.as_deref(), Some("H"));
+    }
+
+    #[tokio::test]
+    async fn test_simulated_analyzer_retransmits_after_nak() {
+        let sink = Arc::new(RecordingSink::default());
+        let (addr, handle) = start_lis(sink.clone()).await;
+
+        let mut analyzer = TcpStream::connect(&addr).await.unwrap();
+        let frames = astm::encode_message(&analyzer_records()[..4]
+            .iter()
+            .cloned()
+            .chain(std::iter::once("L|1|N".to_string()))
+            .collect::<Vec<_>>());
+
+        analyzer.write_all(&[astm::ENQ]).await.unwrap();
+        assert_eq!(read_reply(&mut analyzer).await.unwrap(), astm::ACK);
+
+        for (i, frame) in frames.iter().enumerate() {
+            if i == 2 {
+                // Line noise flips a byte; the LIS must NAK the damaged frame
+                let mut corrupted = frame.clone();
+                corrupted[4] ^= 0x01;
+                analyzer.write_all(&corrupted).await.unwrap();
+                assert_eq!(read_reply(&mut analyzer).await.unwrap(), astm::NAK);
+            }
+
+            analyzer.write_all(frame).await.unwrap();
+            assert_eq!(read_reply(&mut analyzer).await.unwrap(), astm::ACK);
+
+            if i == 3 {
+                // Our ACK got lost: the retransmitted frame is ACKed but not duplicated
+                analyzer.write_all(frame).await.unwrap();
+                assert_eq!(read_reply(&mut analyzer).await.unwrap(), astm::ACK);
+            }
+        }
+
+        analyzer.write_all(&[astm::EOT]).await.unwrap();
+        drop(analyzer);
+
+        assert_eq!(handle.await.unwrap(), 1);
+
+        let results = sink.results.lock().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sample_barcode, "BC0001");
+        assert_eq!(results[0].value, "5.4");
+    }
+
+    #[tokio::test]
+    async fn test_simulated_analyzer_host_query_download() {
+        let sink = Arc::new(RecordingSink::default());
+        let (addr, handle) = start_lis(sink.clone()).await;
+
+        let mut analyzer = TcpStream::connect(&addr).await.unwrap();
+        send_message(&mut analyzer, &[
+            "H|\\^&|||SimAnalyzer^1.0|||||||P|LIS2-A2|20250105093000".to_string(),
+            "Q|1|^BC0001\\^BC9999||^^^ALL||||||||O".to_string(),
+            "L|1|N".to_string(),
+        ]).await.unwrap();
+
+        // The LIS turns the line around and sends the worklist
+        let mut receiver = Receiver::new();
+        let mut buffer = [0u8; 1024];
+        let text = loop {
+            let read = analyzer.read(&mut buffer).await.unwrap();
+            assert!(read > 0, "LIS closed the connection without answering");
+            let output = receiver.feed(&buffer[..read]);
+            analyzer.write_all(&output.replies).await.unwrap();
+            if let Some(message) = output.messages.into_iter().next() {
+                break message;
+            }
+        };
+        drop(analyzer);
+        let response = AstmMessage::parse(&text).unwrap();
+
+        assert_eq!(handle.await.unwrap(), 0);
+        assert_eq!(response.orders.len(), 1);
+        assert_eq!(response.orders[0].specimen_id, "BC0001");
+        assert!(text.contains("O|1|BC0001||^^^GLU\\^^^NA|S|"));
+        assert_eq!(response.termination_code.as_deref(), Some("N"));
+    }
+}
@@ -0,0 +1,426 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for the sample, order and result services used by the instrument
+/// interface to turn analyzer output into LIS results.
+#[derive(Clone)]
+pub struct LisClient {
+    sample_service_url: String,
+    order_service_url: String,
+    result_service_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampleByBarcodeResponse {
+    #[serde(rename = "sampleByBarcode")]
+    sample_by_barcode: Option<SampleData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SampleData {
+    pub id: Uuid,
+    #[serde(rename = "sampleId")]
+    pub sample_id: String,
+    #[serde(rename = "organizationId")]
+    pub organization_id: Uuid,
+    #[serde(rename = "orderId")]
+    pub order_id: Uuid,
+    #[serde(rename = "patientId")]
+    pub patient_id: Uuid,
+    #[serde(rename = "isRejected")]
+    pub is_rejected: bool,
+    /// STAT, URGENT or ROUTINE
+    pub priority: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderItemsResponse {
+    #[serde(rename = "orderItems")]
+    order_items: Vec<OrderItemData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderItemData {
+    pub id: Uuid,
+    #[serde(rename = "testId")]
+    pub test_id: Option<Uuid>,
+    #[serde(rename = "testCode")]
+    pub test_code: String,
+    #[serde(rename = "testName")]
+    pub test_name: String,
+    #[serde(rename = "itemStatus")]
+    pub item_status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateResultResponse {
+    #[serde(rename = "createResult")]
+    create_result: CreatedResultData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedResultData {
+    #[serde(rename = "resultNumber")]
+    pub result_number: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultsBySampleResponse {
+    #[serde(rename = "resultsBySample")]
+    results_by_sample: Vec<SampleResultData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorrectResultResponse {
+    #[serde(rename = "correctResult")]
+    correct_result: CreatedResultData,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateResultResponse {
+    #[serde(rename = "updateResult")]
+    update_result: CreatedResultData,
+}
+
+/// A result already recorded for a sample
+#[derive(Debug, Clone, Deserialize)]
+pub struct SampleResultData {
+    pub id: Uuid,
+    #[serde(rename = "testId")]
+    pub test_id: Uuid,
+    #[serde(rename = "approvalDate")]
+    pub approval_date: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Mirrors result-service's `CreateResultInput`
+#[derive(Debug, Clone)]
+pub struct CreateResultInput {
+    pub order_id: Uuid,
+    pub order_item_id: Uuid,
+    pub test_id: Uuid,
+    pub sample_id: Uuid,
+    pub result_value: String,
+    pub result_unit: Option<String>,
+    pub entry_method: String,
+    pub instrument_id: Option<Uuid>,
+    pub run_number: Option<String>,
+    pub technician_notes: Option<String>,
+}
+
+impl LisClient {
+    pub fn new(
+        sample_service_url: String,
+        order_service_url: String,
+        result_service_url: String,
+        jwt: JwtService,
+    ) -> Self {
+        Self {
+            sample_service_url,
+            order_service_url,
+            result_service_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Runs a GraphQL operation. Calls made for an organization carry a
+    /// service token so the target service can record who acted; other
+    /// calls carry the tenant of the instrument connection.
+    async fn execute<T: DeserializeOwned>(
+        &self,
+        service_name: &str,
+        base_url: &str,
+        organization_id: Option<Uuid>,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T> {
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables,
+        };
+
+        let url = format!("{}/graphql", base_url);
+
+        let token = match organization_id {
+            Some(organization_id) => Some(self.jwt.service_token(organization_id)?),
+            None => self.jwt.tenant_token().ok(),
+        };
+
+        let mut builder = self.client.post(&url);
+        if let Some(token) = token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response = builder
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call {}: {}", service_name, e);
+                Error::ExternalService(format!("Failed to connect to {}: {}", service_name, e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("{} returned error {}: {}", service_name, status, body);
+            return Err(Error::ExternalService(
+                format!("{} returned error {}: {}", service_name, status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse {} response: {}", service_name, e);
+                Error::ExternalService(format!("Invalid response from {}: {}", service_name, e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            let error_msg = error_messages.join(", ");
+            tracing::error!("{} GraphQL errors: {}", service_name, error_msg);
+            return Err(Error::ExternalService(
+                format!("{} request failed: {}", service_name, error_msg)
+            ));
+        }
+
+        graphql_response.data
+            .ok_or_else(|| Error::ExternalService(format!("No data returned from {}", service_name)))
+    }
+
+    /// Look up a sample by the barcode scanned on the analyzer
+    pub async fn find_sample_by_barcode(&self, barcode: &str) -> Result<Option<SampleData>> {
+        let query = r#"
+            query SampleByBarcode($barcode: String!) {
+                sampleByBarcode(barcode: $barcode) {
+                    id
+                    sampleId
+                    organizationId
+                    orderId
+                    patientId
+                    isRejected
+                    priority
+                }
+            }
+        "#;
+
+        let data: SampleByBarcodeResponse = self
+            .execute(
+                "sample-service",
+                &self.sample_service_url,
+                None,
+                query,
+                serde_json::json!({ "barcode": barcode }),
+            )
+            .await?;
+
+        Ok(data.sample_by_barcode)
+    }
+
+    pub async fn get_order_items(&self, order_id: Uuid) -> Result<Vec<OrderItemData>> {
+        let query = r#"
+            query OrderItems($orderId: ID!) {
+                orderItems(orderId: $orderId) {
+                    id
+                    testId
+                    testCode
+                    testName
+                    itemStatus
+                }
+            }
+        "#;
+
+        let data: OrderItemsResponse = self
+            .execute(
+                "order-service",
+                &self.order_service_url,
+                None,
+                query,
+                serde_json::json!({ "orderId": order_id.to_string() }),
+            )
+            .await?;
+
+        Ok(data.order_items)
+    }
+
+    pub async fn create_result(&self, organization_id: Uuid, input: CreateResultInput) -> Result<CreatedResultData> {
+        let query = r#"
+            mutation CreateResult($input: CreateResultInputGQL!) {
+                createResult(input: $input) {
+                    resultNumber
+                }
+            }
+        "#;
+
+        let variables = serde_json::json!({
+            "input": {
+                "orderId": input.order_id.to_string(),
+                "orderItemId": input.order_item_id.to_string(),
+                "testId": input.test_id.to_string(),
+                "sampleId": input.sample_id.to_string(),
+                "resultValue": input.result_value,
+                "resultUnit": input.result_unit,
+                "entryMethod": input.entry_method,
+                "instrumentId": input.instrument_id.map(|id| id.to_string()),
+                "runNumber": input.run_number,
+                "technicianNotes": input.technician_notes,
+            }
+        });
+
+        let data: CreateResultResponse = self
+            .execute("result-service", &self.result_service_url, Some(organization_id), query, variables)
+            .await?;
+
+        Ok(data.create_result)
+    }
+
+    pub async fn get_sample_results(&self, organization_id: Uuid, sample_id: Uuid) -> Result<Vec<SampleResultData>> {
+        let query = r#"
+            query ResultsBySample($sampleId: ID!) {
+                resultsBySample(sampleId: $sampleId) {
+                    id
+                    testId
+                    approvalDate
+                    createdAt
+                }
+            }
+        "#;
+
+        let data: ResultsBySampleResponse = self
+            .execute(
+                "result-service",
+                &self.result_service_url,
+                Some(organization_id),
+                query,
+                serde_json::json!({ "sampleId": sample_id.to_string() }),
+            )
+            .await?;
+
+        Ok(data.results_by_sample)
+    }
+
+    /// Replace an approved result through the correction workflow
+    pub async fn correct_result(
+        &self,
+        organization_id: Uuid,
+        result_id: Uuid,
+        new_result_value: &str,
+        correction_reason: &str,
+    ) -> Result<CreatedResultData> {
+        let query = r#"
+            mutation CorrectResult($input: CorrectResultInputGQL!) {
+                correctResult(input: $input) {
+                    resultNumber
+                }
+            }
+        "#;
+
+        let variables = serde_json::json!({
+            "input": {
+                "resultId": result_id.to_string(),
+                "newResultValue": new_result_value,
+                "correctionReason": correction_reason,
+            }
+        });
+
+        let data: CorrectResultResponse = self
+            .execute("result-service", &self.result_service_url, Some(organization_id), query, variables)
+            .await?;
+
+        Ok(data.correct_result)
+    }
+
+    /// Change the value of a result that is not approved yet
+    pub async fn update_result(
+        &self,
+        organization_id: Uuid,
+        result_id: Uuid,
+        result_value: &str,
+        result_unit: Option<&str>,
+        technician_notes: Option<&str>,
+    ) -> Result<CreatedResultData> {
+        let query = r#"
+            mutation UpdateResult($input: UpdateResultInputGQL!) {
+                updateResult(input: $input) {
+                    resultNumber
+                }
+            }
+        "#;
+
+        let variables = serde_json::json!({
+            "input": {
+                "resultId": result_id.to_string(),
+                "resultValue": result_value,
+                "resultUnit": result_unit,
+                "technicianNotes": technician_notes,
+            }
+        });
+
+        let data: UpdateResultResponse = self
+            .execute("result-service", &self.result_service_url, Some(organization_id), query, variables)
+            .await?;
+
+        Ok(data.update_result)
+    }
+
+    /// Append an entry to the sample's chain of custody
+    pub async fn record_custody_event(
+        &self,
+        sample_id: Uuid,
+        handler_id: Uuid,
+        action: &str,
+        location: &str,
+    ) -> Result<()> {
+        let query = r#"
+            mutation RecordCustodyEvent($sampleId: ID!, $handlerId: ID!, $action: String!, $location: String!) {
+                recordCustodyEvent(sampleId: $sampleId, handlerId: $handlerId, action: $action, location: $location) {
+                    id
+                }
+            }
+        "#;
+
+        let _: serde_json::Value = self
+            .execute(
+                "sample-service",
+                &self.sample_service_url,
+                None,
+                query,
+                serde_json::json!({
+                    "sampleId": sample_id.to_string(),
+                    "handlerId": handler_id.to_string(),
+                    "action": action,
+                    "location": location,
+                }),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,26 +1,38 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, JwtService, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::DatabasePool;
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod astm;
+mod lis_client;
+mod instrument_driver;
+mod host_query;
+mod hl7_query;
 
 use repository::*;
 use service::EquipmentService;
 use api::{QueryRoot, MutationRoot};
 use config::Config;
+use instrument_driver::{AstmListener, InterfaceResultSink};
+use lis_client::LisClient;
+use host_query::HostQueryResponder;
+use hl7_query::Hl7QueryListener;
 
 type EquipmentSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<EquipmentSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -89,21 +101,22 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("  Max DB connections: {}", config.database_max_connections);
     tracing::info!("  Caching enabled: {}", config.enable_caching);
     tracing::info!("  Events enabled: {}", config.enable_events);
+    tracing::info!("  ASTM listener enabled: {}", config.astm_listener_enabled);
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -127,6 +140,45 @@ async fn main() -> std::io::Result<()> {
         alert_repo,
     );
 
+    let lis_client = LisClient::new(
+        config.sample_service_url.clone(),
+        config.order_service_url.clone(),
+        config.result_service_url.clone(),
+        JwtService::new(&config.jwt_secret),
+    );
+    let host_query_responder = HostQueryResponder::new(lis_client.clone(), equipment_service.clone());
+
+    // Start ASTM instrument listener
+    if config.astm_listener_enabled {
+        let listener = AstmListener::new(
+            format!("{}:{}", config.host, config.astm_listener_port),
+            equipment_service.clone(),
+            InterfaceResultSink::new(lis_client.clone(), equipment_service.clone()),
+            host_query_responder.clone(),
+        );
+
+        tokio::spawn(async move {
+            if let Err(e) = listener.run().await {
+                tracing::error!("ASTM instrument listener stopped: {}", e);
+            }
+        });
+    }
+
+    // Start HL7 host query listener
+    if config.hl7_query_listener_enabled {
+        let listener = Hl7QueryListener::new(
+            format!("{}:{}", config.host, config.hl7_query_listener_port),
+            equipment_service.clone(),
+            host_query_responder,
+        );
+
+        tokio::spawn(async move {
+            if let Err(e) = listener.run().await {
+                tracing::error!("HL7 host query listener stopped: {}", e);
+            }
+        });
+    }
+
     // Build GraphQL schema
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(equipment_service)
@@ -144,8 +196,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
@@ -100,6 +100,18 @@ impl EquipmentRepository {
         Ok(equipment)
     }
 
+    pub async fn find_by_ip_address(&self, ip_address: &str) -> Result<Option<Equipment>> {
+        let equipment = sqlx::query_as::<_, Equipment>(
+            "SELECT * FROM equipment WHERE ip_address = $1 AND is_deleted = FALSE"
+        )
+        .bind(ip_address)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(equipment)
+    }
+
     pub async fn list(
         &self,
         filter: EquipmentFilter,
@@ -102,6 +102,27 @@ impl EquipmentService {
             .ok_or_else(|| Error::NotFound("Equipment not found".to_string()))
     }
 
+    /// Resolves the analyzer behind an instrument interface connection
+    pub async fn get_interfaced_equipment_by_ip(&self, ip_address: &str) -> Result<Equipment> {
+        let equipment = self.equipment_repo
+            .find_by_ip_address(ip_address)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("No equipment registered for {}", ip_address)))?;
+
+        if !equipment.lis_integration_enabled.unwrap_or(false) {
+            return Err(Error::Validation(format!(
+                "LIS integration is not enabled for equipment {}",
+                equipment.equipment_code
+            )));
+        }
+
+        if !equipment.is_operational() {
+            return Err(Error::EquipmentOffline);
+        }
+
+        Ok(equipment)
+    }
+
     pub async fn list_equipment(
         &self,
         filter: EquipmentFilter,
@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('inventory_item');
+SELECT enable_tenant_isolation('purchase_order');
+SELECT enable_tenant_isolation('stock_alert');
+SELECT enable_tenant_isolation('stock_batch');
+SELECT enable_tenant_isolation('stock_movement');
+SELECT enable_tenant_isolation('vendor');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('purchase_order_item', 'item_id', 'inventory_item');
@@ -1,6 +1,8 @@
 use async_graphql::{Context, Object, Result as GqlResult, ID};
 use crate::domain::*;
 use crate::service::{InventoryService, InventoryError};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use uuid::Uuid;
 use std::str::FromStr;
 
@@ -20,6 +22,7 @@ impl QueryRoot {
     // Vendor Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn vendor(&self, ctx: &Context<'_>, id: ID) -> GqlResult<Vendor> {
         let service = ctx.data::<InventoryService>()?;
         let vendor_id = Uuid::from_str(&id)?;
@@ -27,6 +30,7 @@ impl QueryRoot {
         Ok(vendor)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn vendors(
         &self,
         ctx: &Context<'_>,
@@ -56,6 +60,7 @@ impl QueryRoot {
     // Inventory Item Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn inventory_item(&self, ctx: &Context<'_>, id: ID) -> GqlResult<InventoryItem> {
         let service = ctx.data::<InventoryService>()?;
         let item_id = Uuid::from_str(&id)?;
@@ -63,6 +68,7 @@ impl QueryRoot {
         Ok(item)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn inventory_items(
         &self,
         ctx: &Context<'_>,
@@ -90,6 +96,7 @@ impl QueryRoot {
         Ok(items)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn low_stock_items(&self, ctx: &Context<'_>, organization_id: ID) -> GqlResult<Vec<InventoryItem>> {
         let service = ctx.data::<InventoryService>()?;
         let org_id = Uuid::from_str(&organization_id)?;
@@ -101,6 +108,7 @@ impl QueryRoot {
     // Stock Batch Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn stock_batch(&self, ctx: &Context<'_>, id: ID) -> GqlResult<StockBatch> {
         let service = ctx.data::<InventoryService>()?;
         let batch_id = Uuid::from_str(&id)?;
@@ -108,6 +116,7 @@ impl QueryRoot {
         Ok(batch)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn item_batches(&self, ctx: &Context<'_>, item_id: ID) -> GqlResult<Vec<StockBatch>> {
         let service = ctx.data::<InventoryService>()?;
         let item_uuid = Uuid::from_str(&item_id)?;
@@ -115,6 +124,7 @@ impl QueryRoot {
         Ok(batches)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn expiring_batches(
         &self,
         ctx: &Context<'_>,
@@ -131,6 +141,7 @@ impl QueryRoot {
     // Stock Movement Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn stock_movements(
         &self,
         ctx: &Context<'_>,
@@ -162,6 +173,7 @@ impl QueryRoot {
     // Purchase Order Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn purchase_order(&self, ctx: &Context<'_>, id: ID) -> GqlResult<PurchaseOrder> {
         let service = ctx.data::<InventoryService>()?;
         let po_id = Uuid::from_str(&id)?;
@@ -169,6 +181,7 @@ impl QueryRoot {
         Ok(po)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn purchase_order_items(&self, ctx: &Context<'_>, po_id: ID) -> GqlResult<Vec<PurchaseOrderItem>> {
         let service = ctx.data::<InventoryService>()?;
         let po_uuid = Uuid::from_str(&po_id)?;
@@ -176,6 +189,7 @@ impl QueryRoot {
         Ok(items)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn purchase_orders(
         &self,
         ctx: &Context<'_>,
@@ -209,6 +223,7 @@ impl QueryRoot {
     // Stock Alert Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_READ)")]
     async fn stock_alerts(
         &self,
         ctx: &Context<'_>,
@@ -245,14 +260,14 @@ impl MutationRoot {
     // Vendor Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_MANAGE)")]
     async fn create_vendor(
         &self,
         ctx: &Context<'_>,
         input: CreateVendorInput,
-        created_by: ID,
     ) -> GqlResult<Vendor> {
         let service = ctx.data::<InventoryService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let vendor = service.create_vendor(input, creator_id).await?;
         Ok(vendor)
     }
@@ -261,14 +276,14 @@ impl MutationRoot {
     // Inventory Item Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_MANAGE)")]
     async fn create_inventory_item(
         &self,
         ctx: &Context<'_>,
         input: CreateInventoryItemInput,
-        created_by: ID,
     ) -> GqlResult<InventoryItem> {
         let service = ctx.data::<InventoryService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let item = service.create_item(input, creator_id).await?;
         Ok(item)
     }
@@ -277,16 +292,15 @@ impl MutationRoot {
     // Stock Batch Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_UPDATE)")]
     async fn create_stock_batch(
         &self,
         ctx: &Context<'_>,
         input: CreateStockBatchInput,
-        organization_id: ID,
-        created_by: ID,
     ) -> GqlResult<StockBatch> {
         let service = ctx.data::<InventoryService>()?;
-        let org_id = Uuid::from_str(&organization_id)?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let org_id = ctx.organization_id()?;
+        let creator_id = ctx.user_id()?;
         let batch = service.create_batch(input, org_id, creator_id).await?;
         Ok(batch)
     }
@@ -295,16 +309,15 @@ impl MutationRoot {
     // Stock Movement Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_UPDATE)")]
     async fn record_stock_movement(
         &self,
         ctx: &Context<'_>,
         input: RecordStockMovementInput,
-        organization_id: ID,
-        performed_by: ID,
     ) -> GqlResult<StockMovement> {
         let service = ctx.data::<InventoryService>()?;
-        let org_id = Uuid::from_str(&organization_id)?;
-        let performer_id = Uuid::from_str(&performed_by)?;
+        let org_id = ctx.organization_id()?;
+        let performer_id = ctx.user_id()?;
         let movement = service.record_movement(input, org_id, performer_id).await?;
         Ok(movement)
     }
@@ -313,41 +326,40 @@ impl MutationRoot {
     // Purchase Order Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_MANAGE)")]
     async fn create_purchase_order(
         &self,
         ctx: &Context<'_>,
         input: CreatePurchaseOrderInput,
-        created_by: ID,
     ) -> GqlResult<PurchaseOrder> {
         let service = ctx.data::<InventoryService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let po = service.create_purchase_order(input, creator_id).await?;
         Ok(po)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_APPROVE)")]
     async fn approve_purchase_order(
         &self,
         ctx: &Context<'_>,
         po_id: ID,
-        approved_by: ID,
     ) -> GqlResult<PurchaseOrder> {
         let service = ctx.data::<InventoryService>()?;
         let po_uuid = Uuid::from_str(&po_id)?;
-        let approver_id = Uuid::from_str(&approved_by)?;
+        let approver_id = ctx.user_id()?;
         let po = service.approve_purchase_order(po_uuid, approver_id).await?;
         Ok(po)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_UPDATE)")]
     async fn receive_purchase_order(
         &self,
         ctx: &Context<'_>,
         input: ReceivePurchaseOrderInput,
-        organization_id: ID,
-        received_by: ID,
     ) -> GqlResult<PurchaseOrder> {
         let service = ctx.data::<InventoryService>()?;
-        let org_id = Uuid::from_str(&organization_id)?;
-        let receiver_id = Uuid::from_str(&received_by)?;
+        let org_id = ctx.organization_id()?;
+        let receiver_id = ctx.user_id()?;
         let po = service.receive_purchase_order(input, org_id, receiver_id).await?;
         Ok(po)
     }
@@ -356,16 +368,16 @@ impl MutationRoot {
     // Stock Alert Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::INVENTORY_UPDATE)")]
     async fn resolve_stock_alert(
         &self,
         ctx: &Context<'_>,
         alert_id: ID,
-        resolved_by: ID,
         notes: Option<String>,
     ) -> GqlResult<StockAlert> {
         let service = ctx.data::<InventoryService>()?;
         let alert_uuid = Uuid::from_str(&alert_id)?;
-        let resolver_id = Uuid::from_str(&resolved_by)?;
+        let resolver_id = ctx.user_id()?;
         let alert = service.resolve_alert(alert_uuid, resolver_id, notes).await?;
         Ok(alert)
     }
@@ -6,6 +6,7 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
 }
@@ -16,6 +17,7 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8091)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
             .add_source(config::Environment::default().separator("__"));
@@ -29,6 +31,7 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8091,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
         }
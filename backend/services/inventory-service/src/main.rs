@@ -1,7 +1,9 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::DatabasePool;
 
 mod domain;
 mod repository;
@@ -18,9 +20,10 @@ type InventorySchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<InventorySchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -92,18 +95,18 @@ async fn main() -> std::io::Result<()> {
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -144,8 +147,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
@@ -0,0 +1,83 @@
+-- ============================================================================
+-- Critical Value Call Tree
+-- ============================================================================
+-- Critical results page the first level of the organization's call tree and
+-- move down a level whenever a page goes unacknowledged for that level's
+-- wait time. Every page is a regular notification referencing the alert.
+
+CREATE TYPE call_tree_recipient AS ENUM (
+    'ORDERING_CLINICIAN',
+    'CONTACT'
+);
+
+CREATE TYPE critical_alert_status AS ENUM (
+    'PAGING',
+    'ACKNOWLEDGED',
+    'EXHAUSTED'
+);
+
+CREATE TABLE critical_call_tree_level (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,
+    department VARCHAR(100),           -- NULL applies to every department
+
+    level_number INTEGER NOT NULL CHECK (level_number > 0),
+    recipient_type call_tree_recipient NOT NULL,
+
+    -- Fixed recipient for CONTACT levels
+    recipient_id UUID,
+    recipient_name VARCHAR(200),
+    recipient_contact VARCHAR(200),
+
+    notification_channel notification_channel NOT NULL,
+    wait_minutes INTEGER NOT NULL CHECK (wait_minutes > 0),
+
+    is_active BOOLEAN DEFAULT true,
+
+    -- Audit fields
+    created_by UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP,
+
+    CONSTRAINT call_tree_contact_required CHECK (
+        recipient_type = 'ORDERING_CLINICIAN'
+        OR recipient_contact IS NOT NULL
+        OR recipient_id IS NOT NULL
+    )
+);
+
+CREATE UNIQUE INDEX idx_call_tree_level_unique
+    ON critical_call_tree_level(organization_id, COALESCE(department, ''), level_number)
+    WHERE is_active = true;
+
+CREATE TABLE critical_alert (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,
+    event_id UUID NOT NULL UNIQUE,     -- CRITICAL_VALUE_DETECTED event, for redelivery
+
+    result_id UUID NOT NULL,
+    result_number VARCHAR(100) NOT NULL,
+    department VARCHAR(100),
+    ordering_clinician_id UUID,
+    ordering_clinician_name VARCHAR(200),
+
+    message TEXT NOT NULL,
+    payload JSONB,
+
+    -- Escalation state
+    alert_status critical_alert_status NOT NULL DEFAULT 'PAGING',
+    current_level INTEGER NOT NULL DEFAULT 0,
+    last_notification_id UUID REFERENCES notification(id),
+    last_paged_at TIMESTAMP,
+    next_escalation_at TIMESTAMP,
+
+    acknowledged_at TIMESTAMP,
+    acknowledged_by VARCHAR(200),
+
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP
+);
+
+CREATE INDEX idx_critical_alert_result ON critical_alert(result_id);
+CREATE INDEX idx_critical_alert_due ON critical_alert(next_escalation_at)
+    WHERE alert_status = 'PAGING';
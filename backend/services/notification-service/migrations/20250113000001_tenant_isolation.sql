@@ -0,0 +1,16 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('critical_alert');
+SELECT enable_tenant_isolation('critical_call_tree_level');
+SELECT enable_tenant_isolation('notification');
+SELECT enable_tenant_isolation('notification_preference');
+SELECT enable_tenant_isolation('notification_queue');
+SELECT enable_tenant_isolation('notification_template');
+SELECT enable_tenant_isolation('provider_configuration');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('notification_log', 'notification_id', 'notification');
@@ -0,0 +1,39 @@
+-- Provider delivery and delivery receipts
+--
+-- Notifications can carry an attachment fetched from a URL when sent, e.g.
+-- the secure download link of a lab report. Provider webhooks report what
+-- became of a sent message; receipts are matched on provider_message_id and
+-- published to the services that asked for the notification through the
+-- transactional outbox (infrastructure::outbox::OutboxRelay).
+
+ALTER TABLE notification
+    ADD COLUMN attachment_url VARCHAR(1000),
+    ADD COLUMN attachment_name VARCHAR(200);
+
+CREATE INDEX idx_notification_provider_message ON notification(notification_channel, provider_message_id)
+    WHERE provider_message_id IS NOT NULL;
+
+CREATE TABLE event_outbox (
+    seq BIGSERIAL PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    topic VARCHAR(255) NOT NULL,
+    event_type VARCHAR(100) NOT NULL,
+    aggregate_type VARCHAR(100) NOT NULL,
+    aggregate_id VARCHAR(100) NOT NULL,
+    organization_id VARCHAR(100) NOT NULL,
+    event JSONB NOT NULL,
+
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'PUBLISHED', 'FAILED')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_event_outbox_pending ON event_outbox(next_attempt_at, seq) WHERE status = 'PENDING';
+CREATE INDEX idx_event_outbox_aggregate ON event_outbox(aggregate_id, seq) WHERE status = 'PENDING';
+
+SELECT enable_tenant_isolation('event_outbox');
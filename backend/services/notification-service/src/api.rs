@@ -1,6 +1,9 @@
 use async_graphql::{Context, Object, Result as GqlResult, ID, ErrorExtensions};
 use crate::domain::*;
 use crate::service::{NotificationService, NotificationError};
+use crate::critical_alert::CriticalAlertService;
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use uuid::Uuid;
 use std::str::FromStr;
 
@@ -18,6 +21,7 @@ impl QueryRoot {
     // Template Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification_template(&self, ctx: &Context<'_>, id: ID) -> GqlResult<NotificationTemplate> {
         let service = ctx.data::<NotificationService>()?;
         let template_id = Uuid::from_str(&id)?;
@@ -25,6 +29,7 @@ impl QueryRoot {
         Ok(template)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification_template_by_code(
         &self,
         ctx: &Context<'_>,
@@ -37,6 +42,7 @@ impl QueryRoot {
         Ok(template)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification_templates(
         &self,
         ctx: &Context<'_>,
@@ -66,6 +72,7 @@ impl QueryRoot {
     // Notification Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification(&self, ctx: &Context<'_>, id: ID) -> GqlResult<Notification> {
         let service = ctx.data::<NotificationService>()?;
         let notification_id = Uuid::from_str(&id)?;
@@ -73,6 +80,7 @@ impl QueryRoot {
         Ok(notification)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notifications(
         &self,
         ctx: &Context<'_>,
@@ -109,6 +117,7 @@ impl QueryRoot {
     // Preference Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification_preference(
         &self,
         ctx: &Context<'_>,
@@ -126,12 +135,47 @@ impl QueryRoot {
     // Log Queries
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
     async fn notification_logs(&self, ctx: &Context<'_>, notification_id: ID) -> GqlResult<Vec<NotificationLog>> {
         let service = ctx.data::<NotificationService>()?;
         let notif_id = Uuid::from_str(&notification_id)?;
         let logs = service.get_notification_logs(notif_id).await?;
         Ok(logs)
     }
+
+    // ============================================================================
+    // Critical Alert Queries
+    // ============================================================================
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
+    async fn critical_alert(&self, ctx: &Context<'_>, id: ID) -> GqlResult<CriticalAlert> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let alert_id = Uuid::from_str(&id)?;
+        let alert = service.get_alert(alert_id).await?;
+        Ok(alert)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
+    async fn critical_alerts(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: ID,
+        status: Option<CriticalAlertStatus>,
+        limit: Option<i64>,
+    ) -> GqlResult<Vec<CriticalAlert>> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let org_id = Uuid::from_str(&organization_id)?;
+        let alerts = service.list_alerts(org_id, status, limit.unwrap_or(50)).await?;
+        Ok(alerts)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_READ)")]
+    async fn critical_call_tree(&self, ctx: &Context<'_>, organization_id: ID) -> GqlResult<Vec<CriticalCallTreeLevel>> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let org_id = Uuid::from_str(&organization_id)?;
+        let levels = service.list_call_tree(org_id).await?;
+        Ok(levels)
+    }
 }
 
 pub struct MutationRoot;
@@ -142,14 +186,14 @@ impl MutationRoot {
     // Template Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
     async fn create_notification_template(
         &self,
         ctx: &Context<'_>,
         input: CreateNotificationTemplateInput,
-        created_by: ID,
     ) -> GqlResult<NotificationTemplate> {
         let service = ctx.data::<NotificationService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let template = service.create_template(input, creator_id).await?;
         Ok(template)
     }
@@ -158,18 +202,19 @@ impl MutationRoot {
     // Notification Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_SEND)")]
     async fn send_notification(
         &self,
         ctx: &Context<'_>,
         input: SendNotificationInput,
-        created_by: Option<ID>,
     ) -> GqlResult<Notification> {
         let service = ctx.data::<NotificationService>()?;
-        let creator_id = created_by.as_ref().and_then(|id| Uuid::from_str(id).ok());
+        let creator_id = ctx.user_id().ok();
         let notification = service.send_notification(input, creator_id).await?;
         Ok(notification)
     }
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_SEND)")]
     async fn retry_notification(
         &self,
         ctx: &Context<'_>,
@@ -185,6 +230,7 @@ impl MutationRoot {
     // Preference Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
     async fn update_notification_preference(
         &self,
         ctx: &Context<'_>,
@@ -199,14 +245,14 @@ impl MutationRoot {
     // Provider Mutations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
     async fn create_provider_config(
         &self,
         ctx: &Context<'_>,
         input: CreateProviderConfigInput,
-        created_by: ID,
     ) -> GqlResult<ProviderConfiguration> {
         let service = ctx.data::<NotificationService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let config = service.create_provider_config(input, creator_id).await?;
         Ok(config)
     }
@@ -215,6 +261,7 @@ impl MutationRoot {
     // Batch Operations
     // ============================================================================
 
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
     async fn process_pending_notifications(
         &self,
         ctx: &Context<'_>,
@@ -224,4 +271,39 @@ impl MutationRoot {
         let notifications = service.process_pending_notifications(limit).await?;
         Ok(notifications)
     }
+
+    // ============================================================================
+    // Critical Alert Mutations
+    // ============================================================================
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
+    async fn create_critical_call_tree_level(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateCallTreeLevelInput,
+    ) -> GqlResult<CriticalCallTreeLevel> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let creator_id = ctx.user_id()?;
+        let level = service.create_call_tree_level(input, creator_id).await?;
+        Ok(level)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
+    async fn deactivate_critical_call_tree_level(&self, ctx: &Context<'_>, id: ID) -> GqlResult<CriticalCallTreeLevel> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let level_id = Uuid::from_str(&id)?;
+        let level = service.deactivate_call_tree_level(level_id).await?;
+        Ok(level)
+    }
+
+    #[graphql(guard = "PermissionGuard::new(permissions::NOTIFICATION_MANAGE)")]
+    async fn process_critical_escalations(
+        &self,
+        ctx: &Context<'_>,
+        limit: i64,
+    ) -> GqlResult<Vec<CriticalAlert>> {
+        let service = ctx.data::<CriticalAlertService>()?;
+        let alerts = service.process_due_escalations(limit).await?;
+        Ok(alerts)
+    }
 }
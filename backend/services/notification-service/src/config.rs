@@ -6,8 +6,21 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub kafka_brokers: String,
+    /// Wait before escalating when an organization has no call tree
+    pub critical_ack_timeout_minutes: i32,
+    pub critical_escalation_interval_seconds: u64,
+    /// Address providers reach this service at, for status callbacks
+    pub public_url: String,
+    /// Shared token of the SMS and email webhook URLs
+    pub webhook_token: String,
+    /// App secret WhatsApp signs webhook calls with; calls are refused
+    /// while it is empty
+    pub whatsapp_app_secret: String,
+    pub whatsapp_verify_token: String,
 }
 
 impl Config {
@@ -16,8 +29,16 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8092)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("kafka_brokers", "localhost:9092")?
+            .set_default("critical_ack_timeout_minutes", 15)?
+            .set_default("critical_escalation_interval_seconds", 30)?
+            .set_default("public_url", "http://localhost:8092")?
+            .set_default("webhook_token", "development-webhook-token-change-in-production")?
+            .set_default("whatsapp_app_secret", "")?
+            .set_default("whatsapp_verify_token", "development-verify-token-change-in-production")?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -29,8 +50,16 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8092,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            kafka_brokers: "localhost:9092".to_string(),
+            critical_ack_timeout_minutes: 15,
+            critical_escalation_interval_seconds: 30,
+            public_url: "http://localhost:8092".to_string(),
+            webhook_token: "development-webhook-token-change-in-production".to_string(),
+            whatsapp_app_secret: String::new(),
+            whatsapp_verify_token: "development-verify-token-change-in-production".to_string(),
         }
     }
 }
@@ -0,0 +1,466 @@
+//! Critical value paging.
+//!
+//! result-service publishes `CRITICAL_VALUE_DETECTED` for every critical
+//! result. Each event opens a [`CriticalAlert`] and pages the first level of
+//! the organization's call tree. When result-service has not reported the
+//! acknowledgment (`CRITICAL_VALUE_ACKNOWLEDGED`) within a level's wait time,
+//! the escalation worker pages the next level. Organizations without a call
+//! tree page only the ordering clinician.
+
+use chrono::Local;
+use common::tenant::Tenant;
+use infrastructure::event_bus::{events, DomainEvent};
+use infrastructure::EventRouter;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::domain::*;
+use crate::repository::*;
+use crate::service::{NotificationError, NotificationService, Result};
+
+const ALERT_REFERENCE_TYPE: &str = "CRITICAL_ALERT";
+
+/// How long an alert being paged stays claimed. An alert whose page fails
+/// part-way, e.g. on a database error, is due again after this long.
+const PAGE_RETRY_MINUTES: i32 = 1;
+
+/// Payload of `CRITICAL_VALUE_DETECTED`
+#[derive(Debug, Deserialize)]
+struct CriticalValueDetected {
+    result_id: Uuid,
+    result_number: String,
+    patient_mrn: Option<String>,
+    ordering_clinician_id: Option<Uuid>,
+    ordering_clinician_name: Option<String>,
+    test_name: String,
+    department: Option<String>,
+    result_value: Option<String>,
+    result_unit: Option<String>,
+    critical_flag: String,
+}
+
+/// Payload of `CRITICAL_VALUE_ACKNOWLEDGED`
+#[derive(Debug, Deserialize)]
+struct CriticalValueAcknowledged {
+    result_id: Uuid,
+    acknowledged_by: Option<String>,
+}
+
+fn alert_message(detected: &CriticalValueDetected) -> String {
+    let value = [detected.result_value.as_deref(), detected.result_unit.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let patient = detected.patient_mrn
+        .as_deref()
+        .map(|mrn| format!(" for MRN {}", mrn))
+        .unwrap_or_default();
+
+    format!(
+        "CRITICAL RESULT {}{}: {} {} ({}). Call the laboratory to acknowledge and read back the value.",
+        detected.result_number, patient, detected.test_name, value, detected.critical_flag
+    )
+}
+
+/// The call tree for a department: its own levels when it has any,
+/// otherwise the organization-wide levels, in paging order
+pub fn call_tree_for<'a>(levels: &'a [CriticalCallTreeLevel], department: Option<&str>) -> Vec<&'a CriticalCallTreeLevel> {
+    let own: Vec<_> = levels
+        .iter()
+        .filter(|level| department.is_some() && level.department.as_deref() == department)
+        .collect();
+
+    let mut tree = if own.is_empty() {
+        levels.iter().filter(|level| level.department.is_none()).collect()
+    } else {
+        own
+    };
+    tree.sort_by_key(|level| level.level_number);
+    tree
+}
+
+/// Used when an organization has not configured a call tree
+fn default_call_tree(organization_id: Uuid, wait_minutes: i32) -> CriticalCallTreeLevel {
+    CriticalCallTreeLevel {
+        id: Uuid::nil(),
+        organization_id,
+        department: None,
+        level_number: 1,
+        recipient_type: CallTreeRecipient::OrderingClinician,
+        recipient_id: None,
+        recipient_name: None,
+        recipient_contact: None,
+        notification_channel: NotificationChannel::Sms,
+        wait_minutes,
+        is_active: Some(true),
+        created_by: Uuid::nil(),
+        created_at: Local::now().naive_local(),
+        updated_at: None,
+    }
+}
+
+struct Recipient {
+    id: Option<Uuid>,
+    name: Option<String>,
+    contact: String,
+}
+
+// ============================================================================
+// Critical Alert Service
+// ============================================================================
+
+#[derive(Clone)]
+pub struct CriticalAlertService {
+    notification_service: NotificationService,
+    alert_repo: CriticalAlertRepository,
+    call_tree_repo: CriticalCallTreeRepository,
+    preference_repo: NotificationPreferenceRepository,
+    default_wait_minutes: i32,
+}
+
+impl CriticalAlertService {
+    pub fn new(
+        notification_service: NotificationService,
+        alert_repo: CriticalAlertRepository,
+        call_tree_repo: CriticalCallTreeRepository,
+        preference_repo: NotificationPreferenceRepository,
+        default_wait_minutes: i32,
+    ) -> Self {
+        Self {
+            notification_service,
+            alert_repo,
+            call_tree_repo,
+            preference_repo,
+            default_wait_minutes,
+        }
+    }
+
+    // ============================================================================
+    // Event Handling
+    // ============================================================================
+
+    pub async fn handle_event(&self, event: &DomainEvent) -> Result<()> {
+        match event.event_type.as_str() {
+            events::CRITICAL_VALUE_DETECTED => {
+                self.open_alert(event).await?;
+            },
+            events::CRITICAL_VALUE_ACKNOWLEDGED => {
+                let acknowledged: CriticalValueAcknowledged = serde_json::from_value(event.payload.clone())
+                    .map_err(|e| NotificationError::ValidationError(format!("Invalid acknowledgment event: {}", e)))?;
+                self.acknowledge(acknowledged.result_id, acknowledged.acknowledged_by.as_deref()).await?;
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn open_alert(&self, event: &DomainEvent) -> Result<Option<CriticalAlert>> {
+        let detected: CriticalValueDetected = serde_json::from_value(event.payload.clone())
+            .map_err(|e| NotificationError::ValidationError(format!("Invalid critical value event: {}", e)))?;
+        let organization_id = Uuid::parse_str(&event.metadata.organization_id)
+            .map_err(|e| NotificationError::ValidationError(format!("Invalid organization id: {}", e)))?;
+
+        let new_alert = NewCriticalAlert {
+            organization_id,
+            event_id: event.event_id,
+            result_id: detected.result_id,
+            result_number: detected.result_number.clone(),
+            department: detected.department.clone(),
+            ordering_clinician_id: detected.ordering_clinician_id,
+            ordering_clinician_name: detected.ordering_clinician_name.clone(),
+            message: alert_message(&detected),
+            payload: event.payload.clone(),
+        };
+
+        let Some(alert) = self.alert_repo.create(&new_alert, PAGE_RETRY_MINUTES).await? else {
+            tracing::info!("Critical value event {} already handled", event.event_id);
+            return Ok(None);
+        };
+
+        tracing::warn!("Critical alert opened for result {}", alert.result_number);
+        self.page_next_level(alert).await.map(Some)
+    }
+
+    pub async fn acknowledge(&self, result_id: Uuid, acknowledged_by: Option<&str>) -> Result<Vec<CriticalAlert>> {
+        let alerts = self.alert_repo.acknowledge_by_result(result_id, acknowledged_by).await?;
+        for alert in &alerts {
+            tracing::info!(
+                "Critical alert for result {} acknowledged at level {}",
+                alert.result_number,
+                alert.current_level
+            );
+        }
+        Ok(alerts)
+    }
+
+    // ============================================================================
+    // Escalation
+    // ============================================================================
+
+    /// Pages the alerts whose current level has gone unacknowledged too long
+    pub async fn process_due_escalations(&self, limit: i64) -> Result<Vec<CriticalAlert>> {
+        let due = self.alert_repo.claim_due(limit, PAGE_RETRY_MINUTES).await?;
+
+        let mut escalated = Vec::new();
+        for alert in due {
+            let alert_id = alert.id;
+            match self.page_next_level(alert).await {
+                Ok(alert) => escalated.push(alert),
+                Err(e) => tracing::error!("Failed to escalate critical alert {}: {}", alert_id, e),
+            }
+        }
+
+        Ok(escalated)
+    }
+
+    /// Pages the first level after the alert's current one that can be
+    /// reached. Levels without a usable contact, or whose page fails, are
+    /// skipped rather than waited on.
+    async fn page_next_level(&self, alert: CriticalAlert) -> Result<CriticalAlert> {
+        let levels = self.call_tree_repo.list_active(alert.organization_id).await?;
+        let fallback = [default_call_tree(alert.organization_id, self.default_wait_minutes)];
+
+        let mut tree = call_tree_for(&levels, alert.department.as_deref());
+        if tree.is_empty() {
+            tracing::warn!(
+                "No critical call tree for organization {}; paging the ordering clinician only",
+                alert.organization_id
+            );
+            tree = fallback.iter().collect();
+        }
+
+        for level in tree.into_iter().filter(|level| level.level_number > alert.current_level) {
+            let Some(recipient) = self.resolve_recipient(&alert, level).await else {
+                tracing::warn!(
+                    "Call tree level {} has no {:?} contact for result {}; skipping",
+                    level.level_number,
+                    level.notification_channel,
+                    alert.result_number
+                );
+                continue;
+            };
+
+            match self.page(&alert, level, recipient).await {
+                Ok(notification) if notification.notification_status != Some(NotificationStatus::Failed) => {
+                    tracing::warn!(
+                        "Paged call tree level {} for critical result {}",
+                        level.level_number,
+                        alert.result_number
+                    );
+                    let alert = self.alert_repo
+                        .record_page(alert.id, level.level_number, notification.id, level.wait_minutes)
+                        .await?;
+                    return Ok(alert);
+                },
+                Ok(notification) => tracing::warn!(
+                    "Page to call tree level {} for result {} failed: {}",
+                    level.level_number,
+                    alert.result_number,
+                    notification.status_message.unwrap_or_default()
+                ),
+                Err(e) => tracing::warn!(
+                    "Page to call tree level {} for result {} failed: {}",
+                    level.level_number,
+                    alert.result_number,
+                    e
+                ),
+            }
+        }
+
+        tracing::error!(
+            "Critical result {} was not acknowledged by any call tree level",
+            alert.result_number
+        );
+        let alert = self.alert_repo.mark_exhausted(alert.id).await?;
+        Ok(alert)
+    }
+
+    async fn resolve_recipient(&self, alert: &CriticalAlert, level: &CriticalCallTreeLevel) -> Option<Recipient> {
+        let (user_id, name) = match level.recipient_type {
+            CallTreeRecipient::OrderingClinician => {
+                (alert.ordering_clinician_id?, alert.ordering_clinician_name.clone())
+            },
+            CallTreeRecipient::Contact => {
+                if let Some(contact) = level.recipient_contact.clone() {
+                    return Some(Recipient {
+                        id: level.recipient_id,
+                        name: level.recipient_name.clone(),
+                        contact,
+                    });
+                }
+                (level.recipient_id?, level.recipient_name.clone())
+            },
+        };
+
+        let preference = self.preference_repo.get_by_user(alert.organization_id, user_id).await.ok()?;
+        let contact = preference.contact_for(level.notification_channel)?;
+
+        Some(Recipient {
+            id: Some(user_id),
+            name,
+            contact,
+        })
+    }
+
+    async fn page(&self, alert: &CriticalAlert, level: &CriticalCallTreeLevel, recipient: Recipient) -> Result<Notification> {
+        let content = if alert.current_level > 0 {
+            format!("ESCALATION (level {}): earlier page not acknowledged. {}", level.level_number, alert.message)
+        } else {
+            alert.message.clone()
+        };
+
+        let recipient_type = match level.recipient_type {
+            CallTreeRecipient::OrderingClinician => "ORDERING_CLINICIAN",
+            CallTreeRecipient::Contact => "CALL_TREE_CONTACT",
+        };
+
+        let input = SendNotificationInput {
+            organization_id: alert.organization_id,
+            template_id: None,
+            recipient_id: recipient.id,
+            recipient_type: Some(recipient_type.to_string()),
+            recipient_name: recipient.name,
+            recipient_contact: recipient.contact,
+            notification_channel: level.notification_channel,
+            notification_priority: Some(NotificationPriority::Urgent),
+            subject: Some(format!("Critical result {}", alert.result_number)),
+            content,
+            template_data: None,
+            scheduled_at: None,
+            reference_type: Some(ALERT_REFERENCE_TYPE.to_string()),
+            reference_id: Some(alert.id),
+            attachment_url: None,
+            attachment_name: None,
+        };
+
+        self.notification_service.send_notification(input, None).await
+    }
+
+    // ============================================================================
+    // Queries and Call Tree Management
+    // ============================================================================
+
+    pub async fn get_alert(&self, alert_id: Uuid) -> Result<CriticalAlert> {
+        let alert = self.alert_repo.get_by_id(alert_id).await?;
+        Ok(alert)
+    }
+
+    pub async fn list_alerts(&self, organization_id: Uuid, status: Option<CriticalAlertStatus>, limit: i64) -> Result<Vec<CriticalAlert>> {
+        let alerts = self.alert_repo.list(organization_id, status, limit).await?;
+        Ok(alerts)
+    }
+
+    pub async fn list_call_tree(&self, organization_id: Uuid) -> Result<Vec<CriticalCallTreeLevel>> {
+        let levels = self.call_tree_repo.list_active(organization_id).await?;
+        Ok(levels)
+    }
+
+    pub async fn create_call_tree_level(&self, input: CreateCallTreeLevelInput, created_by: Uuid) -> Result<CriticalCallTreeLevel> {
+        if input.level_number < 1 {
+            return Err(NotificationError::ValidationError("Level number must be 1 or higher".to_string()));
+        }
+
+        if input.wait_minutes < 1 {
+            return Err(NotificationError::ValidationError("Wait minutes must be at least 1".to_string()));
+        }
+
+        if input.recipient_type == CallTreeRecipient::Contact
+            && input.recipient_id.is_none()
+            && input.recipient_contact.as_deref().is_none_or(|c| c.trim().is_empty())
+        {
+            return Err(NotificationError::ValidationError(
+                "Contact levels need a recipient id or contact".to_string()
+            ));
+        }
+
+        let level = self.call_tree_repo.create(input, created_by).await?;
+        Ok(level)
+    }
+
+    pub async fn deactivate_call_tree_level(&self, level_id: Uuid) -> Result<CriticalCallTreeLevel> {
+        let level = self.call_tree_repo.deactivate(level_id).await?;
+        Ok(level)
+    }
+}
+
+// ============================================================================
+// Background Tasks
+// ============================================================================
+
+/// Opens and closes alerts from result events
+pub fn event_routes(router: EventRouter, service: CriticalAlertService) -> EventRouter {
+    [events::CRITICAL_VALUE_DETECTED, events::CRITICAL_VALUE_ACKNOWLEDGED]
+        .into_iter()
+        .fold(router, |router, event_type| {
+            let service = service.clone();
+            router.on(event_type, move |event| {
+                let service = service.clone();
+                async move { service.handle_event(&event).await.map_err(Into::into) }
+            })
+        })
+}
+
+/// Escalates unacknowledged alerts on a fixed interval
+pub async fn run_escalation_worker(service: CriticalAlertService, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        // Due alerts of every organization
+        if let Err(e) = Tenant::System.scope(service.process_due_escalations(100)).await {
+            tracing::error!("Critical alert escalation failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(department: Option<&str>, level_number: i32) -> CriticalCallTreeLevel {
+        CriticalCallTreeLevel {
+            department: department.map(str::to_string),
+            level_number,
+            ..default_call_tree(Uuid::nil(), 10)
+        }
+    }
+
+    #[test]
+    fn test_department_tree_overrides_organization_tree() {
+        let levels = vec![
+            level(None, 2),
+            level(None, 1),
+            level(Some("HEMATOLOGY"), 1),
+        ];
+
+        let hematology = call_tree_for(&levels, Some("HEMATOLOGY"));
+        assert_eq!(hematology.len(), 1);
+        assert_eq!(hematology[0].department.as_deref(), Some("HEMATOLOGY"));
+
+        let chemistry = call_tree_for(&levels, Some("CHEMISTRY"));
+        let order: Vec<i32> = chemistry.iter().map(|l| l.level_number).collect();
+        assert_eq!(order, vec![1, 2]);
+
+        assert_eq!(call_tree_for(&levels, None).len(), 2);
+    }
+
+    #[test]
+    fn test_alert_message_carries_value_and_flag() {
+        let detected = CriticalValueDetected {
+            result_id: Uuid::new_v4(),
+            result_number: "ORG-RES-K-20250109-000001".to_string(),
+            patient_mrn: Some("MRN001".to_string()),
+            ordering_clinician_id: None,
+            ordering_clinician_name: None,
+            test_name: "Potassium".to_string(),
+            department: None,
+            result_value: Some("6.9".to_string()),
+            result_unit: Some("mmol/L".to_string()),
+            critical_flag: "PANIC_HIGH".to_string(),
+        };
+
+        let message = alert_message(&detected);
+        assert!(message.contains("MRN001"));
+        assert!(message.contains("Potassium 6.9 mmol/L (PANIC_HIGH)"));
+    }
+}
@@ -30,6 +30,29 @@ pub enum NotificationStatus {
     Bounced,
 }
 
+impl NotificationStatus {
+    /// Whether a provider receipt reporting `reported` moves a notification
+    /// on from this status. Receipts can arrive out of order, so a late
+    /// `sent` never undoes `delivered`, and a message that reached the
+    /// recipient is not failed afterwards.
+    pub fn accepts_receipt(self, reported: NotificationStatus) -> bool {
+        use NotificationStatus::*;
+
+        match reported {
+            Sent => matches!(self, Pending | Queued | Sending),
+            Delivered => matches!(self, Pending | Queued | Sending | Sent),
+            Read => matches!(self, Pending | Queued | Sending | Sent | Delivered),
+            Failed | Bounced => matches!(self, Pending | Queued | Sending | Sent),
+            Pending | Queued | Sending => false,
+        }
+    }
+
+    /// Final outcome of a delivery, as published to other services
+    pub fn is_outcome(self) -> bool {
+        matches!(self, Self::Delivered | Self::Read | Self::Failed | Self::Bounced)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
 #[sqlx(type_name = "notification_priority", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum NotificationPriority {
@@ -53,6 +76,24 @@ pub enum TemplateType {
     Custom,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
+#[sqlx(type_name = "call_tree_recipient", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CallTreeRecipient {
+    /// The clinician on the order the critical result belongs to
+    OrderingClinician,
+    /// A fixed person or number, e.g. the duty resident or lab supervisor
+    Contact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
+#[sqlx(type_name = "critical_alert_status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CriticalAlertStatus {
+    Paging,
+    Acknowledged,
+    /// Every call tree level was paged without an acknowledgment
+    Exhausted,
+}
+
 // ============================================================================
 // Notification Template Entity
 // ============================================================================
@@ -117,6 +158,11 @@ pub struct Notification {
     pub content: String,
     pub html_content: Option<String>,
 
+    /// Fetched when sending and attached to emails, or sent as the
+    /// document of a WhatsApp template
+    pub attachment_url: Option<String>,
+    pub attachment_name: Option<String>,
+
     #[sqlx(json)]
     pub template_data: Option<serde_json::Value>,
 
@@ -165,6 +211,45 @@ impl Notification {
     }
 }
 
+/// What a provider webhook reported about a sent message
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryReceipt {
+    pub channel: NotificationChannel,
+    pub provider_message_id: String,
+    pub status: NotificationStatus,
+    pub message: Option<String>,
+    pub payload: serde_json::Value,
+}
+
+/// Payload of `NOTIFICATION_DELIVERED` and `NOTIFICATION_FAILED`, which tell
+/// the service that asked for a notification what became of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationOutcome {
+    pub notification_id: Uuid,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<Uuid>,
+    pub notification_channel: NotificationChannel,
+    pub notification_status: NotificationStatus,
+    pub status_message: Option<String>,
+    pub provider_name: Option<String>,
+    pub provider_message_id: Option<String>,
+}
+
+impl From<&Notification> for NotificationOutcome {
+    fn from(notification: &Notification) -> Self {
+        Self {
+            notification_id: notification.id,
+            reference_type: notification.reference_type.clone(),
+            reference_id: notification.reference_id,
+            notification_channel: notification.notification_channel,
+            notification_status: notification.notification_status.unwrap_or(NotificationStatus::Pending),
+            status_message: notification.status_message.clone(),
+            provider_name: notification.provider_name.clone(),
+            provider_message_id: notification.provider_message_id.clone(),
+        }
+    }
+}
+
 // ============================================================================
 // Notification Preference Entity
 // ============================================================================
@@ -211,6 +296,17 @@ impl NotificationPreference {
         }
     }
 
+    /// Where a notification on the given channel reaches this user
+    pub fn contact_for(&self, channel: NotificationChannel) -> Option<String> {
+        match channel {
+            NotificationChannel::Email => self.email_address.clone(),
+            NotificationChannel::Sms => self.phone_number.clone(),
+            NotificationChannel::Whatsapp => self.whatsapp_number.clone().or_else(|| self.phone_number.clone()),
+            NotificationChannel::Push => self.push_token.clone(),
+            NotificationChannel::InApp => Some(self.user_id.to_string()),
+        }
+    }
+
     pub fn is_in_quiet_hours(&self) -> bool {
         if !self.quiet_hours_enabled.unwrap_or(false) {
             return false;
@@ -313,6 +409,77 @@ pub struct ProviderConfiguration {
     pub is_deleted: Option<bool>,
 }
 
+// ============================================================================
+// Critical Value Call Tree Entities
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct CriticalCallTreeLevel {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub department: Option<String>,
+
+    pub level_number: i32,
+    pub recipient_type: CallTreeRecipient,
+
+    pub recipient_id: Option<Uuid>,
+    pub recipient_name: Option<String>,
+    pub recipient_contact: Option<String>,
+
+    pub notification_channel: NotificationChannel,
+    /// How long a page at this level may go unacknowledged before escalating
+    pub wait_minutes: i32,
+
+    pub is_active: Option<bool>,
+
+    pub created_by: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct CriticalAlert {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub event_id: Uuid,
+
+    pub result_id: Uuid,
+    pub result_number: String,
+    pub department: Option<String>,
+    pub ordering_clinician_id: Option<Uuid>,
+    pub ordering_clinician_name: Option<String>,
+
+    pub message: String,
+    #[sqlx(json)]
+    pub payload: Option<serde_json::Value>,
+
+    pub alert_status: CriticalAlertStatus,
+    /// Call tree level last paged; 0 before the first page
+    pub current_level: i32,
+    pub last_notification_id: Option<Uuid>,
+    pub last_paged_at: Option<NaiveDateTime>,
+    pub next_escalation_at: Option<NaiveDateTime>,
+
+    pub acknowledged_at: Option<NaiveDateTime>,
+    pub acknowledged_by: Option<String>,
+
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewCriticalAlert {
+    pub organization_id: Uuid,
+    pub event_id: Uuid,
+    pub result_id: Uuid,
+    pub result_number: String,
+    pub department: Option<String>,
+    pub ordering_clinician_id: Option<Uuid>,
+    pub ordering_clinician_name: Option<String>,
+    pub message: String,
+    pub payload: serde_json::Value,
+}
+
 // ============================================================================
 // Input Types
 // ============================================================================
@@ -351,6 +518,9 @@ pub struct SendNotificationInput {
     pub scheduled_at: Option<String>,
     pub reference_type: Option<String>,
     pub reference_id: Option<Uuid>,
+    /// URL the attachment is fetched from when the notification is sent
+    pub attachment_url: Option<String>,
+    pub attachment_name: Option<String>,
 }
 
 #[derive(Debug, Clone, InputObject)]
@@ -383,6 +553,19 @@ pub struct CreateProviderConfigInput {
     pub is_default: Option<bool>,
 }
 
+#[derive(Debug, Clone, InputObject)]
+pub struct CreateCallTreeLevelInput {
+    pub organization_id: Uuid,
+    pub department: Option<String>,
+    pub level_number: i32,
+    pub recipient_type: CallTreeRecipient,
+    pub recipient_id: Option<Uuid>,
+    pub recipient_name: Option<String>,
+    pub recipient_contact: Option<String>,
+    pub notification_channel: NotificationChannel,
+    pub wait_minutes: i32,
+}
+
 // ============================================================================
 // Filter Types
 // ============================================================================
@@ -1,26 +1,37 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use std::time::Duration;
+use infrastructure::event_bus::topics;
+use infrastructure::{ConsumerRuntime, DatabasePool, EventBusConfig, EventRouter, EventTransport, OutboxRelay};
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod critical_alert;
+mod providers;
+mod webhooks;
 
 use repository::*;
 use service::NotificationService;
+use critical_alert::CriticalAlertService;
 use api::{QueryRoot, MutationRoot};
 use config::Config;
+use providers::ProviderClient;
+use webhooks::WebhookConfig;
 
 type NotificationSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<NotificationSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -89,21 +100,29 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("  Max DB connections: {}", config.database_max_connections);
     tracing::info!("  Caching enabled: {}", config.enable_caching);
     tracing::info!("  Events enabled: {}", config.enable_events);
+    if config.enable_events {
+        tracing::info!("  Kafka brokers: {}", config.kafka_brokers);
+    }
+    tracing::info!("  Critical escalation default wait: {} min", config.critical_ack_timeout_minutes);
+    tracing::info!("  Public URL: {}", config.public_url);
+    if config.whatsapp_app_secret.is_empty() {
+        tracing::warn!("No WhatsApp app secret configured; WhatsApp delivery receipts will be refused");
+    }
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -115,24 +134,70 @@ async fn main() -> std::io::Result<()> {
     let preference_repo = NotificationPreferenceRepository::new(pool.clone());
     let log_repo = NotificationLogRepository::new(pool.clone());
     let provider_repo = ProviderConfigurationRepository::new(pool.clone());
+    let call_tree_repo = CriticalCallTreeRepository::new(pool.clone());
+    let alert_repo = CriticalAlertRepository::new(pool.clone());
 
-    // Create service
+    // Create services
     let notification_service = NotificationService::new(
         template_repo,
         notification_repo,
-        preference_repo,
+        preference_repo.clone(),
         log_repo,
         provider_repo,
+        ProviderClient::new(&config.public_url, &config.webhook_token),
     );
 
+    let critical_alert_service = CriticalAlertService::new(
+        notification_service.clone(),
+        alert_repo,
+        call_tree_repo,
+        preference_repo,
+        config.critical_ack_timeout_minutes,
+    );
+
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+        tracing::info!("Outbox relay started");
+    }
+
+    // Start critical value paging
+    if let Some(event_bus) = &event_bus {
+        let consumer = event_bus
+            .subscribe("notification-service", &[topics::RESULT_EVENTS])
+            .expect("Failed to create result event consumer");
+        let router = critical_alert::event_routes(EventRouter::new(), critical_alert_service.clone());
+        ConsumerRuntime::new(consumer, router, event_bus.clone()).spawn();
+    }
+
+    tokio::spawn(critical_alert::run_escalation_worker(
+        critical_alert_service.clone(),
+        Duration::from_secs(config.critical_escalation_interval_seconds),
+    ));
+
+    let webhook_config = WebhookConfig {
+        token: config.webhook_token.clone(),
+        whatsapp_app_secret: config.whatsapp_app_secret.clone(),
+        whatsapp_verify_token: config.whatsapp_verify_token.clone(),
+    };
+
     // Build GraphQL schema
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
-        .data(notification_service)
+        .data(notification_service.clone())
+        .data(critical_alert_service)
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: notificationTemplate, notificationTemplateByCode, notificationTemplates, notification, notifications, notificationPreference, notificationLogs");
-    tracing::info!("  Mutations: createNotificationTemplate, sendNotification, retryNotification, updateNotificationPreference, createProviderConfig, processPendingNotifications");
+    tracing::info!("  Queries: notificationTemplate, notificationTemplateByCode, notificationTemplates, notification, notifications, notificationPreference, notificationLogs, criticalAlert, criticalAlerts, criticalCallTree");
+    tracing::info!("  Mutations: createNotificationTemplate, sendNotification, retryNotification, updateNotificationPreference, createProviderConfig, processPendingNotifications, createCriticalCallTreeLevel, deactivateCriticalCallTreeLevel, processCriticalEscalations");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -141,13 +206,18 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("GraphiQL playground: http://{}/graphql (GET)", bind_addr);
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
+    tracing::info!("Delivery receipt webhooks: http://{}/webhooks/{{whatsapp,sms,email}}", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(notification_service.clone()))
+            .app_data(web::Data::new(webhook_config.clone()))
             .service(
                 web::resource("/graphql")
                     .guard(guard::Post())
@@ -160,6 +230,13 @@ async fn main() -> std::io::Result<()> {
             )
             .service(web::resource("/health").route(web::get().to(health_check)))
             .service(web::resource("/ready").route(web::get().to(ready_check)))
+            .service(
+                web::resource("/webhooks/whatsapp")
+                    .route(web::get().to(webhooks::whatsapp_subscribe))
+                    .route(web::post().to(webhooks::whatsapp_receipts))
+            )
+            .service(web::resource("/webhooks/sms").route(web::post().to(webhooks::sms_receipts)))
+            .service(web::resource("/webhooks/email").route(web::post().to(webhooks::email_receipts)))
     })
     .bind(&bind_addr)?
     .run()
@@ -0,0 +1,420 @@
+//! Sending notifications through provider APIs.
+//!
+//! Each organization configures a default [`ProviderConfiguration`] per
+//! channel. Email goes through a SendGrid v3 compatible API, SMS through a
+//! Twilio compatible API and WhatsApp through the WhatsApp Business Cloud
+//! API; `endpoint_url` overrides the public API address, e.g. for a regional
+//! or sandbox endpoint. A provider accepting a message only means it was
+//! sent: what became of it arrives later at the webhooks in
+//! [`crate::webhooks`], matched on the returned message id.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use infrastructure::external::whatsapp::WhatsAppClient;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::domain::*;
+use crate::service::{NotificationError, Result};
+
+const SENDGRID_API_URL: &str = "https://api.sendgrid.com";
+const TWILIO_API_URL: &str = "https://api.twilio.com";
+const WHATSAPP_API_URL: &str = "https://graph.facebook.com/v18.0";
+
+/// WhatsApp template for documents when the provider configuration names
+/// none in `document_template`
+const DEFAULT_DOCUMENT_TEMPLATE: &str = "report_delivery";
+
+/// `template_data` keys filling the body of the document template, unless
+/// the provider configuration lists others in `document_parameters`
+const DEFAULT_DOCUMENT_PARAMETERS: &[&str] = &["recipient_name", "report_number"];
+
+/// A message a provider accepted
+#[derive(Debug, Clone)]
+pub struct SentMessage {
+    pub provider_message_id: String,
+    pub response: serde_json::Value,
+}
+
+#[derive(Clone)]
+pub struct ProviderClient {
+    http: reqwest::Client,
+    /// Base URL under which providers reach this service's webhooks
+    public_url: String,
+    webhook_token: String,
+}
+
+impl ProviderClient {
+    pub fn new(public_url: &str, webhook_token: &str) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            http,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            webhook_token: webhook_token.to_string(),
+        }
+    }
+
+    /// Hand a notification to the provider. Channels without a provider API
+    /// (push and in-app) are only logged and return `None`.
+    pub async fn send(&self, notification: &Notification, provider: &ProviderConfiguration) -> Result<Option<SentMessage>> {
+        tracing::info!(
+            "Sending {:?} notification {} via {}",
+            notification.notification_channel,
+            notification.id,
+            provider.provider_name
+        );
+
+        match notification.notification_channel {
+            NotificationChannel::Email => self.send_email(notification, provider).await.map(Some),
+            NotificationChannel::Sms => self.send_sms(notification, provider).await.map(Some),
+            NotificationChannel::Whatsapp => self.send_whatsapp(notification, provider).await.map(Some),
+            NotificationChannel::Push | NotificationChannel::InApp => {
+                tracing::info!("{:?}: {}", notification.notification_channel, preview(&notification.content));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Status callback URL of a webhook, carrying the shared token
+    pub fn webhook_url(&self, kind: &str) -> String {
+        format!(
+            "{}/webhooks/{}?token={}",
+            self.public_url,
+            kind,
+            urlencoding::encode(&self.webhook_token)
+        )
+    }
+
+    async fn send_email(&self, notification: &Notification, provider: &ProviderConfiguration) -> Result<SentMessage> {
+        let api_key = required(provider, "api_key", &provider.api_key)?;
+        let from_email = required(provider, "from_email", &provider.from_email)?;
+
+        let mut recipient = json!({ "email": notification.recipient_contact });
+        if let Some(name) = &notification.recipient_name {
+            recipient["name"] = json!(name);
+        }
+
+        let mut content = vec![json!({ "type": "text/plain", "value": notification.content })];
+        if let Some(html) = &notification.html_content {
+            content.push(json!({ "type": "text/html", "value": html }));
+        }
+
+        let mut message = json!({
+            "personalizations": [{
+                "to": [recipient],
+                "custom_args": { "notification_id": notification.id.to_string() },
+            }],
+            "from": { "email": from_email },
+            "subject": notification.subject.as_deref().unwrap_or("Notification"),
+            "content": content,
+        });
+
+        if let Some(url) = &notification.attachment_url {
+            let (bytes, content_type) = self.fetch_attachment(url).await?;
+            message["attachments"] = json!([{
+                "content": STANDARD.encode(bytes),
+                "filename": attachment_name(notification),
+                "type": content_type,
+                "disposition": "attachment",
+            }]);
+        }
+
+        let url = format!("{}/v3/mail/send", api_url(provider, SENDGRID_API_URL));
+        let response = self.http
+            .post(&url)
+            .bearer_auth(api_key)
+            .json(&message)
+            .send()
+            .await
+            .map_err(|e| NotificationError::DeliveryFailed(format!("Email provider unreachable: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(NotificationError::DeliveryFailed(format!("Email provider returned {}: {}", status, body)));
+        }
+
+        // Accepted mail has no body; the id comes in a header
+        let message_id = response.headers()
+            .get("X-Message-Id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| NotificationError::DeliveryFailed("Email provider returned no message id".to_string()))?;
+
+        Ok(SentMessage {
+            response: json!({ "status": status.as_u16(), "message_id": message_id }),
+            provider_message_id: message_id,
+        })
+    }
+
+    async fn send_sms(&self, notification: &Notification, provider: &ProviderConfiguration) -> Result<SentMessage> {
+        let account_sid = required(provider, "api_key", &provider.api_key)?;
+        let auth_token = required(provider, "api_secret", &provider.api_secret)?;
+        let from_number = required(provider, "from_number", &provider.from_number)?;
+
+        let url = format!(
+            "{}/2010-04-01/Accounts/{}/Messages.json",
+            api_url(provider, TWILIO_API_URL),
+            account_sid
+        );
+        let callback = self.webhook_url("sms");
+
+        let response = self.http
+            .post(&url)
+            .basic_auth(account_sid, Some(auth_token))
+            .form(&[
+                ("To", notification.recipient_contact.as_str()),
+                ("From", from_number),
+                ("Body", notification.content.as_str()),
+                ("StatusCallback", callback.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| NotificationError::DeliveryFailed(format!("SMS provider unreachable: {}", e)))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let reason = body["message"].as_str().unwrap_or("no details");
+            return Err(NotificationError::DeliveryFailed(format!("SMS provider returned {}: {}", status, reason)));
+        }
+
+        let message_id = body["sid"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| NotificationError::DeliveryFailed("SMS provider returned no message id".to_string()))?;
+
+        Ok(SentMessage { provider_message_id: message_id, response: body })
+    }
+
+    async fn send_whatsapp(&self, notification: &Notification, provider: &ProviderConfiguration) -> Result<SentMessage> {
+        let access_token = required(provider, "api_key", &provider.api_key)?;
+        let phone_number_id = required(provider, "from_number", &provider.from_number)?;
+
+        let client = WhatsAppClient::new(
+            api_url(provider, WHATSAPP_API_URL).to_string(),
+            phone_number_id.to_string(),
+            access_token.to_string(),
+        );
+        let to = whatsapp_number(&notification.recipient_contact);
+
+        // Outside a conversation the recipient opened, only approved
+        // templates may be sent; documents go in a template header
+        let response = match &notification.attachment_url {
+            Some(link) => {
+                let template = configured_str(provider, "document_template").unwrap_or(DEFAULT_DOCUMENT_TEMPLATE);
+                let parameters = document_parameters(notification, provider)?;
+                client.send_document_template(&to, template, link, &attachment_name(notification), parameters).await
+            }
+            None => client.send_text(&to, &notification.content).await,
+        }
+        .map_err(|e| NotificationError::DeliveryFailed(e.to_string()))?;
+
+        let message_id = response.messages
+            .first()
+            .map(|message| message.id.clone())
+            .ok_or_else(|| NotificationError::DeliveryFailed("WhatsApp returned no message id".to_string()))?;
+
+        Ok(SentMessage {
+            response: json!({
+                "message_id": message_id,
+                "wa_id": response.contacts.first().map(|contact| contact.wa_id.clone()),
+            }),
+            provider_message_id: message_id,
+        })
+    }
+
+    async fn fetch_attachment(&self, url: &str) -> Result<(Vec<u8>, String)> {
+        let response = self.http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| NotificationError::DeliveryFailed(format!("Attachment unreachable: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(NotificationError::DeliveryFailed(format!("Attachment download returned {}", response.status())));
+        }
+
+        let content_type = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/pdf")
+            .to_string();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| NotificationError::DeliveryFailed(format!("Attachment download failed: {}", e)))?;
+
+        Ok((bytes.to_vec(), content_type))
+    }
+}
+
+fn required<'a>(provider: &ProviderConfiguration, field: &str, value: &'a Option<String>) -> Result<&'a str> {
+    value
+        .as_deref()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| NotificationError::ProviderNotConfigured(format!("{} has no {}", provider.provider_name, field)))
+}
+
+fn api_url<'a>(provider: &'a ProviderConfiguration, default: &'a str) -> &'a str {
+    provider.endpoint_url
+        .as_deref()
+        .filter(|url| !url.is_empty())
+        .unwrap_or(default)
+        .trim_end_matches('/')
+}
+
+fn configured_str<'a>(provider: &'a ProviderConfiguration, key: &str) -> Option<&'a str> {
+    provider.configuration.as_ref()?.get(key)?.as_str()
+}
+
+fn attachment_name(notification: &Notification) -> String {
+    notification.attachment_name.clone().unwrap_or_else(|| "attachment.pdf".to_string())
+}
+
+/// WhatsApp wants the number with country code in digits only
+pub fn whatsapp_number(contact: &str) -> String {
+    contact.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// Body parameters of the document template, in template order, taken from
+/// the notification's `template_data`
+pub fn document_parameters(notification: &Notification, provider: &ProviderConfiguration) -> Result<Vec<String>> {
+    let keys: Vec<&str> = provider.configuration
+        .as_ref()
+        .and_then(|configuration| configuration.get("document_parameters"))
+        .and_then(|keys| keys.as_array())
+        .map(|keys| keys.iter().filter_map(|key| key.as_str()).collect())
+        .unwrap_or_else(|| DEFAULT_DOCUMENT_PARAMETERS.to_vec());
+
+    keys.into_iter()
+        .map(|key| {
+            let value = notification.template_data.as_ref().and_then(|data| data.get(key));
+            match value {
+                Some(serde_json::Value::String(text)) if !text.is_empty() => Ok(text.clone()),
+                Some(value) if !value.is_null() && !value.is_string() => Ok(value.to_string()),
+                _ if key == "recipient_name" => notification.recipient_name
+                    .clone()
+                    .ok_or_else(|| missing_parameter(key)),
+                _ => Err(missing_parameter(key)),
+            }
+        })
+        .collect()
+}
+
+fn missing_parameter(key: &str) -> NotificationError {
+    NotificationError::DeliveryFailed(format!("Template parameter '{}' is missing from the template data", key))
+}
+
+fn preview(content: &str) -> String {
+    content.chars().take(50).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use uuid::Uuid;
+
+    fn notification(template_data: serde_json::Value) -> Notification {
+        Notification {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            template_id: None,
+            recipient_id: None,
+            recipient_type: None,
+            recipient_name: Some("Asha Rao".to_string()),
+            recipient_contact: "+91 98765-43210".to_string(),
+            notification_channel: NotificationChannel::Whatsapp,
+            notification_priority: None,
+            subject: None,
+            content: "Your report is ready".to_string(),
+            html_content: None,
+            attachment_url: Some("https://lab.example.com/reports/download?r=RPT-1".to_string()),
+            attachment_name: Some("RPT-1.pdf".to_string()),
+            template_data: Some(template_data),
+            scheduled_at: None,
+            sent_at: None,
+            delivered_at: None,
+            read_at: None,
+            notification_status: Some(NotificationStatus::Queued),
+            status_message: None,
+            provider_name: None,
+            provider_message_id: None,
+            provider_response: None,
+            retry_count: Some(0),
+            max_retries: Some(3),
+            last_retry_at: None,
+            reference_type: None,
+            reference_id: None,
+            metadata: None,
+            tags: None,
+            created_by: None,
+            created_at: Local::now().naive_local(),
+            updated_at: None,
+            is_deleted: Some(false),
+        }
+    }
+
+    fn provider(configuration: Option<serde_json::Value>) -> ProviderConfiguration {
+        ProviderConfiguration {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            provider_name: "WhatsApp Cloud".to_string(),
+            provider_type: NotificationChannel::Whatsapp,
+            api_key: Some("token".to_string()),
+            api_secret: None,
+            endpoint_url: None,
+            from_number: None,
+            from_email: None,
+            configuration,
+            is_active: Some(true),
+            is_default: Some(true),
+            daily_limit: None,
+            monthly_limit: None,
+            rate_limit_per_second: None,
+            created_by: Uuid::new_v4(),
+            created_at: Local::now().naive_local(),
+            updated_by: None,
+            updated_at: None,
+            is_deleted: Some(false),
+        }
+    }
+
+    #[test]
+    fn test_document_parameters_follow_configuration() {
+        let notification = notification(json!({ "report_number": "RPT-1", "page_count": 3 }));
+
+        let defaults = document_parameters(&notification, &provider(None)).unwrap();
+        assert_eq!(defaults, vec!["Asha Rao", "RPT-1"]);
+
+        let configured = provider(Some(json!({ "document_parameters": ["page_count", "report_number"] })));
+        assert_eq!(document_parameters(&notification, &configured).unwrap(), vec!["3", "RPT-1"]);
+
+        let missing = provider(Some(json!({ "document_parameters": ["lab_name"] })));
+        assert!(matches!(
+            document_parameters(&notification, &missing),
+            Err(NotificationError::DeliveryFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_provider_settings() {
+        assert_eq!(whatsapp_number("+91 98765-43210"), "919876543210");
+
+        let mut whatsapp = provider(None);
+        assert!(matches!(
+            required(&whatsapp, "from_number", &whatsapp.from_number),
+            Err(NotificationError::ProviderNotConfigured(_))
+        ));
+        assert_eq!(api_url(&whatsapp, WHATSAPP_API_URL), WHATSAPP_API_URL);
+        whatsapp.endpoint_url = Some("https://sandbox.example.com/v18.0/".to_string());
+        assert_eq!(api_url(&whatsapp, WHATSAPP_API_URL), "https://sandbox.example.com/v18.0");
+
+        let client = ProviderClient::new("https://lab.example.com/notifications/", "a&b");
+        assert_eq!(client.webhook_url("sms"), "https://lab.example.com/notifications/webhooks/sms?token=a%26b");
+    }
+}
@@ -1,4 +1,6 @@
 use crate::domain::*;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 use sqlx::PgPool;
 use uuid::Uuid;
 use chrono::{NaiveDateTime, NaiveDate, NaiveTime, Local};
@@ -185,9 +187,10 @@ impl NotificationRepository {
                 organization_id, template_id, recipient_id, recipient_type,
                 recipient_name, recipient_contact, notification_channel,
                 notification_priority, subject, content, template_data,
-                scheduled_at, reference_type, reference_id, created_by
+                scheduled_at, reference_type, reference_id, created_by,
+                attachment_url, attachment_name
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING *
             "#
         )
@@ -206,6 +209,8 @@ impl NotificationRepository {
         .bind(input.reference_type)
         .bind(input.reference_id)
         .bind(created_by)
+        .bind(input.attachment_url)
+        .bind(input.attachment_name)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
@@ -304,6 +309,115 @@ impl NotificationRepository {
         Ok(notification)
     }
 
+    /// Records the message a provider accepted for the notification
+    pub async fn mark_sent(
+        &self,
+        notification_id: Uuid,
+        provider_name: &str,
+        provider_message_id: &str,
+        provider_response: &serde_json::Value,
+    ) -> Result<Notification> {
+        let notification = sqlx::query_as::<_, Notification>(
+            r#"
+            UPDATE notification
+            SET notification_status = 'SENT',
+                status_message = NULL,
+                provider_name = $2,
+                provider_message_id = $3,
+                provider_response = $4,
+                sent_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND is_deleted = false
+            RETURNING *
+            "#
+        )
+        .bind(notification_id)
+        .bind(provider_name)
+        .bind(provider_message_id)
+        .bind(provider_response)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(notification)
+    }
+
+    /// Applies a provider receipt to the message it is about. Returns `None`
+    /// for unknown messages and for receipts that arrive after a later
+    /// status. When the receipt settles a referenced notification, the
+    /// outcome is published in the same transaction.
+    pub async fn apply_receipt(&self, receipt: &DeliveryReceipt) -> Result<Option<Notification>> {
+        let mut tx = self.pool.begin().await.map_err(|e| Error::Database(e.to_string()))?;
+
+        let current = sqlx::query_as::<_, Notification>(
+            r#"
+            SELECT * FROM notification
+            WHERE notification_channel = $1 AND provider_message_id = $2 AND is_deleted = false
+            FOR UPDATE
+            "#
+        )
+        .bind(receipt.channel)
+        .bind(&receipt.provider_message_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        let Some(current) = current else {
+            return Ok(None);
+        };
+        let previous = current.notification_status.unwrap_or(NotificationStatus::Pending);
+        if !previous.accepts_receipt(receipt.status) {
+            return Ok(None);
+        }
+
+        let notification = sqlx::query_as::<_, Notification>(
+            r#"
+            UPDATE notification
+            SET notification_status = $2,
+                status_message = COALESCE($3, status_message),
+                provider_response = $4,
+                sent_at = COALESCE(sent_at, CURRENT_TIMESTAMP),
+                delivered_at = CASE WHEN $2 IN ('DELIVERED', 'READ') THEN COALESCE(delivered_at, CURRENT_TIMESTAMP) ELSE delivered_at END,
+                read_at = CASE WHEN $2 = 'READ' THEN CURRENT_TIMESTAMP ELSE read_at END,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(current.id)
+        .bind(receipt.status)
+        .bind(&receipt.message)
+        .bind(&receipt.payload)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        // Reading a delivered message settles nothing new
+        let settled = receipt.status.is_outcome() && !previous.is_outcome();
+        if settled && notification.reference_type.is_some() {
+            let event_type = match receipt.status {
+                NotificationStatus::Delivered | NotificationStatus::Read => events::NOTIFICATION_DELIVERED,
+                _ => events::NOTIFICATION_FAILED,
+            };
+            outbox::enqueue_event(
+                &mut *tx,
+                topics::NOTIFICATION_EVENTS,
+                event_type,
+                "Notification",
+                notification.id,
+                notification.organization_id,
+                None,
+                &NotificationOutcome::from(&notification),
+            )
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        }
+
+        tx.commit().await.map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(Some(notification))
+    }
+
     pub async fn increment_retry(&self, notification_id: Uuid) -> Result<Notification> {
         let notification = sqlx::query_as::<_, Notification>(
             "UPDATE notification SET retry_count = retry_count + 1, last_retry_at = CURRENT_TIMESTAMP WHERE id = $1 AND is_deleted = false RETURNING *"
@@ -524,3 +638,258 @@ impl ProviderConfigurationRepository {
         Ok(config)
     }
 }
+
+// ============================================================================
+// Critical Call Tree Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct CriticalCallTreeRepository {
+    pool: PgPool,
+}
+
+impl CriticalCallTreeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, input: CreateCallTreeLevelInput, created_by: Uuid) -> Result<CriticalCallTreeLevel> {
+        let level = sqlx::query_as::<_, CriticalCallTreeLevel>(
+            r#"
+            INSERT INTO critical_call_tree_level (
+                organization_id, department, level_number, recipient_type,
+                recipient_id, recipient_name, recipient_contact,
+                notification_channel, wait_minutes, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#
+        )
+        .bind(input.organization_id)
+        .bind(input.department)
+        .bind(input.level_number)
+        .bind(input.recipient_type)
+        .bind(input.recipient_id)
+        .bind(input.recipient_name)
+        .bind(input.recipient_contact)
+        .bind(input.notification_channel)
+        .bind(input.wait_minutes)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(level)
+    }
+
+    /// Active levels of every department's tree for the organization
+    pub async fn list_active(&self, organization_id: Uuid) -> Result<Vec<CriticalCallTreeLevel>> {
+        let levels = sqlx::query_as::<_, CriticalCallTreeLevel>(
+            r#"
+            SELECT * FROM critical_call_tree_level
+            WHERE organization_id = $1 AND is_active = true
+            ORDER BY department NULLS FIRST, level_number
+            "#
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(levels)
+    }
+
+    pub async fn deactivate(&self, level_id: Uuid) -> Result<CriticalCallTreeLevel> {
+        let level = sqlx::query_as::<_, CriticalCallTreeLevel>(
+            "UPDATE critical_call_tree_level SET is_active = false, updated_at = CURRENT_TIMESTAMP WHERE id = $1 RETURNING *"
+        )
+        .bind(level_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?
+        .ok_or_else(|| Error::NotFound(format!("Call tree level {} not found", level_id)))?;
+
+        Ok(level)
+    }
+}
+
+// ============================================================================
+// Critical Alert Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct CriticalAlertRepository {
+    pool: PgPool,
+}
+
+impl CriticalAlertRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Opens an alert for a detected critical value. Returns `None` when the
+    /// event was already handled. The alert is due again after
+    /// `retry_minutes`, so it is still paged if its first page never completes.
+    pub async fn create(&self, alert: &NewCriticalAlert, retry_minutes: i32) -> Result<Option<CriticalAlert>> {
+        let alert = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            INSERT INTO critical_alert (
+                organization_id, event_id, result_id, result_number, department,
+                ordering_clinician_id, ordering_clinician_name, message, payload,
+                next_escalation_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP + make_interval(mins => $10))
+            ON CONFLICT (event_id) DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(alert.organization_id)
+        .bind(alert.event_id)
+        .bind(alert.result_id)
+        .bind(&alert.result_number)
+        .bind(&alert.department)
+        .bind(alert.ordering_clinician_id)
+        .bind(&alert.ordering_clinician_name)
+        .bind(&alert.message)
+        .bind(&alert.payload)
+        .bind(retry_minutes)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(alert)
+    }
+
+    pub async fn get_by_id(&self, alert_id: Uuid) -> Result<CriticalAlert> {
+        let alert = sqlx::query_as::<_, CriticalAlert>(
+            "SELECT * FROM critical_alert WHERE id = $1"
+        )
+        .bind(alert_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?
+        .ok_or_else(|| Error::NotFound(format!("Critical alert {} not found", alert_id)))?;
+
+        Ok(alert)
+    }
+
+    pub async fn list(&self, organization_id: Uuid, status: Option<CriticalAlertStatus>, limit: i64) -> Result<Vec<CriticalAlert>> {
+        let alerts = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            SELECT * FROM critical_alert
+            WHERE organization_id = $1
+            AND ($2::critical_alert_status IS NULL OR alert_status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(organization_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(alerts)
+    }
+
+    /// Takes alerts whose wait has run out. Their escalation time moves
+    /// `retry_minutes` ahead so a concurrent worker does not page the same
+    /// level twice, and a claim whose page never completes is taken again.
+    pub async fn claim_due(&self, limit: i64, retry_minutes: i32) -> Result<Vec<CriticalAlert>> {
+        let alerts = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            UPDATE critical_alert
+            SET next_escalation_at = CURRENT_TIMESTAMP + make_interval(mins => $2),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id IN (
+                SELECT id FROM critical_alert
+                WHERE alert_status = 'PAGING'
+                AND next_escalation_at <= CURRENT_TIMESTAMP
+                ORDER BY next_escalation_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#
+        )
+        .bind(limit)
+        .bind(retry_minutes)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(alerts)
+    }
+
+    pub async fn record_page(
+        &self,
+        alert_id: Uuid,
+        level_number: i32,
+        notification_id: Uuid,
+        wait_minutes: i32,
+    ) -> Result<CriticalAlert> {
+        let alert = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            UPDATE critical_alert
+            SET current_level = $2,
+                last_notification_id = $3,
+                last_paged_at = CURRENT_TIMESTAMP,
+                next_escalation_at = CURRENT_TIMESTAMP + make_interval(mins => $4),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(alert_id)
+        .bind(level_number)
+        .bind(notification_id)
+        .bind(wait_minutes)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(alert)
+    }
+
+    pub async fn mark_exhausted(&self, alert_id: Uuid) -> Result<CriticalAlert> {
+        let alert = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            UPDATE critical_alert
+            SET alert_status = 'EXHAUSTED', next_escalation_at = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND alert_status = 'PAGING'
+            RETURNING *
+            "#
+        )
+        .bind(alert_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?
+        .ok_or_else(|| Error::NotFound(format!("Paging critical alert {} not found", alert_id)))?;
+
+        Ok(alert)
+    }
+
+    /// Stops escalation for every open alert on the result
+    pub async fn acknowledge_by_result(&self, result_id: Uuid, acknowledged_by: Option<&str>) -> Result<Vec<CriticalAlert>> {
+        let alerts = sqlx::query_as::<_, CriticalAlert>(
+            r#"
+            UPDATE critical_alert
+            SET alert_status = 'ACKNOWLEDGED',
+                acknowledged_at = CURRENT_TIMESTAMP,
+                acknowledged_by = $2,
+                next_escalation_at = NULL,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE result_id = $1 AND alert_status <> 'ACKNOWLEDGED'
+            RETURNING *
+            "#
+        )
+        .bind(result_id)
+        .bind(acknowledged_by)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(alerts)
+    }
+}
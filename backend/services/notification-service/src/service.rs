@@ -1,4 +1,5 @@
 use crate::domain::*;
+use crate::providers::ProviderClient;
 use crate::repository::*;
 use uuid::Uuid;
 use std::collections::HashMap;
@@ -40,6 +41,18 @@ impl From<Error> for NotificationError {
     }
 }
 
+/// Only invalid requests are final; delivery, provider and channel failures
+/// and quiet hours may clear up, so they are retried
+impl From<NotificationError> for common::error::Error {
+    fn from(error: NotificationError) -> Self {
+        match error {
+            NotificationError::ValidationError(msg) => Self::Validation(msg),
+            NotificationError::NotFound(msg) => Self::NotFound(msg),
+            other => Self::ExternalService(other.to_string()),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, NotificationError>;
 
 #[derive(Clone)]
@@ -49,6 +62,7 @@ pub struct NotificationService {
     preference_repo: NotificationPreferenceRepository,
     log_repo: NotificationLogRepository,
     provider_repo: ProviderConfigurationRepository,
+    providers: ProviderClient,
 }
 
 impl NotificationService {
@@ -58,6 +72,7 @@ impl NotificationService {
         preference_repo: NotificationPreferenceRepository,
         log_repo: NotificationLogRepository,
         provider_repo: ProviderConfigurationRepository,
+        providers: ProviderClient,
     ) -> Self {
         Self {
             template_repo,
@@ -65,6 +80,7 @@ impl NotificationService {
             preference_repo,
             log_repo,
             provider_repo,
+            providers,
         }
     }
 
@@ -221,9 +237,23 @@ impl NotificationService {
                     None,
                 ).await?;
 
-                // Send via provider (simplified - in production, use actual provider APIs)
-                match self.send_via_provider(&notification, &provider).await {
-                    Ok(_) => {
+                match self.providers.send(&notification, &provider).await {
+                    Ok(Some(sent)) => {
+                        // Delivery receipts find the notification by this id
+                        notification = self.notification_repo.mark_sent(
+                            notification.id,
+                            &provider.provider_name,
+                            &sent.provider_message_id,
+                            &sent.response,
+                        ).await?;
+
+                        let _ = self.log_repo.log_event(
+                            notification.id,
+                            "SENT",
+                            Some(format!("Accepted by {} as {}", provider.provider_name, sent.provider_message_id)),
+                        ).await;
+                    },
+                    Ok(None) => {
                         // Update status to sent
                         notification = self.notification_repo.update_status(
                             notification.id,
@@ -260,46 +290,6 @@ impl NotificationService {
         Ok(notification)
     }
 
-    async fn send_via_provider(&self, notification: &Notification, provider: &ProviderConfiguration) -> Result<()> {
-        // In production, this would integrate with actual providers:
-        // - Twilio for SMS
-        // - SendGrid for Email
-        // - WhatsApp Business API for WhatsApp
-        // - FCM for Push notifications
-
-        tracing::info!(
-            "Sending {:?} notification via {} to {}",
-            notification.notification_channel,
-            provider.provider_name,
-            notification.recipient_contact
-        );
-
-        // Simulate sending
-        match notification.notification_channel {
-            NotificationChannel::Email => {
-                // Would use SendGrid API
-                tracing::info!("Email: Subject: {:?}, Body: {}", notification.subject, &notification.content[..50.min(notification.content.len())]);
-            },
-            NotificationChannel::Sms => {
-                // Would use Twilio API
-                tracing::info!("SMS: {}", &notification.content[..50.min(notification.content.len())]);
-            },
-            NotificationChannel::Whatsapp => {
-                // Would use WhatsApp Business API
-                tracing::info!("WhatsApp: {}", &notification.content[..50.min(notification.content.len())]);
-            },
-            NotificationChannel::Push => {
-                // Would use FCM
-                tracing::info!("Push: {}", &notification.content[..50.min(notification.content.len())]);
-            },
-            NotificationChannel::InApp => {
-                tracing::info!("In-App: {}", notification.content);
-            }
-        }
-
-        Ok(())
-    }
-
     fn replace_variables(&self, template: &str, data: &HashMap<String, serde_json::Value>) -> String {
         let mut result = template.to_string();
         for (key, value) in data {
@@ -337,6 +327,25 @@ impl NotificationService {
         self.process_notification(notification).await
     }
 
+    /// Apply a delivery receipt from a provider webhook. Returns `None` when
+    /// the receipt is about an unknown message or is out of date.
+    pub async fn record_receipt(&self, receipt: &DeliveryReceipt) -> Result<Option<Notification>> {
+        let Some(notification) = self.notification_repo.apply_receipt(receipt).await? else {
+            tracing::debug!(
+                "Ignored {:?} receipt for {:?} message {}",
+                receipt.status,
+                receipt.channel,
+                receipt.provider_message_id
+            );
+            return Ok(None);
+        };
+
+        let event_type = format!("{:?}", receipt.status).to_uppercase();
+        let _ = self.log_repo.log_event(notification.id, &event_type, receipt.message.clone()).await;
+
+        Ok(Some(notification))
+    }
+
     // ============================================================================
     // Preference Operations
     // ============================================================================
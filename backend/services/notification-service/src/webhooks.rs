@@ -0,0 +1,313 @@
+//! Delivery receipts from provider webhooks.
+//!
+//! Providers call back when a sent message is delivered, read or fails.
+//! WhatsApp signs its calls with the app secret; the SMS and email
+//! providers are given callback URLs carrying a shared token instead (see
+//! [`crate::providers::ProviderClient::webhook_url`]). Receipts are matched
+//! to notifications by provider message id and, once they settle a
+//! notification, published to the service that asked for it.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::tenant::Tenant;
+use infrastructure::external::whatsapp::{self, MessageStatus, WebhookPayload};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::domain::{DeliveryReceipt, NotificationChannel, NotificationStatus};
+use crate::service::NotificationService;
+
+/// Secrets the webhooks check calls against
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// Shared token of the SMS and email callback URLs
+    pub token: String,
+    /// App secret WhatsApp signs webhook calls with
+    pub whatsapp_app_secret: String,
+    /// Token WhatsApp presents when the webhook is subscribed
+    pub whatsapp_verify_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenQuery {
+    token: Option<String>,
+}
+
+/// WhatsApp subscription handshake: echo the challenge for our token
+pub async fn whatsapp_subscribe(
+    config: web::Data<WebhookConfig>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let subscribing = query.get("hub.mode").map(String::as_str) == Some("subscribe");
+    let token = query.get("hub.verify_token").map(String::as_str).unwrap_or_default();
+
+    match query.get("hub.challenge") {
+        Some(challenge) if subscribing && same_secret(token, &config.whatsapp_verify_token) => {
+            HttpResponse::Ok().content_type("text/plain").body(challenge.clone())
+        }
+        _ => HttpResponse::Forbidden().finish(),
+    }
+}
+
+pub async fn whatsapp_receipts(
+    service: web::Data<NotificationService>,
+    config: web::Data<WebhookConfig>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> HttpResponse {
+    let signature = req.headers()
+        .get("X-Hub-Signature-256")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if config.whatsapp_app_secret.is_empty()
+        || !whatsapp::verify_webhook_signature(&config.whatsapp_app_secret, &body, signature)
+    {
+        tracing::warn!("Rejected WhatsApp webhook call with a bad signature");
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let receipts = payload.statuses().filter_map(whatsapp_receipt).collect();
+    record(&service, receipts).await
+}
+
+pub async fn sms_receipts(
+    service: web::Data<NotificationService>,
+    config: web::Data<WebhookConfig>,
+    query: web::Query<TokenQuery>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    if !has_token(&config, &query) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let receipts = sms_receipt(&form).into_iter().collect();
+    record(&service, receipts).await
+}
+
+pub async fn email_receipts(
+    service: web::Data<NotificationService>,
+    config: web::Data<WebhookConfig>,
+    query: web::Query<TokenQuery>,
+    events: web::Json<Vec<serde_json::Value>>,
+) -> HttpResponse {
+    if !has_token(&config, &query) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let receipts = events.iter().filter_map(email_receipt).collect();
+    record(&service, receipts).await
+}
+
+/// Failures make the provider call again, so receipts are not lost while
+/// the database is unavailable
+async fn record(service: &NotificationService, receipts: Vec<DeliveryReceipt>) -> HttpResponse {
+    // Receipts of every organization arrive here
+    let recorded = Tenant::System
+        .scope(async {
+            for receipt in &receipts {
+                service.record_receipt(receipt).await?;
+            }
+            Ok::<_, crate::service::NotificationError>(())
+        })
+        .await;
+
+    match recorded {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            tracing::error!("Failed to record delivery receipts: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+fn has_token(config: &WebhookConfig, query: &TokenQuery) -> bool {
+    let valid = query.token.as_deref().is_some_and(|token| same_secret(token, &config.token));
+    if !valid {
+        tracing::warn!("Rejected webhook call without a valid token");
+    }
+    valid
+}
+
+/// Compares secrets in time independent of where they differ
+fn same_secret(given: &str, expected: &str) -> bool {
+    !expected.is_empty()
+        && given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+pub fn whatsapp_receipt(status: &MessageStatus) -> Option<DeliveryReceipt> {
+    let notification_status = match status.status.as_str() {
+        "sent" => NotificationStatus::Sent,
+        "delivered" => NotificationStatus::Delivered,
+        "read" => NotificationStatus::Read,
+        "failed" => NotificationStatus::Failed,
+        _ => return None,
+    };
+
+    let message = status.errors.first().map(|error| {
+        let detail = error.message.as_deref().or(error.title.as_deref()).unwrap_or("Unknown error");
+        match error.code {
+            Some(code) => format!("WhatsApp error {}: {}", code, detail),
+            None => format!("WhatsApp error: {}", detail),
+        }
+    });
+
+    Some(DeliveryReceipt {
+        channel: NotificationChannel::Whatsapp,
+        provider_message_id: status.id.clone(),
+        status: notification_status,
+        message,
+        payload: serde_json::to_value(status).unwrap_or_default(),
+    })
+}
+
+/// A Twilio style status callback (`MessageSid`, `MessageStatus`)
+pub fn sms_receipt(form: &HashMap<String, String>) -> Option<DeliveryReceipt> {
+    let notification_status = match form.get("MessageStatus")?.as_str() {
+        "sent" => NotificationStatus::Sent,
+        "delivered" => NotificationStatus::Delivered,
+        "read" => NotificationStatus::Read,
+        "undelivered" | "failed" => NotificationStatus::Failed,
+        _ => return None,
+    };
+
+    let message = form.get("ErrorCode").map(|code| match form.get("ErrorMessage") {
+        Some(detail) => format!("SMS error {}: {}", code, detail),
+        None => format!("SMS error {}", code),
+    });
+
+    Some(DeliveryReceipt {
+        channel: NotificationChannel::Sms,
+        provider_message_id: form.get("MessageSid")?.clone(),
+        status: notification_status,
+        message,
+        payload: serde_json::to_value(form).unwrap_or_default(),
+    })
+}
+
+/// A SendGrid style event. Its `sg_message_id` is the id returned when the
+/// mail was accepted, followed by a dot and a per-recipient suffix.
+pub fn email_receipt(event: &serde_json::Value) -> Option<DeliveryReceipt> {
+    let notification_status = match event.get("event")?.as_str()? {
+        "delivered" => NotificationStatus::Delivered,
+        "open" => NotificationStatus::Read,
+        "bounce" => NotificationStatus::Bounced,
+        "dropped" => NotificationStatus::Failed,
+        _ => return None,
+    };
+
+    let message_id = event.get("sg_message_id")?.as_str()?.split('.').next()?;
+    let message = event.get("reason").and_then(|reason| reason.as_str()).map(str::to_string);
+
+    Some(DeliveryReceipt {
+        channel: NotificationChannel::Email,
+        provider_message_id: message_id.to_string(),
+        status: notification_status,
+        message,
+        payload: event.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_whatsapp_statuses_become_receipts() {
+        let body = json!({
+            "object": "whatsapp_business_account",
+            "entry": [{
+                "id": "1",
+                "changes": [{
+                    "field": "messages",
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "statuses": [
+                            { "id": "wamid.A", "status": "delivered", "timestamp": "1737000000", "recipient_id": "919876543210" },
+                            { "id": "wamid.B", "status": "failed", "errors": [{ "code": 131026, "title": "Message undeliverable" }] },
+                            { "id": "wamid.C", "status": "deleted" }
+                        ]
+                    }
+                }]
+            }]
+        })
+        .to_string();
+
+        let secret = "app-secret";
+        let signature = {
+            use hmac::{Hmac, Mac};
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+            mac.update(body.as_bytes());
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        };
+        assert!(whatsapp::verify_webhook_signature(secret, body.as_bytes(), &signature));
+        assert!(!whatsapp::verify_webhook_signature("other", body.as_bytes(), &signature));
+        assert!(!whatsapp::verify_webhook_signature(secret, b"{}", &signature));
+        assert!(!whatsapp::verify_webhook_signature(secret, body.as_bytes(), "sha256=zz"));
+
+        let payload: WebhookPayload = serde_json::from_str(&body).unwrap();
+        let receipts: Vec<_> = payload.statuses().filter_map(whatsapp_receipt).collect();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].provider_message_id, "wamid.A");
+        assert_eq!(receipts[0].status, NotificationStatus::Delivered);
+        assert_eq!(receipts[1].status, NotificationStatus::Failed);
+        assert_eq!(receipts[1].message.as_deref(), Some("WhatsApp error 131026: Message undeliverable"));
+    }
+
+    #[test]
+    fn test_sms_and_email_receipts() {
+        let form: HashMap<String, String> = [
+            ("MessageSid", "SM123"),
+            ("MessageStatus", "undelivered"),
+            ("ErrorCode", "30003"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let receipt = sms_receipt(&form).unwrap();
+        assert_eq!(receipt.channel, NotificationChannel::Sms);
+        assert_eq!(receipt.provider_message_id, "SM123");
+        assert_eq!(receipt.status, NotificationStatus::Failed);
+        assert_eq!(receipt.message.as_deref(), Some("SMS error 30003"));
+
+        let bounce = json!({
+            "event": "bounce",
+            "sg_message_id": "14c5d75ce93.dfd.64b469.filter0001.16648.5515E0B88.0",
+            "reason": "550 5.1.1 User unknown",
+        });
+        let receipt = email_receipt(&bounce).unwrap();
+        assert_eq!(receipt.provider_message_id, "14c5d75ce93");
+        assert_eq!(receipt.status, NotificationStatus::Bounced);
+        assert_eq!(receipt.message.as_deref(), Some("550 5.1.1 User unknown"));
+
+        assert!(email_receipt(&json!({ "event": "processed", "sg_message_id": "x.y" })).is_none());
+    }
+
+    #[test]
+    fn test_receipts_do_not_move_back() {
+        use NotificationStatus::*;
+
+        assert!(Sent.accepts_receipt(Delivered));
+        assert!(Delivered.accepts_receipt(Read));
+        assert!(Sent.accepts_receipt(Failed));
+        assert!(!Delivered.accepts_receipt(Sent));
+        assert!(!Delivered.accepts_receipt(Failed));
+        assert!(!Read.accepts_receipt(Delivered));
+        assert!(!Failed.accepts_receipt(Delivered));
+        assert!(!Sent.accepts_receipt(Pending));
+    }
+
+    #[test]
+    fn test_secrets_compare_exactly() {
+        assert!(same_secret("token", "token"));
+        assert!(!same_secret("token", "tokeN"));
+        assert!(!same_secret("toke", "token"));
+        assert!(!same_secret("", ""));
+    }
+}
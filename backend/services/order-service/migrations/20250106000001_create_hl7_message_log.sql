@@ -0,0 +1,62 @@
+-- HL7 v2 interface message log
+-- Every inbound message and the acknowledgment we returned is persisted so
+-- that failed messages can be inspected and replayed.
+
+ALTER TYPE order_source ADD VALUE 'HL7';
+
+-- Sending systems (HIS/EMR) allowed to place orders, each for one organization.
+-- The MLLP listener only serves peers registered here.
+CREATE TABLE hl7_interface (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,
+
+    interface_name VARCHAR(100) NOT NULL,
+    peer_address VARCHAR(255) NOT NULL,        -- IP address the sender connects from
+    order_user_id UUID NOT NULL,               -- Account recorded as creator of its orders
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    -- Audit
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    created_by UUID NOT NULL,
+
+    CONSTRAINT unique_hl7_interface_peer UNIQUE (peer_address)
+);
+
+CREATE INDEX idx_hl7_interface_organization ON hl7_interface(organization_id);
+
+CREATE TRIGGER update_hl7_interface_updated_at BEFORE UPDATE ON hl7_interface
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE hl7_message_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,             -- Organization of the interface it arrived on
+
+    -- Message Identification
+    direction VARCHAR(20) NOT NULL,            -- INBOUND, OUTBOUND
+    message_type VARCHAR(20),                  -- e.g. OML^O21, ORU^R01
+    message_control_id VARCHAR(100),
+    peer_address VARCHAR(255),
+
+    -- Content
+    raw_message TEXT NOT NULL,
+    ack_message TEXT,
+    ack_code VARCHAR(10),                      -- AA, AE, AR
+
+    -- Processing
+    status VARCHAR(20) NOT NULL DEFAULT 'RECEIVED', -- RECEIVED, PROCESSED, FAILED, SENT, ACKNOWLEDGED, REJECTED
+    error_message TEXT,
+    related_entity_id UUID,                    -- Order created from the message
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+
+    -- Audit
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    processed_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_hl7_direction CHECK (direction IN ('INBOUND', 'OUTBOUND'))
+);
+
+CREATE INDEX idx_hl7_message_log_organization ON hl7_message_log(organization_id);
+CREATE INDEX idx_hl7_message_log_control_id ON hl7_message_log(message_control_id);
+CREATE INDEX idx_hl7_message_log_status ON hl7_message_log(status);
+CREATE INDEX idx_hl7_message_log_created ON hl7_message_log(created_at);
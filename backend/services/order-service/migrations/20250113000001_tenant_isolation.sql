@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('hl7_interface');
+SELECT enable_tenant_isolation('hl7_message_log');
+SELECT enable_tenant_isolation('test_order');
+SELECT enable_tenant_isolation('test_price');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('order_status_history', 'order_id', 'test_order');
+SELECT enable_parent_isolation('test_order_item', 'order_id', 'test_order');
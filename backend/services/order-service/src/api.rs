@@ -1,9 +1,12 @@
 use async_graphql::{Context, Object, Result, SimpleObject, InputObject, Enum, ID};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 
 use crate::domain::*;
 use crate::service::OrderService;
+use crate::hl7_interface::Hl7InterfaceEngine;
 use common::types::{OrderStatus, Priority};
 
 // ============================================================================
@@ -482,6 +485,94 @@ impl TryFrom<UpdateOrderStatusInputGQL> for UpdateOrderStatusInput {
     }
 }
 
+#[derive(SimpleObject)]
+pub struct Hl7InterfaceGQL {
+    pub id: ID,
+    pub interface_name: String,
+    pub peer_address: String,
+    pub order_user_id: ID,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Hl7Interface> for Hl7InterfaceGQL {
+    fn from(interface: Hl7Interface) -> Self {
+        Self {
+            id: interface.id.to_string().into(),
+            interface_name: interface.interface_name,
+            peer_address: interface.peer_address,
+            order_user_id: interface.order_user_id.to_string().into(),
+            is_active: interface.is_active,
+            created_at: interface.created_at.to_rfc3339(),
+            updated_at: interface.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct RegisterHl7InterfaceInputGQL {
+    pub interface_name: String,
+    /// IP address the sending system connects from
+    pub peer_address: String,
+    /// User recorded as the creator of orders received on the interface
+    pub order_user_id: ID,
+}
+
+impl TryFrom<RegisterHl7InterfaceInputGQL> for RegisterHl7InterfaceInput {
+    type Error = String;
+
+    fn try_from(input: RegisterHl7InterfaceInputGQL) -> std::result::Result<Self, Self::Error> {
+        let order_user_id = Uuid::parse_str(&input.order_user_id)
+            .map_err(|e| format!("Invalid order_user_id: {}", e))?;
+
+        Ok(RegisterHl7InterfaceInput {
+            interface_name: input.interface_name,
+            peer_address: input.peer_address,
+            order_user_id,
+        })
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Hl7MessageLogGQL {
+    pub id: ID,
+    pub direction: String,
+    pub message_type: Option<String>,
+    pub message_control_id: Option<String>,
+    pub peer_address: Option<String>,
+    pub raw_message: String,
+    pub ack_message: Option<String>,
+    pub ack_code: Option<String>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub related_entity_id: Option<ID>,
+    pub attempt_count: i32,
+    pub created_at: String,
+    pub processed_at: Option<String>,
+}
+
+impl From<Hl7MessageLog> for Hl7MessageLogGQL {
+    fn from(log: Hl7MessageLog) -> Self {
+        Self {
+            id: log.id.to_string().into(),
+            direction: log.direction,
+            message_type: log.message_type,
+            message_control_id: log.message_control_id,
+            peer_address: log.peer_address,
+            raw_message: log.raw_message,
+            ack_message: log.ack_message,
+            ack_code: log.ack_code,
+            status: log.status,
+            error_message: log.error_message,
+            related_entity_id: log.related_entity_id.map(|id| id.to_string().into()),
+            attempt_count: log.attempt_count,
+            created_at: log.created_at.to_rfc3339(),
+            processed_at: log.processed_at.map(|dt| dt.to_rfc3339()),
+        }
+    }
+}
+
 // ============================================================================
 // GraphQL Query Root
 // ============================================================================
@@ -491,6 +582,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get test by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn test(&self, ctx: &Context<'_>, id: ID) -> Result<Option<TestCatalogGQL>> {
         let service = ctx.data::<OrderService>()?;
         let test_id = Uuid::parse_str(&id)?;
@@ -502,6 +594,7 @@ impl QueryRoot {
     }
 
     /// Get test by code
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn test_by_code(&self, ctx: &Context<'_>, code: String) -> Result<Option<TestCatalogGQL>> {
         let service = ctx.data::<OrderService>()?;
 
@@ -512,6 +605,7 @@ impl QueryRoot {
     }
 
     /// Search tests
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn search_tests(
         &self,
         ctx: &Context<'_>,
@@ -540,6 +634,7 @@ impl QueryRoot {
     }
 
     /// Get all active tests
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn all_active_tests(&self, ctx: &Context<'_>, limit: Option<i32>) -> Result<Vec<TestCatalogGQL>> {
         let service = ctx.data::<OrderService>()?;
         let tests = service.get_all_active_tests(limit.unwrap_or(100) as i64).await?;
@@ -547,6 +642,7 @@ impl QueryRoot {
     }
 
     /// Get panel by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn panel(&self, ctx: &Context<'_>, id: ID) -> Result<Option<TestPanelGQL>> {
         let service = ctx.data::<OrderService>()?;
         let panel_id = Uuid::parse_str(&id)?;
@@ -558,6 +654,7 @@ impl QueryRoot {
     }
 
     /// Get panel tests
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn panel_tests(&self, ctx: &Context<'_>, panel_id: ID) -> Result<Vec<TestCatalogGQL>> {
         let service = ctx.data::<OrderService>()?;
         let id = Uuid::parse_str(&panel_id)?;
@@ -566,6 +663,7 @@ impl QueryRoot {
     }
 
     /// Get popular panels
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn popular_panels(&self, ctx: &Context<'_>, limit: Option<i32>) -> Result<Vec<TestPanelGQL>> {
         let service = ctx.data::<OrderService>()?;
         let panels = service.get_popular_panels(limit.unwrap_or(10) as i64).await?;
@@ -573,6 +671,7 @@ impl QueryRoot {
     }
 
     /// Get order by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn order(&self, ctx: &Context<'_>, id: ID) -> Result<Option<TestOrderGQL>> {
         let service = ctx.data::<OrderService>()?;
         let order_id = Uuid::parse_str(&id)?;
@@ -583,7 +682,20 @@ impl QueryRoot {
         }
     }
 
+    /// Get orders by IDs; unknown IDs are skipped
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
+    async fn orders_by_ids(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<Vec<TestOrderGQL>> {
+        let service = ctx.data::<OrderService>()?;
+        let ids = ids
+            .iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let orders = service.get_orders_by_ids(&ids).await?;
+        Ok(orders.into_iter().map(|o| o.into()).collect())
+    }
+
     /// Get order by order number
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn order_by_number(&self, ctx: &Context<'_>, order_number: String) -> Result<Option<TestOrderGQL>> {
         let service = ctx.data::<OrderService>()?;
 
@@ -594,6 +706,7 @@ impl QueryRoot {
     }
 
     /// Get orders by patient
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn orders_by_patient(&self, ctx: &Context<'_>, patient_id: ID, limit: Option<i32>) -> Result<Vec<TestOrderGQL>> {
         let service = ctx.data::<OrderService>()?;
         let id = Uuid::parse_str(&patient_id)?;
@@ -602,12 +715,31 @@ impl QueryRoot {
     }
 
     /// Get order items
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_READ)")]
     async fn order_items(&self, ctx: &Context<'_>, order_id: ID) -> Result<Vec<TestOrderItemGQL>> {
         let service = ctx.data::<OrderService>()?;
         let id = Uuid::parse_str(&order_id)?;
         let items = service.get_order_items(id).await?;
         Ok(items.into_iter().map(|i| i.into()).collect())
     }
+
+    /// List logged HL7 messages, optionally filtered by status (RECEIVED, PROCESSED, FAILED)
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn hl7_messages(&self, ctx: &Context<'_>, status: Option<String>, limit: Option<i32>) -> Result<Vec<Hl7MessageLogGQL>> {
+        let engine = ctx.data::<Hl7InterfaceEngine>()?;
+        let org_id = ctx.organization_id()?;
+        let messages = engine.list_messages(org_id, status.as_deref(), limit.unwrap_or(50) as i64).await?;
+        Ok(messages.into_iter().map(|m| m.into()).collect())
+    }
+
+    /// List the sending systems registered to place orders over HL7
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn hl7_interfaces(&self, ctx: &Context<'_>) -> Result<Vec<Hl7InterfaceGQL>> {
+        let engine = ctx.data::<Hl7InterfaceEngine>()?;
+        let org_id = ctx.organization_id()?;
+        let interfaces = engine.list_interfaces(org_id).await?;
+        Ok(interfaces.into_iter().map(|i| i.into()).collect())
+    }
 }
 
 // ============================================================================
@@ -619,12 +751,12 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create new order
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_CREATE)")]
     async fn create_order(&self, ctx: &Context<'_>, input: CreateOrderInputGQL) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
 
-        // TODO: Get org_id and user_id from auth context
-        let org_id = Uuid::nil();
-        let user_id = Uuid::nil();
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -634,6 +766,7 @@ impl MutationRoot {
     }
 
     /// Add test or panel to order
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_UPDATE)")]
     async fn add_test_to_order(&self, ctx: &Context<'_>, input: AddTestToOrderInputGQL) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
 
@@ -645,6 +778,7 @@ impl MutationRoot {
     }
 
     /// Remove item from order
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_UPDATE)")]
     async fn remove_item_from_order(&self, ctx: &Context<'_>, order_id: ID, item_id: ID) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
         let oid = Uuid::parse_str(&order_id)?;
@@ -655,11 +789,11 @@ impl MutationRoot {
     }
 
     /// Confirm order
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_CONFIRM)")]
     async fn confirm_order(&self, ctx: &Context<'_>, input: ConfirmOrderInputGQL) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -669,11 +803,11 @@ impl MutationRoot {
     }
 
     /// Cancel order
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_CANCEL)")]
     async fn cancel_order(&self, ctx: &Context<'_>, input: CancelOrderInputGQL) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -683,11 +817,11 @@ impl MutationRoot {
     }
 
     /// Update order status
+    #[graphql(guard = "PermissionGuard::new(permissions::ORDER_UPDATE)")]
     async fn update_order_status(&self, ctx: &Context<'_>, input: UpdateOrderStatusInputGQL) -> Result<TestOrderGQL> {
         let service = ctx.data::<OrderService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -695,4 +829,39 @@ impl MutationRoot {
         let order = service.update_order_status(domain_input, user_id).await?;
         Ok(order.into())
     }
+
+    /// Reprocess a logged inbound HL7 message that previously failed
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn replay_hl7_message(&self, ctx: &Context<'_>, id: ID) -> Result<Hl7MessageLogGQL> {
+        let engine = ctx.data::<Hl7InterfaceEngine>()?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+        let log_id = Uuid::parse_str(&id)?;
+        let log = engine.replay(log_id, org_id, user_id).await?;
+        Ok(log.into())
+    }
+
+    /// Register a sending system allowed to place orders over HL7
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn register_hl7_interface(&self, ctx: &Context<'_>, input: RegisterHl7InterfaceInputGQL) -> Result<Hl7InterfaceGQL> {
+        let engine = ctx.data::<Hl7InterfaceEngine>()?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+
+        let domain_input = input.try_into()
+            .map_err(|e: String| async_graphql::Error::new(e))?;
+
+        let interface = engine.register_interface(domain_input, org_id, user_id).await?;
+        Ok(interface.into())
+    }
+
+    /// Enable or disable an HL7 interface; disabled peers are refused
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn set_hl7_interface_active(&self, ctx: &Context<'_>, id: ID, is_active: bool) -> Result<Hl7InterfaceGQL> {
+        let engine = ctx.data::<Hl7InterfaceEngine>()?;
+        let org_id = ctx.organization_id()?;
+        let interface_id = Uuid::parse_str(&id)?;
+        let interface = engine.set_interface_active(interface_id, org_id, is_active).await?;
+        Ok(interface.into())
+    }
 }
@@ -6,10 +6,14 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub kafka_brokers: String,
     pub patient_service_url: String,
     pub sample_service_url: String,
+    pub hl7_listener_enabled: bool,
+    pub hl7_listener_port: u16,
 }
 
 impl Config {
@@ -18,10 +22,14 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8083)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("kafka_brokers", "localhost:9092")?
             .set_default("patient_service_url", "http://localhost:8081")?
             .set_default("sample_service_url", "http://localhost:8082")?
+            .set_default("hl7_listener_enabled", false)?
+            .set_default("hl7_listener_port", 2575)?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -33,10 +41,14 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8083,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            kafka_brokers: "localhost:9092".to_string(),
             patient_service_url: "http://localhost:8081".to_string(),
             sample_service_url: "http://localhost:8082".to_string(),
+            hl7_listener_enabled: false,
+            hl7_listener_port: 2575,
         }
     }
 }
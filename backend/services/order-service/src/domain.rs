@@ -223,6 +223,55 @@ impl TestOrderItem {
     }
 }
 
+// ============================================================================
+// HL7 Message Log Domain Model
+// ============================================================================
+
+pub mod hl7_status {
+    pub const RECEIVED: &str = "RECEIVED";
+    pub const PROCESSED: &str = "PROCESSED";
+    pub const FAILED: &str = "FAILED";
+}
+
+/// A sending system allowed to place orders for its organization
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Hl7Interface {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+
+    pub interface_name: String,
+    pub peer_address: String,
+    pub order_user_id: Uuid,
+    pub is_active: bool,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Hl7MessageLog {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+
+    pub direction: String,
+    pub message_type: Option<String>,
+    pub message_control_id: Option<String>,
+    pub peer_address: Option<String>,
+
+    pub raw_message: String,
+    pub ack_message: Option<String>,
+    pub ack_code: Option<String>,
+
+    pub status: String,
+    pub error_message: Option<String>,
+    pub related_entity_id: Option<Uuid>,
+    pub attempt_count: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
 // ============================================================================
 // Input DTOs
 // ============================================================================
@@ -252,6 +301,29 @@ impl CreateOrderInput {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterHl7InterfaceInput {
+    pub interface_name: String,
+    pub peer_address: String,
+    pub order_user_id: Uuid,
+}
+
+impl RegisterHl7InterfaceInput {
+    pub fn validate(&self) -> Result<(), common::error::Error> {
+        if self.interface_name.trim().is_empty() {
+            return Err(common::error::Error::Validation("Interface name is required".to_string()));
+        }
+        // Peers are matched by the IP address they connect from
+        if self.peer_address.parse::<std::net::IpAddr>().is_err() {
+            return Err(common::error::Error::Validation(format!(
+                "Peer address must be an IP address: {}",
+                self.peer_address
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddTestToOrderInput {
     pub order_id: Uuid,
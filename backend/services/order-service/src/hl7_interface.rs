@@ -0,0 +1,519 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+use common::error::{Error, Result};
+use common::tenant::Tenant;
+use common::types::Priority;
+use infrastructure::hl7::{self, AckCode, Hl7Endpoint, Hl7Message, MllpDecoder};
+
+use crate::domain::*;
+use crate::patient_client::PatientClient;
+use crate::repository::{Hl7InterfaceRepository, Hl7MessageLogRepository};
+use crate::service::OrderService;
+
+const DIRECTION_INBOUND: &str = "INBOUND";
+const ORDER_SOURCE_HL7: &str = "HL7";
+
+/// Pause after a failed accept, e.g. when out of file descriptors
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Order request extracted from an OML^O21 message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hl7OrderRequest {
+    pub mrn_number: String,
+    pub placer_order_number: Option<String>,
+    pub priority: Priority,
+    pub ordering_provider: Option<String>,
+    pub clinical_notes: Option<String>,
+    pub collection_date_time: Option<DateTime<Utc>>,
+    /// `TestCatalog.test_code` (or panel code) for each OBR
+    pub test_codes: Vec<String>,
+}
+
+/// Maps the segments of an OML^O21 onto an order request. PID-3 supplies the
+/// MRN (the `MR` identifier when several are sent), ORC/TQ1 the priority and
+/// ordering provider, and each OBR-4 one test code.
+pub fn parse_order_request(message: &Hl7Message) -> Result<Hl7OrderRequest> {
+    match message.message_type().as_deref() {
+        Some("OML^O21") => {}
+        other => {
+            return Err(Error::InvalidInput(format!(
+                "Unsupported message type: {}",
+                other.unwrap_or("(none)")
+            )))
+        }
+    }
+
+    let pid = message
+        .segment("PID")
+        .ok_or_else(|| Error::Validation("PID segment is required".to_string()))?;
+
+    let identifiers: Vec<Vec<String>> = pid
+        .repetitions(3)
+        .iter()
+        .map(|r| pid.split_components(r))
+        .collect();
+    let mrn_number = identifiers
+        .iter()
+        .find(|cx| cx.get(4).map(|t| t == "MR").unwrap_or(false))
+        .or_else(|| identifiers.first())
+        .and_then(|cx| cx.first().cloned())
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| Error::Validation("PID-3 does not contain a medical record number".to_string()))?;
+
+    let orc = message.segment("ORC");
+    if let Some(control) = orc.and_then(|o| o.field(1)) {
+        if control != "NW" {
+            return Err(Error::Validation(format!("Unsupported order control code: {}", control)));
+        }
+    }
+
+    let first_obr = message.segment("OBR");
+
+    let priority_code = message
+        .segment("TQ1")
+        .and_then(|tq1| tq1.component(9, 1))
+        .or_else(|| orc.and_then(|o| o.component(7, 6)))
+        .or_else(|| first_obr.and_then(|o| o.component(27, 6)));
+    let priority = match priority_code.as_deref() {
+        Some("S") => Priority::Stat,
+        Some("A") => Priority::Urgent,
+        _ => Priority::Routine,
+    };
+
+    let provider_segment = orc
+        .filter(|o| o.field(12).is_some())
+        .map(|o| (o, 12))
+        .or_else(|| first_obr.map(|o| (o, 16)));
+    let ordering_provider = provider_segment.and_then(|(segment, field)| {
+        let name: Vec<String> = [3, 2]
+            .iter()
+            .filter_map(|c| segment.component(field, *c))
+            .collect();
+        if name.is_empty() { None } else { Some(name.join(" ")) }
+    });
+
+    let placer_order_number = orc
+        .and_then(|o| o.component(2, 1))
+        .or_else(|| first_obr.and_then(|o| o.component(2, 1)));
+
+    let mut notes: Vec<String> = message
+        .segments_named("NTE")
+        .filter_map(|nte| nte.field(3))
+        .collect();
+    if let Some(ref placer) = placer_order_number {
+        notes.insert(0, format!("Placer order: {}", placer));
+    }
+
+    let collection_date_time = first_obr
+        .and_then(|o| o.field(7))
+        .and_then(|ts| hl7::parse_timestamp(&ts));
+
+    let test_codes: Vec<String> = message
+        .segments_named("OBR")
+        .filter_map(|obr| obr.component(4, 1))
+        .collect();
+    if test_codes.is_empty() {
+        return Err(Error::Validation("Message does not contain any OBR test requests".to_string()));
+    }
+
+    Ok(Hl7OrderRequest {
+        mrn_number,
+        placer_order_number,
+        priority,
+        ordering_provider,
+        clinical_notes: if notes.is_empty() { None } else { Some(notes.join("\n")) },
+        collection_date_time,
+        test_codes,
+    })
+}
+
+// ============================================================================
+// Interface Engine
+// ============================================================================
+
+#[derive(Clone)]
+pub struct Hl7InterfaceEngine {
+    order_service: OrderService,
+    patient_client: PatientClient,
+    log_repo: Hl7MessageLogRepository,
+    interface_repo: Hl7InterfaceRepository,
+}
+
+impl Hl7InterfaceEngine {
+    pub fn new(
+        order_service: OrderService,
+        patient_client: PatientClient,
+        log_repo: Hl7MessageLogRepository,
+        interface_repo: Hl7InterfaceRepository,
+    ) -> Self {
+        Self {
+            order_service,
+            patient_client,
+            log_repo,
+            interface_repo,
+        }
+    }
+
+    pub async fn register_interface(
+        &self,
+        input: RegisterHl7InterfaceInput,
+        org_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Hl7Interface> {
+        input.validate()?;
+        self.interface_repo.create(input, org_id, user_id).await
+    }
+
+    pub async fn list_interfaces(&self, org_id: Uuid) -> Result<Vec<Hl7Interface>> {
+        self.interface_repo.list(org_id).await
+    }
+
+    pub async fn set_interface_active(&self, id: Uuid, org_id: Uuid, is_active: bool) -> Result<Hl7Interface> {
+        self.interface_repo
+            .set_active(id, org_id, is_active)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("HL7 interface not found: {}", id)))
+    }
+
+    /// The active interface registered for a peer; connections from anyone
+    /// else are refused
+    pub async fn find_interface(&self, peer_address: &str) -> Result<Hl7Interface> {
+        self.interface_repo
+            .find_active_by_peer(peer_address)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("No HL7 interface registered for {}", peer_address)))
+    }
+
+    /// Processes one message received on `interface` and returns the encoded
+    /// acknowledgment. Retransmissions of an already processed control ID get
+    /// the original ACK.
+    pub async fn handle_inbound(&self, interface: &Hl7Interface, raw: &str, peer_address: Option<&str>) -> Result<String> {
+        let org_id = interface.organization_id;
+        let message = match Hl7Message::parse(raw) {
+            Ok(message) => message,
+            Err(e) => {
+                let log = self.log_repo.create(org_id, DIRECTION_INBOUND, None, None, peer_address, raw).await?;
+                let endpoint = Hl7Endpoint {
+                    sending_application: "LIS".to_string(),
+                    sending_facility: String::new(),
+                    receiving_application: String::new(),
+                    receiving_facility: String::new(),
+                };
+                let mut ack = Hl7Message::new(&endpoint, "ACK", "", "ACK");
+                ack.push(hl7::Segment::new("MSA").set(1, AckCode::Reject.as_str()).set(3, &e.to_string()));
+                let ack = ack.encode();
+
+                self.log_repo
+                    .record_outcome(log.id, hl7_status::FAILED, Some(&ack), Some("AR"), Some(&e.to_string()), None)
+                    .await?;
+                return Ok(ack);
+            }
+        };
+
+        let control_id = message.control_id();
+        if let Some(ref control_id) = control_id {
+            if let Some(previous) = self.log_repo
+                .find_processed_by_control_id(org_id, DIRECTION_INBOUND, control_id)
+                .await?
+            {
+                tracing::info!("Duplicate HL7 message {} acknowledged from log", control_id);
+                if let Some(ack) = previous.ack_message {
+                    return Ok(ack);
+                }
+            }
+        }
+
+        let log = self.log_repo
+            .create(
+                org_id,
+                DIRECTION_INBOUND,
+                message.message_type().as_deref(),
+                control_id.as_deref(),
+                peer_address,
+                raw,
+            )
+            .await?;
+
+        let log = self.process(log.id, &message, org_id, interface.order_user_id).await?;
+        Ok(log.ack_message.unwrap_or_default())
+    }
+
+    /// Reprocesses a logged inbound message, e.g. after the missing patient
+    /// or test catalog entry has been created. The order is created by the
+    /// user replaying it.
+    pub async fn replay(&self, log_id: Uuid, org_id: Uuid, user_id: Uuid) -> Result<Hl7MessageLog> {
+        let log = self.log_repo
+            .find_by_id(log_id, org_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("HL7 message not found: {}", log_id)))?;
+
+        if log.status == hl7_status::PROCESSED {
+            return Err(Error::InvalidState("HL7 message has already been processed".to_string()));
+        }
+
+        let message = Hl7Message::parse(&log.raw_message)?;
+        tracing::info!("Replaying HL7 message {}", log.id);
+
+        self.process(log.id, &message, org_id, user_id).await
+    }
+
+    pub async fn list_messages(&self, org_id: Uuid, status: Option<&str>, limit: i64) -> Result<Vec<Hl7MessageLog>> {
+        self.log_repo.list(org_id, status, limit).await
+    }
+
+    async fn process(&self, log_id: Uuid, message: &Hl7Message, org_id: Uuid, created_by: Uuid) -> Result<Hl7MessageLog> {
+        match self.create_order(message, org_id, created_by).await {
+            Ok(order) => {
+                let text = format!("Order {} created", order.order_number);
+                let ack = message.build_ack(AckCode::Accept, Some(&text), None).encode();
+
+                tracing::info!(
+                    "HL7 order {} created from message {}",
+                    order.order_number,
+                    message.control_id().unwrap_or_default()
+                );
+
+                self.log_repo
+                    .record_outcome(log_id, hl7_status::PROCESSED, Some(&ack), Some("AA"), None, Some(order.id))
+                    .await
+            }
+            Err(e) => {
+                let code = match e {
+                    Error::InvalidInput(_) => AckCode::Reject,
+                    _ => AckCode::Error,
+                };
+                let ack = message
+                    .build_ack(code, Some("Message not processed"), Some(&e.to_string()))
+                    .encode();
+
+                tracing::warn!(
+                    "HL7 message {} failed: {}",
+                    message.control_id().unwrap_or_default(),
+                    e
+                );
+
+                self.log_repo
+                    .record_outcome(log_id, hl7_status::FAILED, Some(&ack), Some(code.as_str()), Some(&e.to_string()), None)
+                    .await
+            }
+        }
+    }
+
+    async fn create_order(&self, message: &Hl7Message, org_id: Uuid, created_by: Uuid) -> Result<TestOrder> {
+        let request = parse_order_request(message)?;
+        let patient = self.patient_client.find_by_mrn(&request.mrn_number).await?;
+        if patient.organization_id != org_id {
+            return Err(Error::NotFound(format!("Patient not found: {}", request.mrn_number)));
+        }
+
+        // Resolve every code before creating anything so a bad OBR rejects the whole order
+        let mut items = Vec::new();
+        let mut unknown = Vec::new();
+        for code in &request.test_codes {
+            match self.order_service.get_test_by_code(code).await {
+                Ok(test) => items.push((Some(test.id), None)),
+                Err(Error::NotFound(_)) => match self.order_service.get_panel_by_code(code).await {
+                    Ok(panel) => items.push((None, Some(panel.id))),
+                    Err(Error::NotFound(_)) => unknown.push(code.clone()),
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !unknown.is_empty() {
+            return Err(Error::Validation(format!("Unknown test codes: {}", unknown.join(", "))));
+        }
+
+        let order = self.order_service
+            .create_order(
+                CreateOrderInput {
+                    patient_id: patient.id,
+                    order_source: ORDER_SOURCE_HL7.to_string(),
+                    priority: request.priority,
+                    referring_doctor_name: request.ordering_provider,
+                    clinical_notes: request.clinical_notes,
+                    home_collection_requested: false,
+                    collection_date_time: request.collection_date_time,
+                    report_delivery_method: None,
+                    report_delivery_email: None,
+                    report_delivery_phone: None,
+                },
+                org_id,
+                created_by,
+            )
+            .await?;
+
+        let mut order = order;
+        for (test_id, panel_id) in items {
+            order = self.order_service
+                .add_test_to_order(AddTestToOrderInput {
+                    order_id: order.id,
+                    test_id,
+                    panel_id,
+                    quantity: 1,
+                })
+                .await?;
+        }
+
+        Ok(order)
+    }
+}
+
+// ============================================================================
+// MLLP Listener
+// ============================================================================
+
+pub struct Hl7Listener {
+    bind_addr: String,
+    engine: Arc<Hl7InterfaceEngine>,
+}
+
+impl Hl7Listener {
+    pub fn new(bind_addr: String, engine: Hl7InterfaceEngine) -> Self {
+        Self {
+            bind_addr,
+            engine: Arc::new(engine),
+        }
+    }
+
+    /// Accepts HIS connections. Only peers registered as an active HL7
+    /// interface are served; everyone else is disconnected.
+    pub async fn run(self) -> Result<()> {
+        let listener = TcpListener::bind(&self.bind_addr).await?;
+        tracing::info!("HL7 MLLP listener started on {}", self.bind_addr);
+
+        loop {
+            let (mut stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::error!("Failed to accept HL7 connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let peer_ip = peer.ip().to_string();
+
+            // Senders are known by address only; the connection then works
+            // for the organization that registered the interface
+            let lookup = self.engine.find_interface(&peer_ip);
+            let interface = match Tenant::System.scope(lookup).await {
+                Ok(interface) => interface,
+                Err(e) => {
+                    tracing::warn!("Rejected HL7 connection from {}: {}", peer_ip, e);
+                    continue;
+                }
+            };
+
+            tracing::info!("HL7 interface {} connected from {}", interface.interface_name, peer);
+
+            let engine = self.engine.clone();
+            let peer_address = peer.to_string();
+            let tenant = Tenant::Organization(interface.organization_id);
+            tokio::spawn(tenant.scope(async move {
+                let mut decoder = MllpDecoder::new();
+                let mut pending = Vec::new();
+
+                loop {
+                    let raw = match hl7::read_mllp_message(&mut stream, &mut decoder, &mut pending).await {
+                        Ok(Some(raw)) => raw,
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::error!("HL7 connection {} error: {}", peer_address, e);
+                            break;
+                        }
+                    };
+
+                    let ack = match engine.handle_inbound(&interface, &raw, Some(&peer_address)).await {
+                        Ok(ack) => ack,
+                        Err(e) => {
+                            // Log persistence failed: NAK so the sender retries later
+                            tracing::error!("Failed to handle HL7 message from {}: {}", peer_address, e);
+                            match Hl7Message::parse(&raw) {
+                                Ok(message) => message
+                                    .build_ack(AckCode::Error, Some("Temporary processing failure"), None)
+                                    .encode(),
+                                Err(_) => break,
+                            }
+                        }
+                    };
+
+                    if let Err(e) = hl7::write_mllp_message(&mut stream, &ack).await {
+                        tracing::error!("Failed to send HL7 acknowledgment to {}: {}", peer_address, e);
+                        break;
+                    }
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oml(extra: &str) -> Hl7Message {
+        let text = format!(
+            "MSH|^~\\&|HIS|CITYHOSP|LIS|LAB|20250105101500||OML^O21^OML_O21|MSG00001|P|2.5.1\r\
+PID|1||998877^^^NATID^NI~MRN0001^^^CITYHOSP^MR||Doe^Jane||19800115|F\r\
+ORC|NW|PLC123||||||||||1234^Smith^John\r\
+TQ1|1||||||||S\r\
+OBR|1|PLC123||GLU^Glucose^L|||20250105100000\r\
+OBR|2|PLC123||LFT^Liver Function Panel^L\r{}",
+            extra
+        );
+        Hl7Message::parse(&text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_order_request_maps_segments() {
+        let request = parse_order_request(&oml("NTE|1||Fasting sample\r")).unwrap();
+
+        assert_eq!(request.mrn_number, "MRN0001");
+        assert_eq!(request.placer_order_number.as_deref(), Some("PLC123"));
+        assert_eq!(request.priority, Priority::Stat);
+        assert_eq!(request.ordering_provider.as_deref(), Some("John Smith"));
+        assert_eq!(request.test_codes, vec!["GLU".to_string(), "LFT".to_string()]);
+        assert_eq!(
+            request.clinical_notes.as_deref(),
+            Some("Placer order: PLC123\nFasting sample")
+        );
+        assert_eq!(
+            request.collection_date_time.map(hl7::format_timestamp).as_deref(),
+            Some("20250105100000")
+        );
+    }
+
+    #[test]
+    fn test_parse_order_request_rejects_other_message_types() {
+        let message = Hl7Message::parse("MSH|^~\\&|HIS|H|LIS|L|20250105||ADT^A01|1|P|2.5.1\r").unwrap();
+
+        assert!(matches!(parse_order_request(&message), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_interfaces_are_registered_by_ip_address() {
+        let input = |peer_address: &str| RegisterHl7InterfaceInput {
+            interface_name: "City Hospital HIS".to_string(),
+            peer_address: peer_address.to_string(),
+            order_user_id: Uuid::new_v4(),
+        };
+
+        assert!(input("10.20.0.15").validate().is_ok());
+        assert!(input("fd00::15").validate().is_ok());
+        assert!(input("his.cityhospital.local").validate().is_err());
+        assert!(input("10.20.0.15:2575").validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_order_request_requires_mrn() {
+        let message = Hl7Message::parse(
+            "MSH|^~\\&|HIS|H|LIS|L|20250105||OML^O21|1|P|2.5.1\rPID|1\rOBR|1||||GLU\r"
+        ).unwrap();
+
+        assert!(matches!(parse_order_request(&message), Err(Error::Validation(_))));
+    }
+}
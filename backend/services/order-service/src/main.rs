@@ -1,26 +1,33 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, JwtService, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::{DatabasePool, EventBusConfig, EventTransport, OutboxRelay};
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod patient_client;
+mod hl7_interface;
 
 use repository::*;
 use service::OrderService;
 use api::{QueryRoot, MutationRoot};
 use config::Config;
+use hl7_interface::{Hl7InterfaceEngine, Hl7Listener};
+use patient_client::PatientClient;
 
 type OrderSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<OrderSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -89,31 +96,51 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("  Max DB connections: {}", config.database_max_connections);
     tracing::info!("  Caching enabled: {}", config.enable_caching);
     tracing::info!("  Events enabled: {}", config.enable_events);
+    tracing::info!("  HL7 listener enabled: {}", config.hl7_listener_enabled);
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&pool))
         .await
         .expect("Failed to run migrations");
 
     tracing::info!("Migrations completed successfully");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+        tracing::info!("Outbox relay started");
+    }
+
     // Create repositories
     let test_catalog_repo = TestCatalogRepository::new(pool.clone());
     let test_panel_repo = TestPanelRepository::new(pool.clone());
     let order_repo = TestOrderRepository::new(pool.clone());
     let order_item_repo = TestOrderItemRepository::new(pool.clone());
+    let hl7_log_repo = Hl7MessageLogRepository::new(pool.clone());
+    let hl7_interface_repo = Hl7InterfaceRepository::new(pool.clone());
+
+    // Calls to other services carry the tenant of the request
+    let jwt = JwtService::new(&config.jwt_secret);
 
     // Create service
     let order_service = OrderService::new(
@@ -123,14 +150,35 @@ async fn main() -> std::io::Result<()> {
         order_item_repo,
     );
 
+    // Create HL7 interface engine
+    let hl7_engine = Hl7InterfaceEngine::new(
+        order_service.clone(),
+        PatientClient::new(config.patient_service_url.clone(), jwt.clone()),
+        hl7_log_repo,
+        hl7_interface_repo,
+    );
+
+    if config.hl7_listener_enabled {
+        let listener = Hl7Listener::new(
+            format!("{}:{}", config.host, config.hl7_listener_port),
+            hl7_engine.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = listener.run().await {
+                tracing::error!("HL7 listener stopped: {}", e);
+            }
+        });
+    }
+
     // Build GraphQL schema
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(order_service)
+        .data(hl7_engine)
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: test, testByCode, searchTests, allActiveTests, panel, panelTests, popularPanels, order, orderByNumber, ordersByPatient, orderItems");
-    tracing::info!("  Mutations: createOrder, addTestToOrder, removeItemFromOrder, confirmOrder, cancelOrder, updateOrderStatus");
+    tracing::info!("  Queries: test, testByCode, searchTests, allActiveTests, panel, panelTests, popularPanels, order, orderByNumber, ordersByPatient, orderItems, hl7Messages, hl7Interfaces");
+    tracing::info!("  Mutations: createOrder, addTestToOrder, removeItemFromOrder, confirmOrder, cancelOrder, updateOrderStatus, replayHl7Message, registerHl7Interface, setHl7InterfaceActive");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -140,8 +188,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the patient-service
+#[derive(Clone)]
+pub struct PatientClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatientByMrnResponse {
+    #[serde(rename = "patientByMrn")]
+    patient_by_mrn: PatientData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatientData {
+    pub id: Uuid,
+    #[serde(rename = "organizationId")]
+    pub organization_id: Uuid,
+}
+
+impl PatientClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Look up a patient by medical record number
+    pub async fn find_by_mrn(&self, mrn_number: &str) -> Result<PatientData> {
+        let query = r#"
+            query PatientByMrn($mrnNumber: String!) {
+                patientByMrn(mrnNumber: $mrnNumber) {
+                    id
+                    organizationId
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "mrnNumber": mrn_number }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call patient-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to patient-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Patient-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Patient-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<PatientByMrnResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse patient-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from patient-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::NotFound(
+                format!("Patient {} not found: {}", mrn_number, error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .map(|data| data.patient_by_mrn)
+            .ok_or_else(|| Error::ExternalService("No data returned from patient-service".to_string()))
+    }
+}
@@ -2,6 +2,8 @@ use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use common::error::{Error, Result};
 use common::types::OrderStatus;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 
 use crate::domain::*;
 
@@ -181,6 +183,8 @@ impl TestOrderRepository {
 
         let order_number = self.generate_order_number(&org_id).await?;
 
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let order = sqlx::query_as::<_, TestOrder>(
             r#"
             INSERT INTO test_order (
@@ -210,10 +214,24 @@ impl TestOrderRepository {
         .bind(&input.report_delivery_email)
         .bind(&input.report_delivery_phone)
         .bind(user_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::ORDER_EVENTS,
+            events::ORDER_CREATED,
+            "TestOrder",
+            order.id,
+            order.organization_id,
+            Some(user_id),
+            &order,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(order)
     }
 
@@ -229,6 +247,18 @@ impl TestOrderRepository {
         Ok(order)
     }
 
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<TestOrder>> {
+        let orders = sqlx::query_as::<_, TestOrder>(
+            "SELECT * FROM test_order WHERE id = ANY($1) AND is_deleted = FALSE"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(orders)
+    }
+
     pub async fn find_by_order_number(&self, order_number: &str) -> Result<Option<TestOrder>> {
         let order = sqlx::query_as::<_, TestOrder>(
             "SELECT * FROM test_order WHERE order_number = $1 AND is_deleted = FALSE"
@@ -304,7 +334,10 @@ impl TestOrderRepository {
         Ok(orders)
     }
 
+    /// Completing an order publishes ORDER_COMPLETED; other transitions are internal
     pub async fn update_status(&self, input: UpdateOrderStatusInput, user_id: Uuid) -> Result<TestOrder> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let order = sqlx::query_as::<_, TestOrder>(
             r#"
             UPDATE test_order
@@ -316,14 +349,32 @@ impl TestOrderRepository {
         .bind(&input.new_status)
         .bind(user_id)
         .bind(input.order_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        if order.order_status == OrderStatus::Completed {
+            outbox::enqueue_event(
+                &mut *tx,
+                topics::ORDER_EVENTS,
+                events::ORDER_COMPLETED,
+                "TestOrder",
+                order.id,
+                order.organization_id,
+                Some(user_id),
+                &order,
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(order)
     }
 
     pub async fn confirm_order(&self, input: ConfirmOrderInput, user_id: Uuid) -> Result<TestOrder> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let order = sqlx::query_as::<_, TestOrder>(
             r#"
             UPDATE test_order
@@ -342,14 +393,30 @@ impl TestOrderRepository {
         .bind(input.advance_paid)
         .bind(user_id)
         .bind(input.order_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::ORDER_EVENTS,
+            events::ORDER_CONFIRMED,
+            "TestOrder",
+            order.id,
+            order.organization_id,
+            Some(user_id),
+            &order,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(order)
     }
 
     pub async fn cancel_order(&self, input: CancelOrderInput, user_id: Uuid) -> Result<TestOrder> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let order = sqlx::query_as::<_, TestOrder>(
             r#"
             UPDATE test_order
@@ -368,10 +435,24 @@ impl TestOrderRepository {
         .bind(user_id)
         .bind(&input.cancellation_reason)
         .bind(input.order_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::ORDER_EVENTS,
+            events::ORDER_CANCELLED,
+            "TestOrder",
+            order.id,
+            order.organization_id,
+            Some(user_id),
+            &order,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(order)
     }
 
@@ -529,3 +610,225 @@ impl TestOrderItemRepository {
         Ok(())
     }
 }
+
+// ============================================================================
+// HL7 Message Log Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct Hl7MessageLogRepository {
+    pool: PgPool,
+}
+
+impl Hl7MessageLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        org_id: Uuid,
+        direction: &str,
+        message_type: Option<&str>,
+        message_control_id: Option<&str>,
+        peer_address: Option<&str>,
+        raw_message: &str,
+    ) -> Result<Hl7MessageLog> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            INSERT INTO hl7_message_log (
+                id, organization_id, direction, message_type, message_control_id, peer_address, raw_message, status
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(org_id)
+        .bind(direction)
+        .bind(message_type)
+        .bind(message_control_id)
+        .bind(peer_address)
+        .bind(raw_message)
+        // Until the message is processed
+        .bind(hl7_status::RECEIVED)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid, org_id: Uuid) -> Result<Option<Hl7MessageLog>> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            "SELECT * FROM hl7_message_log WHERE id = $1 AND organization_id = $2"
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    /// Latest successfully processed message of the organization with the
+    /// given MSH-10, used to detect retransmissions from the sending system
+    pub async fn find_processed_by_control_id(
+        &self,
+        org_id: Uuid,
+        direction: &str,
+        message_control_id: &str,
+    ) -> Result<Option<Hl7MessageLog>> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            SELECT * FROM hl7_message_log
+            WHERE organization_id = $1 AND direction = $2 AND message_control_id = $3 AND status = 'PROCESSED'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(org_id)
+        .bind(direction)
+        .bind(message_control_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn record_outcome(
+        &self,
+        id: Uuid,
+        status: &str,
+        ack_message: Option<&str>,
+        ack_code: Option<&str>,
+        error_message: Option<&str>,
+        related_entity_id: Option<Uuid>,
+    ) -> Result<Hl7MessageLog> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            UPDATE hl7_message_log
+            SET
+                status = $1,
+                ack_message = $2,
+                ack_code = $3,
+                error_message = $4,
+                related_entity_id = COALESCE($5, related_entity_id),
+                attempt_count = attempt_count + 1,
+                processed_at = NOW()
+            WHERE id = $6
+            RETURNING *
+            "#
+        )
+        .bind(status)
+        .bind(ack_message)
+        .bind(ack_code)
+        .bind(error_message)
+        .bind(related_entity_id)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn list(&self, org_id: Uuid, status: Option<&str>, limit: i64) -> Result<Vec<Hl7MessageLog>> {
+        let logs = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            SELECT * FROM hl7_message_log
+            WHERE organization_id = $1 AND ($2::VARCHAR IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(org_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(logs)
+    }
+}
+
+// ============================================================================
+// HL7 Interface Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct Hl7InterfaceRepository {
+    pool: PgPool,
+}
+
+impl Hl7InterfaceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, input: RegisterHl7InterfaceInput, org_id: Uuid, user_id: Uuid) -> Result<Hl7Interface> {
+        let interface = sqlx::query_as::<_, Hl7Interface>(
+            r#"
+            INSERT INTO hl7_interface (
+                id, organization_id, interface_name, peer_address, order_user_id, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(org_id)
+        .bind(&input.interface_name)
+        .bind(&input.peer_address)
+        .bind(input.order_user_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(interface)
+    }
+
+    /// The active interface a connection from `peer_address` belongs to, of
+    /// any organization
+    pub async fn find_active_by_peer(&self, peer_address: &str) -> Result<Option<Hl7Interface>> {
+        let interface = sqlx::query_as::<_, Hl7Interface>(
+            "SELECT * FROM hl7_interface WHERE peer_address = $1 AND is_active = TRUE"
+        )
+        .bind(peer_address)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(interface)
+    }
+
+    pub async fn list(&self, org_id: Uuid) -> Result<Vec<Hl7Interface>> {
+        let interfaces = sqlx::query_as::<_, Hl7Interface>(
+            "SELECT * FROM hl7_interface WHERE organization_id = $1 ORDER BY interface_name"
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(interfaces)
+    }
+
+    pub async fn set_active(&self, id: Uuid, org_id: Uuid, is_active: bool) -> Result<Option<Hl7Interface>> {
+        let interface = sqlx::query_as::<_, Hl7Interface>(
+            "UPDATE hl7_interface SET is_active = $3 WHERE id = $1 AND organization_id = $2 RETURNING *"
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(is_active)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(interface)
+    }
+}
@@ -102,7 +102,6 @@ impl OrderService {
 
         let order = self.order_repo.create(input, org_id, user_id).await?;
 
-        // TODO: Publish ORDER_CREATED event
         // TODO: Cache order
 
         tracing::info!("Order created: {}", order.order_number);
@@ -116,6 +115,10 @@ impl OrderService {
             .ok_or_else(|| Error::NotFound(format!("Order not found: {}", id)))
     }
 
+    pub async fn get_orders_by_ids(&self, ids: &[Uuid]) -> Result<Vec<TestOrder>> {
+        self.order_repo.find_by_ids(ids).await
+    }
+
     pub async fn get_order_by_number(&self, order_number: &str) -> Result<TestOrder> {
         self.order_repo
             .find_by_order_number(order_number)
@@ -239,7 +242,6 @@ impl OrderService {
         // Confirm order
         let order = self.order_repo.confirm_order(input, user_id).await?;
 
-        // TODO: Publish ORDER_CONFIRMED event
         // TODO: Trigger sample creation for each order item
         // TODO: Send confirmation notifications
         // TODO: Invalidate cache
@@ -268,7 +270,6 @@ impl OrderService {
         // Cancel order
         let order = self.order_repo.cancel_order(input, user_id).await?;
 
-        // TODO: Publish ORDER_CANCELLED event
         // TODO: Cancel associated samples
         // TODO: Process refunds if applicable
         // TODO: Send cancellation notifications
@@ -288,7 +289,6 @@ impl OrderService {
         // Update status
         let order = self.order_repo.update_status(input, user_id).await?;
 
-        // TODO: Publish ORDER_STATUS_CHANGED event
         // TODO: Send status update notifications
         // TODO: Invalidate cache
 
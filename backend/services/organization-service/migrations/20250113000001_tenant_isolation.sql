@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('accreditation');
+SELECT enable_tenant_isolation('department');
+SELECT enable_tenant_isolation('organization_audit_log');
+SELECT enable_tenant_isolation('organization_branch');
+SELECT enable_tenant_isolation('organization_setting');
+SELECT enable_tenant_isolation('working_hours_template');
+
+-- An organization is its own tenant
+SELECT enable_tenant_isolation('organization', tenant_column => 'id');
@@ -1,6 +1,9 @@
 use async_graphql::{Context, Object, Result, ErrorExtensions};
 use uuid::Uuid;
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use common::pagination::PaginationParams;
+use common::tenant::Tenant;
 use crate::domain::*;
 use crate::service::OrganizationService;
 
@@ -13,6 +16,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get organization by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn organization(
         &self,
         ctx: &Context<'_>,
@@ -24,6 +28,7 @@ impl QueryRoot {
     }
 
     /// Get organization by code
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn organization_by_code(
         &self,
         ctx: &Context<'_>,
@@ -35,6 +40,7 @@ impl QueryRoot {
     }
 
     /// List organizations with filtering and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn organizations(
         &self,
         ctx: &Context<'_>,
@@ -61,6 +67,7 @@ impl QueryRoot {
     }
 
     /// Get branch by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn branch(
         &self,
         ctx: &Context<'_>,
@@ -72,6 +79,7 @@ impl QueryRoot {
     }
 
     /// List branches for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn branches(
         &self,
         ctx: &Context<'_>,
@@ -83,6 +91,7 @@ impl QueryRoot {
     }
 
     /// Get accreditation by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn accreditation(
         &self,
         ctx: &Context<'_>,
@@ -94,6 +103,7 @@ impl QueryRoot {
     }
 
     /// List accreditations for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn accreditations(
         &self,
         ctx: &Context<'_>,
@@ -104,6 +114,7 @@ impl QueryRoot {
     }
 
     /// Get department by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn department(
         &self,
         ctx: &Context<'_>,
@@ -115,6 +126,7 @@ impl QueryRoot {
     }
 
     /// List departments for an organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn departments(
         &self,
         ctx: &Context<'_>,
@@ -126,6 +138,7 @@ impl QueryRoot {
     }
 
     /// Get organization setting
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn organization_setting(
         &self,
         ctx: &Context<'_>,
@@ -139,6 +152,7 @@ impl QueryRoot {
     }
 
     /// List organization settings
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_READ)")]
     async fn organization_settings(
         &self,
         ctx: &Context<'_>,
@@ -159,216 +173,206 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create a new organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn create_organization(
         &self,
         ctx: &Context<'_>,
         input: CreateOrganizationInput,
     ) -> Result<Organization> {
         let service = ctx.data::<OrganizationService>()?;
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
-
-        Ok(service.create_organization(input, created_by).await.map_err(|e| e.extend())?)
+        // A new organization lies outside the caller's tenant
+        Tenant::System
+            .scope(service.create_organization(input, created_by))
+            .await
+            .map_err(|e| e.extend())
     }
 
     /// Update organization
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn update_organization(
         &self,
         ctx: &Context<'_>,
         input: UpdateOrganizationInput,
     ) -> Result<Organization> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_organization(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update organization status
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn update_organization_status(
         &self,
         ctx: &Context<'_>,
         input: UpdateOrganizationStatusInput,
     ) -> Result<Organization> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_organization_status(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update organization subscription
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn update_subscription(
         &self,
         ctx: &Context<'_>,
         input: UpdateSubscriptionInput,
     ) -> Result<Organization> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_subscription(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Delete organization (soft delete)
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn delete_organization(
         &self,
         ctx: &Context<'_>,
         id: Uuid,
     ) -> Result<bool> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get deleted_by from authenticated user context
-        let deleted_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let deleted_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.delete_organization(id, deleted_by).await.map_err(|e| e.extend())?)
     }
 
     /// Create a new branch
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn create_branch(
         &self,
         ctx: &Context<'_>,
         input: CreateBranchInput,
     ) -> Result<OrganizationBranch> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_branch(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update branch
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn update_branch(
         &self,
         ctx: &Context<'_>,
         input: UpdateBranchInput,
     ) -> Result<OrganizationBranch> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_branch(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Deactivate branch
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn deactivate_branch(
         &self,
         ctx: &Context<'_>,
         id: Uuid,
     ) -> Result<bool> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.deactivate_branch(id, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Add accreditation
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn add_accreditation(
         &self,
         ctx: &Context<'_>,
         input: AddAccreditationInput,
     ) -> Result<Accreditation> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.add_accreditation(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update accreditation
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn update_accreditation(
         &self,
         ctx: &Context<'_>,
         input: UpdateAccreditationInput,
     ) -> Result<Accreditation> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_accreditation(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Deactivate accreditation
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn deactivate_accreditation(
         &self,
         ctx: &Context<'_>,
         id: Uuid,
     ) -> Result<bool> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.deactivate_accreditation(id, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Create department
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn create_department(
         &self,
         ctx: &Context<'_>,
         input: CreateDepartmentInput,
     ) -> Result<Department> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_department(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update department
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn update_department(
         &self,
         ctx: &Context<'_>,
         input: UpdateDepartmentInput,
     ) -> Result<Department> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_department(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Deactivate department
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_MANAGE)")]
     async fn deactivate_department(
         &self,
         ctx: &Context<'_>,
         id: Uuid,
     ) -> Result<bool> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.deactivate_department(id, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update organization setting (upsert)
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
     async fn update_organization_setting(
         &self,
         ctx: &Context<'_>,
         input: UpdateOrganizationSettingInput,
     ) -> Result<OrganizationSetting> {
         let service = ctx.data::<OrganizationService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_setting(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Delete organization setting
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
     async fn delete_organization_setting(
         &self,
         ctx: &Context<'_>,
@@ -381,6 +385,7 @@ impl MutationRoot {
     }
 
     /// Increment test counter (called by other services when processing tests)
+    #[graphql(guard = "PermissionGuard::new(permissions::ORGANIZATION_ADMIN)")]
     async fn increment_test_counter(
         &self,
         ctx: &Context<'_>,
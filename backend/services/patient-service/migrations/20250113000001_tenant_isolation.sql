@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('patient');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('patient_address', 'patient_id', 'patient');
+SELECT enable_parent_isolation('patient_consent', 'patient_id', 'patient');
+SELECT enable_parent_isolation('patient_contact_person', 'patient_id', 'patient');
+SELECT enable_parent_isolation('patient_insurance', 'patient_id', 'patient');
+SELECT enable_parent_isolation('patient_medical_history', 'patient_id', 'patient');
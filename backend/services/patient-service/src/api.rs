@@ -1,4 +1,6 @@
 use async_graphql::{Context, Object, Result};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -12,6 +14,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get patient by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
     async fn patient(&self, ctx: &Context<'_>, id: String) -> Result<Patient> {
         let patient_id = Uuid::parse_str(&id)?;
 
@@ -24,7 +27,23 @@ impl QueryRoot {
         Ok(patient)
     }
 
+    /// Get patients by IDs; unknown IDs are skipped
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
+    async fn patients_by_ids(&self, ctx: &Context<'_>, ids: Vec<String>) -> Result<Vec<Patient>> {
+        let ids = ids
+            .iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let pool = ctx.data::<PgPool>()?;
+        let repository = PatientRepository::new(pool.clone());
+
+        let patients = repository.find_by_ids(&ids).await?;
+        Ok(patients)
+    }
+
     /// Get patient by MRN number
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
     async fn patient_by_mrn(&self, ctx: &Context<'_>, mrn_number: String) -> Result<Patient> {
         let pool = ctx.data::<PgPool>()?;
         let repository = PatientRepository::new(pool.clone());
@@ -35,6 +54,7 @@ impl QueryRoot {
     }
 
     /// Get patient by mobile number
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
     async fn patient_by_mobile(&self, ctx: &Context<'_>, mobile_number: String) -> Result<Option<Patient>> {
         let pool = ctx.data::<PgPool>()?;
         let repository = PatientRepository::new(pool.clone());
@@ -44,6 +64,7 @@ impl QueryRoot {
     }
 
     /// Search patients by query (MRN, name, mobile)
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
     async fn search_patients(
         &self,
         ctx: &Context<'_>,
@@ -63,6 +84,7 @@ impl QueryRoot {
     }
 
     /// Get all patients for an organization (paginated)
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_READ)")]
     async fn patients(
         &self,
         ctx: &Context<'_>,
@@ -102,15 +124,14 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create a new patient
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_CREATE)")]
     async fn create_patient(
         &self,
         ctx: &Context<'_>,
         input: CreatePatientInput,
-        organization_id: String,
-        created_by: String,
     ) -> Result<Patient> {
-        let org_id = Uuid::parse_str(&organization_id)?;
-        let user_id = Uuid::parse_str(&created_by)?;
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
 
         let pool = ctx.data::<PgPool>()?;
         let repository = PatientRepository::new(pool.clone());
@@ -118,7 +139,6 @@ impl MutationRoot {
 
         let patient = service.create_patient(input, org_id, user_id).await?;
 
-        // TODO: Publish PATIENT_CREATED event to Kafka
         // TODO: Cache patient in Redis
 
         tracing::info!("Patient created: {} (MRN: {})", patient.id, patient.mrn_number);
@@ -126,62 +146,21 @@ impl MutationRoot {
     }
 
     /// Update patient information
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_UPDATE)")]
     async fn update_patient(
         &self,
         ctx: &Context<'_>,
         id: String,
         input: UpdatePatientInput,
-        updated_by: String,
     ) -> Result<Patient> {
         let patient_id = Uuid::parse_str(&id)?;
-        let user_id = Uuid::parse_str(&updated_by)?;
+        let user_id = ctx.user_id()?;
 
         let pool = ctx.data::<PgPool>()?;
+        let repository = PatientRepository::new(pool.clone());
 
-        // Build update query dynamically based on provided fields
-        let mut update_parts = Vec::new();
-        let mut bind_index = 1;
-
-        let mut query = String::from("UPDATE patient SET ");
-
-        if input.first_name.is_some() {
-            update_parts.push(format!("first_name = ${}", bind_index));
-            bind_index += 1;
-        }
-        if input.mobile_number.is_some() {
-            update_parts.push(format!("mobile_number = ${}", bind_index));
-            bind_index += 1;
-        }
-        if input.email.is_some() {
-            update_parts.push(format!("email = ${}", bind_index));
-            bind_index += 1;
-        }
-
-        update_parts.push(format!("updated_by = ${}", bind_index));
-        bind_index += 1;
-        update_parts.push("updated_at = NOW()".to_string());
-
-        query.push_str(&update_parts.join(", "));
-        query.push_str(&format!(" WHERE id = ${} AND is_deleted = false RETURNING *", bind_index));
-
-        let mut db_query = sqlx::query_as::<_, Patient>(&query);
-
-        if let Some(first_name) = input.first_name {
-            db_query = db_query.bind(first_name);
-        }
-        if let Some(mobile_number) = input.mobile_number {
-            db_query = db_query.bind(common::utils::format_indian_phone(&mobile_number));
-        }
-        if let Some(email) = input.email {
-            db_query = db_query.bind(email);
-        }
-
-        db_query = db_query.bind(user_id);
-        db_query = db_query.bind(patient_id);
-
-        let patient = db_query.fetch_one(pool).await?;
+        let patient = repository.update(patient_id, input, user_id).await?;
 
-        // TODO: Publish PATIENT_UPDATED event
         // TODO: Invalidate cache
 
         tracing::info!("Patient updated: {}", patient.id);
@@ -189,37 +168,22 @@ impl MutationRoot {
     }
 
     /// Soft delete a patient
+    #[graphql(guard = "PermissionGuard::new(permissions::PATIENT_DELETE)")]
     async fn delete_patient(
         &self,
         ctx: &Context<'_>,
         id: String,
-        deleted_by: String,
     ) -> Result<bool> {
         let patient_id = Uuid::parse_str(&id)?;
-        let user_id = Uuid::parse_str(&deleted_by)?;
+        let user_id = ctx.user_id()?;
 
         let pool = ctx.data::<PgPool>()?;
+        let repository = PatientRepository::new(pool.clone());
 
-        let result = sqlx::query(
-            r#"
-            UPDATE patient
-            SET is_deleted = true,
-                deleted_at = NOW(),
-                updated_by = $2,
-                updated_at = NOW()
-            WHERE id = $1 AND is_deleted = false
-            "#,
-        )
-        .bind(patient_id)
-        .bind(user_id)
-        .execute(pool)
-        .await?;
-
-        let deleted = result.rows_affected() > 0;
+        let deleted = repository.soft_delete(patient_id, user_id).await?;
 
         if deleted {
             tracing::info!("Patient soft-deleted: {}", patient_id);
-            // TODO: Publish PATIENT_DELETED event
             // TODO: Remove from cache
         }
 
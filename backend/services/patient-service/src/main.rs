@@ -8,7 +8,9 @@ use actix_cors::Cors;
 use actix_web::{middleware, web, App, HttpServer};
 use async_graphql::{EmptySubscription, Schema};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::{DatabasePool, EventBusConfig, EventTransport, OutboxRelay};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -33,22 +35,35 @@ async fn main() -> std::io::Result<()> {
     info!("Starting Patient Service on {}:{}", config.host, config.port);
 
     // Database connection pool
-    let db_pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let db_pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     info!("Connected to database");
 
-    // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&db_pool)
+    // Run migrations; they may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&db_pool))
         .await
         .expect("Failed to run migrations");
 
     info!("Database migrations completed");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(db_pool.clone(), event_bus.clone()).spawn();
+        info!("Outbox relay started");
+    }
+
     // Redis connection
     let redis_client = redis::Client::open(config.redis_url.clone())
         .expect("Failed to create Redis client");
@@ -70,6 +85,7 @@ async fn main() -> std::io::Result<()> {
     // Start HTTP server
     let bind_address = format!("{}:{}", config.host, config.port);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         let cors = Cors::default()
             .allowed_origin("http://localhost:3000")
@@ -83,6 +99,7 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
 
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .app_data(web::Data::new(schema.clone()))
@@ -100,9 +117,10 @@ async fn main() -> std::io::Result<()> {
 
 async fn graphql(
     schema: web::Data<ServiceSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> actix_web::Result<actix_web::HttpResponse> {
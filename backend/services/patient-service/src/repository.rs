@@ -1,9 +1,28 @@
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::domain::{CreatePatientInput, Patient};
+use crate::domain::{CreatePatientInput, Patient, UpdatePatientInput};
 use common::error::{Error, Result};
 
+/// Payload of patient events: identifiers only. Consumers read anything
+/// else from patient-service, so identity documents and contact details
+/// never reach the outbox or the event bus.
+fn event_payload(patient: &Patient) -> serde_json::Value {
+    serde_json::json!({
+        "id": patient.id,
+        "mrn_number": patient.mrn_number,
+    })
+}
+
+/// Payload of `PATIENT_UPDATED`: the identifiers plus which fields changed
+fn updated_payload(patient: &Patient, changed_fields: &[&str]) -> serde_json::Value {
+    let mut payload = event_payload(patient);
+    payload["changed_fields"] = serde_json::json!(changed_fields);
+    payload
+}
+
 pub struct PatientRepository {
     pool: PgPool,
 }
@@ -36,6 +55,8 @@ impl PatientRepository {
         // Format phone number
         let mobile_number = common::utils::format_indian_phone(&input.mobile_number);
 
+        let mut tx = self.pool.begin().await?;
+
         let patient = sqlx::query_as::<_, Patient>(
             r#"
             INSERT INTO patient (
@@ -79,12 +100,143 @@ impl PatientRepository {
         .bind(common::types::RegistrationSource::WalkIn)
         .bind(created_by)
         .bind(created_by)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::PATIENT_EVENTS,
+            events::PATIENT_CREATED,
+            "Patient",
+            patient.id,
+            patient.organization_id,
+            Some(created_by),
+            &event_payload(&patient),
+        )
         .await?;
 
+        tx.commit().await?;
+
         Ok(patient)
     }
 
+    pub async fn update(&self, id: Uuid, input: UpdatePatientInput, updated_by: Uuid) -> Result<Patient> {
+        // Names, not values, of the changed fields for the event
+        let changed_fields: Vec<&str> = [
+            ("first_name", input.first_name.is_some()),
+            ("mobile_number", input.mobile_number.is_some()),
+            ("email", input.email.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then_some(field))
+        .collect();
+
+        // Build update query dynamically based on provided fields
+        let mut update_parts = Vec::new();
+        let mut bind_index = 1;
+
+        let mut query = String::from("UPDATE patient SET ");
+
+        if input.first_name.is_some() {
+            update_parts.push(format!("first_name = ${}", bind_index));
+            bind_index += 1;
+        }
+        if input.mobile_number.is_some() {
+            update_parts.push(format!("mobile_number = ${}", bind_index));
+            bind_index += 1;
+        }
+        if input.email.is_some() {
+            update_parts.push(format!("email = ${}", bind_index));
+            bind_index += 1;
+        }
+
+        update_parts.push(format!("updated_by = ${}", bind_index));
+        bind_index += 1;
+        update_parts.push("updated_at = NOW()".to_string());
+
+        query.push_str(&update_parts.join(", "));
+        query.push_str(&format!(" WHERE id = ${} AND is_deleted = false RETURNING *", bind_index));
+
+        let mut db_query = sqlx::query_as::<_, Patient>(&query);
+
+        if let Some(first_name) = input.first_name {
+            db_query = db_query.bind(first_name);
+        }
+        if let Some(mobile_number) = input.mobile_number {
+            db_query = db_query.bind(common::utils::format_indian_phone(&mobile_number));
+        }
+        if let Some(email) = input.email {
+            db_query = db_query.bind(email);
+        }
+
+        db_query = db_query.bind(updated_by);
+        db_query = db_query.bind(id);
+
+        let mut tx = self.pool.begin().await?;
+
+        let patient = db_query
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(Error::NotFound("Patient not found".to_string()))?;
+
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::PATIENT_EVENTS,
+            events::PATIENT_UPDATED,
+            "Patient",
+            patient.id,
+            patient.organization_id,
+            Some(updated_by),
+            &updated_payload(&patient, &changed_fields),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(patient)
+    }
+
+    /// Soft delete; returns false when the patient does not exist
+    pub async fn soft_delete(&self, id: Uuid, deleted_by: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let organization_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE patient
+            SET is_deleted = true,
+                deleted_at = NOW(),
+                updated_by = $2,
+                updated_at = NOW()
+            WHERE id = $1 AND is_deleted = false
+            RETURNING organization_id
+            "#,
+        )
+        .bind(id)
+        .bind(deleted_by)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(organization_id) = organization_id else {
+            return Ok(false);
+        };
+
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::PATIENT_EVENTS,
+            events::PATIENT_DELETED,
+            "Patient",
+            id,
+            organization_id,
+            Some(deleted_by),
+            &serde_json::json!({ "id": id }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
     pub async fn find_by_id(&self, id: Uuid) -> Result<Patient> {
         let patient = sqlx::query_as::<_, Patient>(
             "SELECT * FROM patient WHERE id = $1 AND is_deleted = false",
@@ -97,6 +249,17 @@ impl PatientRepository {
         Ok(patient)
     }
 
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Patient>> {
+        let patients = sqlx::query_as::<_, Patient>(
+            "SELECT * FROM patient WHERE id = ANY($1) AND is_deleted = false",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(patients)
+    }
+
     pub async fn find_by_mrn(&self, mrn_number: &str) -> Result<Patient> {
         let patient = sqlx::query_as::<_, Patient>(
             "SELECT * FROM patient WHERE mrn_number = $1 AND is_deleted = false",
@@ -0,0 +1,27 @@
+-- ============================================================================
+-- Westgard Multirule
+-- ============================================================================
+-- Adds the remaining multirule patterns, groups results into analytical runs
+-- so rules can be evaluated across control levels, and records which QC
+-- results made up each violation.
+
+ALTER TYPE qc_rule_enum ADD VALUE IF NOT EXISTS 'WESTGARD_2OF32S';  -- 2 of 3 controls exceed 2SD (same side)
+ALTER TYPE qc_rule_enum ADD VALUE IF NOT EXISTS 'WESTGARD_31S';     -- 3 consecutive controls exceed 1SD (same side)
+ALTER TYPE qc_rule_enum ADD VALUE IF NOT EXISTS 'WESTGARD_6X';      -- 6 consecutive controls on same side of mean
+ALTER TYPE qc_rule_enum ADD VALUE IF NOT EXISTS 'WESTGARD_8X';      -- 8 consecutive controls on same side of mean
+ALTER TYPE qc_rule_enum ADD VALUE IF NOT EXISTS 'WESTGARD_12X';     -- 12 consecutive controls on same side of mean
+
+-- CUSTOM rules: {"count": 2, "window": 3, "limit_sd": 2.0}
+ALTER TABLE qc_rule ADD COLUMN rule_parameters JSONB;
+
+-- Results sharing a run_id were measured in the same analytical run
+ALTER TABLE qc_result ADD COLUMN run_id UUID;
+UPDATE qc_result SET run_id = id WHERE run_id IS NULL;
+ALTER TABLE qc_result ALTER COLUMN run_id SET NOT NULL;
+
+CREATE INDEX idx_qc_result_run ON qc_result(run_id);
+CREATE INDEX idx_qc_result_test_history ON qc_result(test_id, equipment_id, result_date DESC, result_time DESC);
+
+ALTER TABLE qc_violation
+    ADD COLUMN contributing_result_ids UUID[] NOT NULL DEFAULT '{}',
+    ADD COLUMN evaluation_scope VARCHAR(30);
@@ -0,0 +1,10 @@
+-- Default rules for the multirule patterns added in the previous migration
+-- (new enum values cannot be used in the transaction that adds them)
+
+INSERT INTO qc_rule (id, organization_id, rule_name, rule_type, rule_description, is_active, is_blocking, violation_severity)
+VALUES
+(uuid_generate_v4(), (SELECT id FROM organization LIMIT 1), '2of3-2s Rejection', 'WESTGARD_2OF32S', 'Two of three controls exceed 2SD on same side', TRUE, TRUE, 'HIGH'),
+(uuid_generate_v4(), (SELECT id FROM organization LIMIT 1), '3-1s Rejection', 'WESTGARD_31S', 'Three consecutive controls exceed 1SD on same side', TRUE, TRUE, 'MEDIUM'),
+(uuid_generate_v4(), (SELECT id FROM organization LIMIT 1), '6-x Rejection', 'WESTGARD_6X', 'Six consecutive controls on same side of mean', TRUE, TRUE, 'MEDIUM'),
+(uuid_generate_v4(), (SELECT id FROM organization LIMIT 1), '8-x Rejection', 'WESTGARD_8X', 'Eight consecutive controls on same side of mean', TRUE, TRUE, 'MEDIUM'),
+(uuid_generate_v4(), (SELECT id FROM organization LIMIT 1), '12-x Rejection', 'WESTGARD_12X', 'Twelve consecutive controls on same side of mean', TRUE, TRUE, 'MEDIUM');
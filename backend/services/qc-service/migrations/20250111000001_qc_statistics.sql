@@ -0,0 +1,25 @@
+-- ============================================================================
+-- QC Statistics Maintenance
+-- ============================================================================
+-- qc_statistics keeps three rows per material and day: that day's results,
+-- the rolling window of the most recent accepted results, and every
+-- accepted result of the lot so far. Rejected (out of control) results are
+-- counted but left out of mean, SD and CV.
+
+CREATE TYPE qc_statistics_type AS ENUM (
+    'DAILY',
+    'ROLLING',
+    'CUMULATIVE'
+);
+
+ALTER TABLE qc_statistics
+    ADD COLUMN statistics_type qc_statistics_type NOT NULL DEFAULT 'DAILY',
+    ADD COLUMN lot_number VARCHAR(100),
+    ADD COLUMN window_size INTEGER,
+    ADD COLUMN updated_at TIMESTAMP;
+
+ALTER TABLE qc_statistics DROP CONSTRAINT unique_material_date;
+ALTER TABLE qc_statistics
+    ADD CONSTRAINT unique_material_date_type UNIQUE(qc_material_id, statistics_date, statistics_type);
+
+CREATE INDEX idx_qc_result_material_date ON qc_result(qc_material_id, result_date, result_time);
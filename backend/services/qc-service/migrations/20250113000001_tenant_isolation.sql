@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('qc_external_program');
+SELECT enable_tenant_isolation('qc_material');
+SELECT enable_tenant_isolation('qc_result');
+SELECT enable_tenant_isolation('qc_rule');
+SELECT enable_tenant_isolation('qc_violation');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('qc_corrective_action', 'qc_violation_id', 'qc_violation');
+SELECT enable_parent_isolation('qc_external_survey', 'program_id', 'qc_external_program');
+SELECT enable_parent_isolation('qc_material_rule', 'qc_material_id', 'qc_material');
+SELECT enable_parent_isolation('qc_statistics', 'qc_material_id', 'qc_material');
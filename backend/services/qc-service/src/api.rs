@@ -1,5 +1,7 @@
 use async_graphql::{Context, Object, Result, ErrorExtensions};
 use uuid::Uuid;
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use common::pagination::PaginationParams;
 use crate::domain::*;
 use crate::service::QcService;
@@ -13,6 +15,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get QC material by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_material(
         &self,
         ctx: &Context<'_>,
@@ -24,6 +27,7 @@ impl QueryRoot {
     }
 
     /// Get QC material by code
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_material_by_code(
         &self,
         ctx: &Context<'_>,
@@ -35,6 +39,7 @@ impl QueryRoot {
     }
 
     /// List QC materials with filtering and pagination
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_materials(
         &self,
         ctx: &Context<'_>,
@@ -61,6 +66,7 @@ impl QueryRoot {
     }
 
     /// Get QC rule by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_rule(
         &self,
         ctx: &Context<'_>,
@@ -72,6 +78,7 @@ impl QueryRoot {
     }
 
     /// List QC rules for organization
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_rules(
         &self,
         ctx: &Context<'_>,
@@ -82,6 +89,7 @@ impl QueryRoot {
     }
 
     /// Get QC result by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_result(
         &self,
         ctx: &Context<'_>,
@@ -93,6 +101,7 @@ impl QueryRoot {
     }
 
     /// List QC results with filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_results(
         &self,
         ctx: &Context<'_>,
@@ -102,7 +111,36 @@ impl QueryRoot {
         Ok(service.list_qc_results(filter).await.map_err(|e| e.extend())?)
     }
 
+    /// QC state governing patient results of a test measured on an instrument at a given time
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
+    async fn qc_gate(
+        &self,
+        ctx: &Context<'_>,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        lot_number: Option<String>,
+        at: chrono::NaiveDateTime,
+    ) -> Result<QcGate> {
+        let service = ctx.data::<QcService>()?;
+        service
+            .qc_gate(test_id, equipment_id, lot_number, at)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// Window of patient results affected by an out of control QC result
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
+    async fn qc_lookback_window(
+        &self,
+        ctx: &Context<'_>,
+        qc_result_id: Uuid,
+    ) -> Result<QcLookbackWindow> {
+        let service = ctx.data::<QcService>()?;
+        service.qc_lookback_window(qc_result_id).await.map_err(|e| e.extend())
+    }
+
     /// Get QC violation by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_violation(
         &self,
         ctx: &Context<'_>,
@@ -114,6 +152,7 @@ impl QueryRoot {
     }
 
     /// List QC violations with filtering
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn qc_violations(
         &self,
         ctx: &Context<'_>,
@@ -124,6 +163,7 @@ impl QueryRoot {
     }
 
     /// Get corrective action by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn corrective_action(
         &self,
         ctx: &Context<'_>,
@@ -135,6 +175,7 @@ impl QueryRoot {
     }
 
     /// List corrective actions for violation
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn corrective_actions(
         &self,
         ctx: &Context<'_>,
@@ -145,6 +186,7 @@ impl QueryRoot {
     }
 
     /// Get external QC program by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn external_program(
         &self,
         ctx: &Context<'_>,
@@ -156,6 +198,7 @@ impl QueryRoot {
     }
 
     /// List external QC programs for organization
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
     async fn external_programs(
         &self,
         ctx: &Context<'_>,
@@ -164,6 +207,56 @@ impl QueryRoot {
         let service = ctx.data::<QcService>()?;
         Ok(service.list_external_programs(organization_id).await.map_err(|e| e.extend())?)
     }
+
+    /// Daily, rolling and cumulative statistics of a QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
+    async fn qc_statistics(
+        &self,
+        ctx: &Context<'_>,
+        qc_material_id: Uuid,
+        statistics_type: Option<QcStatisticsType>,
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<QcStatistics>> {
+        let service = ctx.data::<QcService>()?;
+        service
+            .get_qc_statistics(qc_material_id, statistics_type, from_date, to_date)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// Levey-Jennings series for a QC material's control level across lots
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
+    async fn levey_jennings(
+        &self,
+        ctx: &Context<'_>,
+        qc_material_id: Uuid,
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<LeveyJenningsChart> {
+        let service = ctx.data::<QcService>()?;
+        service
+            .levey_jennings(qc_material_id, from_date, to_date)
+            .await
+            .map_err(|e| e.extend())
+    }
+
+    /// Recommend Westgard rules for a QC material from its sigma metric
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_READ)")]
+    async fn westgard_rule_selection(
+        &self,
+        ctx: &Context<'_>,
+        qc_material_id: Uuid,
+        allowable_error_percent: f64,
+        #[graphql(default = 0.0)] bias_percent: f64,
+        #[graphql(default = 2)] control_levels: u32,
+    ) -> Result<WestgardRuleSelection> {
+        let service = ctx.data::<QcService>()?;
+        service
+            .westgard_rule_selection(qc_material_id, allowable_error_percent, bias_percent, control_levels as usize)
+            .await
+            .map_err(|e| e.extend())
+    }
 }
 
 // ============================================================================
@@ -175,34 +268,33 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn create_qc_material(
         &self,
         ctx: &Context<'_>,
         input: CreateQcMaterialInput,
     ) -> Result<QcMaterial> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_qc_material(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn update_qc_material(
         &self,
         ctx: &Context<'_>,
         input: UpdateQcMaterialInput,
     ) -> Result<QcMaterial> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_qc_material(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Delete QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn delete_qc_material(
         &self,
         ctx: &Context<'_>,
@@ -213,34 +305,33 @@ impl MutationRoot {
     }
 
     /// Create QC rule
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn create_qc_rule(
         &self,
         ctx: &Context<'_>,
         input: CreateQcRuleInput,
     ) -> Result<QcRule> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_qc_rule(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Assign rule to QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn assign_rule_to_material(
         &self,
         ctx: &Context<'_>,
         input: AssignRuleToMaterialInput,
     ) -> Result<QcMaterialRule> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.assign_rule_to_material(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Unassign rule from QC material
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn unassign_rule_from_material(
         &self,
         ctx: &Context<'_>,
@@ -252,102 +343,119 @@ impl MutationRoot {
     }
 
     /// Record QC result
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_ENTRY)")]
     async fn record_qc_result(
         &self,
         ctx: &Context<'_>,
         input: RecordQcResultInput,
     ) -> Result<QcResult> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.record_qc_result(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Review QC result
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_REVIEW)")]
     async fn review_qc_result(
         &self,
         ctx: &Context<'_>,
         input: ReviewQcResultInput,
     ) -> Result<QcResult> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get reviewed_by from authenticated user context
-        let reviewed_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let reviewed_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.review_qc_result(input, reviewed_by).await.map_err(|e| e.extend())?)
     }
 
     /// Acknowledge violation
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_REVIEW)")]
     async fn acknowledge_violation(
         &self,
         ctx: &Context<'_>,
         input: AcknowledgeViolationInput,
     ) -> Result<QcViolation> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get acknowledged_by from authenticated user context
-        let acknowledged_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let acknowledged_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.acknowledge_violation(input, acknowledged_by).await.map_err(|e| e.extend())?)
     }
 
     /// Resolve violation
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_REVIEW)")]
     async fn resolve_violation(
         &self,
         ctx: &Context<'_>,
         input: ResolveViolationInput,
     ) -> Result<QcViolation> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get resolved_by from authenticated user context
-        let resolved_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let resolved_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.resolve_violation(input, resolved_by).await.map_err(|e| e.extend())?)
     }
 
     /// Create corrective action
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_REVIEW)")]
     async fn create_corrective_action(
         &self,
         ctx: &Context<'_>,
         input: CreateCorrectiveActionInput,
     ) -> Result<QcCorrectiveAction> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_corrective_action(input, created_by).await.map_err(|e| e.extend())?)
     }
 
     /// Update corrective action
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_REVIEW)")]
     async fn update_corrective_action(
         &self,
         ctx: &Context<'_>,
         input: UpdateCorrectiveActionInput,
     ) -> Result<QcCorrectiveAction> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get updated_by from authenticated user context
-        let updated_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.update_corrective_action(input, updated_by).await.map_err(|e| e.extend())?)
     }
 
     /// Create external QC program
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
     async fn create_external_program(
         &self,
         ctx: &Context<'_>,
         input: CreateExternalProgramInput,
     ) -> Result<QcExternalProgram> {
         let service = ctx.data::<QcService>()?;
-
-        // In production, get created_by from authenticated user context
-        let created_by = Uuid::new_v4(); // TODO: Replace with actual user ID from JWT
+        let created_by = ctx.user_id().map_err(|e| e.extend())?;
 
         Ok(service.create_external_program(input, created_by).await.map_err(|e| e.extend())?)
     }
+
+    /// Establish a new lot's target mean and SD from its first runs
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
+    async fn establish_qc_targets(
+        &self,
+        ctx: &Context<'_>,
+        input: EstablishQcTargetsInput,
+    ) -> Result<QcMaterial> {
+        let service = ctx.data::<QcService>()?;
+        let updated_by = ctx.user_id().map_err(|e| e.extend())?;
+
+        service.establish_qc_targets(input, updated_by).await.map_err(|e| e.extend())
+    }
+
+    /// Rebuild a QC material's statistics from all of its results
+    #[graphql(guard = "PermissionGuard::new(permissions::QC_MANAGE)")]
+    async fn recalculate_qc_statistics(
+        &self,
+        ctx: &Context<'_>,
+        qc_material_id: Uuid,
+    ) -> Result<Vec<QcStatistics>> {
+        let service = ctx.data::<QcService>()?;
+        service.recalculate_qc_statistics(qc_material_id).await.map_err(|e| e.extend())
+    }
 }
 
 // ============================================================================
@@ -36,15 +36,32 @@ pub enum QcResultStatus {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
-#[sqlx(type_name = "qc_rule_type", rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "qc_rule_enum")]
 pub enum QcRuleType {
-    Westgard12s,   // 1 control exceeds 2SD
+    #[sqlx(rename = "WESTGARD_12S")]
+    Westgard12s,   // 1 control exceeds 2SD (warning)
+    #[sqlx(rename = "WESTGARD_13S")]
     Westgard13s,   // 1 control exceeds 3SD
+    #[sqlx(rename = "WESTGARD_22S")]
     Westgard22s,   // 2 consecutive controls exceed 2SD (same side)
-    WestgardR4s,   // Range between 2 controls exceeds 4SD
+    #[sqlx(rename = "WESTGARD_R4S")]
+    WestgardR4s,   // One control exceeds +2SD and another -2SD in the same run
+    #[sqlx(rename = "WESTGARD_41S")]
     Westgard41s,   // 4 consecutive controls exceed 1SD (same side)
+    #[sqlx(rename = "WESTGARD_10X")]
     Westgard10x,   // 10 consecutive controls on same side of mean
-    Custom,
+    #[sqlx(rename = "WESTGARD_2OF32S")]
+    Westgard2of32s, // 2 of 3 controls exceed 2SD (same side)
+    #[sqlx(rename = "WESTGARD_31S")]
+    Westgard31s,   // 3 consecutive controls exceed 1SD (same side)
+    #[sqlx(rename = "WESTGARD_6X")]
+    Westgard6x,    // 6 consecutive controls on same side of mean
+    #[sqlx(rename = "WESTGARD_8X")]
+    Westgard8x,    // 8 consecutive controls on same side of mean
+    #[sqlx(rename = "WESTGARD_12X")]
+    Westgard12x,   // 12 consecutive controls on same side of mean
+    #[sqlx(rename = "CUSTOM")]
+    Custom,        // Parameters in QcRule.rule_parameters
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
@@ -56,6 +73,14 @@ pub enum ViolationSeverity {
     Critical,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
+#[sqlx(type_name = "qc_statistics_type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QcStatisticsType {
+    Daily,       // Results of the day
+    Rolling,     // Most recent accepted results up to the day
+    Cumulative,  // Every accepted result of the lot up to the day
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
 #[sqlx(type_name = "corrective_action_status", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum CorrectiveActionStatus {
@@ -204,6 +229,7 @@ pub struct QcRule {
     pub rule_name: String,
     pub rule_type: QcRuleType,
     pub rule_description: Option<String>,
+    pub rule_parameters: Option<serde_json::Value>,
 
     // Configuration
     #[graphql(skip)]
@@ -266,6 +292,9 @@ pub struct QcResult {
     // Equipment
     pub equipment_id: Option<Uuid>,
 
+    // Analytical run shared by the control levels measured together
+    pub run_id: Uuid,
+
     // Result Data
     pub result_date: NaiveDate,
     pub result_time: NaiveTime,
@@ -345,6 +374,10 @@ pub struct QcViolation {
     pub rule_type: QcRuleType,
     pub rule_description: Option<String>,
 
+    // QC results that together violated the rule, oldest first
+    pub contributing_result_ids: Vec<Uuid>,
+    pub evaluation_scope: Option<String>,
+
     // Violation Details
     pub violation_date: NaiveDate,
     pub violation_time: NaiveTime,
@@ -443,10 +476,15 @@ impl QcCorrectiveAction {
 pub struct QcStatistics {
     pub id: Uuid,
     pub qc_material_id: Uuid,
+    pub statistics_type: QcStatisticsType,
+    pub lot_number: Option<String>,
 
     // Date
     pub statistics_date: NaiveDate,
 
+    // Results considered by rolling statistics
+    pub window_size: Option<i32>,
+
     // Sample Size
     pub n_count: i32,
 
@@ -465,6 +503,7 @@ pub struct QcStatistics {
 
     // Metadata
     pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
 }
 
 // ============================================================================
@@ -544,6 +583,116 @@ pub struct QcExternalSurvey {
     pub updated_at: Option<NaiveDateTime>,
 }
 
+// ============================================================================
+// Westgard Rule Selection
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct WestgardRuleSelection {
+    /// (TEa - |bias|) / CV
+    pub sigma: f64,
+    pub rules: Vec<QcRuleType>,
+    /// Control measurements per run (N)
+    pub controls_per_run: i32,
+    /// Runs the rules look back over (R)
+    pub runs: i32,
+}
+
+// ============================================================================
+// Levey-Jennings Chart
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LeveyJenningsPoint {
+    pub qc_result_id: Uuid,
+    pub result_number: String,
+    pub qc_material_id: Uuid,
+    pub lot_number: String,
+    pub run_id: Uuid,
+    pub result_date: NaiveDate,
+    pub result_time: NaiveTime,
+    pub result_value: Decimal,
+    /// Mean and SD the result was judged against
+    pub mean_value: Option<Decimal>,
+    pub sd_value: Option<Decimal>,
+    pub z_score: Option<Decimal>,
+    pub result_status: QcResultStatus,
+    /// Names of the Westgard rules the result violated
+    pub rule_flags: Vec<String>,
+}
+
+/// First result of a new control lot on the chart
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LeveyJenningsLotBoundary {
+    pub qc_material_id: Uuid,
+    pub lot_number: String,
+    pub starts_at: NaiveDateTime,
+    pub mean_value: Option<Decimal>,
+    pub sd_value: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct LeveyJenningsCorrectiveAction {
+    pub corrective_action_id: Uuid,
+    pub qc_violation_id: Uuid,
+    pub qc_result_id: Uuid,
+    pub rule_type: QcRuleType,
+    pub action_description: String,
+    pub action_status: CorrectiveActionStatus,
+    pub created_at: Option<NaiveDateTime>,
+    pub completed_date: Option<NaiveDate>,
+}
+
+/// Levey-Jennings series for one control level of a test, across lots
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct LeveyJenningsChart {
+    pub test_id: Uuid,
+    pub test_name: Option<String>,
+    pub level_number: Option<i32>,
+    pub level_name: Option<String>,
+    pub points: Vec<LeveyJenningsPoint>,
+    pub lot_boundaries: Vec<LeveyJenningsLotBoundary>,
+    pub corrective_actions: Vec<LeveyJenningsCorrectiveAction>,
+}
+
+// ============================================================================
+// QC Release Gate
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum QcGateStatus {
+    InControl,     // Last QC run passed (possibly with warnings)
+    OutOfControl,  // Last QC run was rejected and no corrective action is complete
+    Corrected,     // Last QC run was rejected and a corrective action is complete
+    NoQc,          // No QC was run for the test on this instrument
+}
+
+/// QC state governing patient results measured at a point in time: the
+/// last QC run of the test on the instrument at or before that time
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct QcGate {
+    pub status: QcGateStatus,
+    /// Patient results measured under this QC must not be released
+    pub hold: bool,
+    pub qc_result_id: Option<Uuid>,
+    pub qc_result_number: Option<String>,
+    pub performed_at: Option<NaiveDateTime>,
+    pub rules_violated: Vec<String>,
+}
+
+/// Patient results measured in this window were reported without a good QC
+/// behind them and are candidates for re-testing
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct QcLookbackWindow {
+    pub qc_result_id: Uuid,
+    pub test_id: Uuid,
+    pub equipment_id: Option<Uuid>,
+    /// Last accepted QC run before the failure; open when there was none
+    pub from: Option<NaiveDateTime>,
+    /// The failed QC run
+    pub to: NaiveDateTime,
+}
+
 // ============================================================================
 // Input DTOs
 // ============================================================================
@@ -605,6 +754,8 @@ pub struct RecordQcResultInput {
     pub result_value: Decimal,
 
     pub equipment_id: Option<Uuid>,
+    /// Levels measured in the same run share a run id; a new run is started when omitted
+    pub run_id: Option<Uuid>,
     pub performed_by: Option<Uuid>,
     pub performed_by_name: Option<String>,
 
@@ -623,6 +774,8 @@ pub struct CreateQcRuleInput {
     pub rule_name: String,
     pub rule_type: QcRuleType,
     pub rule_description: Option<String>,
+    /// Required for CUSTOM rules, e.g. {"count": 2, "window": 3, "limit_sd": 2.0}
+    pub rule_parameters: Option<serde_json::Value>,
     pub is_blocking: Option<bool>,
     pub violation_severity: ViolationSeverity,
 }
@@ -688,6 +841,13 @@ pub struct QcMaterialFilter {
     pub search_query: Option<String>,
 }
 
+#[derive(Debug, Clone, InputObject)]
+pub struct EstablishQcTargetsInput {
+    pub qc_material_id: Uuid,
+    /// First runs of the lot to use; 20 when omitted
+    pub runs: Option<i32>,
+}
+
 #[derive(Debug, Clone, InputObject)]
 pub struct QcResultFilter {
     pub qc_material_id: Option<Uuid>,
@@ -1,13 +1,17 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::DatabasePool;
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod westgard;
+mod statistics;
 
 use repository::*;
 use service::QcService;
@@ -18,9 +22,10 @@ type QcSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<QcSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -92,18 +97,18 @@ async fn main() -> std::io::Result<()> {
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -117,6 +122,7 @@ async fn main() -> std::io::Result<()> {
     let violation_repo = QcViolationRepository::new(pool.clone());
     let corrective_action_repo = QcCorrectiveActionRepository::new(pool.clone());
     let external_program_repo = QcExternalProgramRepository::new(pool.clone());
+    let statistics_repo = QcStatisticsRepository::new(pool.clone());
 
     // Create service
     let qc_service = QcService::new(
@@ -127,6 +133,7 @@ async fn main() -> std::io::Result<()> {
         violation_repo,
         corrective_action_repo,
         external_program_repo,
+        statistics_repo,
     );
 
     // Build GraphQL schema
@@ -135,8 +142,8 @@ async fn main() -> std::io::Result<()> {
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: qcMaterial, qcMaterialByCode, qcMaterials, qcRule, qcRules, qcResult, qcResults, qcViolation, qcViolations, correctiveAction, correctiveActions, externalProgram, externalPrograms");
-    tracing::info!("  Mutations: createQcMaterial, updateQcMaterial, deleteQcMaterial, createQcRule, assignRuleToMaterial, unassignRuleFromMaterial, recordQcResult, reviewQcResult, acknowledgeViolation, resolveViolation, createCorrectiveAction, updateCorrectiveAction, createExternalProgram");
+    tracing::info!("  Queries: qcMaterial, qcMaterialByCode, qcMaterials, qcRule, qcRules, qcResult, qcResults, qcViolation, qcViolations, correctiveAction, correctiveActions, externalProgram, externalPrograms, qcStatistics, leveyJennings, westgardRuleSelection, qcGate, qcLookbackWindow");
+    tracing::info!("  Mutations: createQcMaterial, updateQcMaterial, deleteQcMaterial, createQcRule, assignRuleToMaterial, unassignRuleFromMaterial, recordQcResult, reviewQcResult, acknowledgeViolation, resolveViolation, createCorrectiveAction, updateCorrectiveAction, createExternalProgram, establishQcTargets, recalculateQcStatistics");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -146,8 +153,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
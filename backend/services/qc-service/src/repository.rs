@@ -3,6 +3,8 @@ use uuid::Uuid;
 use common::error::{Error, Result};
 use common::pagination::{Paginated, PaginationParams};
 use crate::domain::*;
+use crate::statistics;
+use rust_decimal::Decimal;
 
 // ============================================================================
 // QC Material Repository
@@ -194,6 +196,71 @@ impl QcMaterialRepository {
         Ok(material)
     }
 
+    /// Sets the lot's target and working mean/SD with their ±1/2/3 SD limits
+    pub async fn set_control_limits(
+        &self,
+        id: Uuid,
+        mean: Decimal,
+        sd: Decimal,
+        cv: Option<Decimal>,
+        updated_by: Uuid,
+    ) -> Result<QcMaterial> {
+        let [sd_1, sd_2, sd_3] = statistics::control_limits(mean, sd);
+
+        let material = sqlx::query_as::<_, QcMaterial>(
+            r#"
+            UPDATE qc_material
+            SET target_mean = $2, target_sd = $3,
+                mean_value = $2, sd_value = $3, cv_value = $4,
+                sd_1_low = $5, sd_1_high = $6,
+                sd_2_low = $7, sd_2_high = $8,
+                sd_3_low = $9, sd_3_high = $10,
+                updated_by = $11
+            WHERE id = $1 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(mean)
+        .bind(sd)
+        .bind(cv)
+        .bind(sd_1.0)
+        .bind(sd_1.1)
+        .bind(sd_2.0)
+        .bind(sd_2.1)
+        .bind(sd_3.0)
+        .bind(sd_3.1)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(material)
+    }
+
+    /// Every lot of the same control level of a test
+    pub async fn list_lots_of_level(&self, material: &QcMaterial) -> Result<Vec<QcMaterial>> {
+        let materials = sqlx::query_as::<_, QcMaterial>(
+            r#"
+            SELECT * FROM qc_material
+            WHERE organization_id = $1
+              AND test_id = $2
+              AND level_number IS NOT DISTINCT FROM $3
+              AND qc_type = $4
+              AND is_deleted = FALSE
+            "#
+        )
+        .bind(material.organization_id)
+        .bind(material.test_id)
+        .bind(material.level_number)
+        .bind(material.qc_type)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(materials)
+    }
+
     pub async fn delete(&self, id: Uuid) -> Result<bool> {
         let result = sqlx::query(
             "UPDATE qc_material SET is_deleted = TRUE WHERE id = $1"
@@ -228,9 +295,9 @@ impl QcRuleRepository {
             r#"
             INSERT INTO qc_rule (
                 id, organization_id, rule_name, rule_type, rule_description,
-                is_active, is_blocking, violation_severity, created_by
+                rule_parameters, is_active, is_blocking, violation_severity, created_by
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#
         )
@@ -239,6 +306,7 @@ impl QcRuleRepository {
         .bind(&input.rule_name)
         .bind(input.rule_type)
         .bind(&input.rule_description)
+        .bind(&input.rule_parameters)
         .bind(true)
         .bind(input.is_blocking.unwrap_or(false))
         .bind(input.violation_severity)
@@ -379,13 +447,13 @@ impl QcResultRepository {
             r#"
             INSERT INTO qc_result (
                 id, result_number, qc_material_id, organization_id,
-                test_id, test_name, equipment_id,
+                test_id, test_name, equipment_id, run_id,
                 result_date, result_time, result_value,
                 mean_value, sd_value,
                 result_status,
                 performed_by, performed_by_name, comments
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             RETURNING *
             "#
         )
@@ -396,6 +464,7 @@ impl QcResultRepository {
         .bind(material.test_id)
         .bind(test_name)
         .bind(input.equipment_id)
+        .bind(input.run_id.unwrap_or_else(Uuid::new_v4))
         .bind(input.result_date)
         .bind(input.result_time)
         .bind(input.result_value)
@@ -506,6 +575,145 @@ impl QcResultRepository {
 
         Ok(results)
     }
+
+    /// Most recent results for every control level of a test on one
+    /// instrument, returned oldest first
+    pub async fn get_recent_results_for_test(
+        &self,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        limit: i32,
+    ) -> Result<Vec<QcResult>> {
+        let mut results = sqlx::query_as::<_, QcResult>(
+            r#"
+            SELECT * FROM qc_result
+            WHERE test_id = $1 AND equipment_id IS NOT DISTINCT FROM $2
+            ORDER BY result_date DESC, result_time DESC, created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(test_id)
+        .bind(equipment_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        results.reverse();
+        Ok(results)
+    }
+
+    /// A material's results in measurement order
+    pub async fn list_by_material(&self, qc_material_id: Uuid) -> Result<Vec<QcResult>> {
+        let results = sqlx::query_as::<_, QcResult>(
+            r#"
+            SELECT * FROM qc_result
+            WHERE qc_material_id = $1
+            ORDER BY result_date, result_time, created_at
+            "#
+        )
+        .bind(qc_material_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
+    /// Results of any of the materials in a date range, in measurement order
+    pub async fn list_for_chart(
+        &self,
+        qc_material_ids: &[Uuid],
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<QcResult>> {
+        let results = sqlx::query_as::<_, QcResult>(
+            r#"
+            SELECT * FROM qc_result
+            WHERE qc_material_id = ANY($1)
+              AND ($2::DATE IS NULL OR result_date >= $2)
+              AND ($3::DATE IS NULL OR result_date <= $3)
+            ORDER BY result_date, result_time, created_at
+            "#
+        )
+        .bind(qc_material_ids)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
+    /// Every result of the last QC run of a test on an instrument at or
+    /// before `at`, optionally limited to one control lot
+    pub async fn find_last_run_at(
+        &self,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        lot_number: Option<&str>,
+        at: chrono::NaiveDateTime,
+    ) -> Result<Vec<QcResult>> {
+        let results = sqlx::query_as::<_, QcResult>(
+            r#"
+            SELECT * FROM qc_result
+            WHERE run_id = (
+                SELECT r.run_id
+                FROM qc_result r
+                JOIN qc_material m ON m.id = r.qc_material_id
+                WHERE r.test_id = $1
+                  AND r.equipment_id IS NOT DISTINCT FROM $2
+                  AND ($3::VARCHAR IS NULL OR m.lot_number = $3)
+                  AND r.result_date + r.result_time <= $4
+                  AND r.result_status <> 'PENDING'
+                ORDER BY r.result_date DESC, r.result_time DESC, r.created_at DESC
+                LIMIT 1
+            )
+            ORDER BY result_date, result_time, created_at
+            "#
+        )
+        .bind(test_id)
+        .bind(equipment_id)
+        .bind(lot_number)
+        .bind(at)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
+    /// When the last run with no rejected level was measured before `before`
+    pub async fn find_last_accepted_run_before(
+        &self,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        before: chrono::NaiveDateTime,
+    ) -> Result<Option<chrono::NaiveDateTime>> {
+        let accepted_at: (Option<chrono::NaiveDateTime>,) = sqlx::query_as(
+            r#"
+            SELECT MAX(r.result_date + r.result_time)
+            FROM qc_result r
+            WHERE r.test_id = $1
+              AND r.equipment_id IS NOT DISTINCT FROM $2
+              AND r.result_date + r.result_time < $3
+              AND r.result_status IN ('IN_CONTROL', 'WARNING')
+              AND NOT EXISTS (
+                  SELECT 1 FROM qc_result o
+                  WHERE o.run_id = r.run_id AND o.result_status = 'OUT_OF_CONTROL'
+              )
+            "#
+        )
+        .bind(test_id)
+        .bind(equipment_id)
+        .bind(before)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(accepted_at.0)
+    }
 }
 
 // ============================================================================
@@ -524,12 +732,10 @@ impl QcViolationRepository {
 
     pub async fn create(
         &self,
-        qc_result_id: Uuid,
-        qc_material_id: Uuid,
-        organization_id: Uuid,
+        qc_result: &QcResult,
         qc_rule: &QcRule,
-        violation_date: chrono::NaiveDate,
-        violation_time: chrono::NaiveTime,
+        contributing_result_ids: &[Uuid],
+        evaluation_scope: &str,
     ) -> Result<QcViolation> {
         let id = Uuid::new_v4();
 
@@ -538,21 +744,24 @@ impl QcViolationRepository {
             INSERT INTO qc_violation (
                 id, qc_result_id, qc_material_id, organization_id,
                 qc_rule_id, rule_type, rule_description,
+                contributing_result_ids, evaluation_scope,
                 violation_date, violation_time, severity
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING *
             "#
         )
         .bind(id)
-        .bind(qc_result_id)
-        .bind(qc_material_id)
-        .bind(organization_id)
+        .bind(qc_result.id)
+        .bind(qc_result.qc_material_id)
+        .bind(qc_result.organization_id)
         .bind(qc_rule.id)
         .bind(qc_rule.rule_type)
         .bind(&qc_rule.rule_description)
-        .bind(violation_date)
-        .bind(violation_time)
+        .bind(contributing_result_ids)
+        .bind(evaluation_scope)
+        .bind(qc_result.result_date)
+        .bind(qc_result.result_time)
         .bind(qc_rule.violation_severity)
         .fetch_one(&self.pool)
         .await
@@ -690,6 +899,48 @@ impl QcCorrectiveActionRepository {
         Ok(action)
     }
 
+    /// Whether a corrective action raised against any of the QC results'
+    /// violations has been completed
+    pub async fn has_completed_for_results(&self, qc_result_ids: &[Uuid]) -> Result<bool> {
+        let completed: (bool,) = sqlx::query_as(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM qc_corrective_action a
+                JOIN qc_violation v ON v.id = a.qc_violation_id
+                WHERE v.qc_result_id = ANY($1)
+                  AND a.action_status IN ('COMPLETED', 'VERIFIED')
+            )
+            "#
+        )
+        .bind(qc_result_ids)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(completed.0)
+    }
+
+    /// Corrective actions raised against violations of the QC results
+    pub async fn list_for_results(&self, qc_result_ids: &[Uuid]) -> Result<Vec<LeveyJenningsCorrectiveAction>> {
+        let actions = sqlx::query_as::<_, LeveyJenningsCorrectiveAction>(
+            r#"
+            SELECT a.id AS corrective_action_id, a.qc_violation_id, v.qc_result_id, v.rule_type,
+                   a.action_description, a.action_status, a.created_at, a.completed_date
+            FROM qc_corrective_action a
+            JOIN qc_violation v ON v.id = a.qc_violation_id
+            WHERE v.qc_result_id = ANY($1)
+            ORDER BY a.created_at
+            "#
+        )
+        .bind(qc_result_ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(actions)
+    }
+
     pub async fn list_by_violation(&self, qc_violation_id: Uuid) -> Result<Vec<QcCorrectiveAction>> {
         let actions = sqlx::query_as::<_, QcCorrectiveAction>(
             "SELECT * FROM qc_corrective_action WHERE qc_violation_id = $1 ORDER BY created_at DESC"
@@ -799,3 +1050,95 @@ impl QcExternalProgramRepository {
         Ok(programs)
     }
 }
+
+// ============================================================================
+// QC Statistics Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct QcStatisticsRepository {
+    pool: PgPool,
+}
+
+impl QcStatisticsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn upsert(&self, statistics: &QcStatistics) -> Result<QcStatistics> {
+        let saved = sqlx::query_as::<_, QcStatistics>(
+            r#"
+            INSERT INTO qc_statistics (
+                id, qc_material_id, statistics_type, lot_number, statistics_date, window_size,
+                n_count, mean_value, sd_value, cv_value, min_value, max_value, range_value,
+                in_control_count, out_of_control_count, warning_count
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            ON CONFLICT (qc_material_id, statistics_date, statistics_type) DO UPDATE
+            SET lot_number = EXCLUDED.lot_number,
+                window_size = EXCLUDED.window_size,
+                n_count = EXCLUDED.n_count,
+                mean_value = EXCLUDED.mean_value,
+                sd_value = EXCLUDED.sd_value,
+                cv_value = EXCLUDED.cv_value,
+                min_value = EXCLUDED.min_value,
+                max_value = EXCLUDED.max_value,
+                range_value = EXCLUDED.range_value,
+                in_control_count = EXCLUDED.in_control_count,
+                out_of_control_count = EXCLUDED.out_of_control_count,
+                warning_count = EXCLUDED.warning_count,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(statistics.id)
+        .bind(statistics.qc_material_id)
+        .bind(statistics.statistics_type)
+        .bind(&statistics.lot_number)
+        .bind(statistics.statistics_date)
+        .bind(statistics.window_size)
+        .bind(statistics.n_count)
+        .bind(statistics.mean_value)
+        .bind(statistics.sd_value)
+        .bind(statistics.cv_value)
+        .bind(statistics.min_value)
+        .bind(statistics.max_value)
+        .bind(statistics.range_value)
+        .bind(statistics.in_control_count)
+        .bind(statistics.out_of_control_count)
+        .bind(statistics.warning_count)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(saved)
+    }
+
+    pub async fn list(
+        &self,
+        qc_material_id: Uuid,
+        statistics_type: Option<QcStatisticsType>,
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<QcStatistics>> {
+        let statistics = sqlx::query_as::<_, QcStatistics>(
+            r#"
+            SELECT * FROM qc_statistics
+            WHERE qc_material_id = $1
+              AND ($2::qc_statistics_type IS NULL OR statistics_type = $2)
+              AND ($3::DATE IS NULL OR statistics_date >= $3)
+              AND ($4::DATE IS NULL OR statistics_date <= $4)
+            ORDER BY statistics_date, statistics_type
+            "#
+        )
+        .bind(qc_material_id)
+        .bind(statistics_type)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(statistics)
+    }
+}
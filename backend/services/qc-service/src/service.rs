@@ -3,8 +3,14 @@ use common::error::{Error, Result};
 use common::pagination::{Paginated, PaginationParams};
 use crate::domain::*;
 use crate::repository::*;
+use crate::statistics;
+use crate::westgard;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 
+/// Enough results to cover the longest rule (12x) across three control levels
+const WESTGARD_HISTORY_LIMIT: i32 = 36;
+
 // ============================================================================
 // QC Service
 // ============================================================================
@@ -18,9 +24,11 @@ pub struct QcService {
     violation_repo: QcViolationRepository,
     corrective_action_repo: QcCorrectiveActionRepository,
     external_program_repo: QcExternalProgramRepository,
+    statistics_repo: QcStatisticsRepository,
 }
 
 impl QcService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         material_repo: QcMaterialRepository,
         rule_repo: QcRuleRepository,
@@ -29,6 +37,7 @@ impl QcService {
         violation_repo: QcViolationRepository,
         corrective_action_repo: QcCorrectiveActionRepository,
         external_program_repo: QcExternalProgramRepository,
+        statistics_repo: QcStatisticsRepository,
     ) -> Self {
         Self {
             material_repo,
@@ -38,6 +47,7 @@ impl QcService {
             violation_repo,
             corrective_action_repo,
             external_program_repo,
+            statistics_repo,
         }
     }
 
@@ -167,6 +177,9 @@ impl QcService {
             return Err(Error::Validation("Rule name cannot be empty".to_string()));
         }
 
+        westgard::pattern_for(input.rule_type, input.rule_parameters.as_ref())
+            .map_err(|e| Error::Validation(format!("Invalid rule parameters: {}", e)))?;
+
         let rule = self.rule_repo.create(input, created_by).await?;
 
         tracing::info!("QC Rule created: {} ({:?})", rule.rule_name, rule.rule_type);
@@ -263,6 +276,11 @@ impl QcService {
         // Evaluate Westgard rules
         result = self.evaluate_westgard_rules(result).await?;
 
+        // Statistics are derived data; a failure here must not lose the result
+        if let Err(e) = self.update_statistics(&material, result.result_date).await {
+            tracing::warn!("Failed to update QC statistics for {}: {}", material.material_code, e);
+        }
+
         tracing::info!(
             "QC Result recorded: {} - Status: {:?}",
             result.result_number,
@@ -298,6 +316,255 @@ impl QcService {
         Ok(result)
     }
 
+    // ========================================================================
+    // QC Statistics and Levey-Jennings
+    // ========================================================================
+
+    /// Recomputes the material's statistics from `from_date` on, since a
+    /// result on a day also moves the rolling and cumulative figures after it
+    async fn update_statistics(
+        &self,
+        material: &QcMaterial,
+        from_date: chrono::NaiveDate,
+    ) -> Result<Vec<QcStatistics>> {
+        let results = self.result_repo.list_by_material(material.id).await?;
+
+        let mut dates: Vec<chrono::NaiveDate> = results
+            .iter()
+            .map(|r| r.result_date)
+            .filter(|date| *date >= from_date)
+            .collect();
+        dates.dedup();
+
+        let mut saved = Vec::new();
+        for date in dates {
+            for row in statistics::statistics_for_date(material, &results, date) {
+                saved.push(self.statistics_repo.upsert(&row).await?);
+            }
+        }
+
+        Ok(saved)
+    }
+
+    pub async fn recalculate_qc_statistics(&self, qc_material_id: Uuid) -> Result<Vec<QcStatistics>> {
+        let material = self.get_qc_material(qc_material_id).await?;
+        let saved = self.update_statistics(&material, chrono::NaiveDate::MIN).await?;
+
+        tracing::info!("QC statistics recalculated: {} ({} rows)", material.material_code, saved.len());
+
+        Ok(saved)
+    }
+
+    pub async fn get_qc_statistics(
+        &self,
+        qc_material_id: Uuid,
+        statistics_type: Option<QcStatisticsType>,
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<QcStatistics>> {
+        self.statistics_repo
+            .list(qc_material_id, statistics_type, from_date, to_date)
+            .await
+    }
+
+    /// Levey-Jennings series for the material's control level, including
+    /// the other lots of that level so lot changes show on the chart
+    pub async fn levey_jennings(
+        &self,
+        qc_material_id: Uuid,
+        from_date: Option<chrono::NaiveDate>,
+        to_date: Option<chrono::NaiveDate>,
+    ) -> Result<LeveyJenningsChart> {
+        let material = self.get_qc_material(qc_material_id).await?;
+        let lots = self.material_repo.list_lots_of_level(&material).await?;
+
+        let lot_ids: Vec<Uuid> = lots.iter().map(|lot| lot.id).collect();
+        let results = self.result_repo.list_for_chart(&lot_ids, from_date, to_date).await?;
+
+        let lot_of = |id: Uuid| lots.iter().find(|lot| lot.id == id);
+
+        let points: Vec<LeveyJenningsPoint> = results
+            .iter()
+            .map(|r| LeveyJenningsPoint {
+                qc_result_id: r.id,
+                result_number: r.result_number.clone(),
+                qc_material_id: r.qc_material_id,
+                lot_number: lot_of(r.qc_material_id).map(|lot| lot.lot_number.clone()).unwrap_or_default(),
+                run_id: r.run_id,
+                result_date: r.result_date,
+                result_time: r.result_time,
+                result_value: r.result_value,
+                mean_value: r.mean_value,
+                sd_value: r.sd_value,
+                z_score: r.z_score,
+                result_status: r.result_status,
+                rule_flags: r
+                    .rules_violated
+                    .as_ref()
+                    .and_then(|v| v.as_array())
+                    .map(|rules| {
+                        rules
+                            .iter()
+                            .filter_map(|rule| rule.get("rule_name")?.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        let mut lot_boundaries: Vec<LeveyJenningsLotBoundary> = Vec::new();
+        for point in &points {
+            if lot_boundaries.last().is_none_or(|b| b.qc_material_id != point.qc_material_id) {
+                let lot = lot_of(point.qc_material_id);
+                lot_boundaries.push(LeveyJenningsLotBoundary {
+                    qc_material_id: point.qc_material_id,
+                    lot_number: point.lot_number.clone(),
+                    starts_at: point.result_date.and_time(point.result_time),
+                    mean_value: lot.and_then(|lot| lot.mean_value),
+                    sd_value: lot.and_then(|lot| lot.sd_value),
+                });
+            }
+        }
+
+        let result_ids: Vec<Uuid> = results.iter().map(|r| r.id).collect();
+        let corrective_actions = self.corrective_action_repo.list_for_results(&result_ids).await?;
+
+        Ok(LeveyJenningsChart {
+            test_id: material.test_id,
+            test_name: material.test_name,
+            level_number: material.level_number,
+            level_name: material.level_name,
+            points,
+            lot_boundaries,
+            corrective_actions,
+        })
+    }
+
+    /// Sets a new lot's target mean and SD from its first runs
+    pub async fn establish_qc_targets(
+        &self,
+        input: EstablishQcTargetsInput,
+        updated_by: Uuid,
+    ) -> Result<QcMaterial> {
+        let material = self.get_qc_material(input.qc_material_id).await?;
+
+        let runs = match input.runs {
+            Some(runs) if runs > 0 => runs as usize,
+            Some(_) => return Err(Error::Validation("Runs must be positive".to_string())),
+            None => statistics::DEFAULT_TARGET_RUNS,
+        };
+
+        let results = self.result_repo.list_by_material(material.id).await?;
+        let summary = statistics::establish_targets(&results, runs).map_err(Error::InvalidState)?;
+        let sd = summary.sd.ok_or_else(|| Error::InvalidState("SD could not be established".to_string()))?;
+
+        let material = self
+            .material_repo
+            .set_control_limits(material.id, summary.mean, sd, summary.cv, updated_by)
+            .await?;
+
+        tracing::info!(
+            "QC targets established for {} lot {}: mean {} SD {} from {} runs",
+            material.material_code,
+            material.lot_number,
+            summary.mean,
+            sd,
+            summary.n
+        );
+
+        Ok(material)
+    }
+
+    // ========================================================================
+    // QC Release Gate
+    // ========================================================================
+
+    /// QC state for patient results of a test measured on an instrument at
+    /// `at`. A rejected run holds them until a corrective action on one of
+    /// its violations is completed.
+    pub async fn qc_gate(
+        &self,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        lot_number: Option<String>,
+        at: chrono::NaiveDateTime,
+    ) -> Result<QcGate> {
+        let run = self
+            .result_repo
+            .find_last_run_at(test_id, equipment_id, lot_number.as_deref(), at)
+            .await?;
+
+        let Some(last) = run.last() else {
+            return Ok(QcGate {
+                status: QcGateStatus::NoQc,
+                hold: false,
+                qc_result_id: None,
+                qc_result_number: None,
+                performed_at: None,
+                rules_violated: vec![],
+            });
+        };
+
+        // A run is rejected when any of its control levels is
+        let rejected: Vec<&QcResult> = run
+            .iter()
+            .filter(|r| r.result_status == QcResultStatus::OutOfControl)
+            .collect();
+        let governing = rejected.first().copied().unwrap_or(last);
+
+        let status = if rejected.is_empty() {
+            QcGateStatus::InControl
+        } else {
+            let ids: Vec<Uuid> = rejected.iter().map(|r| r.id).collect();
+            if self.corrective_action_repo.has_completed_for_results(&ids).await? {
+                QcGateStatus::Corrected
+            } else {
+                QcGateStatus::OutOfControl
+            }
+        };
+
+        let rules_violated = rejected
+            .iter()
+            .filter_map(|r| r.rules_violated.as_ref()?.as_array().cloned())
+            .flatten()
+            .filter_map(|v| v.get("rule_name")?.as_str().map(str::to_string))
+            .collect();
+
+        Ok(QcGate {
+            status,
+            hold: status == QcGateStatus::OutOfControl,
+            qc_result_id: Some(governing.id),
+            qc_result_number: Some(governing.result_number.clone()),
+            performed_at: Some(governing.result_date.and_time(governing.result_time)),
+            rules_violated,
+        })
+    }
+
+    /// Window of patient results to look back on for a rejected QC result
+    pub async fn qc_lookback_window(&self, qc_result_id: Uuid) -> Result<QcLookbackWindow> {
+        let result = self.get_qc_result(qc_result_id).await?;
+
+        if result.result_status != QcResultStatus::OutOfControl {
+            return Err(Error::InvalidState(
+                "Look-back only applies to out of control QC results".to_string(),
+            ));
+        }
+
+        let to = result.result_date.and_time(result.result_time);
+        let from = self
+            .result_repo
+            .find_last_accepted_run_before(result.test_id, result.equipment_id, to)
+            .await?;
+
+        Ok(QcLookbackWindow {
+            qc_result_id,
+            test_id: result.test_id,
+            equipment_id: result.equipment_id,
+            from,
+            to,
+        })
+    }
+
     // ========================================================================
     // Westgard Rules Evaluation
     // ========================================================================
@@ -315,53 +582,59 @@ impl QcService {
             return Ok(result);
         }
 
-        // Get recent results for trend analysis
-        let recent_results = self
+        // Every control level of the test on this instrument, so rules can
+        // look across levels within the run as well as across runs
+        let history = self
             .result_repo
-            .get_recent_results(result.qc_material_id, 20)
+            .get_recent_results_for_test(result.test_id, result.equipment_id, WESTGARD_HISTORY_LIMIT)
             .await?;
+        let observations = westgard::observations(&history);
 
         let mut violations = vec![];
-        let mut is_blocking_violation = false;
+        let mut is_rejected = false;
 
         for rule in &rules {
             if !rule.is_active() {
                 continue;
             }
 
-            let violated = self.check_rule(&result, &recent_results, rule).await?;
-
-            if violated {
-                violations.push(serde_json::json!({
-                    "rule_id": rule.id,
-                    "rule_name": rule.rule_name,
-                    "rule_type": format!("{:?}", rule.rule_type),
-                    "severity": format!("{:?}", rule.violation_severity),
-                }));
-
-                // Create violation record
-                let _ = self
-                    .violation_repo
-                    .create(
-                        result.id,
-                        result.qc_material_id,
-                        result.organization_id,
-                        rule,
-                        result.result_date,
-                        result.result_time,
-                    )
-                    .await?;
-
-                if rule.is_blocking() {
-                    is_blocking_violation = true;
+            let pattern = match westgard::pattern_for(rule.rule_type, rule.rule_parameters.as_ref()) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    tracing::warn!("Skipping QC rule {}: {}", rule.rule_name, e);
+                    continue;
                 }
+            };
+
+            let Some(violation) = westgard::evaluate(pattern, &observations, result.id) else {
+                continue;
+            };
+
+            violations.push(serde_json::json!({
+                "rule_id": rule.id,
+                "rule_name": rule.rule_name,
+                "rule_type": format!("{:?}", rule.rule_type),
+                "severity": format!("{:?}", rule.violation_severity),
+                "scope": violation.scope,
+                "result_ids": violation.result_ids,
+            }));
+
+            // Create violation record
+            let _ = self
+                .violation_repo
+                .create(&result, rule, &violation.result_ids, violation.scope.as_str())
+                .await?;
+
+            // 1-2s only triggers inspection by the other rules
+            if rule.is_blocking() && !westgard::is_warning_rule(rule.rule_type) {
+                is_rejected = true;
             }
         }
 
         // Update result status based on violations
         let status = if violations.is_empty() {
             QcResultStatus::InControl
-        } else if is_blocking_violation {
+        } else if is_rejected {
             QcResultStatus::OutOfControl
         } else {
             QcResultStatus::Warning
@@ -381,125 +654,30 @@ impl QcService {
         Ok(result)
     }
 
-    async fn check_rule(
+    /// Recommends Westgard rules for a material from its sigma metric.
+    /// Imprecision is the material's CV, or SD / mean when no CV is set.
+    pub async fn westgard_rule_selection(
         &self,
-        current_result: &QcResult,
-        recent_results: &[QcResult],
-        rule: &QcRule,
-    ) -> Result<bool> {
-        match rule.rule_type {
-            QcRuleType::Westgard12s => self.check_1_2s(current_result),
-            QcRuleType::Westgard13s => self.check_1_3s(current_result),
-            QcRuleType::Westgard22s => self.check_2_2s(current_result, recent_results),
-            QcRuleType::WestgardR4s => self.check_r_4s(current_result, recent_results),
-            QcRuleType::Westgard41s => self.check_4_1s(current_result, recent_results),
-            QcRuleType::Westgard10x => self.check_10_x(current_result, recent_results),
-            QcRuleType::Custom => Ok(false), // Custom rules not implemented yet
-        }
-    }
-
-    fn check_1_2s(&self, result: &QcResult) -> Result<bool> {
-        // 1-2s: One control observation exceeds ±2SD
-        if let Some(z) = result.z_score {
-            Ok(z.abs() > Decimal::from(2))
-        } else {
-            Ok(false)
-        }
-    }
-
-    fn check_1_3s(&self, result: &QcResult) -> Result<bool> {
-        // 1-3s: One control observation exceeds ±3SD
-        if let Some(z) = result.z_score {
-            Ok(z.abs() > Decimal::from(3))
-        } else {
-            Ok(false)
-        }
-    }
-
-    fn check_2_2s(&self, current: &QcResult, recent: &[QcResult]) -> Result<bool> {
-        // 2-2s: Two consecutive controls exceed ±2SD on the same side of the mean
-        if let Some(current_z) = current.z_score {
-            if current_z.abs() <= Decimal::from(2) {
-                return Ok(false);
-            }
-
-            // Check the most recent result
-            if let Some(previous) = recent.first() {
-                if let Some(prev_z) = previous.z_score {
-                    if prev_z.abs() > Decimal::from(2) {
-                        // Both exceed 2SD, check if on same side
-                        return Ok((current_z > Decimal::ZERO && prev_z > Decimal::ZERO)
-                            || (current_z < Decimal::ZERO && prev_z < Decimal::ZERO));
-                    }
-                }
-            }
-        }
-        Ok(false)
-    }
-
-    fn check_r_4s(&self, current: &QcResult, recent: &[QcResult]) -> Result<bool> {
-        // R-4s: Range between two consecutive controls exceeds 4SD
-        if let Some(current_z) = current.z_score {
-            if let Some(previous) = recent.first() {
-                if let Some(prev_z) = previous.z_score {
-                    let range = (current_z - prev_z).abs();
-                    return Ok(range > Decimal::from(4));
-                }
-            }
+        qc_material_id: Uuid,
+        allowable_error_percent: f64,
+        bias_percent: f64,
+        control_levels: usize,
+    ) -> Result<WestgardRuleSelection> {
+        let material = self.get_qc_material(qc_material_id).await?;
+
+        let cv_percent = match (material.cv_value, material.mean_value, material.sd_value) {
+            (Some(cv), _, _) => cv.to_f64(),
+            (None, Some(mean), Some(sd)) if !mean.is_zero() => (sd / mean * Decimal::from(100)).to_f64(),
+            _ => None,
         }
-        Ok(false)
-    }
+        .ok_or_else(|| {
+            Error::InvalidState("QC material has no established imprecision (CV or mean and SD)".to_string())
+        })?;
 
-    fn check_4_1s(&self, current: &QcResult, recent: &[QcResult]) -> Result<bool> {
-        // 4-1s: Four consecutive controls exceed ±1SD on the same side
-        if let Some(current_z) = current.z_score {
-            if current_z.abs() <= Decimal::from(1) {
-                return Ok(false);
-            }
+        let sigma = westgard::sigma_metric(allowable_error_percent, bias_percent, cv_percent)
+            .ok_or_else(|| Error::InvalidState("QC material CV must be greater than zero".to_string()))?;
 
-            let is_positive = current_z > Decimal::ZERO;
-            let mut consecutive_count = 1;
-
-            for result in recent.iter().take(3) {
-                if let Some(z) = result.z_score {
-                    if z.abs() > Decimal::from(1) {
-                        let same_side = (z > Decimal::ZERO) == is_positive;
-                        if same_side {
-                            consecutive_count += 1;
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                }
-            }
-
-            return Ok(consecutive_count >= 4);
-        }
-        Ok(false)
-    }
-
-    fn check_10_x(&self, current: &QcResult, recent: &[QcResult]) -> Result<bool> {
-        // 10-x: Ten consecutive controls on same side of mean
-        if let Some(current_z) = current.z_score {
-            let is_positive = current_z > Decimal::ZERO;
-            let mut consecutive_count = 1;
-
-            for result in recent.iter().take(9) {
-                if let Some(z) = result.z_score {
-                    let same_side = (z > Decimal::ZERO) == is_positive;
-                    if same_side {
-                        consecutive_count += 1;
-                    } else {
-                        break;
-                    }
-                }
-            }
-
-            return Ok(consecutive_count >= 10);
-        }
-        Ok(false)
+        Ok(westgard::select_rules(sigma, control_levels))
     }
 
     // ========================================================================
@@ -0,0 +1,164 @@
+//! QC statistics for a control material (one lot of one level).
+//!
+//! Each day with results gets three summaries: the day itself, the rolling
+//! window of the most recent results and everything since the lot started.
+//! Out of control results are counted but never enter mean, SD or CV.
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::domain::*;
+
+/// Results summarized by rolling statistics
+pub const ROLLING_WINDOW: usize = 20;
+
+/// Runs a new lot is measured before its targets are established (CLSI C24)
+pub const DEFAULT_TARGET_RUNS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: Decimal,
+    /// Sample SD; needs at least two values
+    pub sd: Option<Decimal>,
+    /// SD as a percentage of the mean
+    pub cv: Option<Decimal>,
+    pub min: Decimal,
+    pub max: Decimal,
+}
+
+pub fn summarize(values: &[Decimal]) -> Option<Summary> {
+    let n = values.len();
+    let min = values.iter().min().copied()?;
+    let max = values.iter().max().copied()?;
+    let mean = values.iter().sum::<Decimal>() / Decimal::from(n);
+
+    let sd = (n > 1)
+        .then(|| {
+            let squares: Decimal = values.iter().map(|v| (v - mean) * (v - mean)).sum();
+            let variance = squares / Decimal::from(n - 1);
+            Decimal::from_f64(variance.to_f64()?.sqrt())
+        })
+        .flatten();
+
+    let cv = sd.filter(|_| !mean.is_zero()).map(|sd| sd / mean.abs() * Decimal::from(100));
+
+    Some(Summary {
+        n,
+        mean: mean.round_dp(4),
+        sd: sd.map(|sd| sd.round_dp(4)),
+        cv: cv.map(|cv| cv.round_dp(2)),
+        min,
+        max,
+    })
+}
+
+fn is_accepted(result: &QcResult) -> bool {
+    result.result_status != QcResultStatus::OutOfControl
+}
+
+/// Daily, rolling and cumulative statistics of a material on `date`.
+/// `results` is the lot's history in measurement order.
+pub fn statistics_for_date(material: &QcMaterial, results: &[QcResult], date: NaiveDate) -> Vec<QcStatistics> {
+    let to_date: Vec<&QcResult> = results.iter().filter(|r| r.result_date <= date).collect();
+    let daily: Vec<&QcResult> = to_date.iter().copied().filter(|r| r.result_date == date).collect();
+    let rolling = &to_date[to_date.len().saturating_sub(ROLLING_WINDOW)..];
+
+    [
+        (QcStatisticsType::Daily, daily.as_slice(), None),
+        (QcStatisticsType::Rolling, rolling, Some(ROLLING_WINDOW as i32)),
+        (QcStatisticsType::Cumulative, to_date.as_slice(), None),
+    ]
+    .into_iter()
+    .filter(|(_, scope, _)| !scope.is_empty())
+    .map(|(statistics_type, scope, window_size)| {
+        let count = |status| scope.iter().filter(|r| r.result_status == status).count() as i32;
+        let values: Vec<Decimal> = scope.iter().filter(|r| is_accepted(r)).map(|r| r.result_value).collect();
+        let summary = summarize(&values);
+
+        QcStatistics {
+            id: Uuid::new_v4(),
+            qc_material_id: material.id,
+            statistics_type,
+            lot_number: Some(material.lot_number.clone()),
+            statistics_date: date,
+            window_size,
+            n_count: values.len() as i32,
+            mean_value: summary.as_ref().map(|s| s.mean),
+            sd_value: summary.as_ref().and_then(|s| s.sd),
+            cv_value: summary.as_ref().and_then(|s| s.cv),
+            min_value: summary.as_ref().map(|s| s.min),
+            max_value: summary.as_ref().map(|s| s.max),
+            range_value: summary.as_ref().map(|s| s.max - s.min),
+            in_control_count: Some(count(QcResultStatus::InControl)),
+            out_of_control_count: Some(count(QcResultStatus::OutOfControl)),
+            warning_count: Some(count(QcResultStatus::Warning)),
+            created_at: None,
+            updated_at: None,
+        }
+    })
+    .collect()
+}
+
+/// Target mean and SD from the first `runs` accepted results of a new lot
+pub fn establish_targets(results: &[QcResult], runs: usize) -> Result<Summary, String> {
+    if runs < 2 {
+        return Err("At least 2 runs are needed to establish an SD".to_string());
+    }
+
+    let values: Vec<Decimal> = results
+        .iter()
+        .filter(|r| is_accepted(r))
+        .take(runs)
+        .map(|r| r.result_value)
+        .collect();
+
+    if values.len() < runs {
+        return Err(format!(
+            "Lot has {} accepted results; {} are needed to establish targets",
+            values.len(),
+            runs
+        ));
+    }
+
+    let summary = summarize(&values).ok_or_else(|| "No results to establish targets from".to_string())?;
+    match summary.sd {
+        Some(sd) if !sd.is_zero() => Ok(summary),
+        _ => Err("Results have no spread; SD would be zero".to_string()),
+    }
+}
+
+/// ±1, ±2 and ±3 SD limits around a mean
+pub fn control_limits(mean: Decimal, sd: Decimal) -> [(Decimal, Decimal); 3] {
+    [1, 2, 3].map(|k| {
+        let offset = sd * Decimal::from(k);
+        (mean - offset, mean + offset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[Decimal::from(98), Decimal::from(100), Decimal::from(102), Decimal::from(100)]).unwrap();
+        assert_eq!(summary.n, 4);
+        assert_eq!(summary.mean, Decimal::from(100));
+        assert_eq!(summary.sd, Some(Decimal::new(1633, 3)));
+        assert_eq!(summary.cv, Some(Decimal::new(163, 2)));
+        assert_eq!((summary.min, summary.max), (Decimal::from(98), Decimal::from(102)));
+
+        assert_eq!(summarize(&[Decimal::from(5)]).unwrap().sd, None);
+        assert!(summarize(&[]).is_none());
+    }
+
+    #[test]
+    fn test_control_limits() {
+        let limits = control_limits(Decimal::from(100), Decimal::from(5));
+        assert_eq!(limits[0], (Decimal::from(95), Decimal::from(105)));
+        assert_eq!(limits[2], (Decimal::from(85), Decimal::from(115)));
+    }
+}
@@ -0,0 +1,339 @@
+//! Westgard multirule evaluation.
+//!
+//! Each rule is a pattern over control z-scores that ends with the result
+//! being evaluated. A pattern is checked within the result's run (across
+//! control levels), then across runs of the same control material, then
+//! across runs and levels together. The first scope in which it fires is
+//! reported together with the QC results that make it up.
+//!
+//! 1-2s is a warning trigger only: it never rejects a run on its own.
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// `count` of the last `window` observations beyond `limit` SD on the
+    /// same side of the mean. A limit of zero counts sides of the mean.
+    Exceed { count: usize, window: usize, limit: f64 },
+    /// One observation above +limit and another below -limit in one run
+    Range { limit: f64 },
+}
+
+/// The pattern a rule checks. CUSTOM rules take theirs from
+/// `rule_parameters`: `{"count": 2, "window": 3, "limit_sd": 2.0}`.
+pub fn pattern_for(rule_type: QcRuleType, parameters: Option<&serde_json::Value>) -> Result<Pattern, String> {
+    let exceed = |count, window, limit| Pattern::Exceed { count, window, limit };
+
+    Ok(match rule_type {
+        QcRuleType::Westgard12s => exceed(1, 1, 2.0),
+        QcRuleType::Westgard13s => exceed(1, 1, 3.0),
+        QcRuleType::Westgard22s => exceed(2, 2, 2.0),
+        QcRuleType::WestgardR4s => Pattern::Range { limit: 2.0 },
+        QcRuleType::Westgard41s => exceed(4, 4, 1.0),
+        QcRuleType::Westgard2of32s => exceed(2, 3, 2.0),
+        QcRuleType::Westgard31s => exceed(3, 3, 1.0),
+        QcRuleType::Westgard6x => exceed(6, 6, 0.0),
+        QcRuleType::Westgard8x => exceed(8, 8, 0.0),
+        QcRuleType::Westgard10x => exceed(10, 10, 0.0),
+        QcRuleType::Westgard12x => exceed(12, 12, 0.0),
+        QcRuleType::Custom => custom_pattern(parameters)?,
+    })
+}
+
+fn custom_pattern(parameters: Option<&serde_json::Value>) -> Result<Pattern, String> {
+    let parameters = parameters.ok_or_else(|| "Custom rules need rule_parameters".to_string())?;
+
+    let count = match parameters.get("count").and_then(|v| v.as_u64()) {
+        Some(count) if count > 0 => count as usize,
+        _ => return Err("'count' must be a positive whole number".to_string()),
+    };
+    let window = match parameters.get("window") {
+        None | Some(serde_json::Value::Null) => count,
+        Some(value) => match value.as_u64() {
+            Some(window) if window as usize >= count => window as usize,
+            _ => return Err("'window' must be a whole number no smaller than 'count'".to_string()),
+        },
+    };
+    let limit = match parameters.get("limit_sd").and_then(|v| v.as_f64()) {
+        Some(limit) if limit >= 0.0 => limit,
+        _ => return Err("'limit_sd' must be zero or a positive number".to_string()),
+    };
+
+    Ok(Pattern::Exceed { count, window, limit })
+}
+
+pub fn is_warning_rule(rule_type: QcRuleType) -> bool {
+    rule_type == QcRuleType::Westgard12s
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub result_id: Uuid,
+    pub qc_material_id: Uuid,
+    pub run_id: Uuid,
+    pub z: f64,
+}
+
+/// Control observations in measurement order. Results without a z-score
+/// (no established mean and SD) are left out.
+pub fn observations(history: &[QcResult]) -> Vec<Observation> {
+    history
+        .iter()
+        .filter_map(|result| {
+            Some(Observation {
+                result_id: result.id,
+                qc_material_id: result.qc_material_id,
+                run_id: result.run_id,
+                z: result.z_score?.to_f64()?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EvaluationScope {
+    /// Control levels measured in the same run
+    WithinRun,
+    /// Consecutive runs of the same control material
+    AcrossRuns,
+    /// Consecutive runs across every control level of the test
+    AcrossLevels,
+}
+
+impl EvaluationScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvaluationScope::WithinRun => "WITHIN_RUN",
+            EvaluationScope::AcrossRuns => "ACROSS_RUNS",
+            EvaluationScope::AcrossLevels => "ACROSS_LEVELS",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleViolation {
+    pub scope: EvaluationScope,
+    /// Oldest first; the evaluated result is last
+    pub result_ids: Vec<Uuid>,
+}
+
+/// Checks a rule for the given result against the observations up to it
+pub fn evaluate(
+    pattern: Pattern,
+    observations: &[Observation],
+    result_id: Uuid,
+) -> Option<RuleViolation> {
+    let index = observations.iter().position(|o| o.result_id == result_id)?;
+    let history = &observations[..=index];
+    let current = &history[index];
+
+    let within_run: Vec<&Observation> = history.iter().filter(|o| o.run_id == current.run_id).collect();
+    let across_runs: Vec<&Observation> = history.iter().filter(|o| o.qc_material_id == current.qc_material_id).collect();
+    let across_levels: Vec<&Observation> = history.iter().collect();
+
+    let scopes = [
+        (EvaluationScope::WithinRun, within_run),
+        (EvaluationScope::AcrossRuns, across_runs),
+        (EvaluationScope::AcrossLevels, across_levels),
+    ];
+
+    scopes.into_iter().find_map(|(scope, sequence)| {
+        let result_ids = match pattern {
+            Pattern::Exceed { count, window, limit } => match_exceed(&sequence, count, window, limit),
+            // R-4s only applies within a run
+            Pattern::Range { limit } if scope == EvaluationScope::WithinRun => match_range(&sequence, limit),
+            Pattern::Range { .. } => None,
+        }?;
+
+        Some(RuleViolation { scope, result_ids })
+    })
+}
+
+fn match_exceed(sequence: &[&Observation], count: usize, window: usize, limit: f64) -> Option<Vec<Uuid>> {
+    let current = sequence.last()?;
+    if current.z.abs() <= limit {
+        return None;
+    }
+
+    let side = current.z.signum();
+    let start = sequence.len().saturating_sub(window);
+    let hits: Vec<Uuid> = sequence[start..]
+        .iter()
+        .filter(|o| o.z * side > limit)
+        .map(|o| o.result_id)
+        .collect();
+
+    (hits.len() >= count).then_some(hits)
+}
+
+fn match_range(sequence: &[&Observation], limit: f64) -> Option<Vec<Uuid>> {
+    let (current, earlier) = sequence.split_last()?;
+    if current.z.abs() <= limit {
+        return None;
+    }
+
+    let side = current.z.signum();
+    let opposite = earlier.iter().rev().find(|o| o.z * -side > limit)?;
+    Some(vec![opposite.result_id, current.result_id])
+}
+
+// ============================================================================
+// Sigma-based Rule Selection
+// ============================================================================
+
+/// Sigma metric from allowable total error, bias and imprecision, all in percent
+pub fn sigma_metric(allowable_error_percent: f64, bias_percent: f64, cv_percent: f64) -> Option<f64> {
+    (cv_percent > 0.0).then(|| (allowable_error_percent - bias_percent.abs()) / cv_percent)
+}
+
+/// Westgard sigma rules: the fewest rules and controls that still detect
+/// medically important errors at the method's sigma. Three-level tests use
+/// the 2of3-2s/3-1s/6x family; others the 2-2s/4-1s/8x family.
+pub fn select_rules(sigma: f64, levels: usize) -> WestgardRuleSelection {
+    use QcRuleType::*;
+
+    let three_levels = levels >= 3;
+    let per_run = if three_levels { 3 } else { 2 };
+
+    let (rules, controls_per_run, runs) = if sigma >= 6.0 {
+        (vec![Westgard13s], per_run, 1)
+    } else if sigma >= 5.0 {
+        if three_levels {
+            (vec![Westgard13s, Westgard2of32s, WestgardR4s], 3, 1)
+        } else {
+            (vec![Westgard13s, Westgard22s, WestgardR4s], 2, 1)
+        }
+    } else if sigma >= 4.0 {
+        if three_levels {
+            (vec![Westgard13s, Westgard2of32s, WestgardR4s, Westgard31s], 3, 1)
+        } else {
+            (vec![Westgard13s, Westgard22s, WestgardR4s, Westgard41s], 4, 1)
+        }
+    } else if three_levels {
+        (vec![Westgard13s, Westgard2of32s, WestgardR4s, Westgard31s, Westgard6x], 6, 2)
+    } else {
+        (vec![Westgard13s, Westgard22s, WestgardR4s, Westgard41s, Westgard8x], 4, 2)
+    };
+
+    WestgardRuleSelection {
+        sigma,
+        rules,
+        controls_per_run,
+        runs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Run {
+        id: Uuid,
+    }
+
+    fn run() -> Run {
+        Run { id: Uuid::new_v4() }
+    }
+
+    fn obs(run: &Run, material: Uuid, z: f64) -> Observation {
+        Observation {
+            result_id: Uuid::new_v4(),
+            qc_material_id: material,
+            run_id: run.id,
+            z,
+        }
+    }
+
+    fn check(rule_type: QcRuleType, observations: &[Observation]) -> Option<RuleViolation> {
+        let current = observations.last().unwrap().result_id;
+        evaluate(pattern_for(rule_type, None).unwrap(), observations, current)
+    }
+
+    #[test]
+    fn test_2_2s_within_run_across_levels() {
+        let (level1, level2) = (Uuid::new_v4(), Uuid::new_v4());
+        let r = run();
+        let history = vec![obs(&r, level1, 2.3), obs(&r, level2, 2.6)];
+
+        let violation = check(QcRuleType::Westgard22s, &history).unwrap();
+        assert_eq!(violation.scope, EvaluationScope::WithinRun);
+        assert_eq!(violation.result_ids, vec![history[0].result_id, history[1].result_id]);
+
+        // Opposite sides are R-4s, not 2-2s
+        let history = vec![obs(&r, level1, -2.3), obs(&r, level2, 2.6)];
+        assert!(check(QcRuleType::Westgard22s, &history).is_none());
+        assert_eq!(check(QcRuleType::WestgardR4s, &history).unwrap().result_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_r_4s_does_not_span_runs() {
+        let material = Uuid::new_v4();
+        let history = vec![obs(&run(), material, -2.5), obs(&run(), material, 2.5)];
+        assert!(check(QcRuleType::WestgardR4s, &history).is_none());
+    }
+
+    #[test]
+    fn test_4_1s_across_levels_and_runs() {
+        let (level1, level2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (r1, r2) = (run(), run());
+        let history = vec![
+            obs(&r1, level1, 1.2),
+            obs(&r1, level2, 1.5),
+            obs(&r2, level1, 1.1),
+            obs(&r2, level2, 1.8),
+        ];
+
+        let violation = check(QcRuleType::Westgard41s, &history).unwrap();
+        assert_eq!(violation.scope, EvaluationScope::AcrossLevels);
+        assert_eq!(violation.result_ids.len(), 4);
+    }
+
+    #[test]
+    fn test_2of3_2s_and_mean_rules() {
+        let material = Uuid::new_v4();
+        let history = vec![
+            obs(&run(), material, 2.2),
+            obs(&run(), material, 0.4),
+            obs(&run(), material, 2.1),
+        ];
+        let violation = check(QcRuleType::Westgard2of32s, &history).unwrap();
+        assert_eq!(violation.scope, EvaluationScope::AcrossRuns);
+        assert_eq!(violation.result_ids, vec![history[0].result_id, history[2].result_id]);
+
+        let below: Vec<Observation> = (0..8).map(|_| obs(&run(), material, -0.3)).collect();
+        assert!(check(QcRuleType::Westgard8x, &below).is_some());
+        assert!(check(QcRuleType::Westgard10x, &below).is_none());
+    }
+
+    #[test]
+    fn test_custom_rule_parameters() {
+        let parameters = serde_json::json!({"count": 3, "window": 5, "limit_sd": 1.5});
+        assert_eq!(
+            pattern_for(QcRuleType::Custom, Some(&parameters)),
+            Ok(Pattern::Exceed { count: 3, window: 5, limit: 1.5 })
+        );
+        assert!(pattern_for(QcRuleType::Custom, None).is_err());
+        assert!(pattern_for(QcRuleType::Custom, Some(&serde_json::json!({"count": 3, "window": 2, "limit_sd": 1}))).is_err());
+    }
+
+    #[test]
+    fn test_sigma_rule_selection() {
+        let sigma = sigma_metric(10.0, 1.0, 1.5).unwrap();
+        assert_eq!(select_rules(sigma, 2).rules, vec![QcRuleType::Westgard13s]);
+
+        let poor = select_rules(3.2, 3);
+        assert!(poor.rules.contains(&QcRuleType::Westgard6x));
+        assert_eq!((poor.controls_per_run, poor.runs), (6, 2));
+
+        assert!(sigma_metric(10.0, 1.0, 0.0).is_none());
+    }
+}
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Approved results received from result-service
+-- ============================================================================
+
+-- Source data for patient reports; kept current by RESULT_APPROVED events
+CREATE TABLE IF NOT EXISTS result_snapshot (
+    result_id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    order_id UUID NOT NULL,
+    patient_id UUID NOT NULL,
+    test_code VARCHAR(50) NOT NULL,
+    result_data JSONB NOT NULL,
+    approved_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Set when an approved correction replaces this result
+    superseded_by UUID
+);
+
+CREATE INDEX idx_result_snapshot_order ON result_snapshot(order_id) WHERE superseded_by IS NULL;
+CREATE INDEX idx_result_snapshot_patient ON result_snapshot(patient_id, test_code, approved_at);
@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('digital_signature');
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('generated_report');
+SELECT enable_tenant_isolation('report_delivery');
+SELECT enable_tenant_isolation('report_template');
+SELECT enable_tenant_isolation('result_snapshot');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('report_access_log', 'report_id', 'generated_report');
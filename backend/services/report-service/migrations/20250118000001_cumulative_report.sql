@@ -0,0 +1,2 @@
+-- Cumulative reports: a patient's recent values of each analyte side by side
+ALTER TYPE report_template_type ADD VALUE IF NOT EXISTS 'CUMULATIVE_REPORT';
@@ -0,0 +1,6 @@
+-- PAdES signatures: each signatory fills one signature field of the report PDF
+ALTER TABLE digital_signature ADD COLUMN IF NOT EXISTS signature_field VARCHAR(200);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_digital_signature_field
+    ON digital_signature(report_id, signature_field)
+    WHERE is_deleted = false AND signature_field IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Report delivery through notification-service
+--
+-- Every attempt to deliver a report is a notification; the latest one is
+-- kept so receipts for earlier attempts can be told apart. Deliveries that
+-- are scheduled, or failed with retries left, carry the time of their next
+-- attempt for the delivery worker.
+
+ALTER TABLE report_delivery
+    ADD COLUMN notification_id UUID,
+    ADD COLUMN next_attempt_at TIMESTAMP;
+
+CREATE INDEX idx_report_delivery_next_attempt ON report_delivery(next_attempt_at)
+    WHERE next_attempt_at IS NOT NULL AND is_deleted = false;
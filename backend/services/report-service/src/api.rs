@@ -1,9 +1,21 @@
 use async_graphql::{Context, Object, Result as GqlResult, ID, ErrorExtensions};
+use base64::{Engine as _, engine::general_purpose};
 use crate::domain::*;
 use crate::service::{ReportService, ReportError};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
+use common::tenant::Tenant;
 use uuid::Uuid;
 use std::str::FromStr;
 
+/// Where a request came from, recorded when anonymous readers look up
+/// reports
+#[derive(Debug, Clone, Default)]
+pub struct RequestOrigin {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
 // Convert service errors to GraphQL errors
 impl ErrorExtensions for ReportError {
     fn extend(&self) -> async_graphql::Error {
@@ -20,6 +32,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get report template by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report_template(&self, ctx: &Context<'_>, id: ID) -> GqlResult<ReportTemplate> {
         let service = ctx.data::<ReportService>()?;
         let template_id = Uuid::from_str(&id)?;
@@ -28,6 +41,7 @@ impl QueryRoot {
     }
 
     /// Get report template by code
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report_template_by_code(
         &self,
         ctx: &Context<'_>,
@@ -41,6 +55,7 @@ impl QueryRoot {
     }
 
     /// List report templates with optional filters
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report_templates(
         &self,
         ctx: &Context<'_>,
@@ -71,6 +86,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get generated report by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report(&self, ctx: &Context<'_>, id: ID) -> GqlResult<GeneratedReport> {
         let service = ctx.data::<ReportService>()?;
         let report_id = Uuid::from_str(&id)?;
@@ -79,6 +95,7 @@ impl QueryRoot {
     }
 
     /// Get generated report by report number
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report_by_number(
         &self,
         ctx: &Context<'_>,
@@ -90,6 +107,7 @@ impl QueryRoot {
     }
 
     /// List generated reports with optional filters
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn reports(
         &self,
         ctx: &Context<'_>,
@@ -129,6 +147,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get digital signatures for a report
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn report_signatures(
         &self,
         ctx: &Context<'_>,
@@ -140,11 +159,40 @@ impl QueryRoot {
         Ok(signatures)
     }
 
+    /// Check the PAdES signatures of a report's stored PDF
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
+    async fn verify_report_signatures(
+        &self,
+        ctx: &Context<'_>,
+        report_id: ID,
+    ) -> GqlResult<SignatureVerification> {
+        let service = ctx.data::<ReportService>()?;
+        let report_uuid = Uuid::from_str(&report_id)?;
+        let verification = service.verify_report_signatures(report_uuid).await?;
+        Ok(verification)
+    }
+
+    /// Check the PAdES signatures of a report PDF (base64), such as a copy
+    /// handed to a patient. Only the file itself is examined.
+    async fn verify_signed_pdf(
+        &self,
+        ctx: &Context<'_>,
+        pdf: String,
+    ) -> GqlResult<SignatureVerification> {
+        let service = ctx.data::<ReportService>()?;
+        let bytes = general_purpose::STANDARD
+            .decode(pdf.trim())
+            .map_err(|e| ReportError::ValidationError(format!("PDF is not valid base64: {}", e)))?;
+        let verification = service.verify_pdf_signatures(&bytes)?;
+        Ok(verification)
+    }
+
     // ============================================================================
     // Report Delivery Queries
     // ============================================================================
 
     /// Get delivery by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn delivery(&self, ctx: &Context<'_>, id: ID) -> GqlResult<ReportDelivery> {
         let service = ctx.data::<ReportService>()?;
         let delivery_id = Uuid::from_str(&id)?;
@@ -153,6 +201,7 @@ impl QueryRoot {
     }
 
     /// List deliveries with optional filters
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn deliveries(
         &self,
         ctx: &Context<'_>,
@@ -183,6 +232,7 @@ impl QueryRoot {
     // ============================================================================
 
     /// Get access logs for a report
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_AUDIT)")]
     async fn report_access_logs(
         &self,
         ctx: &Context<'_>,
@@ -204,14 +254,14 @@ impl MutationRoot {
     // ============================================================================
 
     /// Create a new report template
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_TEMPLATE_MANAGE)")]
     async fn create_report_template(
         &self,
         ctx: &Context<'_>,
         input: CreateReportTemplateInput,
-        created_by: ID,
     ) -> GqlResult<ReportTemplate> {
         let service = ctx.data::<ReportService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let template = service.create_template(input, creator_id).await?;
         Ok(template)
     }
@@ -221,29 +271,42 @@ impl MutationRoot {
     // ============================================================================
 
     /// Generate a new report
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_GENERATE)")]
     async fn generate_report(
         &self,
         ctx: &Context<'_>,
         input: GenerateReportInput,
-        created_by: ID,
     ) -> GqlResult<GeneratedReport> {
         let service = ctx.data::<ReportService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let report = service.generate_report(input, creator_id).await?;
         Ok(report)
     }
 
+    /// Generate a cumulative report of a patient's recent results
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_GENERATE)")]
+    async fn generate_cumulative_report(
+        &self,
+        ctx: &Context<'_>,
+        input: GenerateCumulativeReportInput,
+    ) -> GqlResult<GeneratedReport> {
+        let service = ctx.data::<ReportService>()?;
+        let creator_id = ctx.user_id()?;
+        let report = service.generate_cumulative_report(input, creator_id).await?;
+        Ok(report)
+    }
+
     /// Download a report (logs access)
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_DOWNLOAD)")]
     async fn download_report(
         &self,
         ctx: &Context<'_>,
         report_id: ID,
-        accessed_by: Option<ID>,
         ip_address: Option<String>,
     ) -> GqlResult<GeneratedReport> {
         let service = ctx.data::<ReportService>()?;
         let report_uuid = Uuid::from_str(&report_id)?;
-        let accessor_id = accessed_by.as_ref().and_then(|id| Uuid::from_str(id).ok());
+        let accessor_id = ctx.user_id().ok();
         let report = service.download_report(report_uuid, accessor_id, ip_address).await?;
         Ok(report)
     }
@@ -256,22 +319,51 @@ impl MutationRoot {
         access_code: String,
     ) -> GqlResult<GeneratedReport> {
         let service = ctx.data::<ReportService>()?;
-        let report = service.verify_access_code(&report_number, &access_code).await?;
+        // Access codes are checked for anonymous readers, so look across organizations
+        let report = Tenant::System
+            .scope(service.verify_access_code(&report_number, &access_code))
+            .await?;
         Ok(report)
     }
 
+    /// Verify a report from the link in its QR code, for the public
+    /// verification portal
+    async fn verify_report(
+        &self,
+        ctx: &Context<'_>,
+        report_number: String,
+        access_code: String,
+        signature: String,
+    ) -> GqlResult<ReportVerification> {
+        let service = ctx.data::<ReportService>()?;
+        let origin = ctx.data_opt::<RequestOrigin>().cloned().unwrap_or_default();
+        // Looked up by anonymous readers, so across organizations
+        let verification = Tenant::System
+            .scope(service.verify_report_link(
+                &report_number,
+                &access_code,
+                &signature,
+                origin.ip_address,
+                origin.user_agent,
+            ))
+            .await?;
+        Ok(verification)
+    }
+
     // ============================================================================
     // Digital Signature Mutations
     // ============================================================================
 
     /// Sign a report
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_SIGN)")]
     async fn sign_report(
         &self,
         ctx: &Context<'_>,
         input: SignReportInput,
     ) -> GqlResult<DigitalSignature> {
         let service = ctx.data::<ReportService>()?;
-        let signature = service.sign_report(input).await?;
+        let signatory_id = ctx.user_id()?;
+        let signature = service.sign_report(input, signatory_id).await?;
         Ok(signature)
     }
 
@@ -280,19 +372,20 @@ impl MutationRoot {
     // ============================================================================
 
     /// Deliver a report via specified channel
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_DELIVER)")]
     async fn deliver_report(
         &self,
         ctx: &Context<'_>,
         input: DeliverReportInput,
-        created_by: ID,
     ) -> GqlResult<ReportDelivery> {
         let service = ctx.data::<ReportService>()?;
-        let creator_id = Uuid::from_str(&created_by)?;
+        let creator_id = ctx.user_id()?;
         let delivery = service.deliver_report(input, creator_id).await?;
         Ok(delivery)
     }
 
     /// Retry a failed delivery
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_DELIVER)")]
     async fn retry_delivery(
         &self,
         ctx: &Context<'_>,
@@ -309,6 +402,7 @@ impl MutationRoot {
     // ============================================================================
 
     /// Log report access
+    #[graphql(guard = "PermissionGuard::new(permissions::REPORT_READ)")]
     async fn log_report_access(
         &self,
         ctx: &Context<'_>,
@@ -6,8 +6,24 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub kafka_brokers: String,
+    pub report_storage_dir: String,
+    pub result_service_url: String,
+    pub patient_service_url: String,
+    pub user_service_url: String,
+    pub signing_keystore_dir: String,
+    pub signing_trust_anchors: Option<String>,
+    pub verification_portal_url: String,
+    pub verification_secret: String,
+    pub notification_service_url: String,
+    pub report_download_url: String,
+    pub delivery_link_ttl_hours: i64,
+    pub delivery_retry_base_seconds: u64,
+    pub delivery_retry_max_seconds: u64,
+    pub delivery_worker_interval_seconds: u64,
 }
 
 impl Config {
@@ -16,8 +32,23 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8090)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("kafka_brokers", "localhost:9092")?
+            .set_default("report_storage_dir", "./data/reports")?
+            .set_default("result_service_url", "http://localhost:8084")?
+            .set_default("patient_service_url", "http://localhost:8081")?
+            .set_default("user_service_url", "http://localhost:8080")?
+            .set_default("signing_keystore_dir", "./data/keystore")?
+            .set_default("verification_portal_url", "http://localhost:3000/verify")?
+            .set_default("verification_secret", "development-verification-secret-change-in-production")?
+            .set_default("notification_service_url", "http://localhost:8092")?
+            .set_default("report_download_url", "http://localhost:8090/reports/download")?
+            .set_default("delivery_link_ttl_hours", 168)?
+            .set_default("delivery_retry_base_seconds", 60)?
+            .set_default("delivery_retry_max_seconds", 3600)?
+            .set_default("delivery_worker_interval_seconds", 30)?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -29,8 +60,24 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8090,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            kafka_brokers: "localhost:9092".to_string(),
+            report_storage_dir: "./data/reports".to_string(),
+            result_service_url: "http://localhost:8084".to_string(),
+            patient_service_url: "http://localhost:8081".to_string(),
+            user_service_url: "http://localhost:8080".to_string(),
+            signing_keystore_dir: "./data/keystore".to_string(),
+            signing_trust_anchors: None,
+            verification_portal_url: "http://localhost:3000/verify".to_string(),
+            verification_secret: "development-verification-secret-change-in-production".to_string(),
+            notification_service_url: "http://localhost:8092".to_string(),
+            report_download_url: "http://localhost:8090/reports/download".to_string(),
+            delivery_link_ttl_hours: 168,
+            delivery_retry_base_seconds: 60,
+            delivery_retry_max_seconds: 3600,
+            delivery_worker_interval_seconds: 30,
         }
     }
 }
@@ -0,0 +1,191 @@
+//! Cumulative patient reports.
+//!
+//! Recent values of each analyte side by side, one column per collection
+//! date, so a clinician reads a patient's trend along a row. The results come
+//! from result-service and are rendered by the `cumulative_table` section.
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+use crate::result_client::PatientResult;
+
+/// Date columns when the request does not say
+pub const DEFAULT_COLUMNS: usize = 6;
+
+/// Most date columns that fit across a page
+pub const MAX_COLUMNS: usize = 12;
+
+/// Results released to clinicians
+const REPORTABLE_STATUSES: &[&str] = &["FINAL", "CORRECTED", "AMENDED"];
+
+/// Verification a released result has passed
+const VERIFIED_STATUSES: &[&str] = &["AUTO_VERIFIED", "MANUALLY_VERIFIED"];
+
+/// A correction is entered as CORRECTED but unverified, and only replaces
+/// the result it corrects once it is verified and approved
+fn is_released(result: &PatientResult) -> bool {
+    REPORTABLE_STATUSES.contains(&result.result_status.as_str())
+        && VERIFIED_STATUSES.contains(&result.verification_status.as_str())
+        && result.approval_date.is_some()
+}
+
+/// Report data of a cumulative report: `dates` oldest first and `analytes`,
+/// each with one entry in `values` per date (`null` when not measured).
+///
+/// The dates are the `columns` most recent ones on which any selected analyte
+/// was collected. An analyte collected twice on a date shows the later result,
+/// which is also how a correction replaces the result it corrects.
+pub fn assemble(results: &[PatientResult], test_codes: &[String], columns: usize) -> Value {
+    let selected: Vec<&PatientResult> = results
+        .iter()
+        .filter(|r| is_released(r))
+        .filter(|r| r.result_value.as_deref().is_some_and(|value| !value.trim().is_empty()))
+        .filter(|r| test_codes.is_empty() || test_codes.iter().any(|code| code.eq_ignore_ascii_case(&r.test_code)))
+        .collect();
+
+    let all_dates: BTreeSet<NaiveDate> = selected.iter().map(|r| r.result_date.date_naive()).collect();
+    let skip = all_dates.len().saturating_sub(columns);
+    let dates: Vec<NaiveDate> = all_dates.into_iter().skip(skip).collect();
+
+    // Requested tests keep the requested order, others go by department
+    let mut codes: Vec<&str> = Vec::new();
+    for result in &selected {
+        if !codes.contains(&result.test_code.as_str()) {
+            codes.push(&result.test_code);
+        }
+    }
+    let position = |code: &str| test_codes.iter().position(|c| c.eq_ignore_ascii_case(code));
+    let latest = |code: &str| selected.iter().filter(|r| r.test_code == code).max_by_key(|r| r.result_date).copied();
+    codes.sort_by_key(|code| {
+        let result = latest(code);
+        (
+            position(code),
+            result.and_then(|r| r.department.clone()),
+            result.map(|r| r.test_name.clone()),
+        )
+    });
+
+    let analytes: Vec<Value> = codes
+        .into_iter()
+        .filter_map(|code| {
+            let values: Vec<Option<&PatientResult>> = dates
+                .iter()
+                .map(|date| {
+                    selected
+                        .iter()
+                        .filter(|r| r.test_code == code && r.result_date.date_naive() == *date)
+                        .max_by_key(|r| r.result_date)
+                        .copied()
+                })
+                .collect();
+            if values.iter().all(Option::is_none) {
+                return None;
+            }
+
+            // Units and ranges as of the newest result shown
+            let newest = values.iter().rev().flatten().next()?;
+            Some(json!({
+                "test_code": newest.test_code,
+                "test_name": newest.test_name,
+                "department": newest.department,
+                "unit": newest.result_unit,
+                "reference_range_text": newest.reference_range_text,
+                "values": values.into_iter().map(|value| value.map(|r| json!({
+                    "result_id": r.id,
+                    "date": r.result_date.date_naive(),
+                    "value": r.result_value,
+                    "interpretation": r.interpretation,
+                    "is_abnormal": r.is_abnormal,
+                    "is_critical": r.is_critical,
+                }))).collect::<Vec<_>>(),
+            }))
+        })
+        .collect();
+
+    json!({
+        "dates": dates,
+        "analytes": analytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn result(code: &str, day: u32, hour: u32, value: &str, status: &str) -> PatientResult {
+        PatientResult {
+            id: Uuid::new_v4(),
+            test_code: code.to_string(),
+            test_name: format!("{} test", code),
+            department: Some(if code == "HBA1C" { "Haematology" } else { "Biochemistry" }.to_string()),
+            result_value: Some(value.to_string()),
+            result_unit: Some("mg/dL".to_string()),
+            reference_range_text: Some(format!("range {}", day)),
+            interpretation: if value == "9.1" { "ABNORMAL_HIGH" } else { "NORMAL" }.to_string(),
+            is_abnormal: value == "9.1",
+            is_critical: false,
+            result_status: status.to_string(),
+            verification_status: "MANUALLY_VERIFIED".to_string(),
+            result_date: Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap(),
+            approval_date: Some(Utc.with_ymd_and_hms(2025, 1, day, 20, 0, 0).unwrap()),
+        }
+    }
+
+    fn unapproved(code: &str, day: u32, hour: u32, value: &str, status: &str) -> PatientResult {
+        PatientResult {
+            verification_status: "NOT_VERIFIED".to_string(),
+            approval_date: None,
+            ..result(code, day, hour, value, status)
+        }
+    }
+
+    #[test]
+    fn test_values_are_aligned_by_collection_date() {
+        let results = vec![
+            result("GLU", 1, 8, "110", "FINAL"),
+            result("GLU", 5, 8, "130", "FINAL"),
+            // Corrected later the same day
+            result("GLU", 5, 15, "125", "CORRECTED"),
+            result("CREAT", 3, 8, "1.1", "FINAL"),
+            result("CREAT", 5, 8, "1.3", "FINAL"),
+            result("HBA1C", 5, 8, "9.1", "FINAL"),
+            // Not released or not asked for
+            result("GLU", 7, 8, "140", "PRELIMINARY"),
+            unapproved("GLU", 5, 18, "999", "CORRECTED"),
+            unapproved("CREAT", 6, 8, "1.2", "FINAL"),
+            result("UREA", 6, 8, "40", "FINAL"),
+        ];
+        let codes = vec!["hba1c".to_string(), "GLU".to_string(), "CREAT".to_string()];
+
+        let data = assemble(&results, &codes, 6);
+        assert_eq!(data["dates"], json!(["2025-01-01", "2025-01-03", "2025-01-05"]));
+
+        let analytes = data["analytes"].as_array().unwrap();
+        let codes: Vec<&str> = analytes.iter().map(|a| a["test_code"].as_str().unwrap()).collect();
+        assert_eq!(codes, vec!["HBA1C", "GLU", "CREAT"]);
+
+        let values = |i: usize| -> Vec<Value> {
+            analytes[i]["values"].as_array().unwrap().iter().map(|v| v["value"].clone()).collect()
+        };
+        assert_eq!(values(0), vec![Value::Null, Value::Null, json!("9.1")]);
+        assert_eq!(values(1), vec![json!("110"), Value::Null, json!("125")]);
+        assert_eq!(values(2), vec![Value::Null, json!("1.1"), json!("1.3")]);
+        assert_eq!(analytes[0]["values"][2]["is_abnormal"], json!(true));
+        assert_eq!(analytes[1]["reference_range_text"], json!("range 5"));
+    }
+
+    #[test]
+    fn test_only_the_most_recent_dates_are_kept() {
+        let results: Vec<PatientResult> = (1..=9).map(|day| result("GLU", day, 8, "100", "FINAL")).collect();
+
+        let data = assemble(&results, &[], 4);
+        assert_eq!(data["dates"], json!(["2025-01-06", "2025-01-07", "2025-01-08", "2025-01-09"]));
+        assert_eq!(data["analytes"][0]["values"].as_array().unwrap().len(), 4);
+
+        let empty = assemble(&results, &["K".to_string()], 4);
+        assert_eq!(empty, json!({"dates": [], "analytes": []}));
+    }
+}
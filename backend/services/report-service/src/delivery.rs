@@ -0,0 +1,287 @@
+//! Report delivery through notification-service.
+//!
+//! Email, SMS and WhatsApp deliveries become notifications: emails attach
+//! the report, WhatsApp sends it as the document of a template and SMS
+//! carries a signed download link (see [`crate::verification`]). An attempt
+//! that fails is retried by the delivery worker with exponential backoff
+//! until the delivery's `max_retries` is used up; the worker also makes the
+//! first attempt of scheduled deliveries. Whether a sent report reached the
+//! recipient arrives later as a notification event.
+
+use serde::Deserialize;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::*;
+use crate::notification_client::NotificationRequest;
+use crate::service::ReportService;
+use common::tenant::Tenant;
+
+/// `reference_type` of the notifications of report deliveries
+pub const REFERENCE_TYPE: &str = "REPORT_DELIVERY";
+
+/// Deliveries the worker takes per run
+const WORKER_BATCH_SIZE: i64 = 50;
+
+/// Wait before retrying a failed attempt, doubling with each failure
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay after the `retry_count`th failure
+    pub fn delay(&self, retry_count: i32) -> Duration {
+        let doublings = retry_count.saturating_sub(1).clamp(0, 30) as u32;
+        self.base_delay.saturating_mul(1 << doublings).min(self.max_delay)
+    }
+}
+
+/// Payload of `NOTIFICATION_DELIVERED` and `NOTIFICATION_FAILED`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationOutcome {
+    pub notification_id: Uuid,
+    pub reference_type: Option<String>,
+    pub reference_id: Option<Uuid>,
+    pub notification_status: String,
+    pub status_message: Option<String>,
+    pub provider_message_id: Option<String>,
+}
+
+impl NotificationOutcome {
+    /// The report delivery the notification was sent for
+    pub fn delivery_id(&self) -> Option<Uuid> {
+        (self.reference_type.as_deref() == Some(REFERENCE_TYPE))
+            .then_some(self.reference_id)
+            .flatten()
+    }
+
+    /// Statuses arrive as notification-service serializes its enum
+    pub fn delivery_status(&self) -> Option<DeliveryStatus> {
+        match self.notification_status.as_str() {
+            "Delivered" | "Read" => Some(DeliveryStatus::Delivered),
+            "Failed" => Some(DeliveryStatus::Failed),
+            "Bounced" => Some(DeliveryStatus::Bounced),
+            _ => None,
+        }
+    }
+}
+
+/// Query of a signed download link (see [`crate::verification::ReportVerifier::download_link`])
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadLink {
+    /// Report number
+    pub r: String,
+    /// Access code
+    pub c: String,
+    /// Expiry, as a Unix timestamp
+    pub e: i64,
+    /// Signature
+    pub s: String,
+}
+
+/// The notification delivering a report on its channel. Downloads and
+/// prints are handed over at the lab and need none.
+pub fn notification_request(
+    report: &GeneratedReport,
+    delivery: &ReportDelivery,
+    link: &str,
+) -> Option<NotificationRequest> {
+    let channel = match delivery.delivery_channel {
+        DeliveryChannel::Email => "EMAIL",
+        DeliveryChannel::Sms => "SMS",
+        DeliveryChannel::Whatsapp => "WHATSAPP",
+        DeliveryChannel::Download | DeliveryChannel::Print => return None,
+    };
+
+    let report_date = report.report_date.format("%d %b %Y").to_string();
+    let content = match delivery.delivery_channel {
+        DeliveryChannel::Email => delivery.message.clone().unwrap_or_else(|| {
+            format!(
+                "Dear {},\n\nYour lab report {} dated {} is attached to this email.\n\nYou can also download it here: {}",
+                delivery.recipient_name, report.report_number, report_date, link
+            )
+        }),
+        // The link is the report, so it goes after any custom message
+        _ => format!(
+            "{} {}",
+            delivery.message.clone().unwrap_or_else(|| {
+                format!("Your lab report {} is ready. Download it securely:", report.report_number)
+            }),
+            link
+        ),
+    };
+
+    let template_data = serde_json::json!({
+        "recipient_name": delivery.recipient_name,
+        "report_number": report.report_number,
+        "report_date": report_date,
+        "report_url": link,
+    });
+
+    // SMS cannot carry files
+    let attachment = !matches!(delivery.delivery_channel, DeliveryChannel::Sms);
+
+    Some(NotificationRequest {
+        organization_id: delivery.organization_id,
+        notification_channel: channel,
+        recipient_type: "PATIENT".to_string(),
+        recipient_name: Some(delivery.recipient_name.clone()),
+        recipient_contact: delivery.recipient_contact.clone(),
+        subject: Some(
+            delivery.subject.clone().unwrap_or_else(|| format!("Your lab report {}", report.report_number)),
+        ),
+        content,
+        template_data: Some(template_data.to_string()),
+        reference_type: REFERENCE_TYPE.to_string(),
+        reference_id: delivery.id,
+        attachment_url: attachment.then(|| link.to_string()),
+        attachment_name: attachment.then(|| format!("{}.pdf", report.report_number)),
+    })
+}
+
+/// Makes the attempts that are due: scheduled first attempts and retries
+pub async fn run_delivery_worker(service: ReportService, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        // Due deliveries of every organization
+        if let Err(e) = Tenant::System.scope(service.process_due_deliveries(WORKER_BATCH_SIZE)).await {
+            tracing::error!("Report delivery worker failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDate};
+
+    fn report() -> GeneratedReport {
+        let now = Local::now().naive_local();
+        GeneratedReport {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            template_id: None,
+            report_number: "RPT-20250120-0001".to_string(),
+            report_title: "Laboratory Report ORD-1".to_string(),
+            report_type: ReportTemplateType::PatientReport,
+            patient_id: None,
+            order_id: None,
+            result_id: None,
+            batch_id: None,
+            report_data: serde_json::Value::Null,
+            generated_content: None,
+            report_format: None,
+            file_path: Some("reports/RPT-20250120-0001.pdf".to_string()),
+            file_size_bytes: None,
+            file_hash: None,
+            storage_location: None,
+            report_date: NaiveDate::from_ymd_opt(2025, 1, 20).unwrap(),
+            generated_at: None,
+            expires_at: None,
+            report_status: ReportStatus::Generated,
+            error_message: None,
+            is_confidential: None,
+            access_code: Some("K7M2QX9P".to_string()),
+            download_count: None,
+            last_downloaded_at: None,
+            requires_signature: None,
+            is_signed: None,
+            signed_at: None,
+            signed_by: None,
+            created_by: Uuid::new_v4(),
+            created_at: now,
+            updated_by: None,
+            updated_at: None,
+            is_deleted: None,
+        }
+    }
+
+    fn delivery(channel: DeliveryChannel, message: Option<&str>) -> ReportDelivery {
+        ReportDelivery {
+            id: Uuid::new_v4(),
+            report_id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            delivery_channel: channel,
+            recipient_name: "Asha Rao".to_string(),
+            recipient_contact: "+919876543210".to_string(),
+            subject: None,
+            message: message.map(str::to_string),
+            attachment_url: None,
+            delivery_status: Some(DeliveryStatus::Pending),
+            scheduled_at: None,
+            sent_at: None,
+            delivered_at: None,
+            provider_name: None,
+            provider_message_id: None,
+            provider_response: None,
+            notification_id: None,
+            error_message: None,
+            retry_count: Some(0),
+            max_retries: Some(3),
+            next_attempt_at: None,
+            created_by: Uuid::new_v4(),
+            created_at: Local::now().naive_local(),
+            updated_at: None,
+            is_deleted: Some(false),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy { base_delay: Duration::from_secs(60), max_delay: Duration::from_secs(600) };
+        assert_eq!(policy.delay(1), Duration::from_secs(60));
+        assert_eq!(policy.delay(2), Duration::from_secs(120));
+        assert_eq!(policy.delay(4), Duration::from_secs(480));
+        assert_eq!(policy.delay(5), Duration::from_secs(600));
+        assert_eq!(policy.delay(1000), Duration::from_secs(600));
+        assert_eq!(policy.delay(0), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_channels_carry_the_report_their_own_way() {
+        let report = report();
+        let link = "https://lab.example.com/reports/download?r=RPT-20250120-0001&c=K7M2QX9P&e=1&s=x";
+
+        let sms = notification_request(&report, &delivery(DeliveryChannel::Sms, Some("Results from City Lab.")), link).unwrap();
+        assert_eq!(sms.notification_channel, "SMS");
+        assert_eq!(sms.content, format!("Results from City Lab. {}", link));
+        assert!(sms.attachment_url.is_none());
+
+        let whatsapp = notification_request(&report, &delivery(DeliveryChannel::Whatsapp, None), link).unwrap();
+        assert_eq!(whatsapp.attachment_url.as_deref(), Some(link));
+        assert_eq!(whatsapp.attachment_name.as_deref(), Some("RPT-20250120-0001.pdf"));
+        let data: serde_json::Value = serde_json::from_str(whatsapp.template_data.as_deref().unwrap()).unwrap();
+        assert_eq!(data["report_number"], "RPT-20250120-0001");
+        assert_eq!(data["recipient_name"], "Asha Rao");
+
+        let email = notification_request(&report, &delivery(DeliveryChannel::Email, None), link).unwrap();
+        assert_eq!(email.subject.as_deref(), Some("Your lab report RPT-20250120-0001"));
+        assert!(email.content.contains("dated 20 Jan 2025 is attached"));
+        assert_eq!(email.reference_type, REFERENCE_TYPE);
+
+        assert!(notification_request(&report, &delivery(DeliveryChannel::Print, None), link).is_none());
+    }
+
+    #[test]
+    fn test_outcomes_map_to_delivery_statuses() {
+        let outcome: NotificationOutcome = serde_json::from_value(serde_json::json!({
+            "notification_id": Uuid::new_v4(),
+            "reference_type": REFERENCE_TYPE,
+            "reference_id": Uuid::nil(),
+            "notification_channel": "Whatsapp",
+            "notification_status": "Read",
+            "status_message": null,
+            "provider_name": "WhatsApp Cloud",
+            "provider_message_id": "wamid.A",
+        }))
+        .unwrap();
+        assert_eq!(outcome.delivery_id(), Some(Uuid::nil()));
+        assert_eq!(outcome.delivery_status(), Some(DeliveryStatus::Delivered));
+
+        let other = NotificationOutcome { reference_type: Some("CRITICAL_ALERT".to_string()), ..outcome };
+        assert_eq!(other.delivery_id(), None);
+    }
+}
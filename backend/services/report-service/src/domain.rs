@@ -15,6 +15,7 @@ pub enum ReportTemplateType {
     QcReport,
     SummaryReport,
     CustomReport,
+    CumulativeReport,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum, sqlx::Type)]
@@ -230,6 +231,7 @@ pub struct DigitalSignature {
 
     // Signature details
     pub signature_type: String,
+    pub signature_field: Option<String>,
     pub signature_image_path: Option<String>,
     pub digital_certificate: Option<String>,
 
@@ -253,6 +255,49 @@ pub struct DigitalSignature {
     pub is_deleted: Option<bool>,
 }
 
+/// A signature found in a report PDF
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct VerifiedSignature {
+    pub field_name: String,
+    pub signer_name: Option<String>,
+    /// Claimed signing time, UTC
+    pub signing_time: Option<NaiveDateTime>,
+    pub reason: Option<String>,
+    pub certificate_subject: Option<String>,
+    pub certificate_issuer: Option<String>,
+    pub certificate_serial: Option<String>,
+    /// Signed after every revision of the file; false for earlier signatures
+    /// of a report signed by several signatories
+    pub covers_whole_document: bool,
+    /// The signed bytes are unchanged
+    pub is_intact: bool,
+    /// The certificate chains to a trusted authority as of the signing time
+    pub is_trusted: bool,
+    pub error: Option<String>,
+}
+
+/// Outcome of checking the signatures of a report PDF
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SignatureVerification {
+    /// SHA-256 of the checked file
+    pub file_hash: String,
+    /// Whether the file is the report's stored file; unknown for uploaded files
+    pub matches_report: Option<bool>,
+    pub signatures: Vec<VerifiedSignature>,
+    /// Signed, every signature intact and trusted, and nothing appended after
+    /// the last one
+    pub is_valid: bool,
+}
+
+impl SignatureVerification {
+    pub fn new(file_hash: String, matches_report: Option<bool>, signatures: Vec<VerifiedSignature>) -> Self {
+        let is_valid = signatures.last().is_some_and(|last| last.covers_whole_document)
+            && signatures.iter().all(|s| s.is_intact && s.is_trusted)
+            && matches_report != Some(false);
+        Self { file_hash, matches_report, signatures, is_valid }
+    }
+}
+
 // ============================================================================
 // Report Delivery Entity
 // ============================================================================
@@ -284,11 +329,15 @@ pub struct ReportDelivery {
     pub provider_message_id: Option<String>,
     #[sqlx(json)]
     pub provider_response: Option<serde_json::Value>,
+    /// notification-service notification of the latest attempt
+    pub notification_id: Option<Uuid>,
 
     // Error handling
     pub error_message: Option<String>,
     pub retry_count: Option<i32>,
     pub max_retries: Option<i32>,
+    /// When the delivery worker makes the next attempt
+    pub next_attempt_at: Option<NaiveDateTime>,
 
     // Audit fields
     pub created_by: Uuid,
@@ -307,6 +356,34 @@ impl ReportDelivery {
 
 // ============================================================================
 // Report Access Log Entity
+/// How a report's stored file compares with the hash recorded for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum HashStatus {
+    Match,
+    Mismatch,
+    /// The file could not be read
+    Unavailable,
+}
+
+/// What the public verification portal tells about a report, enough to
+/// recognise it without disclosing results
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ReportVerification {
+    pub report_number: String,
+    pub report_date: NaiveDate,
+    pub patient_initials: Option<String>,
+    /// Pathologist whose signature completed the report
+    pub signed_off_by: Option<String>,
+    pub signatory_designation: Option<String>,
+    pub signed_at: Option<NaiveDateTime>,
+    /// Verification code of the sign-off signature
+    pub verification_code: Option<String>,
+    pub hash_status: HashStatus,
+    /// The file is intact and, where the report is signed or needs to be,
+    /// signed off with trusted signatures matching their records
+    pub is_genuine: bool,
+}
+
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
@@ -329,6 +406,32 @@ pub struct ReportAccessLog {
     pub duration_seconds: Option<i32>,
 }
 
+// ============================================================================
+// Result Snapshot Entity
+// ============================================================================
+
+/// An approved result as published by result-service
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ResultSnapshot {
+    pub result_id: Uuid,
+    pub organization_id: Uuid,
+    pub order_id: Uuid,
+    pub patient_id: Uuid,
+    pub test_code: String,
+    pub result_data: serde_json::Value,
+    pub approved_at: NaiveDateTime,
+    pub superseded_by: Option<Uuid>,
+}
+
+/// The fields of a completed `TestOrder` that its report needs
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompletedOrder {
+    pub id: Uuid,
+    pub order_number: String,
+    pub patient_id: Uuid,
+    pub organization_id: Uuid,
+}
+
 // ============================================================================
 // Input Types
 // ============================================================================
@@ -346,8 +449,16 @@ pub struct CreateReportTemplateInput {
     pub styles: Option<String>,
     pub page_size: Option<String>,
     pub page_orientation: Option<String>,
+    pub margin_top: Option<i32>,
+    pub margin_bottom: Option<i32>,
+    pub margin_left: Option<i32>,
+    pub margin_right: Option<i32>,
     pub fields_config: Option<String>,
+    pub show_logo: Option<bool>,
+    pub show_watermark: Option<bool>,
+    pub watermark_text: Option<String>,
     pub requires_signature: Option<bool>,
+    pub signature_fields: Option<String>, // JSON array string
     pub is_default: Option<bool>,
 }
 
@@ -368,11 +479,22 @@ pub struct GenerateReportInput {
     pub generate_access_code: Option<bool>,
 }
 
+#[derive(Debug, Clone, InputObject)]
+pub struct GenerateCumulativeReportInput {
+    pub organization_id: Uuid,
+    pub patient_id: Uuid,
+    pub template_id: Option<Uuid>,
+    pub report_title: Option<String>,
+    /// Tests in report order; every reported test when absent
+    pub test_codes: Option<Vec<String>>,
+    /// Most recent collection dates shown side by side
+    pub max_columns: Option<i32>,
+    pub requires_signature: Option<bool>,
+}
+
 #[derive(Debug, Clone, InputObject)]
 pub struct SignReportInput {
     pub report_id: Uuid,
-    pub signatory_id: Uuid,
-    pub signatory_name: String,
     pub signatory_role: Option<String>,
     pub signatory_designation: Option<String>,
     pub signatory_qualification: Option<String>,
@@ -381,6 +503,8 @@ pub struct SignReportInput {
     pub signature_location: Option<String>,
     pub signature_ip_address: Option<String>,
     pub signature_comments: Option<String>,
+    /// Password of the signatory's PKCS#12 keystore; required
+    pub keystore_password: Option<String>,
 }
 
 #[derive(Debug, Clone, InputObject)]
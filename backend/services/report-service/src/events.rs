@@ -0,0 +1,79 @@
+//! Reactions to order, result and notification events.
+//!
+//! Approved results are kept as [`ResultSnapshot`]s; once the order is
+//! completed its patient report is generated from them. Outcomes of the
+//! notifications that delivered reports settle their deliveries.
+
+use chrono::{DateTime, Utc};
+use infrastructure::event_bus::events;
+use infrastructure::EventRouter;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::delivery::NotificationOutcome;
+use crate::domain::{CompletedOrder, ResultSnapshot};
+use crate::service::ReportService;
+
+/// The fields of an approved `TestResult` that reports need
+#[derive(Debug, Deserialize)]
+struct ApprovedResult {
+    id: Uuid,
+    organization_id: Uuid,
+    order_id: Uuid,
+    patient_id: Uuid,
+    test_code: String,
+    approval_date: Option<DateTime<Utc>>,
+    corrected_from_result_id: Option<Uuid>,
+}
+
+pub fn event_routes(router: EventRouter, service: ReportService) -> EventRouter {
+    let results = service.clone();
+    let orders = service.clone();
+    let delivered = service.clone();
+
+    router
+        .on(events::RESULT_APPROVED, move |event| {
+            let service = results.clone();
+            async move {
+                let result: ApprovedResult = serde_json::from_value(event.payload.clone())?;
+                let snapshot = ResultSnapshot {
+                    result_id: result.id,
+                    organization_id: result.organization_id,
+                    order_id: result.order_id,
+                    patient_id: result.patient_id,
+                    test_code: result.test_code,
+                    result_data: event.payload,
+                    approved_at: result.approval_date.unwrap_or(event.metadata.timestamp).naive_utc(),
+                    superseded_by: None,
+                };
+                service.record_approved_result(snapshot, result.corrected_from_result_id).await?;
+                Ok(())
+            }
+        })
+        .on(events::ORDER_COMPLETED, move |event| {
+            let service = orders.clone();
+            async move {
+                let order: CompletedOrder = serde_json::from_value(event.payload.clone())?;
+                let created_by = event.user_uuid().unwrap_or_default();
+                if let Some(report) = service.generate_order_report(&order, &event.payload, created_by).await? {
+                    tracing::info!("Report {} generated for order {}", report.report_number, order.order_number);
+                }
+                Ok(())
+            }
+        })
+        .on_payload(events::NOTIFICATION_DELIVERED, move |_, outcome: NotificationOutcome| {
+            let service = delivered.clone();
+            async move { record_outcome(&service, outcome).await }
+        })
+        .on_payload(events::NOTIFICATION_FAILED, move |_, outcome: NotificationOutcome| {
+            let service = service.clone();
+            async move { record_outcome(&service, outcome).await }
+        })
+}
+
+async fn record_outcome(service: &ReportService, outcome: NotificationOutcome) -> common::error::Result<()> {
+    if let Some(delivery) = service.record_delivery_receipt(&outcome).await? {
+        tracing::info!("Delivery {} of report {} is {:?}", delivery.id, delivery.report_id, delivery.delivery_status);
+    }
+    Ok(())
+}
@@ -1,26 +1,63 @@
-use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
+use actix_web::{web, App, HttpRequest, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, JwtService, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::event_bus::topics;
+use infrastructure::{ConsumerRuntime, DatabasePool, EventBusConfig, EventRouter, EventTransport, OutboxRelay};
+use std::time::Duration;
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod events;
+mod pdf;
+mod storage;
+mod cumulative;
+mod result_client;
+mod patient_client;
+mod user_client;
+mod signing;
+mod qr;
+mod verification;
+mod notification_client;
+mod delivery;
 
 use repository::*;
 use service::ReportService;
-use api::{QueryRoot, MutationRoot};
+use api::{QueryRoot, MutationRoot, RequestOrigin};
 use config::Config;
+use storage::ReportStorage;
+use result_client::ResultClient;
+use patient_client::PatientClient;
+use user_client::UserClient;
+use signing::Keystore;
+use verification::ReportVerifier;
+use notification_client::NotificationClient;
+use delivery::{DownloadLink, RetryPolicy};
+use service::ReportError;
 
 type ReportSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<ReportSchema>,
+    http_req: HttpRequest,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    let origin = RequestOrigin {
+        ip_address: http_req.connection_info().realip_remote_addr().map(str::to_string),
+        user_agent: http_req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|agent| agent.to_str().ok())
+            .map(str::to_string),
+    };
+    let tenant = claims.tenant();
+    let request = claims.attach(req.into_inner()).data(origin);
+    tenant::scope(tenant, schema.execute(request)).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -29,6 +66,38 @@ async fn graphql_playground() -> HttpResponse {
         .body(GraphiQLSource::build().endpoint("/graphql").finish())
 }
 
+/// A report from the signed link a delivery sent. Public: the link is the
+/// credential.
+async fn download_report(
+    service: web::Data<ReportService>,
+    http_req: HttpRequest,
+    link: web::Query<DownloadLink>,
+) -> HttpResponse {
+    let ip_address = http_req.connection_info().realip_remote_addr().map(str::to_string);
+    let user_agent = http_req
+        .headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|agent| agent.to_str().ok())
+        .map(str::to_string);
+
+    // Links do not say which organization issued them
+    match Tenant::System.scope(service.download_by_link(&link, ip_address, user_agent)).await {
+        Ok((report, bytes)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                actix_web::http::header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}.pdf\"", report.report_number),
+            ))
+            .body(bytes),
+        Err(ReportError::AccessDenied) => HttpResponse::Forbidden().finish(),
+        Err(ReportError::ReportNotReady) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            tracing::error!("Failed to serve report download: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 async fn health_check() -> HttpResponse {
     HttpResponse::Ok().json(serde_json::json!({
         "status": "healthy",
@@ -89,50 +158,118 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("  Max DB connections: {}", config.database_max_connections);
     tracing::info!("  Caching enabled: {}", config.enable_caching);
     tracing::info!("  Events enabled: {}", config.enable_events);
+    tracing::info!("  Report storage: {}", config.report_storage_dir);
+    tracing::info!("  Result service: {}", config.result_service_url);
+    tracing::info!("  Patient service: {}", config.patient_service_url);
+    tracing::info!("  User service: {}", config.user_service_url);
+    tracing::info!("  Signing keystore: {}", config.signing_keystore_dir);
+    tracing::info!("  Verification portal: {}", config.verification_portal_url);
+    tracing::info!("  Notification service: {}", config.notification_service_url);
+    tracing::info!("  Report downloads: {}", config.report_download_url);
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&pool))
         .await
         .expect("Failed to run migrations");
 
     tracing::info!("Migrations completed successfully");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+        tracing::info!("Outbox relay started");
+    }
+
     // Create repositories
     let template_repo = ReportTemplateRepository::new(pool.clone());
     let report_repo = GeneratedReportRepository::new(pool.clone());
     let signature_repo = DigitalSignatureRepository::new(pool.clone());
     let delivery_repo = ReportDeliveryRepository::new(pool.clone());
     let access_log_repo = ReportAccessLogRepository::new(pool.clone());
+    let snapshot_repo = ResultSnapshotRepository::new(pool.clone());
+
+    // Certificates that signature checks trust
+    let trust_anchors = signing::load_trust_anchors(config.signing_trust_anchors.as_deref())
+        .expect("Failed to load signing trust anchors");
+    if trust_anchors.is_empty() {
+        tracing::warn!("No signing trust anchors configured; report signatures will not verify as trusted");
+    }
 
     // Create service
+    let jwt = JwtService::new(&config.jwt_secret);
     let report_service = ReportService::new(
         template_repo,
         report_repo,
         signature_repo,
         delivery_repo,
         access_log_repo,
+        snapshot_repo,
+        ReportStorage::new(&config.report_storage_dir),
+        ResultClient::new(config.result_service_url.clone(), jwt.clone()),
+        PatientClient::new(config.patient_service_url.clone(), jwt.clone()),
+        UserClient::new(config.user_service_url.clone(), jwt.clone()),
+        Keystore::new(&config.signing_keystore_dir),
+        trust_anchors,
+        ReportVerifier::new(
+            &config.verification_secret,
+            &config.verification_portal_url,
+            &config.report_download_url,
+        ),
+        NotificationClient::new(config.notification_service_url.clone(), jwt),
+        RetryPolicy {
+            base_delay: Duration::from_secs(config.delivery_retry_base_seconds),
+            max_delay: Duration::from_secs(config.delivery_retry_max_seconds),
+        },
+        chrono::Duration::hours(config.delivery_link_ttl_hours),
     );
 
+    // React to order, result and notification events
+    if let Some(event_bus) = &event_bus {
+        let consumer = event_bus
+            .subscribe(
+                "report-service",
+                &[topics::ORDER_EVENTS, topics::RESULT_EVENTS, topics::NOTIFICATION_EVENTS],
+            )
+            .expect("Failed to create event consumer");
+        let router = events::event_routes(EventRouter::new(), report_service.clone());
+        ConsumerRuntime::new(consumer, router, event_bus.clone()).spawn();
+    }
+
+    // Scheduled deliveries and retries
+    tokio::spawn(delivery::run_delivery_worker(
+        report_service.clone(),
+        Duration::from_secs(config.delivery_worker_interval_seconds),
+    ));
+    tracing::info!("Delivery worker started");
+
     // Build GraphQL schema
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
-        .data(report_service)
+        .data(report_service.clone())
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: reportTemplate, reportTemplateByCode, reportTemplates, report, reportByNumber, reports, reportSignatures, delivery, deliveries, reportAccessLogs");
-    tracing::info!("  Mutations: createReportTemplate, generateReport, downloadReport, verifyReportAccess, signReport, deliverReport, retryDelivery, logReportAccess");
+    tracing::info!("  Queries: reportTemplate, reportTemplateByCode, reportTemplates, report, reportByNumber, reports, reportSignatures, verifyReportSignatures, verifySignedPdf, delivery, deliveries, reportAccessLogs");
+    tracing::info!("  Mutations: createReportTemplate, generateReport, generateCumulativeReport, downloadReport, verifyReportAccess, verifyReport, signReport, deliverReport, retryDelivery, logReportAccess");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -141,13 +278,17 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("GraphiQL playground: http://{}/graphql (GET)", bind_addr);
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
+    tracing::info!("Report downloads: http://{}/reports/download", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(report_service.clone()))
             .service(
                 web::resource("/graphql")
                     .guard(guard::Post())
@@ -160,6 +301,7 @@ async fn main() -> std::io::Result<()> {
             )
             .service(web::resource("/health").route(web::get().to(health_check)))
             .service(web::resource("/ready").route(web::get().to(ready_check)))
+            .service(web::resource("/reports/download").route(web::get().to(download_report)))
     })
     .bind(&bind_addr)?
     .run()
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the notification-service
+#[derive(Clone)]
+pub struct NotificationClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendNotificationResponse {
+    send_notification: SentNotification,
+}
+
+/// A notification to send, as `SendNotificationInput` of notification-service
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRequest {
+    pub organization_id: Uuid,
+    /// `EMAIL`, `SMS` or `WHATSAPP`
+    pub notification_channel: &'static str,
+    pub recipient_type: String,
+    pub recipient_name: Option<String>,
+    pub recipient_contact: String,
+    pub subject: Option<String>,
+    pub content: String,
+    /// JSON object, as a string
+    pub template_data: Option<String>,
+    pub reference_type: String,
+    pub reference_id: Uuid,
+    pub attachment_url: Option<String>,
+    pub attachment_name: Option<String>,
+}
+
+/// The notification as notification-service left it after trying to send it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentNotification {
+    pub id: Uuid,
+    pub notification_status: Option<String>,
+    pub status_message: Option<String>,
+    pub provider_name: Option<String>,
+    pub provider_message_id: Option<String>,
+}
+
+impl SentNotification {
+    /// Whether a provider took the message
+    pub fn is_sent(&self) -> bool {
+        matches!(self.notification_status.as_deref(), Some("SENT" | "DELIVERED" | "READ"))
+    }
+}
+
+impl NotificationClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Send a notification right away
+    pub async fn send(&self, request: &NotificationRequest) -> Result<SentNotification> {
+        let query = r#"
+            mutation SendNotification($input: SendNotificationInput!) {
+                sendNotification(input: $input) {
+                    id
+                    notificationStatus
+                    statusMessage
+                    providerName
+                    providerMessageId
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "input": request }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call notification-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to notification-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Notification-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Notification-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<SendNotificationResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse notification-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from notification-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("Notification-service refused the notification: {}", error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .map(|data| data.send_notification)
+            .ok_or_else(|| Error::ExternalService("No data returned from notification-service".to_string()))
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the patient-service
+#[derive(Clone)]
+pub struct PatientClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatientResponse {
+    patient: PatientName,
+}
+
+/// The name of a patient, for the initials shown on report verification
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientName {
+    pub first_name: String,
+    pub last_name: Option<String>,
+}
+
+impl PatientClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Fetch the name of a patient
+    pub async fn get_patient_name(&self, patient_id: Uuid) -> Result<PatientName> {
+        let query = r#"
+            query Patient($id: String!) {
+                patient(id: $id) {
+                    firstName
+                    lastName
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "id": patient_id.to_string() }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call patient-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to patient-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Patient-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Patient-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<PatientResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse patient-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from patient-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::NotFound(
+                format!("Patient {} not found: {}", patient_id, error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .map(|data| data.patient)
+            .ok_or_else(|| Error::ExternalService("No data returned from patient-service".to_string()))
+    }
+}
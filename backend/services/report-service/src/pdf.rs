@@ -0,0 +1,1857 @@
+//! PDF rendering of lab reports.
+//!
+//! A [`ReportTemplate`] drives the layout:
+//!
+//! - `template_content.sections` is the body, drawn top to bottom. Section
+//!   types are `heading`, `text`, `fields`, `results_table`,
+//!   `cumulative_table`, `signatures`, `verification_qr`, `spacer` and `page_break`. Text may reference the report data as
+//!   `{{order.order_number}}` and the report itself as `{{report.report_number}}`.
+//! - Reports with a verification link get its QR code at the end of the body
+//!   unless a `verification_qr` section places it.
+//! - `header_content` (`title`, `lines`, base64 JPEG `logo`) and
+//!   `footer_content` (`text`, `show_page_numbers`) repeat on every page.
+//! - `styles` overrides font sizes and `#RRGGBB` colours.
+//! - Page size, orientation, margins (mm), logo and watermark come from the
+//!   template's own columns.
+//!
+//! Without a template the default patient layout is used. Layout runs first
+//! and yields positioned drawing operations per page, so the page count is
+//! known when footers print "Page X of Y"; printpdf then draws the pages.
+
+use base64::{engine::general_purpose, Engine as _};
+use printpdf::path::PaintMode;
+use printpdf::{
+    BuiltinFont, Color, Image, ImageFilter, ImageTransform, ImageXObject, IndirectFontRef, Line,
+    Mm, PdfDocument, PdfLayerReference, Point, Px, Rect, TextMatrix,
+};
+use serde_json::{json, Value};
+
+use crate::domain::{GeneratedReport, ReportTemplate, ReportTemplateType};
+use crate::qr::QrCode;
+
+const PT_TO_MM: f32 = 25.4 / 72.0;
+const DEFAULT_MARGIN: f32 = 10.0;
+const CELL_PADDING: f32 = 1.2;
+const HEADER_GAP: f32 = 4.0;
+const FOOTER_HEIGHT: f32 = 8.0;
+const WATERMARK_ANGLE: f32 = 45.0;
+/// Side of a QR code module in mm
+const QR_MODULE_SIZE: f32 = 0.5;
+/// Light modules around a QR code that readers need to find it
+const QR_QUIET_ZONE: usize = 4;
+const SECTION_TYPES: &[&str] = &[
+    "heading", "text", "fields", "results_table", "cumulative_table", "signatures", "verification_qr", "spacer",
+    "page_break",
+];
+
+/// Render a report to PDF, with a QR code of its verification link if it has
+/// one
+pub fn render(
+    report: &GeneratedReport,
+    data: &Value,
+    template: Option<&ReportTemplate>,
+    verification_url: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let layout = layout(report, data, template, verification_url)?;
+    write(report, &layout)
+}
+
+/// Page dimensions in mm, portrait
+pub fn page_dimensions(page_size: &str) -> Option<(f32, f32)> {
+    match page_size.trim().to_ascii_uppercase().as_str() {
+        "A3" => Some((297.0, 420.0)),
+        "A4" => Some((210.0, 297.0)),
+        "A5" => Some((148.0, 210.0)),
+        "LETTER" => Some((215.9, 279.4)),
+        "LEGAL" => Some((215.9, 355.6)),
+        _ => None,
+    }
+}
+
+/// Check the body sections of template content
+pub fn validate_sections(content: &Value) -> Result<(), String> {
+    match content.get("sections") {
+        None => Ok(()),
+        Some(Value::Array(sections)) => sections.iter().try_for_each(|section| {
+            let kind = section.get("type").and_then(Value::as_str).unwrap_or_default();
+            if SECTION_TYPES.contains(&kind) {
+                Ok(())
+            } else {
+                Err(format!("Unknown template section type '{}'", kind))
+            }
+        }),
+        Some(_) => Err("Template sections must be an array".to_string()),
+    }
+}
+
+/// Whether an orientation is landscape
+pub fn is_landscape(orientation: &str) -> Option<bool> {
+    match orientation.trim().to_ascii_uppercase().as_str() {
+        "PORTRAIT" => Some(false),
+        "LANDSCAPE" => Some(true),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Page setup and styles
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageSetup {
+    width: f32,
+    height: f32,
+    margin_top: f32,
+    margin_bottom: f32,
+    margin_left: f32,
+    margin_right: f32,
+}
+
+impl PageSetup {
+    fn new(template: Option<&ReportTemplate>) -> Self {
+        let (width, height) = template
+            .and_then(|t| t.page_size.as_deref())
+            .and_then(page_dimensions)
+            .unwrap_or((210.0, 297.0));
+        let landscape = template
+            .and_then(|t| t.page_orientation.as_deref())
+            .and_then(is_landscape)
+            .unwrap_or(false);
+        let (width, height) = if landscape { (height, width) } else { (width, height) };
+
+        let margin = |margin: Option<i32>| margin.map(|m| m.clamp(0, 50) as f32).unwrap_or(DEFAULT_MARGIN);
+
+        Self {
+            width,
+            height,
+            margin_top: margin(template.and_then(|t| t.margin_top)),
+            margin_bottom: margin(template.and_then(|t| t.margin_bottom)),
+            margin_left: margin(template.and_then(|t| t.margin_left)),
+            margin_right: margin(template.and_then(|t| t.margin_right)),
+        }
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - self.margin_left - self.margin_right
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgb(f32, f32, f32);
+
+impl Rgb {
+    fn hex(value: &str) -> Option<Self> {
+        let hex = value.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(|c| c as f32 / 255.0);
+        Some(Self(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Style {
+    font_size: f32,
+    small_size: f32,
+    heading_size: f32,
+    title_size: f32,
+    logo_height: f32,
+    text_color: Rgb,
+    muted_color: Rgb,
+    primary_color: Rgb,
+    rule_color: Rgb,
+    table_header_fill: Rgb,
+    abnormal_color: Rgb,
+    abnormal_fill: Rgb,
+    critical_color: Rgb,
+    critical_fill: Rgb,
+    watermark_color: Rgb,
+    watermark_size: f32,
+    trend_band_fill: Rgb,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            font_size: 9.0,
+            small_size: 7.5,
+            heading_size: 12.0,
+            title_size: 15.0,
+            logo_height: 15.0,
+            text_color: Rgb(0.1, 0.1, 0.1),
+            muted_color: Rgb(0.4, 0.4, 0.4),
+            primary_color: Rgb(0.12, 0.31, 0.47),
+            rule_color: Rgb(0.8, 0.8, 0.8),
+            table_header_fill: Rgb(0.85, 0.89, 0.95),
+            abnormal_color: Rgb(0.75, 0.35, 0.0),
+            abnormal_fill: Rgb(1.0, 0.95, 0.85),
+            critical_color: Rgb(0.75, 0.0, 0.0),
+            critical_fill: Rgb(0.99, 0.87, 0.87),
+            watermark_color: Rgb(0.9, 0.9, 0.9),
+            watermark_size: 60.0,
+            trend_band_fill: Rgb(0.88, 0.95, 0.88),
+        }
+    }
+}
+
+impl Style {
+    fn new(styles: Option<&Value>) -> Self {
+        let mut style = Self::default();
+        let Some(styles) = styles else {
+            return style;
+        };
+
+        let size = |key: &str, target: &mut f32, max: f32| {
+            if let Some(value) = styles.get(key).and_then(Value::as_f64) {
+                *target = (value as f32).clamp(4.0, max);
+            }
+        };
+        size("font_size", &mut style.font_size, 24.0);
+        size("small_size", &mut style.small_size, 24.0);
+        size("heading_size", &mut style.heading_size, 36.0);
+        size("title_size", &mut style.title_size, 48.0);
+        size("logo_height", &mut style.logo_height, 60.0);
+        size("watermark_size", &mut style.watermark_size, 144.0);
+
+        let color = |key: &str, target: &mut Rgb| {
+            if let Some(value) = styles.get(key).and_then(Value::as_str).and_then(Rgb::hex) {
+                *target = value;
+            }
+        };
+        color("text_color", &mut style.text_color);
+        color("muted_color", &mut style.muted_color);
+        color("primary_color", &mut style.primary_color);
+        color("rule_color", &mut style.rule_color);
+        color("table_header_fill", &mut style.table_header_fill);
+        color("abnormal_color", &mut style.abnormal_color);
+        color("abnormal_fill", &mut style.abnormal_fill);
+        color("critical_color", &mut style.critical_color);
+        color("critical_fill", &mut style.critical_fill);
+        color("watermark_color", &mut style.watermark_color);
+        color("trend_band_fill", &mut style.trend_band_fill);
+
+        style
+    }
+}
+
+// ============================================================================
+// Text measurement
+// ============================================================================
+
+/// Helvetica advance widths of ' '..='~' in 1/1000 em
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Helvetica-Bold advance widths of ' '..='~' in 1/1000 em
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Width of a single line of text in mm
+fn text_width(text: &str, bold: bool, size: f32) -> f32 {
+    let widths = if bold { &HELVETICA_BOLD } else { &HELVETICA };
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => widths[c as usize - 32] as u32,
+            _ => 556,
+        })
+        .sum();
+    units as f32 / 1000.0 * size * PT_TO_MM
+}
+
+fn line_height(size: f32) -> f32 {
+    size * 1.3 * PT_TO_MM
+}
+
+/// Baseline of a line whose top is at `top`
+fn baseline(top: f32, size: f32) -> f32 {
+    top - size * PT_TO_MM
+}
+
+/// Break text into lines no wider than `width`
+fn wrap(text: &str, bold: bool, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if text_width(&candidate, bold, size) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Words longer than a line are broken between characters
+            for c in word.chars() {
+                line.push(c);
+                if text_width(&line, bold, size) > width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Shorten text with an ellipsis to fit `width`
+fn truncate(text: &str, bold: bool, size: f32, width: f32) -> String {
+    if text_width(text, bold, size) <= width {
+        return text.to_string();
+    }
+    let mut shortened: String = text.to_string();
+    while !shortened.is_empty() && text_width(&format!("{}...", shortened), bold, size) > width {
+        shortened.pop();
+    }
+    format!("{}...", shortened.trim_end())
+}
+
+// ============================================================================
+// Report data
+// ============================================================================
+
+/// Report data with the report itself under `report` and its verification
+/// link under `verification.url`
+fn context(report: &GeneratedReport, data: &Value, verification_url: Option<&str>) -> Value {
+    let mut context = match data {
+        Value::Object(map) => map.clone(),
+        other => {
+            let mut map = serde_json::Map::new();
+            map.insert("data".to_string(), other.clone());
+            map
+        }
+    };
+    context.insert("report".to_string(), serde_json::to_value(report).unwrap_or(Value::Null));
+    if let Some(url) = verification_url {
+        context.insert("verification".to_string(), json!({ "url": url }));
+    }
+    Value::Object(context)
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => value.get(key),
+    })
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(display).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// Replace `{{path}}` placeholders with report data; unknown paths are blank
+fn fill(text: &str, context: &Value) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                if let Some(value) = lookup(context, after[..end].trim()) {
+                    filled.push_str(&display(value));
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                filled.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Normal,
+    Abnormal,
+    Critical,
+}
+
+/// `AbnormalHigh` and `ABNORMAL_HIGH` both become `abnormalhigh`
+fn interpretation(result: &Value) -> String {
+    result
+        .get("interpretation")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .replace('_', "")
+        .to_ascii_lowercase()
+}
+
+fn severity(result: &Value) -> Severity {
+    let flagged = |key: &str| result.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let interpretation = interpretation(result);
+
+    if flagged("is_critical") || interpretation.starts_with("critical") {
+        Severity::Critical
+    } else if flagged("is_abnormal") || interpretation.starts_with("abnormal") {
+        Severity::Abnormal
+    } else {
+        Severity::Normal
+    }
+}
+
+fn result_flag(result: &Value) -> String {
+    match interpretation(result).as_str() {
+        "criticalhigh" => "HH",
+        "criticallow" => "LL",
+        "abnormalhigh" => "H",
+        "abnormallow" => "L",
+        _ => match severity(result) {
+            Severity::Critical => "!!",
+            Severity::Abnormal => "*",
+            Severity::Normal => "",
+        },
+    }
+    .to_string()
+}
+
+fn reference_range(result: &Value) -> String {
+    let text = result.get("reference_range_text").map(display).unwrap_or_default();
+    if !text.is_empty() {
+        return text;
+    }
+    let bound = |key: &str| result.get(key).map(display).unwrap_or_default();
+    match (bound("reference_range_min"), bound("reference_range_max")) {
+        (min, max) if !min.is_empty() && !max.is_empty() => format!("{} - {}", min, max),
+        (min, _) if !min.is_empty() => format!(">= {}", min),
+        (_, max) if !max.is_empty() => format!("<= {}", max),
+        _ => String::new(),
+    }
+}
+
+/// Cell text of a results table column
+fn cell(result: &Value, field: &str) -> String {
+    match field {
+        "flag" => result_flag(result),
+        "reference_range" => reference_range(result),
+        _ => lookup(result, field).map(display).unwrap_or_default(),
+    }
+}
+
+fn numeric(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Bounds of a two-sided range such as "70 - 110 mg/dL"
+fn parse_range(text: &str) -> Option<(f64, f64)> {
+    let leading = |s: &str| {
+        let s = s.trim();
+        let end = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+        numeric(&s[..end])
+    };
+    let (low, high) = text.split_once('-')?;
+    let (low, high) = (leading(low)?, leading(high)?);
+    (low < high).then_some((low, high))
+}
+
+/// Two-line column heading of an ISO date: "05 Jan" over "2025"
+fn date_heading(date: &str) -> (String, String) {
+    match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => (date.format("%d %b").to_string(), date.format("%Y").to_string()),
+        Err(_) => (date.to_string(), String::new()),
+    }
+}
+
+// ============================================================================
+// Layout
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    /// Text with its baseline at `y`
+    Text { x: f32, y: f32, size: f32, bold: bool, color: Rgb, text: String },
+    /// Filled rectangle with its lower left corner at (`x`, `y`)
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Rgb },
+    /// Horizontal rule
+    Rule { x: f32, y: f32, width: f32, color: Rgb },
+    /// Open line through points
+    Polyline { points: Vec<(f32, f32)>, color: Rgb },
+    /// The template logo with its lower left corner at (`x`, `y`)
+    Logo { x: f32, y: f32, width: f32, height: f32 },
+    /// Diagonal text starting at (`x`, `y`)
+    Watermark { x: f32, y: f32, size: f32, color: Rgb, text: String },
+}
+
+/// A JPEG embedded as is, without decoding
+#[derive(Debug, Clone)]
+struct Jpeg {
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+    components: u8,
+}
+
+impl Jpeg {
+    /// Read the dimensions from the first frame header
+    fn parse(data: Vec<u8>) -> Option<Self> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return None;
+        }
+        let mut i = 2;
+        while i + 9 < data.len() {
+            if data[i] != 0xFF {
+                return None;
+            }
+            let marker = data[i + 1];
+            if marker == 0xFF {
+                i += 1;
+                continue;
+            }
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let height = u16::from_be_bytes([data[i + 5], data[i + 6]]) as usize;
+                let width = u16::from_be_bytes([data[i + 7], data[i + 8]]) as usize;
+                let components = data[i + 9];
+                if width == 0 || height == 0 || !matches!(components, 1 | 3 | 4) {
+                    return None;
+                }
+                return Some(Self { data, width, height, components });
+            }
+            i += 2 + u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        }
+        None
+    }
+}
+
+struct Layout {
+    setup: PageSetup,
+    pages: Vec<Vec<Op>>,
+    logo: Option<Jpeg>,
+}
+
+/// Places body sections onto pages
+struct Body<'a> {
+    setup: PageSetup,
+    style: &'a Style,
+    context: &'a Value,
+    pages: Vec<Vec<Op>>,
+    /// Top of the next block, in mm from the bottom of the page
+    cursor: f32,
+    top: f32,
+    bottom: f32,
+}
+
+impl<'a> Body<'a> {
+    fn left(&self) -> f32 {
+        self.setup.margin_left
+    }
+
+    fn width(&self) -> f32 {
+        self.setup.content_width()
+    }
+
+    fn at_page_top(&self) -> bool {
+        self.cursor >= self.top
+    }
+
+    fn fits(&self, height: f32) -> bool {
+        self.cursor - height >= self.bottom
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.cursor = self.top;
+    }
+
+    /// Start a new page unless `height` fits on this one. A block taller than
+    /// a page is drawn from the top of a fresh page and overflows it.
+    fn reserve(&mut self, height: f32) {
+        if !self.fits(height) && !self.at_page_top() {
+            self.new_page();
+        }
+    }
+
+    fn push(&mut self, op: Op) {
+        if let Some(page) = self.pages.last_mut() {
+            page.push(op);
+        }
+    }
+
+    fn text(&mut self, x: f32, top: f32, size: f32, bold: bool, color: Rgb, text: String) {
+        if !text.is_empty() {
+            self.push(Op::Text { x, y: baseline(top, size), size, bold, color, text });
+        }
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32, bold: bool, color: Rgb) {
+        for line in wrap(text, bold, size, self.width()) {
+            self.reserve(line_height(size));
+            self.text(self.left(), self.cursor, size, bold, color, line);
+            self.cursor -= line_height(size);
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        if !self.at_page_top() {
+            self.cursor -= height;
+        }
+    }
+
+    fn section(&mut self, section: &Value, template: Option<&ReportTemplate>) -> Result<(), String> {
+        let kind = section.get("type").and_then(Value::as_str).unwrap_or_default();
+        let context = self.context;
+        let text = |key: &str| fill(section.get(key).and_then(Value::as_str).unwrap_or_default(), context);
+
+        match kind {
+            "heading" => {
+                let heading = text("text");
+                let size = self.style.heading_size;
+                self.gap(2.0);
+                self.reserve(line_height(size) + line_height(self.style.font_size));
+                self.paragraph(&heading, size, true, self.style.primary_color);
+                self.cursor -= 1.0;
+            }
+            "text" => {
+                let paragraph = text("text");
+                let bold = section.get("bold").and_then(Value::as_bool).unwrap_or(false);
+                let size = section.get("size").and_then(Value::as_f64).map(|s| (s as f32).clamp(4.0, 36.0))
+                    .unwrap_or(self.style.font_size);
+                self.paragraph(&paragraph, size, bold, self.style.text_color);
+                self.cursor -= 1.0;
+            }
+            "fields" => self.fields(section),
+            "results_table" => self.results_table(section),
+            "cumulative_table" => self.cumulative_table(section),
+            "signatures" => self.signatures(section, template),
+            "verification_qr" => self.verification_qr(section)?,
+            "spacer" => {
+                let height = section.get("height").and_then(Value::as_f64).unwrap_or(4.0) as f32;
+                self.cursor -= height.max(0.0);
+                if self.cursor < self.bottom {
+                    self.new_page();
+                }
+            }
+            "page_break" => {
+                if !self.at_page_top() {
+                    self.new_page();
+                }
+            }
+            other => return Err(format!("Unknown template section type '{}'", other)),
+        }
+        Ok(())
+    }
+
+    /// Label/value pairs in columns, skipping blank values
+    fn fields(&mut self, section: &Value) {
+        let fields: Vec<(String, String)> = section
+            .get("fields")
+            .and_then(Value::as_array)
+            .map(|fields| {
+                fields
+                    .iter()
+                    .map(|field| {
+                        let get = |key: &str| field.get(key).and_then(Value::as_str).unwrap_or_default();
+                        (fill(get("label"), self.context), fill(get("value"), self.context))
+                    })
+                    .filter(|(_, value)| !value.trim().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let columns = section.get("columns").and_then(Value::as_u64).unwrap_or(2).clamp(1, 4) as usize;
+        let size = self.style.font_size;
+        let column_width = self.width() / columns as f32;
+        let label_width = column_width * 0.4;
+        let value_width = column_width - label_width - 2.0;
+
+        for row in fields.chunks(columns) {
+            let cells: Vec<(Vec<String>, Vec<String>)> = row
+                .iter()
+                .map(|(label, value)| (wrap(label, true, size, label_width - 1.0), wrap(value, false, size, value_width)))
+                .collect();
+            let lines = cells.iter().map(|(l, v)| l.len().max(v.len())).max().unwrap_or(1);
+            let height = lines as f32 * line_height(size) + 0.8;
+            self.reserve(height);
+
+            for (column, (label, value)) in cells.into_iter().enumerate() {
+                let x = self.left() + column as f32 * column_width;
+                for (i, line) in label.into_iter().enumerate() {
+                    self.text(x, self.cursor - i as f32 * line_height(size), size, true, self.style.muted_color, line);
+                }
+                for (i, line) in value.into_iter().enumerate() {
+                    let top = self.cursor - i as f32 * line_height(size);
+                    self.text(x + label_width, top, size, false, self.style.text_color, line);
+                }
+            }
+            self.cursor -= height;
+        }
+        self.cursor -= 2.0;
+    }
+
+    /// Rows of a table section grouped by its `group_by` field, in order of
+    /// first appearance
+    fn groups(&self, section: &Value, default_source: &str) -> Vec<(String, Vec<&'a Value>)> {
+        let source = section.get("source").and_then(Value::as_str).unwrap_or(default_source);
+        let rows = lookup(self.context, source).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+
+        let group_by = section.get("group_by").and_then(Value::as_str);
+        let mut groups: Vec<(String, Vec<&Value>)> = Vec::new();
+        for row in rows {
+            let group = group_by.and_then(|key| lookup(row, key)).map(display).unwrap_or_default();
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, members)) => members.push(row),
+                None => groups.push((group, vec![row])),
+            }
+        }
+        groups
+    }
+
+    /// Results as a table whose header repeats on every page it spans
+    fn results_table(&mut self, section: &Value) {
+        let columns = table_columns(section, self.width());
+        let groups = self.groups(section, "results");
+
+        let size = self.style.font_size;
+        let header_height = line_height(size) + 2.0 * CELL_PADDING;
+        let group_height = line_height(size) + 2.0 * CELL_PADDING;
+
+        self.gap(1.0);
+        self.reserve(header_height + 2.0 * group_height);
+        self.table_header(&columns);
+
+        if groups.is_empty() {
+            self.text(self.left() + CELL_PADDING, self.cursor - CELL_PADDING, size, false,
+                self.style.muted_color, "No results available".to_string());
+            self.cursor -= group_height;
+        }
+
+        for (group, results) in groups {
+            if !group.is_empty() {
+                if !self.fits(group_height * 2.0) {
+                    self.new_page();
+                    self.table_header(&columns);
+                }
+                self.text(self.left() + CELL_PADDING, self.cursor - CELL_PADDING, size, true,
+                    self.style.primary_color, group);
+                self.cursor -= group_height;
+            }
+
+            for result in results {
+                let severity = severity(result);
+                let cells: Vec<(Vec<String>, bool)> = columns
+                    .iter()
+                    .map(|column| {
+                        let highlighted = severity != Severity::Normal && column.is_highlighted();
+                        (wrap(&cell(result, &column.field), highlighted, size, column.width - 2.0 * CELL_PADDING), highlighted)
+                    })
+                    .collect();
+                let lines = cells.iter().map(|(lines, _)| lines.len()).max().unwrap_or(1);
+                let height = lines as f32 * line_height(size) + 2.0 * CELL_PADDING;
+
+                if !self.fits(height) {
+                    self.new_page();
+                    self.table_header(&columns);
+                }
+
+                let (fill_color, text_color) = match severity {
+                    Severity::Critical => (Some(self.style.critical_fill), self.style.critical_color),
+                    Severity::Abnormal => (Some(self.style.abnormal_fill), self.style.abnormal_color),
+                    Severity::Normal => (None, self.style.text_color),
+                };
+                if let Some(color) = fill_color {
+                    self.push(Op::Rect { x: self.left(), y: self.cursor - height, width: self.width(), height, color });
+                }
+
+                let mut x = self.left();
+                for (column, (lines, highlighted)) in columns.iter().zip(cells) {
+                    let color = if highlighted { text_color } else { self.style.text_color };
+                    for (i, line) in lines.into_iter().enumerate() {
+                        let top = self.cursor - CELL_PADDING - i as f32 * line_height(size);
+                        self.text(x + CELL_PADDING, top, size, highlighted, color, line);
+                    }
+                    x += column.width;
+                }
+
+                self.cursor -= height;
+                self.push(Op::Rule { x: self.left(), y: self.cursor, width: self.width(), color: self.style.rule_color });
+            }
+        }
+        self.cursor -= 3.0;
+    }
+
+    fn table_header(&mut self, columns: &[TableColumn]) {
+        let size = self.style.font_size;
+        let height = line_height(size) + 2.0 * CELL_PADDING;
+        self.push(Op::Rect {
+            x: self.left(),
+            y: self.cursor - height,
+            width: self.width(),
+            height,
+            color: self.style.table_header_fill,
+        });
+        let mut x = self.left();
+        for column in columns {
+            let label = truncate(&column.label, true, size, column.width - 2.0 * CELL_PADDING);
+            self.text(x + CELL_PADDING, self.cursor - CELL_PADDING, size, true, self.style.text_color, label);
+            x += column.width;
+        }
+        self.cursor -= height;
+    }
+
+    /// Analytes against collection dates, with a trend line per analyte
+    fn cumulative_table(&mut self, section: &Value) {
+        let dates: Vec<(String, String)> = section
+            .get("dates")
+            .and_then(Value::as_str)
+            .or(Some("dates"))
+            .and_then(|path| lookup(self.context, path))
+            .and_then(Value::as_array)
+            .map(|dates| dates.iter().map(|date| date_heading(&display(date))).collect())
+            .unwrap_or_default();
+        let groups = self.groups(section, "analytes");
+        let show_trend = section.get("show_trend").and_then(Value::as_bool).unwrap_or(true);
+
+        let size = self.style.font_size;
+        let small = self.style.small_size;
+        let test_width = self.width() * 0.28;
+        let trend_width = if show_trend { self.width() * 0.16 } else { 0.0 };
+        let date_width = (self.width() - test_width - trend_width) / dates.len().max(1) as f32;
+        let group_height = line_height(size) + 2.0 * CELL_PADDING;
+
+        self.gap(1.0);
+        self.reserve(2.0 * line_height(small) + 2.0 * CELL_PADDING + 2.0 * group_height);
+        self.cumulative_header(&dates, test_width, date_width, trend_width);
+
+        if groups.is_empty() || dates.is_empty() {
+            self.text(self.left() + CELL_PADDING, self.cursor - CELL_PADDING, size, false,
+                self.style.muted_color, "No results available".to_string());
+            self.cursor -= group_height;
+        }
+
+        for (group, analytes) in groups {
+            if !group.is_empty() {
+                if !self.fits(group_height * 2.0) {
+                    self.new_page();
+                    self.cumulative_header(&dates, test_width, date_width, trend_width);
+                }
+                self.text(self.left() + CELL_PADDING, self.cursor - CELL_PADDING, size, true,
+                    self.style.primary_color, group);
+                self.cursor -= group_height;
+            }
+
+            for analyte in analytes {
+                let name = wrap(&cell(analyte, "test_name"), false, size, test_width - 2.0 * CELL_PADDING);
+                let unit = cell(analyte, "unit");
+                let range = cell(analyte, "reference_range");
+                let details = match (unit.is_empty(), range.is_empty()) {
+                    (false, false) => format!("{} ({})", unit, range),
+                    (false, true) => unit,
+                    (true, false) => range.clone(),
+                    (true, true) => String::new(),
+                };
+                let details = if details.is_empty() { Vec::new() } else { wrap(&details, false, small, test_width - 2.0 * CELL_PADDING) };
+
+                let values: Vec<Option<&Value>> = (0..dates.len())
+                    .map(|i| analyte.get("values").and_then(|values| values.get(i)).filter(|v| !v.is_null()))
+                    .collect();
+                let cells: Vec<(Vec<String>, Severity)> = values
+                    .iter()
+                    .map(|value| match value {
+                        Some(value) => {
+                            let severity = severity(value);
+                            let text = format!("{} {}", cell(value, "value"), result_flag(value));
+                            (wrap(text.trim(), severity != Severity::Normal, size, date_width - 2.0 * CELL_PADDING), severity)
+                        }
+                        None => (Vec::new(), Severity::Normal),
+                    })
+                    .collect();
+
+                let name_height = name.len() as f32 * line_height(size) + details.len() as f32 * line_height(small);
+                let value_height = cells.iter().map(|(lines, _)| lines.len()).max().unwrap_or(0) as f32 * line_height(size);
+                let trend_height = if show_trend { 8.0 } else { 0.0 };
+                let height = name_height.max(value_height).max(trend_height) + 2.0 * CELL_PADDING;
+
+                if !self.fits(height) {
+                    self.new_page();
+                    self.cumulative_header(&dates, test_width, date_width, trend_width);
+                }
+                let top = self.cursor;
+                let bottom = top - height;
+
+                let mut y = top - CELL_PADDING;
+                for line in name {
+                    self.text(self.left() + CELL_PADDING, y, size, false, self.style.text_color, line);
+                    y -= line_height(size);
+                }
+                for line in details {
+                    self.text(self.left() + CELL_PADDING, y, small, false, self.style.muted_color, line);
+                    y -= line_height(small);
+                }
+
+                for (i, (lines, severity)) in cells.into_iter().enumerate() {
+                    let x = self.left() + test_width + i as f32 * date_width;
+                    let (fill_color, color) = match severity {
+                        Severity::Critical => (Some(self.style.critical_fill), self.style.critical_color),
+                        Severity::Abnormal => (Some(self.style.abnormal_fill), self.style.abnormal_color),
+                        Severity::Normal => (None, self.style.text_color),
+                    };
+                    if let Some(fill_color) = fill_color {
+                        self.push(Op::Rect { x, y: bottom, width: date_width, height, color: fill_color });
+                    }
+                    for (line_index, line) in lines.into_iter().enumerate() {
+                        let line_top = top - CELL_PADDING - line_index as f32 * line_height(size);
+                        self.text(x + CELL_PADDING, line_top, size, severity != Severity::Normal, color, line);
+                    }
+                }
+
+                if show_trend {
+                    let points: Vec<Option<(f64, Severity)>> = values
+                        .iter()
+                        .map(|value| value.and_then(|v| numeric(&cell(v, "value")).map(|n| (n, severity(v)))))
+                        .collect();
+                    let x = self.left() + self.width() - trend_width + CELL_PADDING + 1.0;
+                    let band = parse_range(&range);
+                    self.sparkline(x, bottom + CELL_PADDING + 1.0, trend_width - 2.0 * CELL_PADDING - 2.0,
+                        height - 2.0 * CELL_PADDING - 2.0, &points, band);
+                }
+
+                self.cursor = bottom;
+                self.push(Op::Rule { x: self.left(), y: self.cursor, width: self.width(), color: self.style.rule_color });
+            }
+        }
+        self.cursor -= 3.0;
+    }
+
+    fn cumulative_header(&mut self, dates: &[(String, String)], test_width: f32, date_width: f32, trend_width: f32) {
+        let size = self.style.font_size;
+        let small = self.style.small_size;
+        let height = 2.0 * line_height(small) + 2.0 * CELL_PADDING;
+        self.push(Op::Rect {
+            x: self.left(),
+            y: self.cursor - height,
+            width: self.width(),
+            height,
+            color: self.style.table_header_fill,
+        });
+
+        let top = self.cursor - CELL_PADDING;
+        self.text(self.left() + CELL_PADDING, top, size, true, self.style.text_color, "Test".to_string());
+        for (i, (day, year)) in dates.iter().enumerate() {
+            let x = self.left() + test_width + i as f32 * date_width + CELL_PADDING;
+            let room = date_width - 2.0 * CELL_PADDING;
+            self.text(x, top, small, true, self.style.text_color, truncate(day, true, small, room));
+            self.text(x, top - line_height(small), small, false, self.style.text_color, truncate(year, false, small, room));
+        }
+        if trend_width > 0.0 {
+            let x = self.left() + self.width() - trend_width + CELL_PADDING;
+            self.text(x, top, size, true, self.style.text_color, "Trend".to_string());
+        }
+        self.cursor -= height;
+    }
+
+    /// Values of a row as a line scaled to the box, over the reference range
+    fn sparkline(&mut self, x: f32, y: f32, width: f32, height: f32, values: &[Option<(f64, Severity)>], band: Option<(f64, f64)>) {
+        let points: Vec<(usize, f64, Severity)> = values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| value.map(|(number, severity)| (i, number, severity)))
+            .collect();
+        if points.is_empty() {
+            return;
+        }
+
+        let mut low = points.iter().map(|(_, n, _)| *n).fold(f64::INFINITY, f64::min);
+        let mut high = points.iter().map(|(_, n, _)| *n).fold(f64::NEG_INFINITY, f64::max);
+        if let Some((band_low, band_high)) = band {
+            low = low.min(band_low);
+            high = high.max(band_high);
+        }
+        if (high - low).abs() < f64::EPSILON {
+            low -= 1.0;
+            high += 1.0;
+        }
+
+        let step = if values.len() > 1 { width / (values.len() - 1) as f32 } else { 0.0 };
+        let px = |i: usize| if values.len() > 1 { x + i as f32 * step } else { x + width / 2.0 };
+        let py = |n: f64| y + ((n - low) / (high - low)) as f32 * height;
+
+        if let Some((band_low, band_high)) = band {
+            self.push(Op::Rect {
+                x,
+                y: py(band_low),
+                width,
+                height: py(band_high) - py(band_low),
+                color: self.style.trend_band_fill,
+            });
+        }
+        if points.len() > 1 {
+            self.push(Op::Polyline {
+                points: points.iter().map(|(i, n, _)| (px(*i), py(*n))).collect(),
+                color: self.style.primary_color,
+            });
+        }
+        let marker = 1.0;
+        for (i, n, severity) in points {
+            let color = match severity {
+                Severity::Critical => self.style.critical_color,
+                Severity::Abnormal => self.style.abnormal_color,
+                Severity::Normal => self.style.primary_color,
+            };
+            self.push(Op::Rect { x: px(i) - marker / 2.0, y: py(n) - marker / 2.0, width: marker, height: marker, color });
+        }
+    }
+
+    /// Signature lines side by side
+    fn signatures(&mut self, section: &Value, template: Option<&ReportTemplate>) {
+        let signatories: Vec<(String, String)> = section
+            .get("signatories")
+            .or_else(|| template.and_then(|t| t.signature_fields.as_ref()))
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(label) => (fill(label, self.context), String::new()),
+                        _ => {
+                            let get = |key: &str| item.get(key).and_then(Value::as_str);
+                            let label = get("label").or_else(|| get("title")).or_else(|| get("role")).unwrap_or_default();
+                            (fill(label, self.context), fill(get("name").unwrap_or_default(), self.context))
+                        }
+                    })
+                    .collect()
+            })
+            .filter(|signatories: &Vec<_>| !signatories.is_empty())
+            .unwrap_or_else(|| vec![("Authorized Signatory".to_string(), String::new())]);
+
+        let size = self.style.font_size;
+        let space = 14.0;
+        let height = space + 2.0 * line_height(size) + 2.0;
+        self.gap(4.0);
+        self.reserve(height);
+
+        let column_width = self.width() / signatories.len() as f32;
+        for (i, (label, name)) in signatories.into_iter().enumerate() {
+            let x = self.left() + i as f32 * column_width;
+            let line_width = (column_width - 8.0).max(10.0);
+            self.push(Op::Rule { x, y: self.cursor - space, width: line_width, color: self.style.text_color });
+            let top = self.cursor - space - 1.0;
+            self.text(x, top, size, true, self.style.text_color, truncate(&label, true, size, line_width));
+            self.text(x, top - line_height(size), size, false, self.style.muted_color, truncate(&name, false, size, line_width));
+        }
+        self.cursor -= height;
+    }
+
+    /// QR code of the report's verification link at the right margin, with a
+    /// caption beside it. Drawn black on white whatever the style, so phones
+    /// read it from photocopies too.
+    fn verification_qr(&mut self, section: &Value) -> Result<(), String> {
+        let Some(link) = lookup(self.context, "verification.url").and_then(Value::as_str) else {
+            return Ok(());
+        };
+        let code = QrCode::encode(link.as_bytes())
+            .map_err(|e| format!("Verification link does not fit in a QR code: {}", e))?;
+
+        let module = section.get("module_size").and_then(Value::as_f64).map(|size| (size as f32).clamp(0.3, 1.0))
+            .unwrap_or(QR_MODULE_SIZE);
+        let quiet = QR_QUIET_ZONE as f32 * module;
+        let side = code.size() as f32 * module + 2.0 * quiet;
+        self.gap(4.0);
+        self.reserve(side);
+
+        let x = self.left() + self.width() - side;
+        let top = self.cursor;
+        self.push(Op::Rect { x, y: top - side, width: side, height: side, color: Rgb(1.0, 1.0, 1.0) });
+        for row in 0..code.size() {
+            // One rectangle per run of dark modules
+            let mut column = 0;
+            while column < code.size() {
+                if !code.is_dark(column, row) {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < code.size() && code.is_dark(column, row) {
+                    column += 1;
+                }
+                self.push(Op::Rect {
+                    x: x + quiet + start as f32 * module,
+                    y: top - quiet - (row + 1) as f32 * module,
+                    width: (column - start) as f32 * module,
+                    height: module,
+                    color: Rgb(0.0, 0.0, 0.0),
+                });
+            }
+        }
+
+        let caption = fill(
+            section.get("caption").and_then(Value::as_str).unwrap_or("Verify this report"),
+            self.context,
+        );
+        let note = fill(
+            section.get("text").and_then(Value::as_str)
+                .unwrap_or("Scan the code to confirm with the laboratory that this report is genuine and unaltered."),
+            self.context,
+        );
+        let text_width = (self.width() - side - 4.0).max(20.0);
+        let (size, small) = (self.style.font_size, self.style.small_size);
+        let mut cursor = top - quiet;
+        for line in wrap(&caption, true, size, text_width) {
+            self.text(self.left(), cursor, size, true, self.style.text_color, line);
+            cursor -= line_height(size);
+        }
+        for line in wrap(&note, false, small, text_width) {
+            self.text(self.left(), cursor, small, false, self.style.muted_color, line);
+            cursor -= line_height(small);
+        }
+        self.cursor = cursor.min(top - side);
+        Ok(())
+    }
+}
+
+struct TableColumn {
+    label: String,
+    field: String,
+    width: f32,
+}
+
+impl TableColumn {
+    /// Columns coloured for abnormal results
+    fn is_highlighted(&self) -> bool {
+        matches!(self.field.as_str(), "result_value" | "flag")
+    }
+}
+
+/// Columns of a results table with widths in mm
+fn table_columns(section: &Value, table_width: f32) -> Vec<TableColumn> {
+    let configured: Vec<(String, String, f32)> = section
+        .get("columns")
+        .and_then(Value::as_array)
+        .map(|columns| {
+            columns
+                .iter()
+                .filter_map(|column| {
+                    let field = column.get("field").and_then(Value::as_str)?;
+                    let label = column.get("label").and_then(Value::as_str).unwrap_or(field);
+                    let width = column.get("width").and_then(Value::as_f64).unwrap_or(1.0).max(0.01) as f32;
+                    Some((label.to_string(), field.to_string(), width))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let columns = if configured.is_empty() {
+        [
+            ("Test", "test_name", 0.36),
+            ("Result", "result_value", 0.16),
+            ("Unit", "result_unit", 0.12),
+            ("Reference Range", "reference_range", 0.26),
+            ("Flag", "flag", 0.10),
+        ]
+        .into_iter()
+        .map(|(label, field, width)| (label.to_string(), field.to_string(), width))
+        .collect()
+    } else {
+        configured
+    };
+
+    // Widths are relative
+    let total: f32 = columns.iter().map(|(_, _, width)| width).sum();
+    columns
+        .into_iter()
+        .map(|(label, field, width)| TableColumn { label, field, width: width / total * table_width })
+        .collect()
+}
+
+/// Body sections used without a template
+fn default_sections(report: &GeneratedReport) -> Vec<Value> {
+    let mut sections = match report.report_type {
+        ReportTemplateType::CumulativeReport => vec![
+            json!({
+                "type": "fields",
+                "fields": [
+                    {"label": "Report No.", "value": "{{report.report_number}}"},
+                    {"label": "Report Date", "value": "{{report.report_date}}"},
+                    {"label": "Patient ID", "value": "{{patient.id}}"},
+                ],
+            }),
+            json!({"type": "cumulative_table", "group_by": "department"}),
+        ],
+        _ => vec![
+            json!({
+                "type": "fields",
+                "fields": [
+                    {"label": "Report No.", "value": "{{report.report_number}}"},
+                    {"label": "Report Date", "value": "{{report.report_date}}"},
+                    {"label": "Order No.", "value": "{{order.order_number}}"},
+                    {"label": "Patient ID", "value": "{{order.patient_id}}"},
+                ],
+            }),
+            json!({"type": "results_table", "source": "results", "group_by": "department"}),
+        ],
+    };
+    if report.requires_signature.unwrap_or(false) {
+        sections.push(json!({"type": "signatures"}));
+    }
+    sections
+}
+
+fn header_logo(template: Option<&ReportTemplate>, header: Option<&Value>) -> Option<Jpeg> {
+    if !template.and_then(|t| t.show_logo).unwrap_or(true) {
+        return None;
+    }
+    let encoded = header?.get("logo")?.as_str()?;
+    // Accept data URLs as well as bare base64
+    let encoded = encoded.split_once("base64,").map(|(_, data)| data).unwrap_or(encoded);
+    let logo = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(Jpeg::parse);
+    if logo.is_none() {
+        tracing::warn!("Report template logo is not a base64 JPEG image; it is left out");
+    }
+    logo
+}
+
+fn layout(
+    report: &GeneratedReport,
+    data: &Value,
+    template: Option<&ReportTemplate>,
+    verification_url: Option<&str>,
+) -> Result<Layout, String> {
+    let setup = PageSetup::new(template);
+    let style = Style::new(template.and_then(|t| t.styles.as_ref()));
+    let context = context(report, data, verification_url);
+    let left = setup.margin_left;
+    let width = setup.content_width();
+
+    // Header: logo on the left, title and lines beside it
+    let header_content = template.and_then(|t| t.header_content.as_ref());
+    let logo = header_logo(template, header_content);
+    let mut header = Vec::new();
+    let header_top = setup.height - setup.margin_top;
+    let mut text_x = left;
+    let mut logo_bottom = header_top;
+    if let Some(logo) = &logo {
+        let height = style.logo_height;
+        let logo_width = height * logo.width as f32 / logo.height as f32;
+        header.push(Op::Logo { x: left, y: header_top - height, width: logo_width, height });
+        text_x += logo_width + 4.0;
+        logo_bottom = header_top - height;
+    }
+
+    let (title, lines) = match header_content {
+        Some(Value::String(title)) => (fill(title, &context), Vec::new()),
+        Some(header) => (
+            header.get("title").and_then(Value::as_str).map(|t| fill(t, &context)).unwrap_or_default(),
+            header.get("lines").and_then(Value::as_array)
+                .map(|lines| lines.iter().filter_map(Value::as_str).map(|l| fill(l, &context)).collect())
+                .unwrap_or_default(),
+        ),
+        None => (String::new(), Vec::new()),
+    };
+    let title = if title.is_empty() { report.report_title.clone() } else { title };
+
+    let title_width = left + width - text_x;
+    let mut cursor = header_top;
+    for line in wrap(&title, true, style.title_size, title_width) {
+        header.push(Op::Text {
+            x: text_x,
+            y: baseline(cursor, style.title_size),
+            size: style.title_size,
+            bold: true,
+            color: style.primary_color,
+            text: line,
+        });
+        cursor -= line_height(style.title_size);
+    }
+    for line in lines.iter().flat_map(|line| wrap(line, false, style.small_size, title_width)) {
+        header.push(Op::Text {
+            x: text_x,
+            y: baseline(cursor, style.small_size),
+            size: style.small_size,
+            bold: false,
+            color: style.muted_color,
+            text: line,
+        });
+        cursor -= line_height(style.small_size);
+    }
+    let header_bottom = cursor.min(logo_bottom) - 1.5;
+    header.push(Op::Rule { x: left, y: header_bottom, width, color: style.primary_color });
+
+    // Body
+    let sections = match template.and_then(|t| t.template_content.get("sections")) {
+        Some(Value::Array(sections)) => sections.clone(),
+        Some(_) => return Err("Template sections must be an array".to_string()),
+        None => default_sections(report),
+    };
+    let mut body = Body {
+        setup,
+        style: &style,
+        context: &context,
+        pages: vec![Vec::new()],
+        cursor: header_bottom - HEADER_GAP,
+        top: header_bottom - HEADER_GAP,
+        bottom: setup.margin_bottom + FOOTER_HEIGHT + 2.0,
+    };
+    if body.top - body.bottom < 20.0 {
+        return Err("Page margins and header leave no room for the report body".to_string());
+    }
+    for section in &sections {
+        body.section(section, template)?;
+    }
+    let placed = sections.iter().any(|s| s.get("type").and_then(Value::as_str) == Some("verification_qr"));
+    if verification_url.is_some() && !placed {
+        body.section(&json!({"type": "verification_qr"}), template)?;
+    }
+    let pages = body.pages;
+
+    // Footer
+    let footer_content = template.and_then(|t| t.footer_content.as_ref());
+    let (footer_text, page_numbers) = match footer_content {
+        Some(Value::String(text)) => (text.clone(), true),
+        Some(footer) => (
+            footer.get("text").and_then(Value::as_str).unwrap_or_default().to_string(),
+            footer.get("show_page_numbers").and_then(Value::as_bool).unwrap_or(true),
+        ),
+        None => ("Report No. {{report.report_number}}".to_string(), true),
+    };
+    let footer_text = fill(&footer_text, &context);
+    let footer_baseline = setup.margin_bottom + 2.0;
+
+    // Watermark, centred on the page
+    let watermark = template
+        .filter(|t| t.show_watermark.unwrap_or(false))
+        .and_then(|t| t.watermark_text.as_deref())
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| {
+            let (sin, cos) = WATERMARK_ANGLE.to_radians().sin_cos();
+            let half_width = text_width(text, true, style.watermark_size) / 2.0;
+            let half_cap = style.watermark_size * 0.7 * PT_TO_MM / 2.0;
+            Op::Watermark {
+                x: setup.width / 2.0 - half_width * cos + half_cap * sin,
+                y: setup.height / 2.0 - half_width * sin - half_cap * cos,
+                size: style.watermark_size,
+                color: style.watermark_color,
+                text: text.to_string(),
+            }
+        });
+
+    let total = pages.len();
+    let pages = pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let mut ops: Vec<Op> = watermark.iter().cloned().collect();
+            ops.extend(header.iter().cloned());
+            ops.extend(body);
+
+            ops.push(Op::Rule { x: left, y: footer_baseline + FOOTER_HEIGHT - 4.0, width, color: style.rule_color });
+            let mut text_room = width;
+            if page_numbers {
+                let number = format!("Page {} of {}", index + 1, total);
+                let number_width = text_width(&number, false, style.small_size);
+                text_room -= number_width + 4.0;
+                ops.push(Op::Text {
+                    x: left + width - number_width,
+                    y: footer_baseline,
+                    size: style.small_size,
+                    bold: false,
+                    color: style.muted_color,
+                    text: number,
+                });
+            }
+            if !footer_text.is_empty() {
+                ops.push(Op::Text {
+                    x: left,
+                    y: footer_baseline,
+                    size: style.small_size,
+                    bold: false,
+                    color: style.muted_color,
+                    text: truncate(&footer_text, false, style.small_size, text_room),
+                });
+            }
+            ops
+        })
+        .collect();
+
+    Ok(Layout { setup, pages, logo })
+}
+
+// ============================================================================
+// Drawing
+// ============================================================================
+
+fn color(rgb: Rgb) -> Color {
+    Color::Rgb(printpdf::Rgb::new(rgb.0, rgb.1, rgb.2, None))
+}
+
+fn write(report: &GeneratedReport, layout: &Layout) -> Result<Vec<u8>, String> {
+    let setup = layout.setup;
+    let (doc, first_page, first_layer) =
+        PdfDocument::new(report.report_title.as_str(), Mm(setup.width), Mm(setup.height), "Report");
+    let doc = doc.with_document_id(report.id.simple().to_string());
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|e| e.to_string())?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|e| e.to_string())?;
+
+    for (index, ops) in layout.pages.iter().enumerate() {
+        let layer = if index == 0 {
+            doc.get_page(first_page).get_layer(first_layer)
+        } else {
+            let (page, layer) = doc.add_page(Mm(setup.width), Mm(setup.height), "Report");
+            doc.get_page(page).get_layer(layer)
+        };
+        for op in ops {
+            draw(&layer, op, &regular, &bold, layout.logo.as_ref());
+        }
+    }
+
+    doc.save_to_bytes().map_err(|e| format!("Failed to write PDF: {}", e))
+}
+
+fn draw(layer: &PdfLayerReference, op: &Op, regular: &IndirectFontRef, bold: &IndirectFontRef, logo: Option<&Jpeg>) {
+    match op {
+        Op::Text { x, y, size, bold: is_bold, color: rgb, text } => {
+            layer.set_fill_color(color(*rgb));
+            layer.use_text(text.as_str(), *size, Mm(*x), Mm(*y), if *is_bold { bold } else { regular });
+        }
+        Op::Rect { x, y, width, height, color: rgb } => {
+            layer.set_fill_color(color(*rgb));
+            layer.add_rect(Rect::new(Mm(*x), Mm(*y), Mm(x + width), Mm(y + height)).with_mode(PaintMode::Fill));
+        }
+        Op::Rule { x, y, width, color: rgb } => {
+            layer.set_outline_color(color(*rgb));
+            layer.set_outline_thickness(0.5);
+            layer.add_line(Line {
+                points: vec![(Point::new(Mm(*x), Mm(*y)), false), (Point::new(Mm(x + width), Mm(*y)), false)],
+                is_closed: false,
+            });
+        }
+        Op::Polyline { points, color: rgb } => {
+            layer.set_outline_color(color(*rgb));
+            layer.set_outline_thickness(0.75);
+            layer.add_line(Line {
+                points: points.iter().map(|(x, y)| (Point::new(Mm(*x), Mm(*y)), false)).collect(),
+                is_closed: false,
+            });
+        }
+        Op::Logo { x, y, width, height } => {
+            let Some(logo) = logo else { return };
+            let dpi = 300.0;
+            let natural_width = logo.width as f32 / dpi * 25.4;
+            let natural_height = logo.height as f32 / dpi * 25.4;
+            let image = ImageXObject {
+                width: Px(logo.width),
+                height: Px(logo.height),
+                color_space: match logo.components {
+                    1 => printpdf::ColorSpace::Greyscale,
+                    4 => printpdf::ColorSpace::Cmyk,
+                    _ => printpdf::ColorSpace::Rgb,
+                },
+                bits_per_component: printpdf::ColorBits::Bit8,
+                interpolate: true,
+                image_data: logo.data.clone(),
+                image_filter: Some(ImageFilter::DCT),
+                smask: None,
+                clipping_bbox: None,
+            };
+            Image::from(image).add_to_layer(layer.clone(), ImageTransform {
+                translate_x: Some(Mm(*x)),
+                translate_y: Some(Mm(*y)),
+                rotate: None,
+                scale_x: Some(width / natural_width),
+                scale_y: Some(height / natural_height),
+                dpi: Some(dpi),
+            });
+        }
+        Op::Watermark { x, y, size, color: rgb, text } => {
+            layer.save_graphics_state();
+            layer.set_fill_color(color(*rgb));
+            layer.begin_text_section();
+            layer.set_font(bold, *size);
+            layer.set_text_matrix(TextMatrix::TranslateRotate(Mm(*x).into_pt(), Mm(*y).into_pt(), WATERMARK_ANGLE));
+            layer.write_text(text.as_str(), bold);
+            layer.end_text_section();
+            layer.restore_graphics_state();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ReportStatus, ReportTemplateType};
+    use uuid::Uuid;
+
+    fn report() -> GeneratedReport {
+        let now = chrono::Local::now().naive_local();
+        GeneratedReport {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            template_id: None,
+            report_number: "RPT-20250115-0001".to_string(),
+            report_title: "Laboratory Report ORD-1".to_string(),
+            report_type: ReportTemplateType::PatientReport,
+            patient_id: None,
+            order_id: None,
+            result_id: None,
+            batch_id: None,
+            report_data: Value::Null,
+            generated_content: None,
+            report_format: None,
+            file_path: None,
+            file_size_bytes: None,
+            file_hash: None,
+            storage_location: None,
+            report_date: now.date(),
+            generated_at: None,
+            expires_at: None,
+            report_status: ReportStatus::Generating,
+            error_message: None,
+            is_confidential: None,
+            access_code: None,
+            download_count: None,
+            last_downloaded_at: None,
+            requires_signature: Some(true),
+            is_signed: None,
+            signed_at: None,
+            signed_by: None,
+            created_by: Uuid::new_v4(),
+            created_at: now,
+            updated_by: None,
+            updated_at: None,
+            is_deleted: None,
+        }
+    }
+
+    fn template(content: Value) -> ReportTemplate {
+        ReportTemplate {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            template_name: "Patient".to_string(),
+            template_code: "PATIENT".to_string(),
+            template_type: ReportTemplateType::PatientReport,
+            description: None,
+            template_content: content,
+            header_content: None,
+            footer_content: None,
+            styles: None,
+            page_size: Some("A4".to_string()),
+            page_orientation: Some("PORTRAIT".to_string()),
+            margin_top: Some(10),
+            margin_bottom: Some(10),
+            margin_left: Some(10),
+            margin_right: Some(10),
+            fields_config: None,
+            sections_config: None,
+            show_logo: Some(true),
+            show_watermark: Some(false),
+            watermark_text: None,
+            requires_signature: Some(false),
+            signature_fields: None,
+            default_format: None,
+            enable_auto_delivery: None,
+            auto_delivery_channels: None,
+            is_active: Some(true),
+            is_default: Some(true),
+            version: Some(1),
+            created_by: Uuid::new_v4(),
+            created_at: chrono::Local::now().naive_local(),
+            updated_by: None,
+            updated_at: None,
+            is_deleted: None,
+        }
+    }
+
+    fn result(test_name: &str, value: &str, interpretation: &str) -> Value {
+        json!({
+            "test_code": test_name.to_uppercase(),
+            "test_name": test_name,
+            "department": "Biochemistry",
+            "result_value": value,
+            "result_unit": "mg/dL",
+            "reference_range_min": "70",
+            "reference_range_max": "110",
+            "interpretation": interpretation,
+            "is_abnormal": interpretation != "Normal",
+            "is_critical": interpretation.starts_with("Critical"),
+        })
+    }
+
+    fn texts(page: &[Op]) -> Vec<&str> {
+        page.iter()
+            .filter_map(|op| match op {
+                Op::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_results_table_spans_pages_with_page_x_of_y() {
+        let results: Vec<Value> = (0..120).map(|i| result(&format!("Analyte {}", i), "90", "Normal")).collect();
+        let data = json!({"order": {"order_number": "ORD-1"}, "results": results});
+        let report = report();
+
+        let layout = layout(&report, &data, None, None).unwrap();
+        let total = layout.pages.len();
+        assert!(total > 2);
+        for (index, page) in layout.pages.iter().enumerate() {
+            let texts = texts(page);
+            assert!(texts.contains(&format!("Page {} of {}", index + 1, total).as_str()));
+            // Column headings repeat on every page
+            assert!(texts.contains(&"Reference Range"));
+            assert!(texts.contains(&"Laboratory Report ORD-1"));
+        }
+        assert!(texts(&layout.pages[0]).contains(&"70 - 110"));
+        assert!(texts(&layout.pages[total - 1]).contains(&"Authorized Signatory"));
+
+        // Nothing is drawn over the footer
+        let footer_top = layout.setup.margin_bottom + FOOTER_HEIGHT;
+        for page in &layout.pages {
+            for op in page {
+                if let Op::Text { y, text, .. } = op {
+                    if !text.starts_with("Page ") && !text.starts_with("Report No.") {
+                        assert!(*y > footer_top, "{} drawn at {}", text, y);
+                    }
+                }
+            }
+        }
+
+        let bytes = render(&report, &data, None, None).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+        let pdf = printpdf::lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(pdf.get_pages().len(), total);
+    }
+
+    #[test]
+    fn test_abnormal_and_critical_results_are_highlighted() {
+        let data = json!({"results": [
+            result("Glucose", "95", "Normal"),
+            result("Urea", "140", "AbnormalHigh"),
+            result("Potassium", "2.1", "CRITICAL_LOW"),
+        ]});
+        let style = Style::default();
+
+        let layout = layout(&report(), &data, None, None).unwrap();
+        let page = &layout.pages[0];
+        let fills: Vec<Rgb> = page
+            .iter()
+            .filter_map(|op| match op {
+                Op::Rect { color, .. } => Some(*color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fills, vec![style.table_header_fill, style.abnormal_fill, style.critical_fill]);
+
+        let styled = |wanted: &str| {
+            page.iter().find_map(|op| match op {
+                Op::Text { text, bold, color, .. } if text == wanted => Some((*bold, *color)),
+                _ => None,
+            })
+        };
+        assert_eq!(styled("95"), Some((false, style.text_color)));
+        assert_eq!(styled("140"), Some((true, style.abnormal_color)));
+        assert_eq!(styled("H"), Some((true, style.abnormal_color)));
+        assert_eq!(styled("2.1"), Some((true, style.critical_color)));
+        assert_eq!(styled("LL"), Some((true, style.critical_color)));
+        assert_eq!(styled("Biochemistry"), Some((true, style.primary_color)));
+    }
+
+    #[test]
+    fn test_template_drives_page_header_footer_and_watermark() {
+        let mut template = template(json!({"sections": [
+            {"type": "heading", "text": "Results for {{order.order_number}}"},
+            {"type": "fields", "columns": 1, "fields": [
+                {"label": "Referred by", "value": "{{order.referring_doctor}}"},
+                {"label": "Missing", "value": "{{order.nothing}}"},
+            ]},
+            {"type": "results_table", "columns": [
+                {"label": "Analyte", "field": "test_code", "width": 2},
+                {"label": "Value", "field": "result_value", "width": 1},
+            ]},
+            {"type": "signatures", "signatories": [{"label": "Pathologist", "name": "Dr. A. Rao"}]},
+        ]}));
+        template.page_size = Some("Letter".to_string());
+        template.page_orientation = Some("LANDSCAPE".to_string());
+        template.margin_left = Some(25);
+        template.header_content = Some(json!({"title": "City Diagnostics", "lines": ["12 Main Street"]}));
+        template.footer_content = Some(json!({"text": "Confidential {{report.report_number}}", "show_page_numbers": false}));
+        template.styles = Some(json!({"primary_color": "#003366"}));
+        template.show_watermark = Some(true);
+        template.watermark_text = Some("COPY".to_string());
+        let data = json!({
+            "order": {"order_number": "ORD-7", "referring_doctor": "Dr. Mehta"},
+            "results": [result("Glucose", "95", "Normal")],
+        });
+
+        let layout = layout(&report(), &data, Some(&template), None).unwrap();
+        assert_eq!((layout.setup.width, layout.setup.height), (279.4, 215.9));
+        assert_eq!(layout.pages.len(), 1);
+
+        let page = &layout.pages[0];
+        assert!(matches!(&page[0], Op::Watermark { text, .. } if text == "COPY"));
+        let texts = texts(page);
+        for expected in ["City Diagnostics", "12 Main Street", "Results for ORD-7", "Dr. Mehta", "Analyte",
+            "GLUCOSE", "Pathologist", "Dr. A. Rao", "Confidential RPT-20250115-0001"] {
+            assert!(texts.contains(&expected), "missing {}", expected);
+        }
+        assert!(!texts.contains(&"Missing"));
+        assert!(!texts.iter().any(|text| text.starts_with("Page ")));
+        assert!(page.iter().any(|op| matches!(op, Op::Text { x, text, color, .. }
+            if text == "City Diagnostics" && *x == 25.0 && *color == Rgb(0.0, 0.2, 0.4))));
+
+        template.template_content = json!({"sections": [{"type": "chart"}]});
+        assert!(layout_error(&template).contains("chart"));
+        assert!(validate_sections(&template.template_content).is_err());
+        assert!(validate_sections(&json!({"sections": {"type": "text"}})).is_err());
+        assert!(validate_sections(&json!({"sections": [{"type": "page_break"}]})).is_ok());
+    }
+
+    fn layout_error(template: &ReportTemplate) -> String {
+        layout(&report(), &json!({}), Some(template), None).err().unwrap()
+    }
+
+    #[test]
+    fn test_cumulative_table_aligns_values_and_draws_trends() {
+        let value = |value: &str, interpretation: &str| json!({
+            "value": value,
+            "interpretation": interpretation,
+            "is_abnormal": interpretation != "NORMAL",
+            "is_critical": false,
+        });
+        let data = json!({
+            "patient": {"id": "patient-1"},
+            "dates": ["2025-01-01", "2025-01-03", "2025-01-05"],
+            "analytes": [
+                {"test_name": "Glucose", "department": "Biochemistry", "unit": "mg/dL", "reference_range_text": "70 - 110",
+                 "values": [value("95", "NORMAL"), null, value("131", "ABNORMAL_HIGH")]},
+                {"test_name": "HbA1c", "department": "Biochemistry", "unit": "%", "reference_range_text": "4.0-5.6",
+                 "values": [null, value("6.1", "ABNORMAL_HIGH"), null]},
+            ],
+        });
+        let mut report = report();
+        report.report_type = ReportTemplateType::CumulativeReport;
+        report.requires_signature = Some(false);
+        let style = Style::default();
+
+        let layout = layout(&report, &data, None, None).unwrap();
+        let page = &layout.pages[0];
+        let texts = texts(page);
+        for expected in ["patient-1", "Test", "01 Jan", "05 Jan", "2025", "Trend", "Biochemistry", "Glucose", "mg/dL (70 - 110)", "95"] {
+            assert!(texts.contains(&expected), "missing {}", expected);
+        }
+        let styled = |wanted: &str| {
+            page.iter().find_map(|op| match op {
+                Op::Text { x, text, bold, color, .. } if text == wanted => Some((*x, *bold, *color)),
+                _ => None,
+            })
+        };
+        let (first_x, _, _) = styled("95").unwrap();
+        let (last_x, bold, color) = styled("131 H").unwrap();
+        let (middle_x, _, _) = styled("6.1 H").unwrap();
+        assert!(first_x < middle_x && middle_x < last_x);
+        assert!(bold && color == style.abnormal_color);
+
+        // One line for glucose; a single HbA1c value gets a marker only
+        let lines: Vec<&Vec<(f32, f32)>> = page
+            .iter()
+            .filter_map(|op| match op {
+                Op::Polyline { points, .. } => Some(points),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), 2);
+        assert!(lines[0][1].1 > lines[0][0].1);
+        let bands = page.iter().filter(|op| matches!(op, Op::Rect { color, .. } if *color == style.trend_band_fill)).count();
+        assert_eq!(bands, 2);
+
+        assert_eq!(parse_range("70 - 110 mg/dL"), Some((70.0, 110.0)));
+        assert_eq!(parse_range("< 200"), None);
+        assert_eq!(render(&report, &data, None, None).map(|bytes| bytes.starts_with(b"%PDF")), Ok(true));
+    }
+
+    #[test]
+    fn test_verification_qr_code_follows_the_body() {
+        let link = "https://lab.example.com/verify?r=RPT-20250115-0001&c=K7M2QX9P&s=8f1Xv3kQ2m9LrT0aYb4WzA";
+        let data = json!({"results": [result("Glucose", "95", "Normal")]});
+        let black = Rgb(0.0, 0.0, 0.0);
+        let modules = |page: &[Op]| -> (f32, f32) {
+            page.iter()
+                .filter_map(|op| match op {
+                    Op::Rect { y, width, height, color, .. } if *color == black => Some((*y, width * height)),
+                    _ => None,
+                })
+                .fold((f32::MAX, 0.0), |(lowest, area), (y, rect)| (lowest.min(y), area + rect))
+        };
+
+        let appended = layout(&report(), &data, None, Some(link)).unwrap();
+        let page = appended.pages.last().unwrap();
+        let code = QrCode::encode(link.as_bytes()).unwrap();
+        let dark = (0..code.size()).flat_map(|y| (0..code.size()).map(move |x| (x, y))).filter(|(x, y)| code.is_dark(*x, *y)).count();
+        let (lowest, area) = modules(page);
+        assert!((area - dark as f32 * QR_MODULE_SIZE * QR_MODULE_SIZE).abs() < 0.01);
+        assert!(lowest > appended.setup.margin_bottom + FOOTER_HEIGHT);
+        assert!(texts(page).contains(&"Verify this report"));
+
+        // A template places it, once
+        let template = template(json!({"sections": [
+            {"type": "verification_qr", "caption": "Check {{report.report_number}}"},
+            {"type": "results_table"},
+        ]}));
+        let placed = layout(&report(), &data, Some(&template), Some(link)).unwrap();
+        let page = &placed.pages[0];
+        assert!(texts(page).contains(&"Check RPT-20250115-0001"));
+        assert!(!texts(page).contains(&"Verify this report"));
+        assert!((modules(page).1 - area).abs() < 0.01);
+
+        let without = layout(&report(), &data, Some(&template), None).unwrap();
+        assert_eq!(modules(&without.pages[0]).1, 0.0);
+        assert!(layout(&report(), &data, None, Some(&"x".repeat(300))).is_err());
+    }
+
+    #[test]
+    fn test_logo_is_read_from_a_base64_jpeg() {
+        // SOI, an APP0 segment, then a baseline frame header of 64x32 RGB
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend([0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x20, 0x00, 0x40, 0x03]);
+        jpeg.extend([0u8; 9]);
+        jpeg.extend([0xFF, 0xD9]);
+
+        let mut template = template(json!({"sections": []}));
+        template.header_content = Some(json!({
+            "logo": format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(&jpeg)),
+        }));
+        let layout = layout(&report(), &json!({}), Some(&template), None).unwrap();
+        let logo = layout.logo.as_ref().unwrap();
+        assert_eq!((logo.width, logo.height, logo.components), (64, 32, 3));
+        assert!(layout.pages[0].iter().any(|op| matches!(op, Op::Logo { width, height, .. }
+            if *height == 15.0 && *width == 30.0)));
+
+        template.show_logo = Some(false);
+        assert!(super::layout(&report(), &json!({}), Some(&template), None).unwrap().logo.is_none());
+        assert!(Jpeg::parse(b"\x89PNG\r\n\x1a\n".to_vec()).is_none());
+    }
+
+    #[test]
+    fn test_wrap_keeps_lines_within_width() {
+        let text = "Hemoglobin A1c estimated average glucose Supercalifragilisticexpialidocious";
+        let lines = wrap(text, false, 9.0, 30.0);
+        assert!(lines.len() > 2);
+        assert!(lines.iter().all(|line| text_width(line, false, 9.0) <= 30.0));
+        assert_eq!(lines.join("").replace(' ', ""), text.replace(' ', ""));
+        assert_eq!(fill("{{a.0.b}} and {{ c }}", &json!({"a": [{"b": 1}], "c": "x"})), "1 and x");
+    }
+}
@@ -0,0 +1,494 @@
+//! QR codes printed on reports.
+//!
+//! A small encoder of ISO/IEC 18004 symbols in byte mode at error correction
+//! level M, versions 1 to 10. That holds up to 213 bytes, plenty for a
+//! verification link, and survives a creased or smudged printout.
+
+/// Largest version encoded
+const MAX_VERSION: usize = 10;
+
+/// Level M error correction per version: codewords per block, and the
+/// (block count, data codewords per block) of both block groups
+const BLOCKS: [(usize, [(usize, usize); 2]); MAX_VERSION] = [
+    (10, [(1, 16), (0, 0)]),
+    (16, [(1, 28), (0, 0)]),
+    (26, [(1, 44), (0, 0)]),
+    (18, [(2, 32), (0, 0)]),
+    (24, [(2, 43), (0, 0)]),
+    (16, [(4, 27), (0, 0)]),
+    (18, [(4, 31), (0, 0)]),
+    (22, [(2, 38), (2, 39)]),
+    (22, [(3, 36), (2, 37)]),
+    (26, [(4, 43), (1, 44)]),
+];
+
+/// Centre rows and columns of the alignment patterns per version
+const ALIGNMENT: [&[usize]; MAX_VERSION] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+
+/// Level M in the format information
+const LEVEL_M: u32 = 0b00;
+
+/// A QR code symbol, without its quiet zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode bytes in the smallest version that holds them
+    pub fn encode(data: &[u8]) -> Result<Self, String> {
+        let version = (1..=MAX_VERSION)
+            .find(|&version| data.len() <= capacity(version))
+            .ok_or_else(|| format!("{} bytes do not fit in a QR code of version {}", data.len(), MAX_VERSION))?;
+        let codewords = interleave(version, &data_codewords(version, data));
+
+        let mut matrix = Matrix::new(version);
+        matrix.draw_function_patterns();
+        for ((x, y), bit) in matrix.data_positions().into_iter().zip(bits(&codewords)) {
+            matrix.modules[y * matrix.size + x] = bit;
+        }
+
+        // The mask that leaves the fewest patterns confusing a reader
+        let (_, matrix) = (0..8)
+            .map(|mask| {
+                let mut candidate = matrix.clone();
+                candidate.apply_mask(mask);
+                candidate.draw_format(mask);
+                (candidate.penalty(), candidate)
+            })
+            .min_by_key(|(penalty, _)| *penalty)
+            .ok_or("No QR mask")?;
+
+        Ok(Self { size: matrix.size, modules: matrix.modules })
+    }
+
+    /// Modules along each side
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module in column `x` of row `y`, counted from the top
+    /// left, is dark
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+}
+
+/// Bytes a version holds in byte mode
+fn capacity(version: usize) -> usize {
+    (total_data_codewords(version) * 8 - 4 - count_bits(version)) / 8
+}
+
+fn total_data_codewords(version: usize) -> usize {
+    BLOCKS[version - 1].1.iter().map(|(blocks, length)| blocks * length).sum()
+}
+
+/// Width of the byte count
+fn count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+fn bits(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    bytes.iter().flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+}
+
+/// Mode, count, data, terminator and padding, as codewords
+fn data_codewords(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = total_data_codewords(version) * 8;
+    let mut buffer: Vec<bool> = Vec::with_capacity(capacity);
+    let mut push = |value: usize, width: usize| buffer.extend((0..width).rev().map(|i| value >> i & 1 == 1));
+
+    push(0b0100, 4);
+    push(data.len(), count_bits(version));
+    for byte in data {
+        push(*byte as usize, 8);
+    }
+    let terminator = (capacity - buffer.len()).min(4);
+    buffer.extend(std::iter::repeat_n(false, terminator));
+    buffer.resize(buffer.len().div_ceil(8) * 8, false);
+
+    let mut codewords: Vec<u8> = buffer
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | *bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() * 8 >= capacity {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Split into blocks, add error correction to each, and interleave them
+fn interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let (ec_length, groups) = BLOCKS[version - 1];
+    let divisor = reed_solomon_divisor(ec_length);
+
+    let mut blocks: Vec<(&[u8], Vec<u8>)> = Vec::new();
+    let mut offset = 0;
+    for (count, length) in groups {
+        for _ in 0..count {
+            let block = &data[offset..offset + length];
+            blocks.push((block, reed_solomon_remainder(block, &divisor)));
+            offset += length;
+        }
+    }
+
+    let longest = blocks.iter().map(|(block, _)| block.len()).max().unwrap_or(0);
+    let mut codewords = Vec::new();
+    for i in 0..longest {
+        codewords.extend(blocks.iter().filter_map(|(block, _)| block.get(i)));
+    }
+    for i in 0..ec_length {
+        codewords.extend(blocks.iter().map(|(_, ec)| ec[i]));
+    }
+    codewords
+}
+
+/// Product in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut product: u16 = 0;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x11D);
+        product ^= ((y >> i) & 1) as u16 * x as u16;
+    }
+    product as u8
+}
+
+/// Generator polynomial of the given degree, highest coefficient first and
+/// the leading 1 left out
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (r, d) in remainder.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(*d, factor);
+        }
+    }
+    remainder
+}
+
+/// BCH-coded format information of level M with a mask
+fn format_bits(mask: u32) -> u32 {
+    let data = LEVEL_M << 3 | mask;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    (data << 10 | remainder) ^ 0x5412
+}
+
+/// BCH-coded version information, present from version 7
+fn version_bits(version: usize) -> u32 {
+    let mut remainder = version as u32;
+    for _ in 0..12 {
+        remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+    }
+    (version as u32) << 12 | remainder
+}
+
+/// A symbol being built: module colours and which modules are fixed patterns
+#[derive(Debug, Clone)]
+struct Matrix {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Matrix {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Self { version, size, modules: vec![false; size * size], function: vec![false; size * size] }
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Finders with their light separators
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+
+        let positions = ALIGNMENT[self.version - 1];
+        let last = positions.len().saturating_sub(1);
+        for (i, &cy) in positions.iter().enumerate() {
+            for (j, &cx) in positions.iter().enumerate() {
+                // Those corners belong to the finders
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function((cx as i32 + dx) as usize, (cy as i32 + dy) as usize, distance != 1);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format areas until the mask is chosen
+        self.draw_format(0);
+
+        if self.version >= 7 {
+            let bits = version_bits(self.version);
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, mask: u32) {
+        let size = self.size;
+        let bits = format_bits(mask);
+        let bit = |i: usize| bits >> i & 1 == 1;
+
+        // Around the top left finder
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Split between the other two finders
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Modules that carry codewords, in placement order: two columns wide,
+    /// zigzagging up and down from the bottom right
+    fn data_positions(&self) -> Vec<(usize, usize)> {
+        let size = self.size;
+        let mut positions = Vec::new();
+        let mut right = size - 1;
+        loop {
+            // The vertical timing pattern takes a whole column
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..size {
+                let y = if upward { size - 1 - vertical } else { vertical };
+                for x in [right, right - 1] {
+                    if !self.function[y * size + x] {
+                        positions.push((x, y));
+                    }
+                }
+            }
+            if right < 3 {
+                break;
+            }
+            right -= 2;
+        }
+        positions
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Penalty score of the four mask evaluation rules
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let dark = |x: usize, y: usize| self.modules[y * size + x];
+        let lines: Vec<Vec<bool>> = (0..size)
+            .map(|y| (0..size).map(|x| dark(x, y)).collect())
+            .chain((0..size).map(|x| (0..size).map(|y| dark(x, y)).collect()))
+            .collect();
+        const FINDER_LIKE: [bool; 11] = [true, false, true, true, true, false, true, false, false, false, false];
+
+        let mut penalty = 0;
+        for line in &lines {
+            // Runs of five or more modules of one colour
+            for run in line.chunk_by(|a, b| a == b).map(<[bool]>::len) {
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+            }
+            // Finder-like patterns with light space on either side
+            for window in line.windows(11) {
+                if window == FINDER_LIKE || window.iter().rev().eq(FINDER_LIKE.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+
+        // 2x2 blocks of one colour
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let colour = dark(x, y);
+                if dark(x + 1, y) == colour && dark(x, y + 1) == colour && dark(x + 1, y + 1) == colour {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light
+        let dark_count = self.modules.iter().filter(|module| **module).count();
+        let percent = dark_count * 100 / self.modules.len();
+        penalty + percent.abs_diff(50) / 5 * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read the bytes back from a symbol
+    fn decode(code: &QrCode) -> Vec<u8> {
+        let version = (code.size - 17) / 4;
+        let mut matrix = Matrix::new(version);
+        matrix.draw_function_patterns();
+
+        let bit = |x: usize, y: usize| code.is_dark(x, y) as u32;
+        let format = (0..6).map(|i| bit(8, i) << i).sum::<u32>()
+            | bit(8, 7) << 6
+            | bit(8, 8) << 7
+            | bit(7, 8) << 8
+            | (9..15).map(|i| bit(14 - i, 8) << i).sum::<u32>();
+        let mask = (0..8).find(|&mask| format_bits(mask) == format).expect("format information");
+
+        let mut unmasked = Matrix { modules: code.modules.clone(), ..matrix.clone() };
+        unmasked.apply_mask(mask);
+        let stream: Vec<bool> = matrix.data_positions().into_iter().map(|(x, y)| unmasked.modules[y * code.size + x]).collect();
+        let codewords: Vec<u8> = stream
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | *bit as u8))
+            .collect();
+
+        // De-interleave and check each block against its error correction
+        let (ec_length, groups) = BLOCKS[version - 1];
+        let lengths: Vec<usize> = groups.iter().flat_map(|(count, length)| std::iter::repeat_n(*length, *count)).collect();
+        let data_total: usize = lengths.iter().sum();
+        let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); lengths.len()];
+        let mut next = codewords.iter();
+        for i in 0..lengths.iter().max().copied().unwrap_or(0) {
+            for (block, length) in blocks.iter_mut().zip(&lengths) {
+                if i < *length {
+                    block.push(*next.next().unwrap());
+                }
+            }
+        }
+        let divisor = reed_solomon_divisor(ec_length);
+        for (b, block) in blocks.iter().enumerate() {
+            let ec: Vec<u8> = (0..ec_length).map(|i| codewords[data_total + i * lengths.len() + b]).collect();
+            assert_eq!(reed_solomon_remainder(block, &divisor), ec, "block {} of version {}", b, version);
+        }
+
+        let data: Vec<bool> = bits(&blocks.concat()).collect();
+        let read = |from: usize, width: usize| data[from..from + width].iter().fold(0, |acc, bit| acc << 1 | *bit as usize);
+        assert_eq!(read(0, 4), 0b0100, "byte mode");
+        let count_width = count_bits(version);
+        let length = read(4, count_width);
+        (0..length).map(|i| read(4 + count_width + i * 8, 8) as u8).collect()
+    }
+
+    #[test]
+    fn test_error_correction_matches_the_standard_example() {
+        // "HELLO WORLD" at version 1-M
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        assert_eq!(
+            reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(4), 0b100010111111001);
+        assert_eq!(version_bits(7), 0b000111110010010100);
+        assert_eq!(version_bits(10), 0b001010010011010011);
+    }
+
+    #[test]
+    fn test_symbols_read_back() {
+        let link = b"https://lab.example.com/verify?r=RPT-20250118-0001&c=K7M2QX9P&s=8f1Xv3kQ2m9LrT0aYb4WzA";
+        for data in [&b"A"[..], b"RPT-20250118-0001", link, &[b'x'; 152], &[0xA5; 213]] {
+            let code = QrCode::encode(data).unwrap();
+            assert_eq!(decode(&code), data, "{} bytes", data.len());
+
+            // Finder in the top left corner, timing along row 6
+            assert!((0..7).all(|i| code.is_dark(i, 0) && code.is_dark(0, i)));
+            assert!(!code.is_dark(7, 0) && code.is_dark(8, 6) && !code.is_dark(9, 6));
+        }
+
+        assert_eq!(QrCode::encode(b"A").unwrap().size(), 21);
+        assert_eq!(QrCode::encode(link).unwrap().size(), 41);
+        assert_eq!(QrCode::encode(&[b'x'; 152]).unwrap().size(), 49);
+        assert_eq!(QrCode::encode(&[0xA5; 213]).unwrap().size(), 57);
+        assert!(QrCode::encode(&[0; 214]).is_err());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::domain::*;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 use sqlx::PgPool;
 use uuid::Uuid;
 use chrono::{NaiveDate, Local};
@@ -62,14 +64,25 @@ impl ReportTemplateRepository {
             .transpose()
             .map_err(|e| Error::InvalidInput(format!("Invalid fields_config JSON: {}", e)))?;
 
+        let signature_fields: Option<serde_json::Value> = input.signature_fields
+            .map(|s| serde_json::from_str(&s))
+            .transpose()
+            .map_err(|e| Error::InvalidInput(format!("Invalid signature_fields JSON: {}", e)))?;
+
         let template = sqlx::query_as::<_, ReportTemplate>(
             r#"
             INSERT INTO report_template (
                 organization_id, template_name, template_code, template_type, description,
                 template_content, header_content, footer_content, styles, fields_config,
-                page_size, page_orientation, requires_signature, is_default, created_by
+                page_size, page_orientation, requires_signature, is_default, created_by,
+                margin_top, margin_bottom, margin_left, margin_right,
+                show_logo, show_watermark, watermark_text, signature_fields
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15,
+                COALESCE($16, 10), COALESCE($17, 10), COALESCE($18, 10), COALESCE($19, 10),
+                COALESCE($20, true), COALESCE($21, false), $22, $23
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
             RETURNING *
             "#
         )
@@ -88,6 +101,14 @@ impl ReportTemplateRepository {
         .bind(input.requires_signature.unwrap_or(false))
         .bind(input.is_default.unwrap_or(false))
         .bind(created_by)
+        .bind(input.margin_top)
+        .bind(input.margin_bottom)
+        .bind(input.margin_left)
+        .bind(input.margin_right)
+        .bind(input.show_logo)
+        .bind(input.show_watermark)
+        .bind(input.watermark_text)
+        .bind(signature_fields)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
@@ -145,6 +166,7 @@ impl ReportTemplateRepository {
                     ReportTemplateType::QcReport => "QC_REPORT",
                     ReportTemplateType::SummaryReport => "SUMMARY_REPORT",
                     ReportTemplateType::CustomReport => "CUSTOM_REPORT",
+                    ReportTemplateType::CumulativeReport => "CUMULATIVE_REPORT",
                 };
                 query.push_str(&format!(" AND template_type = '{}'", type_str));
             }
@@ -304,6 +326,7 @@ impl GeneratedReportRepository {
                     ReportTemplateType::QcReport => "QC_REPORT",
                     ReportTemplateType::SummaryReport => "SUMMARY_REPORT",
                     ReportTemplateType::CustomReport => "CUSTOM_REPORT",
+                    ReportTemplateType::CumulativeReport => "CUMULATIVE_REPORT",
                 };
                 query.push_str(&format!(" AND report_type = '{}'", type_str));
             }
@@ -342,6 +365,8 @@ impl GeneratedReportRepository {
         status: ReportStatus,
         error_message: Option<String>,
     ) -> Result<GeneratedReport> {
+        let mut tx = self.pool.begin().await.map_err(|e| Error::Database(e.to_string()))?;
+
         let report = sqlx::query_as::<_, GeneratedReport>(
             r#"
             UPDATE generated_report
@@ -355,10 +380,27 @@ impl GeneratedReportRepository {
         .bind(report_id)
         .bind(status)
         .bind(error_message)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
 
+        if report.report_status == ReportStatus::Generated {
+            outbox::enqueue_event(
+                &mut *tx,
+                topics::REPORT_EVENTS,
+                events::REPORT_GENERATED,
+                "GeneratedReport",
+                report.id,
+                report.organization_id,
+                None,
+                &report,
+            )
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        }
+
+        tx.commit().await.map_err(|e| Error::Database(e.to_string()))?;
+
         Ok(report)
     }
 
@@ -368,14 +410,16 @@ impl GeneratedReportRepository {
         file_path: String,
         file_size_bytes: i64,
         file_hash: String,
+        storage_location: &str,
     ) -> Result<GeneratedReport> {
         let report = sqlx::query_as::<_, GeneratedReport>(
-            "UPDATE generated_report SET file_path = $2, file_size_bytes = $3, file_hash = $4 WHERE id = $1 AND is_deleted = false RETURNING *"
+            "UPDATE generated_report SET file_path = $2, file_size_bytes = $3, file_hash = $4, storage_location = $5 WHERE id = $1 AND is_deleted = false RETURNING *"
         )
         .bind(report_id)
         .bind(file_path)
         .bind(file_size_bytes)
         .bind(file_hash)
+        .bind(storage_location)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
@@ -383,20 +427,63 @@ impl GeneratedReportRepository {
         Ok(report)
     }
 
+    /// Point a report at a newly signed revision of its file. Fails when
+    /// another signature was stored since `previous_hash` was read.
+    pub async fn update_signed_file(
+        &self,
+        report_id: Uuid,
+        previous_hash: &str,
+        file_path: String,
+        file_size_bytes: i64,
+        file_hash: String,
+    ) -> Result<GeneratedReport> {
+        let report = sqlx::query_as::<_, GeneratedReport>(
+            "UPDATE generated_report SET file_path = $3, file_size_bytes = $4, file_hash = $5 WHERE id = $1 AND file_hash = $2 AND is_deleted = false RETURNING *"
+        )
+        .bind(report_id)
+        .bind(previous_hash)
+        .bind(file_path)
+        .bind(file_size_bytes)
+        .bind(file_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?
+        .ok_or_else(|| Error::InvalidInput("Report was signed by someone else meanwhile; sign again".to_string()))?;
+
+        Ok(report)
+    }
+
     pub async fn mark_as_signed(
         &self,
         report_id: Uuid,
         signed_by: Uuid,
     ) -> Result<GeneratedReport> {
+        let mut tx = self.pool.begin().await.map_err(|e| Error::Database(e.to_string()))?;
+
         let report = sqlx::query_as::<_, GeneratedReport>(
             "UPDATE generated_report SET is_signed = true, signed_at = CURRENT_TIMESTAMP, signed_by = $2 WHERE id = $1 AND is_deleted = false RETURNING *"
         )
         .bind(report_id)
         .bind(signed_by)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::REPORT_EVENTS,
+            events::REPORT_SIGNED,
+            "GeneratedReport",
+            report.id,
+            report.organization_id,
+            Some(signed_by),
+            &report,
+        )
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
 
+        tx.commit().await.map_err(|e| Error::Database(e.to_string()))?;
+
         Ok(report)
     }
 
@@ -427,31 +514,46 @@ impl DigitalSignatureRepository {
         Self { pool }
     }
 
-    pub async fn create(&self, input: SignReportInput, signature_hash: String) -> Result<DigitalSignature> {
+    pub async fn create(
+        &self,
+        input: SignReportInput,
+        signatory_id: Uuid,
+        signatory_name: String,
+        signatory_qualification: Option<String>,
+        signature_field: &str,
+        signature_hash: String,
+        verification_code: String,
+        digital_certificate: String,
+    ) -> Result<DigitalSignature> {
         let signature = sqlx::query_as::<_, DigitalSignature>(
             r#"
             INSERT INTO digital_signature (
                 report_id, organization_id, signatory_id, signatory_name, signatory_role,
                 signatory_designation, signatory_qualification, signature_type,
                 signature_image_path, signature_location, signature_ip_address,
-                signature_hash
+                signature_hash, verification_code, signature_field, digital_certificate,
+                signature_comments
             )
-            SELECT $1, organization_id, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11
+            SELECT $1, organization_id, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15
             FROM generated_report WHERE id = $1
             RETURNING *
             "#
         )
         .bind(input.report_id)
-        .bind(input.signatory_id)
-        .bind(input.signatory_name)
+        .bind(signatory_id)
+        .bind(signatory_name)
         .bind(input.signatory_role)
         .bind(input.signatory_designation)
-        .bind(input.signatory_qualification)
+        .bind(signatory_qualification)
         .bind(input.signature_type)
         .bind(input.signature_image_path)
         .bind(input.signature_location)
         .bind(input.signature_ip_address)
         .bind(signature_hash)
+        .bind(verification_code)
+        .bind(signature_field)
+        .bind(digital_certificate)
+        .bind(input.signature_comments)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
@@ -498,9 +600,9 @@ impl ReportDeliveryRepository {
             r#"
             INSERT INTO report_delivery (
                 report_id, organization_id, delivery_channel, recipient_name,
-                recipient_contact, subject, message, scheduled_at, created_by
+                recipient_contact, subject, message, scheduled_at, next_attempt_at, created_by
             )
-            SELECT $1, organization_id, $2, $3, $4, $5, $6, $7, $8
+            SELECT $1, organization_id, $2, $3, $4, $5, $6, $7, $7, $8
             FROM generated_report WHERE id = $1
             RETURNING *
             "#
@@ -588,15 +690,22 @@ impl ReportDeliveryRepository {
         provider_message_id: Option<String>,
         error_message: Option<String>,
     ) -> Result<ReportDelivery> {
+        let mut tx = self.pool.begin().await.map_err(|e| Error::Database(e.to_string()))?;
+
         let delivery = sqlx::query_as::<_, ReportDelivery>(
             r#"
             UPDATE report_delivery
             SET delivery_status = $2,
-                provider_message_id = $3,
+                provider_message_id = COALESCE($3, provider_message_id),
                 error_message = $4,
-                sent_at = CASE WHEN $2 = 'SENT' OR $2 = 'DELIVERED' THEN CURRENT_TIMESTAMP ELSE sent_at END,
+                sent_at = CASE
+                    WHEN $2 = 'SENT' THEN CURRENT_TIMESTAMP
+                    WHEN $2 = 'DELIVERED' THEN COALESCE(sent_at, CURRENT_TIMESTAMP)
+                    ELSE sent_at
+                END,
                 delivered_at = CASE WHEN $2 = 'DELIVERED' THEN CURRENT_TIMESTAMP ELSE delivered_at END,
-                retry_count = CASE WHEN $2 = 'FAILED' THEN retry_count + 1 ELSE retry_count END
+                retry_count = CASE WHEN $2 = 'FAILED' THEN retry_count + 1 ELSE retry_count END,
+                next_attempt_at = NULL
             WHERE id = $1 AND is_deleted = false
             RETURNING *
             "#
@@ -605,12 +714,108 @@ impl ReportDeliveryRepository {
         .bind(status)
         .bind(provider_message_id)
         .bind(error_message)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        if delivery.delivery_status == Some(DeliveryStatus::Delivered) {
+            outbox::enqueue_event(
+                &mut *tx,
+                topics::REPORT_EVENTS,
+                events::REPORT_DELIVERED,
+                "GeneratedReport",
+                delivery.report_id,
+                delivery.organization_id,
+                None,
+                &delivery,
+            )
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        }
+
+        tx.commit().await.map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(delivery)
+    }
+
+    /// Record the notification a provider accepted for the delivery
+    pub async fn record_sent(
+        &self,
+        delivery_id: Uuid,
+        notification_id: Uuid,
+        provider_name: Option<String>,
+        provider_message_id: Option<String>,
+    ) -> Result<ReportDelivery> {
+        let delivery = sqlx::query_as::<_, ReportDelivery>(
+            r#"
+            UPDATE report_delivery
+            SET delivery_status = 'SENT',
+                notification_id = $2,
+                provider_name = $3,
+                provider_message_id = $4,
+                error_message = NULL,
+                sent_at = CURRENT_TIMESTAMP,
+                next_attempt_at = NULL
+            WHERE id = $1 AND is_deleted = false
+            RETURNING *
+            "#
+        )
+        .bind(delivery_id)
+        .bind(notification_id)
+        .bind(provider_name)
+        .bind(provider_message_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(delivery)
+    }
+
+    /// Have the delivery worker make the next attempt at `at`
+    pub async fn schedule_attempt(&self, delivery_id: Uuid, at: chrono::NaiveDateTime) -> Result<ReportDelivery> {
+        let delivery = sqlx::query_as::<_, ReportDelivery>(
+            "UPDATE report_delivery SET next_attempt_at = $2 WHERE id = $1 AND is_deleted = false RETURNING *"
+        )
+        .bind(delivery_id)
+        .bind(at)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::Database(e.to_string()))?;
 
         Ok(delivery)
     }
+
+    /// Take deliveries whose next attempt is due. Their next attempt moves
+    /// `lease` ahead, so another worker only takes them again if this one
+    /// dies before the attempt is recorded.
+    pub async fn claim_due(&self, limit: i64, lease: chrono::Duration) -> Result<Vec<ReportDelivery>> {
+        let now = Local::now().naive_local();
+
+        let deliveries = sqlx::query_as::<_, ReportDelivery>(
+            r#"
+            UPDATE report_delivery
+            SET next_attempt_at = $3
+            WHERE id IN (
+                SELECT id FROM report_delivery
+                WHERE delivery_status IN ('PENDING', 'FAILED')
+                  AND next_attempt_at <= $1
+                  AND is_deleted = false
+                ORDER BY next_attempt_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#
+        )
+        .bind(now)
+        .bind(limit)
+        .bind(now + lease)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(deliveries)
+    }
 }
 
 // ============================================================================
@@ -664,3 +869,75 @@ impl ReportAccessLogRepository {
         Ok(logs)
     }
 }
+
+// ============================================================================
+// Result Snapshot Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct ResultSnapshotRepository {
+    pool: PgPool,
+}
+
+impl ResultSnapshotRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store an approved result; a correction supersedes the result it corrects
+    pub async fn upsert(
+        &self,
+        snapshot: &ResultSnapshot,
+        corrects: Option<Uuid>,
+    ) -> Result<ResultSnapshot> {
+        let mut tx = self.pool.begin().await.map_err(|e| Error::Database(e.to_string()))?;
+
+        let saved = sqlx::query_as::<_, ResultSnapshot>(
+            r#"
+            INSERT INTO result_snapshot (
+                result_id, organization_id, order_id, patient_id, test_code, result_data, approved_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (result_id) DO UPDATE
+            SET result_data = EXCLUDED.result_data, approved_at = EXCLUDED.approved_at
+            RETURNING *
+            "#
+        )
+        .bind(snapshot.result_id)
+        .bind(snapshot.organization_id)
+        .bind(snapshot.order_id)
+        .bind(snapshot.patient_id)
+        .bind(&snapshot.test_code)
+        .bind(&snapshot.result_data)
+        .bind(snapshot.approved_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        if let Some(original) = corrects {
+            sqlx::query("UPDATE result_snapshot SET superseded_by = $2 WHERE result_id = $1")
+                .bind(original)
+                .bind(snapshot.result_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Database(e.to_string()))?;
+        }
+
+        tx.commit().await.map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(saved)
+    }
+
+    /// Current results of an order
+    pub async fn find_by_order(&self, order_id: Uuid) -> Result<Vec<ResultSnapshot>> {
+        let snapshots = sqlx::query_as::<_, ResultSnapshot>(
+            "SELECT * FROM result_snapshot WHERE order_id = $1 AND superseded_by IS NULL ORDER BY test_code"
+        )
+        .bind(order_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(snapshots)
+    }
+}
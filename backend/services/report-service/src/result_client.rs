@@ -0,0 +1,138 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the result-service
+#[derive(Clone)]
+pub struct ResultClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatientResultsResponse {
+    #[serde(rename = "resultsByPatient")]
+    results_by_patient: Vec<PatientResult>,
+}
+
+/// A result of a patient as cumulative reports need it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientResult {
+    pub id: Uuid,
+    pub test_code: String,
+    pub test_name: String,
+    pub department: Option<String>,
+    pub result_value: Option<String>,
+    pub result_unit: Option<String>,
+    pub reference_range_text: Option<String>,
+    pub interpretation: String,
+    pub is_abnormal: bool,
+    pub is_critical: bool,
+    pub result_status: String,
+    pub verification_status: String,
+    /// Observation (specimen collection) time
+    pub result_date: DateTime<Utc>,
+    /// Set once a pathologist approved the result for release
+    pub approval_date: Option<DateTime<Utc>>,
+}
+
+impl ResultClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Fetch the most recent results of a patient, newest first
+    pub async fn get_results_by_patient(&self, patient_id: Uuid, limit: i32) -> Result<Vec<PatientResult>> {
+        let query = r#"
+            query ResultsByPatient($patientId: ID!, $limit: Int) {
+                resultsByPatient(patientId: $patientId, limit: $limit) {
+                    id
+                    testCode
+                    testName
+                    department
+                    resultValue
+                    resultUnit
+                    referenceRangeText
+                    interpretation
+                    isAbnormal
+                    isCritical
+                    resultStatus
+                    verificationStatus
+                    resultDate
+                    approvalDate
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "patientId": patient_id.to_string(), "limit": limit }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call result-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to result-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Result-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Result-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<PatientResultsResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse result-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from result-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("Results of patient {} unavailable: {}", patient_id, error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .map(|data| data.results_by_patient)
+            .ok_or_else(|| Error::ExternalService("No data returned from result-service".to_string()))
+    }
+}
@@ -1,9 +1,27 @@
 use crate::domain::*;
 use crate::repository::*;
+use crate::cumulative;
+use crate::delivery::{self, DownloadLink, NotificationOutcome, RetryPolicy};
+use crate::notification_client::NotificationClient;
+use crate::patient_client::PatientClient;
+use crate::pdf;
+use crate::result_client::ResultClient;
+use crate::signing::{self, Keystore, SignatureField, SignatureRequest};
+use crate::storage::{self, ReportStorage, StoredFile};
+use crate::user_client::UserClient;
+use crate::verification::{self, ReportVerifier};
+use common::tenant::Tenant;
+use openssl::x509::X509;
 use uuid::Uuid;
-use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose};
 
+/// Results of a patient read for a cumulative report
+const CUMULATIVE_RESULT_LIMIT: i32 = 1000;
+
+/// How long a delivery the worker took stays its own before another
+/// worker may take it again
+const DELIVERY_CLAIM_MINUTES: i64 = 10;
+
 #[derive(Debug)]
 pub enum ReportError {
     NotFound(String),
@@ -41,6 +59,18 @@ impl From<Error> for ReportError {
     }
 }
 
+/// Missing records, database and generation failures are retried; unready,
+/// unsigned or inaccessible reports are final
+impl From<ReportError> for common::error::Error {
+    fn from(error: ReportError) -> Self {
+        match error {
+            ReportError::NotFound(msg) => Self::NotFound(msg),
+            ReportError::DatabaseError(msg) | ReportError::GenerationFailed(msg) => Self::ExternalService(msg),
+            other => Self::Validation(other.to_string()),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ReportError>;
 
 #[derive(Clone)]
@@ -50,6 +80,17 @@ pub struct ReportService {
     signature_repo: DigitalSignatureRepository,
     delivery_repo: ReportDeliveryRepository,
     access_log_repo: ReportAccessLogRepository,
+    snapshot_repo: ResultSnapshotRepository,
+    storage: ReportStorage,
+    result_client: ResultClient,
+    patient_client: PatientClient,
+    user_client: UserClient,
+    keystore: Keystore,
+    trust_anchors: Vec<X509>,
+    verifier: ReportVerifier,
+    notification_client: NotificationClient,
+    retry_policy: RetryPolicy,
+    link_ttl: chrono::Duration,
 }
 
 impl ReportService {
@@ -59,6 +100,17 @@ impl ReportService {
         signature_repo: DigitalSignatureRepository,
         delivery_repo: ReportDeliveryRepository,
         access_log_repo: ReportAccessLogRepository,
+        snapshot_repo: ResultSnapshotRepository,
+        storage: ReportStorage,
+        result_client: ResultClient,
+        patient_client: PatientClient,
+        user_client: UserClient,
+        keystore: Keystore,
+        trust_anchors: Vec<X509>,
+        verifier: ReportVerifier,
+        notification_client: NotificationClient,
+        retry_policy: RetryPolicy,
+        link_ttl: chrono::Duration,
     ) -> Self {
         Self {
             template_repo,
@@ -66,6 +118,17 @@ impl ReportService {
             signature_repo,
             delivery_repo,
             access_log_repo,
+            snapshot_repo,
+            storage,
+            result_client,
+            patient_client,
+            user_client,
+            keystore,
+            trust_anchors,
+            verifier,
+            notification_client,
+            retry_policy,
+            link_ttl,
         }
     }
 
@@ -89,8 +152,30 @@ impl ReportService {
         }
 
         // Validate JSON content
-        serde_json::from_str::<serde_json::Value>(&input.template_content)
+        let content = serde_json::from_str::<serde_json::Value>(&input.template_content)
             .map_err(|e| ReportError::ValidationError(format!("Invalid template content JSON: {}", e)))?;
+        pdf::validate_sections(&content).map_err(ReportError::ValidationError)?;
+
+        // Validate page settings
+        if let Some(page_size) = input.page_size.as_deref() {
+            if pdf::page_dimensions(page_size).is_none() {
+                return Err(ReportError::ValidationError(format!("Unsupported page size: {}", page_size)));
+            }
+        }
+        if let Some(orientation) = input.page_orientation.as_deref() {
+            if pdf::is_landscape(orientation).is_none() {
+                return Err(ReportError::ValidationError(format!("Unsupported page orientation: {}", orientation)));
+            }
+        }
+        let margins = [input.margin_top, input.margin_bottom, input.margin_left, input.margin_right];
+        if margins.iter().flatten().any(|margin| !(0..=50).contains(margin)) {
+            return Err(ReportError::ValidationError("Page margins must be between 0 and 50 mm".to_string()));
+        }
+        if let Some(signature_fields) = input.signature_fields.as_deref() {
+            let signature_fields = serde_json::from_str::<serde_json::Value>(signature_fields)
+                .map_err(|e| ReportError::ValidationError(format!("Invalid signature fields JSON: {}", e)))?;
+            signing::validate_signature_fields(&signature_fields).map_err(ReportError::ValidationError)?;
+        }
 
         let template = self.template_repo.create(input, created_by).await?;
         Ok(template)
@@ -127,10 +212,11 @@ impl ReportService {
     // Report Generation Operations
     // ============================================================================
 
-    /// Generate a new report
+    /// Generate a new report. Reports get an access code unless the input
+    /// turns it off, since the QR code verifying them needs one.
     pub async fn generate_report(
         &self,
-        input: GenerateReportInput,
+        mut input: GenerateReportInput,
         created_by: Uuid,
     ) -> Result<GeneratedReport> {
         // Validate inputs
@@ -141,6 +227,7 @@ impl ReportService {
         // Validate JSON data
         let report_data: serde_json::Value = serde_json::from_str(&input.report_data)
             .map_err(|e| ReportError::ValidationError(format!("Invalid report data JSON: {}", e)))?;
+        input.generate_access_code.get_or_insert(true);
 
         // Get template if specified
         let template = if let Some(template_id) = input.template_id {
@@ -165,13 +252,14 @@ impl ReportService {
 
         // Generate PDF content
         match self.generate_pdf_content(&report, &report_data, template.as_ref()).await {
-            Ok((file_path, file_size, file_hash)) => {
+            Ok(file) => {
                 // Update report with file info
                 report = self.report_repo.update_file_info(
                     report.id,
-                    file_path,
-                    file_size,
-                    file_hash,
+                    file.path,
+                    file.size_bytes,
+                    file.sha256,
+                    ReportStorage::LOCATION,
                 ).await?;
 
                 // Update status to generated
@@ -195,41 +283,24 @@ impl ReportService {
         Ok(report)
     }
 
-    /// Generate PDF content (simplified - in production, use proper PDF generation library)
+    /// Render the report to PDF, with the QR code of its verification link,
+    /// and store the file
     async fn generate_pdf_content(
         &self,
         report: &GeneratedReport,
         data: &serde_json::Value,
-        _template: Option<&ReportTemplate>,
-    ) -> std::result::Result<(String, i64, String), String> {
-        // In a real implementation, this would:
-        // 1. Use the template to generate HTML
-        // 2. Convert HTML to PDF using printpdf or similar
-        // 3. Save to file system or S3
-        // 4. Return file path, size, and hash
-
-        // For now, create a simple placeholder
-        let data_str = serde_json::to_string_pretty(data)
-            .map_err(|e| e.to_string())?;
-
-        let content = format!(
-            "Report: {}\nType: {:?}\nDate: {}\nData: {}",
-            report.report_title,
-            report.report_type,
-            report.report_date,
-            data_str
-        );
-
-        // Calculate hash
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        let hash = format!("{:x}", hasher.finalize());
-
-        // In production: save to file system/S3
-        let file_path = format!("/tmp/reports/{}.pdf", report.report_number);
-        let file_size = content.len() as i64;
-
-        Ok((file_path, file_size, hash))
+        template: Option<&ReportTemplate>,
+    ) -> std::result::Result<StoredFile, String> {
+        let verification_url = report
+            .access_code
+            .as_deref()
+            .map(|access_code| self.verifier.link(&report.report_number, access_code));
+        let bytes = pdf::render(report, data, template, verification_url.as_deref())?;
+
+        self.storage
+            .store(report.organization_id, &format!("{}.pdf", report.report_number), &bytes)
+            .await
+            .map_err(|e| format!("Failed to store report file: {}", e))
     }
 
     /// Get report by ID
@@ -259,8 +330,17 @@ impl ReportService {
     // Digital Signature Operations
     // ============================================================================
 
-    /// Sign a report
-    pub async fn sign_report(&self, input: SignReportInput) -> Result<DigitalSignature> {
+    /// Sign a report with the signatory's key, appending a PAdES signature to
+    /// its PDF. A report whose template lists several signature fields counts
+    /// as signed once every field is.
+    /// Sign a report as `signatory_id`, with their own keystore and the
+    /// name on their user record
+    pub async fn sign_report(&self, input: SignReportInput, signatory_id: Uuid) -> Result<DigitalSignature> {
+        let keystore_password = input.keystore_password
+            .as_deref()
+            .filter(|password| !password.is_empty())
+            .ok_or_else(|| ReportError::ValidationError("Keystore password is required".to_string()))?;
+
         // Get report and validate
         let report = self.report_repo.get_by_id(input.report_id).await?;
 
@@ -274,26 +354,131 @@ impl ReportService {
             ));
         }
 
-        // Generate signature hash (hash of report content + signatory + timestamp)
-        let signature_content = format!(
-            "{}|{}|{}",
-            report.file_hash.as_ref().unwrap_or(&String::new()),
-            input.signatory_id,
-            chrono::Local::now().to_rfc3339()
-        );
-        let mut hasher = Sha256::new();
-        hasher.update(signature_content.as_bytes());
-        let signature_hash = format!("{:x}", hasher.finalize());
+        let (Some(file_path), Some(file_hash)) = (report.file_path.as_deref(), report.file_hash.as_deref()) else {
+            return Err(ReportError::ReportNotReady);
+        };
+
+        // The signature fields of the report's template
+        let template = match report.template_id {
+            Some(template_id) => Some(self.template_repo.get_by_id(template_id).await?),
+            None => None,
+        };
+        let required = signing::required_signatures(template.as_ref().and_then(|t| t.signature_fields.as_ref()));
+        let signature_type = input.signature_type.trim().to_uppercase();
+        let field = if required.is_empty() {
+            SignatureField { name: input.signature_type.trim().to_string(), signature_type }
+        } else {
+            required
+                .iter()
+                .find(|field| field.signature_type == signature_type)
+                .cloned()
+                .ok_or_else(|| ReportError::ValidationError(format!(
+                    "Report template has no {} signature", input.signature_type
+                )))?
+        };
+
+        let existing = self.signature_repo.get_by_report_id(report.id).await?;
+        if existing.iter().any(|s| s.signature_field.as_deref() == Some(field.name.as_str())) {
+            return Err(ReportError::ValidationError(format!(
+                "Report is already signed as {}", field.name
+            )));
+        }
+
+        let bytes = self.storage
+            .read(file_path)
+            .await
+            .map_err(|e| ReportError::GenerationFailed(format!("Failed to read report file: {}", e)))?;
+        if storage::sha256_hex(&bytes) != file_hash {
+            return Err(ReportError::GenerationFailed(
+                "Stored report file does not match its recorded hash".to_string()
+            ));
+        }
 
-        // Create signature
-        let signature = self.signature_repo.create(input.clone(), signature_hash).await?;
+        let signatory = self.user_client
+            .get_signatory(signatory_id)
+            .await
+            .map_err(|e| ReportError::GenerationFailed(format!("Signatory unavailable: {}", e)))?;
+        let signatory_name = signatory.display_name();
+
+        let signer = self.keystore
+            .signer(signatory_id, keystore_password)
+            .await
+            .map_err(ReportError::ValidationError)?;
+        let certificate = signer.certificate_pem().map_err(ReportError::GenerationFailed)?;
+        let signed = signing::sign(&bytes, &signer, &SignatureRequest {
+            field_name: &field.name,
+            signer_name: &signatory_name,
+            reason: input.signature_comments.as_deref(),
+            location: input.signature_location.as_deref(),
+            signing_time: chrono::Utc::now(),
+        })
+        .map_err(ReportError::GenerationFailed)?;
+
+        // Each signed revision gets its own file, so concurrent signatories of
+        // a report cannot overwrite the file the other one recorded
+        let file_name = format!("{}-{}.pdf", report.report_number, field.signature_type.to_lowercase());
+        let stored = self.storage
+            .store(report.organization_id, &file_name, &signed.bytes)
+            .await
+            .map_err(|e| ReportError::GenerationFailed(format!("Failed to store signed report: {}", e)))?;
+        self.report_repo.update_signed_file(
+            report.id,
+            file_hash,
+            stored.path,
+            stored.size_bytes,
+            stored.sha256,
+        ).await?;
 
-        // Mark report as signed
-        self.report_repo.mark_as_signed(input.report_id, input.signatory_id).await?;
+        let verification_code = self.verifier.signature_code(&report.report_number, &signed.content_hash);
+        let qualification = input.signatory_qualification.clone().or(signatory.qualification);
+        let signature = self.signature_repo
+            .create(
+                input.clone(),
+                signatory_id,
+                signatory_name,
+                qualification,
+                &field.name,
+                signed.content_hash,
+                verification_code,
+                certificate,
+            )
+            .await?;
+
+        // Mark report as signed once every signatory has signed
+        let signed_fields: Vec<&str> = existing
+            .iter()
+            .filter_map(|s| s.signature_field.as_deref())
+            .chain([field.name.as_str()])
+            .collect();
+        if required.iter().all(|field| signed_fields.contains(&field.name.as_str())) {
+            self.report_repo.mark_as_signed(input.report_id, signatory_id).await?;
+        }
 
         Ok(signature)
     }
 
+    /// Check the signatures of a report's stored file
+    pub async fn verify_report_signatures(&self, report_id: Uuid) -> Result<SignatureVerification> {
+        let report = self.report_repo.get_by_id(report_id).await?;
+        let file_path = report.file_path.as_deref().ok_or(ReportError::ReportNotReady)?;
+        let bytes = self.storage
+            .read(file_path)
+            .await
+            .map_err(|e| ReportError::NotFound(format!("Report file unavailable: {}", e)))?;
+
+        let file_hash = storage::sha256_hex(&bytes);
+        let matches_report = report.file_hash.as_deref() == Some(file_hash.as_str());
+        let signatures = signing::verify(&bytes, &self.trust_anchors).map_err(ReportError::ValidationError)?;
+        Ok(SignatureVerification::new(file_hash, Some(matches_report), signatures))
+    }
+
+    /// Check the signatures of a report PDF on its own, such as a copy a
+    /// patient received
+    pub fn verify_pdf_signatures(&self, pdf: &[u8]) -> Result<SignatureVerification> {
+        let signatures = signing::verify(pdf, &self.trust_anchors).map_err(ReportError::ValidationError)?;
+        Ok(SignatureVerification::new(storage::sha256_hex(pdf), None, signatures))
+    }
+
     /// Get signatures for a report
     pub async fn get_report_signatures(&self, report_id: Uuid) -> Result<Vec<DigitalSignature>> {
         let signatures = self.signature_repo.get_by_report_id(report_id).await?;
@@ -341,21 +526,157 @@ impl ReportService {
             _ => {}
         }
 
+        // Sent reports are reached through a download link
+        let sent = !matches!(input.delivery_channel, DeliveryChannel::Download | DeliveryChannel::Print);
+        if sent && report.access_code.is_none() {
+            return Err(ReportError::ValidationError(
+                "Report has no access code to send a download link for".to_string()
+            ));
+        }
+
         // Create delivery record
         let delivery = self.delivery_repo.create(input, created_by).await?;
 
-        // In production: trigger actual delivery via external service (SendGrid, Twilio, etc.)
-        // For now, just mark as sent
+        if !sent {
+            // Handed over at the lab
+            let delivery = self.delivery_repo.update_status(
+                delivery.id,
+                DeliveryStatus::Delivered,
+                None,
+                None,
+            ).await?;
+            return Ok(delivery);
+        }
+
+        // Scheduled deliveries are left to the delivery worker
+        if delivery.scheduled_at.is_some() {
+            return Ok(delivery);
+        }
+
+        self.dispatch(&report, delivery).await
+    }
+
+    /// Send a delivery's report through notification-service. A failed
+    /// attempt is scheduled again while the delivery has retries left.
+    async fn dispatch(&self, report: &GeneratedReport, delivery: ReportDelivery) -> Result<ReportDelivery> {
+        let access_code = match report.access_code.as_deref() {
+            Some(access_code) if report.is_downloadable() => access_code,
+            _ => return self.delivery_failed(delivery, "Report is no longer downloadable".to_string(), false).await,
+        };
+
+        let expires_at = chrono::Utc::now() + self.link_ttl;
+        let link = self.verifier.download_link(&report.report_number, access_code, expires_at.timestamp());
+
+        let Some(request) = delivery::notification_request(report, &delivery, &link) else {
+            return Ok(delivery);
+        };
+
+        // The worker runs for every organization, notifications belong to one
+        let sent = Tenant::Organization(delivery.organization_id)
+            .scope(self.notification_client.send(&request))
+            .await;
+
+        match sent {
+            Ok(notification) if notification.is_sent() => {
+                let delivery = self.delivery_repo.record_sent(
+                    delivery.id,
+                    notification.id,
+                    notification.provider_name,
+                    notification.provider_message_id,
+                ).await?;
+                Ok(delivery)
+            }
+            Ok(notification) => {
+                let error = notification.status_message
+                    .unwrap_or_else(|| "Notification was not sent".to_string());
+                self.delivery_failed(delivery, error, true).await
+            }
+            Err(e) => self.delivery_failed(delivery, e.to_string(), true).await,
+        }
+    }
+
+    /// Record a failed attempt, scheduling the next one with backoff when
+    /// `retry` and the delivery has retries left
+    async fn delivery_failed(&self, delivery: ReportDelivery, error: String, retry: bool) -> Result<ReportDelivery> {
+        tracing::warn!("Delivery {} of report {} failed: {}", delivery.id, delivery.report_id, error);
+
         let delivery = self.delivery_repo.update_status(
             delivery.id,
-            DeliveryStatus::Sent,
-            Some(format!("MSG_{}", Uuid::new_v4())),
+            DeliveryStatus::Failed,
             None,
+            Some(error),
         ).await?;
 
+        if !retry || !delivery.can_retry() {
+            return Ok(delivery);
+        }
+
+        let delay = self.retry_policy.delay(delivery.retry_count.unwrap_or(1));
+        let next_attempt_at = chrono::Local::now().naive_local()
+            + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::hours(1));
+        let delivery = self.delivery_repo.schedule_attempt(delivery.id, next_attempt_at).await?;
+
         Ok(delivery)
     }
 
+    /// Make the delivery attempts that are due. Returns how many were made.
+    pub async fn process_due_deliveries(&self, limit: i64) -> Result<usize> {
+        let due = self.delivery_repo
+            .claim_due(limit, chrono::Duration::minutes(DELIVERY_CLAIM_MINUTES))
+            .await?;
+        let attempts = due.len();
+
+        for delivery in due {
+            let delivery_id = delivery.id;
+            let attempted = match self.report_repo.get_by_id(delivery.report_id).await {
+                Ok(report) => self.dispatch(&report, delivery).await,
+                Err(Error::NotFound(_)) => {
+                    self.delivery_failed(delivery, "Report no longer exists".to_string(), false).await
+                }
+                Err(e) => Err(e.into()),
+            };
+            // The claim runs out and the attempt is made again
+            if let Err(e) = attempted {
+                tracing::error!("Failed to attempt delivery {}: {}", delivery_id, e);
+            }
+        }
+
+        Ok(attempts)
+    }
+
+    /// Settle a sent delivery from the outcome of its notification.
+    /// Outcomes of earlier attempts, and of other notifications, are ignored.
+    pub async fn record_delivery_receipt(&self, outcome: &NotificationOutcome) -> Result<Option<ReportDelivery>> {
+        let (Some(delivery_id), Some(status)) = (outcome.delivery_id(), outcome.delivery_status()) else {
+            return Ok(None);
+        };
+
+        let delivery = self.delivery_repo.get_by_id(delivery_id).await?;
+        if delivery.notification_id != Some(outcome.notification_id)
+            || delivery.delivery_status != Some(DeliveryStatus::Sent)
+        {
+            tracing::debug!("Ignoring stale outcome of notification {}", outcome.notification_id);
+            return Ok(None);
+        }
+
+        let delivery = match status {
+            DeliveryStatus::Failed => {
+                let error = outcome.status_message.clone()
+                    .unwrap_or_else(|| "Provider could not deliver the report".to_string());
+                self.delivery_failed(delivery, error, true).await?
+            }
+            // Delivered, or bounced by a recipient it cannot reach
+            _ => self.delivery_repo.update_status(
+                delivery.id,
+                status,
+                outcome.provider_message_id.clone(),
+                outcome.status_message.clone(),
+            ).await?,
+        };
+
+        Ok(Some(delivery))
+    }
+
     /// Get delivery status
     pub async fn get_delivery(&self, delivery_id: Uuid) -> Result<ReportDelivery> {
         let delivery = self.delivery_repo.get_by_id(delivery_id).await?;
@@ -389,15 +710,133 @@ impl ReportService {
             ));
         }
 
-        // Reset to pending status for retry
-        let delivery = self.delivery_repo.update_status(
-            delivery_id,
-            DeliveryStatus::Pending,
-            None,
+        let report = self.report_repo.get_by_id(delivery.report_id).await?;
+        self.dispatch(&report, delivery).await
+    }
+
+    // ============================================================================
+    // Event Reactions
+    // ============================================================================
+
+    /// Keep an approved result for its order's report
+    pub async fn record_approved_result(
+        &self,
+        snapshot: ResultSnapshot,
+        corrects: Option<Uuid>,
+    ) -> Result<ResultSnapshot> {
+        let snapshot = self.snapshot_repo.upsert(&snapshot, corrects).await?;
+        Ok(snapshot)
+    }
+
+    /// Generate the patient report of a completed order from its approved
+    /// results. Returns `None` when the order already has one.
+    pub async fn generate_order_report(
+        &self,
+        order: &CompletedOrder,
+        order_data: &serde_json::Value,
+        created_by: Uuid,
+    ) -> Result<Option<GeneratedReport>> {
+        let existing = self.report_repo.list(
+            Some(GeneratedReportFilter {
+                organization_id: Some(order.organization_id),
+                patient_id: None,
+                order_id: Some(order.id),
+                report_type: Some(ReportTemplateType::PatientReport),
+                report_status: None,
+                from_date: None,
+                to_date: None,
+            }),
             None,
+            Some(1),
         ).await?;
+        if !existing.is_empty() {
+            return Ok(None);
+        }
 
-        Ok(delivery)
+        // Result events travel on their own topic and may still be on the way
+        let results = self.snapshot_repo.find_by_order(order.id).await?;
+        if results.is_empty() {
+            return Err(ReportError::NotFound(format!(
+                "No approved results for order {} yet", order.order_number
+            )));
+        }
+
+        let report_data = serde_json::json!({
+            "order": order_data,
+            "results": results.iter().map(|r| &r.result_data).collect::<Vec<_>>(),
+        });
+
+        let report = self.generate_report(
+            GenerateReportInput {
+                organization_id: order.organization_id,
+                template_id: None,
+                report_title: format!("Laboratory Report {}", order.order_number),
+                report_type: ReportTemplateType::PatientReport,
+                patient_id: Some(order.patient_id),
+                order_id: Some(order.id),
+                result_id: None,
+                batch_id: None,
+                report_data: report_data.to_string(),
+                report_format: None,
+                report_date: None,
+                requires_signature: Some(true),
+                generate_access_code: Some(true),
+            },
+            created_by,
+        ).await?;
+
+        Ok(Some(report))
+    }
+
+    /// Generate a cumulative report of a patient's recent results, one column
+    /// per collection date
+    pub async fn generate_cumulative_report(
+        &self,
+        input: GenerateCumulativeReportInput,
+        created_by: Uuid,
+    ) -> Result<GeneratedReport> {
+        let columns = match input.max_columns {
+            None => cumulative::DEFAULT_COLUMNS,
+            Some(columns) if (1..=cumulative::MAX_COLUMNS as i32).contains(&columns) => columns as usize,
+            Some(_) => {
+                return Err(ReportError::ValidationError(format!(
+                    "max_columns must be between 1 and {}", cumulative::MAX_COLUMNS
+                )));
+            }
+        };
+
+        let results = self.result_client
+            .get_results_by_patient(input.patient_id, CUMULATIVE_RESULT_LIMIT)
+            .await
+            .map_err(|e| ReportError::GenerationFailed(e.to_string()))?;
+
+        let test_codes = input.test_codes.unwrap_or_default();
+        let mut report_data = cumulative::assemble(&results, &test_codes, columns);
+        if report_data["analytes"].as_array().is_none_or(Vec::is_empty) {
+            return Err(ReportError::NotFound(format!(
+                "No reported results for patient {}", input.patient_id
+            )));
+        }
+        report_data["patient"] = serde_json::json!({ "id": input.patient_id });
+
+        self.generate_report(
+            GenerateReportInput {
+                organization_id: input.organization_id,
+                template_id: input.template_id,
+                report_title: input.report_title.unwrap_or_else(|| "Cumulative Report".to_string()),
+                report_type: ReportTemplateType::CumulativeReport,
+                patient_id: Some(input.patient_id),
+                order_id: None,
+                result_id: None,
+                batch_id: None,
+                report_data: report_data.to_string(),
+                report_format: None,
+                report_date: None,
+                requires_signature: input.requires_signature,
+                generate_access_code: Some(true),
+            },
+            created_by,
+        ).await
     }
 
     // ============================================================================
@@ -433,6 +872,154 @@ impl ReportService {
         Err(ReportError::AccessDenied)
     }
 
+    /// Check a report from the link in its QR code. Every lookup of an
+    /// existing report is logged, refused ones included.
+    pub async fn verify_report_link(
+        &self,
+        report_number: &str,
+        access_code: &str,
+        link_signature: &str,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<ReportVerification> {
+        let report = match self.report_repo.get_by_report_number(report_number).await {
+            Ok(report) => report,
+            // Nothing to log against, and no hint which numbers exist
+            Err(Error::NotFound(_)) => {
+                tracing::warn!("Verification lookup of unknown report {}", report_number);
+                return Err(ReportError::AccessDenied);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let verified = if self.verifier.check_link(report_number, access_code, link_signature) {
+            self.verify_access_code(report_number, access_code).await
+        } else {
+            Err(ReportError::AccessDenied)
+        };
+
+        self.access_log_repo.log_access(LogReportAccessInput {
+            report_id: report.id,
+            accessed_by: None,
+            access_code_used: Some(access_code.chars().take(50).collect()),
+            ip_address,
+            user_agent,
+            access_method: if verified.is_ok() { "QR_VERIFY" } else { "QR_VERIFY_DENIED" }.to_string(),
+            session_id: None,
+        }).await?;
+
+        self.report_verification(&verified?).await
+    }
+
+    /// What the verification portal shows about a report
+    async fn report_verification(&self, report: &GeneratedReport) -> Result<ReportVerification> {
+        let bytes = match report.file_path.as_deref() {
+            Some(path) => self.storage.read(path).await.ok(),
+            None => None,
+        };
+        let hash_status = match (&bytes, report.file_hash.as_deref()) {
+            (Some(bytes), Some(file_hash)) if storage::sha256_hex(bytes) == file_hash => HashStatus::Match,
+            (Some(_), Some(_)) => HashStatus::Mismatch,
+            _ => HashStatus::Unavailable,
+        };
+
+        // The signature that completed the report
+        let is_signed = report.is_signed.unwrap_or(false);
+        let signatures = self.signature_repo.get_by_report_id(report.id).await?;
+        let sign_off = signatures
+            .iter()
+            .filter(|_| is_signed)
+            .filter(|s| report.signed_by.is_none_or(|signatory| s.signatory_id == signatory))
+            .max_by_key(|s| s.signature_timestamp);
+
+        let signed_off = match (sign_off, &bytes) {
+            (Some(signature), Some(bytes)) => {
+                let expected = self.verifier.signature_code(&report.report_number, &signature.signature_hash);
+                // Signatures made before codes were issued have none
+                let code_matches = signature.verification_code.as_deref().is_none_or(|code| code == expected);
+                let signatures_valid = signing::verify(bytes, &self.trust_anchors)
+                    .map(|found| SignatureVerification::new(storage::sha256_hex(bytes), None, found).is_valid)
+                    .unwrap_or(false);
+                code_matches && signatures_valid
+            }
+            _ => false,
+        };
+        let needs_sign_off = is_signed || report.requires_signature.unwrap_or(false);
+
+        let patient_initials = match report.patient_id {
+            Some(patient_id) => {
+                match Tenant::Organization(report.organization_id)
+                    .scope(self.patient_client.get_patient_name(patient_id))
+                    .await
+                {
+                    Ok(name) => verification::initials(&name.first_name, name.last_name.as_deref()),
+                    Err(e) => {
+                        tracing::warn!("Patient of report {} unavailable for verification: {}", report.report_number, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        Ok(ReportVerification {
+            report_number: report.report_number.clone(),
+            report_date: report.report_date,
+            patient_initials,
+            signed_off_by: sign_off.map(|s| s.signatory_name.clone()),
+            signatory_designation: sign_off.and_then(|s| s.signatory_designation.clone()),
+            signed_at: sign_off.map(|s| s.signature_timestamp),
+            verification_code: sign_off.and_then(|s| s.verification_code.clone()),
+            hash_status,
+            is_genuine: hash_status == HashStatus::Match && (!needs_sign_off || signed_off),
+        })
+    }
+
+    /// Download a report from the signed link a delivery sent. Every use of
+    /// a link to an existing report is logged, refused ones included.
+    pub async fn download_by_link(
+        &self,
+        link: &DownloadLink,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<(GeneratedReport, Vec<u8>)> {
+        let report = match self.report_repo.get_by_report_number(&link.r).await {
+            Ok(report) => report,
+            Err(Error::NotFound(_)) => {
+                tracing::warn!("Download link of unknown report {}", link.r);
+                return Err(ReportError::AccessDenied);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let granted = link.e >= chrono::Utc::now().timestamp()
+            && self.verifier.check_download_link(&link.r, &link.c, link.e, &link.s)
+            && report.access_code.as_deref() == Some(link.c.as_str())
+            && report.is_downloadable();
+
+        self.access_log_repo.log_access(LogReportAccessInput {
+            report_id: report.id,
+            accessed_by: None,
+            access_code_used: Some(link.c.chars().take(50).collect()),
+            ip_address,
+            user_agent,
+            access_method: if granted { "LINK_DOWNLOAD" } else { "LINK_DOWNLOAD_DENIED" }.to_string(),
+            session_id: None,
+        }).await?;
+
+        if !granted {
+            return Err(ReportError::AccessDenied);
+        }
+
+        let path = report.file_path.as_deref().ok_or(ReportError::ReportNotReady)?;
+        let bytes = self.storage
+            .read(path)
+            .await
+            .map_err(|e| ReportError::GenerationFailed(format!("Failed to read report file: {}", e)))?;
+
+        Ok((report, bytes))
+    }
+
     /// Download report (generates access log entry)
     pub async fn download_report(
         &self,
@@ -0,0 +1,884 @@
+//! PAdES signatures of report PDFs.
+//!
+//! A signature is appended to the stored PDF as an incremental update: a
+//! signature field whose value holds a detached CMS SignedData over every byte
+//! of the file except the signature itself (`ETSI.CAdES.detached`, PAdES
+//! baseline B-B). Earlier revisions stay byte for byte, so each signatory of a
+//! report adds a signature without breaking the ones before, and a signature
+//! can be checked with nothing but the file and the trusted certificates.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use openssl::asn1::Asn1Time;
+use openssl::cms::{CMSOptions, CmsContentInfo};
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkcs12::Pkcs12;
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
+use openssl::pkey::{Id, PKey, Private};
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::verify::X509VerifyParam;
+use openssl::x509::{X509NameRef, X509};
+use printpdf::lopdf::{Dictionary, Document, IncrementalDocument, Object, ObjectId, StringFormat};
+use serde_json::Value;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::domain::VerifiedSignature;
+
+/// Bytes reserved in the PDF for the CMS signature
+const SIGNATURE_SIZE: usize = 16 * 1024;
+
+/// Wide enough for any final offset, so the byte range can be patched in place
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+
+const SUB_FILTER: &str = "ETSI.CAdES.detached";
+
+/// Annotation flags of a signature widget: printed and locked
+const WIDGET_FLAGS: i64 = 132;
+
+// Object identifiers
+const OID_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 1];
+const OID_SIGNED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 2];
+const OID_SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+const OID_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+const OID_ECDSA_WITH_SHA256: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+const OID_CONTENT_TYPE: &[u64] = &[1, 2, 840, 113549, 1, 9, 3];
+const OID_MESSAGE_DIGEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 4];
+const OID_SIGNING_CERTIFICATE_V2: &[u64] = &[1, 2, 840, 113549, 1, 9, 16, 2, 47];
+
+// ============================================================================
+// Signing Keys
+// ============================================================================
+
+/// Signing keys of the signatories.
+///
+/// Each signatory has a PKCS#12 file named after their user id, holding the
+/// private key, the certificate and optionally its issuing chain. This stands
+/// in for an HSM: only `signer` needs to change to use one.
+#[derive(Debug, Clone)]
+pub struct Keystore {
+    root: PathBuf,
+}
+
+impl Keystore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Open the key of a signatory
+    pub async fn signer(&self, signatory_id: Uuid, password: &str) -> Result<Signer, String> {
+        let path = self.root.join(format!("{}.p12", signatory_id));
+        let der = tokio::fs::read(&path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("No signing certificate for signatory {}", signatory_id),
+            _ => format!("Failed to read the keystore of signatory {}: {}", signatory_id, e),
+        })?;
+        Signer::from_pkcs12(&der, password)
+    }
+}
+
+/// A private key with its certificate
+pub struct Signer {
+    key: PKey<Private>,
+    certificate: X509,
+    chain: Vec<X509>,
+}
+
+impl Signer {
+    pub fn from_pkcs12(der: &[u8], password: &str) -> Result<Self, String> {
+        let parsed = Pkcs12::from_der(der)
+            .and_then(|keystore| keystore.parse2(password))
+            .map_err(|_| "Keystore cannot be opened; check its password".to_string())?;
+        let (Some(key), Some(certificate)) = (parsed.pkey, parsed.cert) else {
+            return Err("Keystore holds no private key with a certificate".to_string());
+        };
+        let matches = certificate.public_key().map(|public| public.public_eq(&key)).unwrap_or(false);
+        if !matches {
+            return Err("Keystore certificate does not belong to its private key".to_string());
+        }
+        if !matches!(key.id(), Id::RSA | Id::EC) {
+            return Err("Only RSA and EC signing keys are supported".to_string());
+        }
+
+        Ok(Self {
+            key,
+            certificate,
+            chain: parsed.ca.map(|chain| chain.into_iter().collect()).unwrap_or_default(),
+        })
+    }
+
+    /// Certificate as stored in `digital_signature.digital_certificate`
+    pub fn certificate_pem(&self) -> Result<String, String> {
+        let pem = self.certificate.to_pem().map_err(|e| e.to_string())?;
+        String::from_utf8(pem).map_err(|e| e.to_string())
+    }
+
+    fn check_validity(&self, at: DateTime<Utc>) -> Result<(), String> {
+        let at_time = Asn1Time::from_unix(at.timestamp()).map_err(|e| e.to_string())?;
+        if self.certificate.not_before() > at_time || self.certificate.not_after() < at_time {
+            return Err(format!(
+                "Signing certificate of {} is not valid on {}",
+                name(self.certificate.subject_name()),
+                at.format("%Y-%m-%d"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Trusted certificates from a PEM bundle; none when no bundle is configured
+pub fn load_trust_anchors(path: Option<&str>) -> Result<Vec<X509>, String> {
+    match path.filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let pem = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            X509::stack_from_pem(&pem).map_err(|e| format!("Invalid certificates in {}: {}", path, e))
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+// ============================================================================
+// Signature Fields
+// ============================================================================
+
+/// A signature that a report template asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureField {
+    /// Name of the PDF signature field
+    pub name: String,
+    /// `SignReportInput.signature_type` that fills it
+    pub signature_type: String,
+}
+
+/// Signatures asked for by `ReportTemplate.signature_fields`, in signing order.
+///
+/// Entries are labels or objects with a `label` (or `title`) and optionally a
+/// `signature_type` or `role`; the type defaults to the label.
+pub fn required_signatures(signature_fields: Option<&Value>) -> Vec<SignatureField> {
+    signature_fields
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let get = |key: &str| item.get(key).and_then(Value::as_str).map(str::trim).filter(|v| !v.is_empty());
+                    let name = match item {
+                        Value::String(label) => Some(label.trim()).filter(|label| !label.is_empty()),
+                        _ => get("label").or_else(|| get("title")).or_else(|| get("role")),
+                    }?;
+                    let signature_type = get("signature_type").or_else(|| get("role")).unwrap_or(name);
+                    Some(SignatureField {
+                        name: name.to_string(),
+                        signature_type: signature_type.to_uppercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check `signature_fields` of a template before it is saved
+pub fn validate_signature_fields(signature_fields: &Value) -> Result<(), String> {
+    let items = signature_fields
+        .as_array()
+        .ok_or("Signature fields must be a JSON array")?;
+    let fields = required_signatures(Some(signature_fields));
+    if fields.len() != items.len() {
+        return Err("Every signature field needs a label".to_string());
+    }
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|other| other.signature_type == field.signature_type || other.name == field.name) {
+            return Err(format!("Signature field {} is listed twice", field.name));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Signing
+// ============================================================================
+
+/// A signature to add to a PDF
+#[derive(Debug, Clone)]
+pub struct SignatureRequest<'a> {
+    pub field_name: &'a str,
+    pub signer_name: &'a str,
+    pub reason: Option<&'a str>,
+    pub location: Option<&'a str>,
+    pub signing_time: DateTime<Utc>,
+}
+
+/// A PDF with one more signature
+#[derive(Debug, Clone)]
+pub struct SignedPdf {
+    pub bytes: Vec<u8>,
+    /// Lowercase hex SHA-256 of the bytes the signature covers
+    pub content_hash: String,
+}
+
+/// Append a signature to a PDF
+pub fn sign(pdf: &[u8], signer: &Signer, request: &SignatureRequest) -> Result<SignedPdf, String> {
+    signer.check_validity(request.signing_time)?;
+
+    let mut bytes = prepare(pdf, request)?;
+
+    // The signature covers everything but its own hex string
+    let placeholder = format!("<{}>", "0".repeat(SIGNATURE_SIZE * 2));
+    let contents_start = find(&bytes, placeholder.as_bytes(), pdf.len())
+        .ok_or("Signature placeholder is missing from the update")?;
+    let contents_end = contents_start + placeholder.len();
+    let byte_range = [0, contents_start, contents_end, bytes.len() - contents_end];
+
+    let open = find(&bytes, b"/ByteRange", pdf.len())
+        .and_then(|at| find(&bytes, b"[", at))
+        .ok_or("Byte range placeholder is missing from the update")?;
+    let close = find(&bytes, b"]", open).ok_or("Byte range placeholder is missing from the update")? + 1;
+    let range = format!("[{} {} {} {}", byte_range[0], byte_range[1], byte_range[2], byte_range[3]);
+    if range.len() + 1 > close - open {
+        return Err("Report file is too large to sign".to_string());
+    }
+    let padded = format!("{:<width$}]", range, width = close - open - 1);
+    bytes[open..close].copy_from_slice(padded.as_bytes());
+
+    let mut covered = bytes[..contents_start].to_vec();
+    covered.extend_from_slice(&bytes[contents_end..]);
+    let digest = hash(MessageDigest::sha256(), &covered).map_err(|e| e.to_string())?;
+
+    let cms = signed_data(signer, &digest)?;
+    if cms.len() > SIGNATURE_SIZE {
+        return Err("Signature does not fit the space reserved for it".to_string());
+    }
+    let hex: String = cms.iter().map(|byte| format!("{:02X}", byte)).collect();
+    bytes[contents_start + 1..contents_start + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+
+    Ok(SignedPdf {
+        bytes,
+        content_hash: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    })
+}
+
+/// The PDF with an incremental update holding an empty signature field on the
+/// last page, where the signature lines are drawn
+fn prepare(pdf: &[u8], request: &SignatureRequest) -> Result<Vec<u8>, String> {
+    let document = Document::load_mem(pdf).map_err(|e| format!("Report file is not a readable PDF: {}", e))?;
+    let catalog_id = document
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|_| "PDF has no document catalog".to_string())?;
+    let page_id = *document.get_pages().values().last().ok_or("PDF has no pages")?;
+    let existing_form = document
+        .get_object(catalog_id)
+        .and_then(Object::as_dict)
+        .and_then(|catalog| catalog.get(b"AcroForm"))
+        .ok()
+        .cloned();
+
+    let mut update = IncrementalDocument::create_from(pdf.to_vec(), document);
+    let new = &mut update.new_document;
+
+    let mut signature = Dictionary::new();
+    signature.set("Type", Object::Name(b"Sig".to_vec()));
+    signature.set("Filter", Object::Name(b"Adobe.PPKLite".to_vec()));
+    signature.set("SubFilter", Object::Name(SUB_FILTER.as_bytes().to_vec()));
+    signature.set("ByteRange", vec![Object::Integer(0), BYTE_RANGE_PLACEHOLDER.into(), BYTE_RANGE_PLACEHOLDER.into(), BYTE_RANGE_PLACEHOLDER.into()]);
+    signature.set("Contents", Object::String(vec![0; SIGNATURE_SIZE], StringFormat::Hexadecimal));
+    signature.set("M", Object::string_literal(request.signing_time.format("D:%Y%m%d%H%M%S+00'00'").to_string()));
+    signature.set("Name", text_string(request.signer_name));
+    if let Some(reason) = request.reason {
+        signature.set("Reason", text_string(reason));
+    }
+    if let Some(location) = request.location {
+        signature.set("Location", text_string(location));
+    }
+    let signature_id = new.add_object(signature);
+
+    let mut field = Dictionary::new();
+    field.set("Type", Object::Name(b"Annot".to_vec()));
+    field.set("Subtype", Object::Name(b"Widget".to_vec()));
+    field.set("FT", Object::Name(b"Sig".to_vec()));
+    field.set("T", text_string(request.field_name));
+    field.set("V", signature_id);
+    field.set("Rect", vec![0.into(), 0.into(), 0.into(), 0.into()]);
+    field.set("F", WIDGET_FLAGS);
+    field.set("P", page_id);
+    let field_id = new.add_object(field);
+
+    // The page lists the widget among its annotations
+    let annotations = update
+        .get_prev_documents()
+        .get_object(page_id)
+        .and_then(Object::as_dict)
+        .and_then(|page| page.get(b"Annots"))
+        .ok()
+        .cloned();
+    match annotations {
+        Some(Object::Reference(array_id)) => append(&mut update, array_id, None, field_id)?,
+        other => {
+            let mut annotations = other.and_then(|a| a.as_array().ok().cloned()).unwrap_or_default();
+            annotations.push(field_id.into());
+            update.opt_clone_object_to_new_document(page_id).map_err(|e| e.to_string())?;
+            dictionary_mut(&mut update, page_id)?.set("Annots", annotations);
+        }
+    }
+
+    // The form lists the field and says the document is signed
+    match existing_form {
+        Some(Object::Reference(form_id)) => append(&mut update, form_id, Some(b"Fields"), field_id)?,
+        other => {
+            let mut form = other.and_then(|form| form.as_dict().ok().cloned()).unwrap_or_default();
+            let mut fields = form.get(b"Fields").and_then(Object::as_array).cloned().unwrap_or_default();
+            fields.push(field_id.into());
+            form.set("Fields", fields);
+            form.set("SigFlags", 3);
+            let form_id = update.new_document.add_object(form);
+            update.opt_clone_object_to_new_document(catalog_id).map_err(|e| e.to_string())?;
+            dictionary_mut(&mut update, catalog_id)?.set("AcroForm", form_id);
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(pdf.len() + SIGNATURE_SIZE * 3);
+    update.save_to(&mut bytes).map_err(|e| format!("Failed to write the signed PDF: {}", e))?;
+    Ok(bytes)
+}
+
+/// Add a reference to an array object, or to an array in a dictionary object
+fn append(update: &mut IncrementalDocument, object_id: ObjectId, key: Option<&[u8]>, item: ObjectId) -> Result<(), String> {
+    update.opt_clone_object_to_new_document(object_id).map_err(|e| e.to_string())?;
+    let object = update.new_document.get_object_mut(object_id).map_err(|e| e.to_string())?;
+    let array = match (key, object) {
+        (None, Object::Array(array)) => array,
+        (Some(key), Object::Dictionary(dictionary)) => {
+            if !dictionary.has(key) {
+                dictionary.set(key, Vec::<Object>::new());
+            }
+            if key == b"Fields" {
+                dictionary.set("SigFlags", 3);
+            }
+            dictionary.get_mut(key).and_then(Object::as_array_mut).map_err(|e| e.to_string())?
+        }
+        _ => return Err("PDF form structure is not supported".to_string()),
+    };
+    array.push(item.into());
+    Ok(())
+}
+
+fn dictionary_mut(update: &mut IncrementalDocument, object_id: ObjectId) -> Result<&mut Dictionary, String> {
+    update
+        .new_document
+        .get_object_mut(object_id)
+        .and_then(Object::as_dict_mut)
+        .map_err(|e| e.to_string())
+}
+
+/// PDF text string: literal when ASCII, UTF-16BE otherwise
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        Object::string_literal(text)
+    } else {
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        Object::String(bytes, StringFormat::Hexadecimal)
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|at| from + at)
+}
+
+/// Detached CMS SignedData with the attributes PAdES baseline B-B asks for
+fn signed_data(signer: &Signer, digest: &[u8]) -> Result<Vec<u8>, String> {
+    let certificate = signer.certificate.to_der().map_err(|e| e.to_string())?;
+    let issuer = signer.certificate.issuer_name().to_der().map_err(|e| e.to_string())?;
+    let serial = signer
+        .certificate
+        .serial_number()
+        .to_bn()
+        .map(|serial| integer(&serial.to_vec()))
+        .map_err(|e| e.to_string())?;
+    let sha256 = sequence(&[&oid(OID_SHA256), &[0x05, 0x00]]);
+    let certificate_hash = hash(MessageDigest::sha256(), &certificate).map_err(|e| e.to_string())?;
+
+    // ESSCertIDv2 with the default hash algorithm and the issuer and serial
+    let issuer_serial = sequence(&[&sequence(&[&tlv(0xA4, &issuer)]), &serial]);
+    let certificate_id = sequence(&[&tlv(0x04, &certificate_hash), &issuer_serial]);
+    let signed_attributes = set_of(vec![
+        attribute(OID_CONTENT_TYPE, &oid(OID_DATA)),
+        attribute(OID_MESSAGE_DIGEST, &tlv(0x04, digest)),
+        attribute(OID_SIGNING_CERTIFICATE_V2, &sequence(&[&sequence(&[&certificate_id])])),
+    ]);
+
+    let mut signing = openssl::sign::Signer::new(MessageDigest::sha256(), &signer.key).map_err(|e| e.to_string())?;
+    let signature = signing.sign_oneshot_to_vec(&signed_attributes).map_err(|e| e.to_string())?;
+    let signature_algorithm = match signer.key.id() {
+        Id::EC => sequence(&[&oid(OID_ECDSA_WITH_SHA256)]),
+        _ => sequence(&[&oid(OID_RSA_ENCRYPTION), &[0x05, 0x00]]),
+    };
+
+    // Signed attributes are [0] IMPLICIT in the SignerInfo
+    let mut implicit_attributes = signed_attributes;
+    implicit_attributes[0] = 0xA0;
+    let signer_info = sequence(&[
+        &integer(&[1]),
+        &sequence(&[&issuer, &serial]),
+        &sha256,
+        &implicit_attributes,
+        &signature_algorithm,
+        &tlv(0x04, &signature),
+    ]);
+
+    let mut certificates = certificate;
+    for certificate in &signer.chain {
+        certificates.extend(certificate.to_der().map_err(|e| e.to_string())?);
+    }
+    let signed_data = sequence(&[
+        &integer(&[1]),
+        &set_of(vec![sha256.clone()]),
+        &sequence(&[&oid(OID_DATA)]),
+        &tlv(0xA0, &certificates),
+        &set_of(vec![signer_info]),
+    ]);
+
+    Ok(sequence(&[&oid(OID_SIGNED_DATA), &tlv(0xA0, &signed_data)]))
+}
+
+// ============================================================================
+// Verification
+// ============================================================================
+
+/// Check every signature of a PDF.
+///
+/// A signature is intact when its CMS matches the bytes it covers, and trusted
+/// when its certificate chains to one of `trust_anchors` as of the signing
+/// time. Nothing is fetched, so revocation is not checked.
+pub fn verify(pdf: &[u8], trust_anchors: &[X509]) -> Result<Vec<VerifiedSignature>, String> {
+    let document = Document::load_mem(pdf).map_err(|e| format!("File is not a readable PDF: {}", e))?;
+
+    let mut fields = Vec::new();
+    let form_fields = document
+        .catalog()
+        .and_then(|catalog| catalog.get(b"AcroForm"))
+        .and_then(|form| resolve(&document, form).as_dict())
+        .and_then(|form| form.get(b"Fields"))
+        .and_then(|list| resolve(&document, list).as_array())
+        .cloned()
+        .unwrap_or_default();
+    collect_signature_fields(&document, &form_fields, &mut fields, 0);
+
+    Ok(fields
+        .into_iter()
+        .map(|(name, signature)| check_signature(pdf, name, &signature, trust_anchors))
+        .collect())
+}
+
+fn resolve<'a>(document: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => document.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+/// Signed signature fields with their signature dictionaries, in form order
+fn collect_signature_fields(document: &Document, items: &[Object], fields: &mut Vec<(String, Dictionary)>, depth: usize) {
+    for item in items {
+        let Ok(field) = resolve(document, item).as_dict() else { continue };
+        if let Ok(kids) = field.get(b"Kids").and_then(|kids| resolve(document, kids).as_array()) {
+            if depth < 8 {
+                collect_signature_fields(document, kids, fields, depth + 1);
+            }
+        }
+        let is_signature = field.get(b"FT").and_then(Object::as_name).map(|ft| ft == b"Sig").unwrap_or(false);
+        let value = field.get(b"V").ok().map(|value| resolve(document, value)).and_then(|value| value.as_dict().ok());
+        if let (true, Some(signature)) = (is_signature, value) {
+            let name = field.get(b"T").ok().map(pdf_text).unwrap_or_default();
+            fields.push((name, signature.clone()));
+        }
+    }
+}
+
+fn check_signature(pdf: &[u8], field_name: String, signature: &Dictionary, trust_anchors: &[X509]) -> VerifiedSignature {
+    let text = |key: &[u8]| signature.get(key).ok().map(pdf_text).filter(|t| !t.is_empty());
+    let signing_time = text(b"M").and_then(|m| parse_pdf_date(&m));
+    let mut result = VerifiedSignature {
+        field_name,
+        signer_name: text(b"Name"),
+        signing_time: signing_time.map(|time| time.naive_utc()),
+        reason: text(b"Reason"),
+        certificate_subject: None,
+        certificate_issuer: None,
+        certificate_serial: None,
+        covers_whole_document: false,
+        is_intact: false,
+        is_trusted: false,
+        error: None,
+    };
+    if let Err(error) = check_cms(pdf, signature, signing_time, trust_anchors, &mut result) {
+        result.error = Some(error);
+    }
+    result
+}
+
+fn check_cms(
+    pdf: &[u8],
+    signature: &Dictionary,
+    signing_time: Option<DateTime<Utc>>,
+    trust_anchors: &[X509],
+    result: &mut VerifiedSignature,
+) -> Result<(), String> {
+    let sub_filter = signature.get(b"SubFilter").and_then(Object::as_name).unwrap_or_default();
+    if sub_filter != SUB_FILTER.as_bytes() && sub_filter != b"adbe.pkcs7.detached" {
+        return Err(format!("Unsupported signature format {}", String::from_utf8_lossy(sub_filter)));
+    }
+
+    // Two ranges around the hex string of the signature
+    let range: Vec<usize> = signature
+        .get(b"ByteRange")
+        .and_then(Object::as_array)
+        .map(|range| range.iter().filter_map(|n| n.as_i64().ok()).filter_map(|n| usize::try_from(n).ok()).collect())
+        .unwrap_or_default();
+    let [start, first_len, second_start, second_len] = range[..] else {
+        return Err("Signature has no valid byte range".to_string());
+    };
+    let end = second_start.checked_add(second_len).filter(|end| *end <= pdf.len());
+    if start != 0 || first_len >= second_start || end.is_none()
+        || pdf[first_len] != b'<' || pdf[second_start - 1] != b'>' {
+        return Err("Signature byte range does not fit the file".to_string());
+    }
+    result.covers_whole_document = end == Some(pdf.len());
+    let mut covered = pdf[..first_len].to_vec();
+    covered.extend_from_slice(&pdf[second_start..second_start + second_len]);
+
+    let contents = match signature.get(b"Contents") {
+        Ok(Object::String(bytes, _)) => bytes.as_slice(),
+        _ => return Err("Signature has no contents".to_string()),
+    };
+    let cms_der = &contents[..der_length(contents).ok_or("Signature contents are not DER")?];
+
+    let pkcs7 = Pkcs7::from_der(cms_der).map_err(|_| "Signature is not a CMS SignedData".to_string())?;
+    let no_certificates = Stack::new().map_err(|e| e.to_string())?;
+    let signers = pkcs7
+        .signers(&no_certificates, Pkcs7Flags::empty())
+        .map_err(|_| "Signature carries no signer certificate".to_string())?;
+    let certificate = signers.get(0).ok_or("Signature carries no signer certificate")?;
+    result.certificate_subject = Some(name(certificate.subject_name()));
+    result.certificate_issuer = Some(name(certificate.issuer_name()));
+    result.certificate_serial = certificate
+        .serial_number()
+        .to_bn()
+        .and_then(|serial| serial.to_hex_str().map(|hex| hex.to_string()))
+        .ok();
+
+    let mut cms = CmsContentInfo::from_der(cms_der).map_err(|e| e.to_string())?;
+    result.is_intact = cms
+        .verify(None, None, Some(&covered), None, CMSOptions::NO_SIGNER_CERT_VERIFY | CMSOptions::BINARY)
+        .is_ok();
+    if !result.is_intact {
+        return Err("Signed bytes were changed after signing".to_string());
+    }
+
+    if trust_anchors.is_empty() {
+        return Err("No trusted certificates are configured".to_string());
+    }
+    let mut store = X509StoreBuilder::new().map_err(|e| e.to_string())?;
+    for anchor in trust_anchors {
+        store.add_cert(anchor.clone()).map_err(|e| e.to_string())?;
+    }
+    // Certificates may have expired since; what counts is the signing time
+    let mut param = X509VerifyParam::new().map_err(|e| e.to_string())?;
+    param.set_time(signing_time.unwrap_or_else(Utc::now).timestamp());
+    store.set_param(&param).map_err(|e| e.to_string())?;
+    let store = store.build();
+    result.is_trusted = cms
+        .verify(None, Some(&store), Some(&covered), None, CMSOptions::BINARY)
+        .is_ok();
+    if !result.is_trusted {
+        return Err("Signer certificate is not issued by a trusted authority".to_string());
+    }
+    Ok(())
+}
+
+/// Readable distinguished name
+fn name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry.data().to_string().unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Text of a PDF string object
+fn pdf_text(object: &Object) -> String {
+    match object {
+        Object::String(bytes, _) if bytes.starts_with(&[0xFE, 0xFF]) => {
+            let units: Vec<u16> = bytes[2..].chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        Object::String(bytes, _) => bytes.iter().map(|&byte| byte as char).collect(),
+        _ => String::new(),
+    }
+}
+
+/// `D:YYYYMMDDHHmmSS` with an optional `Z`, `+HH'mm'` or `-HH'mm'` offset
+fn parse_pdf_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    let local = NaiveDateTime::parse_from_str(text.get(..14)?, "%Y%m%d%H%M%S").ok()?;
+    let offset = text.get(14..).unwrap_or_default();
+    let seconds = match offset.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let digits: String = offset[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i64 = digits.get(..2)?.parse().ok()?;
+            let minutes: i64 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '+' { seconds } else { -seconds }
+        }
+        _ => 0,
+    };
+    Some(Utc.from_utc_datetime(&(local - chrono::Duration::seconds(seconds))))
+}
+
+// ============================================================================
+// DER Encoding
+// ============================================================================
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|byte| *byte == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    tlv(0x30, &parts.concat())
+}
+
+/// DER sorts the encodings of a SET OF
+fn set_of(mut items: Vec<Vec<u8>>) -> Vec<u8> {
+    items.sort();
+    tlv(0x31, &items.concat())
+}
+
+fn attribute(kind: &[u64], value: &[u8]) -> Vec<u8> {
+    sequence(&[&oid(kind), &tlv(0x31, value)])
+}
+
+fn oid(arcs: &[u64]) -> Vec<u8> {
+    let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for &arc in &arcs[2..] {
+        let mut base128 = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            base128.push(0x80 | (rest & 0x7F) as u8);
+            rest >>= 7;
+        }
+        content.extend(base128.into_iter().rev());
+    }
+    tlv(0x06, &content)
+}
+
+/// INTEGER from unsigned big-endian bytes
+fn integer(bytes: &[u8]) -> Vec<u8> {
+    let trimmed: Vec<u8> = bytes.iter().copied().skip_while(|byte| *byte == 0).collect();
+    let mut content = if trimmed.first().is_some_and(|byte| byte & 0x80 != 0) { vec![0] } else { Vec::new() };
+    content.extend(trimmed);
+    if content.is_empty() {
+        content.push(0);
+    }
+    tlv(0x02, &content)
+}
+
+/// Length of the DER element at the start of some bytes; the signature
+/// contents are padded with zeros
+fn der_length(bytes: &[u8]) -> Option<usize> {
+    let first = *bytes.get(1)? as usize;
+    let (header, len) = if first < 0x80 {
+        (2, first)
+    } else {
+        let count = first & 0x7F;
+        let len = bytes.get(2..2 + count)?.iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        (2 + count, len)
+    };
+    let total = header.checked_add(len)?;
+    (total <= bytes.len()).then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{GeneratedReport, ReportStatus, ReportTemplateType};
+    use crate::pdf;
+    use openssl::asn1::Asn1Integer;
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::rsa::Rsa;
+    use openssl::x509::extension::BasicConstraints;
+    use openssl::x509::X509NameBuilder;
+
+    fn certificate(common_name: &str, key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>, serial: u32) -> X509 {
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", common_name).unwrap();
+        subject.append_entry_by_text("O", "City Lab").unwrap();
+        let subject = subject.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&Asn1Integer::from_bn(&BigNum::from_u32(serial).unwrap()).unwrap()).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_issuer_name(issuer.map(|(ca, _)| ca.subject_name()).unwrap_or(&subject)).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(365).unwrap()).unwrap();
+        if issuer.is_none() {
+            builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+        }
+        builder.sign(issuer.map(|(_, key)| key).unwrap_or(key), MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    /// A lab CA and a keystore for one of its pathologists
+    fn keystore(key: PKey<Private>, common_name: &str, authority: &str) -> (X509, Vec<u8>) {
+        let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let ca = certificate(authority, &ca_key, None, 1);
+        let signer = certificate(common_name, &key, Some((&ca, &ca_key)), 7);
+        let mut chain = Stack::new().unwrap();
+        chain.push(ca.clone()).unwrap();
+        let p12 = Pkcs12::builder()
+            .name(common_name)
+            .pkey(&key)
+            .cert(&signer)
+            .ca(chain)
+            .build2("secret")
+            .unwrap();
+        (ca, p12.to_der().unwrap())
+    }
+
+    fn report_pdf() -> Vec<u8> {
+        let report = GeneratedReport {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            template_id: None,
+            report_number: "RPT-20250118-0001".to_string(),
+            report_title: "Laboratory Report".to_string(),
+            report_type: ReportTemplateType::PatientReport,
+            patient_id: None,
+            order_id: None,
+            result_id: None,
+            batch_id: None,
+            report_data: serde_json::json!({}),
+            generated_content: None,
+            report_format: None,
+            file_path: None,
+            file_size_bytes: None,
+            file_hash: None,
+            storage_location: None,
+            report_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 18).unwrap(),
+            generated_at: None,
+            expires_at: None,
+            report_status: ReportStatus::Generated,
+            error_message: None,
+            is_confidential: Some(true),
+            access_code: None,
+            download_count: Some(0),
+            last_downloaded_at: None,
+            requires_signature: Some(true),
+            is_signed: Some(false),
+            signed_at: None,
+            signed_by: None,
+            created_by: Uuid::new_v4(),
+            created_at: chrono::Local::now().naive_local(),
+            updated_by: None,
+            updated_at: None,
+            is_deleted: Some(false),
+        };
+        let data = serde_json::json!({"results": [{"test_name": "Glucose", "result_value": "95"}]});
+        pdf::render(&report, &data, None, None).unwrap()
+    }
+
+    fn request<'a>(field_name: &'a str, signer_name: &'a str) -> SignatureRequest<'a> {
+        SignatureRequest {
+            field_name,
+            signer_name,
+            reason: Some("Results reviewed"),
+            location: Some("City Lab"),
+            signing_time: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_each_signatory_adds_a_signature_that_verifies_offline() {
+        let (ca, pathologist) = keystore(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(), "Dr. A. Rao", "City Lab Signing CA");
+        let ec_key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()).unwrap();
+        let (consultant_ca, consultant) = keystore(ec_key, "Dr. Jürgen Weiß", "Partner Lab Signing CA");
+        let trusted = vec![ca, consultant_ca];
+
+        let original = report_pdf();
+        let first = sign(&original, &Signer::from_pkcs12(&pathologist, "secret").unwrap(), &request("Pathologist", "Dr. A. Rao")).unwrap();
+        assert!(first.bytes.starts_with(&original));
+
+        let second = sign(&first.bytes, &Signer::from_pkcs12(&consultant, "secret").unwrap(), &request("Consultant", "Dr. Jürgen Weiß")).unwrap();
+        assert!(second.bytes.starts_with(&first.bytes));
+
+        let signatures = verify(&second.bytes, &trusted).unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[0].field_name, "Pathologist");
+        assert_eq!(signatures[1].field_name, "Consultant");
+        assert_eq!(signatures[1].signer_name.as_deref(), Some("Dr. Jürgen Weiß"));
+        assert!(signatures.iter().all(|s| s.is_intact && s.is_trusted && s.error.is_none()), "{:?}", signatures);
+        // Only the latest signature covers the revisions after the first
+        assert!(!signatures[0].covers_whole_document);
+        assert!(signatures[1].covers_whole_document);
+        assert_eq!(signatures[0].certificate_subject.as_deref(), Some("CN=Dr. A. Rao, O=City Lab"));
+        assert_eq!(signatures[0].certificate_serial.as_deref(), Some("07"));
+
+        // Without the issuing authority the signature is intact but not trusted
+        let untrusted = verify(&first.bytes, &trusted[1..]).unwrap();
+        assert!(untrusted[0].is_intact && !untrusted[0].is_trusted);
+    }
+
+    #[test]
+    fn test_changed_bytes_break_the_signature() {
+        let (ca, pathologist) = keystore(PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(), "Dr. A. Rao", "City Lab Signing CA");
+        let signed = sign(&report_pdf(), &Signer::from_pkcs12(&pathologist, "secret").unwrap(), &request("Pathologist", "Dr. A. Rao")).unwrap();
+
+        let mut tampered = signed.bytes.clone();
+        let at = find(&tampered, b"Laboratory Report", 0).unwrap();
+        tampered[at] = b'l';
+
+        let signatures = verify(&tampered, &[ca]).unwrap();
+        assert!(!signatures[0].is_intact);
+        assert_eq!(signatures[0].error.as_deref(), Some("Signed bytes were changed after signing"));
+        assert!(Signer::from_pkcs12(&pathologist, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_signature_fields_come_from_the_template() {
+        let fields = serde_json::json!([
+            "Pathologist",
+            {"label": "Consultant Microbiologist", "role": "consultant"},
+            {"title": "Lab Director", "signature_type": "DIRECTOR"},
+        ]);
+        let required = required_signatures(Some(&fields));
+        let types: Vec<&str> = required.iter().map(|f| f.signature_type.as_str()).collect();
+        assert_eq!(types, vec!["PATHOLOGIST", "CONSULTANT", "DIRECTOR"]);
+        assert_eq!(required[1].name, "Consultant Microbiologist");
+        assert!(validate_signature_fields(&fields).is_ok());
+
+        assert!(validate_signature_fields(&serde_json::json!(["Pathologist", "pathologist"])).is_err());
+        assert!(validate_signature_fields(&serde_json::json!([{"name": "Dr. A. Rao"}])).is_err());
+        assert!(required_signatures(None).is_empty());
+        assert_eq!(parse_pdf_date("D:20250118103000+05'30'").unwrap().to_rfc3339(), "2025-01-18T05:00:00+00:00");
+    }
+}
@@ -0,0 +1,94 @@
+//! Storage of rendered report files.
+//!
+//! Files are kept on the local filesystem under one directory per
+//! organization. The recorded hash is the SHA-256 of the exact bytes written,
+//! so a stored file can be checked against `generated_report.file_hash`.
+
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// A file as written to storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFile {
+    pub path: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportStorage {
+    root: PathBuf,
+}
+
+impl ReportStorage {
+    /// Value of `generated_report.storage_location` for files kept here
+    pub const LOCATION: &'static str = "LOCAL";
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Write a file, replacing any earlier file of the same name
+    pub async fn store(&self, organization_id: Uuid, file_name: &str, bytes: &[u8]) -> std::io::Result<StoredFile> {
+        let directory = self.root.join(organization_id.to_string());
+        tokio::fs::create_dir_all(&directory).await?;
+
+        // Readers never see a partly written file
+        let path = directory.join(sanitize(file_name));
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, bytes).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(StoredFile {
+            path: path.to_string_lossy().into_owned(),
+            size_bytes: bytes.len() as i64,
+            sha256: sha256_hex(bytes),
+        })
+    }
+
+    /// Read a stored file back
+    pub async fn read(&self, path: &str) -> std::io::Result<Vec<u8>> {
+        tokio::fs::read(path).await
+    }
+}
+
+/// Lowercase hex SHA-256 of some bytes
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Keep file names inside their directory
+fn sanitize(file_name: &str) -> String {
+    file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stored_file_matches_its_recorded_hash() {
+        let root = std::env::temp_dir().join(format!("report-storage-{}", Uuid::new_v4()));
+        let storage = ReportStorage::new(&root);
+        let organization_id = Uuid::new_v4();
+
+        storage.store(organization_id, "../RPT-1.pdf", b"%PDF-1.3 first").await.unwrap();
+        let stored = storage.store(organization_id, "../RPT-1.pdf", b"%PDF-1.3 second").await.unwrap();
+
+        let path = root.join(organization_id.to_string()).join("_RPT-1.pdf");
+        assert_eq!(stored.path, path.to_string_lossy());
+        let on_disk = tokio::fs::read(&path).await.unwrap();
+        assert_eq!(on_disk, b"%PDF-1.3 second");
+        assert_eq!(stored.size_bytes, on_disk.len() as i64);
+        assert_eq!(stored.sha256, sha256_hex(&on_disk));
+        assert_eq!(stored.sha256.len(), 64);
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
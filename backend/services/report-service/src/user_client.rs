@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the user-service
+#[derive(Clone)]
+pub struct UserClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserResponse {
+    user: Option<Signatory>,
+}
+
+/// The user record of a signatory, for the name their signatures carry
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signatory {
+    pub first_name: String,
+    pub last_name: String,
+    pub professional_title: Option<String>,
+    pub qualification: Option<String>,
+}
+
+impl Signatory {
+    /// Name as it appears on a signature, e.g. "Dr. Asha Rao"
+    pub fn display_name(&self) -> String {
+        let name = format!("{} {}", self.first_name, self.last_name);
+        match self.professional_title.as_deref().map(str::trim) {
+            Some(title) if !title.is_empty() => format!("{} {}", title, name),
+            _ => name,
+        }
+    }
+}
+
+impl UserClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Fetch the user record of a signatory
+    pub async fn get_signatory(&self, user_id: Uuid) -> Result<Signatory> {
+        let query = r#"
+            query User($id: ID!) {
+                user(id: $id) {
+                    firstName
+                    lastName
+                    professionalTitle
+                    qualification
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "id": user_id.to_string() }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call user-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to user-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("User-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("User-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<UserResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse user-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from user-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("Failed to fetch user {}: {}", user_id, error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .ok_or_else(|| Error::ExternalService("No data returned from user-service".to_string()))?
+            .user
+            .ok_or_else(|| Error::NotFound(format!("User {} not found", user_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signatory(professional_title: Option<&str>) -> Signatory {
+        Signatory {
+            first_name: "Asha".to_string(),
+            last_name: "Rao".to_string(),
+            professional_title: professional_title.map(str::to_string),
+            qualification: Some("MD Pathology".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_display_name_includes_professional_title() {
+        assert_eq!(signatory(Some("Dr.")).display_name(), "Dr. Asha Rao");
+        assert_eq!(signatory(Some(" ")).display_name(), "Asha Rao");
+        assert_eq!(signatory(None).display_name(), "Asha Rao");
+    }
+}
@@ -0,0 +1,175 @@
+//! Public verification of reports.
+//!
+//! Every report carries a QR code linking to the verification portal with the
+//! report number, its access code and an HMAC of both. The portal can then
+//! answer anyone holding a printout, while links for guessed report numbers
+//! or codes are refused before the code is even compared. Reports delivered
+//! by SMS, WhatsApp or email travel as download links signed the same way,
+//! which also carry their expiry.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bytes of the HMAC kept in a link, enough to make forging hopeless while
+/// keeping the QR code small
+const LINK_TAG_LENGTH: usize = 16;
+
+/// Characters of signature verification codes, without look-alikes
+const CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+#[derive(Clone)]
+pub struct ReportVerifier {
+    secret: Vec<u8>,
+    portal_url: String,
+    download_url: String,
+}
+
+impl ReportVerifier {
+    pub fn new(secret: &str, portal_url: &str, download_url: &str) -> Self {
+        Self {
+            secret: secret.as_bytes().to_vec(),
+            portal_url: portal_url.to_string(),
+            download_url: download_url.to_string(),
+        }
+    }
+
+    fn mac(&self, purpose: &str, parts: &[&str]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(purpose.as_bytes());
+        for part in parts {
+            // Length prefixes keep ("AB", "C") apart from ("A", "BC")
+            mac.update(&(part.len() as u32).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+
+    /// `base` with the query parameters and the truncated HMAC of their values
+    fn signed_url(&self, base: &str, purpose: &str, params: &[(&str, &str)]) -> String {
+        let values: Vec<&str> = params.iter().map(|(_, value)| *value).collect();
+        let tag = self.mac(purpose, &values).finalize().into_bytes();
+        let mut url = base.to_string();
+        for (i, (name, value)) in params.iter().enumerate() {
+            let separator = if i == 0 && !base.contains('?') { '?' } else { '&' };
+            url.push_str(&format!("{}{}={}", separator, name, urlencoding::encode(value)));
+        }
+        format!("{}&s={}", url, URL_SAFE_NO_PAD.encode(&tag[..LINK_TAG_LENGTH]))
+    }
+
+    fn check(&self, purpose: &str, parts: &[&str], signature: &str) -> bool {
+        match URL_SAFE_NO_PAD.decode(signature.trim()) {
+            Ok(tag) if tag.len() == LINK_TAG_LENGTH => {
+                self.mac(purpose, parts).verify_truncated_left(&tag).is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    /// The portal link printed as a QR code on a report
+    pub fn link(&self, report_number: &str, access_code: &str) -> String {
+        self.signed_url(&self.portal_url, "link", &[("r", report_number), ("c", access_code)])
+    }
+
+    /// Whether the signature of a link was made by [`Self::link`]
+    pub fn check_link(&self, report_number: &str, access_code: &str, signature: &str) -> bool {
+        self.check("link", &[report_number, access_code], signature)
+    }
+
+    /// Link to the report file itself, valid until `expires_at` (Unix seconds)
+    pub fn download_link(&self, report_number: &str, access_code: &str, expires_at: i64) -> String {
+        let expires_at = expires_at.to_string();
+        self.signed_url(
+            &self.download_url,
+            "download",
+            &[("r", report_number), ("c", access_code), ("e", &expires_at)],
+        )
+    }
+
+    /// Whether the signature of a link was made by [`Self::download_link`];
+    /// the expiry is for the caller to check
+    pub fn check_download_link(&self, report_number: &str, access_code: &str, expires_at: i64, signature: &str) -> bool {
+        self.check("download", &[report_number, access_code, &expires_at.to_string()], signature)
+    }
+
+    /// Verification code of a signature, derived from the hash of the bytes
+    /// it signed, e.g. `K7M2Q-X9PAB`
+    pub fn signature_code(&self, report_number: &str, signature_hash: &str) -> String {
+        let digest = self.mac("signature", &[report_number, signature_hash]).finalize().into_bytes();
+        // Five bits per character
+        let bits = u64::from_be_bytes(digest[..8].try_into().expect("eight bytes"));
+        let code: String = (0..10)
+            .map(|i| CODE_CHARSET[(bits >> (59 - i * 5) & 0x1F) as usize] as char)
+            .collect();
+        format!("{}-{}", &code[..5], &code[5..])
+    }
+}
+
+/// Initials of a name, such as "J. D." for John Doe
+pub fn initials(first_name: &str, last_name: Option<&str>) -> Option<String> {
+    let initials: Vec<String> = [first_name, last_name.unwrap_or_default()]
+        .iter()
+        .flat_map(|name| name.split_whitespace())
+        .filter_map(|part| part.chars().find(|c| c.is_alphabetic()))
+        .map(|c| format!("{}.", c.to_uppercase()))
+        .collect();
+    (!initials.is_empty()).then(|| initials.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_are_signed() {
+        let verifier = ReportVerifier::new("secret", "https://lab.example.com/verify", "https://lab.example.com/reports/download");
+        let link = verifier.link("RPT-20250118-0001", "K7M2QX9P");
+        assert!(link.starts_with("https://lab.example.com/verify?r=RPT-20250118-0001&c=K7M2QX9P&s="), "{}", link);
+        let signature = link.rsplit("s=").next().unwrap();
+        assert_eq!(signature.len(), 22);
+
+        assert!(verifier.check_link("RPT-20250118-0001", "K7M2QX9P", signature));
+        assert!(!verifier.check_link("RPT-20250118-0002", "K7M2QX9P", signature));
+        assert!(!verifier.check_link("RPT-20250118-0001", "K7M2QX9Q", signature));
+        assert!(!verifier.check_link("RPT-20250118-0001", "K7M2QX9P", &signature[..12]));
+        assert!(!verifier.check_link("RPT-20250118-0001", "K7M2QX9P", "not base64!"));
+        let other = ReportVerifier::new("other secret", "https://lab.example.com/verify", "https://lab.example.com/reports/download");
+        assert!(!other.check_link("RPT-20250118-0001", "K7M2QX9P", signature));
+
+        let with_query = ReportVerifier::new("secret", "https://lab.example.com/portal?lang=en", "");
+        assert!(with_query.link("RPT 1", "A").starts_with("https://lab.example.com/portal?lang=en&r=RPT%201&c=A&s="));
+    }
+
+    #[test]
+    fn test_download_links_are_signed_with_their_expiry() {
+        let verifier = ReportVerifier::new("secret", "https://lab.example.com/verify", "https://lab.example.com/reports/download");
+        let link = verifier.download_link("RPT-20250118-0001", "K7M2QX9P", 1737500000);
+        assert!(
+            link.starts_with("https://lab.example.com/reports/download?r=RPT-20250118-0001&c=K7M2QX9P&e=1737500000&s="),
+            "{}",
+            link
+        );
+        let signature = link.rsplit("s=").next().unwrap();
+
+        assert!(verifier.check_download_link("RPT-20250118-0001", "K7M2QX9P", 1737500000, signature));
+        assert!(!verifier.check_download_link("RPT-20250118-0001", "K7M2QX9P", 1737600000, signature));
+        // Portal and download signatures are not interchangeable
+        assert!(!verifier.check_link("RPT-20250118-0001", "K7M2QX9P", signature));
+    }
+
+    #[test]
+    fn test_signature_codes_and_initials() {
+        let verifier = ReportVerifier::new("secret", "https://lab.example.com/verify", "https://lab.example.com/reports/download");
+        let code = verifier.signature_code("RPT-20250118-0001", "ab12");
+        assert_eq!(code.len(), 11);
+        assert!(code.chars().enumerate().all(|(i, c)| if i == 5 { c == '-' } else { CODE_CHARSET.contains(&(c as u8)) }));
+        assert_eq!(code, verifier.signature_code("RPT-20250118-0001", "ab12"));
+        assert_ne!(code, verifier.signature_code("RPT-20250118-0001", "ab13"));
+
+        assert_eq!(initials("John", Some("Doe")).as_deref(), Some("J. D."));
+        assert_eq!(initials("mary ann", None).as_deref(), Some("M. A."));
+        assert_eq!(initials(" ", Some("")), None);
+    }
+}
@@ -0,0 +1,36 @@
+-- HL7 v2 interface message log
+-- Every outbound result message and the acknowledgment we received is
+-- persisted so that rejected messages can be inspected and resent.
+
+CREATE TABLE hl7_message_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,             -- Organization of the reported result
+
+    -- Message Identification
+    direction VARCHAR(20) NOT NULL,            -- INBOUND, OUTBOUND
+    message_type VARCHAR(20),                  -- e.g. OML^O21, ORU^R01
+    message_control_id VARCHAR(100),
+    peer_address VARCHAR(255),
+
+    -- Content
+    raw_message TEXT NOT NULL,
+    ack_message TEXT,
+    ack_code VARCHAR(10),                      -- AA, AE, AR
+
+    -- Processing
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING', -- PENDING, ACKNOWLEDGED, REJECTED, FAILED
+    error_message TEXT,
+    related_entity_id UUID,                    -- Result the message reports
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+
+    -- Audit
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    processed_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT valid_hl7_direction CHECK (direction IN ('INBOUND', 'OUTBOUND'))
+);
+
+CREATE INDEX idx_hl7_message_log_organization ON hl7_message_log(organization_id);
+CREATE INDEX idx_hl7_message_log_control_id ON hl7_message_log(message_control_id);
+CREATE INDEX idx_hl7_message_log_status ON hl7_message_log(status);
+CREATE INDEX idx_hl7_message_log_created ON hl7_message_log(created_at);
@@ -0,0 +1,14 @@
+-- Patient-aware reference ranges
+-- Neonatal and paediatric ranges are defined in days or months, and every
+-- result records the range it was interpreted against for audit.
+
+ALTER TABLE reference_range
+    ADD COLUMN age_unit VARCHAR(10) NOT NULL DEFAULT 'YEARS'; -- DAYS, MONTHS, YEARS
+
+ALTER TABLE reference_range
+    ADD CONSTRAINT chk_reference_range_age_unit CHECK (age_unit IN ('DAYS', 'MONTHS', 'YEARS'));
+
+ALTER TABLE test_result
+    ADD COLUMN reference_range_id UUID REFERENCES reference_range(id);
+
+CREATE INDEX idx_test_result_reference_range ON test_result(reference_range_id) WHERE reference_range_id IS NOT NULL;
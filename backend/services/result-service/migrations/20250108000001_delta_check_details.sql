@@ -0,0 +1,6 @@
+-- Delta check explanation
+-- Records the previous result compared against, the limits applied and
+-- which of them fired, so reviewers can see why a delta flag was raised.
+
+ALTER TABLE test_result
+    ADD COLUMN delta_check_details JSONB;
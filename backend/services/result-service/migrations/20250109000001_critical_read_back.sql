@@ -0,0 +1,10 @@
+-- Critical value read-back
+-- The clinician repeats the value back before a critical notification counts
+-- as acknowledged; who repeated it, when and what they said are kept for audit.
+
+ALTER TABLE critical_result_notification
+    ADD COLUMN read_back_by VARCHAR(200),
+    ADD COLUMN read_back_at TIMESTAMP,
+    ADD COLUMN read_back_value VARCHAR(500),
+    ADD COLUMN read_back_confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN read_back_recorded_by UUID;
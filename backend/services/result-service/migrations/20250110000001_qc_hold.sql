@@ -0,0 +1,11 @@
+-- QC-gated release
+-- Results measured after a rejected QC run are held from auto-verification
+-- and release until a corrective action for that run is completed.
+
+ALTER TABLE test_result
+    ADD COLUMN qc_hold BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN qc_hold_qc_result_id UUID,
+    ADD COLUMN qc_hold_reason TEXT;
+
+CREATE INDEX idx_test_result_qc_hold ON test_result(qc_hold_qc_result_id) WHERE qc_hold = TRUE;
+CREATE INDEX idx_test_result_instrument_date ON test_result(test_id, instrument_id, result_date);
@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('hl7_message_log');
+SELECT enable_tenant_isolation('test_result');
+
+-- Ranges and rules without an organization apply to every lab
+SELECT enable_tenant_isolation('auto_verification_rule', shared_rows => TRUE);
+SELECT enable_tenant_isolation('reference_range', shared_rows => TRUE);
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('critical_result_notification', 'result_id', 'test_result');
+SELECT enable_parent_isolation('result_audit_log', 'result_id', 'test_result');
@@ -1,6 +1,8 @@
 use async_graphql::{Context, Object, Result, SimpleObject, InputObject, Enum, ID};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 
 use crate::domain::*;
 use crate::service::ResultService;
@@ -24,17 +26,30 @@ pub struct TestResultGQL {
     pub result_unit: Option<String>,
     pub result_type: String,
     pub reference_range_text: Option<String>,
+    /// Reference range the result was interpreted against
+    pub reference_range_id: Option<ID>,
     pub interpretation: InterpretationEnum,
     pub clinical_interpretation: Option<String>,
     pub critical_flag: CriticalFlagEnum,
     pub delta_flag: DeltaFlagEnum,
+    /// JSON: previous result compared against, limits applied and which fired
+    pub delta_check_details: Option<String>,
     pub is_abnormal: bool,
     pub is_critical: bool,
     pub result_status: ResultStatusEnum,
     pub verification_status: VerificationStatusEnum,
     pub entry_method: Option<String>,
+    /// JSON array of rule traces: rule, outcome, expression and the inputs it saw
+    pub verification_rules_passed: Option<String>,
+    pub verification_rules_failed: Option<String>,
+    pub qc_passed: Option<bool>,
+    /// Held from auto-verification and release by an out of control QC run
+    pub qc_hold: bool,
+    pub qc_hold_qc_result_id: Option<ID>,
+    pub qc_hold_reason: Option<String>,
     pub result_date: String,
     pub reported_date: Option<String>,
+    pub approval_date: Option<String>,
     pub technician_notes: Option<String>,
     pub pathologist_notes: Option<String>,
     pub is_corrected: bool,
@@ -59,17 +74,26 @@ impl From<TestResult> for TestResultGQL {
             result_unit: result.result_unit,
             result_type: result.result_type,
             reference_range_text: result.reference_range_text,
+            reference_range_id: result.reference_range_id.map(|id| id.to_string().into()),
             interpretation: result.interpretation.into(),
             clinical_interpretation: result.clinical_interpretation,
             critical_flag: result.critical_flag.into(),
             delta_flag: result.delta_flag.into(),
+            delta_check_details: result.delta_check_details.map(|v| v.to_string()),
             is_abnormal: result.is_abnormal,
             is_critical: result.is_critical,
             result_status: result.result_status.into(),
             verification_status: result.verification_status.into(),
             entry_method: result.entry_method,
+            verification_rules_passed: result.verification_rules_passed.map(|v| v.to_string()),
+            verification_rules_failed: result.verification_rules_failed.map(|v| v.to_string()),
+            qc_passed: result.qc_passed,
+            qc_hold: result.qc_hold,
+            qc_hold_qc_result_id: result.qc_hold_qc_result_id.map(|id| id.to_string().into()),
+            qc_hold_reason: result.qc_hold_reason,
             result_date: result.result_date.to_rfc3339(),
             reported_date: result.reported_date.map(|dt| dt.to_rfc3339()),
+            approval_date: result.approval_date.map(|dt| dt.to_rfc3339()),
             technician_notes: result.technician_notes,
             pathologist_notes: result.pathologist_notes,
             is_corrected: result.is_corrected,
@@ -90,6 +114,12 @@ pub struct CriticalResultNotificationGQL {
     pub acknowledged: bool,
     pub acknowledged_by: Option<String>,
     pub acknowledgment_date: Option<String>,
+    pub acknowledgment_method: Option<String>,
+    pub read_back_by: Option<String>,
+    pub read_back_at: Option<String>,
+    pub read_back_value: Option<String>,
+    pub read_back_confirmed: bool,
+    pub read_back_recorded_by: Option<ID>,
     pub notes: Option<String>,
 }
 
@@ -104,11 +134,61 @@ impl From<CriticalResultNotification> for CriticalResultNotificationGQL {
             acknowledged: notification.acknowledged,
             acknowledged_by: notification.acknowledged_by,
             acknowledgment_date: notification.acknowledgment_date.map(|dt| dt.to_rfc3339()),
+            acknowledgment_method: notification.acknowledgment_method,
+            read_back_by: notification.read_back_by,
+            read_back_at: notification.read_back_at.map(|dt| dt.to_rfc3339()),
+            read_back_value: notification.read_back_value,
+            read_back_confirmed: notification.read_back_confirmed,
+            read_back_recorded_by: notification.read_back_recorded_by.map(|id| id.to_string().into()),
             notes: notification.notes,
         }
     }
 }
 
+#[derive(SimpleObject)]
+pub struct AutoVerificationRuleGQL {
+    pub id: ID,
+    pub rule_code: String,
+    pub rule_name: String,
+    pub test_id: Option<ID>,
+    pub department: Option<String>,
+    pub is_global: bool,
+    pub rule_type: String,
+    /// JSON rule definition; EXPRESSION rules hold an `expression`
+    pub rule_definition: String,
+    pub min_value: Option<String>,
+    pub max_value: Option<String>,
+    pub delta_percentage_limit: Option<String>,
+    pub delta_absolute_limit: Option<String>,
+    pub priority: i32,
+    pub is_blocking: bool,
+    pub is_active: bool,
+    pub created_at: String,
+}
+
+impl From<AutoVerificationRule> for AutoVerificationRuleGQL {
+    fn from(rule: AutoVerificationRule) -> Self {
+        Self {
+            id: rule.id.to_string().into(),
+            rule_code: rule.rule_code,
+            rule_name: rule.rule_name,
+            test_id: rule.test_id.map(|id| id.to_string().into()),
+            department: rule.department,
+            is_global: rule.is_global,
+            rule_type: rule.rule_type,
+            rule_definition: rule.rule_definition.to_string(),
+            min_value: rule.min_value.map(|v| v.to_string()),
+            max_value: rule.max_value.map(|v| v.to_string()),
+            delta_percentage_limit: rule.delta_percentage_limit.map(|v| v.to_string()),
+            delta_absolute_limit: rule.delta_absolute_limit.map(|v| v.to_string()),
+            priority: rule.priority,
+            is_blocking: rule.is_blocking,
+            is_active: rule.is_active,
+            created_at: rule.created_at.to_rfc3339(),
+        }
+    }
+}
+
 // ============================================================================
 // Enums
 // ============================================================================
@@ -236,6 +316,45 @@ impl From<InterpretationType> for InterpretationEnum {
     }
 }
 
+#[derive(SimpleObject)]
+pub struct Hl7MessageLogGQL {
+    pub id: ID,
+    pub direction: String,
+    pub message_type: Option<String>,
+    pub message_control_id: Option<String>,
+    pub peer_address: Option<String>,
+    pub raw_message: String,
+    pub ack_message: Option<String>,
+    pub ack_code: Option<String>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub related_entity_id: Option<ID>,
+    pub attempt_count: i32,
+    pub created_at: String,
+    pub processed_at: Option<String>,
+}
+
+impl From<Hl7MessageLog> for Hl7MessageLogGQL {
+    fn from(log: Hl7MessageLog) -> Self {
+        Self {
+            id: log.id.to_string().into(),
+            direction: log.direction,
+            message_type: log.message_type,
+            message_control_id: log.message_control_id,
+            peer_address: log.peer_address,
+            raw_message: log.raw_message,
+            ack_message: log.ack_message,
+            ack_code: log.ack_code,
+            status: log.status,
+            error_message: log.error_message,
+            related_entity_id: log.related_entity_id.map(|id| id.to_string().into()),
+            attempt_count: log.attempt_count,
+            created_at: log.created_at.to_rfc3339(),
+            processed_at: log.processed_at.map(|dt| dt.to_rfc3339()),
+        }
+    }
+}
+
 // ============================================================================
 // Input Types
 // ============================================================================
@@ -376,6 +495,33 @@ impl TryFrom<CorrectResultInputGQL> for CorrectResultInput {
     }
 }
 
+#[derive(InputObject)]
+pub struct AcknowledgeCriticalNotificationInputGQL {
+    pub notification_id: ID,
+    pub acknowledged_by: String,
+    pub method: String,
+    /// The value exactly as the receiver repeated it back
+    pub read_back_value: String,
+    pub read_back_by: Option<String>,
+}
+
+impl TryFrom<AcknowledgeCriticalNotificationInputGQL> for AcknowledgeCriticalNotificationInput {
+    type Error = String;
+
+    fn try_from(input: AcknowledgeCriticalNotificationInputGQL) -> std::result::Result<Self, Self::Error> {
+        let notification_id = Uuid::parse_str(&input.notification_id)
+            .map_err(|e| format!("Invalid notification_id: {}", e))?;
+
+        Ok(AcknowledgeCriticalNotificationInput {
+            notification_id,
+            acknowledged_by: input.acknowledged_by,
+            acknowledgment_method: input.method,
+            read_back_value: input.read_back_value,
+            read_back_by: input.read_back_by,
+        })
+    }
+}
+
 #[derive(InputObject)]
 pub struct RecordCriticalNotificationInputGQL {
     pub result_id: ID,
@@ -404,6 +550,64 @@ impl TryFrom<RecordCriticalNotificationInputGQL> for RecordCriticalNotificationI
     }
 }
 
+#[derive(InputObject)]
+pub struct CreateAutoVerificationRuleInputGQL {
+    pub rule_code: String,
+    pub rule_name: String,
+    pub test_id: Option<ID>,
+    pub department: Option<String>,
+    pub is_global: Option<bool>,
+    /// EXPRESSION, RANGE_CHECK, DELTA_CHECK, QC_CHECK or CRITICAL_CHECK
+    pub rule_type: String,
+    /// JSON object, e.g. {"expression": "value between 3.5 and 5.1 and not specimen.hemolyzed"}
+    pub rule_definition: Option<String>,
+    pub min_value: Option<String>,
+    pub max_value: Option<String>,
+    pub delta_percentage_limit: Option<String>,
+    pub delta_absolute_limit: Option<String>,
+    pub priority: Option<i32>,
+    pub is_blocking: Option<bool>,
+}
+
+fn parse_decimal(value: Option<String>, field: &str) -> std::result::Result<Option<rust_decimal::Decimal>, String> {
+    value
+        .map(|v| v.parse::<rust_decimal::Decimal>().map_err(|e| format!("Invalid {}: {}", field, e)))
+        .transpose()
+}
+
+impl TryFrom<CreateAutoVerificationRuleInputGQL> for CreateAutoVerificationRuleInput {
+    type Error = String;
+
+    fn try_from(input: CreateAutoVerificationRuleInputGQL) -> std::result::Result<Self, Self::Error> {
+        let test_id = input.test_id
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| format!("Invalid test_id: {}", e))?;
+
+        let rule_definition = match input.rule_definition {
+            Some(definition) => serde_json::from_str(&definition)
+                .map_err(|e| format!("Invalid rule_definition JSON: {}", e))?,
+            None => serde_json::json!({}),
+        };
+
+        Ok(CreateAutoVerificationRuleInput {
+            rule_code: input.rule_code,
+            rule_name: input.rule_name,
+            test_id,
+            department: input.department,
+            is_global: input.is_global.unwrap_or(false),
+            rule_type: input.rule_type,
+            rule_definition,
+            min_value: parse_decimal(input.min_value, "min_value")?,
+            max_value: parse_decimal(input.max_value, "max_value")?,
+            delta_percentage_limit: parse_decimal(input.delta_percentage_limit, "delta_percentage_limit")?,
+            delta_absolute_limit: parse_decimal(input.delta_absolute_limit, "delta_absolute_limit")?,
+            priority: input.priority.unwrap_or(100),
+            is_blocking: input.is_blocking.unwrap_or(false),
+        })
+    }
+}
+
 // ============================================================================
 // GraphQL Query Root
 // ============================================================================
@@ -413,6 +617,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get result by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn result(&self, ctx: &Context<'_>, id: ID) -> Result<Option<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
         let result_id = Uuid::parse_str(&id)?;
@@ -424,6 +629,7 @@ impl QueryRoot {
     }
 
     /// Get result by result number
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn result_by_number(&self, ctx: &Context<'_>, result_number: String) -> Result<Option<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
 
@@ -434,6 +640,7 @@ impl QueryRoot {
     }
 
     /// Get results by patient
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn results_by_patient(&self, ctx: &Context<'_>, patient_id: ID, limit: Option<i32>) -> Result<Vec<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
         let id = Uuid::parse_str(&patient_id)?;
@@ -442,6 +649,7 @@ impl QueryRoot {
     }
 
     /// Get results by order
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn results_by_order(&self, ctx: &Context<'_>, order_id: ID) -> Result<Vec<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
         let id = Uuid::parse_str(&order_id)?;
@@ -450,6 +658,7 @@ impl QueryRoot {
     }
 
     /// Get results by sample
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn results_by_sample(&self, ctx: &Context<'_>, sample_id: ID) -> Result<Vec<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
         let id = Uuid::parse_str(&sample_id)?;
@@ -458,28 +667,68 @@ impl QueryRoot {
     }
 
     /// Get pending verification results
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn pending_verification(&self, ctx: &Context<'_>, limit: Option<i32>) -> Result<Vec<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
-        let org_id = Uuid::nil(); // TODO: Get from auth context
+        let org_id = ctx.organization_id()?;
         let results = service.get_pending_verification(org_id, limit.unwrap_or(50) as i64).await?;
         Ok(results.into_iter().map(|r| r.into()).collect())
     }
 
     /// Get critical results
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn critical_results(&self, ctx: &Context<'_>, limit: Option<i32>) -> Result<Vec<TestResultGQL>> {
         let service = ctx.data::<ResultService>()?;
-        let org_id = Uuid::nil(); // TODO: Get from auth context
+        let org_id = ctx.organization_id()?;
         let results = service.get_critical_results(org_id, limit.unwrap_or(50) as i64).await?;
         Ok(results.into_iter().map(|r| r.into()).collect())
     }
 
+    /// Get results held by an out of control QC run
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
+    async fn qc_held_results(&self, ctx: &Context<'_>, limit: Option<i32>) -> Result<Vec<TestResultGQL>> {
+        let service = ctx.data::<ResultService>()?;
+        let org_id = ctx.organization_id()?;
+        let results = service.get_qc_held_results(org_id, limit.unwrap_or(50) as i64).await?;
+        Ok(results.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Patient results measured since the last good QC before an out of control QC result, for re-testing
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
+    async fn qc_lookback_results(&self, ctx: &Context<'_>, qc_result_id: ID, limit: Option<i32>) -> Result<Vec<TestResultGQL>> {
+        let service = ctx.data::<ResultService>()?;
+        let id = Uuid::parse_str(&qc_result_id)?;
+        let results = service.get_qc_lookback_results(id, limit.unwrap_or(500) as i64).await?;
+        Ok(results.into_iter().map(|r| r.into()).collect())
+    }
+
     /// Get critical notifications for a result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
     async fn critical_notifications(&self, ctx: &Context<'_>, result_id: ID) -> Result<Vec<CriticalResultNotificationGQL>> {
         let service = ctx.data::<ResultService>()?;
         let id = Uuid::parse_str(&result_id)?;
         let notifications = service.get_critical_notifications(id).await?;
         Ok(notifications.into_iter().map(|n| n.into()).collect())
     }
+
+    /// List outbound HL7 messages, optionally filtered by status (PENDING, ACKNOWLEDGED, REJECTED, FAILED)
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn hl7_messages(&self, ctx: &Context<'_>, status: Option<String>, limit: Option<i32>) -> Result<Vec<Hl7MessageLogGQL>> {
+        let service = ctx.data::<ResultService>()?;
+        let org_id = ctx.organization_id()?;
+        let messages = service.get_hl7_messages(org_id, status.as_deref(), limit.unwrap_or(50) as i64).await?;
+        Ok(messages.into_iter().map(|m| m.into()).collect())
+    }
+
+    /// List auto-verification rules, optionally those that apply to a test
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_READ)")]
+    async fn auto_verification_rules(&self, ctx: &Context<'_>, test_id: Option<ID>) -> Result<Vec<AutoVerificationRuleGQL>> {
+        let service = ctx.data::<ResultService>()?;
+        let test_id = test_id.map(|id| Uuid::parse_str(&id)).transpose()?;
+        let org_id = ctx.organization_id()?;
+        let rules = service.get_auto_verification_rules(test_id, org_id).await?;
+        Ok(rules.into_iter().map(|r| r.into()).collect())
+    }
 }
 
 // ============================================================================
@@ -491,12 +740,12 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create new result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_CREATE)")]
     async fn create_result(&self, ctx: &Context<'_>, input: CreateResultInputGQL) -> Result<TestResultGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get org_id and user_id from auth context
-        let org_id = Uuid::nil();
-        let user_id = Uuid::nil();
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -506,11 +755,11 @@ impl MutationRoot {
     }
 
     /// Update result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_UPDATE)")]
     async fn update_result(&self, ctx: &Context<'_>, input: UpdateResultInputGQL) -> Result<TestResultGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -520,11 +769,11 @@ impl MutationRoot {
     }
 
     /// Verify result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_VERIFY)")]
     async fn verify_result(&self, ctx: &Context<'_>, input: VerifyResultInputGQL) -> Result<TestResultGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -534,11 +783,11 @@ impl MutationRoot {
     }
 
     /// Approve result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_APPROVE)")]
     async fn approve_result(&self, ctx: &Context<'_>, input: ApproveResultInputGQL) -> Result<TestResultGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -547,12 +796,21 @@ impl MutationRoot {
         Ok(result.into())
     }
 
+    /// Release results held by a QC result once its corrective action is completed
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_RELEASE)")]
+    async fn release_qc_holds(&self, ctx: &Context<'_>, qc_result_id: ID) -> Result<Vec<TestResultGQL>> {
+        let service = ctx.data::<ResultService>()?;
+        let id = Uuid::parse_str(&qc_result_id)?;
+        let results = service.release_qc_holds(id).await?;
+        Ok(results.into_iter().map(|r| r.into()).collect())
+    }
+
     /// Correct result
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_CORRECT)")]
     async fn correct_result(&self, ctx: &Context<'_>, input: CorrectResultInputGQL) -> Result<TestResultGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -562,11 +820,11 @@ impl MutationRoot {
     }
 
     /// Record critical notification
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_CRITICAL_NOTIFY)")]
     async fn record_critical_notification(&self, ctx: &Context<'_>, input: RecordCriticalNotificationInputGQL) -> Result<CriticalResultNotificationGQL> {
         let service = ctx.data::<ResultService>()?;
 
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -575,18 +833,50 @@ impl MutationRoot {
         Ok(notification.into())
     }
 
-    /// Acknowledge critical notification
+    /// Acknowledge critical notification with the receiver's read-back
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_CRITICAL_NOTIFY)")]
     async fn acknowledge_critical_notification(
         &self,
         ctx: &Context<'_>,
-        notification_id: ID,
-        acknowledged_by: String,
-        method: String
+        input: AcknowledgeCriticalNotificationInputGQL
     ) -> Result<CriticalResultNotificationGQL> {
         let service = ctx.data::<ResultService>()?;
-        let id = Uuid::parse_str(&notification_id)?;
 
-        let notification = service.acknowledge_critical_notification(id, &acknowledged_by, &method).await?;
+        let user_id = ctx.user_id()?;
+
+        let domain_input = input.try_into()
+            .map_err(|e: String| async_graphql::Error::new(e))?;
+
+        let notification = service.acknowledge_critical_notification(domain_input, user_id).await?;
         Ok(notification.into())
     }
+
+    /// Resend an outbound HL7 message that was rejected or not delivered
+    #[graphql(guard = "PermissionGuard::new(permissions::HL7_MANAGE)")]
+    async fn replay_hl7_message(&self, ctx: &Context<'_>, id: ID) -> Result<Hl7MessageLogGQL> {
+        let service = ctx.data::<ResultService>()?;
+        let org_id = ctx.organization_id()?;
+        let log_id = Uuid::parse_str(&id)?;
+        let log = service.replay_hl7_message(log_id, org_id).await?;
+        Ok(log.into())
+    }
+
+    /// Create an auto-verification rule; the rule is compiled and rejected if invalid
+    #[graphql(guard = "PermissionGuard::new(permissions::RESULT_RULE_MANAGE)")]
+    async fn create_auto_verification_rule(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateAutoVerificationRuleInputGQL,
+    ) -> Result<AutoVerificationRuleGQL> {
+        let service = ctx.data::<ResultService>()?;
+
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+
+        let domain_input = input.try_into()
+            .map_err(|e: String| async_graphql::Error::new(e))?;
+
+        let rule = service.create_auto_verification_rule(domain_input, org_id, user_id).await?;
+        Ok(rule.into())
+    }
 }
@@ -6,11 +6,19 @@ pub struct Config {
     pub database_max_connections: u32,
     pub host: String,
     pub port: u16,
+    pub jwt_secret: String,
     pub enable_caching: bool,
     pub enable_events: bool,
+    pub kafka_brokers: String,
     pub patient_service_url: String,
     pub sample_service_url: String,
     pub order_service_url: String,
+    pub qc_service_url: String,
+    pub hl7_outbound_enabled: bool,
+    pub hl7_outbound_address: String,
+    pub hl7_sending_facility: String,
+    pub hl7_receiving_application: String,
+    pub hl7_receiving_facility: String,
 }
 
 impl Config {
@@ -19,11 +27,19 @@ impl Config {
             .set_default("database_max_connections", 32)?
             .set_default("host", "0.0.0.0")?
             .set_default("port", 8084)?
+            .set_default("jwt_secret", "development-secret-change-in-production")?
             .set_default("enable_caching", false)?
             .set_default("enable_events", false)?
+            .set_default("kafka_brokers", "localhost:9092")?
             .set_default("patient_service_url", "http://localhost:8081")?
             .set_default("sample_service_url", "http://localhost:8082")?
             .set_default("order_service_url", "http://localhost:8083")?
+            .set_default("qc_service_url", "http://localhost:8088")?
+            .set_default("hl7_outbound_enabled", false)?
+            .set_default("hl7_outbound_address", "localhost:2576")?
+            .set_default("hl7_sending_facility", "LAB")?
+            .set_default("hl7_receiving_application", "HIS")?
+            .set_default("hl7_receiving_facility", "")?
             .add_source(config::Environment::default().separator("__"));
 
         builder.build()?.try_deserialize()
@@ -35,11 +51,19 @@ impl Config {
             database_max_connections: 32,
             host: "0.0.0.0".to_string(),
             port: 8084,
+            jwt_secret: "development-secret-change-in-production".to_string(),
             enable_caching: false,
             enable_events: false,
+            kafka_brokers: "localhost:9092".to_string(),
             patient_service_url: "http://localhost:8081".to_string(),
             sample_service_url: "http://localhost:8082".to_string(),
             order_service_url: "http://localhost:8083".to_string(),
+            qc_service_url: "http://localhost:8088".to_string(),
+            hl7_outbound_enabled: false,
+            hl7_outbound_address: "localhost:2576".to_string(),
+            hl7_sending_facility: "LAB".to_string(),
+            hl7_receiving_application: "HIS".to_string(),
+            hl7_receiving_facility: String::new(),
         }
     }
 }
@@ -0,0 +1,287 @@
+//! Delta checks: comparison of a result with the patient's previous result
+//! for the same test.
+//!
+//! Limits come from the test's `DELTA_CHECK` auto-verification rule. The
+//! percent and absolute limits are the rule's `delta_percentage_limit` and
+//! `delta_absolute_limit`; the remaining parameters live in
+//! `rule_definition`:
+//!
+//! ```json
+//! {
+//!   "lookback_days": 30,
+//!   "rate_per_day_limit": 0.5,
+//!   "rcv": { "analytical_cv": 1.6, "within_subject_cv": 4.6, "z": 1.96 }
+//! }
+//! ```
+//!
+//! Tests without a delta rule fall back to a 50% change limit.
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::domain::*;
+
+pub const DEFAULT_PERCENT_LIMIT: f64 = 50.0;
+
+/// Two-sided 95% z-score used for the RCV when the rule does not set one
+const DEFAULT_RCV_Z: f64 = 1.96;
+
+/// Results taken within the same hour are compared as one hour apart, so
+/// the rate of change stays finite for repeats
+const MIN_ELAPSED_DAYS: f64 = 1.0 / 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeltaLimitKind {
+    Percent,
+    Absolute,
+    RatePerDay,
+    Rcv,
+}
+
+impl DeltaLimitKind {
+    fn describe(&self, threshold: f64, observed: f64) -> String {
+        match self {
+            DeltaLimitKind::Percent => format!("change of {:.1}% exceeds the {}% limit", observed, threshold),
+            DeltaLimitKind::Absolute => format!("change of {} exceeds the absolute limit of {}", round(observed), threshold),
+            DeltaLimitKind::RatePerDay => format!("rate of {}/day exceeds the limit of {}/day", round(observed), threshold),
+            DeltaLimitKind::Rcv => format!("change of {:.1}% exceeds the reference change value of {:.1}%", observed, threshold),
+        }
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+/// Reference change value in percent: the smallest difference between two
+/// serial results that exceeds analytical and within-subject variation.
+pub fn reference_change_value(analytical_cv: f64, within_subject_cv: f64, z: f64) -> f64 {
+    z * 2f64.sqrt() * (analytical_cv.powi(2) + within_subject_cv.powi(2)).sqrt()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeltaLimits {
+    pub percent: Option<f64>,
+    pub absolute: Option<f64>,
+    pub rate_per_day: Option<f64>,
+    pub rcv_percent: Option<f64>,
+    /// Previous results older than this are not compared against
+    pub lookback_days: Option<i64>,
+}
+
+fn positive(definition: &serde_json::Value, key: &str) -> Result<Option<f64>, String> {
+    match definition.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => match value.as_f64() {
+            Some(number) if number > 0.0 => Ok(Some(number)),
+            _ => Err(format!("'{}' must be a positive number", key)),
+        },
+    }
+}
+
+fn positive_decimal(value: Option<Decimal>, name: &str) -> Result<Option<f64>, String> {
+    match value.map(|d| d.to_f64()) {
+        None => Ok(None),
+        Some(Some(number)) if number > 0.0 => Ok(Some(number)),
+        Some(_) => Err(format!("{} must be positive", name)),
+    }
+}
+
+impl DeltaLimits {
+    pub fn default_limits() -> Self {
+        Self {
+            percent: Some(DEFAULT_PERCENT_LIMIT),
+            ..Self::default()
+        }
+    }
+
+    /// Builds the limits of a `DELTA_CHECK` rule, rejecting malformed parameters
+    pub fn from_definition(
+        definition: &serde_json::Value,
+        delta_percentage_limit: Option<Decimal>,
+        delta_absolute_limit: Option<Decimal>,
+    ) -> Result<Self, String> {
+        let lookback_days = match definition.get("lookback_days") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(value) => match value.as_i64() {
+                Some(days) if days > 0 => Some(days),
+                _ => return Err("'lookback_days' must be a positive whole number".to_string()),
+            },
+        };
+
+        let rcv_percent = match definition.get("rcv") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(rcv) => {
+                let analytical_cv = positive(rcv, "analytical_cv")?
+                    .ok_or_else(|| "'rcv.analytical_cv' is required".to_string())?;
+                let within_subject_cv = positive(rcv, "within_subject_cv")?
+                    .ok_or_else(|| "'rcv.within_subject_cv' is required".to_string())?;
+                let z = positive(rcv, "z")?.unwrap_or(DEFAULT_RCV_Z);
+                Some(reference_change_value(analytical_cv, within_subject_cv, z))
+            }
+        };
+
+        let limits = Self {
+            percent: positive_decimal(delta_percentage_limit, "delta_percentage_limit")?,
+            absolute: positive_decimal(delta_absolute_limit, "delta_absolute_limit")?,
+            rate_per_day: positive(definition, "rate_per_day_limit")?,
+            rcv_percent,
+            lookback_days,
+        };
+
+        if limits.percent.is_none()
+            && limits.absolute.is_none()
+            && limits.rate_per_day.is_none()
+            && limits.rcv_percent.is_none()
+        {
+            return Err("Delta rule needs a percent, absolute, rate_per_day_limit or rcv limit".to_string());
+        }
+
+        Ok(limits)
+    }
+
+    pub fn from_rule(rule: &AutoVerificationRule) -> Result<Self, String> {
+        Self::from_definition(&rule.rule_definition, rule.delta_percentage_limit, rule.delta_absolute_limit)
+    }
+
+    /// Earliest previous result date that may be compared against
+    pub fn lookback_start(&self, result_date: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.lookback_days.map(|days| result_date - Duration::days(days))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitBreach {
+    pub limit: DeltaLimitKind,
+    pub threshold: f64,
+    pub observed: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeltaEvaluation {
+    pub delta_absolute: f64,
+    /// Undefined when the previous value is zero
+    pub delta_percent: Option<f64>,
+    pub elapsed_days: f64,
+    pub rate_per_day: f64,
+    pub breaches: Vec<LimitBreach>,
+}
+
+/// Compares a result with the previous one against every configured limit
+pub fn evaluate(current: f64, previous: f64, elapsed_days: f64, limits: &DeltaLimits) -> DeltaEvaluation {
+    let delta_absolute = current - previous;
+    let delta_percent = (previous != 0.0).then(|| delta_absolute / previous.abs() * 100.0);
+    let elapsed_days = elapsed_days.max(MIN_ELAPSED_DAYS);
+    let rate_per_day = delta_absolute / elapsed_days;
+
+    let mut breaches = Vec::new();
+    let mut check = |limit: DeltaLimitKind, threshold: Option<f64>, observed: Option<f64>| {
+        if let (Some(threshold), Some(observed)) = (threshold, observed) {
+            if observed.abs() > threshold {
+                breaches.push(LimitBreach {
+                    limit,
+                    threshold,
+                    observed: observed.abs(),
+                });
+            }
+        }
+    };
+
+    check(DeltaLimitKind::Percent, limits.percent, delta_percent);
+    check(DeltaLimitKind::Absolute, limits.absolute, Some(delta_absolute));
+    check(DeltaLimitKind::RatePerDay, limits.rate_per_day, Some(rate_per_day));
+    check(DeltaLimitKind::Rcv, limits.rcv_percent, delta_percent);
+
+    DeltaEvaluation {
+        delta_absolute,
+        delta_percent,
+        elapsed_days,
+        rate_per_day,
+        breaches,
+    }
+}
+
+impl DeltaEvaluation {
+    pub fn flag(&self) -> DeltaFlag {
+        if self.breaches.is_empty() {
+            DeltaFlag::Normal
+        } else if self.delta_absolute > 0.0 {
+            DeltaFlag::SignificantIncrease
+        } else {
+            DeltaFlag::SignificantDecrease
+        }
+    }
+
+    pub fn explanation(&self, rule_code: Option<&str>) -> String {
+        let source = rule_code
+            .map(|code| format!("rule {}", code))
+            .unwrap_or_else(|| "default limit".to_string());
+
+        if self.breaches.is_empty() {
+            return format!("Change within limits ({})", source);
+        }
+
+        let reasons: Vec<String> = self.breaches
+            .iter()
+            .map(|breach| breach.limit.describe(breach.threshold, breach.observed))
+            .collect();
+        format!("Delta check failed ({}): {}", source, reasons.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rcv_from_biological_variation() {
+        // Sodium: CVa 0.5%, CVi 0.6% -> about 2.2%
+        let rcv = reference_change_value(0.5, 0.6, 1.96);
+        assert!((rcv - 2.165).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_limits_fire_independently() {
+        let definition = serde_json::json!({
+            "rate_per_day_limit": 10,
+            "rcv": { "analytical_cv": 2.0, "within_subject_cv": 5.0 }
+        });
+        let limits = DeltaLimits::from_definition(&definition, Some(Decimal::from(50)), Some(Decimal::from(30))).unwrap();
+
+        // +20% over ten days: within percent/absolute/rate, but above the ~14.9% RCV
+        let outcome = evaluate(120.0, 100.0, 10.0, &limits);
+        assert_eq!(outcome.flag(), DeltaFlag::SignificantIncrease);
+        assert_eq!(outcome.breaches.len(), 1);
+        assert_eq!(outcome.breaches[0].limit, DeltaLimitKind::Rcv);
+
+        // The same change within a day also breaches the rate limit
+        let outcome = evaluate(120.0, 100.0, 0.5, &limits);
+        assert!(outcome.breaches.iter().any(|b| b.limit == DeltaLimitKind::RatePerDay));
+        assert!(outcome.explanation(Some("DELTA_GLU")).contains("DELTA_GLU"));
+    }
+
+    #[test]
+    fn test_zero_previous_value_still_checks_absolute_change() {
+        let limits = DeltaLimits {
+            percent: Some(50.0),
+            absolute: Some(2.0),
+            ..DeltaLimits::default()
+        };
+
+        let outcome = evaluate(-5.0, 0.0, 1.0, &limits);
+        assert_eq!(outcome.delta_percent, None);
+        assert_eq!(outcome.flag(), DeltaFlag::SignificantDecrease);
+        assert_eq!(outcome.breaches[0].limit, DeltaLimitKind::Absolute);
+    }
+
+    #[test]
+    fn test_invalid_definitions_are_rejected() {
+        let empty = serde_json::json!({});
+        assert!(DeltaLimits::from_definition(&empty, None, None).is_err());
+        assert!(DeltaLimits::from_definition(&serde_json::json!({"lookback_days": 0}), Some(Decimal::from(10)), None).is_err());
+        assert!(DeltaLimits::from_definition(&serde_json::json!({"rcv": {"analytical_cv": 2}}), None, None).is_err());
+    }
+}
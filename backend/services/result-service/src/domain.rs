@@ -1,8 +1,9 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use common::error::{Error, Result};
+use common::types::Gender;
 
 // ============================================================================
 // Enums
@@ -50,7 +51,7 @@ pub enum DeltaFlag {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
-#[sqlx(type_name = "interpretation_type", rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "interpretation", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum InterpretationType {
     Normal,
     AbnormalLow,
@@ -92,6 +93,7 @@ pub struct TestResult {
     pub reference_range_text: Option<String>,
     pub reference_range_min: Option<rust_decimal::Decimal>,
     pub reference_range_max: Option<rust_decimal::Decimal>,
+    pub reference_range_id: Option<Uuid>,
 
     // Interpretation
     pub interpretation: InterpretationType,
@@ -108,6 +110,7 @@ pub struct TestResult {
     pub previous_result_date: Option<DateTime<Utc>>,
     pub delta_percentage: Option<rust_decimal::Decimal>,
     pub delta_absolute: Option<rust_decimal::Decimal>,
+    pub delta_check_details: Option<serde_json::Value>,
 
     // Status and Workflow
     pub result_status: ResultStatus,
@@ -139,6 +142,9 @@ pub struct TestResult {
     pub qc_lot_number: Option<String>,
     pub qc_passed: Option<bool>,
     pub qc_notes: Option<String>,
+    pub qc_hold: bool,
+    pub qc_hold_qc_result_id: Option<Uuid>,
+    pub qc_hold_reason: Option<String>,
 
     // Timing
     pub result_date: DateTime<Utc>,
@@ -237,6 +243,61 @@ impl TestResult {
 // Reference Range Domain Model
 // ============================================================================
 
+/// Unit a reference range's age band is expressed in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "varchar", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AgeUnit {
+    Days,
+    Months,
+    Years,
+}
+
+/// A patient's age on a given date, in each unit a reference range may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatientAge {
+    pub days: i64,
+    pub months: i32,
+    pub years: i32,
+}
+
+impl PatientAge {
+    pub fn on(date_of_birth: NaiveDate, date: NaiveDate) -> Self {
+        let days = (date - date_of_birth).num_days().max(0);
+
+        let mut months = (date.year() - date_of_birth.year()) * 12
+            + date.month() as i32
+            - date_of_birth.month() as i32;
+        if date.day() < date_of_birth.day() {
+            months -= 1;
+        }
+        let months = months.max(0);
+
+        Self {
+            days,
+            months,
+            years: months / 12,
+        }
+    }
+
+    pub fn in_unit(&self, unit: AgeUnit) -> i64 {
+        match unit {
+            AgeUnit::Days => self.days,
+            AgeUnit::Months => self.months as i64,
+            AgeUnit::Years => self.years as i64,
+        }
+    }
+}
+
+/// Gender code used by reference ranges (MALE, FEMALE, OTHER)
+pub fn gender_code(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Male => "MALE",
+        Gender::Female => "FEMALE",
+        Gender::Other => "OTHER",
+        Gender::PreferNotToSay => "PREFER_NOT_TO_SAY",
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ReferenceRange {
     pub id: Uuid,
@@ -246,6 +307,7 @@ pub struct ReferenceRange {
     // Range Criteria
     pub age_min: Option<i32>,
     pub age_max: Option<i32>,
+    pub age_unit: AgeUnit,
     pub gender: Option<String>,
 
     // Range Values
@@ -275,24 +337,71 @@ pub struct ReferenceRange {
 }
 
 impl ReferenceRange {
-    pub fn is_applicable(&self, age: i32, gender: &str) -> bool {
-        let age_match = match (self.age_min, self.age_max) {
-            (Some(min), Some(max)) => age >= min && age <= max,
-            (Some(min), None) => age >= min,
-            (None, Some(max)) => age <= max,
-            (None, None) => true,
+    /// Whether the range applies to a patient on the given date. Without
+    /// demographics only ranges that have no age or gender criteria apply.
+    pub fn is_applicable(&self, age: Option<&PatientAge>, gender: Option<Gender>, on: NaiveDate) -> bool {
+        let age_match = match (self.age_min, self.age_max, age) {
+            (None, None, _) => true,
+            (_, _, None) => false,
+            (min, max, Some(age)) => {
+                let age = age.in_unit(self.age_unit);
+                min.is_none_or(|min| age >= min as i64) && max.is_none_or(|max| age <= max as i64)
+            }
         };
 
-        let gender_match = if let Some(ref range_gender) = self.gender {
-            range_gender == "ALL" || range_gender.eq_ignore_ascii_case(gender)
-        } else {
-            true
+        let gender_match = match (self.gender.as_deref(), gender) {
+            (None, _) | (Some("ALL"), _) => true,
+            (Some(_), None) => false,
+            (Some(range_gender), Some(gender)) => range_gender.eq_ignore_ascii_case(gender_code(gender)),
+        };
+
+        let effective = self.effective_from.is_none_or(|from| on >= from)
+            && self.effective_to.is_none_or(|to| on <= to);
+
+        age_match && gender_match && effective && self.is_active
+    }
+
+    /// Approximate width of the age band in days, used to prefer the
+    /// narrowest band when several ranges match
+    fn age_span_days(&self) -> i64 {
+        let days_per_unit = match self.age_unit {
+            AgeUnit::Days => 1,
+            AgeUnit::Months => 30,
+            AgeUnit::Years => 365,
         };
+        match (self.age_min, self.age_max) {
+            (Some(min), Some(max)) => (max - min) as i64 * days_per_unit,
+            (None, Some(max)) => max as i64 * days_per_unit,
+            _ => i64::MAX,
+        }
+    }
 
-        age_match && gender_match && self.is_active
+    /// Ordering key for choosing between matching ranges: organization
+    /// ranges beat shared ones, gender-specific beat ALL, narrower age bands
+    /// beat wider ones.
+    pub fn specificity(&self) -> (bool, bool, std::cmp::Reverse<i64>) {
+        let gender_specific = self.gender.as_deref().is_some_and(|g| g != "ALL");
+        (
+            self.organization_id.is_some(),
+            gender_specific,
+            std::cmp::Reverse(self.age_span_days()),
+        )
     }
 }
 
+/// Picks the most specific range that applies to the patient
+pub fn select_reference_range(
+    ranges: Vec<ReferenceRange>,
+    age: Option<&PatientAge>,
+    gender: Option<Gender>,
+    on: NaiveDate,
+) -> Option<ReferenceRange> {
+    ranges
+        .into_iter()
+        .filter(|range| range.is_applicable(age, gender, on))
+        .max_by_key(|range| range.specificity())
+}
+
 // ============================================================================
 // Auto-Verification Rule Domain Model
 // ============================================================================
@@ -353,6 +462,13 @@ pub struct CriticalResultNotification {
     pub acknowledgment_date: Option<DateTime<Utc>>,
     pub acknowledgment_method: Option<String>,
 
+    // Read-back
+    pub read_back_by: Option<String>,
+    pub read_back_at: Option<DateTime<Utc>>,
+    pub read_back_value: Option<String>,
+    pub read_back_confirmed: bool,
+    pub read_back_recorded_by: Option<Uuid>,
+
     // Documentation
     pub caller_name: Option<String>,
     pub call_back_number: Option<String>,
@@ -363,6 +479,40 @@ pub struct CriticalResultNotification {
     pub created_by: Uuid,
 }
 
+// ============================================================================
+// HL7 Message Log Domain Model
+// ============================================================================
+
+pub mod hl7_status {
+    pub const PENDING: &str = "PENDING";
+    pub const ACKNOWLEDGED: &str = "ACKNOWLEDGED";
+    pub const REJECTED: &str = "REJECTED";
+    pub const FAILED: &str = "FAILED";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Hl7MessageLog {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+
+    pub direction: String,
+    pub message_type: Option<String>,
+    pub message_control_id: Option<String>,
+    pub peer_address: Option<String>,
+
+    pub raw_message: String,
+    pub ack_message: Option<String>,
+    pub ack_code: Option<String>,
+
+    pub status: String,
+    pub error_message: Option<String>,
+    pub related_entity_id: Option<Uuid>,
+    pub attempt_count: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
 // ============================================================================
 // Input DTOs
 // ============================================================================
@@ -461,6 +611,102 @@ impl RecordCriticalNotificationInput {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcknowledgeCriticalNotificationInput {
+    pub notification_id: Uuid,
+    pub acknowledged_by: String,
+    pub acknowledgment_method: String,
+    /// The value as repeated back by the person receiving the call
+    pub read_back_value: String,
+    /// Defaults to `acknowledged_by` when someone else repeated the value
+    pub read_back_by: Option<String>,
+}
+
+impl AcknowledgeCriticalNotificationInput {
+    pub fn validate(&self) -> Result<()> {
+        if self.acknowledged_by.trim().is_empty() {
+            return Err(Error::Validation("Acknowledged by field is required".to_string()));
+        }
+        if self.acknowledgment_method.trim().is_empty() {
+            return Err(Error::Validation("Acknowledgment method is required".to_string()));
+        }
+        if self.read_back_value.trim().is_empty() {
+            return Err(Error::Validation("Read-back value is required".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn read_back_by(&self) -> &str {
+        self.read_back_by
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(&self.acknowledged_by)
+    }
+}
+
+/// Whether a read-back repeats the reported value. Numbers are compared by
+/// value so "7.0" matches "7"; the result unit may be repeated or omitted.
+pub fn read_back_matches(result_value: &str, result_unit: Option<&str>, read_back: &str) -> bool {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+
+    let mut spoken = normalize(read_back);
+    if let Some(unit) = result_unit.map(normalize).filter(|unit| !unit.is_empty()) {
+        if let Some(stripped) = spoken.strip_suffix(&unit) {
+            spoken = stripped.trim_end().to_string();
+        }
+    }
+    let reported = normalize(result_value);
+
+    match (reported.parse::<f64>(), spoken.parse::<f64>()) {
+        (Ok(reported), Ok(spoken)) => reported == spoken,
+        _ => reported == spoken,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAutoVerificationRuleInput {
+    pub rule_code: String,
+    pub rule_name: String,
+    pub test_id: Option<Uuid>,
+    pub department: Option<String>,
+    pub is_global: bool,
+    pub rule_type: String,
+    pub rule_definition: serde_json::Value,
+    pub min_value: Option<rust_decimal::Decimal>,
+    pub max_value: Option<rust_decimal::Decimal>,
+    pub delta_percentage_limit: Option<rust_decimal::Decimal>,
+    pub delta_absolute_limit: Option<rust_decimal::Decimal>,
+    pub priority: i32,
+    pub is_blocking: bool,
+}
+
+impl CreateAutoVerificationRuleInput {
+    pub fn validate(&self) -> Result<()> {
+        if self.rule_code.trim().is_empty() {
+            return Err(Error::Validation("Rule code is required".to_string()));
+        }
+        if self.rule_name.trim().is_empty() {
+            return Err(Error::Validation("Rule name is required".to_string()));
+        }
+        if self.test_id.is_none() && self.department.is_none() && !self.is_global {
+            return Err(Error::Validation("Rule must apply to a test, a department or be global".to_string()));
+        }
+
+        // Rules that would not compile are rejected here rather than failing every result later
+        crate::rule_engine::compile_rule(
+            &self.rule_type,
+            &self.rule_definition,
+            self.min_value,
+            self.max_value,
+            self.delta_percentage_limit,
+            self.delta_absolute_limit,
+        )
+        .map_err(|e| Error::Validation(format!("Invalid rule {}: {}", self.rule_code, e)))?;
+
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Query Filters
 // ============================================================================
@@ -477,3 +723,98 @@ pub struct ResultFilter {
     pub date_from: Option<DateTime<Utc>>,
     pub date_to: Option<DateTime<Utc>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(age_min: Option<i32>, age_max: Option<i32>, age_unit: AgeUnit, gender: Option<&str>) -> ReferenceRange {
+        ReferenceRange {
+            id: Uuid::new_v4(),
+            test_id: Uuid::new_v4(),
+            organization_id: None,
+            age_min,
+            age_max,
+            age_unit,
+            gender: gender.map(str::to_string),
+            range_min: None,
+            range_max: None,
+            range_text: None,
+            panic_low: None,
+            panic_high: None,
+            critical_low: None,
+            critical_high: None,
+            unit: None,
+            is_active: true,
+            effective_from: None,
+            effective_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+            updated_by: None,
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_patient_age_units() {
+        let age = PatientAge::on(date(2024, 3, 31), date(2025, 3, 30));
+        assert_eq!(age.days, 364);
+        assert_eq!(age.months, 11);
+        assert_eq!(age.years, 0);
+
+        let age = PatientAge::on(date(1990, 6, 15), date(2025, 6, 15));
+        assert_eq!(age.years, 35);
+    }
+
+    #[test]
+    fn test_neonate_gets_range_in_days() {
+        let today = date(2025, 1, 10);
+        let neonate = PatientAge::on(date(2025, 1, 5), today);
+
+        let ranges = vec![
+            range(Some(0), Some(7), AgeUnit::Days, Some("ALL")),
+            range(Some(0), Some(12), AgeUnit::Months, None),
+            range(Some(18), None, AgeUnit::Years, Some("FEMALE")),
+        ];
+        let first_week = ranges[0].id;
+
+        let selected = select_reference_range(ranges, Some(&neonate), Some(Gender::Male), today).unwrap();
+        assert_eq!(selected.id, first_week);
+    }
+
+    #[test]
+    fn test_gender_and_effective_dates() {
+        let today = date(2025, 1, 10);
+        let adult = PatientAge::on(date(1980, 1, 1), today);
+
+        let mut superseded = range(Some(18), Some(120), AgeUnit::Years, Some("FEMALE"));
+        superseded.effective_to = Some(date(2024, 12, 31));
+        let female = range(Some(18), Some(120), AgeUnit::Years, Some("FEMALE"));
+        let any = range(Some(18), Some(120), AgeUnit::Years, Some("ALL"));
+        let female_id = female.id;
+        let any_id = any.id;
+
+        let ranges = vec![superseded, any, female];
+        let selected = select_reference_range(ranges.clone(), Some(&adult), Some(Gender::Female), today).unwrap();
+        assert_eq!(selected.id, female_id);
+
+        let selected = select_reference_range(ranges.clone(), Some(&adult), Some(Gender::Male), today).unwrap();
+        assert_eq!(selected.id, any_id);
+
+        // Without demographics only unconditional ranges apply
+        assert!(select_reference_range(ranges, None, None, today).is_none());
+    }
+
+    #[test]
+    fn test_read_back_matching() {
+        assert!(read_back_matches("2.8", Some("mmol/L"), "2.80 mmol/L"));
+        assert!(read_back_matches("2.8", Some("mmol/L"), " 2.8 "));
+        assert!(read_back_matches("Positive", None, "positive"));
+        assert!(!read_back_matches("2.8", Some("mmol/L"), "28 mmol/L"));
+        assert!(!read_back_matches("2.8", Some("mmol/L"), "2.8 mg/dL"));
+    }
+}
@@ -0,0 +1,305 @@
+use uuid::Uuid;
+use common::error::{Error, Result};
+use common::types::Gender;
+use infrastructure::hl7::{self, AckCode, Hl7Endpoint, Hl7Message, MllpClient, Segment};
+
+use crate::domain::*;
+use crate::patient_client::{PatientClient, PatientData};
+use crate::repository::Hl7MessageLogRepository;
+
+const DIRECTION_OUTBOUND: &str = "OUTBOUND";
+
+/// HL7 table 0078 abnormal flag for a result
+fn abnormal_flag(result: &TestResult) -> Option<&'static str> {
+    match result.critical_flag {
+        CriticalFlag::PanicLow => return Some("LL"),
+        CriticalFlag::PanicHigh => return Some("HH"),
+        CriticalFlag::Low => return Some("L"),
+        CriticalFlag::High => return Some("H"),
+        CriticalFlag::None => {}
+    }
+
+    match result.interpretation {
+        InterpretationType::Normal => Some("N"),
+        InterpretationType::AbnormalLow => Some("L"),
+        InterpretationType::AbnormalHigh => Some("H"),
+        InterpretationType::CriticalLow => Some("LL"),
+        InterpretationType::CriticalHigh => Some("HH"),
+        InterpretationType::Indeterminate => None,
+    }
+}
+
+fn reference_range(result: &TestResult) -> Option<String> {
+    match (result.reference_range_min, result.reference_range_max) {
+        (Some(min), Some(max)) => Some(format!("{}-{}", min, max)),
+        (Some(min), None) => Some(format!(">{}", min)),
+        (None, Some(max)) => Some(format!("<{}", max)),
+        (None, None) => result.reference_range_text.clone(),
+    }
+}
+
+fn administrative_sex(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Male => "M",
+        Gender::Female => "F",
+        Gender::Other => "O",
+        Gender::PreferNotToSay => "U",
+    }
+}
+
+/// Builds an ORU^R01 carrying one approved result: PID, ORC, OBR, a single
+/// OBX and NTE segments for the pathologist's comments.
+pub fn build_oru_message(endpoint: &Hl7Endpoint, result: &TestResult, patient: &PatientData) -> Hl7Message {
+    let mut message = Hl7Message::new(endpoint, "ORU", "R01", "ORU_R01");
+
+    let status = if result.is_corrected { "C" } else { "F" };
+    let order_id = result.order_id.to_string();
+    let value = result.result_value.clone().unwrap_or_default();
+    let value_type = if value.trim().parse::<f64>().is_ok() { "NM" } else { "ST" };
+    let reported = hl7::format_timestamp(result.approval_date.unwrap_or(result.updated_at));
+
+    message.push(
+        Segment::new("PID")
+            .set(1, "1")
+            .set_components(3, &[&patient.mrn_number, "", "", &endpoint.sending_facility, "MR"])
+            .set_components(5, &[patient.last_name.as_deref().unwrap_or_default(), &patient.first_name])
+            .set(7, &hl7::format_date(patient.date_of_birth))
+            .set(8, administrative_sex(patient.gender)),
+    );
+
+    message.push(
+        Segment::new("ORC")
+            .set(1, "RE")
+            .set(3, &order_id)
+            .set(5, "CM"),
+    );
+
+    message.push(
+        Segment::new("OBR")
+            .set(1, "1")
+            .set(3, &order_id)
+            .set_components(4, &[&result.test_code, &result.test_name, "L"])
+            .set(7, &hl7::format_timestamp(result.result_date))
+            .set(22, &reported)
+            .set_opt(24, result.department.as_deref())
+            .set(25, status),
+    );
+
+    message.push(
+        Segment::new("OBX")
+            .set(1, "1")
+            .set(2, value_type)
+            .set_components(3, &[&result.test_code, &result.test_name, "L"])
+            .set(5, &value)
+            .set_opt(6, result.result_unit.as_deref())
+            .set_opt(7, reference_range(result).as_deref())
+            .set_opt(8, abnormal_flag(result))
+            .set(11, status)
+            .set(14, &hl7::format_timestamp(result.result_date))
+            .set_opt(18, result.instrument_name.as_deref()),
+    );
+
+    let comments = [
+        result.clinical_interpretation.as_deref(),
+        result.pathologist_notes.as_deref(),
+    ];
+    for (index, comment) in comments.iter().flatten().enumerate() {
+        message.push(
+            Segment::new("NTE")
+                .set(1, &(index + 1).to_string())
+                .set(2, "L")
+                .set(3, comment),
+        );
+    }
+
+    message
+}
+
+// ============================================================================
+// Result Sender
+// ============================================================================
+
+/// Sends approved results to the downstream HIS/EMR as ORU^R01 over MLLP.
+/// Every attempt is logged so rejected or undelivered messages can be replayed.
+#[derive(Clone)]
+pub struct Hl7ResultSender {
+    client: MllpClient,
+    endpoint: Hl7Endpoint,
+    patient_client: PatientClient,
+    log_repo: Hl7MessageLogRepository,
+}
+
+impl Hl7ResultSender {
+    pub fn new(
+        client: MllpClient,
+        endpoint: Hl7Endpoint,
+        patient_client: PatientClient,
+        log_repo: Hl7MessageLogRepository,
+    ) -> Self {
+        Self {
+            client,
+            endpoint,
+            patient_client,
+            log_repo,
+        }
+    }
+
+    pub async fn send_result(&self, result: &TestResult) -> Result<Hl7MessageLog> {
+        let patient = self.patient_client.get_patient(result.patient_id).await?;
+        let message = build_oru_message(&self.endpoint, result, &patient);
+        let raw = message.encode();
+
+        let log = self.log_repo
+            .create(
+                result.organization_id,
+                DIRECTION_OUTBOUND,
+                message.message_type().as_deref(),
+                message.control_id().as_deref(),
+                Some(self.client.address()),
+                &raw,
+            )
+            .await?;
+
+        self.transmit(log.id, &raw, Some(result.id)).await
+    }
+
+    /// Resends a logged message that was rejected or never acknowledged
+    pub async fn replay(&self, log_id: Uuid, org_id: Uuid) -> Result<Hl7MessageLog> {
+        let log = self.log_repo
+            .find_by_id(log_id, org_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("HL7 message not found: {}", log_id)))?;
+
+        if log.status == hl7_status::ACKNOWLEDGED {
+            return Err(Error::InvalidState("HL7 message has already been acknowledged".to_string()));
+        }
+
+        tracing::info!("Replaying HL7 message {}", log.id);
+        self.transmit(log.id, &log.raw_message, log.related_entity_id).await
+    }
+
+    pub async fn list_messages(&self, org_id: Uuid, status: Option<&str>, limit: i64) -> Result<Vec<Hl7MessageLog>> {
+        self.log_repo.list(org_id, status, limit).await
+    }
+
+    async fn transmit(&self, log_id: Uuid, raw: &str, related: Option<Uuid>) -> Result<Hl7MessageLog> {
+        let raw_ack = match self.client.send(raw).await {
+            Ok(raw_ack) => raw_ack,
+            Err(e) => {
+                tracing::warn!("Failed to deliver HL7 message {}: {}", log_id, e);
+                return self.log_repo
+                    .record_outcome(log_id, hl7_status::FAILED, None, None, Some(&e.to_string()), related)
+                    .await;
+            }
+        };
+
+        match hl7::interpret_ack(&raw_ack) {
+            Ok((AckCode::Accept, _)) => {
+                self.log_repo
+                    .record_outcome(log_id, hl7_status::ACKNOWLEDGED, Some(&raw_ack), Some("AA"), None, related)
+                    .await
+            }
+            Ok((code, text)) => {
+                self.log_repo
+                    .record_outcome(
+                        log_id,
+                        hl7_status::REJECTED,
+                        Some(&raw_ack),
+                        Some(code.as_str()),
+                        text.as_deref(),
+                        related,
+                    )
+                    .await
+            }
+            Err(e) => {
+                self.log_repo
+                    .record_outcome(log_id, hl7_status::FAILED, Some(&raw_ack), None, Some(&e.to_string()), related)
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn endpoint() -> Hl7Endpoint {
+        Hl7Endpoint {
+            sending_application: "LIS".to_string(),
+            sending_facility: "LAB".to_string(),
+            receiving_application: "HIS".to_string(),
+            receiving_facility: "CITYHOSP".to_string(),
+        }
+    }
+
+    fn result() -> TestResult {
+        serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "result_number": "RES-0001",
+            "patient_id": Uuid::new_v4(),
+            "order_id": Uuid::new_v4(),
+            "order_item_id": Uuid::new_v4(),
+            "test_id": Uuid::new_v4(),
+            "sample_id": Uuid::new_v4(),
+            "organization_id": Uuid::new_v4(),
+            "test_code": "K",
+            "test_name": "Potassium",
+            "result_value": "6.9",
+            "result_unit": "mmol/L",
+            "result_type": "NUMERIC",
+            "reference_range_min": "3.5",
+            "reference_range_max": "5.1",
+            "interpretation": "CriticalHigh",
+            "critical_flag": "PanicHigh",
+            "delta_flag": "Normal",
+            "is_abnormal": true,
+            "is_critical": true,
+            "result_status": "Final",
+            "verification_status": "ManuallyVerified",
+            "entry_date": "2025-01-05T10:00:00Z",
+            "result_date": "2025-01-05T10:30:00Z",
+            "approval_date": "2025-01-05T11:00:00Z",
+            "pathologist_notes": "Repeat to exclude haemolysis",
+            "qc_hold": false,
+            "is_corrected": false,
+            "created_at": "2025-01-05T10:00:00Z",
+            "updated_at": "2025-01-05T11:00:00Z",
+            "is_deleted": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_build_oru_message() {
+        let patient = PatientData {
+            mrn_number: "MRN0001".to_string(),
+            first_name: "Jane".to_string(),
+            last_name: Some("Doe".to_string()),
+            date_of_birth: NaiveDate::from_ymd_opt(1980, 1, 15).unwrap(),
+            gender: Gender::Female,
+        };
+
+        let message = Hl7Message::parse(&build_oru_message(&endpoint(), &result(), &patient).encode()).unwrap();
+
+        assert_eq!(message.message_type().as_deref(), Some("ORU^R01"));
+
+        let pid = message.segment("PID").unwrap();
+        assert_eq!(pid.component(3, 1).as_deref(), Some("MRN0001"));
+        assert_eq!(pid.component(3, 5).as_deref(), Some("MR"));
+        assert_eq!(pid.field(7).as_deref(), Some("19800115"));
+        assert_eq!(pid.field(8).as_deref(), Some("F"));
+
+        let obx = message.segment("OBX").unwrap();
+        assert_eq!(obx.field(2).as_deref(), Some("NM"));
+        assert_eq!(obx.component(3, 1).as_deref(), Some("K"));
+        assert_eq!(obx.field(5).as_deref(), Some("6.9"));
+        assert_eq!(obx.field(7).as_deref(), Some("3.5-5.1"));
+        assert_eq!(obx.field(8).as_deref(), Some("HH"));
+        assert_eq!(obx.field(11).as_deref(), Some("F"));
+
+        let nte = message.segment("NTE").unwrap();
+        assert_eq!(nte.field(3).as_deref(), Some("Repeat to exclude haemolysis"));
+    }
+}
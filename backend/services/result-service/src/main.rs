@@ -1,26 +1,43 @@
 use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, JwtService, RequestClaims};
+use common::tenant::{self, Tenant};
+use std::time::Duration;
+use infrastructure::hl7::{Hl7Endpoint, MllpClient};
+use infrastructure::{DatabasePool, EventBusConfig, EventTransport, OutboxRelay};
 
 mod domain;
 mod repository;
 mod service;
 mod api;
 mod config;
+mod patient_client;
+mod sample_client;
+mod order_client;
+mod qc_client;
+mod hl7_sender;
+mod rule_engine;
+mod delta_check;
 
 use repository::*;
 use service::ResultService;
 use api::{QueryRoot, MutationRoot};
 use config::Config;
+use hl7_sender::Hl7ResultSender;
+use patient_client::PatientClient;
+use sample_client::SampleClient;
+use order_client::OrderClient;
+use qc_client::QcClient;
 
 type ResultSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<ResultSchema>,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -89,21 +106,25 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("  Max DB connections: {}", config.database_max_connections);
     tracing::info!("  Caching enabled: {}", config.enable_caching);
     tracing::info!("  Events enabled: {}", config.enable_events);
+    if config.enable_events {
+        tracing::info!("  Kafka brokers: {}", config.kafka_brokers);
+    }
+    tracing::info!("  HL7 outbound enabled: {}", config.hl7_outbound_enabled);
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -115,12 +136,51 @@ async fn main() -> std::io::Result<()> {
     let auto_verification_repo = AutoVerificationRuleRepository::new(pool.clone());
     let critical_notification_repo = CriticalResultNotificationRepository::new(pool.clone());
 
+    // Calls to other services carry the tenant of the request
+    let jwt = JwtService::new(&config.jwt_secret);
+
+    // Create HL7 result sender
+    let hl7_sender = if config.hl7_outbound_enabled {
+        Some(Hl7ResultSender::new(
+            MllpClient::new(config.hl7_outbound_address.clone(), Duration::from_secs(30)),
+            Hl7Endpoint {
+                sending_application: "LIS".to_string(),
+                sending_facility: config.hl7_sending_facility.clone(),
+                receiving_application: config.hl7_receiving_application.clone(),
+                receiving_facility: config.hl7_receiving_facility.clone(),
+            },
+            PatientClient::new(config.patient_service_url.clone(), jwt.clone()),
+            Hl7MessageLogRepository::new(pool.clone()),
+        ))
+    } else {
+        None
+    };
+
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+    }
+
     // Create service
     let result_service = ResultService::new(
         result_repo,
         reference_range_repo,
         auto_verification_repo,
         critical_notification_repo,
+        hl7_sender,
+        PatientClient::new(config.patient_service_url.clone(), jwt.clone()),
+        SampleClient::new(config.sample_service_url.clone(), jwt.clone()),
+        OrderClient::new(config.order_service_url.clone(), jwt.clone()),
+        QcClient::new(config.qc_service_url.clone(), jwt.clone()),
+        config.enable_events,
     );
 
     // Build GraphQL schema
@@ -129,8 +189,8 @@ async fn main() -> std::io::Result<()> {
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: result, resultByNumber, resultsByPatient, resultsByOrder, resultsBySample, pendingVerification, criticalResults, criticalNotifications");
-    tracing::info!("  Mutations: createResult, updateResult, verifyResult, approveResult, correctResult, recordCriticalNotification, acknowledgeCriticalNotification");
+    tracing::info!("  Queries: result, resultByNumber, resultsByPatient, resultsByOrder, resultsBySample, pendingVerification, criticalResults, qcHeldResults, qcLookbackResults, criticalNotifications, hl7Messages, autoVerificationRules");
+    tracing::info!("  Mutations: createResult, updateResult, verifyResult, approveResult, releaseQcHolds, correctResult, recordCriticalNotification, acknowledgeCriticalNotification, replayHl7Message, createAutoVerificationRule");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -140,8 +200,10 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
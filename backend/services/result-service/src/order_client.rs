@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the order-service
+#[derive(Clone)]
+pub struct OrderClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderResponse {
+    order: Option<OrderData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderData {
+    #[serde(rename = "orderNumber")]
+    pub order_number: String,
+    #[serde(rename = "referringDoctorId")]
+    pub referring_doctor_id: Option<Uuid>,
+    #[serde(rename = "referringDoctorName")]
+    pub referring_doctor_name: Option<String>,
+}
+
+impl OrderClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Fetch the order a result belongs to, for its ordering clinician
+    pub async fn get_order(&self, order_id: Uuid) -> Result<OrderData> {
+        let query = r#"
+            query Order($id: ID!) {
+                order(id: $id) {
+                    id
+                    orderNumber
+                    referringDoctorId
+                    referringDoctorName
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "id": order_id.to_string() }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call order-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to order-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Order-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Order-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<OrderResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse order-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from order-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("Order-service error: {}", error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .and_then(|data| data.order)
+            .ok_or_else(|| Error::NotFound(format!("Order not found: {}", order_id)))
+    }
+}
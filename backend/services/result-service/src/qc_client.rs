@@ -0,0 +1,180 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the qc-service
+#[derive(Clone)]
+pub struct QcClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct QcGateResponse {
+    #[serde(rename = "qcGate")]
+    qc_gate: QcGate,
+}
+
+#[derive(Debug, Deserialize)]
+struct QcLookbackWindowResponse {
+    #[serde(rename = "qcLookbackWindow")]
+    qc_lookback_window: QcLookbackWindow,
+}
+
+/// QC state governing a patient result, see `qcGate` in qc-service
+#[derive(Debug, Clone, Deserialize)]
+pub struct QcGate {
+    pub hold: bool,
+    #[serde(rename = "qcResultId")]
+    pub qc_result_id: Option<Uuid>,
+    #[serde(rename = "qcResultNumber")]
+    pub qc_result_number: Option<String>,
+    #[serde(rename = "rulesViolated")]
+    pub rules_violated: Vec<String>,
+}
+
+impl QcGate {
+    pub fn hold_reason(&self) -> String {
+        let qc = self.qc_result_number.as_deref().unwrap_or("unknown");
+        if self.rules_violated.is_empty() {
+            format!("QC {} out of control", qc)
+        } else {
+            format!("QC {} out of control ({})", qc, self.rules_violated.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QcLookbackWindow {
+    #[serde(rename = "testId")]
+    pub test_id: Uuid,
+    #[serde(rename = "equipmentId")]
+    pub equipment_id: Option<Uuid>,
+    pub from: Option<NaiveDateTime>,
+}
+
+impl QcClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// QC state for a test measured on an instrument at `at`
+    pub async fn get_gate(
+        &self,
+        test_id: Uuid,
+        equipment_id: Option<Uuid>,
+        lot_number: Option<&str>,
+        at: DateTime<Utc>,
+    ) -> Result<QcGate> {
+        let query = r#"
+            query QcGate($testId: UUID!, $equipmentId: UUID, $lotNumber: String, $at: NaiveDateTime!) {
+                qcGate(testId: $testId, equipmentId: $equipmentId, lotNumber: $lotNumber, at: $at) {
+                    hold
+                    qcResultId
+                    qcResultNumber
+                    rulesViolated
+                }
+            }
+        "#;
+
+        let variables = serde_json::json!({
+            "testId": test_id,
+            "equipmentId": equipment_id,
+            "lotNumber": lot_number,
+            "at": at.naive_utc(),
+        });
+
+        let response: QcGateResponse = self.execute(query, variables).await?;
+        Ok(response.qc_gate)
+    }
+
+    /// Patient result window affected by an out of control QC result
+    pub async fn get_lookback_window(&self, qc_result_id: Uuid) -> Result<QcLookbackWindow> {
+        let query = r#"
+            query QcLookbackWindow($qcResultId: UUID!) {
+                qcLookbackWindow(qcResultId: $qcResultId) {
+                    testId
+                    equipmentId
+                    from
+                }
+            }
+        "#;
+
+        let variables = serde_json::json!({ "qcResultId": qc_result_id });
+
+        let response: QcLookbackWindowResponse = self.execute(query, variables).await?;
+        Ok(response.qc_lookback_window)
+    }
+
+    async fn execute<T: DeserializeOwned>(&self, query: &str, variables: serde_json::Value) -> Result<T> {
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables,
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call qc-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to qc-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("QC-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("QC-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse qc-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from qc-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("QC-service error: {}", error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .ok_or_else(|| Error::ExternalService("Empty response from qc-service".to_string()))
+    }
+}
@@ -1,9 +1,39 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use common::error::{Error, Result};
+use common::types::Gender;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
+use sqlx::PgExecutor;
 
 use crate::domain::*;
 
+/// Enqueue a result event in the caller's transaction
+async fn enqueue_result_event<'e, E, T>(
+    executor: E,
+    event_type: &str,
+    result: &TestResult,
+    user_id: Uuid,
+    payload: &T,
+) -> Result<()>
+where
+    E: PgExecutor<'e>,
+    T: serde::Serialize,
+{
+    outbox::enqueue_event(
+        executor,
+        topics::RESULT_EVENTS,
+        event_type,
+        "TestResult",
+        result.id,
+        result.organization_id,
+        Some(user_id),
+        payload,
+    )
+    .await
+}
+
 // ============================================================================
 // Test Result Repository
 // ============================================================================
@@ -239,6 +269,8 @@ impl TestResultRepository {
             VerificationStatus::ManuallyVerified
         };
 
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let result = sqlx::query_as::<_, TestResult>(
             r#"
             UPDATE test_result
@@ -257,14 +289,20 @@ impl TestResultRepository {
         .bind(user_id)
         .bind(&input.pathologist_notes)
         .bind(input.result_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        enqueue_result_event(&mut *tx, events::RESULT_VERIFIED, &result, user_id, &result).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(result)
     }
 
     pub async fn approve_result(&self, input: ApproveResultInput, user_id: Uuid) -> Result<TestResult> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let result = sqlx::query_as::<_, TestResult>(
             r#"
             UPDATE test_result
@@ -283,10 +321,14 @@ impl TestResultRepository {
         .bind(user_id)
         .bind(&input.approval_notes)
         .bind(input.result_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        enqueue_result_event(&mut *tx, events::RESULT_APPROVED, &result, user_id, &result).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(result)
     }
 
@@ -298,6 +340,8 @@ impl TestResultRepository {
             .ok_or_else(|| Error::NotFound(format!("Result not found: {}", input.result_id)))?;
 
         // Create corrected result
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let result = sqlx::query_as::<_, TestResult>(
             r#"
             INSERT INTO test_result (
@@ -337,7 +381,7 @@ impl TestResultRepository {
         .bind(input.result_id)
         .bind(&input.correction_reason)
         .bind(user_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
@@ -346,10 +390,14 @@ impl TestResultRepository {
             "UPDATE test_result SET is_corrected = TRUE, updated_at = NOW() WHERE id = $1"
         )
         .bind(input.result_id)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        enqueue_result_event(&mut *tx, events::RESULT_AMENDED, &result, user_id, &result).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(result)
     }
 
@@ -394,7 +442,15 @@ impl TestResultRepository {
         Ok(results)
     }
 
-    pub async fn get_previous_result(&self, patient_id: Uuid, test_id: Uuid) -> Result<Option<TestResult>> {
+    /// Latest final result for the patient and test before `before`, limited
+    /// to results on or after `since` when a lookback window applies
+    pub async fn get_previous_result(
+        &self,
+        patient_id: Uuid,
+        test_id: Uuid,
+        before: DateTime<Utc>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Option<TestResult>> {
         let result = sqlx::query_as::<_, TestResult>(
             r#"
             SELECT * FROM test_result
@@ -402,12 +458,16 @@ impl TestResultRepository {
               AND test_id = $2
               AND result_status = 'FINAL'
               AND is_deleted = FALSE
+              AND result_date < $3
+              AND ($4::TIMESTAMPTZ IS NULL OR result_date >= $4)
             ORDER BY result_date DESC
             LIMIT 1
             "#
         )
         .bind(patient_id)
         .bind(test_id)
+        .bind(before)
+        .bind(since)
         .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
@@ -415,6 +475,193 @@ impl TestResultRepository {
         Ok(result)
     }
 
+    /// Persists the reference range, flags, delta check and auto-verification
+    /// outcome computed for a newly entered result, together with its
+    /// RESULT_ENTERED event. An auto-verified result is also announced as
+    /// verified, and `critical_value` carries the paging payload of a
+    /// critical result.
+    pub async fn save_evaluation(
+        &self,
+        result: &TestResult,
+        user_id: Uuid,
+        critical_value: Option<&serde_json::Value>,
+    ) -> Result<TestResult> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let saved = sqlx::query_as::<_, TestResult>(
+            r#"
+            UPDATE test_result
+            SET
+                reference_range_text = $1,
+                reference_range_min = $2,
+                reference_range_max = $3,
+                reference_range_id = $18,
+                interpretation = $4,
+                critical_flag = $5,
+                delta_flag = $6,
+                is_abnormal = $7,
+                is_critical = $8,
+                previous_result_value = $9,
+                previous_result_date = $10,
+                delta_percentage = $11,
+                delta_absolute = $12,
+                delta_check_details = $19,
+                verification_status = $13,
+                auto_verification_confidence = $14,
+                verification_rules_passed = $15,
+                verification_rules_failed = $16,
+                qc_passed = $20,
+                qc_hold = $21,
+                qc_hold_qc_result_id = $22,
+                qc_hold_reason = $23,
+                updated_at = NOW()
+            WHERE id = $17 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(&result.reference_range_text)
+        .bind(result.reference_range_min)
+        .bind(result.reference_range_max)
+        .bind(&result.interpretation)
+        .bind(&result.critical_flag)
+        .bind(&result.delta_flag)
+        .bind(result.is_abnormal)
+        .bind(result.is_critical)
+        .bind(&result.previous_result_value)
+        .bind(result.previous_result_date)
+        .bind(result.delta_percentage)
+        .bind(result.delta_absolute)
+        .bind(&result.verification_status)
+        .bind(result.auto_verification_confidence)
+        .bind(&result.verification_rules_passed)
+        .bind(&result.verification_rules_failed)
+        .bind(result.id)
+        .bind(result.reference_range_id)
+        .bind(&result.delta_check_details)
+        .bind(result.qc_passed)
+        .bind(result.qc_hold)
+        .bind(result.qc_hold_qc_result_id)
+        .bind(&result.qc_hold_reason)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        enqueue_result_event(&mut *tx, events::RESULT_ENTERED, &saved, user_id, &saved).await?;
+
+        if saved.verification_status == VerificationStatus::AutoVerified {
+            enqueue_result_event(&mut *tx, events::RESULT_VERIFIED, &saved, user_id, &saved).await?;
+        }
+
+        if let Some(payload) = critical_value {
+            enqueue_result_event(&mut *tx, events::CRITICAL_VALUE_DETECTED, &saved, user_id, payload).await?;
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(saved)
+    }
+
+    pub async fn place_qc_hold(&self, id: Uuid, qc_result_id: Option<Uuid>, reason: &str) -> Result<TestResult> {
+        let result = sqlx::query_as::<_, TestResult>(
+            r#"
+            UPDATE test_result
+            SET qc_hold = TRUE, qc_passed = FALSE, qc_hold_qc_result_id = $2, qc_hold_reason = $3, updated_at = NOW()
+            WHERE id = $1 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(qc_result_id)
+        .bind(reason)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result)
+    }
+
+    pub async fn release_qc_hold(&self, id: Uuid) -> Result<TestResult> {
+        let result = sqlx::query_as::<_, TestResult>(
+            r#"
+            UPDATE test_result
+            SET qc_hold = FALSE, qc_passed = TRUE, updated_at = NOW()
+            WHERE id = $1 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result)
+    }
+
+    pub async fn find_held_by_qc(&self, qc_result_id: Uuid) -> Result<Vec<TestResult>> {
+        let results = sqlx::query_as::<_, TestResult>(
+            r#"
+            SELECT * FROM test_result
+            WHERE qc_hold = TRUE AND qc_hold_qc_result_id = $1 AND is_deleted = FALSE
+            ORDER BY result_date
+            "#
+        )
+        .bind(qc_result_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
+    pub async fn get_qc_held(&self, org_id: Uuid, limit: i64) -> Result<Vec<TestResult>> {
+        let results = sqlx::query_as::<_, TestResult>(
+            r#"
+            SELECT * FROM test_result
+            WHERE organization_id = $1 AND qc_hold = TRUE AND is_deleted = FALSE
+            ORDER BY result_date
+            LIMIT $2
+            "#
+        )
+        .bind(org_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
+    /// Results of a test on an instrument measured since `from`
+    pub async fn find_for_qc_lookback(
+        &self,
+        test_id: Uuid,
+        instrument_id: Option<Uuid>,
+        from: Option<chrono::NaiveDateTime>,
+        limit: i64,
+    ) -> Result<Vec<TestResult>> {
+        let results = sqlx::query_as::<_, TestResult>(
+            r#"
+            SELECT * FROM test_result
+            WHERE test_id = $1
+              AND instrument_id IS NOT DISTINCT FROM $2
+              AND ($3::TIMESTAMP IS NULL OR result_date > $3)
+              AND result_status <> 'CANCELLED'
+              AND is_deleted = FALSE
+            ORDER BY result_date
+            LIMIT $4
+            "#
+        )
+        .bind(test_id)
+        .bind(instrument_id)
+        .bind(from)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(results)
+    }
+
     async fn generate_result_number(&self, org_id: &Uuid, test_id: &Uuid) -> Result<String> {
         let org_code = "LAB"; // Should fetch from organization service
         let test_code = "TST"; // Should fetch from test catalog
@@ -466,21 +713,33 @@ impl ReferenceRangeRepository {
         Ok(ranges)
     }
 
+    /// Resolves the range to interpret a result against: the most specific
+    /// active range for the patient's age and gender that is in effect on
+    /// the result date, from the organization's own or shared ranges.
     pub async fn find_applicable_range(
         &self,
         test_id: Uuid,
-        age: i32,
-        gender: &str
+        org_id: Uuid,
+        age: Option<&PatientAge>,
+        gender: Option<Gender>,
+        on: NaiveDate,
     ) -> Result<Option<ReferenceRange>> {
-        let ranges = self.find_by_test(test_id).await?;
-
-        for range in ranges {
-            if range.is_applicable(age, gender) {
-                return Ok(Some(range));
-            }
-        }
+        let ranges = sqlx::query_as::<_, ReferenceRange>(
+            r#"
+            SELECT * FROM reference_range
+            WHERE test_id = $1
+              AND (organization_id IS NULL OR organization_id = $2)
+              AND is_active = TRUE
+            ORDER BY age_min, created_at
+            "#
+        )
+        .bind(test_id)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
 
-        Ok(None)
+        Ok(select_reference_range(ranges, age, gender, on))
     }
 }
 
@@ -531,6 +790,96 @@ impl AutoVerificationRuleRepository {
 
         Ok(rules)
     }
+
+    /// Active rules for a result: those for its test or department plus global
+    /// rules, limited to the organization's own and shared rules
+    pub async fn find_applicable(
+        &self,
+        test_id: Uuid,
+        department: Option<&str>,
+        org_id: Uuid,
+    ) -> Result<Vec<AutoVerificationRule>> {
+        let rules = sqlx::query_as::<_, AutoVerificationRule>(
+            r#"
+            SELECT * FROM auto_verification_rule
+            WHERE (test_id = $1 OR department = $2 OR is_global = TRUE)
+              AND (organization_id IS NULL OR organization_id = $3)
+              AND is_active = TRUE
+            ORDER BY priority, rule_code
+            "#
+        )
+        .bind(test_id)
+        .bind(department)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rules)
+    }
+
+    pub async fn list(&self, test_id: Option<Uuid>, org_id: Uuid) -> Result<Vec<AutoVerificationRule>> {
+        let rules = sqlx::query_as::<_, AutoVerificationRule>(
+            r#"
+            SELECT * FROM auto_verification_rule
+            WHERE ($1::UUID IS NULL OR test_id = $1 OR is_global = TRUE)
+              AND (organization_id IS NULL OR organization_id = $2)
+            ORDER BY priority, rule_code
+            "#
+        )
+        .bind(test_id)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rules)
+    }
+
+    pub async fn create(
+        &self,
+        input: CreateAutoVerificationRuleInput,
+        org_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<AutoVerificationRule> {
+        input.validate()?;
+
+        let rule = sqlx::query_as::<_, AutoVerificationRule>(
+            r#"
+            INSERT INTO auto_verification_rule (
+                id, rule_code, rule_name,
+                test_id, department, organization_id, is_global,
+                rule_type, rule_definition,
+                min_value, max_value, delta_percentage_limit, delta_absolute_limit,
+                priority, is_blocking,
+                created_by, updated_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $16)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&input.rule_code)
+        .bind(&input.rule_name)
+        .bind(input.test_id)
+        .bind(&input.department)
+        .bind(org_id)
+        .bind(input.is_global)
+        .bind(&input.rule_type)
+        .bind(&input.rule_definition)
+        .bind(input.min_value)
+        .bind(input.max_value)
+        .bind(input.delta_percentage_limit)
+        .bind(input.delta_absolute_limit)
+        .bind(input.priority)
+        .bind(input.is_blocking)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rule)
+    }
 }
 
 // ============================================================================
@@ -587,7 +936,27 @@ impl CriticalResultNotificationRepository {
         Ok(notifications)
     }
 
-    pub async fn acknowledge(&self, notification_id: Uuid, acknowledged_by: &str, method: &str) -> Result<CriticalResultNotification> {
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<CriticalResultNotification>> {
+        let notification = sqlx::query_as::<_, CriticalResultNotification>(
+            "SELECT * FROM critical_result_notification WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(notification)
+    }
+
+    /// Records the acknowledgment together with the confirmed read-back
+    pub async fn acknowledge(
+        &self,
+        input: &AcknowledgeCriticalNotificationInput,
+        result: &TestResult,
+        user_id: Uuid,
+    ) -> Result<CriticalResultNotification> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let notification = sqlx::query_as::<_, CriticalResultNotification>(
             r#"
             UPDATE critical_result_notification
@@ -595,18 +964,39 @@ impl CriticalResultNotificationRepository {
                 acknowledged = TRUE,
                 acknowledged_by = $1,
                 acknowledgment_date = NOW(),
-                acknowledgment_method = $2
-            WHERE id = $3
+                acknowledgment_method = $2,
+                read_back_by = $3,
+                read_back_at = NOW(),
+                read_back_value = $4,
+                read_back_confirmed = TRUE,
+                read_back_recorded_by = $5
+            WHERE id = $6
             RETURNING *
             "#
         )
-        .bind(acknowledged_by)
-        .bind(method)
-        .bind(notification_id)
-        .fetch_one(&self.pool)
+        .bind(&input.acknowledged_by)
+        .bind(&input.acknowledgment_method)
+        .bind(input.read_back_by())
+        .bind(&input.read_back_value)
+        .bind(user_id)
+        .bind(input.notification_id)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        let payload = serde_json::json!({
+            "result_id": result.id,
+            "result_number": result.result_number,
+            "notification_id": notification.id,
+            "acknowledged_by": notification.acknowledged_by,
+            "acknowledgment_method": notification.acknowledgment_method,
+            "read_back_by": notification.read_back_by,
+            "read_back_value": notification.read_back_value,
+        });
+        enqueue_result_event(&mut *tx, events::CRITICAL_VALUE_ACKNOWLEDGED, result, user_id, &payload).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(notification)
     }
 
@@ -628,3 +1018,121 @@ impl CriticalResultNotificationRepository {
         Ok(notifications)
     }
 }
+
+// ============================================================================
+// HL7 Message Log Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct Hl7MessageLogRepository {
+    pool: PgPool,
+}
+
+impl Hl7MessageLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        org_id: Uuid,
+        direction: &str,
+        message_type: Option<&str>,
+        message_control_id: Option<&str>,
+        peer_address: Option<&str>,
+        raw_message: &str,
+    ) -> Result<Hl7MessageLog> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            INSERT INTO hl7_message_log (
+                id, organization_id, direction, message_type, message_control_id, peer_address, raw_message, status
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(org_id)
+        .bind(direction)
+        .bind(message_type)
+        .bind(message_control_id)
+        .bind(peer_address)
+        .bind(raw_message)
+        // Until the receiver acknowledges it
+        .bind(hl7_status::PENDING)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid, org_id: Uuid) -> Result<Option<Hl7MessageLog>> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            "SELECT * FROM hl7_message_log WHERE id = $1 AND organization_id = $2"
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn record_outcome(
+        &self,
+        id: Uuid,
+        status: &str,
+        ack_message: Option<&str>,
+        ack_code: Option<&str>,
+        error_message: Option<&str>,
+        related_entity_id: Option<Uuid>,
+    ) -> Result<Hl7MessageLog> {
+        let log = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            UPDATE hl7_message_log
+            SET
+                status = $1,
+                ack_message = $2,
+                ack_code = $3,
+                error_message = $4,
+                related_entity_id = COALESCE($5, related_entity_id),
+                attempt_count = attempt_count + 1,
+                processed_at = NOW()
+            WHERE id = $6
+            RETURNING *
+            "#
+        )
+        .bind(status)
+        .bind(ack_message)
+        .bind(ack_code)
+        .bind(error_message)
+        .bind(related_entity_id)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(log)
+    }
+
+    pub async fn list(&self, org_id: Uuid, status: Option<&str>, limit: i64) -> Result<Vec<Hl7MessageLog>> {
+        let logs = sqlx::query_as::<_, Hl7MessageLog>(
+            r#"
+            SELECT * FROM hl7_message_log
+            WHERE organization_id = $1 AND ($2::VARCHAR IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(org_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(logs)
+    }
+}
@@ -0,0 +1,993 @@
+//! Auto-verification rule language.
+//!
+//! Rules are boolean expressions over the result being verified, e.g.
+//!
+//! ```text
+//! value between 3.5 and 5.1 and not specimen.hemolyzed
+//! (abs(delta_percent) <= 20 or not has_previous) and patient.age_years >= 18
+//! interpretation in ["NORMAL", "ABNORMAL_LOW"] and qc_passed
+//! ```
+//!
+//! Evaluation uses three-valued logic: a comparison involving a missing value
+//! (no previous result, unknown patient age, non-numeric value...) is unknown,
+//! and a rule only passes when its expression is definitely true.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use common::types::Gender;
+
+use crate::delta_check::DeltaLimits;
+use crate::domain::*;
+
+// ============================================================================
+// Values and Variables
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl Value {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::json!(b),
+            Value::Number(n) => serde_json::json!(n),
+            Value::Text(s) => serde_json::json!(s),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Bool,
+    Number,
+    Text,
+    /// The `null` literal, comparable with anything
+    Null,
+}
+
+/// Every variable a rule may reference, with its type
+pub const VARIABLES: &[(&str, ValueType)] = &[
+    ("value", ValueType::Number),
+    ("value_text", ValueType::Text),
+    ("unit", ValueType::Text),
+    ("reference_low", ValueType::Number),
+    ("reference_high", ValueType::Number),
+    ("within_reference_range", ValueType::Bool),
+    ("interpretation", ValueType::Text),
+    ("critical_flag", ValueType::Text),
+    ("is_abnormal", ValueType::Bool),
+    ("is_critical", ValueType::Bool),
+    ("delta_flag", ValueType::Text),
+    ("delta_percent", ValueType::Number),
+    ("delta_absolute", ValueType::Number),
+    ("has_previous", ValueType::Bool),
+    ("previous_value", ValueType::Number),
+    ("days_since_previous", ValueType::Number),
+    ("qc_passed", ValueType::Bool),
+    ("entry_method", ValueType::Text),
+    ("specimen.hemolyzed", ValueType::Bool),
+    ("specimen.lipemic", ValueType::Bool),
+    ("specimen.icteric", ValueType::Bool),
+    ("patient.age_years", ValueType::Number),
+    ("patient.age_days", ValueType::Number),
+    ("patient.sex", ValueType::Text),
+];
+
+fn variable_type(name: &str) -> Option<ValueType> {
+    VARIABLES.iter().find(|(n, _)| *n == name).map(|(_, t)| *t)
+}
+
+// ============================================================================
+// Lexer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    And,
+    Or,
+    Not,
+    In,
+    Between,
+    True,
+    False,
+    Null,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid number '{}' at position {}", text, start))?;
+            tokens.push((Token::Number(number), start));
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let token = match word.to_ascii_lowercase().as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "not" => Token::Not,
+                "in" => Token::In,
+                "between" => Token::Between,
+                "true" => Token::True,
+                "false" => Token::False,
+                "null" => Token::Null,
+                _ => Token::Ident(word),
+            };
+            tokens.push((token, start));
+            continue;
+        }
+
+        if c == '"' || c == '\'' {
+            i += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(i) {
+                    Some(&q) if q == c => break,
+                    Some(&ch) => text.push(ch),
+                    None => return Err(format!("Unterminated string starting at position {}", start)),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Text(text), start));
+            continue;
+        }
+
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let (token, width) = match two.as_str() {
+            "==" => (Token::Eq, 2),
+            "!=" => (Token::Ne, 2),
+            "<=" => (Token::Le, 2),
+            ">=" => (Token::Ge, 2),
+            "&&" => (Token::And, 2),
+            "||" => (Token::Or, 2),
+            _ => match c {
+                '=' => (Token::Eq, 1),
+                '<' => (Token::Lt, 1),
+                '>' => (Token::Gt, 1),
+                '!' => (Token::Not, 1),
+                '+' => (Token::Plus, 1),
+                '-' => (Token::Minus, 1),
+                '*' => (Token::Star, 1),
+                '/' => (Token::Slash, 1),
+                '(' => (Token::LParen, 1),
+                ')' => (Token::RParen, 1),
+                '[' => (Token::LBracket, 1),
+                ']' => (Token::RBracket, 1),
+                ',' => (Token::Comma, 1),
+                other => return Err(format!("Unexpected character '{}' at position {}", other, start)),
+            },
+        };
+        tokens.push((token, start));
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Abs,
+    Exists,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    Arith(ArithOp, Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    In(Box<Expr>, Vec<Value>),
+    Between(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    source_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map(|(_, p)| *p).unwrap_or(self.source_len)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), String> {
+        let position = self.position();
+        match self.advance() {
+            Some(ref t) if *t == expected => Ok(()),
+            Some(t) => Err(format!("Expected {} at position {}, found {:?}", what, position, t)),
+            None => Err(format!("Expected {} at end of expression", what)),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.advance();
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.advance();
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_additive()?;
+
+        let op = match self.peek() {
+            Some(Token::Eq) => CompareOp::Eq,
+            Some(Token::Ne) => CompareOp::Ne,
+            Some(Token::Lt) => CompareOp::Lt,
+            Some(Token::Le) => CompareOp::Le,
+            Some(Token::Gt) => CompareOp::Gt,
+            Some(Token::Ge) => CompareOp::Ge,
+            Some(Token::In) => {
+                self.advance();
+                return Ok(Expr::In(Box::new(left), self.parse_list()?));
+            }
+            Some(Token::Between) => {
+                self.advance();
+                let low = self.parse_additive()?;
+                self.expect(Token::And, "'and' in between")?;
+                let high = self.parse_additive()?;
+                return Ok(Expr::Between(Box::new(left), Box::new(low), Box::new(high)));
+            }
+            _ => return Ok(left),
+        };
+
+        self.advance();
+        let right = self.parse_additive()?;
+        Ok(Expr::Compare(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Value>, String> {
+        self.expect(Token::LBracket, "'['")?;
+        let mut values = Vec::new();
+
+        loop {
+            let position = self.position();
+            let value = match self.advance() {
+                Some(Token::Number(n)) => Value::Number(n),
+                Some(Token::Minus) => match self.advance() {
+                    Some(Token::Number(n)) => Value::Number(-n),
+                    _ => return Err(format!("Expected number after '-' at position {}", position)),
+                },
+                Some(Token::Text(s)) => Value::Text(s),
+                Some(Token::True) => Value::Bool(true),
+                Some(Token::False) => Value::Bool(false),
+                Some(Token::RBracket) if values.is_empty() => return Ok(values),
+                _ => return Err(format!("Expected literal in list at position {}", position)),
+            };
+            values.push(value);
+
+            match self.advance() {
+                Some(Token::Comma) => continue,
+                Some(Token::RBracket) => return Ok(values),
+                _ => return Err("Expected ',' or ']' in list".to_string()),
+            }
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => ArithOp::Add,
+                Some(Token::Minus) => ArithOp::Sub,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_multiplicative()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => ArithOp::Mul,
+                Some(Token::Slash) => ArithOp::Div,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_unary()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Minus) {
+            self.advance();
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let position = self.position();
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Token::Text(s)) => Ok(Expr::Literal(Value::Text(s))),
+            Some(Token::True) => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::False) => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Null) => Ok(Expr::Literal(Value::Null)),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    let function = match name.to_ascii_lowercase().as_str() {
+                        "abs" => Function::Abs,
+                        "exists" => Function::Exists,
+                        _ => return Err(format!("Unknown function '{}' at position {}", name, position)),
+                    };
+                    self.advance();
+                    let argument = self.parse_or()?;
+                    self.expect(Token::RParen, "')'")?;
+                    return Ok(Expr::Call(function, Box::new(argument)));
+                }
+
+                if variable_type(&name).is_none() {
+                    return Err(format!("Unknown variable '{}' at position {}", name, position));
+                }
+                Ok(Expr::Variable(name))
+            }
+            Some(t) => Err(format!("Unexpected {:?} at position {}", t, position)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+pub fn parse_expression(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    if tokens.is_empty() {
+        return Err("Expression is empty".to_string());
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        source_len: source.len(),
+    };
+    let expr = parser.parse_or()?;
+
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("Unexpected trailing input at position {}", parser.position()));
+    }
+
+    match type_of(&expr)? {
+        ValueType::Bool => Ok(expr),
+        other => Err(format!("Rule expression must be boolean, found {:?}", other)),
+    }
+}
+
+fn compatible(a: ValueType, b: ValueType) -> bool {
+    a == b || a == ValueType::Null || b == ValueType::Null
+}
+
+/// Static type check so mistakes surface when the rule is created
+fn type_of(expr: &Expr) -> Result<ValueType, String> {
+    match expr {
+        Expr::Literal(Value::Null) => Ok(ValueType::Null),
+        Expr::Literal(Value::Bool(_)) => Ok(ValueType::Bool),
+        Expr::Literal(Value::Number(_)) => Ok(ValueType::Number),
+        Expr::Literal(Value::Text(_)) => Ok(ValueType::Text),
+        Expr::Variable(name) => variable_type(name).ok_or_else(|| format!("Unknown variable '{}'", name)),
+        Expr::Not(inner) => match type_of(inner)? {
+            ValueType::Bool => Ok(ValueType::Bool),
+            other => Err(format!("'not' needs a boolean operand, found {:?}", other)),
+        },
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            for side in [a, b] {
+                let t = type_of(side)?;
+                if t != ValueType::Bool {
+                    return Err(format!("'and'/'or' need boolean operands, found {:?}", t));
+                }
+            }
+            Ok(ValueType::Bool)
+        }
+        Expr::Compare(op, a, b) => {
+            let (ta, tb) = (type_of(a)?, type_of(b)?);
+            if !compatible(ta, tb) {
+                return Err(format!("Cannot compare {:?} with {:?}", ta, tb));
+            }
+            let ordered = !matches!(op, CompareOp::Eq | CompareOp::Ne);
+            if ordered && (ta != ValueType::Number || tb != ValueType::Number) {
+                return Err("Ordering comparisons need numeric operands".to_string());
+            }
+            Ok(ValueType::Bool)
+        }
+        Expr::Arith(_, a, b) => {
+            let (ta, tb) = (type_of(a)?, type_of(b)?);
+            if ta != ValueType::Number || tb != ValueType::Number {
+                return Err("Arithmetic needs numeric operands".to_string());
+            }
+            Ok(ValueType::Number)
+        }
+        Expr::Negate(inner) => match type_of(inner)? {
+            ValueType::Number => Ok(ValueType::Number),
+            other => Err(format!("Cannot negate {:?}", other)),
+        },
+        Expr::In(inner, values) => {
+            let t = type_of(inner)?;
+            for value in values {
+                let tv = type_of(&Expr::Literal(value.clone()))?;
+                if !compatible(t, tv) {
+                    return Err(format!("List value {} does not match {:?}", value, t));
+                }
+            }
+            Ok(ValueType::Bool)
+        }
+        Expr::Between(inner, low, high) => {
+            for side in [inner, low, high] {
+                if type_of(side)? != ValueType::Number {
+                    return Err("'between' needs numeric operands".to_string());
+                }
+            }
+            Ok(ValueType::Bool)
+        }
+        Expr::Call(Function::Abs, inner) => match type_of(inner)? {
+            ValueType::Number => Ok(ValueType::Number),
+            other => Err(format!("abs() needs a number, found {:?}", other)),
+        },
+        Expr::Call(Function::Exists, _) => Ok(ValueType::Bool),
+    }
+}
+
+fn collect_variables(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::Variable(name) => {
+            if !out.contains(name) {
+                out.push(name.clone());
+            }
+        }
+        Expr::Literal(_) => {}
+        Expr::Not(a) | Expr::Negate(a) | Expr::Call(_, a) | Expr::In(a, _) => collect_variables(a, out),
+        Expr::And(a, b) | Expr::Or(a, b) | Expr::Compare(_, a, b) | Expr::Arith(_, a, b) => {
+            collect_variables(a, out);
+            collect_variables(b, out);
+        }
+        Expr::Between(a, b, c) => {
+            collect_variables(a, out);
+            collect_variables(b, out);
+            collect_variables(c, out);
+        }
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+/// Variable bindings for one result
+#[derive(Debug, Clone, Default)]
+pub struct RuleContext {
+    values: HashMap<&'static str, Value>,
+}
+
+/// Specimen quality indices reported by sample-service
+#[derive(Debug, Clone, Default)]
+pub struct SpecimenQuality {
+    pub is_hemolyzed: bool,
+    pub is_lipemic: bool,
+    pub is_icteric: bool,
+}
+
+/// Patient demographics relevant to rules
+#[derive(Debug, Clone)]
+pub struct PatientFacts {
+    pub date_of_birth: NaiveDate,
+    pub gender: Gender,
+}
+
+fn decimal(value: Option<Decimal>) -> Value {
+    value.and_then(|d| d.to_f64()).map(Value::Number).unwrap_or(Value::Null)
+}
+
+fn text(value: Option<&str>) -> Value {
+    value.map(|s| Value::Text(s.to_string())).unwrap_or(Value::Null)
+}
+
+/// Upper snake case name of a domain enum, e.g. `PanicHigh` -> `PANIC_HIGH`
+pub(crate) fn enum_name<T: fmt::Debug>(value: &T) -> String {
+    let debug = format!("{:?}", value);
+    let mut name = String::new();
+    for (i, c) in debug.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+impl RuleContext {
+    pub fn new(
+        result: &TestResult,
+        specimen: Option<&SpecimenQuality>,
+        patient: Option<&PatientFacts>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut values = HashMap::new();
+        let numeric = result
+            .result_value
+            .as_deref()
+            .and_then(|v| v.trim().parse::<f64>().ok());
+
+        values.insert("value", numeric.map(Value::Number).unwrap_or(Value::Null));
+        values.insert("value_text", text(result.result_value.as_deref()));
+        values.insert("unit", text(result.result_unit.as_deref()));
+        values.insert("reference_low", decimal(result.reference_range_min));
+        values.insert("reference_high", decimal(result.reference_range_max));
+        values.insert(
+            "within_reference_range",
+            if numeric.is_some() && result.reference_range_min.is_some() && result.reference_range_max.is_some() {
+                Value::Bool(result.is_within_reference_range())
+            } else {
+                Value::Null
+            },
+        );
+        values.insert("interpretation", Value::Text(enum_name(&result.interpretation)));
+        values.insert("critical_flag", Value::Text(enum_name(&result.critical_flag)));
+        values.insert("is_abnormal", Value::Bool(result.is_abnormal));
+        values.insert("is_critical", Value::Bool(result.is_critical));
+        values.insert("delta_flag", Value::Text(enum_name(&result.delta_flag)));
+        values.insert("delta_percent", decimal(result.delta_percentage));
+        values.insert("delta_absolute", decimal(result.delta_absolute));
+        values.insert("has_previous", Value::Bool(result.previous_result_value.is_some()));
+        values.insert(
+            "previous_value",
+            result
+                .previous_result_value
+                .as_deref()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+        );
+        values.insert(
+            "days_since_previous",
+            result
+                .previous_result_date
+                .map(|date| Value::Number((result.result_date - date).num_minutes() as f64 / 1440.0))
+                .unwrap_or(Value::Null),
+        );
+        values.insert("qc_passed", result.qc_passed.map(Value::Bool).unwrap_or(Value::Null));
+        values.insert("entry_method", text(result.entry_method.as_deref()));
+
+        if let Some(specimen) = specimen {
+            values.insert("specimen.hemolyzed", Value::Bool(specimen.is_hemolyzed));
+            values.insert("specimen.lipemic", Value::Bool(specimen.is_lipemic));
+            values.insert("specimen.icteric", Value::Bool(specimen.is_icteric));
+        }
+
+        if let Some(patient) = patient {
+            let today = now.date_naive();
+            let age_days = (today - patient.date_of_birth).num_days();
+            values.insert("patient.age_days", Value::Number(age_days as f64));
+            values.insert("patient.age_years", Value::Number(today.years_since(patient.date_of_birth).unwrap_or(0) as f64));
+            values.insert("patient.sex", Value::Text(enum_name(&patient.gender)));
+        }
+
+        Self { values }
+    }
+
+    pub fn get(&self, name: &str) -> Value {
+        self.values.get(name).cloned().unwrap_or(Value::Null)
+    }
+}
+
+fn evaluate(expr: &Expr, ctx: &RuleContext) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Variable(name) => ctx.get(name),
+        Expr::Not(inner) => match evaluate(inner, ctx) {
+            Value::Bool(b) => Value::Bool(!b),
+            _ => Value::Null,
+        },
+        Expr::And(a, b) => match (evaluate(a, ctx), evaluate(b, ctx)) {
+            (Value::Bool(false), _) | (_, Value::Bool(false)) => Value::Bool(false),
+            (Value::Bool(true), Value::Bool(true)) => Value::Bool(true),
+            _ => Value::Null,
+        },
+        Expr::Or(a, b) => match (evaluate(a, ctx), evaluate(b, ctx)) {
+            (Value::Bool(true), _) | (_, Value::Bool(true)) => Value::Bool(true),
+            (Value::Bool(false), Value::Bool(false)) => Value::Bool(false),
+            _ => Value::Null,
+        },
+        Expr::Compare(op, a, b) => {
+            let (left, right) = (evaluate(a, ctx), evaluate(b, ctx));
+            // Explicit null checks are definite; anything else involving null is unknown
+            let is_null_literal = |e: &Expr| matches!(e, Expr::Literal(Value::Null));
+            if is_null_literal(a) || is_null_literal(b) {
+                let both_null = left == Value::Null && right == Value::Null;
+                return match op {
+                    CompareOp::Eq => Value::Bool(both_null),
+                    CompareOp::Ne => Value::Bool(!both_null),
+                    _ => Value::Null,
+                };
+            }
+            match (&left, &right) {
+                (Value::Null, _) | (_, Value::Null) => Value::Null,
+                (Value::Number(x), Value::Number(y)) => Value::Bool(match op {
+                    CompareOp::Eq => x == y,
+                    CompareOp::Ne => x != y,
+                    CompareOp::Lt => x < y,
+                    CompareOp::Le => x <= y,
+                    CompareOp::Gt => x > y,
+                    CompareOp::Ge => x >= y,
+                }),
+                _ => match op {
+                    CompareOp::Eq => Value::Bool(left == right),
+                    CompareOp::Ne => Value::Bool(left != right),
+                    _ => Value::Null,
+                },
+            }
+        }
+        Expr::Arith(op, a, b) => match (evaluate(a, ctx), evaluate(b, ctx)) {
+            (Value::Number(x), Value::Number(y)) => match op {
+                ArithOp::Add => Value::Number(x + y),
+                ArithOp::Sub => Value::Number(x - y),
+                ArithOp::Mul => Value::Number(x * y),
+                ArithOp::Div if y != 0.0 => Value::Number(x / y),
+                ArithOp::Div => Value::Null,
+            },
+            _ => Value::Null,
+        },
+        Expr::Negate(inner) => match evaluate(inner, ctx) {
+            Value::Number(x) => Value::Number(-x),
+            _ => Value::Null,
+        },
+        Expr::In(inner, values) => match evaluate(inner, ctx) {
+            Value::Null => Value::Null,
+            value => Value::Bool(values.contains(&value)),
+        },
+        Expr::Between(inner, low, high) => {
+            match (evaluate(inner, ctx), evaluate(low, ctx), evaluate(high, ctx)) {
+                (Value::Number(x), Value::Number(lo), Value::Number(hi)) => Value::Bool(x >= lo && x <= hi),
+                _ => Value::Null,
+            }
+        }
+        Expr::Call(Function::Abs, inner) => match evaluate(inner, ctx) {
+            Value::Number(x) => Value::Number(x.abs()),
+            _ => Value::Null,
+        },
+        Expr::Call(Function::Exists, inner) => Value::Bool(evaluate(inner, ctx) != Value::Null),
+    }
+}
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+pub const RULE_TYPE_EXPRESSION: &str = "EXPRESSION";
+pub const RULE_TYPE_DELTA_CHECK: &str = "DELTA_CHECK";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOutcome {
+    Pass,
+    Fail,
+    /// The expression could not be decided because an input was missing
+    Unknown,
+    /// The rule itself is invalid (unknown type, bad definition)
+    Error,
+}
+
+impl RuleOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleOutcome::Pass => "PASS",
+            RuleOutcome::Fail => "FAIL",
+            RuleOutcome::Unknown => "UNKNOWN",
+            RuleOutcome::Error => "ERROR",
+        }
+    }
+}
+
+/// A rule turned into an expression, ready to evaluate
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub source: String,
+    expr: Expr,
+    variables: Vec<String>,
+}
+
+impl CompiledRule {
+    fn from_source(source: String) -> Result<Self, String> {
+        let expr = parse_expression(&source)?;
+        let mut variables = Vec::new();
+        collect_variables(&expr, &mut variables);
+        Ok(Self { source, expr, variables })
+    }
+
+    /// Whether the rule needs inputs from the given namespace (`patient`, `specimen`)
+    pub fn references(&self, namespace: &str) -> bool {
+        let prefix = format!("{}.", namespace);
+        self.variables.iter().any(|v| v.starts_with(&prefix))
+    }
+
+    pub fn evaluate(&self, ctx: &RuleContext) -> (RuleOutcome, BTreeMap<String, serde_json::Value>) {
+        let inputs = self
+            .variables
+            .iter()
+            .map(|name| (name.clone(), ctx.get(name).to_json()))
+            .collect();
+
+        let outcome = match evaluate(&self.expr, ctx) {
+            Value::Bool(true) => RuleOutcome::Pass,
+            Value::Bool(false) => RuleOutcome::Fail,
+            _ => RuleOutcome::Unknown,
+        };
+
+        (outcome, inputs)
+    }
+}
+
+fn number(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+/// Compiles a rule from its type, `rule_definition` and thresholds.
+///
+/// `EXPRESSION` rules carry their condition in `rule_definition.expression`.
+/// The legacy types are shorthands for fixed expressions, parameterised by the
+/// rule's thresholds. Any other type is rejected.
+pub fn compile_rule(
+    rule_type: &str,
+    rule_definition: &serde_json::Value,
+    min_value: Option<Decimal>,
+    max_value: Option<Decimal>,
+    delta_percentage_limit: Option<Decimal>,
+    delta_absolute_limit: Option<Decimal>,
+) -> Result<CompiledRule, String> {
+    let source = match rule_type {
+        RULE_TYPE_EXPRESSION => rule_definition
+            .get("expression")
+            .and_then(|e| e.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "EXPRESSION rules need a string 'expression' in rule_definition".to_string())?,
+        "RANGE_CHECK" => match (min_value, max_value) {
+            (Some(min), Some(max)) => format!("value between {} and {}", number(min), number(max)),
+            (Some(min), None) => format!("value >= {}", number(min)),
+            (None, Some(max)) => format!("value <= {}", number(max)),
+            (None, None) => "within_reference_range".to_string(),
+        },
+        // The limits are applied by the delta check itself, which records
+        // which of them fired; the rule only gates on its outcome
+        RULE_TYPE_DELTA_CHECK => {
+            DeltaLimits::from_definition(rule_definition, delta_percentage_limit, delta_absolute_limit)?;
+            "not has_previous or delta_flag == \"NORMAL\"".to_string()
+        }
+        // qc_passed is only known once qc-service has vouched for the run
+        "QC_CHECK" => "qc_passed".to_string(),
+        "CRITICAL_CHECK" => "not is_critical".to_string(),
+        other => return Err(format!("Unknown rule type: {}", other)),
+    };
+
+    CompiledRule::from_source(source)
+}
+
+/// Compiles an `AutoVerificationRule` row
+pub fn compile(rule: &AutoVerificationRule) -> Result<CompiledRule, String> {
+    compile_rule(
+        &rule.rule_type,
+        &rule.rule_definition,
+        rule.min_value,
+        rule.max_value,
+        rule.delta_percentage_limit,
+        rule.delta_absolute_limit,
+    )
+}
+
+/// Trace entry stored in `verification_rules_passed/failed`
+pub fn trace_entry(
+    rule: &AutoVerificationRule,
+    outcome: RuleOutcome,
+    expression: Option<&str>,
+    inputs: BTreeMap<String, serde_json::Value>,
+    message: Option<&str>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "rule_id": rule.id,
+        "rule_code": rule.rule_code,
+        "rule_type": rule.rule_type,
+        "blocking": rule.is_blocking,
+        "outcome": outcome.as_str(),
+        "expression": expression,
+        "inputs": inputs,
+        "message": message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(pairs: &[(&'static str, Value)]) -> RuleContext {
+        RuleContext {
+            values: pairs.iter().cloned().collect(),
+        }
+    }
+
+    fn run(source: &str, ctx: &RuleContext) -> RuleOutcome {
+        CompiledRule::from_source(source.to_string()).unwrap().evaluate(ctx).0
+    }
+
+    #[test]
+    fn test_expression_evaluation() {
+        let ctx = context(&[
+            ("value", Value::Number(4.2)),
+            ("interpretation", Value::Text("NORMAL".to_string())),
+            ("specimen.hemolyzed", Value::Bool(false)),
+            ("delta_percent", Value::Number(-12.5)),
+        ]);
+
+        assert_eq!(run("value between 3.5 and 5.1 and not specimen.hemolyzed", &ctx), RuleOutcome::Pass);
+        assert_eq!(run("interpretation in [\"NORMAL\", \"ABNORMAL_LOW\"]", &ctx), RuleOutcome::Pass);
+        assert_eq!(run("abs(delta_percent) <= 10", &ctx), RuleOutcome::Fail);
+        assert_eq!(run("value * 2 > 8 && !(value >= 5)", &ctx), RuleOutcome::Pass);
+    }
+
+    #[test]
+    fn test_missing_inputs_are_unknown_not_passed() {
+        let ctx = context(&[("value", Value::Number(4.2))]);
+
+        assert_eq!(run("patient.age_years >= 18", &ctx), RuleOutcome::Unknown);
+        assert_eq!(run("not (patient.age_years < 18)", &ctx), RuleOutcome::Unknown);
+        // A definite operand still decides the connective
+        assert_eq!(run("value > 1 or patient.age_years >= 18", &ctx), RuleOutcome::Pass);
+        assert_eq!(run("patient.sex == null", &ctx), RuleOutcome::Pass);
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        assert!(parse_expression("value >").is_err());
+        assert!(parse_expression("hemoglobin > 3").unwrap_err().contains("Unknown variable"));
+        assert!(parse_expression("value > \"high\"").is_err());
+        assert!(parse_expression("value + 1").unwrap_err().contains("boolean"));
+        assert!(parse_expression("median(value) > 1").is_err());
+    }
+
+    #[test]
+    fn test_rule_types() {
+        let definition = serde_json::json!({});
+
+        assert!(compile_rule("UNKNOWN_CHECK", &definition, None, None, None, None).is_err());
+        assert!(compile_rule("EXPRESSION", &definition, None, None, None, None).is_err());
+
+        let range = compile_rule("RANGE_CHECK", &definition, Some(Decimal::new(35, 1)), Some(Decimal::new(51, 1)), None, None).unwrap();
+        assert_eq!(range.source, "value between 3.5 and 5.1");
+
+        assert!(compile_rule("DELTA_CHECK", &definition, None, None, None, None).is_err());
+        let delta = compile_rule("DELTA_CHECK", &definition, None, None, Some(Decimal::new(20, 0)), None).unwrap();
+        let ctx = context(&[
+            ("has_previous", Value::Bool(true)),
+            ("delta_flag", Value::Text("SIGNIFICANT_INCREASE".to_string())),
+        ]);
+        assert_eq!(delta.evaluate(&ctx).0, RuleOutcome::Fail);
+        assert!(!delta.references("patient"));
+
+        // QC status nobody vouched for is not a pass
+        let qc = compile_rule("QC_CHECK", &definition, None, None, None, None).unwrap();
+        assert_eq!(qc.evaluate(&context(&[("qc_passed", Value::Null)])).0, RuleOutcome::Unknown);
+        assert_eq!(qc.evaluate(&context(&[("qc_passed", Value::Bool(false))])).0, RuleOutcome::Fail);
+        assert_eq!(qc.evaluate(&context(&[("qc_passed", Value::Bool(true))])).0, RuleOutcome::Pass);
+    }
+}
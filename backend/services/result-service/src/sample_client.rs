@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::auth::JwtService;
+use common::error::{Error, Result};
+
+/// Client for communicating with the sample-service
+#[derive(Clone)]
+pub struct SampleClient {
+    base_url: String,
+    client: reqwest::Client,
+    jwt: JwtService,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQLRequest {
+    query: String,
+    variables: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampleResponse {
+    sample: Option<SampleData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SampleData {
+    #[serde(rename = "isHemolyzed")]
+    pub is_hemolyzed: bool,
+    #[serde(rename = "isLipemic")]
+    pub is_lipemic: bool,
+    #[serde(rename = "isIcteric")]
+    pub is_icteric: bool,
+}
+
+impl SampleClient {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
+        Self {
+            base_url,
+            client: reqwest::Client::new(),
+            jwt,
+        }
+    }
+
+    /// Fetch the specimen quality indices recorded at accessioning
+    pub async fn get_sample(&self, sample_id: Uuid) -> Result<SampleData> {
+        let query = r#"
+            query Sample($id: ID!) {
+                sample(id: $id) {
+                    id
+                    isHemolyzed
+                    isLipemic
+                    isIcteric
+                }
+            }
+        "#;
+
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: serde_json::json!({ "id": sample_id.to_string() }),
+        };
+
+        let url = format!("{}/graphql", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .bearer_auth(self.jwt.tenant_token()?)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to call sample-service: {}", e);
+                Error::ExternalService(format!("Failed to connect to sample-service: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::error!("Sample-service returned error {}: {}", status, body);
+            return Err(Error::ExternalService(
+                format!("Sample-service returned error {}: {}", status, body)
+            ));
+        }
+
+        let graphql_response: GraphQLResponse<SampleResponse> = response
+            .json()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to parse sample-service response: {}", e);
+                Error::ExternalService(format!("Invalid response from sample-service: {}", e))
+            })?;
+
+        if let Some(errors) = graphql_response.errors {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+            return Err(Error::ExternalService(
+                format!("Sample-service error: {}", error_messages.join(", "))
+            ));
+        }
+
+        graphql_response.data
+            .and_then(|data| data.sample)
+            .ok_or_else(|| Error::NotFound(format!("Sample not found: {}", sample_id)))
+    }
+}
@@ -1,8 +1,18 @@
+use std::collections::BTreeMap;
+use chrono::{NaiveDate, Utc};
 use uuid::Uuid;
 use common::error::{Error, Result};
+use common::types::Gender;
 
 use crate::domain::*;
 use crate::repository::*;
+use crate::hl7_sender::Hl7ResultSender;
+use crate::delta_check::{self, DeltaLimits};
+use crate::order_client::OrderClient;
+use crate::patient_client::{PatientClient, PatientData};
+use crate::qc_client::QcClient;
+use crate::rule_engine::{self, PatientFacts, RuleContext, RuleOutcome, SpecimenQuality};
+use crate::sample_client::SampleClient;
 
 // ============================================================================
 // Result Service
@@ -14,20 +24,39 @@ pub struct ResultService {
     reference_range_repo: ReferenceRangeRepository,
     auto_verification_repo: AutoVerificationRuleRepository,
     critical_notification_repo: CriticalResultNotificationRepository,
+    hl7_sender: Option<Hl7ResultSender>,
+    patient_client: PatientClient,
+    sample_client: SampleClient,
+    order_client: OrderClient,
+    qc_client: QcClient,
+    events_enabled: bool,
 }
 
 impl ResultService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         result_repo: TestResultRepository,
         reference_range_repo: ReferenceRangeRepository,
         auto_verification_repo: AutoVerificationRuleRepository,
         critical_notification_repo: CriticalResultNotificationRepository,
+        hl7_sender: Option<Hl7ResultSender>,
+        patient_client: PatientClient,
+        sample_client: SampleClient,
+        order_client: OrderClient,
+        qc_client: QcClient,
+        events_enabled: bool,
     ) -> Self {
         Self {
             result_repo,
             reference_range_repo,
             auto_verification_repo,
             critical_notification_repo,
+            hl7_sender,
+            patient_client,
+            sample_client,
+            order_client,
+            qc_client,
+            events_enabled,
         }
     }
 
@@ -39,35 +68,25 @@ impl ResultService {
         input.validate()?;
 
         // Create result
-        let mut result = self.result_repo.create(input, org_id, user_id).await?;
+        let result = self.result_repo.create(input, org_id, user_id).await?;
 
-        // Get patient age and gender (TODO: from patient service)
-        let patient_age = 30;
-        let patient_gender = "MALE";
+        let patient = self.load_patient(&result).await;
 
-        // Apply reference range
-        if let Some(range) = self.reference_range_repo
-            .find_applicable_range(result.test_id, patient_age, patient_gender)
-            .await?
-        {
-            result.reference_range_min = range.range_min;
-            result.reference_range_max = range.range_max;
-            result.reference_range_text = range.range_text;
-        }
-
-        // Calculate interpretation
-        result.calculate_interpretation();
-
-        // Check for critical values
-        result = self.check_critical_values(result).await?;
+        // Apply reference range, flag critical values and interpret
+        let result = self.apply_reference_range(result, patient.as_ref()).await?;
 
         // Perform delta check
-        result = self.perform_delta_check(result).await?;
+        let result = self.perform_delta_check(result).await?;
+
+        // Hold the result if the analyzer's QC was out of control
+        let (result, qc_known) = self.apply_qc_gate(result).await;
 
         // Attempt auto-verification
-        result = self.attempt_auto_verification(result).await?;
+        let result = self.attempt_auto_verification(result, patient.as_ref(), qc_known).await?;
+
+        let critical_value = self.critical_value_payload(&result, patient.as_ref()).await;
+        let result = self.result_repo.save_evaluation(&result, user_id, critical_value.as_ref()).await?;
 
-        // TODO: Publish RESULT_CREATED event
         // TODO: Cache result
 
         tracing::info!("Result created: {}", result.result_number);
@@ -116,15 +135,16 @@ impl ResultService {
             ));
         }
 
-        let mut result = self.result_repo.update_result(input, user_id).await?;
+        let result = self.result_repo.update_result(input, user_id).await?;
 
-        // Recalculate interpretation
-        result.calculate_interpretation();
+        // Re-interpret the new value against the patient's range
+        let patient = self.load_patient(&result).await;
+        let result = self.apply_reference_range(result, patient.as_ref()).await?;
 
-        // Re-check critical values
-        result = self.check_critical_values(result).await?;
+        // A corrected value that is still critical is paged again
+        let critical_value = self.critical_value_payload(&result, patient.as_ref()).await;
+        let result = self.result_repo.save_evaluation(&result, user_id, critical_value.as_ref()).await?;
 
-        // TODO: Publish RESULT_UPDATED event
         // TODO: Invalidate cache
 
         tracing::info!("Result updated: {}", result.result_number);
@@ -149,7 +169,6 @@ impl ResultService {
 
         let result = self.result_repo.verify_result(input, user_id).await?;
 
-        // TODO: Publish RESULT_VERIFIED event
         // TODO: Invalidate cache
 
         tracing::info!("Result verified: {}", result.result_number);
@@ -170,6 +189,17 @@ impl ResultService {
             return Err(Error::Validation("Result is already approved".to_string()));
         }
 
+        // QC may have been rejected since the result was verified, or was
+        // unknown then, so the gate is asked again on every approval. The
+        // approval fails while qc-service cannot answer.
+        let current = self.refresh_qc_hold(current).await?;
+        if current.qc_hold {
+            return Err(Error::BusinessRuleViolation(format!(
+                "Result is on QC hold until a corrective action is completed: {}",
+                current.qc_hold_reason.as_deref().unwrap_or("QC out of control")
+            )));
+        }
+
         let result = self.result_repo.approve_result(input, user_id).await?;
 
         // If critical, ensure notification is recorded
@@ -178,7 +208,9 @@ impl ResultService {
             tracing::warn!("Critical result approved: {}. Ensure notification is documented.", result.result_number);
         }
 
-        // TODO: Publish RESULT_APPROVED event
+        // An approved correction replaces the final result the HIS holds (OBX-11 = C)
+        self.send_to_his(&result);
+
         // TODO: Trigger report generation
         // TODO: Invalidate cache
 
@@ -198,9 +230,9 @@ impl ResultService {
             ));
         }
 
+        // Reaches the HIS once the correction is verified and approved
         let result = self.result_repo.correct_result(input, user_id).await?;
 
-        // TODO: Publish RESULT_CORRECTED event
         // TODO: Notify stakeholders
         // TODO: Invalidate cache
 
@@ -248,19 +280,40 @@ impl ResultService {
         self.critical_notification_repo.find_by_result(result_id).await
     }
 
+    /// Acknowledges a critical notification once the receiver has read the
+    /// value back correctly. A wrong read-back is rejected so the caller
+    /// repeats the value and asks again.
     pub async fn acknowledge_critical_notification(
         &self,
-        notification_id: Uuid,
-        acknowledged_by: &str,
-        method: &str
+        input: AcknowledgeCriticalNotificationInput,
+        user_id: Uuid
     ) -> Result<CriticalResultNotification> {
+        input.validate()?;
+
         let notification = self.critical_notification_repo
-            .acknowledge(notification_id, acknowledged_by, method)
-            .await?;
+            .find_by_id(input.notification_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Critical notification not found: {}", input.notification_id)))?;
+
+        if notification.acknowledged {
+            return Err(Error::Validation("Critical notification is already acknowledged".to_string()));
+        }
+
+        let result = self.get_result(notification.result_id).await?;
+        let reported = result.result_value.as_deref().unwrap_or_default();
 
-        // TODO: Publish CRITICAL_NOTIFICATION_ACKNOWLEDGED event
+        if !read_back_matches(reported, result.result_unit.as_deref(), &input.read_back_value) {
+            return Err(Error::Validation(format!(
+                "Read-back '{}' does not match the reported value '{}{}'; repeat the result and confirm again",
+                input.read_back_value.trim(),
+                reported,
+                result.result_unit.as_deref().map(|unit| format!(" {}", unit)).unwrap_or_default(),
+            )));
+        }
 
-        tracing::info!("Critical notification acknowledged: {}", notification_id);
+        let notification = self.critical_notification_repo.acknowledge(&input, &result, user_id).await?;
+
+        tracing::info!("Critical notification acknowledged: {}", notification.id);
         Ok(notification)
     }
 
@@ -268,39 +321,126 @@ impl ResultService {
         self.critical_notification_repo.get_unacknowledged(org_id).await
     }
 
+    // ========================================================================
+    // HL7 Result Interface
+    // ========================================================================
+
+    /// Reports a result to the HIS in the background; delivery failures
+    /// stay in the HL7 log for replay
+    fn send_to_his(&self, result: &TestResult) {
+        if let Some(sender) = self.hl7_sender.clone() {
+            let result = result.clone();
+            common::tenant::spawn(async move {
+                if let Err(e) = sender.send_result(&result).await {
+                    tracing::error!("Failed to send ORU for result {}: {}", result.result_number, e);
+                }
+            });
+        }
+    }
+
+    fn hl7_sender(&self) -> Result<&Hl7ResultSender> {
+        self.hl7_sender
+            .as_ref()
+            .ok_or_else(|| Error::Configuration("HL7 outbound interface is not enabled".to_string()))
+    }
+
+    pub async fn get_hl7_messages(&self, org_id: Uuid, status: Option<&str>, limit: i64) -> Result<Vec<Hl7MessageLog>> {
+        self.hl7_sender()?.list_messages(org_id, status, limit).await
+    }
+
+    pub async fn replay_hl7_message(&self, log_id: Uuid, org_id: Uuid) -> Result<Hl7MessageLog> {
+        self.hl7_sender()?.replay(log_id, org_id).await
+    }
+
     // ========================================================================
     // Auto-Verification Engine
     // ========================================================================
 
-    async fn attempt_auto_verification(&self, mut result: TestResult) -> Result<TestResult> {
+    async fn attempt_auto_verification(
+        &self,
+        mut result: TestResult,
+        patient: Option<&PatientData>,
+        qc_known: bool,
+    ) -> Result<TestResult> {
         // Get applicable rules
-        let rules = self.auto_verification_repo.find_by_test(result.test_id).await?;
+        let rules = self.auto_verification_repo
+            .find_applicable(result.test_id, result.department.as_deref(), result.organization_id)
+            .await?;
 
         if rules.is_empty() {
             return Ok(result);
         }
 
+        let compiled: Vec<_> = rules.iter().map(rule_engine::compile).collect();
+        let needs = |namespace: &str| {
+            compiled.iter().any(|rule| rule.as_ref().is_ok_and(|rule| rule.references(namespace)))
+        };
+
+        // Only call out to sample-service when a rule uses specimen data. If a
+        // lookup failed the variables stay unknown and those rules fail.
+        let specimen = if needs("specimen") {
+            match self.sample_client.get_sample(result.sample_id).await {
+                Ok(sample) => Some(SpecimenQuality {
+                    is_hemolyzed: sample.is_hemolyzed,
+                    is_lipemic: sample.is_lipemic,
+                    is_icteric: sample.is_icteric,
+                }),
+                Err(e) => {
+                    tracing::warn!("Specimen data unavailable for {}: {}", result.result_number, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let patient = patient.map(|patient| PatientFacts {
+            date_of_birth: patient.date_of_birth,
+            gender: patient.gender,
+        });
+
+        let context = RuleContext::new(&result, specimen.as_ref(), patient.as_ref(), Utc::now());
+
         let mut passed_rules = Vec::new();
         let mut failed_rules = Vec::new();
         let mut can_auto_verify = true;
 
-        for rule in rules {
-            let passed = self.evaluate_verification_rule(&result, &rule).await?;
+        for (rule, compiled) in rules.iter().zip(compiled) {
+            let (outcome, trace) = match compiled {
+                Ok(compiled) => {
+                    let (outcome, inputs) = compiled.evaluate(&context);
+                    let message = (outcome == RuleOutcome::Unknown)
+                        .then_some("Rule could not be decided because an input is missing");
+                    (outcome, rule_engine::trace_entry(rule, outcome, Some(&compiled.source), inputs, message))
+                }
+                Err(e) => {
+                    tracing::warn!("Invalid verification rule {}: {}", rule.rule_code, e);
+                    let outcome = RuleOutcome::Error;
+                    (outcome, rule_engine::trace_entry(rule, outcome, None, BTreeMap::new(), Some(&e)))
+                }
+            };
 
-            if passed {
-                passed_rules.push(rule.rule_code.clone());
+            if outcome == RuleOutcome::Pass {
+                passed_rules.push(trace);
             } else {
-                failed_rules.push(rule.rule_code.clone());
+                failed_rules.push(trace);
                 if rule.is_blocking {
                     can_auto_verify = false;
                 }
             }
         }
 
-        result.verification_rules_passed = Some(serde_json::to_value(&passed_rules).unwrap());
-        result.verification_rules_failed = Some(serde_json::to_value(&failed_rules).unwrap());
+        // Never auto-verify behind a rejected QC run, or without knowing
+        // whether QC was in control, whatever the rules say
+        if result.qc_hold || !qc_known {
+            can_auto_verify = false;
+        }
+
+        let all_passed = failed_rules.is_empty();
+        result.verification_rules_passed = Some(serde_json::Value::Array(passed_rules));
+        result.verification_rules_failed = Some(serde_json::Value::Array(failed_rules));
 
-        if can_auto_verify && failed_rules.is_empty() {
+        if can_auto_verify && all_passed {
             result.verification_status = VerificationStatus::AutoVerified;
             result.auto_verification_confidence = Some(rust_decimal::Decimal::from(95)); // 95% confidence
             tracing::info!("Result auto-verified: {}", result.result_number);
@@ -312,83 +452,206 @@ impl ResultService {
         Ok(result)
     }
 
-    async fn evaluate_verification_rule(&self, result: &TestResult, rule: &AutoVerificationRule) -> Result<bool> {
-        match rule.rule_type.as_str() {
-            "RANGE_CHECK" => {
-                // Check if result is within reference range
-                Ok(result.is_within_reference_range())
+    // ========================================================================
+    // QC-Gated Release
+    // ========================================================================
+
+    /// Looks up the QC run governing the result's test, instrument and time.
+    /// A rejected run puts the result on hold. Without a QC run qc_passed
+    /// stays unknown so QC_CHECK rules send the result to manual review.
+    /// Returns false alongside the result when qc-service cannot be reached.
+    async fn apply_qc_gate(&self, mut result: TestResult) -> (TestResult, bool) {
+        let gate = self.qc_client
+            .get_gate(result.test_id, result.instrument_id, result.qc_lot_number.as_deref(), result.result_date)
+            .await;
+
+        match gate {
+            Ok(gate) if gate.hold => {
+                tracing::warn!("Result {} held: {}", result.result_number, gate.hold_reason());
+                result.qc_passed = Some(false);
+                result.qc_hold = true;
+                result.qc_hold_qc_result_id = gate.qc_result_id;
+                result.qc_hold_reason = Some(gate.hold_reason());
             }
-            "DELTA_CHECK" => {
-                // Check if delta is acceptable
-                if result.delta_flag == DeltaFlag::Normal || result.delta_flag == DeltaFlag::NoPreviousResult {
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
+            Ok(gate) if gate.qc_result_id.is_some() => {
+                result.qc_passed = Some(true);
             }
-            "QC_CHECK" => {
-                // Check if QC passed
-                Ok(result.qc_passed.unwrap_or(true))
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("QC status unavailable for {}: {}", result.result_number, e);
+                return (result, false);
             }
-            "CRITICAL_CHECK" => {
-                // Check if result is not critical
-                Ok(!result.is_critical)
+        }
+
+        (result, true)
+    }
+
+    /// Holds the result when its QC run is now rejected, and lifts the hold
+    /// once qc-service reports the QC run corrected
+    async fn refresh_qc_hold(&self, result: TestResult) -> Result<TestResult> {
+        let gate = self.qc_client
+            .get_gate(result.test_id, result.instrument_id, result.qc_lot_number.as_deref(), result.result_date)
+            .await?;
+
+        match (gate.hold, result.qc_hold) {
+            (true, false) => {
+                let result = self.result_repo
+                    .place_qc_hold(result.id, gate.qc_result_id, &gate.hold_reason())
+                    .await?;
+                tracing::warn!("Result {} held: {}", result.result_number, gate.hold_reason());
+                Ok(result)
+            }
+            (false, true) => {
+                let result = self.result_repo.release_qc_hold(result.id).await?;
+                tracing::info!("QC hold released: {}", result.result_number);
+                Ok(result)
+            }
+            _ => Ok(result),
+        }
+    }
+
+    /// Releases results held by a QC result whose corrective action is now
+    /// complete. Returns the results that were released.
+    pub async fn release_qc_holds(&self, qc_result_id: Uuid) -> Result<Vec<TestResult>> {
+        let mut released = Vec::new();
+
+        for result in self.result_repo.find_held_by_qc(qc_result_id).await? {
+            let result = self.refresh_qc_hold(result).await?;
+            if !result.qc_hold {
+                released.push(result);
             }
-            _ => {
-                tracing::warn!("Unknown verification rule type: {}", rule.rule_type);
-                Ok(true)
+        }
+
+        Ok(released)
+    }
+
+    pub async fn get_qc_held_results(&self, org_id: Uuid, limit: i64) -> Result<Vec<TestResult>> {
+        self.result_repo.get_qc_held(org_id, limit).await
+    }
+
+    /// Patient results measured on the instrument since its last accepted QC
+    /// run before the given out of control QC result, for re-testing
+    pub async fn get_qc_lookback_results(&self, qc_result_id: Uuid, limit: i64) -> Result<Vec<TestResult>> {
+        let window = self.qc_client.get_lookback_window(qc_result_id).await?;
+
+        self.result_repo
+            .find_for_qc_lookback(window.test_id, window.equipment_id, window.from, limit)
+            .await
+    }
+
+    // ========================================================================
+    // Auto-Verification Rules
+    // ========================================================================
+
+    pub async fn create_auto_verification_rule(
+        &self,
+        input: CreateAutoVerificationRuleInput,
+        org_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<AutoVerificationRule> {
+        let rule = self.auto_verification_repo.create(input, org_id, user_id).await?;
+        tracing::info!("Auto-verification rule created: {}", rule.rule_code);
+        Ok(rule)
+    }
+
+    pub async fn get_auto_verification_rules(&self, test_id: Option<Uuid>, org_id: Uuid) -> Result<Vec<AutoVerificationRule>> {
+        self.auto_verification_repo.list(test_id, org_id).await
+    }
+
+    // ========================================================================
+    // Reference Range Resolution
+    // ========================================================================
+
+    /// Fetches patient demographics. When patient-service cannot be reached
+    /// the result is still evaluated, but only against ranges that do not
+    /// depend on age or gender.
+    async fn load_patient(&self, result: &TestResult) -> Option<PatientData> {
+        match self.patient_client.get_patient(result.patient_id).await {
+            Ok(patient) => Some(patient),
+            Err(e) => {
+                tracing::warn!(
+                    "Patient demographics unavailable for {}: {}. Only age- and gender-independent ranges apply.",
+                    result.result_number,
+                    e
+                );
+                None
             }
         }
     }
 
+    async fn apply_reference_range(&self, result: TestResult, patient: Option<&PatientData>) -> Result<TestResult> {
+        let result_date = result.result_date.date_naive();
+        let age = patient.map(|p| PatientAge::on(p.date_of_birth, result_date));
+
+        let range = self.reference_range_repo
+            .find_applicable_range(
+                result.test_id,
+                result.organization_id,
+                age.as_ref(),
+                patient.map(|p| p.gender),
+                result_date,
+            )
+            .await?;
+
+        let mut result = result;
+        result.reference_range_id = range.as_ref().map(|r| r.id);
+        result.reference_range_min = range.as_ref().and_then(|r| r.range_min);
+        result.reference_range_max = range.as_ref().and_then(|r| r.range_max);
+        result.reference_range_text = range.as_ref().and_then(|r| r.range_text.clone());
+
+        if range.is_none() {
+            tracing::warn!("No applicable reference range for result {}", result.result_number);
+        }
+
+        // Critical flags first, so the interpretation can reflect them
+        let mut result = self.check_critical_values(result, range.as_ref());
+        result.calculate_interpretation();
+
+        Ok(result)
+    }
+
     // ========================================================================
     // Critical Value Detection
     // ========================================================================
 
-    async fn check_critical_values(&self, mut result: TestResult) -> Result<TestResult> {
-        if let Some(value_str) = &result.result_value {
+    fn check_critical_values(&self, mut result: TestResult, range: Option<&ReferenceRange>) -> TestResult {
+        result.critical_flag = CriticalFlag::None;
+        result.is_critical = false;
+
+        if let (Some(value_str), Some(range)) = (&result.result_value, range) {
             if let Ok(value) = value_str.parse::<f64>() {
-                // Get reference range with critical values
-                let patient_age = 30; // TODO: Get from patient service
-                let patient_gender = "MALE"; // TODO: Get from patient service
-
-                if let Some(range) = self.reference_range_repo
-                    .find_applicable_range(result.test_id, patient_age, patient_gender)
-                    .await?
-                {
-                    // Check panic values
-                    if let Some(panic_low) = range.panic_low {
-                        let panic_low_f = panic_low.to_string().parse::<f64>().unwrap_or(f64::MIN);
-                        if value < panic_low_f {
-                            result.critical_flag = CriticalFlag::PanicLow;
-                            result.is_critical = true;
-                        }
+                // Check panic values
+                if let Some(panic_low) = range.panic_low {
+                    let panic_low_f = panic_low.to_string().parse::<f64>().unwrap_or(f64::MIN);
+                    if value < panic_low_f {
+                        result.critical_flag = CriticalFlag::PanicLow;
+                        result.is_critical = true;
                     }
+                }
 
-                    if let Some(panic_high) = range.panic_high {
-                        let panic_high_f = panic_high.to_string().parse::<f64>().unwrap_or(f64::MAX);
-                        if value > panic_high_f {
-                            result.critical_flag = CriticalFlag::PanicHigh;
-                            result.is_critical = true;
-                        }
+                if let Some(panic_high) = range.panic_high {
+                    let panic_high_f = panic_high.to_string().parse::<f64>().unwrap_or(f64::MAX);
+                    if value > panic_high_f {
+                        result.critical_flag = CriticalFlag::PanicHigh;
+                        result.is_critical = true;
                     }
+                }
 
-                    // Check critical values (less severe than panic)
-                    if !result.is_critical {
-                        if let Some(critical_low) = range.critical_low {
-                            let critical_low_f = critical_low.to_string().parse::<f64>().unwrap_or(f64::MIN);
-                            if value < critical_low_f {
-                                result.critical_flag = CriticalFlag::Low;
-                                result.is_critical = true;
-                            }
+                // Check critical values (less severe than panic)
+                if !result.is_critical {
+                    if let Some(critical_low) = range.critical_low {
+                        let critical_low_f = critical_low.to_string().parse::<f64>().unwrap_or(f64::MIN);
+                        if value < critical_low_f {
+                            result.critical_flag = CriticalFlag::Low;
+                            result.is_critical = true;
                         }
+                    }
 
-                        if let Some(critical_high) = range.critical_high {
-                            let critical_high_f = critical_high.to_string().parse::<f64>().unwrap_or(f64::MAX);
-                            if value > critical_high_f {
-                                result.critical_flag = CriticalFlag::High;
-                                result.is_critical = true;
-                            }
+                    if let Some(critical_high) = range.critical_high {
+                        let critical_high_f = critical_high.to_string().parse::<f64>().unwrap_or(f64::MAX);
+                        if value > critical_high_f {
+                            result.critical_flag = CriticalFlag::High;
+                            result.is_critical = true;
                         }
                     }
                 }
@@ -396,65 +659,167 @@ impl ResultService {
         }
 
         if result.is_critical {
+            // Paging starts once the result is saved, see critical_value_payload
             tracing::warn!("CRITICAL RESULT DETECTED: {} - {}", result.result_number, result.test_name);
-            // TODO: Trigger immediate notification workflow
         }
 
-        Ok(result)
+        result
+    }
+
+    // ========================================================================
+    // Domain Events
+    // ========================================================================
+
+    /// Payload of the CRITICAL_VALUE_DETECTED event for a critical result.
+    /// notification-service pages the ordering clinician from it and
+    /// escalates until the call is acknowledged.
+    async fn critical_value_payload(&self, result: &TestResult, patient: Option<&PatientData>) -> Option<serde_json::Value> {
+        if !result.is_critical {
+            return None;
+        }
+
+        if !self.events_enabled {
+            tracing::warn!(
+                "Events are disabled; critical result {} must be phoned through manually",
+                result.result_number
+            );
+        }
+
+        let order = match self.order_client.get_order(result.order_id).await {
+            Ok(order) => Some(order),
+            Err(e) => {
+                tracing::warn!("Could not load order {} for critical result {}: {}", result.order_id, result.result_number, e);
+                None
+            }
+        };
+
+        let payload = serde_json::json!({
+            "result_id": result.id,
+            "result_number": result.result_number,
+            "patient_id": result.patient_id,
+            "patient_mrn": patient.map(|p| p.mrn_number.clone()),
+            "order_id": result.order_id,
+            "order_number": order.as_ref().map(|o| o.order_number.clone()),
+            "ordering_clinician_id": order.as_ref().and_then(|o| o.referring_doctor_id),
+            "ordering_clinician_name": order.as_ref().and_then(|o| o.referring_doctor_name.clone()),
+            "test_code": result.test_code,
+            "test_name": result.test_name,
+            "department": result.department,
+            "result_value": result.result_value,
+            "result_unit": result.result_unit,
+            "critical_flag": rule_engine::enum_name(&result.critical_flag),
+            "result_date": result.result_date,
+        });
+
+        Some(payload)
     }
 
     // ========================================================================
     // Delta Check Analysis
     // ========================================================================
 
-    async fn perform_delta_check(&self, mut result: TestResult) -> Result<TestResult> {
-        // Get previous result for this patient and test
-        if let Some(previous) = self.result_repo
-            .get_previous_result(result.patient_id, result.test_id)
-            .await?
-        {
-            result.previous_result_value = previous.result_value.clone();
-            result.previous_result_date = Some(previous.result_date);
-
-            // Calculate delta if both are numeric
-            if let (Some(current_str), Some(prev_str)) = (&result.result_value, &previous.result_value) {
-                if let (Ok(current_val), Ok(prev_val)) = (
-                    current_str.parse::<f64>(),
-                    prev_str.parse::<f64>()
-                ) {
-                    if prev_val != 0.0 {
-                        let delta_abs = current_val - prev_val;
-                        let delta_pct = ((current_val - prev_val) / prev_val) * 100.0;
-
-                        result.delta_absolute = Some(rust_decimal::Decimal::try_from(delta_abs).unwrap_or_default());
-                        result.delta_percentage = Some(rust_decimal::Decimal::try_from(delta_pct).unwrap_or_default());
-
-                        // Classify delta
-                        let threshold = 50.0; // 50% change threshold
-                        result.delta_flag = if delta_pct.abs() > threshold {
-                            if delta_pct > 0.0 {
-                                DeltaFlag::SignificantIncrease
-                            } else {
-                                DeltaFlag::SignificantDecrease
-                            }
-                        } else {
-                            DeltaFlag::Normal
-                        };
-
-                        if result.delta_flag != DeltaFlag::Normal {
-                            tracing::warn!(
-                                "Significant delta detected for {}: {}%",
-                                result.result_number,
-                                delta_pct
-                            );
-                        }
-                    }
+    /// The delta rule for a result's test: a rule for the test itself wins
+    /// over a department rule, which wins over a global one
+    async fn find_delta_rule(&self, result: &TestResult) -> Result<Option<AutoVerificationRule>> {
+        let rules = self.auto_verification_repo
+            .find_applicable(result.test_id, result.department.as_deref(), result.organization_id)
+            .await?;
+
+        Ok(rules
+            .into_iter()
+            .filter(|rule| rule.rule_type == rule_engine::RULE_TYPE_DELTA_CHECK)
+            .min_by_key(|rule| {
+                if rule.test_id == Some(result.test_id) {
+                    0
+                } else if rule.department.is_some() && rule.department == result.department {
+                    1
+                } else {
+                    2
                 }
+            }))
+    }
+
+    async fn perform_delta_check(&self, mut result: TestResult) -> Result<TestResult> {
+        let rule = self.find_delta_rule(&result).await?;
+        let limits = match &rule {
+            Some(rule) => DeltaLimits::from_rule(rule).unwrap_or_else(|e| {
+                tracing::warn!("Invalid delta rule {}: {}. Using default limits.", rule.rule_code, e);
+                DeltaLimits::default_limits()
+            }),
+            None => DeltaLimits::default_limits(),
+        };
+        let rule_code = rule.as_ref().map(|r| r.rule_code.as_str());
+
+        // Get previous result for this patient and test within the lookback window
+        let previous = self.result_repo
+            .get_previous_result(
+                result.patient_id,
+                result.test_id,
+                result.result_date,
+                limits.lookback_start(result.result_date),
+            )
+            .await?;
+
+        let previous = match previous {
+            Some(previous) => previous,
+            None => {
+                result.delta_flag = DeltaFlag::NoPreviousResult;
+                result.delta_check_details = Some(serde_json::json!({
+                    "rule_code": rule_code,
+                    "limits": limits,
+                    "explanation": match limits.lookback_days {
+                        Some(days) => format!("No previous result within {} days", days),
+                        None => "No previous result".to_string(),
+                    },
+                }));
+                return Ok(result);
             }
-        } else {
-            result.delta_flag = DeltaFlag::NoPreviousResult;
+        };
+
+        result.previous_result_value = previous.result_value.clone();
+        result.previous_result_date = Some(previous.result_date);
+
+        // Calculate delta if both are numeric
+        let values = result.result_value.as_deref()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .zip(previous.result_value.as_deref().and_then(|v| v.trim().parse::<f64>().ok()));
+
+        let (current_val, prev_val) = match values {
+            Some(values) => values,
+            None => {
+                result.delta_flag = DeltaFlag::Normal;
+                result.delta_check_details = Some(serde_json::json!({
+                    "rule_code": rule_code,
+                    "previous_result_id": previous.id,
+                    "explanation": "Delta not calculated for non-numeric values",
+                }));
+                return Ok(result);
+            }
+        };
+
+        let elapsed_days = (result.result_date - previous.result_date).num_minutes() as f64 / 1440.0;
+        let evaluation = delta_check::evaluate(current_val, prev_val, elapsed_days, &limits);
+        let explanation = evaluation.explanation(rule_code);
+
+        result.delta_absolute = Some(rust_decimal::Decimal::try_from(evaluation.delta_absolute).unwrap_or_default());
+        result.delta_percentage = evaluation.delta_percent
+            .map(|pct| rust_decimal::Decimal::try_from(pct).unwrap_or_default());
+        result.delta_flag = evaluation.flag();
+
+        if result.delta_flag != DeltaFlag::Normal {
+            tracing::warn!("Significant delta detected for {}: {}", result.result_number, explanation);
         }
 
+        result.delta_check_details = Some(serde_json::json!({
+            "rule_code": rule_code,
+            "previous_result_id": previous.id,
+            "previous_value": prev_val,
+            "previous_date": previous.result_date,
+            "limits": limits,
+            "evaluation": evaluation,
+            "explanation": explanation,
+        }));
+
         Ok(result)
     }
 
@@ -469,9 +834,11 @@ impl ResultService {
     pub async fn get_applicable_reference_range(
         &self,
         test_id: Uuid,
-        age: i32,
-        gender: &str
+        org_id: Uuid,
+        age: &PatientAge,
+        gender: Gender,
+        on: NaiveDate,
     ) -> Result<Option<ReferenceRange>> {
-        self.reference_range_repo.find_applicable_range(test_id, age, gender).await
+        self.reference_range_repo.find_applicable_range(test_id, org_id, Some(age), Some(gender), on).await
     }
 }
@@ -100,7 +100,7 @@ mod auto_verification_tests {
 
     #[tokio::test]
     async fn test_delta_check_significant_decrease() {
-        let current_value = 40.0;
+        let current_value: f64 = 40.0;
         let previous_value = 100.0;
         let threshold = 50.0;
 
@@ -0,0 +1,15 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('event_outbox');
+SELECT enable_tenant_isolation('sample');
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('sample_aliquot', 'parent_sample_id', 'sample');
+SELECT enable_parent_isolation('sample_container', 'sample_id', 'sample');
+SELECT enable_parent_isolation('sample_event_log', 'sample_id', 'sample');
+SELECT enable_parent_isolation('sample_routing', 'sample_id', 'sample');
+SELECT enable_parent_isolation('sample_temperature_log', 'sample_id', 'sample');
@@ -1,6 +1,8 @@
 use async_graphql::{Context, Object, Result, ID, SimpleObject, InputObject, Enum};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use common::auth::{AuthContext, PermissionGuard};
+use common::permissions;
 use common::types::{SampleType, SampleStatus, Priority};
 
 use crate::domain::*;
@@ -312,6 +314,7 @@ pub struct QueryRoot;
 #[Object]
 impl QueryRoot {
     /// Get sample by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
     async fn sample(&self, ctx: &Context<'_>, id: ID) -> Result<Option<SampleGQL>> {
         let service = ctx.data::<SampleService>()?;
         let sample_id = Uuid::parse_str(&id)?;
@@ -322,7 +325,21 @@ impl QueryRoot {
         }
     }
 
+    /// Get samples by IDs; unknown IDs are skipped
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
+    async fn samples_by_ids(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<Vec<SampleGQL>> {
+        let service = ctx.data::<SampleService>()?;
+        let ids = ids
+            .iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let samples = service.get_samples_by_ids(&ids).await?;
+        Ok(samples.into_iter().map(|s| s.into()).collect())
+    }
+
     /// Get sample by sample ID (human-readable ID)
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
     async fn sample_by_sample_id(&self, ctx: &Context<'_>, sample_id: String) -> Result<Option<SampleGQL>> {
         let service = ctx.data::<SampleService>()?;
 
@@ -333,6 +350,7 @@ impl QueryRoot {
     }
 
     /// Get sample by barcode
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
     async fn sample_by_barcode(&self, ctx: &Context<'_>, barcode: String) -> Result<Option<SampleGQL>> {
         let service = ctx.data::<SampleService>()?;
 
@@ -343,6 +361,7 @@ impl QueryRoot {
     }
 
     /// Get samples by patient
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
     async fn samples_by_patient(
         &self,
         ctx: &Context<'_>,
@@ -358,6 +377,7 @@ impl QueryRoot {
     }
 
     /// Get samples by order
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_READ)")]
     async fn samples_by_order(&self, ctx: &Context<'_>, order_id: ID) -> Result<Vec<SampleGQL>> {
         let service = ctx.data::<SampleService>()?;
         let order_uuid = Uuid::parse_str(&order_id)?;
@@ -376,21 +396,22 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// Create a new sample
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_CREATE)")]
     async fn create_sample(
         &self,
         ctx: &Context<'_>,
         input: CreateSampleInputGQL,
     ) -> Result<SampleGQL> {
         let service = ctx.data::<SampleService>()?;
-        // TODO: Get from authentication context
-        let org_id = Uuid::nil();
-        let user_id = Uuid::nil();
+        let org_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
 
         let sample = service.create_sample(input.into(), org_id, user_id).await?;
         Ok(sample.into())
     }
 
     /// Receive sample at laboratory
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_RECEIVE)")]
     async fn receive_sample(
         &self,
         ctx: &Context<'_>,
@@ -399,7 +420,7 @@ impl MutationRoot {
     ) -> Result<SampleGQL> {
         let service = ctx.data::<SampleService>()?;
         let sample_uuid = Uuid::parse_str(&sample_id)?;
-        let user_id = Uuid::nil(); // TODO: Get from auth context
+        let user_id = ctx.user_id()?;
 
         let receive_input = ReceiveSampleInput {
             sample_id: sample_uuid,
@@ -418,6 +439,7 @@ impl MutationRoot {
     }
 
     /// Reject sample
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_REJECT)")]
     async fn reject_sample(
         &self,
         ctx: &Context<'_>,
@@ -426,7 +448,7 @@ impl MutationRoot {
     ) -> Result<SampleGQL> {
         let service = ctx.data::<SampleService>()?;
         let sample_uuid = Uuid::parse_str(&sample_id)?;
-        let user_id = Uuid::nil(); // TODO: Get from auth context
+        let user_id = ctx.user_id()?;
 
         let reject_input = RejectSampleInput {
             sample_id: sample_uuid,
@@ -440,16 +462,36 @@ impl MutationRoot {
     }
 
     /// Accept sample for processing
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_RECEIVE)")]
     async fn accept_sample(&self, ctx: &Context<'_>, sample_id: ID) -> Result<SampleGQL> {
         let service = ctx.data::<SampleService>()?;
         let sample_uuid = Uuid::parse_str(&sample_id)?;
-        let user_id = Uuid::nil(); // TODO: Get from auth context
+        let user_id = ctx.user_id()?;
 
         let sample = service.accept_sample(sample_uuid, user_id).await?;
         Ok(sample.into())
     }
 
+    /// Record a chain of custody event
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_UPDATE)")]
+    async fn record_custody_event(
+        &self,
+        ctx: &Context<'_>,
+        sample_id: ID,
+        handler_id: ID,
+        action: String,
+        location: String,
+    ) -> Result<SampleGQL> {
+        let service = ctx.data::<SampleService>()?;
+        let sample_uuid = Uuid::parse_str(&sample_id)?;
+        let handler_uuid = Uuid::parse_str(&handler_id)?;
+
+        let sample = service.record_custody_event(sample_uuid, handler_uuid, &action, &location).await?;
+        Ok(sample.into())
+    }
+
     /// Auto-route sample
+    #[graphql(guard = "PermissionGuard::new(permissions::SAMPLE_RECEIVE)")]
     async fn auto_route_sample(&self, ctx: &Context<'_>, sample_id: ID) -> Result<bool> {
         let service = ctx.data::<SampleService>()?;
         let sample_uuid = Uuid::parse_str(&sample_id)?;
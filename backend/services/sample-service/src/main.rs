@@ -1,7 +1,9 @@
 use actix_web::{web, App, HttpServer, HttpResponse, middleware, guard};
 use async_graphql::{EmptySubscription, Schema, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::{DatabasePool, EventBusConfig, EventTransport, OutboxRelay};
 use tracing::{info, error};
 use tracing_subscriber;
 
@@ -39,23 +41,37 @@ async fn main() -> std::io::Result<()> {
 
     // Create database connection pool
     info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     info!("Database connection established");
 
     // Run migrations
     info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(infrastructure::outbox::migrator(sqlx::migrate!("./migrations"))).run(&pool))
         .await
         .expect("Failed to run migrations");
 
     info!("Migrations completed successfully");
 
+    // Connect the event transport (Kafka unless EVENT_BACKEND says otherwise)
+    let event_bus = if config.enable_events {
+        let event_config = EventBusConfig::from_env(&config.kafka_brokers).expect("Invalid event bus configuration");
+        Some(EventTransport::connect(&event_config).await.expect("Failed to create event bus"))
+    } else {
+        None
+    };
+
+    // Publish outbox events
+    if let Some(event_bus) = &event_bus {
+        OutboxRelay::new(pool.clone(), event_bus.clone()).spawn();
+        info!("Outbox relay started");
+    }
+
     // Create repositories
     let sample_repo = SampleRepository::new(pool.clone());
     let aliquot_repo = SampleAliquotRepository::new(pool.clone());
@@ -77,8 +93,10 @@ async fn main() -> std::io::Result<()> {
     // Start HTTP server
     info!("Starting HTTP server...");
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
             .app_data(web::Data::new(schema.clone()))
@@ -92,8 +110,8 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
-async fn graphql(schema: web::Data<SampleSchema>, req: GraphQLRequest) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+async fn graphql(schema: web::Data<SampleSchema>, claims: RequestClaims, req: GraphQLRequest) -> GraphQLResponse {
+    tenant::scope(claims.tenant(), schema.execute(claims.attach(req.into_inner()))).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -1,4 +1,6 @@
 use chrono::Utc;
+use infrastructure::event_bus::{events, topics};
+use infrastructure::outbox;
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use common::error::{Error, Result};
@@ -27,6 +29,8 @@ impl SampleRepository {
         // Generate sample ID with Luhn checksum
         let sample_id = self.generate_sample_id(&org_id, &input.sample_type).await?;
 
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let sample = sqlx::query_as::<_, Sample>(
             r#"
             INSERT INTO sample (
@@ -58,10 +62,24 @@ impl SampleRepository {
         .bind(&input.special_instructions)
         .bind(user_id)
         .bind(user_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::SAMPLE_EVENTS,
+            events::SAMPLE_COLLECTED,
+            "Sample",
+            sample.id,
+            sample.organization_id,
+            Some(user_id),
+            &sample,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(sample)
     }
 
@@ -78,6 +96,19 @@ impl SampleRepository {
         Ok(sample)
     }
 
+    /// Find samples by IDs
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Sample>> {
+        let samples = sqlx::query_as::<_, Sample>(
+            "SELECT * FROM sample WHERE id = ANY($1) AND is_deleted = FALSE"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(samples)
+    }
+
     /// Find sample by sample ID
     pub async fn find_by_sample_id(&self, sample_id: &str) -> Result<Option<Sample>> {
         let sample = sqlx::query_as::<_, Sample>(
@@ -211,6 +242,8 @@ impl SampleRepository {
 
     /// Receive sample at lab
     pub async fn receive_sample(&self, input: ReceiveSampleInput) -> Result<Sample> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let sample = sqlx::query_as::<_, Sample>(
             r#"
             UPDATE sample
@@ -239,15 +272,31 @@ impl SampleRepository {
         .bind(input.is_lipemic)
         .bind(input.is_icteric)
         .bind(input.sample_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::SAMPLE_EVENTS,
+            events::SAMPLE_RECEIVED,
+            "Sample",
+            sample.id,
+            sample.organization_id,
+            Some(input.received_by),
+            &sample,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(sample)
     }
 
     /// Reject sample
     pub async fn reject_sample(&self, input: RejectSampleInput) -> Result<Sample> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
         let sample = sqlx::query_as::<_, Sample>(
             r#"
             UPDATE sample
@@ -267,13 +316,43 @@ impl SampleRepository {
         .bind(&input.rejection_notes)
         .bind(input.rejected_by)
         .bind(input.sample_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::SAMPLE_EVENTS,
+            events::SAMPLE_REJECTED,
+            "Sample",
+            sample.id,
+            sample.organization_id,
+            Some(input.rejected_by),
+            &sample,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(sample)
     }
 
+    /// Append an entry to the sample's chain of custody
+    pub async fn add_custody_entry(&self, sample_id: Uuid, handler_id: Uuid, action: &str, location: &str) -> Result<Sample> {
+        sqlx::query("SELECT add_custody_entry($1, $2, $3, $4)")
+            .bind(sample_id)
+            .bind(handler_id)
+            .bind(action)
+            .bind(location)
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        self.find_by_id(sample_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Sample not found: {}", sample_id)))
+    }
+
     /// Generate barcode for sample
     pub async fn generate_barcode(&self, sample_id: Uuid, format: &str) -> Result<Sample> {
         let sample = sqlx::query_as::<_, Sample>(
@@ -476,6 +555,17 @@ impl SampleRoutingRepository {
 
         routing.calculate_expected_completion();
 
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let organization_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT organization_id FROM sample WHERE id = $1 AND is_deleted = FALSE"
+        )
+        .bind(routing.sample_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?
+        .ok_or_else(|| Error::NotFound(format!("Sample not found: {}", routing.sample_id)))?;
+
         let routing = sqlx::query_as::<_, SampleRouting>(
             r#"
             INSERT INTO sample_routing (
@@ -499,10 +589,24 @@ impl SampleRoutingRepository {
         .bind(&routing.routing_status)
         .bind(routing.is_automated)
         .bind(routing.automation_confidence)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(Error::Database)?;
 
+        outbox::enqueue_event(
+            &mut *tx,
+            topics::SAMPLE_EVENTS,
+            events::SAMPLE_ROUTED,
+            "Sample",
+            routing.sample_id,
+            organization_id,
+            None,
+            &routing,
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
         Ok(routing)
     }
 
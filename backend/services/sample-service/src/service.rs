@@ -14,8 +14,6 @@ pub struct SampleService {
     sample_repo: SampleRepository,
     aliquot_repo: SampleAliquotRepository,
     routing_repo: SampleRoutingRepository,
-    // Event bus will be added later
-    // event_bus: EventBus,
     // Cache will be added later
     // cache: CacheClient,
 }
@@ -56,9 +54,6 @@ impl SampleService {
         // Generate barcode
         sample = self.sample_repo.generate_barcode(sample.id, "CODE128").await?;
 
-        // TODO: Publish SAMPLE_CREATED event
-        // self.event_bus.publish("sample.created", sample).await?;
-
         // TODO: Cache sample
         // self.cache.set_json(&keys::sample(sample.id), &sample).await?;
 
@@ -86,6 +81,11 @@ impl SampleService {
         Ok(sample)
     }
 
+    /// Get samples by IDs
+    pub async fn get_samples_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Sample>> {
+        self.sample_repo.find_by_ids(ids).await
+    }
+
     /// Get sample by sample ID
     pub async fn get_sample_by_sample_id(&self, sample_id: &str) -> Result<Sample> {
         let sample = self
@@ -172,14 +172,31 @@ impl SampleService {
         // Check quality and auto-accept/reject
         let sample = self.evaluate_sample_quality(sample).await?;
 
-        // TODO: Publish SAMPLE_RECEIVED event
-        // self.event_bus.publish("sample.received", &sample).await?;
-
         tracing::info!("Sample received: {}", sample.sample_id);
 
         Ok(sample)
     }
 
+    /// Record a hand-off in the sample's chain of custody, e.g. a worklist
+    /// download to an analyzer
+    pub async fn record_custody_event(
+        &self,
+        sample_id: Uuid,
+        handler_id: Uuid,
+        action: &str,
+        location: &str,
+    ) -> Result<Sample> {
+        let sample = self.get_sample(sample_id).await?;
+
+        let sample = self.sample_repo
+            .add_custody_entry(sample.id, handler_id, action, location)
+            .await?;
+
+        tracing::info!("Custody event recorded for sample {}: {} at {}", sample.sample_id, action, location);
+
+        Ok(sample)
+    }
+
     /// Reject sample
     pub async fn reject_sample(&self, input: RejectSampleInput) -> Result<Sample> {
         let sample = self.get_sample(input.sample_id).await?;
@@ -194,9 +211,6 @@ impl SampleService {
         // Reject sample
         let sample = self.sample_repo.reject_sample(input).await?;
 
-        // TODO: Publish SAMPLE_REJECTED event
-        // self.event_bus.publish("sample.rejected", &sample).await?;
-
         // TODO: Notify stakeholders
         // self.notification_service.notify_sample_rejection(&sample).await?;
 
@@ -260,9 +274,6 @@ impl SampleService {
         };
         self.update_status(status_input).await?;
 
-        // TODO: Publish SAMPLE_ROUTED event
-        // self.event_bus.publish("sample.routed", &routing).await?;
-
         tracing::info!("Sample routed: {} -> {}", sample.sample_id, routing.route_to);
 
         Ok(routing)
@@ -0,0 +1,17 @@
+-- Row-level tenant isolation of the service's tables, through the helpers
+-- of infrastructure's shared tenant_isolation migration (sql/tenant_isolation.sql).
+-- Tables created by later migrations call enable_tenant_isolation() or
+-- enable_parent_isolation() for themselves.
+
+-- Tables with an organization of their own
+SELECT enable_tenant_isolation('api_key');
+SELECT enable_tenant_isolation('users');
+
+-- System roles have no organization and are offered to every lab
+SELECT enable_tenant_isolation('role', shared_rows => TRUE);
+
+-- Child tables, through the parent row they reference
+SELECT enable_parent_isolation('activity_log', 'user_id', 'users');
+SELECT enable_parent_isolation('role_permission', 'role_id', 'role');
+SELECT enable_parent_isolation('user_role', 'role_id', 'role');
+SELECT enable_parent_isolation('user_session', 'user_id', 'users');
@@ -0,0 +1,50 @@
+-- Two-factor authentication
+--
+-- users.two_factor_secret holds the TOTP secret from enrollment on; the
+-- factor counts once two_factor_enabled is set by a confirmed code.
+-- users.backup_codes holds argon2 hashes of the unused backup codes.
+
+-- Last TOTP time step accepted, so a code cannot be replayed
+ALTER TABLE users ADD COLUMN two_factor_last_step BIGINT;
+
+-- ============================================================================
+-- MFA Challenge Table
+-- ============================================================================
+
+-- Issued by a password login that still needs a second factor
+CREATE TABLE mfa_challenge (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    challenge_token VARCHAR(255) UNIQUE NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Device details carried over to the session
+    device_id VARCHAR(255),
+    device_name VARCHAR(255),
+    ip_address VARCHAR(45),
+
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_mfa_challenge_user ON mfa_challenge(user_id);
+
+SELECT enable_parent_isolation('mfa_challenge', 'user_id', 'users');
+
+-- ============================================================================
+-- Organization Security Policy Table
+-- ============================================================================
+
+CREATE TABLE organization_security_policy (
+    organization_id UUID PRIMARY KEY,
+
+    -- User types that may not sign in without a second factor
+    mfa_required_user_types user_type[] NOT NULL DEFAULT '{}',
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_by UUID
+);
+
+SELECT enable_tenant_isolation('organization_security_policy');
@@ -0,0 +1,47 @@
+-- Password and lockout policy
+--
+-- The organization's password rules live beside its MFA rules. The defaults
+-- match the behaviour before the policy existed: eight characters, no
+-- expiry or history, and a 30 minute lock after five failed logins.
+
+ALTER TABLE organization_security_policy
+    ADD COLUMN min_password_length INTEGER NOT NULL DEFAULT 8
+        CHECK (min_password_length BETWEEN 8 AND 128),
+    ADD COLUMN require_uppercase BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN require_lowercase BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN require_digit BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN require_symbol BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Days a password stays valid; NULL never expires
+    ADD COLUMN password_expiry_days INTEGER
+        CHECK (password_expiry_days > 0),
+
+    -- Previous passwords, the current one included, that may not be reused
+    ADD COLUMN password_history_depth INTEGER NOT NULL DEFAULT 0
+        CHECK (password_history_depth BETWEEN 0 AND 24),
+
+    ADD COLUMN lockout_threshold INTEGER NOT NULL DEFAULT 5
+        CHECK (lockout_threshold > 0),
+    ADD COLUMN lockout_minutes INTEGER NOT NULL DEFAULT 30
+        CHECK (lockout_minutes > 0),
+
+    -- Wait after the first failed login, doubled with each further failure;
+    -- 0 turns the delays off
+    ADD COLUMN progressive_delay_seconds INTEGER NOT NULL DEFAULT 0
+        CHECK (progressive_delay_seconds >= 0);
+
+-- ============================================================================
+-- Password History Table
+-- ============================================================================
+
+-- Hashes of passwords a user has replaced
+CREATE TABLE password_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_password_history_user ON password_history(user_id, created_at DESC);
+
+SELECT enable_parent_isolation('password_history', 'user_id', 'users');
@@ -0,0 +1,94 @@
+-- OpenID Connect single sign-on
+--
+-- Organizations register the identity providers (Keycloak, Azure AD, ...)
+-- their staff sign in with. Users are provisioned on their first sign-in
+-- and their roles follow the provider's role claim through the mappings.
+
+-- ============================================================================
+-- Identity Provider Table
+-- ============================================================================
+
+CREATE TABLE identity_provider (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    organization_id UUID NOT NULL,
+    provider_code VARCHAR(50) NOT NULL,
+    display_name VARCHAR(100) NOT NULL,
+
+    -- Relying party registration
+    issuer_url VARCHAR(500) NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    client_secret VARCHAR(500),
+    redirect_uri VARCHAR(500) NOT NULL,
+    scopes VARCHAR(255) NOT NULL DEFAULT 'openid email profile',
+
+    -- ID token claim holding the user's roles or groups; dots reach into
+    -- nested objects, as in Keycloak's realm_access.roles
+    role_claim VARCHAR(100) NOT NULL DEFAULT 'roles',
+
+    -- Just-in-time provisioning of users signing in for the first time
+    jit_provisioning BOOLEAN NOT NULL DEFAULT TRUE,
+    default_user_type user_type NOT NULL DEFAULT 'TECHNICIAN',
+
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by UUID,
+    updated_by UUID,
+
+    CONSTRAINT unique_identity_provider_code UNIQUE (organization_id, provider_code)
+);
+
+SELECT enable_tenant_isolation('identity_provider');
+
+-- Role granted to users whose role claim holds claim_value
+CREATE TABLE identity_provider_role_mapping (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    identity_provider_id UUID NOT NULL REFERENCES identity_provider(id) ON DELETE CASCADE,
+    claim_value VARCHAR(255) NOT NULL,
+    role_id UUID NOT NULL REFERENCES role(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_identity_provider_role_mapping UNIQUE (identity_provider_id, claim_value, role_id)
+);
+
+SELECT enable_parent_isolation('identity_provider_role_mapping', 'identity_provider_id', 'identity_provider');
+
+-- ============================================================================
+-- OIDC Login Request Table
+-- ============================================================================
+
+-- Authorization request waiting for the provider's redirect
+CREATE TABLE oidc_login_request (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    identity_provider_id UUID NOT NULL REFERENCES identity_provider(id) ON DELETE CASCADE,
+    state VARCHAR(255) UNIQUE NOT NULL,
+    nonce VARCHAR(255) NOT NULL,
+    code_verifier VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+SELECT enable_parent_isolation('oidc_login_request', 'identity_provider_id', 'identity_provider');
+
+-- ============================================================================
+-- User Identity Table
+-- ============================================================================
+
+-- Provider account (the ID token's subject) a user signs in with
+CREATE TABLE user_identity (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    identity_provider_id UUID NOT NULL REFERENCES identity_provider(id) ON DELETE CASCADE,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(255),
+    last_login_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_user_identity_subject UNIQUE (identity_provider_id, subject)
+);
+
+CREATE INDEX idx_user_identity_user ON user_identity(user_id);
+
+SELECT enable_parent_isolation('user_identity', 'user_id', 'users');
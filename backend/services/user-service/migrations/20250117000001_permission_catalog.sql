@@ -0,0 +1,240 @@
+-- Permission catalog and role bundles
+--
+-- Every GraphQL field of every service requires one of these permissions
+-- (common::permissions lists the same codes). Roles bundle them; a user's
+-- access token carries the permissions of all of their roles.
+
+INSERT INTO permission (permission_code, permission_name, module, action) VALUES
+    -- Patients
+    ('PATIENT_CREATE', 'Create Patient', 'PATIENT', 'CREATE'),
+    ('PATIENT_READ', 'View Patient', 'PATIENT', 'READ'),
+    ('PATIENT_UPDATE', 'Update Patient', 'PATIENT', 'UPDATE'),
+    ('PATIENT_DELETE', 'Delete Patient', 'PATIENT', 'DELETE'),
+
+    -- Orders and the test catalog
+    ('ORDER_CREATE', 'Create Order', 'ORDER', 'CREATE'),
+    ('ORDER_READ', 'View Order', 'ORDER', 'READ'),
+    ('ORDER_UPDATE', 'Update Order', 'ORDER', 'UPDATE'),
+    ('ORDER_DELETE', 'Delete Order', 'ORDER', 'DELETE'),
+    ('ORDER_CONFIRM', 'Confirm Order', 'ORDER', 'CONFIRM'),
+    ('ORDER_CANCEL', 'Cancel Order', 'ORDER', 'CANCEL'),
+
+    -- Samples
+    ('SAMPLE_CREATE', 'Create Sample', 'SAMPLE', 'CREATE'),
+    ('SAMPLE_READ', 'View Sample', 'SAMPLE', 'READ'),
+    ('SAMPLE_UPDATE', 'Track Sample', 'SAMPLE', 'UPDATE'),
+    ('SAMPLE_RECEIVE', 'Receive Sample', 'SAMPLE', 'RECEIVE'),
+    ('SAMPLE_REJECT', 'Reject Sample', 'SAMPLE', 'REJECT'),
+
+    -- Results
+    ('RESULT_CREATE', 'Create Result', 'RESULT', 'CREATE'),
+    ('RESULT_READ', 'View Result', 'RESULT', 'READ'),
+    ('RESULT_UPDATE', 'Update Result', 'RESULT', 'UPDATE'),
+    ('RESULT_VERIFY', 'Verify Result', 'RESULT', 'VERIFY'),
+    ('RESULT_APPROVE', 'Approve Result', 'RESULT', 'APPROVE'),
+    ('RESULT_CORRECT', 'Correct Result', 'RESULT', 'CORRECT'),
+    ('RESULT_RELEASE', 'Release QC-Held Results', 'RESULT', 'RELEASE'),
+    ('RESULT_CRITICAL_NOTIFY', 'Notify Critical Results', 'RESULT', 'NOTIFY'),
+    ('RESULT_RULE_MANAGE', 'Manage Auto-Verification Rules', 'RESULT', 'MANAGE'),
+
+    -- Reports
+    ('REPORT_READ', 'View Report', 'REPORT', 'READ'),
+    ('REPORT_GENERATE', 'Generate Report', 'REPORT', 'GENERATE'),
+    ('REPORT_DOWNLOAD', 'Download Report', 'REPORT', 'DOWNLOAD'),
+    ('REPORT_SIGN', 'Sign Report', 'REPORT', 'SIGN'),
+    ('REPORT_DELIVER', 'Deliver Report', 'REPORT', 'DELIVER'),
+    ('REPORT_TEMPLATE_MANAGE', 'Manage Report Templates', 'REPORT', 'MANAGE'),
+    ('REPORT_AUDIT', 'View Report Access Log', 'REPORT', 'AUDIT'),
+
+    -- Billing
+    ('BILLING_CREATE', 'Create Bill', 'BILLING', 'CREATE'),
+    ('BILLING_READ', 'View Bill', 'BILLING', 'READ'),
+    ('BILLING_UPDATE', 'Update Bill', 'BILLING', 'UPDATE'),
+    ('BILLING_PAYMENT', 'Record Payment', 'BILLING', 'PAYMENT'),
+    ('BILLING_REFUND', 'Issue Refund', 'BILLING', 'REFUND'),
+    ('BILLING_CONFIGURE', 'Manage Insurers and Discounts', 'BILLING', 'MANAGE'),
+
+    -- Quality control
+    ('QC_READ', 'View QC', 'QC', 'READ'),
+    ('QC_ENTRY', 'Record QC Result', 'QC', 'CREATE'),
+    ('QC_REVIEW', 'Review QC', 'QC', 'REVIEW'),
+    ('QC_MANAGE', 'Manage QC Materials and Rules', 'QC', 'MANAGE'),
+
+    -- Equipment
+    ('EQUIPMENT_READ', 'View Equipment', 'EQUIPMENT', 'READ'),
+    ('EQUIPMENT_MAINTAIN', 'Maintain Equipment', 'EQUIPMENT', 'MAINTAIN'),
+    ('EQUIPMENT_MANAGE', 'Manage Equipment', 'EQUIPMENT', 'MANAGE'),
+
+    -- Inventory
+    ('INVENTORY_READ', 'View Inventory', 'INVENTORY', 'READ'),
+    ('INVENTORY_UPDATE', 'Update Stock', 'INVENTORY', 'UPDATE'),
+    ('INVENTORY_MANAGE', 'Manage Inventory', 'INVENTORY', 'MANAGE'),
+    ('INVENTORY_APPROVE', 'Approve Purchase Order', 'INVENTORY', 'APPROVE'),
+
+    -- Notifications
+    ('NOTIFICATION_READ', 'View Notifications', 'NOTIFICATION', 'READ'),
+    ('NOTIFICATION_SEND', 'Send Notifications', 'NOTIFICATION', 'SEND'),
+    ('NOTIFICATION_MANAGE', 'Manage Notification Setup', 'NOTIFICATION', 'MANAGE'),
+
+    -- Compliance
+    ('COMPLIANCE_READ', 'View Compliance', 'COMPLIANCE', 'READ'),
+    ('COMPLIANCE_MANAGE', 'Manage Compliance', 'COMPLIANCE', 'MANAGE'),
+    ('DOCUMENT_APPROVE', 'Approve Controlled Document', 'COMPLIANCE', 'APPROVE'),
+    ('AUDIT_READ', 'View Audit Log', 'COMPLIANCE', 'AUDIT'),
+
+    -- Instrument and HIS interfaces
+    ('HL7_MANAGE', 'Manage HL7 Messages', 'INTERFACE', 'MANAGE'),
+
+    -- Analytics
+    ('ANALYTICS_READ', 'View Analytics', 'ANALYTICS', 'READ'),
+
+    -- Organization
+    ('ORGANIZATION_READ', 'View Organization', 'ORGANIZATION', 'READ'),
+    ('ORGANIZATION_MANAGE', 'Manage Organization', 'ORGANIZATION', 'MANAGE'),
+    ('ORGANIZATION_ADMIN', 'Administer All Organizations', 'ORGANIZATION', 'ADMIN'),
+
+    -- Administration
+    ('USER_READ', 'View Users', 'ADMIN', 'READ'),
+    ('USER_MANAGE', 'Manage Users', 'ADMIN', 'MANAGE'),
+    ('ROLE_MANAGE', 'Manage Roles', 'ADMIN', 'MANAGE'),
+    ('SETTINGS_MANAGE', 'Manage Settings', 'ADMIN', 'MANAGE')
+ON CONFLICT (permission_code) DO NOTHING;
+
+-- ============================================================================
+-- Roles
+-- ============================================================================
+
+INSERT INTO role (role_code, role_name, description, is_system_role) VALUES
+    ('ORG_ADMIN', 'Organization Administrator', 'Full access within the organization', TRUE),
+    ('QUALITY_MANAGER', 'Quality Manager', 'Quality control, documents and compliance', TRUE),
+    ('BILLING_STAFF', 'Billing Staff', 'Invoices, payments and insurance claims', TRUE)
+ON CONFLICT (role_code) DO NOTHING;
+
+-- ============================================================================
+-- Tenant Isolation
+-- ============================================================================
+
+-- Assignments follow the user: a system role is visible to every
+-- organization, so isolating through the role would share them all
+SELECT enable_parent_isolation('user_role', 'user_id', 'users');
+
+-- Organizations manage the bundles of their own roles and may only read
+-- those of the system roles
+DROP POLICY IF EXISTS tenant_isolation ON role_permission;
+CREATE POLICY tenant_isolation ON role_permission
+    USING (tenant_bypass() OR EXISTS (
+        SELECT 1 FROM role p WHERE p.id = role_permission.role_id AND p.organization_id = current_tenant()
+    ))
+    WITH CHECK (tenant_bypass() OR EXISTS (
+        SELECT 1 FROM role p WHERE p.id = role_permission.role_id AND p.organization_id = current_tenant()
+    ));
+
+DROP POLICY IF EXISTS tenant_shared_rows ON role_permission;
+CREATE POLICY tenant_shared_rows ON role_permission FOR SELECT
+    USING (EXISTS (SELECT 1 FROM role p WHERE p.id = role_permission.role_id AND p.organization_id IS NULL));
+
+-- ============================================================================
+-- Role Permissions
+-- ============================================================================
+
+-- Platform operators
+INSERT INTO role_permission (role_id, permission_id)
+SELECT r.id, p.id
+FROM role r CROSS JOIN permission p
+WHERE r.role_code = 'SUPER_ADMIN'
+ON CONFLICT (role_id, permission_id) DO NOTHING;
+
+-- Everything inside their own organization
+INSERT INTO role_permission (role_id, permission_id)
+SELECT r.id, p.id
+FROM role r CROSS JOIN permission p
+WHERE r.role_code = 'ORG_ADMIN'
+  AND p.permission_code <> 'ORGANIZATION_ADMIN'
+ON CONFLICT (role_id, permission_id) DO NOTHING;
+
+INSERT INTO role_permission (role_id, permission_id)
+SELECT r.id, p.id
+FROM (VALUES
+    ('LAB_MANAGER', ARRAY[
+        'PATIENT_CREATE', 'PATIENT_READ', 'PATIENT_UPDATE', 'PATIENT_DELETE',
+        'ORDER_CREATE', 'ORDER_READ', 'ORDER_UPDATE', 'ORDER_DELETE', 'ORDER_CONFIRM', 'ORDER_CANCEL',
+        'SAMPLE_CREATE', 'SAMPLE_READ', 'SAMPLE_UPDATE', 'SAMPLE_RECEIVE', 'SAMPLE_REJECT',
+        'RESULT_CREATE', 'RESULT_READ', 'RESULT_UPDATE', 'RESULT_VERIFY', 'RESULT_APPROVE',
+        'RESULT_CORRECT', 'RESULT_RELEASE', 'RESULT_CRITICAL_NOTIFY', 'RESULT_RULE_MANAGE',
+        'REPORT_READ', 'REPORT_GENERATE', 'REPORT_DOWNLOAD', 'REPORT_DELIVER',
+        'REPORT_TEMPLATE_MANAGE', 'REPORT_AUDIT',
+        'BILLING_CREATE', 'BILLING_READ', 'BILLING_UPDATE', 'BILLING_PAYMENT', 'BILLING_REFUND',
+        'QC_READ', 'QC_ENTRY', 'QC_REVIEW', 'QC_MANAGE',
+        'EQUIPMENT_READ', 'EQUIPMENT_MAINTAIN', 'EQUIPMENT_MANAGE',
+        'INVENTORY_READ', 'INVENTORY_UPDATE', 'INVENTORY_MANAGE', 'INVENTORY_APPROVE',
+        'NOTIFICATION_READ', 'NOTIFICATION_SEND', 'NOTIFICATION_MANAGE',
+        'COMPLIANCE_READ', 'AUDIT_READ', 'HL7_MANAGE', 'ANALYTICS_READ',
+        'ORGANIZATION_READ', 'USER_READ', 'USER_MANAGE'
+    ]),
+    ('PATHOLOGIST', ARRAY[
+        'PATIENT_READ', 'ORDER_READ', 'SAMPLE_READ', 'SAMPLE_REJECT',
+        'RESULT_READ', 'RESULT_VERIFY', 'RESULT_APPROVE', 'RESULT_CORRECT',
+        'RESULT_RELEASE', 'RESULT_CRITICAL_NOTIFY',
+        'REPORT_READ', 'REPORT_GENERATE', 'REPORT_DOWNLOAD', 'REPORT_SIGN',
+        'QC_READ', 'QC_REVIEW', 'NOTIFICATION_READ', 'NOTIFICATION_SEND',
+        'COMPLIANCE_READ', 'ANALYTICS_READ', 'ORGANIZATION_READ'
+    ]),
+    ('TECHNICIAN', ARRAY[
+        'PATIENT_READ', 'ORDER_READ',
+        'SAMPLE_READ', 'SAMPLE_UPDATE', 'SAMPLE_RECEIVE', 'SAMPLE_REJECT',
+        'RESULT_CREATE', 'RESULT_READ', 'RESULT_UPDATE', 'RESULT_VERIFY', 'RESULT_CRITICAL_NOTIFY',
+        'REPORT_READ', 'QC_READ', 'QC_ENTRY',
+        'EQUIPMENT_READ', 'EQUIPMENT_MAINTAIN', 'INVENTORY_READ', 'INVENTORY_UPDATE',
+        'NOTIFICATION_READ', 'COMPLIANCE_READ', 'ORGANIZATION_READ'
+    ]),
+    ('RECEPTIONIST', ARRAY[
+        'PATIENT_CREATE', 'PATIENT_READ', 'PATIENT_UPDATE',
+        'ORDER_CREATE', 'ORDER_READ', 'ORDER_UPDATE', 'ORDER_CONFIRM', 'ORDER_CANCEL',
+        'SAMPLE_CREATE', 'SAMPLE_READ',
+        'REPORT_READ', 'REPORT_DOWNLOAD', 'REPORT_DELIVER',
+        'BILLING_CREATE', 'BILLING_READ', 'BILLING_PAYMENT',
+        'NOTIFICATION_READ', 'NOTIFICATION_SEND', 'ORGANIZATION_READ'
+    ]),
+    ('QUALITY_MANAGER', ARRAY[
+        'ORDER_READ', 'SAMPLE_READ', 'RESULT_READ', 'RESULT_RELEASE',
+        'REPORT_READ', 'REPORT_AUDIT',
+        'QC_READ', 'QC_ENTRY', 'QC_REVIEW', 'QC_MANAGE',
+        'EQUIPMENT_READ', 'EQUIPMENT_MAINTAIN', 'INVENTORY_READ',
+        'NOTIFICATION_READ', 'COMPLIANCE_READ', 'COMPLIANCE_MANAGE', 'DOCUMENT_APPROVE', 'AUDIT_READ',
+        'ANALYTICS_READ', 'ORGANIZATION_READ'
+    ]),
+    ('BILLING_STAFF', ARRAY[
+        'PATIENT_READ', 'ORDER_READ', 'REPORT_READ',
+        'BILLING_CREATE', 'BILLING_READ', 'BILLING_UPDATE', 'BILLING_PAYMENT',
+        'BILLING_REFUND', 'BILLING_CONFIGURE',
+        'ANALYTICS_READ', 'ORGANIZATION_READ'
+    ])
+) AS bundle(role_code, permission_codes)
+JOIN role r ON r.role_code = bundle.role_code
+JOIN permission p ON p.permission_code = ANY(bundle.permission_codes)
+ON CONFLICT (role_id, permission_id) DO NOTHING;
+
+-- ============================================================================
+-- Existing Users
+-- ============================================================================
+
+-- Fields were open before the catalog was enforced, so users without a
+-- role get the one matching their user type. Platform administrators and
+-- patients are left for an administrator to decide.
+INSERT INTO user_role (user_id, role_id, assigned_by)
+SELECT u.id, r.id, u.id
+FROM users u
+JOIN role r ON r.role_code = CASE u.user_type
+    WHEN 'ORG_ADMIN' THEN 'ORG_ADMIN'
+    WHEN 'MANAGER' THEN 'LAB_MANAGER'
+    WHEN 'DOCTOR' THEN 'PATHOLOGIST'
+    WHEN 'TECHNICIAN' THEN 'TECHNICIAN'
+    WHEN 'LAB_ASSISTANT' THEN 'TECHNICIAN'
+    WHEN 'NURSE' THEN 'RECEPTIONIST'
+    WHEN 'RECEPTIONIST' THEN 'RECEPTIONIST'
+    WHEN 'BILLING_STAFF' THEN 'BILLING_STAFF'
+    WHEN 'QUALITY_MANAGER' THEN 'QUALITY_MANAGER'
+END
+WHERE u.is_deleted = FALSE
+  AND NOT EXISTS (SELECT 1 FROM user_role ur WHERE ur.user_id = u.id)
+ON CONFLICT (user_id, role_id) DO NOTHING;
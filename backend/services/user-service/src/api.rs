@@ -1,9 +1,18 @@
-use async_graphql::{Context, Object, Result, SimpleObject, InputObject, Enum, ID};
+use async_graphql::{Context, Object, Result, SimpleObject, InputObject, Enum, Union, ID};
+use common::auth::{AuthContext, AuthGuard, PermissionGuard};
+use common::permissions;
+use common::tenant::Tenant;
 use uuid::Uuid;
 
 use crate::domain::*;
 use crate::service::UserService;
 
+/// Client address of the request, recorded with sign-ins
+#[derive(Debug, Clone, Default)]
+pub struct RequestOrigin {
+    pub ip_address: Option<String>,
+}
+
 // ============================================================================
 // GraphQL Types
 // ============================================================================
@@ -93,6 +102,29 @@ impl From<Permission> for PermissionGQL {
     }
 }
 
+/// A role and the codes of the permissions it bundles
+#[derive(SimpleObject)]
+pub struct RolePermissionsGQL {
+    pub role: RoleGQL,
+    pub permission_codes: Vec<String>,
+}
+
+impl From<RolePermissions> for RolePermissionsGQL {
+    fn from(row: RolePermissions) -> Self {
+        Self {
+            role: row.role.into(),
+            permission_codes: row.permission_codes,
+        }
+    }
+}
+
+/// The permission catalog against every role: what each role can do
+#[derive(SimpleObject)]
+pub struct PermissionMatrixGQL {
+    pub permissions: Vec<PermissionGQL>,
+    pub roles: Vec<RolePermissionsGQL>,
+}
+
 #[derive(SimpleObject)]
 pub struct LoginResponseGQL {
     pub user: UserGQL,
@@ -100,6 +132,8 @@ pub struct LoginResponseGQL {
     pub refresh_token: String,
     pub expires_in: i32,
     pub permissions: Vec<String>,
+    /// Backup codes of an enrollment this login completed, shown only once
+    pub backup_codes: Option<Vec<String>>,
 }
 
 impl From<LoginResponse> for LoginResponseGQL {
@@ -110,6 +144,184 @@ impl From<LoginResponse> for LoginResponseGQL {
             refresh_token: response.refresh_token,
             expires_in: response.expires_in as i32,
             permissions: response.permissions,
+            backup_codes: response.backup_codes,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct MfaChallengeGQL {
+    /// Pass to verifyMfa with a code to finish the login
+    pub challenge_token: String,
+    pub expires_in: i32,
+    /// Enroll an authenticator with startMfaEnrollment first
+    pub enrollment_required: bool,
+}
+
+impl From<MfaChallengeResponse> for MfaChallengeGQL {
+    fn from(challenge: MfaChallengeResponse) -> Self {
+        Self {
+            challenge_token: challenge.challenge_token,
+            expires_in: challenge.expires_in as i32,
+            enrollment_required: challenge.enrollment_required,
+        }
+    }
+}
+
+/// A session, or the challenge a second factor must answer first
+#[derive(Union)]
+pub enum LoginResultGQL {
+    Session(Box<LoginResponseGQL>),
+    MfaChallenge(MfaChallengeGQL),
+}
+
+impl From<LoginOutcome> for LoginResultGQL {
+    fn from(outcome: LoginOutcome) -> Self {
+        match outcome {
+            LoginOutcome::Authenticated(response) => LoginResultGQL::Session(Box::new((*response).into())),
+            LoginOutcome::MfaRequired(challenge) => LoginResultGQL::MfaChallenge(challenge.into()),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TotpEnrollmentGQL {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// otpauth:// URI to render as a QR code
+    pub otpauth_uri: String,
+}
+
+impl From<TotpEnrollment> for TotpEnrollmentGQL {
+    fn from(enrollment: TotpEnrollment) -> Self {
+        Self {
+            secret: enrollment.secret,
+            otpauth_uri: enrollment.otpauth_uri,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SecurityPolicyGQL {
+    pub organization_id: ID,
+    pub mfa_required_user_types: Vec<UserTypeEnum>,
+    pub min_password_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub password_expiry_days: Option<i32>,
+    pub password_history_depth: i32,
+    pub lockout_threshold: i32,
+    pub lockout_minutes: i32,
+    pub progressive_delay_seconds: i32,
+    pub updated_at: String,
+}
+
+impl From<SecurityPolicy> for SecurityPolicyGQL {
+    fn from(policy: SecurityPolicy) -> Self {
+        Self {
+            organization_id: policy.organization_id.to_string().into(),
+            mfa_required_user_types: policy.mfa_required_user_types.into_iter().map(|t| t.into()).collect(),
+            min_password_length: policy.min_password_length,
+            require_uppercase: policy.require_uppercase,
+            require_lowercase: policy.require_lowercase,
+            require_digit: policy.require_digit,
+            require_symbol: policy.require_symbol,
+            password_expiry_days: policy.password_expiry_days,
+            password_history_depth: policy.password_history_depth,
+            lockout_threshold: policy.lockout_threshold,
+            lockout_minutes: policy.lockout_minutes,
+            progressive_delay_seconds: policy.progressive_delay_seconds,
+            updated_at: policy.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct IdentityProviderGQL {
+    pub id: ID,
+    pub provider_code: String,
+    pub display_name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub has_client_secret: bool,
+    pub redirect_uri: String,
+    pub scopes: String,
+    pub role_claim: String,
+    pub jit_provisioning: bool,
+    pub default_user_type: UserTypeEnum,
+    pub is_active: bool,
+    pub updated_at: String,
+}
+
+impl From<IdentityProvider> for IdentityProviderGQL {
+    fn from(provider: IdentityProvider) -> Self {
+        Self {
+            id: provider.id.to_string().into(),
+            provider_code: provider.provider_code,
+            display_name: provider.display_name,
+            issuer_url: provider.issuer_url,
+            client_id: provider.client_id,
+            has_client_secret: provider.client_secret.is_some(),
+            redirect_uri: provider.redirect_uri,
+            scopes: provider.scopes,
+            role_claim: provider.role_claim,
+            jit_provisioning: provider.jit_provisioning,
+            default_user_type: provider.default_user_type.into(),
+            is_active: provider.is_active,
+            updated_at: provider.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Identity provider offered on the sign-in page
+#[derive(SimpleObject)]
+pub struct SsoProviderGQL {
+    pub provider_code: String,
+    pub display_name: String,
+}
+
+impl From<IdentityProvider> for SsoProviderGQL {
+    fn from(provider: IdentityProvider) -> Self {
+        Self {
+            provider_code: provider.provider_code,
+            display_name: provider.display_name,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct IdentityProviderRoleMappingGQL {
+    pub id: ID,
+    pub claim_value: String,
+    pub role_id: ID,
+}
+
+impl From<IdentityProviderRoleMapping> for IdentityProviderRoleMappingGQL {
+    fn from(mapping: IdentityProviderRoleMapping) -> Self {
+        Self {
+            id: mapping.id.to_string().into(),
+            claim_value: mapping.claim_value,
+            role_id: mapping.role_id.to_string().into(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SsoLoginStartGQL {
+    /// Send the browser here; the provider redirects back with code and state
+    pub authorization_url: String,
+    pub state: String,
+    pub expires_in: i32,
+}
+
+impl From<SsoLoginStart> for SsoLoginStartGQL {
+    fn from(start: SsoLoginStart) -> Self {
+        Self {
+            authorization_url: start.authorization_url,
+            state: start.state,
+            expires_in: start.expires_in as i32,
         }
     }
 }
@@ -293,6 +505,82 @@ impl TryFrom<ChangePasswordInputGQL> for ChangePasswordInput {
     }
 }
 
+#[derive(InputObject)]
+pub struct PasswordPolicyInputGQL {
+    pub min_password_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Days a password stays valid; null never expires
+    pub password_expiry_days: Option<i32>,
+    /// Previous passwords, the current one included, that may not be reused
+    pub password_history_depth: i32,
+    pub lockout_threshold: i32,
+    pub lockout_minutes: i32,
+    /// Wait after the first failed login, doubled with each further failure
+    pub progressive_delay_seconds: i32,
+}
+
+impl From<PasswordPolicyInputGQL> for PasswordPolicyInput {
+    fn from(input: PasswordPolicyInputGQL) -> Self {
+        Self {
+            min_password_length: input.min_password_length,
+            require_uppercase: input.require_uppercase,
+            require_lowercase: input.require_lowercase,
+            require_digit: input.require_digit,
+            require_symbol: input.require_symbol,
+            password_expiry_days: input.password_expiry_days,
+            password_history_depth: input.password_history_depth,
+            lockout_threshold: input.lockout_threshold,
+            lockout_minutes: input.lockout_minutes,
+            progressive_delay_seconds: input.progressive_delay_seconds,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct ConfigureIdentityProviderInputGQL {
+    /// Code the sign-in page uses, unique in the organization
+    pub provider_code: String,
+    pub display_name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Omit for public clients, or to keep the stored secret
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: Option<String>,
+    /// ID token claim with the user's roles; dots reach into nested objects
+    pub role_claim: Option<String>,
+    pub jit_provisioning: bool,
+    pub default_user_type: UserTypeEnum,
+    pub is_active: bool,
+}
+
+impl From<ConfigureIdentityProviderInputGQL> for ConfigureIdentityProviderInput {
+    fn from(input: ConfigureIdentityProviderInputGQL) -> Self {
+        Self {
+            provider_code: input.provider_code,
+            display_name: input.display_name,
+            issuer_url: input.issuer_url,
+            client_id: input.client_id,
+            client_secret: input.client_secret,
+            redirect_uri: input.redirect_uri,
+            scopes: input.scopes,
+            role_claim: input.role_claim,
+            jit_provisioning: input.jit_provisioning,
+            default_user_type: input.default_user_type.into(),
+            is_active: input.is_active,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct RoleMappingInputGQL {
+    pub claim_value: String,
+    pub role_id: ID,
+}
+
 #[derive(InputObject)]
 pub struct CreateRoleInputGQL {
     pub role_code: String,
@@ -338,8 +626,9 @@ pub struct QueryRoot;
 impl QueryRoot {
     /// Get current user (from auth context)
     async fn me(&self, ctx: &Context<'_>) -> Result<Option<UserGQL>> {
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let Ok(user_id) = ctx.user_id() else {
+            return Ok(None);
+        };
 
         let service = ctx.data::<UserService>()?;
         match service.get_user(user_id).await {
@@ -349,6 +638,7 @@ impl QueryRoot {
     }
 
     /// Get user by ID
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn user(&self, ctx: &Context<'_>, id: ID) -> Result<Option<UserGQL>> {
         let service = ctx.data::<UserService>()?;
         let user_id = Uuid::parse_str(&id)?;
@@ -360,6 +650,7 @@ impl QueryRoot {
     }
 
     /// Get user by email
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn user_by_email(&self, ctx: &Context<'_>, email: String) -> Result<Option<UserGQL>> {
         let service = ctx.data::<UserService>()?;
 
@@ -370,6 +661,7 @@ impl QueryRoot {
     }
 
     /// Search users
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn search_users(
         &self,
         ctx: &Context<'_>,
@@ -381,7 +673,7 @@ impl QueryRoot {
         let service = ctx.data::<UserService>()?;
 
         let filter = UserFilter {
-            organization_id: None, // TODO: Get from auth context
+            organization_id: ctx.organization_id().ok(),
             user_type: user_type.map(|t| t.into()),
             user_status: user_status.map(|s| s.into()),
             department: None,
@@ -393,6 +685,7 @@ impl QueryRoot {
     }
 
     /// Get all roles
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn roles(&self, ctx: &Context<'_>) -> Result<Vec<RoleGQL>> {
         let service = ctx.data::<UserService>()?;
         let roles = service.get_all_roles(None).await?;
@@ -400,13 +693,26 @@ impl QueryRoot {
     }
 
     /// Get all permissions
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn permissions(&self, ctx: &Context<'_>) -> Result<Vec<PermissionGQL>> {
         let service = ctx.data::<UserService>()?;
         let permissions = service.get_all_permissions().await?;
         Ok(permissions.into_iter().map(|p| p.into()).collect())
     }
 
+    /// Permission catalog and the permissions of every role
+    #[graphql(guard = "PermissionGuard::new(permissions::ROLE_MANAGE)")]
+    async fn permission_matrix(&self, ctx: &Context<'_>) -> Result<PermissionMatrixGQL> {
+        let service = ctx.data::<UserService>()?;
+        let (permissions, roles) = service.permission_matrix(ctx.organization_id().ok()).await?;
+        Ok(PermissionMatrixGQL {
+            permissions: permissions.into_iter().map(|p| p.into()).collect(),
+            roles: roles.into_iter().map(|r| r.into()).collect(),
+        })
+    }
+
     /// Get user roles
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn user_roles(&self, ctx: &Context<'_>, user_id: ID) -> Result<Vec<RoleGQL>> {
         let service = ctx.data::<UserService>()?;
         let id = Uuid::parse_str(&user_id)?;
@@ -414,7 +720,47 @@ impl QueryRoot {
         Ok(roles.into_iter().map(|r| r.into()).collect())
     }
 
+    /// Security policy of the caller's organization
+    #[graphql(guard = "AuthGuard")]
+    async fn security_policy(&self, ctx: &Context<'_>) -> Result<SecurityPolicyGQL> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let policy = service.get_security_policy(Some(organization_id)).await?;
+        Ok(policy.into())
+    }
+
+    /// Identity providers of the caller's organization
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn identity_providers(&self, ctx: &Context<'_>) -> Result<Vec<IdentityProviderGQL>> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let providers = service.list_identity_providers(organization_id).await?;
+        Ok(providers.into_iter().map(|p| p.into()).collect())
+    }
+
+    /// Role mappings of an identity provider
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn identity_provider_role_mappings(
+        &self,
+        ctx: &Context<'_>,
+        provider_id: ID,
+    ) -> Result<Vec<IdentityProviderRoleMappingGQL>> {
+        let service = ctx.data::<UserService>()?;
+        let provider_id = Uuid::parse_str(&provider_id)?;
+        let mappings = service.get_identity_provider_role_mappings(provider_id).await?;
+        Ok(mappings.into_iter().map(|m| m.into()).collect())
+    }
+
+    /// Identity providers to offer on an organization's sign-in page
+    async fn sso_providers(&self, ctx: &Context<'_>, organization_id: ID) -> Result<Vec<SsoProviderGQL>> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = Uuid::parse_str(&organization_id)?;
+        let providers = Tenant::System.scope(service.list_sso_providers(organization_id)).await?;
+        Ok(providers.into_iter().map(|p| p.into()).collect())
+    }
+
     /// Get user permissions
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_READ)")]
     async fn user_permissions(&self, ctx: &Context<'_>, user_id: ID) -> Result<Vec<PermissionGQL>> {
         let service = ctx.data::<UserService>()?;
         let id = Uuid::parse_str(&user_id)?;
@@ -438,7 +784,8 @@ impl MutationRoot {
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
 
-        let user = service.register(domain_input).await?;
+        // No tenant before sign-in; the account decides the organization
+        let user = Tenant::System.scope(service.register(domain_input)).await?;
         Ok(user.into())
     }
 
@@ -457,39 +804,186 @@ impl MutationRoot {
             department: None,
         };
 
-        let (user, org_id) = service.register_org_admin(
+        // The organization does not exist yet, so there is no tenant to scope to
+        let (user, org_id) = Tenant::System.scope(service.register_org_admin(
             user_input,
             input.organization_name,
             input.organization_phone,
-        ).await?;
+        )).await?;
 
         tracing::info!("Organization admin created: user={}, org={}", user.id, org_id);
 
         Ok(user.into())
     }
 
-    /// Login
-    async fn login(&self, ctx: &Context<'_>, input: LoginInputGQL) -> Result<LoginResponseGQL> {
+    /// Login; returns a session, or an MFA challenge to answer with verifyMfa
+    async fn login(&self, ctx: &Context<'_>, input: LoginInputGQL) -> Result<LoginResultGQL> {
         let service = ctx.data::<UserService>()?;
 
-        // TODO: Get IP address from request context
-        let ip_address = None;
+        let ip_address = ctx.data_opt::<RequestOrigin>().and_then(|origin| origin.ip_address.clone());
 
-        let response = service.login(input.into(), ip_address).await?;
+        // No tenant before sign-in; the account decides the organization
+        let response = Tenant::System.scope(service.login(input.into(), ip_address)).await?;
+        Ok(response.into())
+    }
+
+    /// Finish a challenged login with an authenticator or backup code
+    async fn verify_mfa(&self, ctx: &Context<'_>, challenge_token: String, code: String) -> Result<LoginResponseGQL> {
+        let service = ctx.data::<UserService>()?;
+        let response = Tenant::System.scope(service.verify_mfa(&challenge_token, &code)).await?;
+        Ok(response.into())
+    }
+
+    /// Enroll an authenticator during a login the organization requires MFA for
+    async fn start_mfa_enrollment(&self, ctx: &Context<'_>, challenge_token: String) -> Result<TotpEnrollmentGQL> {
+        let service = ctx.data::<UserService>()?;
+        let enrollment = Tenant::System.scope(service.start_challenge_enrollment(&challenge_token)).await?;
+        Ok(enrollment.into())
+    }
+
+    /// Start enrolling an authenticator for the current user
+    #[graphql(guard = "AuthGuard")]
+    async fn enroll_totp(&self, ctx: &Context<'_>) -> Result<TotpEnrollmentGQL> {
+        let service = ctx.data::<UserService>()?;
+        let user_id = ctx.user_id()?;
+        let enrollment = service.start_totp_enrollment(user_id).await?;
+        Ok(enrollment.into())
+    }
+
+    /// Confirm the authenticator with a code from it; returns the backup codes
+    #[graphql(guard = "AuthGuard")]
+    async fn confirm_totp_enrollment(&self, ctx: &Context<'_>, code: String) -> Result<Vec<String>> {
+        let service = ctx.data::<UserService>()?;
+        let user_id = ctx.user_id()?;
+        Ok(service.confirm_totp_enrollment(user_id, &code).await?)
+    }
+
+    /// Turn off two-factor authentication for the current user
+    #[graphql(guard = "AuthGuard")]
+    async fn disable_two_factor(&self, ctx: &Context<'_>, code: String) -> Result<UserGQL> {
+        let service = ctx.data::<UserService>()?;
+        let user_id = ctx.user_id()?;
+        let user = service.disable_two_factor(user_id, &code).await?;
+        Ok(user.into())
+    }
+
+    /// Replace the current user's backup codes
+    #[graphql(guard = "AuthGuard")]
+    async fn regenerate_backup_codes(&self, ctx: &Context<'_>, code: String) -> Result<Vec<String>> {
+        let service = ctx.data::<UserService>()?;
+        let user_id = ctx.user_id()?;
+        Ok(service.regenerate_backup_codes(user_id, &code).await?)
+    }
+
+    /// Set the user types of the caller's organization that must use MFA
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn update_mfa_policy(
+        &self,
+        ctx: &Context<'_>,
+        user_types: Vec<UserTypeEnum>,
+    ) -> Result<SecurityPolicyGQL> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let updated_by = ctx.user_id()?;
+
+        let policy = service
+            .update_mfa_policy(organization_id, user_types.into_iter().map(|t| t.into()).collect(), updated_by)
+            .await?;
+        Ok(policy.into())
+    }
+
+    /// Set the password and lockout rules of the caller's organization
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn update_password_policy(
+        &self,
+        ctx: &Context<'_>,
+        input: PasswordPolicyInputGQL,
+    ) -> Result<SecurityPolicyGQL> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let updated_by = ctx.user_id()?;
+
+        let policy = service
+            .update_password_policy(organization_id, input.into(), updated_by)
+            .await?;
+        Ok(policy.into())
+    }
+
+    /// Register or update an identity provider of the caller's organization
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn configure_identity_provider(
+        &self,
+        ctx: &Context<'_>,
+        input: ConfigureIdentityProviderInputGQL,
+    ) -> Result<IdentityProviderGQL> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+
+        let provider = service
+            .configure_identity_provider(organization_id, input.into(), user_id)
+            .await?;
+        Ok(provider.into())
+    }
+
+    /// Replace the claim value to role mappings of an identity provider
+    #[graphql(guard = "PermissionGuard::new(permissions::SETTINGS_MANAGE)")]
+    async fn set_identity_provider_role_mappings(
+        &self,
+        ctx: &Context<'_>,
+        provider_id: ID,
+        mappings: Vec<RoleMappingInputGQL>,
+    ) -> Result<Vec<IdentityProviderRoleMappingGQL>> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+        let provider_id = Uuid::parse_str(&provider_id)?;
+
+        let mappings = mappings
+            .into_iter()
+            .map(|m| Ok((m.claim_value, Uuid::parse_str(&m.role_id)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let saved = service
+            .set_identity_provider_role_mappings(organization_id, provider_id, mappings, user_id)
+            .await?;
+        Ok(saved.into_iter().map(|m| m.into()).collect())
+    }
+
+    /// Start signing in with an organization's identity provider
+    async fn start_sso_login(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: ID,
+        provider_code: String,
+    ) -> Result<SsoLoginStartGQL> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = Uuid::parse_str(&organization_id)?;
+        let start = Tenant::System.scope(service.start_sso_login(organization_id, &provider_code)).await?;
+        Ok(start.into())
+    }
+
+    /// Finish signing in with the code and state the provider redirected back with
+    async fn complete_sso_login(&self, ctx: &Context<'_>, state: String, code: String) -> Result<LoginResultGQL> {
+        let service = ctx.data::<UserService>()?;
+
+        let ip_address = ctx.data_opt::<RequestOrigin>().and_then(|origin| origin.ip_address.clone());
+
+        let response = Tenant::System.scope(service.complete_sso_login(&state, &code, ip_address)).await?;
         Ok(response.into())
     }
 
     /// Logout
     async fn logout(&self, ctx: &Context<'_>, session_token: String) -> Result<bool> {
         let service = ctx.data::<UserService>()?;
-        service.logout(&session_token).await?;
+        Tenant::System.scope(service.logout(&session_token)).await?;
         Ok(true)
     }
 
     /// Refresh token
     async fn refresh_token(&self, ctx: &Context<'_>, refresh_token: String) -> Result<LoginResponseGQL> {
         let service = ctx.data::<UserService>()?;
-        let response = service.refresh_token(&refresh_token).await?;
+        Tenant::System.scope(service.refresh_token(&refresh_token)).await?;
 
         // Convert RefreshTokenResponse to LoginResponseGQL
         // For simplicity, we'll return a basic structure
@@ -498,6 +992,7 @@ impl MutationRoot {
     }
 
     /// Change password
+    #[graphql(guard = "AuthGuard")]
     async fn change_password(&self, ctx: &Context<'_>, input: ChangePasswordInputGQL) -> Result<bool> {
         let service = ctx.data::<UserService>()?;
 
@@ -511,11 +1006,25 @@ impl MutationRoot {
     /// Request password reset
     async fn request_password_reset(&self, ctx: &Context<'_>, email: String) -> Result<bool> {
         let service = ctx.data::<UserService>()?;
-        service.request_password_reset(ResetPasswordInput { email }).await?;
+        Tenant::System.scope(service.request_password_reset(ResetPasswordInput { email })).await?;
+        Ok(true)
+    }
+
+    /// Set a new password with the token from a password reset
+    async fn confirm_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        new_password: String,
+    ) -> Result<bool> {
+        let service = ctx.data::<UserService>()?;
+        let input = ConfirmPasswordResetInput { token, new_password };
+        Tenant::System.scope(service.confirm_password_reset(input)).await?;
         Ok(true)
     }
 
     /// Update user status
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_MANAGE)")]
     async fn update_user_status(
         &self,
         ctx: &Context<'_>,
@@ -530,11 +1039,10 @@ impl MutationRoot {
     }
 
     /// Create role
+    #[graphql(guard = "PermissionGuard::new(permissions::ROLE_MANAGE)")]
     async fn create_role(&self, ctx: &Context<'_>, input: CreateRoleInputGQL) -> Result<RoleGQL> {
         let service = ctx.data::<UserService>()?;
-
-        // TODO: Get user_id from auth context
-        let user_id = Uuid::nil();
+        let user_id = ctx.user_id()?;
 
         let domain_input = input.try_into()
             .map_err(|e: String| async_graphql::Error::new(e))?;
@@ -543,7 +1051,27 @@ impl MutationRoot {
         Ok(role.into())
     }
 
+    /// Replace the permissions of one of the organization's own roles
+    #[graphql(guard = "PermissionGuard::new(permissions::ROLE_MANAGE)")]
+    async fn set_role_permissions(
+        &self,
+        ctx: &Context<'_>,
+        role_id: ID,
+        permission_codes: Vec<String>,
+    ) -> Result<Vec<PermissionGQL>> {
+        let service = ctx.data::<UserService>()?;
+        let organization_id = ctx.organization_id()?;
+        let user_id = ctx.user_id()?;
+        let role_id = Uuid::parse_str(&role_id)?;
+
+        let permissions = service
+            .set_role_permissions(organization_id, role_id, permission_codes, user_id)
+            .await?;
+        Ok(permissions.into_iter().map(|p| p.into()).collect())
+    }
+
     /// Assign role to user
+    #[graphql(guard = "PermissionGuard::new(permissions::ROLE_MANAGE)")]
     async fn assign_role(
         &self,
         ctx: &Context<'_>,
@@ -552,8 +1080,7 @@ impl MutationRoot {
     ) -> Result<bool> {
         let service = ctx.data::<UserService>()?;
 
-        // TODO: Get assigned_by from auth context
-        let assigned_by = Uuid::nil();
+        let assigned_by = ctx.user_id()?;
 
         let input = AssignRoleInput {
             user_id: Uuid::parse_str(&user_id)?,
@@ -565,6 +1092,7 @@ impl MutationRoot {
     }
 
     /// Remove role from user
+    #[graphql(guard = "PermissionGuard::new(permissions::ROLE_MANAGE)")]
     async fn remove_role(
         &self,
         ctx: &Context<'_>,
@@ -581,6 +1109,7 @@ impl MutationRoot {
     }
 
     /// Verify email
+    #[graphql(guard = "PermissionGuard::new(permissions::USER_MANAGE)")]
     async fn verify_email(&self, ctx: &Context<'_>, user_id: ID) -> Result<UserGQL> {
         let service = ctx.data::<UserService>()?;
         let id = Uuid::parse_str(&user_id)?;
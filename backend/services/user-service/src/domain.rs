@@ -1,4 +1,4 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -97,7 +97,10 @@ pub struct User {
     pub two_factor_enabled: bool,
     #[serde(skip_serializing)]
     pub two_factor_secret: Option<String>,
+    #[serde(skip_serializing)]
     pub backup_codes: Option<serde_json::Value>,
+    #[serde(skip_serializing)]
+    pub two_factor_last_step: Option<i64>,
 
     // Email and Mobile Verification
     pub email_verified: bool,
@@ -169,6 +172,401 @@ impl User {
     pub fn can_login(&self) -> bool {
         self.is_active() && !self.is_locked() && self.email_verified
     }
+
+    /// Hashes of the backup codes not used yet
+    pub fn backup_code_hashes(&self) -> Vec<String> {
+        self.backup_codes
+            .as_ref()
+            .and_then(|codes| serde_json::from_value(codes.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl UserType {
+    /// System role a new account of this type starts with. Platform
+    /// administrators and patients get none.
+    pub fn default_role_code(&self) -> Option<&'static str> {
+        match self {
+            UserType::OrgAdmin => Some("ORG_ADMIN"),
+            UserType::Manager => Some("LAB_MANAGER"),
+            UserType::Doctor => Some("PATHOLOGIST"),
+            UserType::Technician | UserType::LabAssistant => Some("TECHNICIAN"),
+            UserType::Nurse | UserType::Receptionist => Some("RECEPTIONIST"),
+            UserType::BillingStaff => Some("BILLING_STAFF"),
+            UserType::QualityManager => Some("QUALITY_MANAGER"),
+            UserType::SuperAdmin | UserType::Patient => None,
+        }
+    }
+}
+
+impl sqlx::postgres::PgHasArrayType for UserType {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_user_type")
+    }
+}
+
+// ============================================================================
+// Two-Factor Authentication
+// ============================================================================
+
+/// Minutes a password login may take to present its second factor
+pub const MFA_CHALLENGE_MINUTES: i64 = 5;
+
+/// Wrong codes after which a challenge is void and the login starts over
+pub const MFA_MAX_ATTEMPTS: i32 = 5;
+
+pub const BACKUP_CODE_COUNT: usize = 10;
+
+/// Issuer shown by authenticator apps
+pub const TOTP_ISSUER: &str = "LIS Modern";
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MfaChallenge {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub challenge_token: String,
+    pub user_id: Uuid,
+
+    pub device_id: Option<String>,
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+
+    pub failed_attempts: i32,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MfaChallenge {
+    pub fn is_open(&self) -> bool {
+        self.completed_at.is_none()
+            && self.expires_at > Utc::now()
+            && self.failed_attempts < MFA_MAX_ATTEMPTS
+    }
+}
+
+// ============================================================================
+// Security Policy
+// ============================================================================
+
+/// Shortest password any organization may allow
+pub const MIN_PASSWORD_LENGTH: i32 = 8;
+
+/// Most previous passwords a policy may refuse to reuse
+pub const MAX_PASSWORD_HISTORY_DEPTH: i32 = 24;
+
+/// Longest progressive delay between failed logins
+pub const MAX_LOGIN_DELAY_SECONDS: i64 = 300;
+
+/// Security rules an organization sets for its users
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SecurityPolicy {
+    pub organization_id: Uuid,
+    pub mfa_required_user_types: Vec<UserType>,
+
+    // Password rules
+    pub min_password_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub password_expiry_days: Option<i32>,
+    pub password_history_depth: i32,
+
+    // Failed logins
+    pub lockout_threshold: i32,
+    pub lockout_minutes: i32,
+    pub progressive_delay_seconds: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<Uuid>,
+}
+
+impl SecurityPolicy {
+    /// Policy of an organization that has not set one: nothing required
+    pub fn default_for(organization_id: Uuid) -> Self {
+        let now = Utc::now();
+        Self {
+            organization_id,
+            mfa_required_user_types: Vec::new(),
+            min_password_length: MIN_PASSWORD_LENGTH,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            password_expiry_days: None,
+            password_history_depth: 0,
+            lockout_threshold: 5,
+            lockout_minutes: 30,
+            progressive_delay_seconds: 0,
+            created_at: now,
+            updated_at: now,
+            updated_by: None,
+        }
+    }
+
+    pub fn requires_mfa(&self, user_type: &UserType) -> bool {
+        self.mfa_required_user_types.contains(user_type)
+    }
+
+    /// Checks a new password against the length and complexity rules,
+    /// naming every rule it breaks
+    pub fn validate_password(&self, password: &str) -> Result<()> {
+        let mut missing = Vec::new();
+        if password.chars().count() < self.min_password_length as usize {
+            missing.push(format!("at least {} characters", self.min_password_length));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            missing.push("an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            missing.push("a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            missing.push("a digit".to_string());
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            missing.push("a symbol".to_string());
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Validation(format!("Password must contain {}", missing.join(", "))))
+        }
+    }
+
+    /// When a password set at `changed_at` stops being accepted
+    pub fn password_expires_at(&self, changed_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.password_expiry_days
+            .map(|days| changed_at + Duration::days(days as i64))
+    }
+
+    /// Whether the user's password is past its expiry, either the one set
+    /// with it or one the policy's current expiry gives
+    pub fn password_expired(&self, user: &User) -> bool {
+        let changed_at = user.password_changed_at.unwrap_or(user.created_at);
+        user.password_expires_at
+            .into_iter()
+            .chain(self.password_expires_at(changed_at))
+            .any(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Wait before the next login after `failed_attempts` consecutive
+    /// failures: the base delay, doubled for each failure after the first
+    pub fn login_delay(&self, failed_attempts: i32) -> Duration {
+        if self.progressive_delay_seconds == 0 || failed_attempts <= 0 {
+            return Duration::zero();
+        }
+
+        let doublings = (failed_attempts - 1).min(16) as u32;
+        let seconds = (self.progressive_delay_seconds as i64)
+            .saturating_mul(1 << doublings)
+            .min(MAX_LOGIN_DELAY_SECONDS);
+        Duration::seconds(seconds)
+    }
+}
+
+/// Password rules an organization administrator sets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordPolicyInput {
+    pub min_password_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub password_expiry_days: Option<i32>,
+    pub password_history_depth: i32,
+    pub lockout_threshold: i32,
+    pub lockout_minutes: i32,
+    pub progressive_delay_seconds: i32,
+}
+
+impl PasswordPolicyInput {
+    pub fn validate(&self) -> Result<()> {
+        if !(MIN_PASSWORD_LENGTH..=128).contains(&self.min_password_length) {
+            return Err(Error::Validation(format!(
+                "Minimum password length must be between {} and 128", MIN_PASSWORD_LENGTH
+            )));
+        }
+        if self.password_expiry_days.is_some_and(|days| days <= 0) {
+            return Err(Error::Validation("Password expiry must be at least one day".to_string()));
+        }
+        if !(0..=MAX_PASSWORD_HISTORY_DEPTH).contains(&self.password_history_depth) {
+            return Err(Error::Validation(format!(
+                "Password history depth must be between 0 and {}", MAX_PASSWORD_HISTORY_DEPTH
+            )));
+        }
+        if self.lockout_threshold <= 0 {
+            return Err(Error::Validation("Lockout threshold must be at least one attempt".to_string()));
+        }
+        if self.lockout_minutes <= 0 {
+            return Err(Error::Validation("Lockout duration must be at least one minute".to_string()));
+        }
+        if self.progressive_delay_seconds < 0 {
+            return Err(Error::Validation("Progressive delay cannot be negative".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Secret handed to an authenticator app; the factor counts once a code
+/// from it is confirmed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+// ============================================================================
+// Single Sign-On
+// ============================================================================
+
+/// Minutes a user may spend at the identity provider before the sign-in
+/// has to start over
+pub const SSO_LOGIN_MINUTES: i64 = 10;
+
+/// OpenID Connect provider an organization's staff sign in with
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IdentityProvider {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub provider_code: String,
+    pub display_name: String,
+
+    pub issuer_url: String,
+    pub client_id: String,
+    #[serde(skip_serializing)]
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: String,
+
+    pub role_claim: String,
+    pub jit_provisioning: bool,
+    pub default_user_type: UserType,
+
+    pub is_active: bool,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub updated_by: Option<Uuid>,
+}
+
+/// Role granted to users whose role claim holds `claim_value`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IdentityProviderRoleMapping {
+    pub id: Uuid,
+    pub identity_provider_id: Uuid,
+    pub claim_value: String,
+    pub role_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Split of the mapped roles into those a user's claim values grant and
+/// those it no longer does. Roles no mapping mentions are left alone.
+pub fn mapped_roles(mappings: &[IdentityProviderRoleMapping], claim_values: &[String]) -> (Vec<Uuid>, Vec<Uuid>) {
+    let mut granted: Vec<Uuid> = mappings
+        .iter()
+        .filter(|m| claim_values.contains(&m.claim_value))
+        .map(|m| m.role_id)
+        .collect();
+    granted.sort();
+    granted.dedup();
+
+    let mut revoked: Vec<Uuid> = mappings
+        .iter()
+        .map(|m| m.role_id)
+        .filter(|role_id| !granted.contains(role_id))
+        .collect();
+    revoked.sort();
+    revoked.dedup();
+
+    (granted, revoked)
+}
+
+/// Authorization request waiting for the provider's redirect
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OidcLoginRequest {
+    pub id: Uuid,
+    pub identity_provider_id: Uuid,
+    #[serde(skip_serializing)]
+    pub nonce: String,
+    #[serde(skip_serializing)]
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OidcLoginRequest {
+    pub fn is_open(&self) -> bool {
+        self.completed_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+/// Provider account a user signs in with
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub identity_provider_id: Uuid,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigureIdentityProviderInput {
+    pub provider_code: String,
+    pub display_name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: Option<String>,
+    pub role_claim: Option<String>,
+    pub jit_provisioning: bool,
+    pub default_user_type: UserType,
+    pub is_active: bool,
+}
+
+impl ConfigureIdentityProviderInput {
+    pub fn validate(&self) -> Result<()> {
+        if self.provider_code.trim().is_empty() {
+            return Err(Error::Validation("Provider code is required".to_string()));
+        }
+        if self.display_name.trim().is_empty() {
+            return Err(Error::Validation("Display name is required".to_string()));
+        }
+        if self.client_id.trim().is_empty() {
+            return Err(Error::Validation("Client ID is required".to_string()));
+        }
+        // Plain HTTP only for a provider on the same machine
+        let local = ["http://localhost", "http://127.0.0.1"]
+            .iter()
+            .any(|prefix| self.issuer_url.starts_with(prefix));
+        if !self.issuer_url.starts_with("https://") && !local {
+            return Err(Error::Validation("Issuer URL must use HTTPS".to_string()));
+        }
+        if self.redirect_uri.trim().is_empty() {
+            return Err(Error::Validation("Redirect URI is required".to_string()));
+        }
+        if self.scopes.as_deref().is_some_and(|scopes| !scopes.split_whitespace().any(|s| s == "openid")) {
+            return Err(Error::Validation("Scopes must include openid".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Where to send the browser to sign in at the identity provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SsoLoginStart {
+    pub authorization_url: String,
+    pub state: String,
+    pub expires_in: i64,
 }
 
 // ============================================================================
@@ -214,6 +612,27 @@ pub struct Permission {
     pub updated_at: DateTime<Utc>,
 }
 
+/// A role with the codes of the permissions it bundles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolePermissions {
+    pub role: Role,
+    pub permission_codes: Vec<String>,
+}
+
+/// Rejects codes missing from the permission catalog
+pub fn validate_permission_codes(codes: &[String]) -> Result<()> {
+    let unknown: Vec<&str> = codes
+        .iter()
+        .filter(|code| common::permissions::find(code).is_none())
+        .map(String::as_str)
+        .collect();
+
+    if !unknown.is_empty() {
+        return Err(Error::Validation(format!("Unknown permissions: {}", unknown.join(", "))));
+    }
+    Ok(())
+}
+
 // ============================================================================
 // User Session Domain Model
 // ============================================================================
@@ -439,6 +858,23 @@ pub struct LoginResponse {
     pub refresh_token: String,
     pub expires_in: i64,
     pub permissions: Vec<String>,
+    /// Backup codes of an enrollment this login completed, shown only once
+    pub backup_codes: Option<Vec<String>>,
+}
+
+/// Second step of a password login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaChallengeResponse {
+    pub challenge_token: String,
+    pub expires_in: i64,
+    /// The user must enroll an authenticator before the login can complete
+    pub enrollment_required: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    Authenticated(Box<LoginResponse>),
+    MfaRequired(MfaChallengeResponse),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -460,3 +896,116 @@ pub struct UserFilter {
     pub department: Option<String>,
     pub search_query: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_policy() -> SecurityPolicy {
+        SecurityPolicy {
+            min_password_length: 12,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..SecurityPolicy::default_for(Uuid::nil())
+        }
+    }
+
+    #[test]
+    fn test_validate_password_names_missing_rules() {
+        let policy = strict_policy();
+        assert!(policy.validate_password("Correct-Horse-42").is_ok());
+
+        let err = policy.validate_password("short").unwrap_err().to_string();
+        assert!(err.contains("at least 12 characters"));
+        assert!(err.contains("an uppercase letter"));
+        assert!(err.contains("a digit"));
+        assert!(err.contains("a symbol"));
+        assert!(!err.contains("a lowercase letter"));
+
+        assert!(SecurityPolicy::default_for(Uuid::nil()).validate_password("plainpassword").is_ok());
+    }
+
+    #[test]
+    fn test_login_delay_doubles_up_to_the_cap() {
+        let mut policy = SecurityPolicy::default_for(Uuid::nil());
+        assert_eq!(policy.login_delay(3), Duration::zero());
+
+        policy.progressive_delay_seconds = 2;
+        assert_eq!(policy.login_delay(0), Duration::zero());
+        assert_eq!(policy.login_delay(1), Duration::seconds(2));
+        assert_eq!(policy.login_delay(2), Duration::seconds(4));
+        assert_eq!(policy.login_delay(4), Duration::seconds(16));
+        assert_eq!(policy.login_delay(40), Duration::seconds(MAX_LOGIN_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn test_password_policy_input_bounds() {
+        let input = PasswordPolicyInput {
+            min_password_length: 8,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            password_expiry_days: Some(90),
+            password_history_depth: 5,
+            lockout_threshold: 5,
+            lockout_minutes: 30,
+            progressive_delay_seconds: 1,
+        };
+        assert!(input.validate().is_ok());
+        assert!(PasswordPolicyInput { min_password_length: 6, ..input.clone() }.validate().is_err());
+        assert!(PasswordPolicyInput { password_expiry_days: Some(0), ..input.clone() }.validate().is_err());
+        assert!(PasswordPolicyInput { password_history_depth: 25, ..input.clone() }.validate().is_err());
+        assert!(PasswordPolicyInput { lockout_threshold: 0, ..input }.validate().is_err());
+    }
+
+    #[test]
+    fn test_mapped_roles_grant_and_revoke_only_mapped_roles() {
+        let (pathologist, manager) = (Uuid::new_v4(), Uuid::new_v4());
+        let mapping = |claim_value: &str, role_id| IdentityProviderRoleMapping {
+            id: Uuid::new_v4(),
+            identity_provider_id: Uuid::nil(),
+            claim_value: claim_value.to_string(),
+            role_id,
+            created_at: Utc::now(),
+        };
+        let mappings = vec![
+            mapping("lis-pathologist", pathologist),
+            mapping("lis-consultant", pathologist),
+            mapping("lis-manager", manager),
+        ];
+
+        let claims = vec!["lis-consultant".to_string(), "offline_access".to_string()];
+        assert_eq!(mapped_roles(&mappings, &claims), (vec![pathologist], vec![manager]));
+
+        let (granted, revoked) = mapped_roles(&mappings, &[]);
+        assert!(granted.is_empty());
+        assert_eq!(revoked.len(), 2);
+    }
+
+    #[test]
+    fn test_permission_catalog_is_seeded_and_bundled() {
+        let migration = include_str!("../migrations/20250117000001_permission_catalog.sql");
+        for permission in common::permissions::CATALOG {
+            assert!(
+                migration.contains(&format!("('{}', '{}'", permission.code, permission.name)),
+                "{} is not seeded",
+                permission.code
+            );
+        }
+
+        // Every code a role bundles is in the catalog
+        for bundle in migration.split("ARRAY[").skip(1) {
+            let codes: Vec<String> = bundle[..bundle.find(']').unwrap()]
+                .split(',')
+                .map(|code| code.trim().trim_matches('\'').to_string())
+                .collect();
+            assert!(validate_permission_codes(&codes).is_ok(), "{:?}", codes);
+        }
+
+        let err = validate_permission_codes(&["RESULT_APPROVE".to_string(), "result:approve".to_string()]);
+        assert_eq!(err.unwrap_err().to_string(), "Validation error: Unknown permissions: result:approve");
+    }
+}
@@ -1,8 +1,10 @@
-use actix_web::{web, App, HttpServer, middleware, HttpResponse, guard};
+use actix_web::{web, App, HttpRequest, HttpServer, middleware, HttpResponse, guard};
 use actix_cors::Cors;
 use async_graphql::{Schema, EmptySubscription, http::GraphiQLSource};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use sqlx::postgres::PgPoolOptions;
+use common::auth::{JwtAuth, JwtService, RequestClaims};
+use common::tenant::{self, Tenant};
+use infrastructure::DatabasePool;
 
 mod domain;
 mod repository;
@@ -10,20 +12,30 @@ mod service;
 mod api;
 mod config;
 mod organization_client;
+mod totp;
+mod oidc;
 
 use repository::*;
 use service::UserService;
-use api::{QueryRoot, MutationRoot};
+use api::{QueryRoot, MutationRoot, RequestOrigin};
 use config::Config;
 use organization_client::OrganizationClient;
+use oidc::OidcClient;
 
 type UserSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
 async fn graphql(
     schema: web::Data<UserSchema>,
+    http_req: HttpRequest,
+    claims: RequestClaims,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    let origin = RequestOrigin {
+        ip_address: http_req.connection_info().realip_remote_addr().map(str::to_string),
+    };
+    let tenant = claims.tenant();
+    let request = claims.attach(req.into_inner()).data(origin);
+    tenant::scope(tenant, schema.execute(request)).await.into()
 }
 
 async fn graphql_playground() -> HttpResponse {
@@ -86,9 +98,6 @@ async fn main() -> std::io::Result<()> {
         Config::default()
     });
 
-    // Set JWT secret as environment variable for service layer
-    std::env::set_var("JWT_SECRET", &config.jwt_secret);
-
     tracing::info!("Configuration loaded:");
     tracing::info!("  Database: {}", mask_password(&config.database_url));
     tracing::info!("  Server: {}:{}", config.host, config.port);
@@ -98,18 +107,18 @@ async fn main() -> std::io::Result<()> {
 
     // Create database pool
     tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
+    let pool = DatabasePool::new(&config.database_url, config.database_max_connections)
         .await
-        .expect("Failed to create database pool");
+        .expect("Failed to create database pool")
+        .pg_pool;
 
     tracing::info!("Database connected successfully");
 
     // Run migrations
     tracing::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    // Migrations may touch rows of every organization
+    Tenant::System
+        .scope(infrastructure::database::migrator(sqlx::migrate!("./migrations")).run(&pool))
         .await
         .expect("Failed to run migrations");
 
@@ -121,13 +130,19 @@ async fn main() -> std::io::Result<()> {
     let permission_repo = PermissionRepository::new(pool.clone());
     let user_role_repo = UserRoleRepository::new(pool.clone());
     let session_repo = SessionRepository::new(pool.clone());
+    let mfa_challenge_repo = MfaChallengeRepository::new(pool.clone());
+    let policy_repo = SecurityPolicyRepository::new(pool.clone());
+    let idp_repo = IdentityProviderRepository::new(pool.clone());
+    let oidc_request_repo = OidcLoginRequestRepository::new(pool.clone());
+    let identity_repo = UserIdentityRepository::new(pool.clone());
     let activity_repo = ActivityLogRepository::new(pool.clone());
 
     // Create organization client
     // Default to port 8094 for organization-service
     let org_service_url = std::env::var("ORGANIZATION_SERVICE_URL")
         .unwrap_or_else(|_| "http://localhost:8094".to_string());
-    let org_client = OrganizationClient::new(org_service_url.clone());
+    let jwt = JwtService::new(&config.jwt_secret);
+    let org_client = OrganizationClient::new(org_service_url.clone(), jwt.clone());
     tracing::info!("Organization service client configured: {}", org_service_url);
 
     // Create service
@@ -137,8 +152,15 @@ async fn main() -> std::io::Result<()> {
         permission_repo,
         user_role_repo,
         session_repo,
+        mfa_challenge_repo,
+        policy_repo,
+        idp_repo,
+        oidc_request_repo,
+        identity_repo,
         activity_repo,
         org_client,
+        OidcClient::new(),
+        jwt,
     );
 
     // Build GraphQL schema
@@ -147,8 +169,10 @@ async fn main() -> std::io::Result<()> {
         .finish();
 
     tracing::info!("GraphQL schema built successfully");
-    tracing::info!("  Queries: me, user, userByEmail, searchUsers, roles, permissions, userRoles, userPermissions");
-    tracing::info!("  Mutations: register, login, logout, changePassword, requestPasswordReset, updateUserStatus, createRole, assignRole, removeRole, verifyEmail");
+    tracing::info!("  Queries: me, user, userByEmail, searchUsers, roles, permissions, userRoles, securityPolicy, userPermissions, permissionMatrix");
+    tracing::info!("  Mutations: register, login, verifyMfa, logout, changePassword, requestPasswordReset, confirmPasswordReset, updateUserStatus, createRole, setRolePermissions, assignRole, removeRole, verifyEmail");
+    tracing::info!("  Security: startMfaEnrollment, enrollTotp, confirmTotpEnrollment, disableTwoFactor, regenerateBackupCodes, updateMfaPolicy, updatePasswordPolicy");
+    tracing::info!("  SSO: ssoProviders, startSsoLogin, completeSsoLogin, identityProviders, configureIdentityProvider, setIdentityProviderRoleMappings");
 
     // Start HTTP server
     let bind_addr = format!("{}:{}", config.host, config.port);
@@ -158,6 +182,7 @@ async fn main() -> std::io::Result<()> {
     tracing::info!("Health check: http://{}/health", bind_addr);
     tracing::info!("Ready check: http://{}/ready", bind_addr);
 
+    let jwt_secret = config.jwt_secret.clone();
     HttpServer::new(move || {
         let cors = Cors::default()
             .allowed_origin("http://localhost:3000")
@@ -171,6 +196,7 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
 
         App::new()
+            .wrap(JwtAuth::new(&jwt_secret))
             .wrap(cors)
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
@@ -0,0 +1,431 @@
+//! OpenID Connect relying party: provider discovery, the authorization code
+//! flow with PKCE (S256) and ID token validation against the provider's JWKS.
+
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use common::error::{Error, Result};
+
+#[cfg(test)]
+mod mock_idp;
+
+/// Signature algorithms accepted on ID tokens; symmetric ones would let
+/// anyone holding the client secret mint tokens
+const ACCEPTED_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+/// Seconds of clock difference with the provider tolerated on `exp`/`iat`
+const LEEWAY_SECONDS: u64 = 60;
+
+/// The parts of the provider's `.well-known/openid-configuration` used here
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// Claims of a validated ID token
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: Option<bool>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub name: Option<String>,
+
+    /// Everything else, for the role claim
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl IdTokenClaims {
+    /// Whether the provider authenticated the user with more than one
+    /// factor, by the `mfa` method of the `amr` claim (RFC 8176)
+    pub fn used_mfa(&self) -> bool {
+        self.claim_values("amr").iter().any(|method| method == "mfa")
+    }
+
+    /// Values of a string or string array claim; dots in `path` reach into
+    /// nested objects (`realm_access.roles`)
+    pub fn claim_values(&self, path: &str) -> Vec<String> {
+        let mut parts = path.split('.');
+        let mut value = parts.next().and_then(|first| self.other.get(first));
+        for part in parts {
+            value = value.and_then(|v| v.get(part));
+        }
+
+        match value {
+            Some(serde_json::Value::String(s)) => vec![s.clone()],
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// PKCE pair: the verifier stays with the login request, the challenge goes
+/// to the provider
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn generate() -> Self {
+        let verifier = random_token();
+        let challenge = Self::challenge_for(&verifier);
+        Self { verifier, challenge }
+    }
+
+    /// S256 code challenge of a verifier
+    pub fn challenge_for(verifier: &str) -> String {
+        BASE64URL_NOPAD.encode(&Sha256::digest(verifier.as_bytes()))
+    }
+}
+
+/// URL-safe random value for `state`, `nonce` and PKCE verifiers
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+/// Details of the authorization request sent to the provider
+pub struct AuthorizationRequest<'a> {
+    pub client_id: &'a str,
+    pub redirect_uri: &'a str,
+    pub scopes: &'a str,
+    pub state: &'a str,
+    pub nonce: &'a str,
+    pub code_challenge: &'a str,
+}
+
+#[derive(Clone)]
+pub struct OidcClient {
+    http: reqwest::Client,
+}
+
+impl OidcClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetches the provider's metadata, which must name the issuer it was
+    /// fetched for
+    pub async fn discover(&self, issuer_url: &str) -> Result<ProviderMetadata> {
+        let url = format!("{}/.well-known/openid-configuration", issuer_url.trim_end_matches('/'));
+        let metadata: ProviderMetadata = self.get_json(&url).await?;
+
+        if metadata.issuer.trim_end_matches('/') != issuer_url.trim_end_matches('/') {
+            return Err(Error::ExternalService(format!(
+                "Identity provider reports issuer {} instead of {}",
+                metadata.issuer, issuer_url
+            )));
+        }
+
+        Ok(metadata)
+    }
+
+    pub fn authorization_url(&self, metadata: &ProviderMetadata, request: &AuthorizationRequest<'_>) -> Result<String> {
+        let mut url = reqwest::Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| Error::ExternalService(format!("Invalid authorization endpoint: {}", e)))?;
+
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", request.client_id)
+            .append_pair("redirect_uri", request.redirect_uri)
+            .append_pair("scope", request.scopes)
+            .append_pair("state", request.state)
+            .append_pair("nonce", request.nonce)
+            .append_pair("code_challenge", request.code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    /// Redeems an authorization code for the ID token it was issued with
+    pub async fn exchange_code(
+        &self,
+        metadata: &ProviderMetadata,
+        client: (&str, Option<&str>),
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<String> {
+        let (client_id, client_secret) = client;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", client_id),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self.http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Failed to reach identity provider: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::warn!("Identity provider refused the authorization code ({}): {}", status, body);
+            return Err(Error::AuthenticationFailed("Identity provider refused the sign-in".to_string()));
+        }
+
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Invalid token response: {}", e)))?;
+
+        tokens.id_token
+            .ok_or_else(|| Error::ExternalService("Identity provider returned no ID token".to_string()))
+    }
+
+    /// Checks the ID token's signature against the provider's keys, and its
+    /// issuer, audience, expiry and nonce
+    pub async fn validate_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        client_id: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims> {
+        let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+        validate_id_token(&jwks, id_token, &metadata.issuer, client_id, nonce)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self.http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Failed to reach identity provider: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(Error::ExternalService(format!(
+                "Identity provider returned {} for {}",
+                response.status(),
+                url
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Invalid response from identity provider: {}", e)))
+    }
+}
+
+fn validate_id_token(
+    jwks: &JwkSet,
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+) -> Result<IdTokenClaims> {
+    let invalid = |reason: &str| Error::AuthenticationFailed(format!("Invalid ID token: {}", reason));
+
+    let header = decode_header(id_token).map_err(|_| invalid("malformed"))?;
+    if !ACCEPTED_ALGORITHMS.contains(&header.alg) {
+        return Err(invalid("unsupported signature algorithm"));
+    }
+
+    let jwk = match header.kid.as_deref() {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| invalid("signed with an unknown key"))?;
+    let key = DecodingKey::from_jwk(jwk).map_err(|_| invalid("unusable signing key"))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    validation.leeway = LEEWAY_SECONDS;
+
+    let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+        .map_err(|e| invalid(&e.to_string()))?
+        .claims;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(invalid("nonce does not match the login request"));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock_idp::MockIdp;
+
+    const CLIENT_ID: &str = "lis-web";
+    const REDIRECT_URI: &str = "https://lis.example/sso/callback";
+
+    fn staff_claims() -> serde_json::Value {
+        serde_json::json!({
+            "sub": "kc-7d1c",
+            "email": "asha.rao@hospital.example",
+            "email_verified": true,
+            "given_name": "Asha",
+            "family_name": "Rao",
+            "realm_access": { "roles": ["lis-pathologist", "offline_access"] },
+        })
+    }
+
+    /// Runs the flow up to the ID token; returns it with the request's nonce
+    async fn sign_in(idp: &MockIdp, client: &OidcClient, metadata: &ProviderMetadata) -> (String, String) {
+        let pkce = Pkce::generate();
+        let nonce = random_token();
+        let url = client.authorization_url(metadata, &AuthorizationRequest {
+            client_id: CLIENT_ID,
+            redirect_uri: REDIRECT_URI,
+            scopes: "openid email profile",
+            state: &random_token(),
+            nonce: &nonce,
+            code_challenge: &pkce.challenge,
+        }).unwrap();
+
+        let code = idp.approve(&url, staff_claims());
+        let id_token = client
+            .exchange_code(metadata, (CLIENT_ID, None), &code, &pkce.verifier, REDIRECT_URI)
+            .await
+            .unwrap();
+        (id_token, nonce)
+    }
+
+    #[actix_web::test]
+    async fn test_authorization_code_flow_with_pkce() {
+        let idp = MockIdp::start().await;
+        let client = OidcClient::new();
+
+        let metadata = client.discover(&idp.issuer).await.unwrap();
+        assert_eq!(metadata.token_endpoint, format!("{}/token", idp.issuer));
+
+        let (id_token, nonce) = sign_in(&idp, &client, &metadata).await;
+        let claims = client.validate_id_token(&metadata, &id_token, CLIENT_ID, &nonce).await.unwrap();
+
+        assert_eq!(claims.sub, "kc-7d1c");
+        assert_eq!(claims.email.as_deref(), Some("asha.rao@hospital.example"));
+        assert_eq!(claims.claim_values("realm_access.roles"), vec!["lis-pathologist", "offline_access"]);
+    }
+
+    #[actix_web::test]
+    async fn test_token_endpoint_checks_the_code_verifier() {
+        let idp = MockIdp::start().await;
+        let client = OidcClient::new();
+        let metadata = client.discover(&idp.issuer).await.unwrap();
+
+        let pkce = Pkce::generate();
+        let url = client.authorization_url(&metadata, &AuthorizationRequest {
+            client_id: CLIENT_ID,
+            redirect_uri: REDIRECT_URI,
+            scopes: "openid",
+            state: "state",
+            nonce: "nonce",
+            code_challenge: &pkce.challenge,
+        }).unwrap();
+        let code = idp.approve(&url, staff_claims());
+
+        let stolen = client
+            .exchange_code(&metadata, (CLIENT_ID, None), &code, &Pkce::generate().verifier, REDIRECT_URI)
+            .await;
+        assert!(matches!(stolen, Err(Error::AuthenticationFailed(_))));
+    }
+
+    #[actix_web::test]
+    async fn test_id_token_must_match_nonce_audience_and_keys() {
+        let idp = MockIdp::start().await;
+        let client = OidcClient::new();
+        let metadata = client.discover(&idp.issuer).await.unwrap();
+        let (id_token, nonce) = sign_in(&idp, &client, &metadata).await;
+
+        assert!(client.validate_id_token(&metadata, &id_token, CLIENT_ID, "replayed").await.is_err());
+        assert!(client.validate_id_token(&metadata, &id_token, "other-client", &nonce).await.is_err());
+
+        // Same token checked against a provider that never issued it
+        let jwks: JwkSet = serde_json::from_value(serde_json::json!({ "keys": [] })).unwrap();
+        assert!(validate_id_token(&jwks, &id_token, &metadata.issuer, CLIENT_ID, &nonce).is_err());
+
+        // Unsigned tokens are refused outright
+        let unsigned = format!(
+            "{}.{}.",
+            BASE64URL_NOPAD.encode(br#"{"alg":"none"}"#),
+            id_token.split('.').nth(1).unwrap()
+        );
+        assert!(client.validate_id_token(&metadata, &unsigned, CLIENT_ID, &nonce).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_discovery_rejects_a_different_issuer() {
+        let idp = MockIdp::start().await;
+        let client = OidcClient::new();
+        let imposter = format!("{}/", idp.issuer.replace("127.0.0.1", "localhost"));
+        assert!(client.discover(&imposter).await.is_err());
+    }
+
+    #[test]
+    fn test_pkce_challenge_is_rfc7636_s256() {
+        // Example from RFC 7636 appendix B
+        assert_eq!(
+            Pkce::challenge_for("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_claim_values_accept_strings_and_arrays() {
+        let claims: IdTokenClaims = serde_json::from_value(serde_json::json!({
+            "iss": "https://idp.example",
+            "sub": "1",
+            "roles": "LabManager",
+            "groups": ["pathology", 7],
+        })).unwrap();
+
+        assert_eq!(claims.claim_values("roles"), vec!["LabManager"]);
+        assert_eq!(claims.claim_values("groups"), vec!["pathology"]);
+        assert!(claims.claim_values("realm_access.roles").is_empty());
+    }
+
+    #[test]
+    fn test_only_an_mfa_amr_counts_as_a_second_factor() {
+        let claims = |amr: serde_json::Value| -> IdTokenClaims {
+            serde_json::from_value(serde_json::json!({ "sub": "1", "amr": amr })).unwrap()
+        };
+
+        assert!(claims(serde_json::json!(["pwd", "mfa"])).used_mfa());
+        assert!(!claims(serde_json::json!(["pwd"])).used_mfa());
+        assert!(!claims(serde_json::Value::Null).used_mfa());
+    }
+}
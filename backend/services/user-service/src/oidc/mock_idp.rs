@@ -0,0 +1,150 @@
+//! Local OpenID provider for tests, standing in for Keycloak or Azure AD.
+//! `approve` plays the user consenting in the browser; the token endpoint
+//! enforces PKCE and signs ID tokens with a fixed RSA test key.
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+use super::{random_token, Pkce};
+
+const KEY_ID: &str = "mock-key";
+const PRIVATE_KEY_PEM: &str = include_str!("mock_idp_key.pem");
+
+/// Public half of the test key, as the JWKS publishes it
+const MODULUS: &str = "m7Cqh1DhmcAS3y6OKVSZ6LjOcogGI1quc_5C2xNiS3UYVM3fB8eSmA25wPgc-6OHj6_f1ALZceQFFEkJ8-4y5DPXrG0obqdNVt4DbhFUogVqJ1mC7r7aU-0EhuLu0iE-exa0op_5uTm5hJeln5A7Y70s6oYPNhFxWLQbe0WLry96bOR13TyLxN_3dVMf3z-mFPEKxnXyxHa8aJMK4R-AQ5krUkCWQ-ly2r538c52IhaoY5asEjZYqM9TnT9NWgpAMXH62thob51vgT4JAje5wy8A4etGXPV6L8xBvFqp-4pNZADhoaRQtbxtfSv7DL3s2zIS6ehevfem3l4NrtU6bw";
+const EXPONENT: &str = "AQAB";
+
+/// Authorization granted by `approve`, redeemable once
+struct Grant {
+    client_id: String,
+    redirect_uri: String,
+    code_challenge: String,
+    nonce: String,
+    claims: serde_json::Value,
+}
+
+#[derive(Clone)]
+struct IdpState {
+    issuer: String,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+}
+
+pub struct MockIdp {
+    pub issuer: String,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+}
+
+impl MockIdp {
+    /// Serves the provider on a free local port until the test ends
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let state = IdpState {
+            issuer: issuer.clone(),
+            grants: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/.well-known/openid-configuration", web::get().to(discovery))
+                .route("/jwks", web::get().to(jwks))
+                .route("/token", web::post().to(token))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        Self {
+            issuer,
+            grants: state.grants,
+        }
+    }
+
+    /// Signs the user in at the authorization URL; returns the code the
+    /// provider would redirect back with
+    pub fn approve(&self, authorization_url: &str, claims: serde_json::Value) -> String {
+        let url = reqwest::Url::parse(authorization_url).unwrap();
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        let code = random_token();
+        self.grants.lock().unwrap().insert(code.clone(), Grant {
+            client_id: params["client_id"].clone(),
+            redirect_uri: params["redirect_uri"].clone(),
+            code_challenge: params["code_challenge"].clone(),
+            nonce: params["nonce"].clone(),
+            claims,
+        });
+        code
+    }
+}
+
+async fn discovery(state: web::Data<IdpState>) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "issuer": state.issuer,
+        "authorization_endpoint": format!("{}/authorize", state.issuer),
+        "token_endpoint": format!("{}/token", state.issuer),
+        "jwks_uri": format!("{}/jwks", state.issuer),
+        "response_types_supported": ["code"],
+        "code_challenge_methods_supported": ["S256"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+    }))
+}
+
+async fn jwks() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "keys": [{
+            "kty": "RSA",
+            "use": "sig",
+            "alg": "RS256",
+            "kid": KEY_ID,
+            "n": MODULUS,
+            "e": EXPONENT,
+        }]
+    }))
+}
+
+async fn token(state: web::Data<IdpState>, form: web::Form<HashMap<String, String>>) -> HttpResponse {
+    let field = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+    let invalid_grant = || HttpResponse::BadRequest().json(serde_json::json!({ "error": "invalid_grant" }));
+
+    let Some(grant) = state.grants.lock().unwrap().remove(field("code")) else {
+        return invalid_grant();
+    };
+    if field("grant_type") != "authorization_code"
+        || field("client_id") != grant.client_id
+        || field("redirect_uri") != grant.redirect_uri
+        || Pkce::challenge_for(field("code_verifier")) != grant.code_challenge
+    {
+        return invalid_grant();
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let mut claims = grant.claims;
+    claims["iss"] = state.issuer.clone().into();
+    claims["aud"] = grant.client_id.into();
+    claims["nonce"] = grant.nonce.into();
+    claims["iat"] = now.into();
+    claims["exp"] = (now + 300).into();
+
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(KEY_ID.to_string());
+    let key = EncodingKey::from_rsa_pem(PRIVATE_KEY_PEM.as_bytes()).unwrap();
+    let id_token = encode(&header, &claims, &key).unwrap();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "access_token": random_token(),
+        "token_type": "Bearer",
+        "expires_in": 300,
+        "id_token": id_token,
+    }))
+}
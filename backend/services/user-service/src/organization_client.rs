@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use common::auth::JwtService;
 use common::error::{Error, Result};
 
 /// Client for communicating with the organization-service
@@ -7,6 +8,7 @@ use common::error::{Error, Result};
 pub struct OrganizationClient {
     base_url: String,
     client: reqwest::Client,
+    jwt: JwtService,
 }
 
 #[derive(Debug, Serialize)]
@@ -50,10 +52,11 @@ pub struct CreateOrganizationInput {
 }
 
 impl OrganizationClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, jwt: JwtService) -> Self {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            jwt,
         }
     }
 
@@ -91,8 +94,10 @@ impl OrganizationClient {
 
         tracing::info!("Creating organization via organization-service at {}", url);
 
+        // The registering admin has no account yet, so the call is made as the system user
         let response = self.client
             .post(&url)
+            .bearer_auth(self.jwt.service_token(Uuid::nil())?)
             .json(&request)
             .send()
             .await
@@ -158,6 +163,7 @@ impl OrganizationClient {
 
         let response = self.client
             .post(&url)
+            .bearer_auth(self.jwt.service_token(organization_id)?)
             .json(&request)
             .send()
             .await
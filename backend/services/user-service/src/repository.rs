@@ -53,6 +53,38 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// Creates an active, verified user for someone an identity provider
+    /// has already authenticated
+    pub async fn provision(&self, input: RegisterUserInput, password_hash: String) -> Result<User> {
+        let user_code = self.generate_user_code(&input.user_type).await?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (
+                id, user_code, organization_id, first_name, last_name,
+                email, password_hash, user_type, user_status,
+                email_verified, email_verified_at, mobile_verified
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, TRUE, NOW(), FALSE)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&user_code)
+        .bind(input.organization_id)
+        .bind(&input.first_name)
+        .bind(&input.last_name)
+        .bind(&input.email)
+        .bind(&password_hash)
+        .bind(&input.user_type)
+        .bind(UserStatus::Active)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
     pub async fn find_by_id(&self, id: Uuid) -> Result<Option<User>> {
         let user = sqlx::query_as::<_, User>(
             "SELECT * FROM users WHERE id = $1 AND is_deleted = FALSE"
@@ -167,13 +199,25 @@ impl UserRepository {
         Ok(user)
     }
 
-    pub async fn update_password(&self, user_id: Uuid, password_hash: String) -> Result<User> {
+    /// Replaces the password, keeping the old hash in the password history
+    pub async fn update_password(
+        &self,
+        user_id: Uuid,
+        password_hash: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<User> {
         let user = sqlx::query_as::<_, User>(
             r#"
+            WITH previous AS (
+                INSERT INTO password_history (user_id, password_hash)
+                SELECT id, password_hash FROM users
+                WHERE id = $2 AND is_deleted = FALSE
+            )
             UPDATE users
             SET
                 password_hash = $1,
                 password_changed_at = NOW(),
+                password_expires_at = $3,
                 must_change_password = FALSE,
                 updated_at = NOW()
             WHERE id = $2 AND is_deleted = FALSE
@@ -182,6 +226,7 @@ impl UserRepository {
         )
         .bind(&password_hash)
         .bind(user_id)
+        .bind(expires_at)
         .fetch_one(&self.pool)
         .await
         .map_err(Error::Database)?;
@@ -189,6 +234,25 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// Hashes of the user's most recently replaced passwords, newest first
+    pub async fn recent_password_hashes(&self, user_id: Uuid, limit: i64) -> Result<Vec<String>> {
+        let hashes = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT password_hash FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(hashes)
+    }
+
     pub async fn update_status(&self, user_id: Uuid, status: UserStatus) -> Result<User> {
         let user = sqlx::query_as::<_, User>(
             r#"
@@ -207,47 +271,59 @@ impl UserRepository {
         Ok(user)
     }
 
-    pub async fn record_login_attempt(&self, user_id: Uuid, success: bool, ip_address: Option<String>) -> Result<()> {
-        if success {
-            sqlx::query(
-                r#"
-                UPDATE users
-                SET
-                    last_login_at = NOW(),
-                    last_login_ip = $1::inet,
-                    login_count = login_count + 1,
-                    failed_login_attempts = 0
-                WHERE id = $2
-                "#
-            )
-            .bind(ip_address)
-            .bind(user_id)
-            .execute(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-        } else {
-            sqlx::query(
-                r#"
-                UPDATE users
-                SET
-                    failed_login_attempts = failed_login_attempts + 1,
-                    last_failed_login_at = NOW(),
-                    locked_until = CASE
-                        WHEN failed_login_attempts + 1 >= 5 THEN NOW() + INTERVAL '30 minutes'
-                        ELSE locked_until
-                    END
-                WHERE id = $1
-                "#
-            )
-            .bind(user_id)
-            .execute(&self.pool)
-            .await
-            .map_err(Error::Database)?;
-        }
+    pub async fn record_login(&self, user_id: Uuid, ip_address: Option<String>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET
+                last_login_at = NOW(),
+                last_login_ip = $1::inet,
+                login_count = login_count + 1,
+                failed_login_attempts = 0
+            WHERE id = $2
+            "#
+        )
+        .bind(ip_address)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
 
         Ok(())
     }
 
+    /// Counts a failed login, locking the account for `lockout_minutes` once
+    /// the failures reach `lockout_threshold`
+    pub async fn record_failed_login(
+        &self,
+        user_id: Uuid,
+        lockout_threshold: i32,
+        lockout_minutes: i32,
+    ) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET
+                failed_login_attempts = failed_login_attempts + 1,
+                last_failed_login_at = NOW(),
+                locked_until = CASE
+                    WHEN failed_login_attempts + 1 >= $2 THEN NOW() + make_interval(mins => $3)
+                    ELSE locked_until
+                END
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(lockout_threshold)
+        .bind(lockout_minutes)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
     pub async fn verify_email(&self, user_id: Uuid) -> Result<User> {
         let user = sqlx::query_as::<_, User>(
             r#"
@@ -330,6 +406,111 @@ impl UserRepository {
         Ok(())
     }
 
+    /// Stores the secret of an enrollment still to be confirmed
+    pub async fn set_two_factor_secret(&self, user_id: Uuid, secret: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET
+                two_factor_secret = $1,
+                two_factor_last_step = NULL,
+                updated_at = NOW()
+            WHERE id = $2 AND two_factor_enabled = FALSE
+            "#
+        )
+        .bind(secret)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    pub async fn enable_two_factor(&self, user_id: Uuid, step: i64, backup_code_hashes: &[String]) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET
+                two_factor_enabled = TRUE,
+                two_factor_last_step = $1,
+                backup_codes = $2,
+                updated_at = NOW()
+            WHERE id = $3 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(step)
+        .bind(serde_json::json!(backup_code_hashes))
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
+    pub async fn disable_two_factor(&self, user_id: Uuid) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET
+                two_factor_enabled = FALSE,
+                two_factor_secret = NULL,
+                two_factor_last_step = NULL,
+                backup_codes = NULL,
+                updated_at = NOW()
+            WHERE id = $1 AND is_deleted = FALSE
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
+    /// Records the time step of an accepted code. False when the step (or a
+    /// later one) was used concurrently.
+    pub async fn use_two_factor_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET two_factor_last_step = $1
+            WHERE id = $2 AND (two_factor_last_step IS NULL OR two_factor_last_step < $1)
+            "#
+        )
+        .bind(step)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Replaces the backup codes if they are still `expected`, so each code
+    /// is used at most once
+    pub async fn replace_backup_codes(&self, user_id: Uuid, expected: &[String], codes: &[String]) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET backup_codes = $1, updated_at = NOW()
+            WHERE id = $2 AND COALESCE(backup_codes, '[]'::jsonb) = $3
+            "#
+        )
+        .bind(serde_json::json!(codes))
+        .bind(user_id)
+        .bind(serde_json::json!(expected))
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
     async fn generate_user_code(&self, user_type: &UserType) -> Result<String> {
         let org_code = "LAB"; // Should fetch from organization
         let type_str = match user_type {
@@ -481,6 +662,63 @@ impl RoleRepository {
 
         Ok(permissions)
     }
+
+    /// (role, permission code) pairs of every role the caller can see
+    pub async fn permission_codes(&self) -> Result<Vec<(Uuid, String)>> {
+        let pairs = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT rp.role_id, p.permission_code
+            FROM role_permission rp
+            INNER JOIN permission p ON p.id = rp.permission_id
+            WHERE p.is_active = TRUE
+            ORDER BY p.module, p.permission_code
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(pairs)
+    }
+
+    /// Replaces the permissions a role bundles
+    pub async fn replace_permissions(
+        &self,
+        role_id: Uuid,
+        permission_codes: &[String],
+        updated_by: Uuid,
+    ) -> Result<Vec<Permission>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        sqlx::query("DELETE FROM role_permission WHERE role_id = $1")
+            .bind(role_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO role_permission (role_id, permission_id)
+            SELECT $1, id FROM permission
+            WHERE permission_code = ANY($2) AND is_active = TRUE
+            "#
+        )
+        .bind(role_id)
+        .bind(permission_codes)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query("UPDATE role SET updated_at = NOW(), updated_by = $2 WHERE id = $1")
+            .bind(role_id)
+            .bind(updated_by)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+        self.get_role_permissions(role_id).await
+    }
 }
 
 // ============================================================================
@@ -766,6 +1004,490 @@ impl SessionRepository {
     }
 }
 
+// ============================================================================
+// MFA Challenge Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct MfaChallengeRepository {
+    pool: PgPool,
+}
+
+impl MfaChallengeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        challenge_token: &str,
+        device_info: (Option<String>, Option<String>),
+        ip_address: Option<String>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<MfaChallenge> {
+        let challenge = sqlx::query_as::<_, MfaChallenge>(
+            r#"
+            INSERT INTO mfa_challenge (
+                id, challenge_token, user_id, device_id, device_name, ip_address, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(challenge_token)
+        .bind(user_id)
+        .bind(device_info.0)
+        .bind(device_info.1)
+        .bind(ip_address)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(challenge)
+    }
+
+    pub async fn find_by_token(&self, challenge_token: &str) -> Result<Option<MfaChallenge>> {
+        let challenge = sqlx::query_as::<_, MfaChallenge>(
+            "SELECT * FROM mfa_challenge WHERE challenge_token = $1"
+        )
+        .bind(challenge_token)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(challenge)
+    }
+
+    pub async fn record_failure(&self, challenge_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE mfa_challenge SET failed_attempts = failed_attempts + 1 WHERE id = $1"
+        )
+        .bind(challenge_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Closes the challenge; false when another request completed it first
+    pub async fn complete(&self, challenge_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE mfa_challenge SET completed_at = NOW() WHERE id = $1 AND completed_at IS NULL"
+        )
+        .bind(challenge_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+// ============================================================================
+// Identity Provider Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct IdentityProviderRepository {
+    pool: PgPool,
+}
+
+impl IdentityProviderRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Creates or updates the provider with the input's code. A missing
+    /// client secret keeps the stored one.
+    pub async fn upsert(
+        &self,
+        organization_id: Uuid,
+        input: &ConfigureIdentityProviderInput,
+        user_id: Uuid,
+    ) -> Result<IdentityProvider> {
+        let provider = sqlx::query_as::<_, IdentityProvider>(
+            r#"
+            INSERT INTO identity_provider (
+                id, organization_id, provider_code, display_name, issuer_url, client_id,
+                client_secret, redirect_uri, scopes, role_claim, jit_provisioning,
+                default_user_type, is_active, created_by, updated_by
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8,
+                COALESCE($9, 'openid email profile'), COALESCE($10, 'roles'),
+                $11, $12, $13, $14, $14
+            )
+            ON CONFLICT (organization_id, provider_code) DO UPDATE
+            SET
+                display_name = EXCLUDED.display_name,
+                issuer_url = EXCLUDED.issuer_url,
+                client_id = EXCLUDED.client_id,
+                client_secret = COALESCE($7, identity_provider.client_secret),
+                redirect_uri = EXCLUDED.redirect_uri,
+                scopes = EXCLUDED.scopes,
+                role_claim = EXCLUDED.role_claim,
+                jit_provisioning = EXCLUDED.jit_provisioning,
+                default_user_type = EXCLUDED.default_user_type,
+                is_active = EXCLUDED.is_active,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(organization_id)
+        .bind(&input.provider_code)
+        .bind(&input.display_name)
+        .bind(&input.issuer_url)
+        .bind(&input.client_id)
+        .bind(&input.client_secret)
+        .bind(&input.redirect_uri)
+        .bind(&input.scopes)
+        .bind(&input.role_claim)
+        .bind(input.jit_provisioning)
+        .bind(&input.default_user_type)
+        .bind(input.is_active)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(provider)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<IdentityProvider>> {
+        let provider = sqlx::query_as::<_, IdentityProvider>(
+            "SELECT * FROM identity_provider WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(provider)
+    }
+
+    pub async fn find_by_code(&self, organization_id: Uuid, provider_code: &str) -> Result<Option<IdentityProvider>> {
+        let provider = sqlx::query_as::<_, IdentityProvider>(
+            "SELECT * FROM identity_provider WHERE organization_id = $1 AND provider_code = $2"
+        )
+        .bind(organization_id)
+        .bind(provider_code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(provider)
+    }
+
+    pub async fn list(&self, organization_id: Uuid) -> Result<Vec<IdentityProvider>> {
+        let providers = sqlx::query_as::<_, IdentityProvider>(
+            "SELECT * FROM identity_provider WHERE organization_id = $1 ORDER BY display_name"
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(providers)
+    }
+
+    pub async fn role_mappings(&self, identity_provider_id: Uuid) -> Result<Vec<IdentityProviderRoleMapping>> {
+        let mappings = sqlx::query_as::<_, IdentityProviderRoleMapping>(
+            r#"
+            SELECT * FROM identity_provider_role_mapping
+            WHERE identity_provider_id = $1
+            ORDER BY claim_value
+            "#
+        )
+        .bind(identity_provider_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(mappings)
+    }
+
+    /// Replaces all role mappings of a provider with (claim value, role) pairs
+    pub async fn replace_role_mappings(
+        &self,
+        identity_provider_id: Uuid,
+        mappings: &[(String, Uuid)],
+    ) -> Result<Vec<IdentityProviderRoleMapping>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        sqlx::query("DELETE FROM identity_provider_role_mapping WHERE identity_provider_id = $1")
+            .bind(identity_provider_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        let (claim_values, role_ids): (Vec<String>, Vec<Uuid>) = mappings.iter().cloned().unzip();
+        let saved = sqlx::query_as::<_, IdentityProviderRoleMapping>(
+            r#"
+            INSERT INTO identity_provider_role_mapping (identity_provider_id, claim_value, role_id)
+            SELECT $1, claim_value, role_id
+            FROM UNNEST($2::text[], $3::uuid[]) AS m(claim_value, role_id)
+            ON CONFLICT DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(identity_provider_id)
+        .bind(&claim_values)
+        .bind(&role_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+        Ok(saved)
+    }
+}
+
+// ============================================================================
+// OIDC Login Request Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct OidcLoginRequestRepository {
+    pool: PgPool,
+}
+
+impl OidcLoginRequestRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        identity_provider_id: Uuid,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<OidcLoginRequest> {
+        let request = sqlx::query_as::<_, OidcLoginRequest>(
+            r#"
+            INSERT INTO oidc_login_request (id, identity_provider_id, state, nonce, code_verifier, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(identity_provider_id)
+        .bind(state)
+        .bind(nonce)
+        .bind(code_verifier)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(request)
+    }
+
+    pub async fn find_by_state(&self, state: &str) -> Result<Option<OidcLoginRequest>> {
+        let request = sqlx::query_as::<_, OidcLoginRequest>(
+            "SELECT * FROM oidc_login_request WHERE state = $1"
+        )
+        .bind(state)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(request)
+    }
+
+    /// Closes the request; false when another callback used it first
+    pub async fn complete(&self, request_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE oidc_login_request SET completed_at = NOW() WHERE id = $1 AND completed_at IS NULL"
+        )
+        .bind(request_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+// ============================================================================
+// User Identity Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct UserIdentityRepository {
+    pool: PgPool,
+}
+
+impl UserIdentityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find(&self, identity_provider_id: Uuid, subject: &str) -> Result<Option<UserIdentity>> {
+        let identity = sqlx::query_as::<_, UserIdentity>(
+            "SELECT * FROM user_identity WHERE identity_provider_id = $1 AND subject = $2"
+        )
+        .bind(identity_provider_id)
+        .bind(subject)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(identity)
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        identity_provider_id: Uuid,
+        subject: &str,
+        email: Option<String>,
+    ) -> Result<UserIdentity> {
+        let identity = sqlx::query_as::<_, UserIdentity>(
+            r#"
+            INSERT INTO user_identity (id, user_id, identity_provider_id, subject, email, last_login_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(identity_provider_id)
+        .bind(subject)
+        .bind(email)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(identity)
+    }
+
+    pub async fn record_login(&self, identity_id: Uuid, email: Option<String>) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_identity SET last_login_at = NOW(), email = COALESCE($2, email) WHERE id = $1"
+        )
+        .bind(identity_id)
+        .bind(email)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Security Policy Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SecurityPolicyRepository {
+    pool: PgPool,
+}
+
+impl SecurityPolicyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find(&self, organization_id: Uuid) -> Result<Option<SecurityPolicy>> {
+        let policy = sqlx::query_as::<_, SecurityPolicy>(
+            "SELECT * FROM organization_security_policy WHERE organization_id = $1"
+        )
+        .bind(organization_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(policy)
+    }
+
+    pub async fn set_mfa_required_user_types(
+        &self,
+        organization_id: Uuid,
+        user_types: &[UserType],
+        updated_by: Uuid,
+    ) -> Result<SecurityPolicy> {
+        let policy = sqlx::query_as::<_, SecurityPolicy>(
+            r#"
+            INSERT INTO organization_security_policy (organization_id, mfa_required_user_types, updated_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (organization_id) DO UPDATE
+            SET
+                mfa_required_user_types = EXCLUDED.mfa_required_user_types,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(organization_id)
+        .bind(user_types)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(policy)
+    }
+
+    pub async fn set_password_policy(
+        &self,
+        organization_id: Uuid,
+        input: &PasswordPolicyInput,
+        updated_by: Uuid,
+    ) -> Result<SecurityPolicy> {
+        let policy = sqlx::query_as::<_, SecurityPolicy>(
+            r#"
+            INSERT INTO organization_security_policy (
+                organization_id, min_password_length, require_uppercase, require_lowercase,
+                require_digit, require_symbol, password_expiry_days, password_history_depth,
+                lockout_threshold, lockout_minutes, progressive_delay_seconds, updated_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (organization_id) DO UPDATE
+            SET
+                min_password_length = EXCLUDED.min_password_length,
+                require_uppercase = EXCLUDED.require_uppercase,
+                require_lowercase = EXCLUDED.require_lowercase,
+                require_digit = EXCLUDED.require_digit,
+                require_symbol = EXCLUDED.require_symbol,
+                password_expiry_days = EXCLUDED.password_expiry_days,
+                password_history_depth = EXCLUDED.password_history_depth,
+                lockout_threshold = EXCLUDED.lockout_threshold,
+                lockout_minutes = EXCLUDED.lockout_minutes,
+                progressive_delay_seconds = EXCLUDED.progressive_delay_seconds,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(organization_id)
+        .bind(input.min_password_length)
+        .bind(input.require_uppercase)
+        .bind(input.require_lowercase)
+        .bind(input.require_digit)
+        .bind(input.require_symbol)
+        .bind(input.password_expiry_days)
+        .bind(input.password_history_depth)
+        .bind(input.lockout_threshold)
+        .bind(input.lockout_minutes)
+        .bind(input.progressive_delay_seconds)
+        .bind(updated_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(policy)
+    }
+}
+
 // ============================================================================
 // Activity Log Repository
 // ============================================================================
@@ -1,10 +1,13 @@
 use uuid::Uuid;
 use chrono::{Duration, Utc};
+use common::auth::{Claims, JwtService};
 use common::error::{Error, Result};
 
 use crate::domain::*;
 use crate::repository::*;
 use crate::organization_client::{OrganizationClient, CreateOrganizationInput};
+use crate::oidc::{self, AuthorizationRequest, IdTokenClaims, OidcClient, Pkce};
+use crate::totp;
 
 // ============================================================================
 // User Service
@@ -17,19 +20,34 @@ pub struct UserService {
     permission_repo: PermissionRepository,
     user_role_repo: UserRoleRepository,
     session_repo: SessionRepository,
+    mfa_challenge_repo: MfaChallengeRepository,
+    policy_repo: SecurityPolicyRepository,
+    idp_repo: IdentityProviderRepository,
+    oidc_request_repo: OidcLoginRequestRepository,
+    identity_repo: UserIdentityRepository,
     activity_repo: ActivityLogRepository,
     org_client: OrganizationClient,
+    oidc: OidcClient,
+    jwt: JwtService,
 }
 
 impl UserService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_repo: UserRepository,
         role_repo: RoleRepository,
         permission_repo: PermissionRepository,
         user_role_repo: UserRoleRepository,
         session_repo: SessionRepository,
+        mfa_challenge_repo: MfaChallengeRepository,
+        policy_repo: SecurityPolicyRepository,
+        idp_repo: IdentityProviderRepository,
+        oidc_request_repo: OidcLoginRequestRepository,
+        identity_repo: UserIdentityRepository,
         activity_repo: ActivityLogRepository,
         org_client: OrganizationClient,
+        oidc: OidcClient,
+        jwt: JwtService,
     ) -> Self {
         Self {
             user_repo,
@@ -37,8 +55,15 @@ impl UserService {
             permission_repo,
             user_role_repo,
             session_repo,
+            mfa_challenge_repo,
+            policy_repo,
+            idp_repo,
+            oidc_request_repo,
+            identity_repo,
             activity_repo,
             org_client,
+            oidc,
+            jwt,
         }
     }
 
@@ -150,6 +175,8 @@ impl UserService {
             }
         };
 
+        self.assign_default_role(&user, user.id).await?;
+
         // Log activity
         self.activity_repo.log(
             Some(user.id),
@@ -165,7 +192,10 @@ impl UserService {
         Ok((user, organization_id))
     }
 
-    pub async fn login(&self, input: LoginInput, ip_address: Option<String>) -> Result<LoginResponse> {
+    /// Checks the password under the organization's lockout and expiry
+    /// rules. Users with a second factor, or whose organization requires one,
+    /// get an MFA challenge instead of a session.
+    pub async fn login(&self, input: LoginInput, ip_address: Option<String>) -> Result<LoginOutcome> {
         input.validate()?;
 
         // Find user by email
@@ -182,11 +212,7 @@ impl UserService {
         }
 
         if user.is_locked() {
-            let locked_until = user.locked_until.unwrap();
-            return Err(Error::AuthenticationFailed(
-                format!("Account is temporarily locked until {}. Please try again later or contact support.",
-                    locked_until.format("%Y-%m-%d %H:%M UTC"))
-            ));
+            return Err(locked_out(&user));
         }
 
         if user.user_status != UserStatus::Active {
@@ -210,22 +236,137 @@ impl UserService {
             ));
         }
 
+        let policy = self.get_security_policy(user.organization_id).await?;
+        check_login_delay(&user, &policy)?;
+
         // Verify password
         if !self.verify_password(&input.password, &user.password_hash)? {
-            // Record failed attempt
-            self.user_repo.record_login_attempt(user.id, false, ip_address.clone()).await?;
+            return Err(self.record_failed_login(&user, &policy, ip_address, "Invalid credentials").await?);
+        }
+
+        if policy.password_expired(&user) {
+            self.activity_repo.log(
+                Some(user.id),
+                None,
+                "PASSWORD_EXPIRED",
+                "AUTH",
+                Some("Login refused, password has expired".to_string()),
+                ip_address,
+            ).await?;
 
-            return Err(Error::AuthenticationFailed("Invalid credentials".to_string()));
+            return Err(Error::AuthenticationFailed(
+                "Your password has expired. Please reset it to sign in.".to_string()
+            ));
         }
 
-        // Check if account is locked
+        if user.two_factor_enabled || policy.requires_mfa(&user.user_type) {
+            let challenge = self.mfa_challenge(
+                &user,
+                (input.device_id, input.device_name),
+                ip_address,
+                "Password accepted, second factor required",
+            ).await?;
+            return Ok(LoginOutcome::MfaRequired(challenge));
+        }
+
+        let response = self.start_session(user, (input.device_id, input.device_name), ip_address).await?;
+        Ok(LoginOutcome::Authenticated(Box::new(response)))
+    }
+
+    /// Opens the challenge a login answers with verifyMfa
+    async fn mfa_challenge(
+        &self,
+        user: &User,
+        device: (Option<String>, Option<String>),
+        ip_address: Option<String>,
+        detail: &str,
+    ) -> Result<MfaChallengeResponse> {
+        let challenge_token = self.generate_token();
+        let expires_at = Utc::now() + Duration::minutes(MFA_CHALLENGE_MINUTES);
+        let challenge = self.mfa_challenge_repo.create(
+            user.id,
+            &challenge_token,
+            device,
+            ip_address.clone(),
+            expires_at,
+        ).await?;
+
+        self.activity_repo.log(
+            Some(user.id),
+            None,
+            "MFA_CHALLENGED",
+            "AUTH",
+            Some(detail.to_string()),
+            ip_address,
+        ).await?;
+
+        Ok(MfaChallengeResponse {
+            challenge_token: challenge.challenge_token,
+            expires_in: MFA_CHALLENGE_MINUTES * 60,
+            enrollment_required: !user.two_factor_enabled,
+        })
+    }
+
+    /// Completes a challenged login with an authenticator or backup code. A
+    /// user enrolling during login confirms the new authenticator here and
+    /// receives their backup codes with the session.
+    pub async fn verify_mfa(&self, challenge_token: &str, code: &str) -> Result<LoginResponse> {
+        let challenge = self.open_challenge(challenge_token).await?;
+        let user = self.get_user(challenge.user_id).await?;
+
+        // Wrong codes count as failed logins, so guessing a code hits the
+        // same delay and lockout as guessing a password
         if user.is_locked() {
-            return Err(Error::AuthenticationFailed("Account is locked due to multiple failed login attempts".to_string()));
+            return Err(locked_out(&user));
         }
+        let policy = self.get_security_policy(user.organization_id).await?;
+        check_login_delay(&user, &policy)?;
+
+        let backup_codes = if user.two_factor_enabled {
+            if !self.check_second_factor(&user, code).await? {
+                return Err(self.reject_mfa_code(&challenge, &user, &policy).await?);
+            }
+            None
+        } else {
+            match self.confirm_two_factor(&user, code).await? {
+                Some(codes) => Some(codes),
+                None => return Err(self.reject_mfa_code(&challenge, &user, &policy).await?),
+            }
+        };
+
+        if !self.mfa_challenge_repo.complete(challenge.id).await? {
+            return Err(Error::AuthenticationFailed("MFA challenge already used".to_string()));
+        }
+
+        let mut response = self.start_session(
+            user,
+            (challenge.device_id, challenge.device_name),
+            challenge.ip_address,
+        ).await?;
+        response.backup_codes = backup_codes;
+        Ok(response)
+    }
+
+    /// Starts enrolling the authenticator of a user whose organization
+    /// requires one, from their login challenge
+    pub async fn start_challenge_enrollment(&self, challenge_token: &str) -> Result<TotpEnrollment> {
+        let challenge = self.open_challenge(challenge_token).await?;
+        self.start_totp_enrollment(challenge.user_id).await
+    }
+
+    /// Opens the session of an authenticated user
+    async fn start_session(
+        &self,
+        user: User,
+        device_info: (Option<String>, Option<String>),
+        ip_address: Option<String>,
+    ) -> Result<LoginResponse> {
+        // Get user permissions
+        let permissions = self.get_user_permission_codes(user.id).await?;
 
         // Generate tokens
         let session_token = self.generate_token();
-        let access_token = self.generate_jwt(&user, Duration::hours(1))?;
+        let access_token = self.generate_jwt(&user, permissions.clone(), Duration::hours(1)).await?;
         let refresh_token = self.generate_token();
 
         // Create session
@@ -237,17 +378,14 @@ impl UserService {
             session_token,
             access_token.clone(),
             refresh_token.clone(),
-            (input.device_id, input.device_name, Some("WEB".to_string())),
+            (device_info.0, device_info.1, Some("WEB".to_string())),
             ip_address.clone(),
             expires_at,
             refresh_expires_at,
         ).await?;
 
         // Record successful login
-        self.user_repo.record_login_attempt(user.id, true, ip_address.clone()).await?;
-
-        // Get user permissions
-        let permissions = self.get_user_permission_codes(user.id).await?;
+        self.user_repo.record_login(user.id, ip_address.clone()).await?;
 
         // Log activity
         self.activity_repo.log(
@@ -269,6 +407,7 @@ impl UserService {
             refresh_token,
             expires_in: 3600, // 1 hour
             permissions,
+            backup_codes: None,
         })
     }
 
@@ -308,7 +447,8 @@ impl UserService {
         let user = self.get_user(session.user_id).await?;
 
         // Generate new tokens
-        let new_access_token = self.generate_jwt(&user, Duration::hours(1))?;
+        let permissions = self.get_user_permission_codes(user.id).await?;
+        let new_access_token = self.generate_jwt(&user, permissions, Duration::hours(1)).await?;
         let new_refresh_token = self.generate_token();
 
         // Update session (in production, you'd want to update the session record)
@@ -398,11 +538,7 @@ impl UserService {
             return Err(Error::AuthenticationFailed("Current password is incorrect".to_string()));
         }
 
-        // Hash new password
-        let password_hash = self.hash_password(&input.new_password)?;
-
-        // Update password
-        let user = self.user_repo.update_password(input.user_id, password_hash).await?;
+        let user = self.set_password(&user, &input.new_password).await?;
 
         // Log activity
         self.activity_repo.log(
@@ -452,11 +588,7 @@ impl UserService {
             .await?
             .ok_or_else(|| Error::NotFound("Invalid or expired reset token".to_string()))?;
 
-        // Hash new password
-        let password_hash = self.hash_password(&input.new_password)?;
-
-        // Update password
-        let user = self.user_repo.update_password(user.id, password_hash).await?;
+        let user = self.set_password(&user, &input.new_password).await?;
 
         // Clear reset token
         self.user_repo.clear_reset_token(user.id).await?;
@@ -475,6 +607,34 @@ impl UserService {
         Ok(user)
     }
 
+    /// Replaces a user's password once it meets their organization's rules
+    async fn set_password(&self, user: &User, new_password: &str) -> Result<User> {
+        let policy = self.get_security_policy(user.organization_id).await?;
+        policy.validate_password(new_password)?;
+
+        if policy.password_history_depth > 0 {
+            let history = self.user_repo
+                .recent_password_hashes(user.id, (policy.password_history_depth - 1) as i64)
+                .await?;
+
+            for hash in std::iter::once(&user.password_hash).chain(&history) {
+                if self.verify_password(new_password, hash)? {
+                    return Err(Error::Validation(format!(
+                        "Password cannot be one of your last {} passwords",
+                        policy.password_history_depth
+                    )));
+                }
+            }
+        }
+
+        // Hash new password
+        let password_hash = self.hash_password(new_password)?;
+        let expires_at = policy.password_expires_at(Utc::now());
+
+        // Update password
+        self.user_repo.update_password(user.id, password_hash, expires_at).await
+    }
+
     // ========================================================================
     // Role and Permission Operations
     // ========================================================================
@@ -496,6 +656,84 @@ impl UserService {
         self.permission_repo.get_all().await
     }
 
+    /// The permission catalog and what each role visible to the caller may do
+    pub async fn permission_matrix(&self, org_id: Option<Uuid>) -> Result<(Vec<Permission>, Vec<RolePermissions>)> {
+        let permissions = self.permission_repo.get_all().await?;
+        let pairs = self.role_repo.permission_codes().await?;
+
+        let roles = self.role_repo
+            .get_all(org_id)
+            .await?
+            .into_iter()
+            .map(|role| {
+                let permission_codes = pairs
+                    .iter()
+                    .filter(|(role_id, _)| *role_id == role.id)
+                    .map(|(_, code)| code.clone())
+                    .collect();
+                RolePermissions { role, permission_codes }
+            })
+            .collect();
+
+        Ok((permissions, roles))
+    }
+
+    /// Replaces the permissions of one of the organization's own roles;
+    /// system roles are shared by every organization and stay as seeded
+    pub async fn set_role_permissions(
+        &self,
+        organization_id: Uuid,
+        role_id: Uuid,
+        permission_codes: Vec<String>,
+        updated_by: Uuid,
+    ) -> Result<Vec<Permission>> {
+        validate_permission_codes(&permission_codes)?;
+
+        let role = self.role_repo
+            .find_by_id(role_id)
+            .await?
+            .ok_or_else(|| Error::NotFound("Role not found".to_string()))?;
+
+        if role.is_system_role {
+            return Err(Error::Validation(
+                "System roles cannot be changed; create a role of your own instead".to_string()
+            ));
+        }
+        if role.organization_id != Some(organization_id) {
+            return Err(Error::NotFound("Role not found".to_string()));
+        }
+
+        let permissions = self.role_repo.replace_permissions(role.id, &permission_codes, updated_by).await?;
+
+        self.activity_repo.log(
+            Some(updated_by),
+            Some(organization_id),
+            "ROLE_PERMISSIONS_UPDATED",
+            "USER",
+            Some(format!("{} now has {} permissions", role.role_code, permissions.len())),
+            None,
+        ).await?;
+
+        tracing::info!("Permissions of role {} replaced", role.role_code);
+        Ok(permissions)
+    }
+
+    /// Gives a new account the system role of its user type
+    async fn assign_default_role(&self, user: &User, assigned_by: Uuid) -> Result<()> {
+        let Some(role_code) = user.user_type.default_role_code() else {
+            return Ok(());
+        };
+
+        let role = self.role_repo
+            .find_by_code(role_code)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Role {} not found", role_code)))?;
+
+        self.user_role_repo
+            .assign_role(AssignRoleInput { user_id: user.id, role_id: role.id }, assigned_by)
+            .await
+    }
+
     pub async fn assign_role_to_user(&self, input: AssignRoleInput, assigned_by: Uuid) -> Result<()> {
         // Verify role exists
         let _ = self.role_repo
@@ -578,6 +816,319 @@ impl UserService {
         self.activity_repo.get_user_activity(user_id, limit).await
     }
 
+    // ========================================================================
+    // Two-Factor Authentication
+    // ========================================================================
+
+    /// Generates a new authenticator secret for the user. It replaces any
+    /// unconfirmed one and counts once confirmed with a code from the app.
+    pub async fn start_totp_enrollment(&self, user_id: Uuid) -> Result<TotpEnrollment> {
+        let user = self.get_user(user_id).await?;
+        if user.two_factor_enabled {
+            return Err(Error::InvalidState("Two-factor authentication is already enabled".to_string()));
+        }
+
+        let secret = totp::generate_secret();
+        self.user_repo.set_two_factor_secret(user.id, &secret).await?;
+
+        Ok(TotpEnrollment {
+            otpauth_uri: totp::provisioning_uri(&secret, TOTP_ISSUER, &user.email),
+            secret,
+        })
+    }
+
+    /// Confirms enrollment with a code from the authenticator and returns
+    /// the backup codes, which are not shown again
+    pub async fn confirm_totp_enrollment(&self, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+        let user = self.get_user(user_id).await?;
+        if user.two_factor_enabled {
+            return Err(Error::InvalidState("Two-factor authentication is already enabled".to_string()));
+        }
+
+        self.confirm_two_factor(&user, code)
+            .await?
+            .ok_or_else(|| Error::AuthenticationFailed("Invalid verification code".to_string()))
+    }
+
+    /// Turns the second factor off, after proving possession of it. Users
+    /// their organization requires MFA of cannot.
+    pub async fn disable_two_factor(&self, user_id: Uuid, code: &str) -> Result<User> {
+        let user = self.get_user(user_id).await?;
+        if !user.two_factor_enabled {
+            return Err(Error::InvalidState("Two-factor authentication is not enabled".to_string()));
+        }
+
+        let policy = self.get_security_policy(user.organization_id).await?;
+        if policy.requires_mfa(&user.user_type) {
+            return Err(Error::BusinessRuleViolation(
+                "Your organization requires two-factor authentication".to_string()
+            ));
+        }
+
+        if !self.check_second_factor(&user, code).await? {
+            return Err(Error::AuthenticationFailed("Invalid verification code".to_string()));
+        }
+
+        let user = self.user_repo.disable_two_factor(user.id).await?;
+
+        self.activity_repo.log(
+            Some(user.id),
+            None,
+            "MFA_DISABLED",
+            "AUTH",
+            Some("Two-factor authentication disabled".to_string()),
+            None,
+        ).await?;
+
+        tracing::info!("Two-factor authentication disabled for user: {}", user.email);
+        Ok(user)
+    }
+
+    /// Replaces every backup code, after proving possession of the factor
+    pub async fn regenerate_backup_codes(&self, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+        let user = self.get_user(user_id).await?;
+        if !user.two_factor_enabled {
+            return Err(Error::InvalidState("Two-factor authentication is not enabled".to_string()));
+        }
+
+        if !self.check_second_factor(&user, code).await? {
+            return Err(Error::AuthenticationFailed("Invalid verification code".to_string()));
+        }
+
+        // Re-read: checking the factor may have used a backup code
+        let user = self.get_user(user_id).await?;
+        let (codes, hashes) = self.generate_backup_codes()?;
+        if !self.user_repo.replace_backup_codes(user.id, &user.backup_code_hashes(), &hashes).await? {
+            return Err(Error::InvalidState("Backup codes changed concurrently, try again".to_string()));
+        }
+
+        self.activity_repo.log(
+            Some(user.id),
+            None,
+            "MFA_BACKUP_CODES_REGENERATED",
+            "AUTH",
+            Some("Backup codes regenerated".to_string()),
+            None,
+        ).await?;
+
+        Ok(codes)
+    }
+
+    /// Policy of an organization; users without one have nothing required
+    pub async fn get_security_policy(&self, organization_id: Option<Uuid>) -> Result<SecurityPolicy> {
+        let Some(organization_id) = organization_id else {
+            return Ok(SecurityPolicy::default_for(Uuid::nil()));
+        };
+
+        Ok(self.policy_repo
+            .find(organization_id)
+            .await?
+            .unwrap_or_else(|| SecurityPolicy::default_for(organization_id)))
+    }
+
+    /// Sets the user types of an organization that must sign in with a
+    /// second factor, typically pathologists (DOCTOR) and administrators
+    pub async fn update_mfa_policy(
+        &self,
+        organization_id: Uuid,
+        user_types: Vec<UserType>,
+        updated_by: Uuid,
+    ) -> Result<SecurityPolicy> {
+        let policy = self.policy_repo
+            .set_mfa_required_user_types(organization_id, &user_types, updated_by)
+            .await?;
+
+        self.activity_repo.log(
+            Some(updated_by),
+            None,
+            "MFA_POLICY_UPDATED",
+            "ADMIN",
+            Some(format!("MFA required for: {:?}", policy.mfa_required_user_types)),
+            None,
+        ).await?;
+
+        tracing::info!("MFA policy updated for organization: {}", organization_id);
+        Ok(policy)
+    }
+
+    /// Sets the password, lockout and delay rules of an organization
+    pub async fn update_password_policy(
+        &self,
+        organization_id: Uuid,
+        input: PasswordPolicyInput,
+        updated_by: Uuid,
+    ) -> Result<SecurityPolicy> {
+        input.validate()?;
+
+        let policy = self.policy_repo
+            .set_password_policy(organization_id, &input, updated_by)
+            .await?;
+
+        self.activity_repo.log(
+            Some(updated_by),
+            None,
+            "PASSWORD_POLICY_UPDATED",
+            "ADMIN",
+            Some(format!(
+                "Minimum length {}, expiry {:?} days, history {}, lockout after {} attempts for {} minutes",
+                policy.min_password_length,
+                policy.password_expiry_days,
+                policy.password_history_depth,
+                policy.lockout_threshold,
+                policy.lockout_minutes
+            )),
+            None,
+        ).await?;
+
+        tracing::info!("Password policy updated for organization: {}", organization_id);
+        Ok(policy)
+    }
+
+    async fn open_challenge(&self, challenge_token: &str) -> Result<MfaChallenge> {
+        self.mfa_challenge_repo
+            .find_by_token(challenge_token)
+            .await?
+            .filter(|challenge| challenge.is_open())
+            .ok_or_else(|| Error::AuthenticationFailed(
+                "MFA challenge is invalid or expired. Please log in again.".to_string()
+            ))
+    }
+
+    async fn reject_mfa_code(&self, challenge: &MfaChallenge, user: &User, policy: &SecurityPolicy) -> Result<Error> {
+        self.mfa_challenge_repo.record_failure(challenge.id).await?;
+
+        self.activity_repo.log(
+            Some(challenge.user_id),
+            None,
+            "MFA_FAILED",
+            "AUTH",
+            Some("Invalid verification code".to_string()),
+            challenge.ip_address.clone(),
+        ).await?;
+
+        self.record_failed_login(user, policy, challenge.ip_address.clone(), "Invalid verification code").await
+    }
+
+    /// Counts a failed password or verification code, locking the account
+    /// once the organization's threshold is reached. Returns the error for
+    /// the caller, `message` unless the account is now locked.
+    async fn record_failed_login(
+        &self,
+        user: &User,
+        policy: &SecurityPolicy,
+        ip_address: Option<String>,
+        message: &str,
+    ) -> Result<Error> {
+        let user = self.user_repo
+            .record_failed_login(user.id, policy.lockout_threshold, policy.lockout_minutes)
+            .await?;
+
+        if user.is_locked() {
+            self.activity_repo.log(
+                Some(user.id),
+                None,
+                "ACCOUNT_LOCKED",
+                "AUTH",
+                Some(format!(
+                    "Locked for {} minutes after {} failed login attempts",
+                    policy.lockout_minutes, user.failed_login_attempts
+                )),
+                ip_address,
+            ).await?;
+
+            tracing::warn!("Account locked after failed logins: {}", user.email);
+            return Ok(Error::AuthenticationFailed(
+                "Account is locked due to multiple failed login attempts".to_string()
+            ));
+        }
+
+        Ok(Error::AuthenticationFailed(message.to_string()))
+    }
+
+    /// Enables the pending authenticator if `code` comes from it, returning
+    /// the new backup codes
+    async fn confirm_two_factor(&self, user: &User, code: &str) -> Result<Option<Vec<String>>> {
+        let secret = user.two_factor_secret.as_deref().ok_or_else(|| {
+            Error::InvalidState("Start authenticator enrollment first".to_string())
+        })?;
+
+        let Some(step) = totp::verify(secret, code, Utc::now().timestamp(), user.two_factor_last_step)? else {
+            return Ok(None);
+        };
+
+        let (codes, hashes) = self.generate_backup_codes()?;
+        self.user_repo.enable_two_factor(user.id, step, &hashes).await?;
+
+        self.activity_repo.log(
+            Some(user.id),
+            None,
+            "MFA_ENABLED",
+            "AUTH",
+            Some("Two-factor authentication enabled".to_string()),
+            None,
+        ).await?;
+
+        tracing::info!("Two-factor authentication enabled for user: {}", user.email);
+        Ok(Some(codes))
+    }
+
+    /// Whether `code` is a current authenticator code or an unused backup
+    /// code of the user. Either is used up by a successful check.
+    async fn check_second_factor(&self, user: &User, code: &str) -> Result<bool> {
+        if let Some(secret) = user.two_factor_secret.as_deref() {
+            if let Some(step) = totp::verify(secret, code, Utc::now().timestamp(), user.two_factor_last_step)? {
+                return self.user_repo.use_two_factor_step(user.id, step).await;
+            }
+        }
+
+        let normalized = normalize_backup_code(code);
+        let hashes = user.backup_code_hashes();
+        for (index, hash) in hashes.iter().enumerate() {
+            if self.verify_password(&normalized, hash)? {
+                let mut remaining = hashes.clone();
+                remaining.remove(index);
+                if !self.user_repo.replace_backup_codes(user.id, &hashes, &remaining).await? {
+                    return Ok(false);
+                }
+
+                self.activity_repo.log(
+                    Some(user.id),
+                    None,
+                    "MFA_BACKUP_CODE_USED",
+                    "AUTH",
+                    Some(format!("Backup code used, {} left", remaining.len())),
+                    None,
+                ).await?;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Fresh backup codes and their hashes
+    fn generate_backup_codes(&self) -> Result<(Vec<String>, Vec<String>)> {
+        use rand::Rng;
+        const CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+        let mut rng = rand::thread_rng();
+
+        let codes: Vec<String> = (0..BACKUP_CODE_COUNT)
+            .map(|_| {
+                let code: String = (0..10)
+                    .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+                    .collect();
+                format!("{}-{}", &code[..5], &code[5..])
+            })
+            .collect();
+
+        let hashes = codes
+            .iter()
+            .map(|code| self.hash_password(&normalize_backup_code(code)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((codes, hashes))
+    }
+
     // ========================================================================
     // Email Verification
     // ========================================================================
@@ -599,6 +1150,302 @@ impl UserService {
         Ok(user)
     }
 
+    // ========================================================================
+    // Single Sign-On
+    // ========================================================================
+
+    /// Registers or updates one of an organization's identity providers,
+    /// once it answers discovery
+    pub async fn configure_identity_provider(
+        &self,
+        organization_id: Uuid,
+        input: ConfigureIdentityProviderInput,
+        user_id: Uuid,
+    ) -> Result<IdentityProvider> {
+        input.validate()?;
+        self.oidc.discover(&input.issuer_url).await?;
+
+        let provider = self.idp_repo.upsert(organization_id, &input, user_id).await?;
+
+        self.activity_repo.log(
+            Some(user_id),
+            None,
+            "IDENTITY_PROVIDER_CONFIGURED",
+            "ADMIN",
+            Some(format!("Identity provider {} ({})", provider.provider_code, provider.issuer_url)),
+            None,
+        ).await?;
+
+        tracing::info!("Identity provider {} configured for organization: {}", provider.provider_code, organization_id);
+        Ok(provider)
+    }
+
+    pub async fn list_identity_providers(&self, organization_id: Uuid) -> Result<Vec<IdentityProvider>> {
+        self.idp_repo.list(organization_id).await
+    }
+
+    /// Providers offered on an organization's sign-in page
+    pub async fn list_sso_providers(&self, organization_id: Uuid) -> Result<Vec<IdentityProvider>> {
+        let providers = self.idp_repo.list(organization_id).await?;
+        Ok(providers.into_iter().filter(|p| p.is_active).collect())
+    }
+
+    pub async fn get_identity_provider_role_mappings(&self, provider_id: Uuid) -> Result<Vec<IdentityProviderRoleMapping>> {
+        self.idp_repo.role_mappings(provider_id).await
+    }
+
+    /// Replaces the claim value to role mappings of a provider. Only system
+    /// roles and the organization's own may be mapped.
+    pub async fn set_identity_provider_role_mappings(
+        &self,
+        organization_id: Uuid,
+        provider_id: Uuid,
+        mappings: Vec<(String, Uuid)>,
+        user_id: Uuid,
+    ) -> Result<Vec<IdentityProviderRoleMapping>> {
+        let provider = self.idp_repo
+            .find_by_id(provider_id)
+            .await?
+            .filter(|p| p.organization_id == organization_id)
+            .ok_or_else(|| Error::NotFound("Identity provider not found".to_string()))?;
+
+        for (claim_value, role_id) in &mappings {
+            if claim_value.trim().is_empty() {
+                return Err(Error::Validation("Claim value is required".to_string()));
+            }
+            self.role_repo
+                .find_by_id(*role_id)
+                .await?
+                .filter(|role| role.organization_id.is_none() || role.organization_id == Some(organization_id))
+                .ok_or_else(|| Error::NotFound("Role not found".to_string()))?;
+        }
+
+        let saved = self.idp_repo.replace_role_mappings(provider.id, &mappings).await?;
+
+        self.activity_repo.log(
+            Some(user_id),
+            None,
+            "IDENTITY_PROVIDER_ROLES_MAPPED",
+            "ADMIN",
+            Some(format!("{} role mappings for identity provider {}", saved.len(), provider.provider_code)),
+            None,
+        ).await?;
+
+        Ok(saved)
+    }
+
+    /// Starts a sign-in at one of an organization's identity providers;
+    /// the browser goes to the returned URL and comes back with a code
+    pub async fn start_sso_login(&self, organization_id: Uuid, provider_code: &str) -> Result<SsoLoginStart> {
+        let provider = self.idp_repo
+            .find_by_code(organization_id, provider_code)
+            .await?
+            .filter(|p| p.is_active)
+            .ok_or_else(|| Error::NotFound("Identity provider not found".to_string()))?;
+
+        let metadata = self.oidc.discover(&provider.issuer_url).await?;
+
+        let pkce = Pkce::generate();
+        let state = oidc::random_token();
+        let nonce = oidc::random_token();
+        let authorization_url = self.oidc.authorization_url(&metadata, &AuthorizationRequest {
+            client_id: &provider.client_id,
+            redirect_uri: &provider.redirect_uri,
+            scopes: &provider.scopes,
+            state: &state,
+            nonce: &nonce,
+            code_challenge: &pkce.challenge,
+        })?;
+
+        let expires_at = Utc::now() + Duration::minutes(SSO_LOGIN_MINUTES);
+        self.oidc_request_repo
+            .create(provider.id, &state, &nonce, &pkce.verifier, expires_at)
+            .await?;
+
+        Ok(SsoLoginStart {
+            authorization_url,
+            state,
+            expires_in: SSO_LOGIN_MINUTES * 60,
+        })
+    }
+
+    /// Finishes a sign-in with the code the provider redirected back with,
+    /// under the same lockout and MFA rules as a password login. A second
+    /// factor the provider asked for counts when its ID token says so in
+    /// `amr`; otherwise the user gets an MFA challenge.
+    pub async fn complete_sso_login(
+        &self,
+        state: &str,
+        code: &str,
+        ip_address: Option<String>,
+    ) -> Result<LoginOutcome> {
+        let request = self.oidc_request_repo
+            .find_by_state(state)
+            .await?
+            .filter(|request| request.is_open())
+            .ok_or_else(|| Error::AuthenticationFailed(
+                "Sign-in request is invalid or expired. Please start again.".to_string()
+            ))?;
+
+        if !self.oidc_request_repo.complete(request.id).await? {
+            return Err(Error::AuthenticationFailed("Sign-in request already used".to_string()));
+        }
+
+        let provider = self.idp_repo
+            .find_by_id(request.identity_provider_id)
+            .await?
+            .filter(|p| p.is_active)
+            .ok_or_else(|| Error::AuthenticationFailed("Identity provider is no longer available".to_string()))?;
+
+        let metadata = self.oidc.discover(&provider.issuer_url).await?;
+        let id_token = self.oidc.exchange_code(
+            &metadata,
+            (&provider.client_id, provider.client_secret.as_deref()),
+            code,
+            &request.code_verifier,
+            &provider.redirect_uri,
+        ).await?;
+        let claims = self.oidc
+            .validate_id_token(&metadata, &id_token, &provider.client_id, &request.nonce)
+            .await?;
+
+        let user = self.federated_user(&provider, &claims, ip_address.clone()).await?;
+        if user.is_locked() {
+            return Err(locked_out(&user));
+        }
+        if !user.is_active() {
+            return Err(Error::AuthenticationFailed(
+                "Your account is not active. Please contact your administrator.".to_string()
+            ));
+        }
+
+        self.sync_sso_roles(&provider, &user, &claims).await?;
+
+        let policy = self.get_security_policy(user.organization_id).await?;
+        if (user.two_factor_enabled || policy.requires_mfa(&user.user_type)) && !claims.used_mfa() {
+            let challenge = self.mfa_challenge(
+                &user,
+                (None, None),
+                ip_address,
+                "Identity provider sign-in accepted, second factor required",
+            ).await?;
+            return Ok(LoginOutcome::MfaRequired(challenge));
+        }
+
+        let response = self.start_session(user, (None, None), ip_address).await?;
+        Ok(LoginOutcome::Authenticated(Box::new(response)))
+    }
+
+    /// User an ID token signs in: the one linked to its subject, else the
+    /// organization's user with its email, else a newly provisioned one
+    async fn federated_user(
+        &self,
+        provider: &IdentityProvider,
+        claims: &IdTokenClaims,
+        ip_address: Option<String>,
+    ) -> Result<User> {
+        if let Some(identity) = self.identity_repo.find(provider.id, &claims.sub).await? {
+            self.identity_repo.record_login(identity.id, claims.email.clone()).await?;
+            return self.get_user(identity.user_id).await;
+        }
+
+        // Only an address the provider verified can claim an account
+        let email = claims.email
+            .clone()
+            .filter(|_| claims.email_verified == Some(true))
+            .ok_or_else(|| Error::AuthenticationFailed(
+                "Identity provider did not share a verified email address".to_string()
+            ))?;
+
+        let (user, action) = match self.user_repo.find_by_email(&email).await? {
+            Some(user) if user.organization_id == Some(provider.organization_id) => (user, "SSO_IDENTITY_LINKED"),
+            Some(_) => {
+                return Err(Error::AuthenticationFailed(
+                    "This email address belongs to an account of another organization".to_string()
+                ));
+            }
+            None if provider.jit_provisioning => {
+                let (first_name, last_name) = names_from_claims(claims, &email);
+                let input = RegisterUserInput {
+                    organization_id: Some(provider.organization_id),
+                    first_name,
+                    last_name,
+                    email: email.clone(),
+                    mobile_number: None,
+                    password: String::new(),
+                    user_type: provider.default_user_type.clone(),
+                    department: None,
+                };
+
+                // Signs in through the provider only; nobody knows this password
+                let password_hash = self.hash_password(&self.generate_token())?;
+                let user = self.user_repo.provision(input, password_hash).await?;
+                self.assign_default_role(&user, provider.id).await?;
+                (user, "SSO_USER_PROVISIONED")
+            }
+            None => {
+                return Err(Error::AuthenticationFailed(format!(
+                    "No account exists for {}. Please ask your administrator for access.",
+                    email
+                )));
+            }
+        };
+
+        self.identity_repo.create(user.id, provider.id, &claims.sub, Some(email)).await?;
+
+        self.activity_repo.log(
+            Some(user.id),
+            None,
+            action,
+            "AUTH",
+            Some(format!("Signed in with {} as {}", provider.display_name, claims.sub)),
+            ip_address,
+        ).await?;
+
+        tracing::info!("{} for {} via {}", action, user.email, provider.provider_code);
+        Ok(user)
+    }
+
+    /// Grants the mapped roles the ID token's role claim names and revokes
+    /// mapped roles it no longer does
+    async fn sync_sso_roles(&self, provider: &IdentityProvider, user: &User, claims: &IdTokenClaims) -> Result<()> {
+        let mappings = self.idp_repo.role_mappings(provider.id).await?;
+        if mappings.is_empty() {
+            return Ok(());
+        }
+
+        let (granted, revoked) = mapped_roles(&mappings, &claims.claim_values(&provider.role_claim));
+        let current: Vec<Uuid> = self.get_user_roles(user.id)
+            .await?
+            .into_iter()
+            .map(|role| role.id)
+            .collect();
+
+        let mut changes = Vec::new();
+        for role_id in granted.into_iter().filter(|id| !current.contains(id)) {
+            // Assigned by the provider rather than a user
+            self.user_role_repo.assign_role(AssignRoleInput { user_id: user.id, role_id }, provider.id).await?;
+            changes.push(format!("+{}", role_id));
+        }
+        for role_id in revoked.into_iter().filter(|id| current.contains(id)) {
+            self.user_role_repo.remove_role(user.id, role_id).await?;
+            changes.push(format!("-{}", role_id));
+        }
+
+        if !changes.is_empty() {
+            self.activity_repo.log(
+                Some(user.id),
+                None,
+                "SSO_ROLES_SYNCED",
+                "AUTH",
+                Some(format!("Roles from {}: {}", provider.provider_code, changes.join(", "))),
+                None,
+            ).await?;
+        }
+
+        Ok(())
+    }
+
     // ========================================================================
     // Helper Methods
     // ========================================================================
@@ -651,38 +1498,104 @@ impl UserService {
             .collect()
     }
 
-    fn generate_jwt(&self, user: &User, duration: Duration) -> Result<String> {
-        use jsonwebtoken::{encode, EncodingKey, Header};
-        use serde::{Deserialize, Serialize};
-
-        #[derive(Debug, Serialize, Deserialize)]
-        struct Claims {
-            sub: String,
-            exp: i64,
-            iat: i64,
-            user_id: String,
-            email: String,
-            user_type: String,
-        }
-
-        let now = Utc::now();
-        let claims = Claims {
-            sub: user.id.to_string(),
-            exp: (now + duration).timestamp(),
-            iat: now.timestamp(),
-            user_id: user.id.to_string(),
-            email: user.email.clone(),
-            user_type: format!("{:?}", user.user_type),
+    /// Access token carrying the claims every service authorizes against
+    async fn generate_jwt(&self, user: &User, permissions: Vec<String>, duration: Duration) -> Result<String> {
+        let roles = self.get_user_roles(user.id)
+            .await?
+            .into_iter()
+            .map(|role| role.role_code)
+            .collect();
+
+        let claims = Claims::new(
+            user.id,
+            user.organization_id.unwrap_or_else(Uuid::nil),
+            roles,
+            permissions,
+            duration.num_hours(),
+        );
+
+        self.jwt.generate_token(claims)
+    }
+}
+
+/// Each failed attempt makes the next one wait longer
+fn check_login_delay(user: &User, policy: &SecurityPolicy) -> Result<()> {
+    if let Some(last_failed_at) = user.last_failed_login_at {
+        let retry_at = last_failed_at + policy.login_delay(user.failed_login_attempts);
+        let wait = retry_at - Utc::now();
+        if wait > Duration::zero() {
+            return Err(Error::AuthenticationFailed(format!(
+                "Too many failed login attempts. Please wait {} seconds before trying again.",
+                wait.num_seconds() + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn locked_out(user: &User) -> Error {
+    let locked_until = user.locked_until.unwrap_or_else(Utc::now);
+    Error::AuthenticationFailed(format!(
+        "Account is temporarily locked until {}. Please try again later or contact support.",
+        locked_until.format("%Y-%m-%d %H:%M UTC")
+    ))
+}
+
+/// First and last name of a provisioned user, from the name claims or, if
+/// the provider shares none, the email address
+fn names_from_claims(claims: &IdTokenClaims, email: &str) -> (String, String) {
+    if let Some(given_name) = claims.given_name.clone().filter(|n| !n.trim().is_empty()) {
+        return (given_name, claims.family_name.clone().unwrap_or_default());
+    }
+
+    if let Some(name) = claims.name.as_deref().filter(|n| !n.trim().is_empty()) {
+        let mut parts = name.trim().splitn(2, ' ');
+        let first = parts.next().unwrap_or_default().to_string();
+        let last = parts.next().unwrap_or_default().trim().to_string();
+        return (first, last);
+    }
+
+    (email.split('@').next().unwrap_or(email).to_string(), String::new())
+}
+
+/// Backup codes are accepted with or without the dash, in any case
+fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_from_claims() {
+        let claims = |value: serde_json::Value| -> IdTokenClaims {
+            let mut value = value;
+            value["iss"] = "https://idp.example".into();
+            value["sub"] = "1".into();
+            serde_json::from_value(value).unwrap()
         };
 
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret.as_bytes()),
-        )
-        .map_err(|e| Error::Custom(format!("Failed to generate JWT: {}", e)))?;
+        assert_eq!(
+            names_from_claims(&claims(serde_json::json!({ "given_name": "Asha", "family_name": "Rao" })), "a@x.in"),
+            ("Asha".to_string(), "Rao".to_string())
+        );
+        assert_eq!(
+            names_from_claims(&claims(serde_json::json!({ "name": "Ravi Kumar Menon" })), "r@x.in"),
+            ("Ravi".to_string(), "Kumar Menon".to_string())
+        );
+        assert_eq!(
+            names_from_claims(&claims(serde_json::json!({})), "lab.tech@x.in"),
+            ("lab.tech".to_string(), String::new())
+        );
+    }
 
-        Ok(token)
+    #[test]
+    fn test_normalize_backup_code() {
+        assert_eq!(normalize_backup_code("ab3de-fgh7k"), "ab3defgh7k");
+        assert_eq!(normalize_backup_code(" AB3DE FGH7K "), "ab3defgh7k");
     }
 }
@@ -0,0 +1,153 @@
+'use client';
+
+import { Suspense, useEffect } from 'react';
+import { useSearchParams } from 'next/navigation';
+import { gql } from '@apollo/client';
+import { useMutation } from '@apollo/client/react';
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
+import { AlertCircle, CheckCircle2, Loader2, ShieldAlert } from 'lucide-react';
+
+export const dynamic = 'force-dynamic';
+
+const VERIFY_REPORT_MUTATION = gql`
+  mutation VerifyReport($reportNumber: String!, $accessCode: String!, $signature: String!) {
+    verifyReport(reportNumber: $reportNumber, accessCode: $accessCode, signature: $signature) {
+      reportNumber
+      reportDate
+      patientInitials
+      signedOffBy
+      signatoryDesignation
+      signedAt
+      verificationCode
+      hashStatus
+      isGenuine
+    }
+  }
+`;
+
+interface VerifyReportResponse {
+  verifyReport: {
+    reportNumber: string;
+    reportDate: string;
+    patientInitials: string | null;
+    signedOffBy: string | null;
+    signatoryDesignation: string | null;
+    signedAt: string | null;
+    verificationCode: string | null;
+    hashStatus: 'MATCH' | 'MISMATCH' | 'UNAVAILABLE';
+    isGenuine: boolean;
+  };
+}
+
+const HASH_STATUS_LABELS: Record<VerifyReportResponse['verifyReport']['hashStatus'], string> = {
+  MATCH: 'Matches the issued report',
+  MISMATCH: 'Does not match the issued report',
+  UNAVAILABLE: 'Could not be checked',
+};
+
+function VerifyReport() {
+  const params = useSearchParams();
+  const reportNumber = params.get('r');
+  const accessCode = params.get('c');
+  const signature = params.get('s');
+  const complete = Boolean(reportNumber && accessCode && signature);
+
+  const [verifyReport, { data, loading, error, called }] =
+    useMutation<VerifyReportResponse>(VERIFY_REPORT_MUTATION);
+
+  useEffect(() => {
+    if (complete) {
+      verifyReport({ variables: { reportNumber, accessCode, signature } });
+    }
+  }, [complete, reportNumber, accessCode, signature, verifyReport]);
+
+  const report = data?.verifyReport;
+
+  if (!complete || error || (called && !loading && !report)) {
+    return (
+      <CardHeader>
+        <div className="mx-auto flex h-12 w-12 items-center justify-center rounded-full bg-red-100 dark:bg-red-900">
+          <AlertCircle className="h-6 w-6 text-red-600 dark:text-red-400" />
+        </div>
+        <CardTitle className="text-center">Report could not be verified</CardTitle>
+        <CardDescription className="text-center">
+          This link is incomplete or was not issued by the laboratory. Scan the QR code on the
+          report again, or contact the laboratory that issued it.
+        </CardDescription>
+      </CardHeader>
+    );
+  }
+
+  if (!report) {
+    return (
+      <CardContent className="flex items-center justify-center gap-2 py-12 text-sm text-gray-600 dark:text-gray-400">
+        <Loader2 className="h-4 w-4 animate-spin" />
+        Checking report...
+      </CardContent>
+    );
+  }
+
+  const rows: [string, string | null][] = [
+    ['Report number', report.reportNumber],
+    ['Report date', report.reportDate],
+    ['Patient', report.patientInitials],
+    [
+      'Signed off by',
+      report.signedOffBy &&
+        [report.signedOffBy, report.signatoryDesignation].filter(Boolean).join(', '),
+    ],
+    ['Signed at', report.signedAt && report.signedAt.replace('T', ' ').slice(0, 16)],
+    ['Verification code', report.verificationCode],
+    ['Document', HASH_STATUS_LABELS[report.hashStatus]],
+  ];
+
+  return (
+    <>
+      <CardHeader>
+        <div
+          className={`mx-auto flex h-12 w-12 items-center justify-center rounded-full ${
+            report.isGenuine ? 'bg-green-100 dark:bg-green-900' : 'bg-amber-100 dark:bg-amber-900'
+          }`}
+        >
+          {report.isGenuine ? (
+            <CheckCircle2 className="h-6 w-6 text-green-600 dark:text-green-400" />
+          ) : (
+            <ShieldAlert className="h-6 w-6 text-amber-600 dark:text-amber-400" />
+          )}
+        </div>
+        <CardTitle className="text-center">
+          {report.isGenuine ? 'Genuine report' : 'Report could not be confirmed'}
+        </CardTitle>
+        <CardDescription className="text-center">
+          {report.isGenuine
+            ? 'This report was issued and signed off by the laboratory and has not been altered.'
+            : 'The laboratory issued this report number, but it is not signed off or its file has changed. Contact the laboratory before relying on it.'}
+        </CardDescription>
+      </CardHeader>
+      <CardContent>
+        <dl className="divide-y divide-gray-200 text-sm dark:divide-gray-800">
+          {rows
+            .filter(([, value]) => value)
+            .map(([label, value]) => (
+              <div key={label} className="flex justify-between gap-4 py-2">
+                <dt className="text-gray-600 dark:text-gray-400">{label}</dt>
+                <dd className="text-right font-medium">{value}</dd>
+              </div>
+            ))}
+        </dl>
+      </CardContent>
+    </>
+  );
+}
+
+export default function VerifyReportPage() {
+  return (
+    <div className="flex min-h-screen items-center justify-center bg-gray-50 px-4 py-12 dark:bg-gray-900">
+      <Card className="w-full max-w-md">
+        <Suspense fallback={null}>
+          <VerifyReport />
+        </Suspense>
+      </Card>
+    </div>
+  );
+}
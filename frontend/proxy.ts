@@ -18,6 +18,7 @@ const publicRoutes = [
   '/pricing',
   '/demo',
   '/security',
+  '/verify',
 ];
 
 // Define routes that should redirect to dashboard if already authenticated